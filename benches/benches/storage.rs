@@ -0,0 +1,130 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion};
+use remi_benches::{bench_storage, Allocations, CountingAllocator};
+use tokio::runtime::{Builder, Runtime};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build tokio runtime")
+}
+
+fn fs<M: Measurement>(c: &mut Criterion<M>) {
+    let runtime = runtime();
+    let tempdir = tempfile::tempdir().expect("failed to create tempdir");
+    let storage = remi_fs::StorageService::new(tempdir.path());
+
+    bench_storage(c, "fs", &runtime, &storage);
+}
+
+fn inmemory<M: Measurement>(c: &mut Criterion<M>) {
+    let runtime = runtime();
+    let storage = remi_inmemory::StorageService::new();
+
+    bench_storage(c, "inmemory", &runtime, &storage);
+}
+
+#[cfg(feature = "containers")]
+fn containers<M: Measurement>(c: &mut Criterion<M>) {
+    use testcontainers::{
+        core::{IntoContainerPort, WaitFor},
+        runners::AsyncRunner,
+        GenericImage, ImageExt,
+    };
+
+    let runtime = runtime();
+
+    // renovate: image="minio/minio"
+    let minio = runtime
+        .block_on(
+            GenericImage::new("minio/minio", "RELEASE.2024-10-13T13-34-11Z")
+                .with_exposed_port(9000.tcp())
+                .with_wait_for(WaitFor::message_on_stdout("API:"))
+                .with_env_var("MINIO_ROOT_USER", "remi")
+                .with_env_var("MINIO_ROOT_PASSWORD", "remi-benches")
+                .with_cmd(["server", "/data"])
+                .start(),
+        )
+        .expect("failed to start minio container");
+
+    let port = runtime
+        .block_on(minio.get_host_port_ipv4(9000))
+        .expect("failed to get port mapping: 9000");
+
+    let s3 = remi_s3::StorageService::new(remi_s3::StorageConfig {
+        enforce_path_access_style: true,
+        access_key_id: String::from("remi"),
+        secret_access_key: remi::Secret::from("remi-benches"),
+        endpoint: Some(format!("http://127.0.0.1:{port}")),
+        region: Some(remi_s3::aws::s3::config::Region::from_static("us-east-1")),
+        bucket: String::from("remi"),
+
+        ..Default::default()
+    })
+    .expect("failed to create s3 storage service");
+
+    bench_storage(c, "s3", &runtime, &s3);
+
+    // renovate: image="mongo"
+    let mongo = runtime
+        .block_on(
+            GenericImage::new("mongo", "7.0.9")
+                .with_wait_for(WaitFor::message_on_stdout("Waiting for connections"))
+                .start(),
+        )
+        .expect("failed to start mongo container");
+
+    let port = runtime
+        .block_on(mongo.get_host_port_ipv4(27017))
+        .expect("failed to get port mapping: 27017");
+
+    let gridfs = runtime
+        .block_on(remi_gridfs::StorageService::from_conn_string(
+            format!("mongodb://127.0.0.1:{port}"),
+            remi_gridfs::StorageConfig {
+                database: Some(String::from("remi")),
+                bucket: String::from("fs"),
+
+                ..Default::default()
+            },
+        ))
+        .expect("failed to create storage service");
+
+    bench_storage(c, "gridfs", &runtime, &gridfs);
+}
+
+#[cfg(not(feature = "containers"))]
+fn containers<M: Measurement>(_: &mut Criterion<M>) {}
+
+criterion_group!(time, fs, inmemory, containers);
+criterion_group!(
+    name = allocations;
+    config = Criterion::default().with_measurement(Allocations);
+    targets = fs, inmemory, containers
+);
+
+criterion_main!(time, allocations);
@@ -0,0 +1,198 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Shared workloads of the `remi-rs` benchmarks. Every storage service runs the same
+//! workloads so that their numbers can be compared with each other, and so that changes
+//! to a storage service have before and after numbers.
+//!
+//! Run them with `cargo bench -p remi-benches`, or with `--features containers` to also
+//! benchmark the storage services that need Docker.
+
+use criterion::{
+    measurement::{Measurement, ValueFormatter},
+    BenchmarkId, Criterion, Throughput,
+};
+use remi::{Bytes, StorageService, UploadRequest};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Debug,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::runtime::Runtime;
+
+/// Sizes of the objects that are uploaded and opened.
+pub const OBJECT_SIZES: &[usize] = &[1024, 64 * 1024, 1024 * 1024];
+
+/// Amount of objects that are listed.
+pub const LISTING_SIZE: usize = 100;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator that counts every allocation, which is used by the [`Allocations`]
+/// measurement. Benchmarks must register it with `#[global_allocator]`.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// [`Measurement`] of the amount of allocations that were made instead of wall time. The
+/// allocations of every thread are counted, so the benchmarks should be run on a
+/// current-thread runtime.
+pub struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> Self::Intermediate {
+        ALLOCATIONS.load(Ordering::Relaxed)
+    }
+
+    fn end(&self, start: Self::Intermediate) -> Self::Value {
+        ALLOCATIONS.load(Ordering::Relaxed) - start
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationsFormatter
+    }
+}
+
+struct AllocationsFormatter;
+
+impl ValueFormatter for AllocationsFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(&self, _typical_value: f64, throughput: &Throughput, values: &mut [f64]) -> &'static str {
+        match *throughput {
+            Throughput::Bytes(bytes) | Throughput::BytesDecimal(bytes) => {
+                for value in values {
+                    *value /= bytes as f64;
+                }
+
+                "allocs/byte"
+            }
+
+            Throughput::Elements(elements) => {
+                for value in values {
+                    *value /= elements as f64;
+                }
+
+                "allocs/element"
+            }
+        }
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+/// Runs the upload, open, and listing workloads against `storage`, with every benchmark
+/// prefixed by `name`.
+pub fn bench_storage<S, M>(c: &mut Criterion<M>, name: &str, runtime: &Runtime, storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+    M: Measurement,
+{
+    runtime
+        .block_on(storage.init())
+        .expect("storage service to be initialized");
+
+    let mut group = c.benchmark_group(format!("{name}/upload"));
+    for &size in OBJECT_SIZES {
+        let data = Bytes::from(vec![0x42; size]);
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, data| {
+            b.to_async(runtime).iter(|| async {
+                storage
+                    .upload("./upload.bin", UploadRequest::default().with_data(data.clone()))
+                    .await
+                    .expect("upload to succeed")
+            })
+        });
+    }
+
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("{name}/open"));
+    for &size in OBJECT_SIZES {
+        let key = format!("./open-{size}.bin");
+        runtime
+            .block_on(storage.upload(&key, UploadRequest::default().with_data(vec![0x42; size])))
+            .expect("upload to succeed");
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &key, |b, key| {
+            b.to_async(runtime)
+                .iter(|| async { storage.open(key).await.expect("open to succeed") })
+        });
+    }
+
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("{name}/blobs"));
+    for i in 0..LISTING_SIZE {
+        runtime
+            .block_on(storage.upload(format!("./listing/{i}.txt"), UploadRequest::default().with_data("weow")))
+            .expect("upload to succeed");
+    }
+
+    group.throughput(Throughput::Elements(LISTING_SIZE as u64));
+    group.bench_function(BenchmarkId::from_parameter(LISTING_SIZE), |b| {
+        b.to_async(runtime).iter(|| async {
+            storage
+                .blobs(Some("./listing"), None)
+                .await
+                .expect("listing to succeed")
+        })
+    });
+
+    group.finish();
+}
@@ -19,9 +19,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use azure_core::auth::Secret;
+use crate::NetworkConfig;
+use azure_core::{auth::Secret, TransportOptions};
+use azure_identity::{ClientSecretCredential, ImdsId, TokenCredentialOptions, VirtualMachineManagedIdentityCredential};
 use azure_storage::StorageCredentials;
 use azure_storage_blobs::prelude::{ClientBuilder, ContainerClient};
+use std::{num::NonZeroU32, sync::Arc};
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -35,6 +38,104 @@ pub struct StorageConfig {
 
     /// Blob Storage container to grab any blob from.
     pub container: String,
+
+    /// Other containers in the same storage account that can be selected with the
+    /// [`Container`][crate::Container] request extension or
+    /// [`StorageService::for_container`][crate::StorageService::for_container].
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub containers: Vec<String>,
+
+    /// Prefix that the names of blobs are kept under in the container, so that a container
+    /// can be shared between storage services.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub prefix: Option<String>,
+
+    /// Whether if the names and paths of returned blobs should keep the [`prefix`][StorageConfig::prefix].
+    /// By default, the prefix is stripped so that they are the same paths that were given to
+    /// the storage service.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub include_prefix: bool,
+
+    /// Name of the zero-byte marker blob that represents an empty directory. This
+    /// will default to [`remi::DEFAULT_DIRECTORY_MARKER`].
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub directory_marker: Option<String>,
+
+    /// Maximum amount of blobs that are returned per page when listing blobs, which Azure
+    /// caps at 5000. Every page is fetched either way, so this only affects how many
+    /// requests are sent. This will default to Azure's default of 5000 if not set, and is
+    /// overridden by [`ListBlobsRequest::page_size`][remi::ListBlobsRequest::page_size].
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub max_results: Option<NonZeroU32>,
+
+    /// Delimiter that separates directories in blob names when listing blobs hierarchically,
+    /// which is done when [`ListBlobsRequest::include_dirs`][remi::ListBlobsRequest::include_dirs]
+    /// is set. This will default to `/` if not set.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub delimiter: Option<String>,
+
+    /// Access tier that new blobs are written into, like [`AccessTier::Cool`] or [`AccessTier::Archive`]
+    /// for data that is rarely read. Azure uses the default access tier of the storage account if
+    /// this isn't set. This can be overridden for a single upload with the
+    /// [`UploadAccessTier`][crate::UploadAccessTier] request extension.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub access_tier: Option<AccessTier>,
+
+    /// Whether if uploads should be verified by comparing the `Content-MD5` that Azure
+    /// acknowledged with the MD5 digest of the data that was sent.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub verify_writes: bool,
+
+    /// Maximum size (in bytes) of a blob that can be uploaded. Uploads that are larger fail
+    /// with an error that wraps a [`TooLarge`][remi::TooLarge] error before any bytes are sent.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub max_object_size: Option<usize>,
+
+    /// Whether if this storage service is read-only. All operations that would modify the
+    /// container fail with a [`ReadOnly`][remi::ReadOnly] error before anything is sent.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub read_only: bool,
+
+    /// Whether if a body whose length doesn't match the size that Azure reported should only
+    /// emit a warning instead of failing with an error that wraps a
+    /// [`TruncatedRead`][remi::TruncatedRead] error.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warn_on_truncated_reads: bool,
+
+    /// Whether if a file that doesn't exist should be looked up again by listing the blobs under its parent prefix and
+    /// matching the file name case-insensitively, which emits a warning if it was found under a
+    /// different case. This helps with data that was migrated from case-insensitive filesystems,
+    /// where `Readme.MD` and `README.md` were the same file.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub case_insensitive_lookup: bool,
+
+    /// Maximum amount of bytes (in bytes) that can be buffered in memory across all concurrent
+    /// operations, which is useful for embedded deployments where opening many large blobs at
+    /// once would otherwise run out of memory. Operations that would go over the limit wait
+    /// until enough bytes were released, unless [`memory_limit_fail_fast`][StorageConfig::memory_limit_fail_fast]
+    /// is set. Blobs are weighted by their content length, which needs an extra request to
+    /// fetch the blob's properties before it is opened. See [`MemoryLimit`][remi::MemoryLimit]
+    /// for more information.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub strict_memory_limit: Option<usize>,
+
+    /// Whether if operations that would go over the [`strict_memory_limit`][StorageConfig::strict_memory_limit]
+    /// should fail right away with an error that wraps a [`MemoryLimitExceeded`][remi::MemoryLimitExceeded]
+    /// error instead of waiting.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub memory_limit_fail_fast: bool,
+
+    /// Whether if [`init`][remi::StorageService::init] should only verify that the container exists instead
+    /// of creating it, which fails with an error that wraps a
+    /// [`ResourceMissing`][remi::ResourceMissing] error if it doesn't. This can also be enabled
+    /// with the [`REMI_SKIP_PROVISIONING`][remi::SKIP_PROVISIONING_ENV] environment variable.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub skip_provisioning: bool,
+
+    /// Connector-level options for IPv6-only networks or custom DNS resolution. The
+    /// default HTTP client of the Azure SDK is used if this is left as the default.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub network: NetworkConfig,
 }
 
 impl StorageConfig {
@@ -43,28 +144,66 @@ impl StorageConfig {
             credentials: Credential::Anonymous,
             container: "dummy-test".into(),
             location: CloudLocation::Public("dummy".into()),
+            containers: Vec::new(),
+            prefix: None,
+            include_prefix: false,
+            directory_marker: None,
+            max_results: None,
+            delimiter: None,
+            access_tier: None,
+            verify_writes: false,
+            max_object_size: None,
+            read_only: false,
+            warn_on_truncated_reads: false,
+            case_insensitive_lookup: false,
+            strict_memory_limit: None,
+            memory_limit_fail_fast: false,
+            skip_provisioning: false,
+            network: NetworkConfig::default(),
         }
     }
 }
 
-/// Credentials information for creating a blob container.
+/// Credentials information for creating a blob container. Access keys, tokens, and client
+/// secrets are redacted when the credential is printed or serialized.
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Credential {
     /// An access-key based credential.
     /// <https://docs.microsoft.com/azure/storage/common/storage-account-keys-manage>
-    AccessKey { account: String, access_key: String },
+    AccessKey { account: String, access_key: remi::Secret },
 
     /// A shared access signature for temporary access to blobs.
     ///
     /// - <https://docs.microsoft.com/azure/storage/common/storage-sas-overview>
     /// - <https://docs.microsoft.com/azure/applied-ai-services/form-recognizer/create-sas-tokens>
-    SASToken(String),
+    SASToken(remi::Secret),
 
     /// OAuth2.0-based Bearer token credential.
     /// <https://docs.microsoft.com/rest/api/storageservices/authorize-with-azure-active-directory>
-    Bearer(String),
+    Bearer(remi::Secret),
+
+    /// Uses the managed identity of the Azure VM or AKS node that the service runs on, which
+    /// is requested from the instance metadata service, so no secrets have to be configured.
+    /// The user-assigned identity with the given `client_id` is used if there is one, otherwise
+    /// it is the system-assigned identity. Tokens are cached and refreshed before they expire.
+    ///
+    /// <https://learn.microsoft.com/entra/identity/managed-identities-azure-resources/overview>
+    ManagedIdentity {
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        client_id: Option<String>,
+    },
+
+    /// Authenticates as a Microsoft Entra service principal with a client secret. Tokens
+    /// are cached and refreshed before they expire.
+    ///
+    /// <https://learn.microsoft.com/entra/identity-platform/app-objects-and-service-principals>
+    ServicePrincipal {
+        tenant: String,
+        client_id: String,
+        client_secret: remi::Secret,
+    },
 
     /// Anonymous credential, doesn't require further authentication.
     #[default]
@@ -76,13 +215,41 @@ impl TryFrom<Credential> for StorageCredentials {
 
     fn try_from(value: Credential) -> Result<Self, Self::Error> {
         match value {
-            Credential::AccessKey { account, access_key } => {
-                Ok(StorageCredentials::access_key(account, Secret::new(access_key)))
-            }
+            Credential::AccessKey { account, access_key } => Ok(StorageCredentials::access_key(
+                account,
+                Secret::new(access_key.into_inner()),
+            )),
 
-            Credential::SASToken(token) => StorageCredentials::sas_token(token),
-            Credential::Bearer(token) => Ok(StorageCredentials::bearer_token(token)),
+            Credential::SASToken(token) => StorageCredentials::sas_token(token.into_inner()),
+            Credential::Bearer(token) => Ok(StorageCredentials::bearer_token(token.into_inner())),
             Credential::Anonymous => Ok(StorageCredentials::anonymous()),
+            Credential::ManagedIdentity { client_id } => {
+                let id = match client_id {
+                    Some(client_id) => ImdsId::ClientId(client_id),
+                    None => ImdsId::SystemAssigned,
+                };
+
+                Ok(StorageCredentials::token_credential(Arc::new(
+                    VirtualMachineManagedIdentityCredential::new(id, TokenCredentialOptions::default()),
+                )))
+            }
+
+            Credential::ServicePrincipal {
+                tenant,
+                client_id,
+                client_secret,
+            } => {
+                let options = TokenCredentialOptions::default();
+                Ok(StorageCredentials::token_credential(Arc::new(
+                    ClientSecretCredential::new(
+                        options.http_client(),
+                        options.authority_host()?,
+                        tenant,
+                        client_id,
+                        client_secret.into_inner(),
+                    ),
+                )))
+            }
         }
     }
 }
@@ -91,10 +258,44 @@ impl TryFrom<StorageConfig> for ContainerClient {
     type Error = azure_core::Error;
 
     fn try_from(value: StorageConfig) -> Result<Self, Self::Error> {
-        Ok(
-            ClientBuilder::with_location::<StorageCredentials>(value.location.into(), value.credentials.try_into()?)
-                .container_client(value.container),
-        )
+        let mut builder =
+            ClientBuilder::with_location::<StorageCredentials>(value.location.into(), value.credentials.try_into()?);
+
+        if !value.network.is_default() {
+            builder = builder.transport(TransportOptions::new(Arc::new(value.network.into_http_client()?)));
+        }
+
+        Ok(builder.container_client(value.container))
+    }
+}
+
+/// Newtype enumeration around [`azure_storage_blobs::prelude::AccessTier`], which decides how
+/// much it costs to store and read a blob.
+///
+/// <https://learn.microsoft.com/azure/storage/blobs/access-tiers-overview>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum AccessTier {
+    /// Optimized for blobs that are read or modified often.
+    Hot,
+
+    /// Optimized for blobs that are rarely read and are kept for at least 30 days.
+    Cool,
+
+    /// Offline tier for blobs that are rarely read and are kept for at least 180 days. Blobs
+    /// in this tier can't be read until they are rehydrated into an online tier with
+    /// [`StorageService::set_tier`][crate::StorageService::set_tier].
+    Archive,
+}
+
+impl From<AccessTier> for azure_storage_blobs::prelude::AccessTier {
+    fn from(value: AccessTier) -> Self {
+        match value {
+            AccessTier::Hot => azure_storage_blobs::prelude::AccessTier::Hot,
+            AccessTier::Cool => azure_storage_blobs::prelude::AccessTier::Cool,
+            AccessTier::Archive => azure_storage_blobs::prelude::AccessTier::Archive,
+        }
     }
 }
 
@@ -0,0 +1,141 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use azure_core::{error::ErrorKind, request_options::LeaseId};
+use azure_storage_blobs::prelude::{BlobLeaseClient, LeaseDuration};
+use std::time::Duration;
+
+/// Shortest finite lease that Azure allows.
+const MIN_LEASE_DURATION: Duration = Duration::from_secs(15);
+
+/// Longest finite lease that Azure allows.
+const MAX_LEASE_DURATION: Duration = Duration::from_secs(60);
+
+/// Converts `duration` into a [`LeaseDuration`], where `None` is a lease that never expires.
+pub(crate) fn lease_duration(duration: Option<Duration>) -> azure_core::Result<LeaseDuration> {
+    let Some(duration) = duration else {
+        return Ok(LeaseDuration::Infinite);
+    };
+
+    if !(MIN_LEASE_DURATION..=MAX_LEASE_DURATION).contains(&duration) {
+        return Err(azure_core::Error::new(
+            ErrorKind::Other,
+            format!(
+                "lease duration must be between {} and {} seconds, got {duration:?}",
+                MIN_LEASE_DURATION.as_secs(),
+                MAX_LEASE_DURATION.as_secs()
+            ),
+        ));
+    }
+
+    // the bounds were checked above, so this always fits
+    Ok(LeaseDuration::Seconds(duration.as_secs() as u8))
+}
+
+/// Lease on a blob that was acquired with [`StorageService::acquire_lease`][crate::StorageService::acquire_lease],
+/// which gives exclusive write and delete access to the blob until it is released or expires.
+/// Uploads to a leased blob must send the lease's ID with the [`Lease`] request extension, and
+/// the lease has to be released before the blob can be deleted.
+///
+/// Unlike [`LeaseGuard`][remi::LeaseGuard], a [`BlobLease`] isn't renewed in the background and
+/// isn't released when it is dropped, since Azure keeps track of the lease. A finite lease has to
+/// be [`renew`][BlobLease::renew]ed before it expires, and an infinite lease is held until it is
+/// [`release`][BlobLease::release]d or broken.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use std::time::Duration;
+/// #
+/// let Some(lease) = storage.acquire_lease("./locks/migrations", Some(Duration::from_secs(30))).await? else {
+///     // another instance holds the lease
+///     return Ok(());
+/// };
+///
+/// run_migrations().await?;
+/// lease.release().await?;
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct BlobLease {
+    client: BlobLeaseClient,
+    path: String,
+}
+
+impl BlobLease {
+    pub(crate) fn new(client: BlobLeaseClient, path: String) -> BlobLease {
+        BlobLease { client, path }
+    }
+
+    /// Returns the ID of this lease.
+    pub fn id(&self) -> LeaseId {
+        *self.client.lease_id()
+    }
+
+    /// Returns the path of the blob that this lease is on, in the form of `azure://{name}`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the [`Lease`] request extension for uploading to the leased blob.
+    pub fn extension(&self) -> Lease {
+        Lease(self.id())
+    }
+
+    /// Renews this lease, which restarts its duration. This also works after the lease expired
+    /// as long as nobody else acquired a lease on the blob in the meantime.
+    pub async fn renew(&self) -> azure_core::Result<()> {
+        #[cfg(feature = "tracing")]
+        ::tracing::debug!(path = self.path, lease = %self.id(), "renewing lease on blob");
+
+        #[cfg(feature = "log")]
+        ::log::debug!("renewing lease [{}] on blob [{}]", self.id(), self.path);
+
+        self.client.renew().await.map(|_| ())
+    }
+
+    /// Releases this lease, so that another lease can be acquired on the blob right away.
+    pub async fn release(self) -> azure_core::Result<()> {
+        #[cfg(feature = "tracing")]
+        ::tracing::debug!(path = self.path, lease = %self.id(), "releasing lease on blob");
+
+        #[cfg(feature = "log")]
+        ::log::debug!("releasing lease [{}] on blob [{}]", self.id(), self.path);
+
+        self.client.release().await.map(|_| ())
+    }
+}
+
+/// Request extension that sends the ID of a [`BlobLease`] with an upload, which is required
+/// when the blob is leased.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::UploadRequest;
+/// #
+/// let request = UploadRequest::default()
+///     .with_data(config)
+///     .with_extra(lease.extension());
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lease(pub LeaseId);
@@ -25,11 +25,14 @@
 
 #[cfg(feature = "export-azure")]
 #[cfg_attr(any(noeldoc, docsrs), doc(cfg(feature = "export-azure")))]
-/// Exports the [`azure_core`], [`azure_storage`], and [`azure_storage_blobs`]
+/// Exports the [`azure_core`], [`azure_identity`], [`azure_storage`], and [`azure_storage_blobs`]
 /// crates without defining them as owned dependencies.
 pub mod core {
     pub use azure_core::*;
 
+    /// Exports the [`azure_identity`] crate without defining it as an owned dependency.
+    pub use azure_identity as identity;
+
     /// Exports the [`azure_storage`] and [`azure_storage_blobs`]
     /// crates without defining them as owned dependencies.
     #[cfg_attr(any(noeldoc, docsrs), doc(cfg(feature = "export-azure")))]
@@ -44,5 +47,21 @@ pub mod core {
 mod config;
 pub use config::*;
 
+mod lease;
+pub use lease::*;
+
+mod network;
+pub use network::*;
+
 mod service;
 pub use service::*;
+
+// Compile-time checks so that a change which makes these types no longer thread-safe
+// fails to build here instead of breaking downstream crates.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    assert_send_sync::<StorageService>();
+    assert_send_sync::<StorageConfig>();
+    assert_send_sync::<BlobLease>();
+};
@@ -0,0 +1,196 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use azure_core::error::{Error, ErrorKind};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::{
+    fmt::Debug,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+/// Connector-level options for environments with IPv6-only networks or internal DNS.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkConfig {
+    /// Which IP address family connections should use.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ip_family: IpFamily,
+
+    /// Whether if connections should race IPv6 and IPv4 addresses ("Happy Eyeballs") when
+    /// a hostname resolves to both. If this is disabled, only addresses of the family that
+    /// was resolved first are used.
+    #[cfg_attr(feature = "serde", serde(default = "__truthy"))]
+    pub happy_eyeballs: bool,
+
+    /// Local address to bind all sockets to.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub local_address: Option<IpAddr>,
+
+    /// Network interface to bind all sockets to (i.e, `eth1`). This is only supported on
+    /// Linux, Android, and Fuchsia and is ignored everywhere else.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub interface: Option<String>,
+
+    /// Custom DNS resolver to use instead of the system's resolver.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub resolver: Option<Resolver>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            ip_family: IpFamily::default(),
+            happy_eyeballs: true,
+            local_address: None,
+            interface: None,
+            resolver: None,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Whether if the default HTTP client of the Azure SDK can be used.
+    pub(crate) fn is_default(&self) -> bool {
+        self.ip_family == IpFamily::Any
+            && self.happy_eyeballs
+            && self.local_address.is_none()
+            && self.interface.is_none()
+            && self.resolver.is_none()
+    }
+
+    /// Sets a custom DNS resolver.
+    pub fn with_resolver<R: Resolve + 'static>(mut self, resolver: R) -> Self {
+        self.resolver = Some(Resolver(Arc::new(resolver)));
+        self
+    }
+
+    pub(crate) fn into_http_client(self) -> azure_core::Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().local_address(self.local_address);
+
+        #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+        if let Some(ref interface) = self.interface {
+            builder = builder.interface(interface);
+        }
+
+        if self.ip_family != IpFamily::Any || !self.happy_eyeballs || self.resolver.is_some() {
+            builder = builder.dns_resolver(Arc::new(FilteringResolver {
+                inner: self.resolver,
+                ip_family: self.ip_family,
+                happy_eyeballs: self.happy_eyeballs,
+            }));
+        }
+
+        builder
+            .build()
+            .map_err(|e| Error::full(ErrorKind::Other, e, "failed to build HTTP client"))
+    }
+}
+
+/// Custom DNS resolver for a [`NetworkConfig`].
+#[derive(Clone)]
+pub struct Resolver(pub Arc<dyn Resolve>);
+
+impl Debug for Resolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Resolver").finish_non_exhaustive()
+    }
+}
+
+/// IP address family that connections can be restricted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum IpFamily {
+    /// Use both IPv4 and IPv6 addresses.
+    #[default]
+    Any,
+
+    /// Only use IPv4 addresses.
+    Ipv4,
+
+    /// Only use IPv6 addresses.
+    Ipv6,
+}
+
+impl IpFamily {
+    /// Whether if `addr` belongs to this address family.
+    pub fn matches(&self, addr: &IpAddr) -> bool {
+        match self {
+            IpFamily::Any => true,
+            IpFamily::Ipv4 => addr.is_ipv4(),
+            IpFamily::Ipv6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// [`Resolve`] that filters resolved addresses with a [`NetworkConfig`].
+struct FilteringResolver {
+    inner: Option<Resolver>,
+    ip_family: IpFamily,
+    happy_eyeballs: bool,
+}
+
+impl Resolve for FilteringResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(resolve(self.inner.clone(), name, self.ip_family, self.happy_eyeballs))
+    }
+}
+
+async fn resolve(
+    inner: Option<Resolver>,
+    name: Name,
+    ip_family: IpFamily,
+    happy_eyeballs: bool,
+) -> Result<Addrs, Box<dyn std::error::Error + Send + Sync>> {
+    let host = name.as_str().to_owned();
+    let addrs: Vec<SocketAddr> = match inner {
+        Some(Resolver(resolver)) => resolver.resolve(name).await?.collect(),
+        None => tokio::net::lookup_host((host.as_str(), 0)).await?.collect(),
+    };
+
+    let addrs = filter_addrs(addrs, ip_family, happy_eyeballs);
+    if addrs.is_empty() {
+        return Err(format!("host [{host}] didn't resolve to any usable address").into());
+    }
+
+    Ok(Box::new(addrs.into_iter()))
+}
+
+fn filter_addrs(addrs: Vec<SocketAddr>, ip_family: IpFamily, happy_eyeballs: bool) -> Vec<SocketAddr> {
+    let mut addrs = addrs
+        .into_iter()
+        .filter(|addr| ip_family.matches(&addr.ip()))
+        .collect::<Vec<_>>();
+
+    if !happy_eyeballs {
+        if let Some(first) = addrs.first().copied() {
+            addrs.retain(|addr| addr.is_ipv6() == first.is_ipv6());
+        }
+    }
+
+    addrs
+}
+
+#[cfg(feature = "serde")]
+const fn __truthy() -> bool {
+    true
+}
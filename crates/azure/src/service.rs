@@ -19,29 +19,265 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::StorageConfig;
+use crate::{lease::lease_duration, AccessTier, BlobLease, CloudLocation, Credential, Lease, StorageConfig};
 use async_trait::async_trait;
-use azure_core::request_options::{Metadata, Prefix};
-use azure_storage::{ErrorKind, ResultExt};
-use azure_storage_blobs::prelude::ContainerClient;
-use bytes::Bytes;
-use futures_util::StreamExt;
-use remi::{Blob, File, ListBlobsRequest, UploadRequest};
-use std::{borrow::Cow, ops::Deref, path::Path, time::SystemTime};
+use azure_core::{
+    request_options::{Delimiter, IfMatchCondition, MaxResults, Metadata, Prefix},
+    StatusCode,
+};
+use azure_storage::{shared_access_signature::service_sas::BlobSasPermissions, ErrorKind, ResultExt};
+use azure_storage_blobs::prelude::{BlobClient, BlobVersioning, ContainerClient, Snapshot};
+use bytes::{Bytes, BytesMut};
+use futures_util::{StreamExt, TryStreamExt};
+use md5::{Digest, Md5};
+use remi::{
+    Blob, Capabilities, CollisionStrategy, ContentStream, DeleteSummary, Directory, Extensions, File, HealthReport,
+    ListBlobsRequest, ObjectEvent, Page, PresignMethod, PresignOptions, PresignedUrl, ServiceInfo, StorageEvent,
+    StorageService as _, UploadRequest, ValidationReport,
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    num::NonZeroU32,
+    ops::Deref,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+use time::OffsetDateTime;
+use tokio::sync::{broadcast, OnceCell};
+
+/// Maximum amount of blobs that Azure returns in a single page of a listing.
+const MAX_RESULTS: u32 = 5000;
+
+/// Request extension to select which container an upload or listing should use. The container
+/// must be the default [`StorageConfig::container`] or be in [`StorageConfig::containers`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Container(pub String);
+
+impl Container {
+    /// Creates a new [`Container`] request extension.
+    pub fn new<I: Into<String>>(name: I) -> Container {
+        Container(name.into())
+    }
+}
+
+/// Request extension to override [`StorageConfig::access_tier`] for a single upload, for
+/// example to write backups directly into the archive tier while everything else stays hot.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi_azure::{AccessTier, UploadAccessTier};
+/// # use remi::UploadRequest;
+/// #
+/// let request = UploadRequest::default()
+///     .with_data(backup)
+///     .with_extra(UploadAccessTier::new(AccessTier::Archive));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UploadAccessTier(pub AccessTier);
+
+impl UploadAccessTier {
+    /// Creates a new [`UploadAccessTier`] request extension.
+    pub fn new(tier: AccessTier) -> UploadAccessTier {
+        UploadAccessTier(tier)
+    }
+}
+
+/// Identifies a point-in-time snapshot of a blob that was taken with [`StorageService::snapshot`],
+/// which can be read with [`StorageService::open_snapshot`] and [`StorageService::blob_snapshot`].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotId(pub Snapshot);
+
+impl From<Snapshot> for SnapshotId {
+    fn from(value: Snapshot) -> Self {
+        SnapshotId(value)
+    }
+}
+
+/// Request extension for [`blobs`][remi::StorageService::blobs] to also list blobs that were
+/// soft-deleted and are still in their retention period, so they can be restored with
+/// [`StorageService::undelete`]. Soft-deleted blobs have their `deleted` metadata key set to
+/// `true` and no contents, since they can't be read until they are restored.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi_azure::IncludeDeleted;
+/// # use remi::ListBlobsRequest;
+/// #
+/// let request = ListBlobsRequest::default().with_extra(IncludeDeleted);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IncludeDeleted;
+
+/// Checks that `path` can be used as the key of a file, see [`InvalidKey`][remi::InvalidKey].
+fn check_key(path: &Path) -> azure_core::Result<()> {
+    remi::InvalidKey::check(path).map_err(|e| azure_core::Error::full(ErrorKind::Other, e, "invalid key"))
+}
+
+/// Returns an [`AlreadyExists`][remi::AlreadyExists] error for the blob `name`.
+fn already_exists(name: &str) -> azure_core::Error {
+    azure_core::Error::full(
+        ErrorKind::Other,
+        remi::AlreadyExists::new(format!("azure://{name}")),
+        "blob already exists",
+    )
+}
+
+/// Encodes the `Content-MD5` of a blob in hexadecimal, see [`File::checksum`].
+fn hex(md5: &[u8]) -> String {
+    md5.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Checks if `error` was caused by the blob itself not existing. A missing container is
+/// reported with the same status code, but is an error rather than a missing blob.
+fn is_blob_not_found(error: &azure_core::Error) -> bool {
+    matches!(
+        error.as_http_error(),
+        Some(e) if e.status() == StatusCode::NotFound && e.error_code() != Some("ContainerNotFound")
+    )
+}
+
+/// Checks if `error` was caused by requesting a range that starts after the end of a blob.
+fn is_range_not_satisfiable(error: &azure_core::Error) -> bool {
+    matches!(
+        error.as_http_error(),
+        Some(e) if e.status() == StatusCode::RequestedRangeNotSatisfiable
+    )
+}
+
+/// Checks if `error` was caused by acquiring a lease on a blob that is already leased.
+fn is_lease_already_present(error: &azure_core::Error) -> bool {
+    matches!(
+        error.as_http_error(),
+        Some(e) if e.status() == StatusCode::Conflict && e.error_code() == Some("LeaseAlreadyPresent")
+    )
+}
+
+/// Checks if `error` was caused by creating a container that already exists.
+fn is_container_already_exists(error: &azure_core::Error) -> bool {
+    matches!(
+        error.as_http_error(),
+        Some(e) if e.status() == StatusCode::Conflict && e.error_code() == Some("ContainerAlreadyExists")
+    )
+}
+
+/// Returns `true` if `error` is likely to go away when the request is retried, like I/O errors
+/// from connections that were dropped, timeouts, and server errors or throttling from Azure. This
+/// can be used as the classifier of a [`RetryingStorageService`][remi::RetryingStorageService].
+///
+/// * since: 0.11.0
+pub fn is_retryable(error: &azure_core::Error) -> bool {
+    if matches!(error.kind(), ErrorKind::Io) {
+        return true;
+    }
+
+    matches!(
+        error.as_http_error().map(|e| e.status()),
+        Some(
+            StatusCode::RequestTimeout
+                | StatusCode::TooManyRequests
+                | StatusCode::InternalServerError
+                | StatusCode::BadGateway
+                | StatusCode::ServiceUnavailable
+                | StatusCode::GatewayTimeout
+        )
+    )
+}
+
+/// Converts `error` into a [`remi::Error`], which keeps `error` as its source.
+///
+/// * since: 0.11.0
+pub fn into_remi_error(error: azure_core::Error) -> remi::Error {
+    if let Some(variant) = error.get_ref().and_then(|e| remi::Error::variant_of(e)) {
+        return variant(Box::new(error));
+    }
+
+    let variant = match error.as_http_error().map(|e| e.status()) {
+        Some(StatusCode::NotFound) => remi::Error::NotFound,
+        Some(StatusCode::Conflict)
+            if error.as_http_error().and_then(|e| e.error_code()) == Some("BlobAlreadyExists") =>
+        {
+            remi::Error::AlreadyExists
+        }
+
+        Some(StatusCode::Unauthorized | StatusCode::Forbidden) => remi::Error::PermissionDenied,
+        Some(StatusCode::RequestTimeout | StatusCode::GatewayTimeout) => remi::Error::Timeout,
+        _ if is_retryable(&error) => remi::Error::Unavailable,
+        _ => remi::Error::Backend,
+    };
+
+    variant(Box::new(error))
+}
+
+/// What [`StorageService::put`] does when the blob already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnConflict {
+    /// Replaces the existing blob.
+    Overwrite,
+
+    /// Takes a snapshot of the existing blob before replacing it.
+    Snapshot,
+
+    /// Fails with [`AlreadyExists`][remi::AlreadyExists].
+    Fail,
+}
 
 #[derive(Debug, Clone)]
 pub struct StorageService {
     container: ContainerClient,
+    containers: Arc<Mutex<HashMap<String, ContainerClient>>>,
+    events: Option<broadcast::Sender<StorageEvent>>,
+    interceptors: remi::UploadInterceptors,
+    initialized: Arc<OnceCell<()>>,
+    memory: Option<remi::MemoryLimit>,
 
     #[allow(unused)]
     config: StorageConfig,
 }
 
+/// Sanitizes `path` into the name of a blob that is kept under `prefix`.
+fn sanitize_path(prefix: Option<&str>, path: &Path) -> azure_core::Result<String> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| azure_core::Error::new(ErrorKind::Other, "was not valid utf-8"))
+        .with_context(ErrorKind::Other, || "failed to convert path into a string")?;
+
+    let path = path.trim_start_matches("./").trim_start_matches("~/");
+    match prefix {
+        Some(prefix) => Ok(format!("{prefix}/{path}")),
+        None => Ok(path.into()),
+    }
+}
+
+/// Entrypoints for the fuzz targets in `fuzz/`, which only exist when built with `cargo fuzz`.
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub mod __fuzzing {
+    use std::path::Path;
+
+    pub fn sanitize_path(prefix: Option<&str>, path: &Path) -> azure_core::Result<String> {
+        super::sanitize_path(prefix, path)
+    }
+}
+
 impl StorageService {
     /// Creates a new [`StorageService`] with a provided [`StorageConfig`].
     pub fn new(config: StorageConfig) -> Result<StorageService, azure_core::Error> {
         Ok(Self {
             container: config.clone().try_into()?,
+            containers: Arc::default(),
+            events: None,
+            interceptors: remi::UploadInterceptors::default(),
+            initialized: Arc::default(),
+            memory: config
+                .strict_memory_limit
+                .map(|limit| match config.memory_limit_fail_fast {
+                    true => remi::MemoryLimit::fail_fast(limit),
+                    false => remi::MemoryLimit::new(limit),
+                }),
             config,
         })
     }
@@ -50,19 +286,883 @@ impl StorageService {
     pub fn with_container_client(container: ContainerClient) -> StorageService {
         Self {
             container,
+            containers: Arc::default(),
+            events: None,
+            interceptors: remi::UploadInterceptors::default(),
+            initialized: Arc::default(),
+            memory: None,
             config: StorageConfig::dummy(),
         }
     }
 
+    /// Returns a [`StorageService`] that uses another container that was configured in
+    /// [`StorageConfig::containers`]. The container clients share the same service client
+    /// and are cached between all clones of this service.
+    pub fn for_container<C: AsRef<str>>(&self, container: C) -> azure_core::Result<StorageService> {
+        let container = container.as_ref();
+        if container == self.config.container {
+            return Ok(self.clone());
+        }
+
+        if !self.config.containers.iter().any(|c| c == container) {
+            return Err(azure_core::Error::new(
+                ErrorKind::Other,
+                format!("container [{container}] is not configured"),
+            ));
+        }
+
+        let client = self
+            .containers
+            .lock()
+            .expect("container cache to not be poisoned")
+            .entry(container.to_owned())
+            .or_insert_with(|| self.container.service_client().container_client(container))
+            .clone();
+
+        let mut config = self.config.clone();
+        config.container = container.to_owned();
+
+        Ok(StorageService {
+            container: client,
+            containers: self.containers.clone(),
+            events: self.events.clone(),
+            interceptors: self.interceptors.clone(),
+            initialized: Arc::default(),
+            memory: self.memory.clone(),
+            config,
+        })
+    }
+
+    /// Returns the [`StorageService`] for the container that was selected with the
+    /// [`Container`] request extension, if it isn't the container of this service.
+    fn selected_container(&self, extras: &Extensions) -> azure_core::Result<Option<StorageService>> {
+        match extras.get::<Container>() {
+            Some(Container(container)) if *container != self.config.container => {
+                self.for_container(container).map(Some)
+            }
+
+            _ => Ok(None),
+        }
+    }
+
+    /// Restores a blob that was soft-deleted, which requires soft-delete to be enabled on the
+    /// storage account. Returns `false` if there was no soft-deleted blob at `path` to restore.
+    ///
+    /// * since: 0.11.0
+    pub async fn undelete<P: AsRef<Path> + Send>(&self, path: P) -> azure_core::Result<bool> {
+        self.check_writable("undelete")?;
+
+        let path = path.as_ref();
+        check_key(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            container = self.config.container,
+            path = %path.display(),
+            "restoring soft-deleted blob in container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "restoring soft-deleted blob [{}] in container [{}]",
+            path.display(),
+            self.config.container
+        );
+
+        let name = self.sanitize_path(path)?;
+        match self.container.blob_client(&name).undelete().await {
+            Ok(_) => Ok(true),
+            Err(e) if matches!(e.as_http_error(), Some(e) if e.status() == StatusCode::NotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Moves the blob at `path` into another access tier, for example to move blobs that are no
+    /// longer read into [`AccessTier::Cool`], or to rehydrate a blob out of [`AccessTier::Archive`]
+    /// so it can be read again. Rehydrating can take hours and happens in the background. Returns
+    /// `false` if there was no blob at `path`.
+    ///
+    /// * since: 0.11.0
+    pub async fn set_tier<P: AsRef<Path> + Send>(&self, path: P, tier: AccessTier) -> azure_core::Result<bool> {
+        self.check_writable("set the access tier")?;
+
+        let path = path.as_ref();
+        check_key(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            container = self.config.container,
+            path = %path.display(),
+            ?tier,
+            "setting access tier of blob in container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "setting access tier of blob [{}] in container [{}] to {tier:?}",
+            path.display(),
+            self.config.container
+        );
+
+        let name = self.sanitize_path(path)?;
+        match self.container.blob_client(&name).set_blob_tier(tier.into()).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_blob_not_found(&e) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Acquires a lease on the blob at `path`, which must exist, for `duration`, which Azure
+    /// requires to be between 15 and 60 seconds. A lease that never expires is acquired if
+    /// `duration` is `None`. Returns `None` if another lease is held on the blob.
+    ///
+    /// * since: 0.11.0
+    pub async fn acquire_lease<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        duration: Option<Duration>,
+    ) -> azure_core::Result<Option<BlobLease>> {
+        self.check_writable("acquire a lease")?;
+
+        let path = path.as_ref();
+        check_key(path)?;
+
+        let duration = lease_duration(duration)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            container = self.config.container,
+            path = %path.display(),
+            "acquiring lease on blob in container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "acquiring lease on blob [{}] in container [{}]",
+            path.display(),
+            self.config.container
+        );
+
+        let name = self.sanitize_path(path)?;
+        let client = self.container.blob_client(&name);
+        let lease = match client.acquire_lease(duration).await {
+            Ok(response) => response.lease_id,
+            Err(e) if is_lease_already_present(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Some(BlobLease::new(
+            client.blob_lease_client(lease),
+            format!("azure://{name}"),
+        )))
+    }
+
+    /// Breaks the lease on the blob at `path`, which is useful for taking over a blob whose holder
+    /// crashed while holding a lease that never expires. Leases that never expire are broken right
+    /// away, while finite leases are broken once their remaining duration has passed. Returns
+    /// `false` if there was no blob at `path`.
+    ///
+    /// * since: 0.11.0
+    pub async fn break_lease<P: AsRef<Path> + Send>(&self, path: P) -> azure_core::Result<bool> {
+        self.check_writable("break a lease")?;
+
+        let path = path.as_ref();
+        check_key(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::warn!(
+            container = self.config.container,
+            path = %path.display(),
+            "breaking lease on blob in container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::warn!(
+            "breaking lease on blob [{}] in container [{}]",
+            path.display(),
+            self.config.container
+        );
+
+        let name = self.sanitize_path(path)?;
+        match self.container.blob_client(&name).break_lease().await {
+            Ok(_) => Ok(true),
+            Err(e) if is_blob_not_found(&e) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the access tier to upload blobs into, preferring the [`UploadAccessTier`]
+    /// request extension over [`StorageConfig::access_tier`].
+    fn access_tier(&self, requested: Option<&UploadAccessTier>) -> Option<AccessTier> {
+        match requested {
+            Some(UploadAccessTier(tier)) => Some(*tier),
+            None => self.config.access_tier,
+        }
+    }
+
+    /// Takes a read-only snapshot of the blob at `path` as it is right now, which is useful for
+    /// keeping a copy of a blob before it is overwritten. Snapshots are deleted together with
+    /// their blob. Returns `None` if there was no blob at `path`.
+    ///
+    /// * since: 0.11.0
+    pub async fn snapshot<P: AsRef<Path> + Send>(&self, path: P) -> azure_core::Result<Option<SnapshotId>> {
+        self.check_writable("take a snapshot")?;
+
+        let path = path.as_ref();
+        check_key(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            container = self.config.container,
+            path = %path.display(),
+            "taking snapshot of blob in container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "taking snapshot of blob [{}] in container [{}]",
+            path.display(),
+            self.config.container
+        );
+
+        let name = self.sanitize_path(path)?;
+        match self.container.blob_client(&name).snapshot().await {
+            Ok(response) => Ok(Some(SnapshotId(response.snapshot))),
+            Err(e) if is_blob_not_found(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Opens the contents of the blob at `path` as they were when `snapshot` was taken. Returns
+    /// `None` if the blob or the snapshot doesn't exist.
+    ///
+    /// * since: 0.11.0
+    pub async fn open_snapshot<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        snapshot: &SnapshotId,
+    ) -> azure_core::Result<Option<Bytes>> {
+        let path = path.as_ref();
+        check_key(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            container = self.config.container,
+            path = %path.display(),
+            "opening snapshot of blob in container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "opening snapshot of blob [{}] in container [{}]",
+            path.display(),
+            self.config.container
+        );
+
+        let client = self.container.blob_client(self.sanitize_path(path)?);
+        let _permit = match self.memory {
+            Some(_) => match client
+                .get_properties()
+                .blob_versioning(BlobVersioning::Snapshot(snapshot.0.clone()))
+                .await
+            {
+                Ok(props) => self.reserve(props.blob.properties.content_length).await?,
+                Err(e) if is_blob_not_found(&e) => return Ok(None),
+                Err(e) => return Err(e),
+            },
+
+            None => None,
+        };
+
+        self.snapshot_contents(&client, snapshot).await
+    }
+
+    /// Returns the blob at `path` as it was when `snapshot` was taken. Returns `None` if the
+    /// blob or the snapshot doesn't exist.
+    ///
+    /// * since: 0.11.0
+    pub async fn blob_snapshot<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        snapshot: &SnapshotId,
+    ) -> azure_core::Result<Option<Blob>> {
+        let path = path.as_ref();
+        check_key(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            container = self.config.container,
+            path = %path.display(),
+            "opening snapshot of blob in container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "opening snapshot of blob [{}] in container [{}]",
+            path.display(),
+            self.config.container
+        );
+
+        let client = self.container.blob_client(self.sanitize_path(path)?);
+        let props = match client
+            .get_properties()
+            .blob_versioning(BlobVersioning::Snapshot(snapshot.0.clone()))
+            .await
+        {
+            Ok(props) => props,
+            Err(e) if is_blob_not_found(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let _permit = self.reserve(props.blob.properties.content_length).await?;
+        let Some(data) = self.snapshot_contents(&client, snapshot).await? else {
+            // the blob was deleted in the meantime, which deletes its snapshots too
+            return Ok(None);
+        };
+
+        self.check_length(path, props.blob.properties.content_length, data.len())?;
+        self.file(props.blob, data).map(|file| Some(Blob::File(file)))
+    }
+
+    /// Downloads the contents of `snapshot` of the blob that `client` points to.
+    async fn snapshot_contents(&self, client: &BlobClient, snapshot: &SnapshotId) -> azure_core::Result<Option<Bytes>> {
+        // large blobs are downloaded in multiple requests
+        let mut responses = client
+            .get()
+            .blob_versioning(BlobVersioning::Snapshot(snapshot.0.clone()))
+            .into_stream();
+
+        let mut data = BytesMut::new();
+        while let Some(response) = responses.next().await {
+            match response {
+                Ok(response) => data.extend_from_slice(&response.data.collect().await?),
+                Err(e) if is_blob_not_found(&e) => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Some(data.freeze()))
+    }
+
+    /// Creates the container of this storage service if it doesn't exist, see
+    /// [`init`][remi::StorageService::init].
+    async fn initialize(&self) -> azure_core::Result<()> {
+        if self.container.exists().await? {
+            return Ok(());
+        }
+
+        if remi::skip_provisioning(self.config.skip_provisioning) {
+            let error = remi::ResourceMissing::new("container", &self.config.container);
+            return Err(azure_core::Error::full(
+                ErrorKind::Other,
+                error,
+                "blob container doesn't exist",
+            ));
+        }
+
+        self.check_writable("create the container")?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!("creating blob container as it doesn't exist");
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "creating blob container [{}] as it doesn't exist",
+            self.config.container
+        );
+
+        match self.container.create().await {
+            // another storage service could've created the container in the meantime
+            Err(e) if is_container_already_exists(&e) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Registers an [`UploadInterceptor`][remi::UploadInterceptor] that can inspect and reject
+    /// uploads before they are written, which fail with an error that holds a
+    /// [`RejectedByPolicy`][remi::RejectedByPolicy] error. Interceptors run in the
+    /// order that they were registered.
+    pub fn with_interceptor<I: remi::UploadInterceptor + 'static>(mut self, interceptor: I) -> StorageService {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Publishes a [`StorageEvent`] to the given channel after every successful upload or deletion.
+    pub fn with_events(mut self, events: broadcast::Sender<StorageEvent>) -> StorageService {
+        self.events = Some(events);
+        self
+    }
+
+    /// Rejects `operation` with a [`ReadOnly`][remi::ReadOnly] error if this storage service is read-only.
+    fn check_writable(&self, operation: &'static str) -> azure_core::Result<()> {
+        remi::ReadOnly::check(self.config.read_only, operation)
+            .map_err(|e| azure_core::Error::full(ErrorKind::Other, e, "storage service is read-only"))
+    }
+
+    fn publish(&self, event: StorageEvent) {
+        if let Some(ref events) = self.events {
+            // an error means that there are no receivers, which is fine
+            let _ = events.send(event);
+        }
+    }
+
+    /// Reserves `bytes` bytes from the [`strict_memory_limit`][StorageConfig::strict_memory_limit],
+    /// which are released once the returned permit is dropped.
+    async fn reserve(&self, bytes: u64) -> azure_core::Result<Option<remi::MemoryPermit>> {
+        let Some(ref limit) = self.memory else {
+            return Ok(None);
+        };
+
+        limit
+            .acquire(usize::try_from(bytes).unwrap_or(usize::MAX))
+            .await
+            .map(Some)
+            .map_err(|e| azure_core::Error::full(ErrorKind::Other, e, "memory limit was exceeded"))
+    }
+
+    /// Checks that the body of a blob that was read matches its reported content length, or
+    /// emits a warning instead if [`StorageConfig::warn_on_truncated_reads`] is enabled.
+    #[cfg_attr(not(any(feature = "tracing", feature = "log")), allow(unused_variables))]
+    fn check_length(&self, path: &Path, expected: u64, actual: usize) -> azure_core::Result<()> {
+        match remi::TruncatedRead::check(expected, actual) {
+            Ok(()) => Ok(()),
+            Err(err) if self.config.warn_on_truncated_reads => {
+                #[cfg(feature = "tracing")]
+                ::tracing::warn!(
+                    container = self.config.container,
+                    path = %path.display(),
+                    expected = err.expected,
+                    actual = err.actual,
+                    "body of blob doesn't match its reported size"
+                );
+
+                #[cfg(feature = "log")]
+                ::log::warn!(
+                    "body of blob [{}] in container [{}] doesn't match its reported size: {err}",
+                    path.display(),
+                    self.config.container
+                );
+
+                Ok(())
+            }
+
+            Err(err) => Err(azure_core::Error::full(
+                ErrorKind::Io,
+                err,
+                "body of blob doesn't match its reported size",
+            )),
+        }
+    }
+
+    /// Uploads `options` into `path`, where `on_conflict` decides what happens if a blob
+    /// already exists in `path`.
+    async fn put(&self, path: &Path, mut options: UploadRequest, on_conflict: OnConflict) -> azure_core::Result<()> {
+        self.check_writable("upload")?;
+
+        check_key(path)?;
+
+        if let Some(service) = self.selected_container(&options.extras)? {
+            options.extras.remove::<Container>();
+            return Box::pin(service.put(path, options, on_conflict)).await;
+        }
+
+        remi::TooLarge::check(options.data.len(), self.config.max_object_size)
+            .map_err(|e| azure_core::Error::full(ErrorKind::Other, e, "blob exceeds the maximum object size"))?;
+
+        let _permit = self.reserve(options.data.len() as u64).await?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            container = self.config.container,
+            path = %path.display(),
+            "uploading blob to container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "uploading blob [{}] into container [{}]",
+            path.display(),
+            self.config.container
+        );
+
+        let name = self.sanitize_path(path)?;
+        self.interceptors
+            .check(&format!("azure://{name}"), &options)
+            .await
+            .map_err(|e| azure_core::Error::full(ErrorKind::Other, e, "upload was rejected by an interceptor"))?;
+
+        let client = self.container.blob_client(&name);
+        let exclusive = on_conflict == OnConflict::Fail;
+        let existed = match on_conflict {
+            OnConflict::Overwrite => self.events.is_some() && client.exists().await?,
+            _ => client.exists().await?,
+        };
+
+        if existed && on_conflict == OnConflict::Snapshot {
+            // the snapshot keeps the current contents of the blob around as a read-only version
+            client.snapshot().await?;
+        } else if existed && exclusive {
+            return Err(already_exists(&name));
+        }
+
+        let event = self
+            .events
+            .is_some()
+            .then(|| StorageEvent::uploaded(format!("azure://{name}"), &options, existed));
+
+        let digest = self.config.verify_writes.then(|| Md5::digest(&options.data));
+        let mut blob = client.put_block_blob(options.data);
+        if exclusive {
+            // another upload could've created the blob in the meantime
+            blob = blob.if_match(IfMatchCondition::NotMatch(String::from("*")));
+        }
+
+        if let Some(ct) = options.content_type {
+            blob = blob.content_type(ct);
+        }
+
+        if let Some(tier) = self.access_tier(options.extras.get::<UploadAccessTier>()) {
+            blob = blob.access_tier(tier.into());
+        }
+
+        if let Some(Lease(lease)) = options.extras.get::<Lease>() {
+            blob = blob.lease_id(*lease);
+        }
+
+        let mut metadata = Metadata::new();
+        for (key, value) in options.metadata.clone() {
+            metadata.insert(key.as_str(), remi::Bytes::from(value));
+        }
+
+        let response = blob
+            .metadata(metadata)
+            .await
+            .map_err(|e| match e.as_http_error().map(|e| e.status()) {
+                Some(StatusCode::Conflict | StatusCode::PreconditionFailed) if exclusive => already_exists(&name),
+                _ => e,
+            })?;
+        if let Some(digest) = digest {
+            match response.content_md5 {
+                Some(md5) if md5.as_slice()[..] != digest[..] => {
+                    return Err(azure_core::Error::new(
+                        ErrorKind::DataConversion,
+                        format!("`Content-MD5` acknowledged for blob [{}] didn't match", path.display()),
+                    ));
+                }
+
+                Some(_) => {}
+                None => {
+                    #[cfg(feature = "tracing")]
+                    ::tracing::warn!(
+                        path = %path.display(),
+                        "Azure didn't acknowledge a `Content-MD5` for blob, unable to verify write"
+                    );
+
+                    #[cfg(feature = "log")]
+                    ::log::warn!(
+                        "Azure didn't acknowledge a `Content-MD5` for blob [{}], unable to verify write",
+                        path.display()
+                    );
+                }
+            }
+        }
+
+        if let Some(event) = event {
+            self.publish(event);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a [`File`] out of the properties of `blob` and its contents.
+    fn file(&self, blob: azure_storage_blobs::blob::Blob, data: Bytes) -> azure_core::Result<File> {
+        Ok(File {
+            last_modified_at: {
+                let last_modified: SystemTime = blob.properties.last_modified.into();
+                Some(
+                    last_modified
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .expect("SystemTime overflow?!")
+                        .as_millis(),
+                )
+            },
+            metadata: blob.metadata.unwrap_or_default(),
+            content_type: Some(blob.properties.content_type),
+            created_at: {
+                let created_at: SystemTime = blob.properties.creation_time.into();
+                Some(
+                    created_at
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .expect("SystemTime overflow?!")
+                        .as_millis(),
+                )
+            },
+            is_symlink: false,
+            checksum: blob.properties.content_md5.as_ref().map(|md5| hex(md5.as_slice())),
+            etag: Some(blob.properties.etag.to_string().trim_matches('"').to_owned()),
+            version: None,
+            expires_at: blob.properties.expiry_time.and_then(|expiry| {
+                SystemTime::from(expiry)
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .ok()
+                    .map(|since| since.as_millis())
+            }),
+            extras: Default::default(),
+            data,
+            path: format!("azure://{}", self.blob_name(&blob.name)),
+            name: self.blob_name(&blob.name).to_owned(),
+            size: blob.properties.content_length.try_into().map_err(|e| {
+                azure_core::Error::new(
+                    azure_core::error::ErrorKind::Other,
+                    format!("expected content length to fit into `usize`: {e}"),
+                )
+            })?,
+        })
+    }
+
     fn sanitize_path<P: AsRef<Path> + Send>(&self, path: P) -> azure_core::Result<String> {
-        let path = path
-            .as_ref()
-            .to_str()
-            .ok_or_else(|| azure_core::Error::new(ErrorKind::Other, "was not valid utf-8"))
-            .with_context(ErrorKind::Other, || "failed to convert path into a string")?;
+        sanitize_path(self.prefix(), path.as_ref())
+    }
+
+    /// Returns [`StorageConfig::prefix`] without any leading or trailing slashes, or `None`
+    /// if it isn't set or is empty.
+    fn prefix(&self) -> Option<&str> {
+        self.config
+            .prefix
+            .as_deref()
+            .map(|prefix| {
+                prefix
+                    .trim_start_matches("./")
+                    .trim_start_matches("~/")
+                    .trim_matches('/')
+            })
+            .filter(|prefix| !prefix.is_empty())
+    }
+
+    /// Returns the blob `name` relative to [`StorageConfig::prefix`], which can be passed back
+    /// into the methods of [`remi::StorageService`].
+    fn strip_prefix<'a>(&self, name: &'a str) -> &'a str {
+        self.prefix()
+            .and_then(|prefix| name.strip_prefix(prefix))
+            .and_then(|rest| rest.strip_prefix('/'))
+            .unwrap_or(name)
+    }
+
+    /// Returns the name that the blob `name` is returned under, which is relative to the
+    /// prefix of this storage service unless [`StorageConfig::include_prefix`] is set.
+    fn blob_name<'a>(&self, name: &'a str) -> &'a str {
+        match self.config.include_prefix {
+            true => name,
+            false => self.strip_prefix(name),
+        }
+    }
+
+    /// Looks for a blob next to `name` whose name only differs in case if
+    /// [`StorageConfig::case_insensitive_lookup`] is enabled.
+    async fn find_case_insensitive(&self, name: &str) -> azure_core::Result<Option<String>> {
+        if !self.config.case_insensitive_lookup {
+            return Ok(None);
+        }
+
+        let (parent, file_name) = match name.rsplit_once('/') {
+            Some((parent, file_name)) => (format!("{parent}/"), file_name),
+            None => (String::new(), name),
+        };
+
+        let mut stream = self
+            .container
+            .list_blobs()
+            .prefix(Prefix::from(parent.clone()))
+            .into_stream();
+        let mut names = vec![];
+        while let Some(value) = stream.next().await {
+            names.extend(
+                value?
+                    .blobs
+                    .blobs()
+                    .filter_map(|blob| blob.name.strip_prefix(parent.as_str()))
+                    .filter(|candidate| !candidate.contains('/') && *candidate != file_name)
+                    .map(String::from),
+            );
+        }
+
+        let Some(found) = remi::find_case_insensitive(file_name, names.iter().map(String::as_str)) else {
+            return Ok(None);
+        };
+
+        let found = format!("{parent}{found}");
+
+        #[cfg(feature = "tracing")]
+        ::tracing::warn!(name, found, "blob was found under a different case");
+
+        #[cfg(feature = "log")]
+        ::log::warn!("blob [{name}] was found under a different case: [{found}]");
+
+        // `sanitize_path` prepends the prefix of this storage service again
+        Ok(Some(self.strip_prefix(&found).to_owned()))
+    }
+
+    fn directory_marker(&self) -> &str {
+        self.config
+            .directory_marker
+            .as_deref()
+            .unwrap_or(remi::DEFAULT_DIRECTORY_MARKER)
+    }
+
+    /// Lists the blobs in the container, starting from the page after the one whose `NextMarker`
+    /// is `continuation`. Only a single page is returned unless `all` is `true`.
+    async fn list(
+        &self,
+        options: &ListBlobsRequest,
+        continuation: Option<&str>,
+        all: bool,
+    ) -> azure_core::Result<Page<Blob>> {
+        let include_deleted = options.extras.get::<IncludeDeleted>().is_some();
+        let mut blobs = self.container.list_blobs().include_deleted(include_deleted);
+
+        let prefix = match self.prefix() {
+            Some(base) => Some(format!("{base}/{}", options.prefix.as_deref().unwrap_or_default())),
+            None => options.prefix.clone(),
+        };
+
+        if let Some(prefix) = prefix {
+            blobs = blobs.prefix(Prefix::from(prefix));
+        }
+
+        let max_results = options
+            .page_size
+            .and_then(|size| NonZeroU32::new(size.get().min(MAX_RESULTS)))
+            .or(self.config.max_results);
+
+        if let Some(max_results) = max_results {
+            blobs = blobs.max_results(MaxResults::new(max_results));
+        }
+
+        // directories are only listed one level deep, as `BlobPrefix` entries
+        let delimiter = self.config.delimiter.as_deref().unwrap_or("/");
+        if options.include_dirs {
+            blobs = blobs.delimiter(Delimiter::from(delimiter.to_owned()));
+        }
+
+        // every page is fetched by following the `NextMarker` of the previous page. The SDK
+        // can't start a listing from a marker, so the pages before `continuation` are listed
+        // again (without downloading any blobs) until the page that it points to
+        let mut stream = blobs.into_stream();
+        let mut requests = 0;
+        if let Some(continuation) = continuation {
+            loop {
+                let Some(value) = stream.next().await else {
+                    return Ok(Page::default().with_requests(requests));
+                };
+
+                requests += 1;
+
+                if value?.next_marker.as_ref().map(|marker| marker.as_str()) == Some(continuation) {
+                    break;
+                }
+            }
+        }
+
+        let mut blobs = vec![];
+        while let Some(value) = stream.next().await {
+            requests += 1;
+            let data = value?;
+            let next_marker = data.next_marker.as_ref().map(|marker| marker.as_str().to_owned());
 
-        let path = path.trim_start_matches("./").trim_start_matches("~/");
-        Ok(path.into())
+            #[cfg(feature = "tracing")]
+            ::tracing::trace!(
+                container = self.config.container,
+                more = data.next_marker.is_some(),
+                "received page of blobs"
+            );
+
+            #[cfg(feature = "log")]
+            ::log::trace!(
+                "received page of blobs in container [{}] (more pages: {})",
+                self.config.container,
+                data.next_marker.is_some()
+            );
+
+            for prefix in data.blobs.prefixes() {
+                let dir = prefix.name.trim_end_matches(delimiter);
+                blobs.push(Blob::Directory(Directory {
+                    created_at: None,
+                    name: dir.rsplit(delimiter).next().unwrap_or(dir).to_owned(),
+                    path: format!("azure://{}", self.blob_name(dir)),
+                }));
+            }
+
+            for blob in data.blobs.blobs() {
+                // empty directories are represented as `{dir}/{marker}`, which are already
+                // listed as `BlobPrefix` entries when directories are included
+                let marker = self.directory_marker();
+                if blob.name == marker || blob.name.ends_with(&format!("/{marker}")) {
+                    continue;
+                }
+
+                // soft-deleted blobs can't be read until they're restored
+                let deleted = blob.deleted.unwrap_or(false);
+                let mut metadata = blob.metadata.clone().unwrap_or_default();
+                if deleted {
+                    metadata.insert(String::from("deleted"), String::from("true"));
+                }
+
+                blobs.push(Blob::File(File {
+                    last_modified_at: {
+                        let last_modified: SystemTime = blob.properties.last_modified.into();
+                        Some(
+                            last_modified
+                                .duration_since(SystemTime::UNIX_EPOCH)
+                                .expect("SystemTime overflow?!")
+                                .as_millis(),
+                        )
+                    },
+                    metadata,
+                    content_type: Some(blob.properties.content_type.clone()),
+                    created_at: {
+                        let created_at: SystemTime = blob.properties.creation_time.into();
+                        Some(
+                            created_at
+                                .duration_since(SystemTime::UNIX_EPOCH)
+                                .expect("SystemTime overflow?!")
+                                .as_millis(),
+                        )
+                    },
+                    is_symlink: false,
+                    checksum: blob.properties.content_md5.as_ref().map(|md5| hex(md5.as_slice())),
+                    etag: Some(blob.properties.etag.to_string().trim_matches('"').to_owned()),
+                    version: None,
+                    expires_at: blob.properties.expiry_time.and_then(|expiry| {
+                        SystemTime::from(expiry)
+                            .duration_since(SystemTime::UNIX_EPOCH)
+                            .ok()
+                            .map(|since| since.as_millis())
+                    }),
+                    extras: Default::default(),
+                    data: match deleted || options.metadata_only {
+                        true => Bytes::new(),
+                        false => {
+                            requests += 1;
+                            self.open(self.strip_prefix(&blob.name)).await?.unwrap()
+                        }
+                    },
+                    path: format!("azure://{}", self.blob_name(&blob.name)),
+                    name: self.blob_name(&blob.name).to_owned(),
+                    size: blob.properties.content_length.try_into().map_err(|e| {
+                        azure_core::Error::new(
+                            azure_core::error::ErrorKind::Other,
+                            format!("expected content length to fit into `usize`: {e}"),
+                        )
+                    })?,
+                }));
+            }
+
+            if !all && next_marker.is_some() {
+                return Ok(Page::new(blobs, next_marker).with_requests(requests));
+            }
+        }
+
+        Ok(Page::last(blobs).with_requests(requests))
     }
 }
 
@@ -94,26 +1194,164 @@ impl remi::StorageService for StorageService {
         )
     )]
     async fn init(&self) -> Result<(), Self::Error> {
-        if self.container.exists().await? {
-            return Ok(());
+        // concurrent callers wait for the first one instead of racing to create the container,
+        // and a failed initialization is retried by the next caller
+        self.initialized.get_or_try_init(|| self.initialize()).await.copied()
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.azure.open",
+            skip_all,
+            fields(
+                remi.service = "azure",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        check_key(path.as_ref())?;
+        let path = path.as_ref();
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            container = self.config.container,
+            path = %path.display(),
+            "opening blob in container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "opening blob [{}] in container [{}]",
+            path.display(),
+            self.config.container
+        );
+
+        let client = self.container.blob_client(self.sanitize_path(path)?);
+
+        // the size of a blob is only known up front from its properties, so they're
+        // only requested if there is a memory limit to reserve it from
+        let _permit = match self.memory {
+            Some(_) => match client.get_properties().await {
+                Ok(props) => self.reserve(props.blob.properties.content_length).await?,
+                Err(e) if is_blob_not_found(&e) => {
+                    return match self.find_case_insensitive(client.blob_name()).await? {
+                        Some(found) => self.open(found).await,
+                        None => Ok(None),
+                    };
+                }
+
+                Err(e) => return Err(e),
+            },
+
+            None => None,
+        };
+
+        match client.get_content().await {
+            Ok(content) => Ok(Some(From::from(content))),
+            Err(e) if is_blob_not_found(&e) => match self.find_case_insensitive(client.blob_name()).await? {
+                Some(found) => self.open(found).await,
+                None => Ok(None),
+            },
+
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.azure.open_range",
+            skip_all,
+            fields(
+                remi.service = "azure",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        check_key(path.as_ref())?;
+        let path = path.as_ref();
+
+        // an empty range can't be sent as a `Range` header
+        if end.is_some_and(|end| end <= start) {
+            return Ok(self.exists(path).await?.then(Bytes::new));
         }
 
         #[cfg(feature = "tracing")]
-        ::tracing::info!("creating blob container as it doesn't exist");
+        ::tracing::info!(
+            container = self.config.container,
+            path = %path.display(),
+            start,
+            end = ?end,
+            "opening range of blob in container"
+        );
 
         #[cfg(feature = "log")]
         ::log::info!(
-            "creating blob container [{}] as it doesn't exist",
+            "opening range [{start}..{end:?}] of blob [{}] in container [{}]",
+            path.display(),
             self.config.container
         );
 
-        self.container.create().await
+        let client = self.container.blob_client(self.sanitize_path(path)?);
+        let _permit = match (self.memory.as_ref(), end) {
+            (Some(_), Some(end)) => self.reserve(end - start).await?,
+            (Some(_), None) => match client.get_properties().await {
+                Ok(props) => {
+                    let (start, end) = remi::clamp_range(start, None, props.blob.properties.content_length);
+                    self.reserve(end - start).await?
+                }
+
+                Err(e) if is_blob_not_found(&e) => {
+                    return match self.find_case_insensitive(client.blob_name()).await? {
+                        Some(found) => self.open_range(found, start, end).await,
+                        None => Ok(None),
+                    };
+                }
+
+                Err(e) => return Err(e),
+            },
+
+            (None, _) => None,
+        };
+
+        let request = match end {
+            Some(end) => client.get().range(start..end),
+            None => client.get().range(start..),
+        };
+
+        // large ranges are downloaded in multiple requests
+        let mut responses = request.into_stream();
+        let mut data = BytesMut::new();
+        while let Some(response) = responses.next().await {
+            match response {
+                Ok(response) => data.extend_from_slice(&response.data.collect().await?),
+                Err(e) if is_range_not_satisfiable(&e) => return Ok(Some(Bytes::new())),
+                Err(e) if is_blob_not_found(&e) => {
+                    return match self.find_case_insensitive(client.blob_name()).await? {
+                        Some(found) => self.open_range(found, start, end).await,
+                        None => Ok(None),
+                    };
+                }
+
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Some(data.freeze()))
     }
 
     #[cfg_attr(
         feature = "tracing",
         tracing::instrument(
-            name = "remi.azure.open",
+            name = "remi.azure.open_stream",
             skip_all,
             fields(
                 remi.service = "azure",
@@ -121,29 +1359,46 @@ impl remi::StorageService for StorageService {
             )
         )
     )]
-    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+    async fn open_stream<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+    ) -> Result<Option<ContentStream<Self::Error>>, Self::Error> {
+        check_key(path.as_ref())?;
         let path = path.as_ref();
 
         #[cfg(feature = "tracing")]
         ::tracing::info!(
             container = self.config.container,
             path = %path.display(),
-            "opening blob in container"
+            "opening blob in container as a stream"
         );
 
         #[cfg(feature = "log")]
         ::log::info!(
-            "opening blob [{}] in container [{}]",
+            "opening blob [{}] in container [{}] as a stream",
             path.display(),
             self.config.container
         );
 
         let client = self.container.blob_client(self.sanitize_path(path)?);
-        if !client.exists().await? {
-            return Ok(None);
-        }
 
-        client.get_content().await.map(|content| Some(From::from(content)))
+        // the blob is downloaded in ranges, which are only requested as the stream is polled
+        let mut ranges = client.get().into_stream();
+        let first = match ranges.next().await {
+            Some(Ok(response)) => response,
+            Some(Err(e)) if is_blob_not_found(&e) => {
+                return match self.find_case_insensitive(client.blob_name()).await? {
+                    Some(found) => self.open_stream(found).await,
+                    None => Ok(None),
+                };
+            }
+
+            Some(Err(e)) => return Err(e),
+            None => return Ok(None),
+        };
+
+        let rest = ranges.map_ok(|response| response.data).try_flatten();
+        Ok(Some(first.data.chain(rest).boxed()))
     }
 
     #[cfg_attr(
@@ -158,6 +1413,7 @@ impl remi::StorageService for StorageService {
         )
     )]
     async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        check_key(path.as_ref())?;
         let path = path.as_ref();
 
         #[cfg(feature = "tracing")]
@@ -175,45 +1431,23 @@ impl remi::StorageService for StorageService {
         );
 
         let client = self.container.blob_client(self.sanitize_path(path)?);
-        if !client.exists().await? {
-            return Ok(None);
-        }
+        let props = match client.get_properties().await {
+            Ok(props) => props,
+            Err(e) if is_blob_not_found(&e) => {
+                return match self.find_case_insensitive(client.blob_name()).await? {
+                    Some(found) => self.blob(found).await,
+                    None => Ok(None),
+                };
+            }
 
-        let props = client.get_properties().await?;
+            Err(e) => return Err(e),
+        };
+
+        let _permit = self.reserve(props.blob.properties.content_length).await?;
         let data = Bytes::from(client.get_content().await?);
+        self.check_length(path, props.blob.properties.content_length, data.len())?;
 
-        Ok(Some(Blob::File(File {
-            last_modified_at: {
-                let last_modified: SystemTime = props.blob.properties.last_modified.into();
-                Some(
-                    last_modified
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .expect("SystemTime overflow?!")
-                        .as_millis(),
-                )
-            },
-            metadata: props.blob.metadata.unwrap_or_default(),
-            content_type: Some(props.blob.properties.content_type),
-            created_at: {
-                let created_at: SystemTime = props.blob.properties.creation_time.into();
-                Some(
-                    created_at
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .expect("SystemTime overflow?!")
-                        .as_millis(),
-                )
-            },
-            is_symlink: false,
-            data,
-            path: format!("azure://{}", props.blob.name),
-            name: props.blob.name,
-            size: props.blob.properties.content_length.try_into().map_err(|e| {
-                azure_core::Error::new(
-                    azure_core::error::ErrorKind::Other,
-                    format!("expected content length to fit into `usize`: {e}"),
-                )
-            })?,
-        })))
+        self.file(props.blob, data).map(|file| Some(Blob::File(file)))
     }
 
     #[cfg_attr(
@@ -250,54 +1484,43 @@ impl remi::StorageService for StorageService {
             return Ok(vec![]);
         }
 
-        let options = request.unwrap_or_default();
-        let mut blobs = self.container.list_blobs();
+        let mut options = request.unwrap_or_default();
+        if let Some(service) = self.selected_container(&options.extras)? {
+            options.extras.remove::<Container>();
+            return service.blobs(None::<&str>, Some(options)).await;
+        }
+
+        self.list(&options, options.continuation.as_deref(), true)
+            .await
+            .map(|page| page.items)
+    }
 
-        if let Some(prefix) = options.prefix {
-            blobs = blobs.prefix(Prefix::from(prefix.clone()));
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.azure.blobs_page",
+            skip_all,
+            fields(
+                remi.service = "azure"
+            )
+        )
+    )]
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        request: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        if path.is_some() {
+            return Ok(Page::default());
         }
 
-        let mut stream = blobs.into_stream();
-        let mut blobs = vec![];
-        while let Some(value) = stream.next().await {
-            let data = value?;
-            for blob in data.blobs.blobs() {
-                blobs.push(Blob::File(File {
-                    last_modified_at: {
-                        let last_modified: SystemTime = blob.properties.last_modified.into();
-                        Some(
-                            last_modified
-                                .duration_since(SystemTime::UNIX_EPOCH)
-                                .expect("SystemTime overflow?!")
-                                .as_millis(),
-                        )
-                    },
-                    metadata: blob.metadata.clone().unwrap_or_default(),
-                    content_type: Some(blob.properties.content_type.clone()),
-                    created_at: {
-                        let created_at: SystemTime = blob.properties.creation_time.into();
-                        Some(
-                            created_at
-                                .duration_since(SystemTime::UNIX_EPOCH)
-                                .expect("SystemTime overflow?!")
-                                .as_millis(),
-                        )
-                    },
-                    is_symlink: false,
-                    data: self.open(&blob.name).await?.unwrap(),
-                    path: format!("azure://{}", blob.name),
-                    name: blob.name.clone(),
-                    size: blob.properties.content_length.try_into().map_err(|e| {
-                        azure_core::Error::new(
-                            azure_core::error::ErrorKind::Other,
-                            format!("expected content length to fit into `usize`: {e}"),
-                        )
-                    })?,
-                }));
-            }
+        let mut options = request.unwrap_or_default();
+        if let Some(service) = self.selected_container(&options.extras)? {
+            options.extras.remove::<Container>();
+            return service.blobs_page(None::<&str>, Some(options)).await;
         }
 
-        Ok(blobs)
+        self.list(&options, options.continuation.as_deref(), false).await
     }
 
     #[cfg_attr(
@@ -311,7 +1534,9 @@ impl remi::StorageService for StorageService {
             )
         )
     )]
-    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.check_writable("delete")?;
+
         let path = path.as_ref();
 
         #[cfg(feature = "tracing")]
@@ -328,12 +1553,122 @@ impl remi::StorageService for StorageService {
             self.config.container
         );
 
-        let client = self.container.blob_client(self.sanitize_path(path)?);
+        let name = self.sanitize_path(path)?;
+        let client = self.container.blob_client(&name);
         if !client.exists().await? {
-            return Ok(());
+            return Ok(false);
+        }
+
+        match client.delete().await {
+            Ok(_) => {}
+
+            // it was deleted by someone else after it was checked
+            Err(e) if is_blob_not_found(&e) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+
+        self.publish(StorageEvent::Deleted {
+            path: format!("azure://{name}"),
+        });
+
+        Ok(true)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.azure.delete_prefix",
+            skip_all,
+            fields(
+                remi.service = "azure",
+                prefix = %prefix.as_ref().display()
+            )
+        )
+    )]
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        self.check_writable("delete")?;
+
+        let prefix = format!("{}/", self.sanitize_path(prefix)?.trim_end_matches('/'));
+        let mut stream = self.container.list_blobs().prefix(Prefix::from(prefix)).into_stream();
+        let mut names = vec![];
+        while let Some(value) = stream.next().await {
+            names.extend(
+                value?
+                    .blobs
+                    .blobs()
+                    .map(|blob| self.strip_prefix(&blob.name).to_owned()),
+            );
         }
 
-        client.delete().await.map(|_| ())
+        Ok(remi::delete_keys(self, names, remi::DEFAULT_DELETE_CONCURRENCY).await)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.azure.copy",
+            skip_all,
+            fields(
+                remi.service = "azure",
+                src = %src.as_ref().display(),
+                dst = %dst.as_ref().display()
+            )
+        )
+    )]
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        self.check_writable("copy")?;
+
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            container = self.config.container,
+            src = %src.display(),
+            dst = %dst.display(),
+            "copying blob in container"
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!(
+            "copying blob [{}] to [{}] in container [{}]",
+            src.display(),
+            dst.display(),
+            self.config.container
+        );
+
+        let source = self.container.blob_client(self.sanitize_path(src)?);
+        let props = match source.get_properties().await {
+            Ok(props) => props,
+            Err(e) if is_blob_not_found(&e) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        let name = self.sanitize_path(dst)?;
+        let client = self.container.blob_client(&name);
+        let existed = match self.events {
+            Some(_) => client.exists().await?,
+            None => false,
+        };
+
+        // copies within the same storage account are authorized by the destination's
+        // credentials, so the plain URL of the source blob is enough
+        client.copy(source.url()?).await?;
+
+        if self.events.is_some() {
+            let event = ObjectEvent {
+                path: format!("azure://{name}"),
+                size: props.blob.properties.content_length.try_into().unwrap_or(usize::MAX),
+                content_type: Some(props.blob.properties.content_type),
+                metadata: props.blob.metadata.unwrap_or_default(),
+            };
+
+            self.publish(match existed {
+                true => StorageEvent::Updated(event),
+                false => StorageEvent::Created(event),
+            });
+        }
+
+        Ok(true)
     }
 
     #[cfg_attr(
@@ -364,7 +1699,15 @@ impl remi::StorageService for StorageService {
             self.config.container
         );
 
-        self.container.blob_client(self.sanitize_path(path)?).exists().await
+        let name = self.sanitize_path(path)?;
+        if self.container.blob_client(&name).exists().await? {
+            return Ok(true);
+        }
+
+        match self.find_case_insensitive(&name).await? {
+            Some(found) => self.exists(found).await,
+            None => Ok(false),
+        }
     }
 
     #[cfg_attr(
@@ -380,51 +1723,259 @@ impl remi::StorageService for StorageService {
     )]
     async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
         let path = path.as_ref();
+        match options.collision() {
+            None | Some(CollisionStrategy::Overwrite) => self.put(path, options, OnConflict::Overwrite).await,
+            Some(CollisionStrategy::Error) => self.put(path, options, OnConflict::Fail).await,
+            Some(CollisionStrategy::NewVersion) => self.put(path, options, OnConflict::Snapshot).await,
+            Some(strategy @ CollisionStrategy::Suffix(_)) => {
+                for candidate in strategy.candidates(path) {
+                    match self.put(&candidate, options.clone(), OnConflict::Fail).await {
+                        Err(e) if e.get_ref().is_some_and(|e| e.is::<remi::AlreadyExists>()) => continue,
+                        result => return result,
+                    }
+                }
+
+                Err(already_exists(&self.sanitize_path(path)?))
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.azure.create_exclusive",
+            skip_all,
+            fields(
+                remi.service = "azure",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        self.put(path.as_ref(), options, OnConflict::Fail).await
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.config.read_only
+    }
+
+    fn info(&self) -> ServiceInfo {
+        let endpoint = match &self.config.location {
+            CloudLocation::Public(account) => format!("https://{account}.blob.core.windows.net"),
+            CloudLocation::China(account) => format!("https://{account}.blob.core.chinacloudapi.cn"),
+            CloudLocation::Emulator { address, port } => format!("http://{address}:{port}"),
+            CloudLocation::Custom { uri, .. } => uri.clone(),
+        };
+
+        ServiceInfo::new(self.name())
+            .with_crate_version(env!("CARGO_PKG_VERSION"))
+            .with_provider_endpoint(Some(endpoint))
+            .with_location(Some(self.config.container.clone()))
+            .with_capabilities(Capabilities {
+                range_reads: true,
+                streaming: true,
+                pagination: true,
+                server_side_copy: true,
+                versioning: true,
+                presigning: true,
+                ..Default::default()
+            })
+    }
+
+    async fn debug_validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let exists = match self.container.exists().await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(format!("container [{}] doesn't exist", self.config.container)),
+            Err(e) => Err(e.to_string()),
+        };
+
+        if !report.record(
+            "container exists",
+            &exists,
+            "check the credentials and location, or call `StorageService::init` to create the container",
+        ) {
+            report.skip("write probe object");
+            report.skip("read probe object");
+            report.skip("delete probe object");
+
+            return report;
+        }
+
+        remi::probe_read_write(self, &mut report).await;
+        report
+    }
+
+    /// Requests the storage account's information with access keys and Microsoft Entra tokens. Shared
+    /// access signatures and anonymous access usually can't read it, so they check that the
+    /// container exists instead.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "remi.azure.verify_credentials", skip_all)
+    )]
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!("verifying credentials");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("verifying credentials for container [{}]", self.config.container);
+
+        match self.config.credentials {
+            Credential::AccessKey { .. }
+            | Credential::Bearer(_)
+            | Credential::ManagedIdentity { .. }
+            | Credential::ServicePrincipal { .. } => self
+                .container
+                .service_client()
+                .get_account_information()
+                .await
+                .map(|_| ()),
+
+            Credential::SASToken(_) | Credential::Anonymous => self.container.exists().await.map(|_| ()),
+        }
+    }
+
+    /// Requests the container's properties, which needs permission to read the container.
+    /// Writes aren't probed, since that would trigger blob events and change feeds.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "remi.azure.healthcheck", skip_all))]
+    async fn healthcheck(&self) -> HealthReport {
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!("performing healthcheck");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("performing healthcheck for container [{}]", self.config.container);
+
+        let started = Instant::now();
+        let result = self.container.get_properties().await;
+
+        let latency = started.elapsed();
+        let Err(e) = result else {
+            return HealthReport::reachable(latency).with_read(true);
+        };
+
+        let Some(status) = e.as_http_error().map(|e| e.status()) else {
+            return HealthReport::unreachable(latency, e);
+        };
+
+        let report = HealthReport::reachable(latency);
+        match status {
+            StatusCode::Unauthorized | StatusCode::Forbidden => report.with_read(false).with_error(e),
+            StatusCode::NotFound => report.with_error(format!("container [{}] doesn't exist", self.config.container)),
+            _ => report.with_error(e),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.azure.create_dir",
+            skip_all,
+            fields(
+                remi.service = "azure",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        self.check_writable("create a directory")?;
+
+        let path = self.sanitize_path(path)?;
+        let name = format!("{}/{}", path.trim_end_matches('/'), self.directory_marker());
 
         #[cfg(feature = "tracing")]
         ::tracing::info!(
             container = self.config.container,
-            path = %path.display(),
-            "uploading blob to container"
+            blob = name,
+            "creating directory marker in container"
         );
 
         #[cfg(feature = "log")]
         ::log::info!(
-            "uploading blob [{}] into container [{}]",
-            path.display(),
+            "creating directory marker [{}] in container [{}]",
+            name,
             self.config.container
         );
 
-        let client = self.container.blob_client(self.sanitize_path(path)?);
-        if client.exists().await? {
-            #[cfg(feature = "tracing")]
-            ::tracing::warn!(
-                container = self.config.container,
-                path = %path.display(),
-                "blob with path already exists in container, skipping"
-            );
+        self.container
+            .blob_client(name)
+            .put_block_blob(Bytes::new())
+            .await
+            .map(|_| ())
+    }
+}
 
-            #[cfg(feature = "log")]
-            ::log::info!(
-                "blob with path [{}] already exist in container [{}], skipping",
-                path.display(),
-                self.config.container
-            );
+#[async_trait]
+impl remi::PresignableStorageService for StorageService {
+    /// Creates a service SAS for the blob, which requires the storage service to be
+    /// configured with an account access key.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.azure.presign",
+            skip(self, path),
+            fields(
+                remi.service = "azure",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn presign<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: PresignOptions,
+    ) -> Result<PresignedUrl, Self::Error> {
+        check_key(path.as_ref())?;
 
-            return Ok(());
-        }
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(
+            container = self.config.container,
+            method = %options.method,
+            "creating shared access signature for blob"
+        );
 
-        let mut blob = client.put_block_blob(options.data);
-        if let Some(ct) = options.content_type {
-            blob = blob.content_type(ct);
-        }
+        #[cfg(feature = "log")]
+        ::log::trace!(
+            "creating shared access signature for {} requests to blob [{}] in container [{}]",
+            options.method,
+            path.as_ref().display(),
+            self.config.container
+        );
 
-        let mut metadata = Metadata::new();
-        for (key, value) in options.metadata.clone() {
-            metadata.insert(key.as_str(), remi::Bytes::from(value));
-        }
+        let permissions = match options.method {
+            PresignMethod::Put => BlobSasPermissions {
+                create: true,
+                write: true,
+                ..Default::default()
+            },
 
-        blob.metadata(metadata).await.map(|_| ())
+            _ => BlobSasPermissions {
+                read: true,
+                ..Default::default()
+            },
+        };
+
+        let expires_at = SystemTime::now() + options.expires_in;
+        let client = self.container.blob_client(self.sanitize_path(path)?);
+        let signature = client
+            .shared_access_signature(permissions, OffsetDateTime::from(expires_at))
+            .await?;
+
+        // blobs that are created with a SAS need to say which type of blob they are
+        let headers = match options.method {
+            PresignMethod::Put => vec![(String::from("x-ms-blob-type"), String::from("BlockBlob"))],
+            _ => Vec::new(),
+        };
+
+        Ok(PresignedUrl {
+            url: client.generate_signed_blob_url(&signature)?.to_string(),
+            method: options.method,
+            headers,
+            expires_at,
+        })
     }
 }
 
@@ -489,7 +2040,7 @@ impl remi::StorageService for StorageService {
 //                         container: String::from("test-container"),
 //                         credentials: Credential::AccessKey {
 //                             account: String::from("devstoreaccount1"),
-//                             access_key: String::from(
+//                             access_key: remi::Secret::from(
 //                                 "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==",
 //                             ),
 //                         },
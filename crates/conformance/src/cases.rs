@@ -0,0 +1,337 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use bytes::Bytes;
+use remi::{Blob, CollisionStrategy, File, ListBlobsRequest, StorageService, UploadRequest};
+use std::{fmt::Debug, num::NonZeroU32};
+
+async fn upload<S>(storage: &S, path: &str, data: &'static [u8])
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    storage
+        .upload(
+            path,
+            UploadRequest::default()
+                .with_collision(CollisionStrategy::Overwrite)
+                .with_data(data),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("uploading `{path}` failed: {e:?}"));
+}
+
+async fn open<S>(storage: &S, path: &str) -> Option<Bytes>
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    storage
+        .open(path)
+        .await
+        .unwrap_or_else(|e| panic!("opening `{path}` failed: {e:?}"))
+}
+
+/// Returns the last segment of a file's name, since some storage services name files
+/// after their full key.
+fn file_name(file: &File) -> &str {
+    file.name.rsplit('/').next().unwrap_or(&file.name)
+}
+
+fn file_names<'a, I: IntoIterator<Item = &'a Blob>>(blobs: I) -> Vec<String> {
+    let mut names = blobs
+        .into_iter()
+        .filter_map(|blob| match blob {
+            Blob::File(file) => Some(file_name(file).to_owned()),
+            Blob::Directory(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    names.sort_unstable();
+    names
+}
+
+/// Checks that [`StorageService::init`] can be called again on a storage service that
+/// was already initialized, without failing or losing the files in it.
+pub async fn init_is_idempotent<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    storage.init().await.unwrap();
+    upload(storage, "./a.txt", b"a").await;
+
+    storage.init().await.unwrap();
+    storage.init().await.unwrap();
+    assert_eq!(open(storage, "./a.txt").await.as_deref(), Some(&b"a"[..]));
+}
+
+/// Checks that files which don't exist are reported as missing instead of failing.
+pub async fn open_missing_file<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    assert_eq!(open(storage, "./missing.txt").await, None);
+    assert!(storage.blob("./missing.txt").await.unwrap().is_none());
+    assert!(!storage.exists("./missing.txt").await.unwrap());
+}
+
+/// Checks that uploaded files can be opened, and that uploading to the same key again
+/// with [`CollisionStrategy::Overwrite`] replaces them.
+pub async fn upload_and_open<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./weow.txt", b"weow fluff").await;
+    assert!(storage.exists("./weow.txt").await.unwrap());
+    assert_eq!(open(storage, "./weow.txt").await.as_deref(), Some(&b"weow fluff"[..]));
+
+    upload(storage, "./weow.txt", b"fluff").await;
+    assert_eq!(open(storage, "./weow.txt").await.as_deref(), Some(&b"fluff"[..]));
+
+    upload(storage, "./nested/weow.txt", b"nested").await;
+    assert_eq!(
+        open(storage, "./nested/weow.txt").await.as_deref(),
+        Some(&b"nested"[..])
+    );
+}
+
+/// Checks that [`StorageService::blob`] returns the contents, size, and name of a file.
+pub async fn blob_of_file<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./nested/weow.txt", b"weow fluff").await;
+
+    let Some(Blob::File(file)) = storage.blob("./nested/weow.txt").await.unwrap() else {
+        panic!("expected `./nested/weow.txt` to be a file");
+    };
+
+    assert_eq!(file_name(&file), "weow.txt");
+    assert!(file.path.ends_with("weow.txt"), "unexpected path `{}`", file.path);
+    assert_eq!(file.size, 10);
+    assert_eq!(file.data, &b"weow fluff"[..]);
+}
+
+/// Checks that [`StorageService::open_range`] returns the requested bytes of a file.
+pub async fn open_range<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./weow.txt", b"hello world").await;
+
+    let range = storage.open_range("./weow.txt", 0, Some(5)).await.unwrap();
+    assert_eq!(range.as_deref(), Some(&b"hello"[..]));
+
+    let range = storage.open_range("./weow.txt", 6, None).await.unwrap();
+    assert_eq!(range.as_deref(), Some(&b"world"[..]));
+
+    let range = storage.open_range("./missing.txt", 0, Some(5)).await.unwrap();
+    assert_eq!(range, None);
+}
+
+/// Checks that deleted files no longer exist, that other files are kept, and that
+/// deleting a file which doesn't exist reports that nothing was deleted.
+pub async fn delete_file<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./a.txt", b"a").await;
+    upload(storage, "./b.txt", b"b").await;
+
+    assert!(storage.delete("./a.txt").await.unwrap());
+    assert!(!storage.delete("./a.txt").await.unwrap());
+    assert!(!storage.exists("./a.txt").await.unwrap());
+    assert_eq!(open(storage, "./a.txt").await, None);
+    assert_eq!(open(storage, "./b.txt").await.as_deref(), Some(&b"b"[..]));
+}
+
+/// Checks that [`StorageService::create_exclusive`] fails on files that exist and
+/// keeps their contents.
+pub async fn create_exclusive<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    storage
+        .create_exclusive("./lock", UploadRequest::default().with_data(&b"first"[..]))
+        .await
+        .unwrap();
+
+    assert!(storage
+        .create_exclusive("./lock", UploadRequest::default().with_data(&b"second"[..]))
+        .await
+        .is_err());
+
+    assert_eq!(open(storage, "./lock").await.as_deref(), Some(&b"first"[..]));
+}
+
+/// Checks that uploads replace files that exist unless they should fail if one does, in
+/// which case the existing file is kept.
+pub async fn upload_overwrite<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./a.txt", b"first").await;
+    storage
+        .upload(
+            "./a.txt",
+            UploadRequest::default().with_overwrite(true).with_data(&b"second"[..]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(open(storage, "./a.txt").await.as_deref(), Some(&b"second"[..]));
+    assert!(storage
+        .upload(
+            "./a.txt",
+            UploadRequest::default()
+                .with_fail_if_exists(true)
+                .with_data(&b"third"[..])
+        )
+        .await
+        .is_err());
+
+    assert_eq!(open(storage, "./a.txt").await.as_deref(), Some(&b"second"[..]));
+}
+
+/// Checks that listing a directory returns the files in it.
+pub async fn list_files<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./dir/b.txt", b"b").await;
+    upload(storage, "./dir/a.txt", b"a").await;
+    upload(storage, "./dir/c.md", b"c").await;
+    upload(storage, "./other.txt", b"other").await;
+
+    let blobs = storage.blobs(Some("./dir"), None).await.unwrap();
+    assert_eq!(file_names(&blobs), ["a.txt", "b.txt", "c.md"]);
+}
+
+/// Checks that listings only include files with the requested extensions.
+pub async fn list_files_with_extensions<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./dir/a.txt", b"a").await;
+    upload(storage, "./dir/b.md", b"b").await;
+    upload(storage, "./dir/c.txt", b"c").await;
+
+    let options = ListBlobsRequest::default().with_extensions(["txt"].into_iter());
+    let blobs = storage.blobs(Some("./dir"), Some(options)).await.unwrap();
+    assert_eq!(file_names(&blobs), ["a.txt", "c.txt"]);
+}
+
+/// Checks that following the continuation tokens of [`StorageService::blobs_page`]
+/// returns every file exactly once.
+pub async fn paginate_files<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./dir/a.txt", b"a").await;
+    upload(storage, "./dir/b.txt", b"b").await;
+    upload(storage, "./dir/c.txt", b"c").await;
+
+    let mut blobs = Vec::new();
+    let mut continuation = None;
+    for _ in 0..10 {
+        let options = ListBlobsRequest::default()
+            .with_page_size(NonZeroU32::new(1))
+            .with_continuation(continuation.take());
+
+        let page = storage.blobs_page(Some("./dir"), Some(options)).await.unwrap();
+        blobs.extend(page.items);
+
+        continuation = page.continuation;
+        if continuation.is_none() {
+            break;
+        }
+    }
+
+    assert!(continuation.is_none(), "listing didn't finish");
+    assert_eq!(file_names(&blobs), ["a.txt", "b.txt", "c.txt"]);
+}
+
+/// Checks that copied files are kept at both keys and that renamed files only exist
+/// at their new key.
+pub async fn copy_and_rename<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./a.txt", b"weow").await;
+
+    assert!(storage.copy("./a.txt", "./b.txt").await.unwrap());
+    assert_eq!(open(storage, "./a.txt").await.as_deref(), Some(&b"weow"[..]));
+    assert_eq!(open(storage, "./b.txt").await.as_deref(), Some(&b"weow"[..]));
+
+    assert!(storage.rename("./b.txt", "./nested/c.txt").await.unwrap());
+    assert!(!storage.exists("./b.txt").await.unwrap());
+    assert_eq!(open(storage, "./nested/c.txt").await.as_deref(), Some(&b"weow"[..]));
+
+    assert!(!storage.copy("./missing.txt", "./d.txt").await.unwrap());
+    assert!(!storage.rename("./missing.txt", "./d.txt").await.unwrap());
+    assert!(!storage.exists("./d.txt").await.unwrap());
+}
+
+/// Checks that [`StorageService::delete_prefix`] deletes every file under a prefix,
+/// including nested ones, and nothing else.
+pub async fn delete_prefix<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    upload(storage, "./dir/a.txt", b"a").await;
+    upload(storage, "./dir/nested/b.txt", b"b").await;
+    upload(storage, "./other.txt", b"other").await;
+
+    let summary = storage.delete_prefix("./dir").await.unwrap();
+    assert!(summary.failed.is_empty(), "failed to delete {:?}", summary.failed);
+
+    assert!(!storage.exists("./dir/a.txt").await.unwrap());
+    assert!(!storage.exists("./dir/nested/b.txt").await.unwrap());
+    assert!(storage.exists("./other.txt").await.unwrap());
+}
+
+/// Checks that keys which don't refer to a file are rejected.
+pub async fn reject_invalid_keys<S>(storage: &S)
+where
+    S: StorageService,
+    S::Error: Debug,
+{
+    for key in ["./", "./dir/", "./dir/.."] {
+        assert!(storage.open(key).await.is_err(), "opening `{key}` should fail");
+        assert!(
+            storage.upload(key, UploadRequest::default()).await.is_err(),
+            "uploading to `{key}` should fail"
+        );
+    }
+}
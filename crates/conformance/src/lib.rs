@@ -0,0 +1,95 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![doc(html_logo_url = "https://cdn.floofy.dev/images/trans.png")]
+#![cfg_attr(any(noeldoc, docsrs), feature(doc_cfg))]
+#![doc = include_str!("../README.md")]
+
+mod cases;
+
+pub use cases::*;
+
+/// Generates a `conformance` module with a `#[tokio::test]` for every case of this
+/// crate. The `setup` block is ran at the start of every test and must bind the
+/// storage service to the given name; anything else that it binds, like a temporary
+/// directory, lives until the test finishes.
+///
+/// The crate that uses this macro needs [`tokio`] as a dev-dependency with the `rt`
+/// and `macros` features.
+///
+/// ## Example
+/// ```rust,ignore
+/// remi_conformance::testsuite! {
+///     setup(storage) {
+///         let tempdir = tempfile::tempdir().unwrap();
+///         let storage = remi_fs::StorageService::new(&tempdir);
+///         storage.init().await.unwrap();
+///     }
+/// }
+/// ```
+///
+/// [`tokio`]: https://crates.io/crates/tokio
+#[macro_export]
+macro_rules! testsuite {
+    (setup($storage:ident) $setup:tt) => {
+        $crate::testsuite!(@cases $storage $setup
+            init_is_idempotent,
+            open_missing_file,
+            upload_and_open,
+            blob_of_file,
+            open_range,
+            delete_file,
+            create_exclusive,
+            upload_overwrite,
+            list_files,
+            list_files_with_extensions,
+            paginate_files,
+            copy_and_rename,
+            delete_prefix,
+            reject_invalid_keys
+        );
+    };
+
+    (@cases $storage:ident $setup:tt $($case:ident),+) => {
+        mod conformance {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $(
+                #[::tokio::test]
+                async fn $case() {
+                    $crate::__setup! $setup
+                    $crate::$case(&$storage).await;
+                }
+            )+
+        }
+    };
+}
+
+// Expands to the statements of the `setup` block without wrapping them in a block, so
+// that the bindings are still around when the case runs.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __setup {
+    ($($setup:tt)*) => {
+        $($setup)*
+    };
+}
@@ -21,17 +21,23 @@
 
 use rustc_version::Version;
 
+/// Newer standard library APIs that remi-fs uses when the compiler supports them, as
+/// `(minor version it was stablised in, cfg to emit)`. Everything else must compile
+/// on the `rust-version` declared in the workspace's `Cargo.toml`.
+const FEATURES: &[(u64, &str)] = &[
+    // `io::ErrorKind::NotADirectory` and friends were stablised in v1.83
+    // https://blog.rust-lang.org/2024/11/28/Rust-1.83.0.html
+    (83, "io_error_more"),
+];
+
 fn main() {
     println!("cargo::rerun-if-changed=build.rs");
 
     let Version { minor, .. } = rustc_version::version().unwrap();
-    if minor >= 77 {
-        println!("cargo::rustc-check-cfg=cfg(no_io_errorkind)");
-    }
-
-    // `io::ErrorKind` variants were stablised in v1.83
-    // https://blog.rust-lang.org/2024/11/28/Rust-1.83.0.html
-    if minor < 83 {
-        println!("cargo::rustc-cfg=no_io_errorkind");
+    for (since, cfg) in FEATURES {
+        println!("cargo::rustc-check-cfg=cfg({cfg})");
+        if minor >= *since {
+            println!("cargo::rustc-cfg={cfg}");
+        }
     }
 }
@@ -38,9 +38,7 @@ async fn main() -> Result<(), io::Error> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let config = StorageConfig {
-        directory: PathBuf::from("./data"),
-    };
+    let config = StorageConfig::new(PathBuf::from("./data"));
 
     let fs = StorageService::with_config(config);
 
@@ -0,0 +1,454 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::temp::TempFile;
+use md5::{Digest, Md5};
+use remi::{
+    async_trait,
+    rt::{self, fs},
+    Bytes, CacheStore,
+};
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+/// Configuration of a [`ChecksumCache`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CacheConfig {
+    /// Directory where cached objects are stored in.
+    pub directory: PathBuf,
+
+    /// Maximum amount of bytes that the cache can hold. When this is exceeded, the
+    /// least recently used objects are evicted.
+    pub max_bytes: u64,
+}
+
+impl CacheConfig {
+    /// Creates a new [`CacheConfig`].
+    pub fn new<P: AsRef<Path>>(directory: P, max_bytes: u64) -> CacheConfig {
+        CacheConfig {
+            directory: directory.as_ref().into(),
+            max_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    size: u64,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    size: u64,
+    last_used: u64,
+}
+
+/// Local directory that stores objects under the MD5 checksum of their contents, with
+/// a least recently used eviction policy. Objects with the same contents are only
+/// stored once.
+///
+/// The cache keeps an index of which path points to which checksum in `{directory}/paths`,
+/// so that it survives restarts (like in CI runs).
+#[derive(Debug)]
+pub struct ChecksumCache {
+    config: CacheConfig,
+    state: Mutex<State>,
+}
+
+impl ChecksumCache {
+    /// Opens the cache directory, creating it if it doesn't exist. Objects that are
+    /// already cached are indexed by their last modification time.
+    pub async fn open(config: CacheConfig) -> io::Result<ChecksumCache> {
+        fs::create_dir_all(config.directory.join("objects")).await?;
+        fs::create_dir_all(config.directory.join("paths")).await?;
+
+        let mut objects = Vec::new();
+        for entry in fs::read_dir(config.directory.join("objects")).await? {
+            let metadata = fs::symlink_metadata(entry.path()).await?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let Some(checksum) = entry.file_name().to_str().map(String::from) else {
+                continue;
+            };
+
+            // temporary files are only left behind if the process crashed while writing them
+            if checksum.starts_with('.') {
+                let _ = fs::remove_file(entry.path()).await;
+                continue;
+            }
+
+            objects.push((checksum, metadata.len(), metadata.modified().ok()));
+        }
+
+        objects.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut state = State::default();
+        for (checksum, size, _) in objects {
+            state.tick += 1;
+            state.size += size;
+            state.entries.insert(
+                checksum,
+                Entry {
+                    size,
+                    last_used: state.tick,
+                },
+            );
+        }
+
+        let cache = ChecksumCache {
+            config,
+            state: Mutex::new(state),
+        };
+
+        cache.evict().await?;
+        Ok(cache)
+    }
+
+    /// Returns the MD5 checksum (in hexadecimal) that objects are stored under.
+    pub fn checksum(data: &[u8]) -> String {
+        Md5::digest(data).iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// Returns how many bytes are stored in the cache.
+    pub fn size(&self) -> u64 {
+        self.state().size
+    }
+
+    /// Returns how many objects are stored in the cache.
+    pub fn len(&self) -> usize {
+        self.state().entries.len()
+    }
+
+    /// Whether if the cache has no objects.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the contents of the object with the given checksum, if it is cached.
+    pub async fn get(&self, checksum: &str) -> io::Result<Option<Bytes>> {
+        if !self.state().entries.contains_key(checksum) {
+            return Ok(None);
+        }
+
+        let path = self.object_path(checksum);
+        let data = match fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                self.forget(checksum);
+                return Ok(None);
+            }
+
+            Err(e) => return Err(e),
+        };
+
+        {
+            let mut state = self.state();
+            state.tick += 1;
+
+            let tick = state.tick;
+            if let Some(entry) = state.entries.get_mut(checksum) {
+                entry.last_used = tick;
+            }
+        }
+
+        // keep the order of use between restarts
+        rt::spawn_blocking("remi_fs::cache::touch", move || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(&path)?
+                .set_modified(SystemTime::now())
+        })
+        .await?;
+
+        Ok(Some(Bytes::from(data)))
+    }
+
+    /// Stores the given data in the cache and returns its checksum. Objects that are
+    /// larger than [`CacheConfig::max_bytes`] are not stored.
+    pub async fn put(&self, data: &[u8]) -> io::Result<String> {
+        let checksum = ChecksumCache::checksum(data);
+        let size = data.len() as u64;
+        if size > self.config.max_bytes || self.state().entries.contains_key(&checksum) {
+            return Ok(checksum);
+        }
+
+        // write into a temporary file first so that a partially written object
+        // is never read back
+        let path = self.object_path(&checksum);
+        let (tmp, mut file) = TempFile::create(&path)?;
+        let contents = data.to_vec();
+        rt::spawn_blocking("remi_fs::cache::put", move || file.write_all(&contents)).await?;
+
+        tmp.persist(&path)?;
+
+        {
+            let mut state = self.state();
+            state.tick += 1;
+            state.size += size;
+
+            let last_used = state.tick;
+            state.entries.insert(checksum.clone(), Entry { size, last_used });
+        }
+
+        self.evict().await?;
+        Ok(checksum)
+    }
+
+    /// Removes the object with the given checksum from the cache.
+    pub async fn remove(&self, checksum: &str) -> io::Result<()> {
+        if !self.forget(checksum) {
+            return Ok(());
+        }
+
+        match fs::remove_file(self.object_path(checksum)).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the checksum that `path` was last stored under, if any.
+    pub async fn lookup<P: AsRef<Path>>(&self, path: P) -> io::Result<Option<String>> {
+        match fs::read_to_string(self.index_path(path.as_ref())).await {
+            Ok(checksum) => Ok(Some(checksum)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Records that `path` points to an object with the given checksum.
+    pub async fn link<P: AsRef<Path>>(&self, path: P, checksum: &str) -> io::Result<()> {
+        fs::write(self.index_path(path.as_ref()), checksum.to_owned()).await
+    }
+
+    /// Removes `path` from the index, so that it is fetched again next time.
+    pub async fn unlink<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        match fs::remove_file(self.index_path(path.as_ref())).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("cache state to not be poisoned")
+    }
+
+    fn forget(&self, checksum: &str) -> bool {
+        let mut state = self.state();
+        match state.entries.remove(checksum) {
+            Some(entry) => {
+                state.size -= entry.size;
+                true
+            }
+
+            None => false,
+        }
+    }
+
+    fn object_path(&self, checksum: &str) -> PathBuf {
+        self.config.directory.join("objects").join(checksum)
+    }
+
+    fn index_path(&self, path: &Path) -> PathBuf {
+        self.config
+            .directory
+            .join("paths")
+            .join(ChecksumCache::checksum(path.to_string_lossy().as_bytes()))
+    }
+
+    async fn evict(&self) -> io::Result<()> {
+        let victims = {
+            let mut state = self.state();
+            let mut victims = Vec::new();
+            while state.size > self.config.max_bytes {
+                let Some(checksum) = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(checksum, _)| checksum.clone())
+                else {
+                    break;
+                };
+
+                let entry = state.entries.remove(&checksum).unwrap();
+                state.size -= entry.size;
+                victims.push(checksum);
+            }
+
+            victims
+        };
+
+        for checksum in victims {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(%checksum, "evicting object from cache");
+
+            #[cfg(feature = "log")]
+            log::trace!("evicting object [{checksum}] from cache");
+
+            match fs::remove_file(self.object_path(&checksum)).await {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CacheStore for ChecksumCache {
+    async fn get(&self, path: &Path) -> io::Result<Option<Bytes>> {
+        match self.lookup(path).await? {
+            Some(checksum) => ChecksumCache::get(self, &checksum).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn insert(&self, path: &Path, data: Bytes) -> io::Result<()> {
+        let checksum = self.put(&data).await?;
+        self.link(path, &checksum).await
+    }
+
+    async fn invalidate(&self, path: &Path) -> io::Result<()> {
+        self.unlink(path).await
+    }
+}
+
+/// Storage service that caches objects from another storage service in a [`ChecksumCache`],
+/// which speeds up repeated reads of the same objects. See [`remi::CachedStorageService`].
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi_fs::{CacheConfig, CachedStorageService, ChecksumCache};
+/// #
+/// let cache = ChecksumCache::open(CacheConfig::new("./.cache/remi", 512 * 1024 * 1024)).await?;
+/// let storage = CachedStorageService::new(s3, cache);
+/// ```
+pub type CachedStorageService<S> = remi::CachedStorageService<S, ChecksumCache>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StorageConfig, StorageService};
+    use remi::{StorageService as _, UploadRequest};
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let cache = ChecksumCache::open(CacheConfig::new(&tempdir, 10)).await?;
+
+        let first = cache.put(b"aaaa").await?;
+        let second = cache.put(b"bbbb").await?;
+        assert_eq!(cache.size(), 8);
+
+        // `first` is now more recently used than `second`
+        assert!(cache.get(&first).await?.is_some());
+
+        cache.put(b"cccc").await?;
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&second).await?.is_none());
+        assert!(cache.get(&first).await?.is_some());
+
+        // objects larger than the cache are never stored
+        cache.put(b"this is way too large").await?;
+        assert_eq!(cache.len(), 2);
+
+        // the index survives reopening the cache
+        drop(cache);
+        let cache = ChecksumCache::open(CacheConfig::new(&tempdir, 10)).await?;
+        assert_eq!(cache.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serves_repeated_reads_from_cache() -> io::Result<()> {
+        let remote = tempfile::tempdir()?;
+        let local = tempfile::tempdir()?;
+
+        let storage = CachedStorageService::new(
+            StorageService::with_config(StorageConfig::new(&remote)),
+            ChecksumCache::open(CacheConfig::new(&local, 1024)).await?,
+        );
+
+        storage.init().await?;
+        storage
+            .remote()
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await?;
+
+        assert_eq!(storage.open("./weow.txt").await?, Some(Bytes::from_static(b"weow")));
+
+        // remove the file behind the cache's back
+        fs::remove_file(remote.path().join("weow.txt")).await?;
+        assert_eq!(storage.open("./weow.txt").await?, Some(Bytes::from_static(b"weow")));
+
+        storage.invalidate("./weow.txt").await?;
+        assert_eq!(storage.open("./weow.txt").await?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reports_cache_hits_in_stats() -> io::Result<()> {
+        let remote = tempfile::tempdir()?;
+        let local = tempfile::tempdir()?;
+
+        let storage = CachedStorageService::new(
+            remi::MeteredStorageService::new(StorageService::with_config(StorageConfig::new(&remote))),
+            ChecksumCache::open(CacheConfig::new(&local, 1024)).await?,
+        );
+
+        storage.init().await?;
+        storage
+            .remote()
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await?;
+
+        storage.open("./weow.txt").await?;
+        storage.open("./weow.txt").await?;
+        assert!(storage
+            .remote()
+            .upload("./weow/", UploadRequest::default())
+            .await
+            .is_err());
+
+        let stats = storage.stats();
+        assert_eq!(stats.operations, 4);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.bytes_read, 4);
+        assert_eq!(stats.bytes_written, 4);
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.cache_misses, 1);
+        assert_eq!(stats.cache_hit_ratio(), Some(0.5));
+
+        Ok(())
+    }
+}
@@ -27,6 +27,56 @@ use std::path::{Path, PathBuf};
 pub struct StorageConfig {
     /// [`PathBuf`] to the directory where `remi-fs` can locate files from with the `./` prefix.
     pub directory: PathBuf,
+
+    /// Whether if uploads should be verified by reading the file back and comparing
+    /// its MD5 digest with the data that was meant to be written.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub verify_writes: bool,
+
+    /// Maximum size (in bytes) of a file that can be uploaded. Uploads that are larger fail
+    /// with an [`InvalidInput`][std::io::ErrorKind::InvalidInput] error that wraps a
+    /// [`TooLarge`][remi::TooLarge] error before anything is written.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub max_object_size: Option<usize>,
+
+    /// Whether if this storage service is read-only. All operations that would modify the
+    /// directory fail with a [`ReadOnly`][remi::ReadOnly] error before anything is sent.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub read_only: bool,
+
+    /// Whether if a file that doesn't exist should be looked up again by listing its parent directory and
+    /// matching the file name case-insensitively, which emits a warning if it was found under a
+    /// different case. This helps with data that was migrated from case-insensitive filesystems,
+    /// where `Readme.MD` and `README.md` were the same file.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub case_insensitive_lookup: bool,
+
+    /// Maximum amount of bytes (in bytes) that can be buffered in memory across all concurrent
+    /// operations, which is useful for embedded deployments where opening many large files at
+    /// once would otherwise run out of memory. Operations that would go over the limit wait
+    /// until enough bytes were released, unless [`memory_limit_fail_fast`][StorageConfig::memory_limit_fail_fast]
+    /// is set. See [`MemoryLimit`][remi::MemoryLimit] for more information.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub strict_memory_limit: Option<usize>,
+
+    /// Whether if operations that would go over the [`strict_memory_limit`][StorageConfig::strict_memory_limit]
+    /// should fail right away with an [`OutOfMemory`][std::io::ErrorKind::OutOfMemory] error that wraps
+    /// a [`MemoryLimitExceeded`][remi::MemoryLimitExceeded] error instead of waiting.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub memory_limit_fail_fast: bool,
+
+    /// Whether if [`init`][remi::StorageService::init] should only verify that the directory exists instead
+    /// of creating it, which fails with a [`NotFound`][std::io::ErrorKind::NotFound] error that wraps
+    /// a [`ResourceMissing`][remi::ResourceMissing] error if it doesn't. This can also be enabled
+    /// with the [`REMI_SKIP_PROVISIONING`][remi::SKIP_PROVISIONING_ENV] environment variable.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub skip_provisioning: bool,
+
+    /// Whether if listing a directory that has a special file in it (like a Unix socket, FIFO,
+    /// or device) should fail with an [`InvalidInput`][std::io::ErrorKind::InvalidInput] error
+    /// instead of skipping it. Special files can't be opened as files either way.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub error_on_special_files: bool,
 }
 
 impl StorageConfig {
@@ -34,6 +84,14 @@ impl StorageConfig {
     pub fn new<P: AsRef<Path>>(path: P) -> StorageConfig {
         StorageConfig {
             directory: path.as_ref().into(),
+            verify_writes: false,
+            max_object_size: None,
+            read_only: false,
+            case_insensitive_lookup: false,
+            strict_memory_limit: None,
+            memory_limit_fail_fast: false,
+            skip_provisioning: false,
+            error_on_special_files: false,
         }
     }
 }
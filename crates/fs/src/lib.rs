@@ -23,10 +23,28 @@
 #![cfg_attr(any(noeldoc, docsrs), feature(doc_cfg))]
 #![doc = include_str!("../README.md")]
 
+#[cfg(not(any(feature = "tokio", feature = "async-std")))]
+compile_error!(
+    "remi-fs requires either the `tokio` or `async-std` feature to be enabled; if you depend on remi-fs with \
+     `default-features = false`, add `features = [\"tokio\"]` or `features = [\"async-std\"]` to it"
+);
+
+mod cache;
 mod config;
 mod content_type;
 mod service;
+mod temp;
 
+pub use cache::*;
 pub use config::*;
 pub use content_type::*;
 pub use service::*;
+
+// Compile-time checks so that a change which makes these types no longer thread-safe
+// fails to build here instead of breaking downstream crates.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    assert_send_sync::<StorageService>();
+    assert_send_sync::<StorageConfig>();
+};
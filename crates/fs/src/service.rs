@@ -19,25 +19,140 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::{default_resolver, ContentTypeResolver, StorageConfig};
-use remi::{async_trait, Blob, Bytes, Directory, File, ListBlobsRequest, StorageService as _, UploadRequest};
+use crate::{default_resolver, temp::TempFile, ChecksumCache, ContentTypeResolver, StorageConfig};
+use filetime::FileTime;
+use futures_util::{stream, StreamExt};
+use md5::{Digest, Md5};
+use remi::{
+    async_trait,
+    rt::{self, fs},
+    Blob, Bytes, Capabilities, CollisionStrategy, ContentStream, DeleteSummary, Directory, File, HealthReport,
+    ListBlobsRequest, ObjectEvent, Page, ServiceInfo, StorageEvent, StorageService as _, UploadRequest,
+    ValidationReport,
+};
 use std::{
     borrow::Cow,
-    io,
+    io::{self, Error, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
     sync::Arc,
-    time::SystemTime,
+    time::{Instant, SystemTime},
 };
-use tokio::{fs, io::*};
+use tokio::sync::{broadcast, OnceCell};
 
 #[cfg(feature = "tracing")]
 use tracing::instrument;
 
+/// [`io::ErrorKind`] used when a path was expected to be a directory. This falls back to
+/// [`io::ErrorKind::InvalidData`] on compilers older than Rust 1.83.
+#[cfg(io_error_more)]
+#[allow(clippy::incompatible_msrv)] // only enabled by `build.rs` on Rust 1.83 or newer
+const NOT_A_DIRECTORY: io::ErrorKind = io::ErrorKind::NotADirectory;
+
+/// [`io::ErrorKind`] used when a path was expected to be a directory. This falls back to
+/// [`io::ErrorKind::InvalidData`] on compilers older than Rust 1.83.
+#[cfg(not(io_error_more))]
+const NOT_A_DIRECTORY: io::ErrorKind = io::ErrorKind::InvalidData;
+
+/// Amount of bytes that are read at a time by [`open_stream`][remi::StorageService::open_stream].
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Amount of blobs in a page that is returned from [`blobs_page`][remi::StorageService::blobs_page]
+/// if [`ListBlobsRequest::page_size`] isn't set.
+const LIST_PAGE_SIZE: u32 = 1000;
+
+/// Amount of bytes that are read from the start of a file to resolve its content type when
+/// listing with [`ListBlobsRequest::metadata_only`].
+const CONTENT_TYPE_SNIFF_LEN: u64 = 8 * 1024;
+
+/// Checks that `path` can be used as the key of a file, see [`InvalidKey`][remi::InvalidKey].
+fn check_key(path: &Path) -> io::Result<()> {
+    remi::InvalidKey::check(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Returns what kind of special file (like a socket or FIFO) `metadata` belongs to, or `None`
+/// if it is a regular file or directory. Reading a special file can block forever or never
+/// end, so they can't be used as files.
+fn special_file_kind(metadata: &std::fs::Metadata) -> Option<&'static str> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        let file_type = metadata.file_type();
+        if file_type.is_socket() {
+            return Some("socket");
+        }
+
+        if file_type.is_fifo() {
+            return Some("FIFO");
+        }
+
+        if file_type.is_block_device() {
+            return Some("block device");
+        }
+
+        if file_type.is_char_device() {
+            return Some("character device");
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = metadata;
+
+    None
+}
+
+fn special_file_error(path: &Path, kind: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("path [{}] is a {kind}, not a regular file", path.display()),
+    )
+}
+
+/// Fails if `path` is a special file, see [`special_file_kind`].
+async fn check_not_special(path: &Path) -> io::Result<()> {
+    match special_file_kind(&fs::metadata(path).await?) {
+        Some(kind) => Err(special_file_error(path, kind)),
+        None => Ok(()),
+    }
+}
+
+/// Sets the modification time of `file`, and its creation time on platforms that
+/// allow changing it, see [`FileTimestamps`][remi::FileTimestamps].
+fn set_timestamps(file: &std::fs::File, timestamps: remi::FileTimestamps) -> io::Result<()> {
+    if let Some(modified) = timestamps.modified {
+        let modified = FileTime::from_unix_time(
+            i64::try_from(modified / 1000).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+            (modified % 1000) as u32 * 1_000_000,
+        );
+
+        filetime::set_file_handle_times(file, None, Some(modified))?;
+    }
+
+    #[cfg(any(windows, target_os = "macos"))]
+    if let Some(created) = timestamps.created {
+        #[cfg(target_os = "macos")]
+        use std::os::macos::fs::FileTimesExt;
+        #[cfg(windows)]
+        use std::os::windows::fs::FileTimesExt;
+
+        let created = u64::try_from(created).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        file.set_times(
+            std::fs::FileTimes::new().set_created(SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(created)),
+        )?;
+    }
+
+    Ok(())
+}
+
 /// Represents an implementation of a [`StorageService`](remi::StorageService) for the
 /// local filesystem.
 #[derive(Clone)]
 pub struct StorageService {
     resolver: Arc<dyn ContentTypeResolver>,
+    events: Option<broadcast::Sender<StorageEvent>>,
+    interceptors: remi::UploadInterceptors,
+    initialized: Arc<OnceCell<()>>,
+    memory: Option<remi::MemoryLimit>,
     config: StorageConfig,
 }
 
@@ -51,6 +166,15 @@ impl StorageService {
     pub fn with_config(config: StorageConfig) -> StorageService {
         StorageService {
             resolver: Arc::new(default_resolver),
+            events: None,
+            interceptors: remi::UploadInterceptors::default(),
+            initialized: Arc::default(),
+            memory: config
+                .strict_memory_limit
+                .map(|limit| match config.memory_limit_fail_fast {
+                    true => remi::MemoryLimit::fail_fast(limit),
+                    false => remi::MemoryLimit::new(limit),
+                }),
             config,
         }
     }
@@ -61,6 +185,68 @@ impl StorageService {
         self
     }
 
+    /// Registers an [`UploadInterceptor`][remi::UploadInterceptor] that can inspect and reject uploads
+    /// before they are written, which fail with an [`io::Error`] that holds a
+    /// [`RejectedByPolicy`][remi::RejectedByPolicy] error. Interceptors run in the order that they were registered.
+    pub fn with_interceptor<I: remi::UploadInterceptor + 'static>(mut self, interceptor: I) -> StorageService {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Publishes a [`StorageEvent`] to the given channel after every successful upload or deletion.
+    pub fn with_events(mut self, events: broadcast::Sender<StorageEvent>) -> StorageService {
+        self.events = Some(events);
+        self
+    }
+
+    /// Rejects `operation` with a [`ReadOnly`][remi::ReadOnly] error if this storage service is read-only.
+    fn check_writable(&self, operation: &'static str) -> io::Result<()> {
+        remi::ReadOnly::check(self.config.read_only, operation)
+            .map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))
+    }
+
+    /// Creates the directory of this storage service if it doesn't exist, see
+    /// [`init`][remi::StorageService::init].
+    async fn initialize(&self) -> io::Result<()> {
+        if !self.config.directory.try_exists()? {
+            if remi::skip_provisioning(self.config.skip_provisioning) {
+                return Err(Error::new(
+                    io::ErrorKind::NotFound,
+                    remi::ResourceMissing::new("directory", self.config.directory.display().to_string()),
+                ));
+            }
+
+            self.check_writable("create the directory")?;
+
+            #[cfg(feature = "tracing")]
+            tracing::info!("creating directory since it doesn't exist");
+
+            #[cfg(feature = "log")]
+            log::info!(
+                "creating directory [{}] since it doesn't exist",
+                self.config.directory.display(),
+            );
+
+            fs::create_dir_all(&self.config.directory).await?;
+        }
+
+        if !self.config.directory.is_dir() {
+            return Err(Error::new(
+                NOT_A_DIRECTORY,
+                format!("path [{}] is a file, not a directory", self.config.directory.display()),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn publish(&self, event: StorageEvent) {
+        if let Some(ref events) = self.events {
+            // an error means that there are no receivers, which is fine
+            let _ = events.send(event);
+        }
+    }
+
     /// Attempts to normalize a given path and returns a canonical, absolute
     /// path. It must follow some strict rules:
     ///
@@ -68,6 +254,9 @@ impl StorageService {
     ///   the directory was found. Otherwise, it'll use the current directory.
     ///
     /// * If the path starts with `~/`, then it will resolve from the home directory from [`etcetera::home_dir`].
+    ///
+    /// A path that can't be normalized (i.e, the home directory couldn't be found) is an
+    /// error, which every method of this storage service returns as-is.
     #[cfg_attr(
         feature = "tracing",
         instrument(
@@ -76,7 +265,7 @@ impl StorageService {
             fields(remi.service = "fs", path = %path.as_ref().display())
         )
     )]
-    pub fn normalize<P: AsRef<Path>>(&self, path: P) -> io::Result<Option<PathBuf>> {
+    pub fn normalize<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
         let path = path.as_ref();
 
         #[cfg(feature = "tracing")]
@@ -86,22 +275,11 @@ impl StorageService {
         log::trace!("resolving path: {}", path.display());
 
         if path == self.config.directory {
-            return std::fs::canonicalize(&self.config.directory).map(|x| Ok(Some(x)))?;
+            return std::fs::canonicalize(&self.config.directory);
         }
 
         if path.starts_with("./") {
-            let Some(directory) = self.normalize(&self.config.directory)? else {
-                #[cfg(feature = "tracing")]
-                tracing::warn!(
-                    directory = %self.config.directory.display(),
-                    "unable to resolve directory from config"
-                );
-
-                #[cfg(feature = "log")]
-                log::warn!("unable to resolve given directory from config");
-
-                return Ok(None);
-            };
+            let directory = self.normalize(&self.config.directory)?;
 
             let normalized = format!("{}/{}", directory.display(), path.strip_prefix("./").unwrap().display());
 
@@ -111,7 +289,7 @@ impl StorageService {
             #[cfg(feature = "log")]
             log::trace!("resolved path {} ~> {normalized}", path.display());
 
-            return Ok(Some(Path::new(&normalized).to_path_buf()));
+            return Ok(Path::new(&normalized).to_path_buf());
         }
 
         if path.starts_with("~/") {
@@ -135,10 +313,175 @@ impl StorageService {
             #[cfg(feature = "log")]
             log::trace!("resolved path {} ~> {normalized}", path.display());
 
-            return Ok(Some(Path::new(&normalized).to_path_buf()));
+            return Ok(Path::new(&normalized).to_path_buf());
+        }
+
+        Ok(path.to_path_buf())
+    }
+
+    /// Normalizes `path` and, if nothing exists in it and [`StorageConfig::case_insensitive_lookup`]
+    /// is enabled, looks for a file in its parent directory whose name only differs in case.
+    async fn resolve(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = self.normalize(path)?;
+        if !self.config.case_insensitive_lookup || path.try_exists()? {
+            return Ok(path);
+        }
+
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name().and_then(|name| name.to_str())) else {
+            return Ok(path);
+        };
+
+        let entries = match fs::read_dir(parent).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(path),
+            Err(e) => return Err(e),
+        };
+
+        let names = entries
+            .iter()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect::<Vec<_>>();
+
+        let Some(found) = remi::find_case_insensitive(name, names.iter().map(String::as_str)) else {
+            return Ok(path);
+        };
+
+        let found = parent.join(found);
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!(found = %found.display(), "file was found under a different case");
+
+        #[cfg(feature = "log")]
+        log::warn!(
+            "file [{}] was found under a different case: [{}]",
+            path.display(),
+            found.display()
+        );
+
+        Ok(found)
+    }
+
+    /// Reserves `bytes` bytes from the [`strict_memory_limit`][StorageConfig::strict_memory_limit],
+    /// which are released once the returned permit is dropped.
+    async fn reserve(&self, bytes: usize) -> io::Result<Option<remi::MemoryPermit>> {
+        let Some(ref limit) = self.memory else {
+            return Ok(None);
+        };
+
+        limit
+            .acquire(bytes)
+            .await
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::OutOfMemory, e))
+    }
+
+    /// Writes `options` into `path`, which fails with [`AlreadyExists`][remi::AlreadyExists]
+    /// instead of replacing an existing file if `exclusive` is `true`.
+    async fn write(&self, path: &Path, options: UploadRequest, exclusive: bool) -> io::Result<()> {
+        self.check_writable("upload")?;
+
+        check_key(path)?;
+
+        remi::TooLarge::check(options.data.len(), self.config.max_object_size)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        // verifying a write reads the whole file back while the data is still around
+        let _permit = self
+            .reserve(match self.config.verify_writes {
+                true => options.data.len().saturating_mul(2),
+                false => options.data.len(),
+            })
+            .await?;
+
+        let path = self.normalize(path)?;
+
+        self.interceptors
+            .check(&format!("fs://{}", path.display()), &options)
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))?;
+
+        let existed = path.try_exists()?;
+        if existed && exclusive {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                remi::AlreadyExists::new(format!("fs://{}", path.display())),
+            ));
+        }
+
+        if existed {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("contents in given path will be overwritten");
+
+            #[cfg(feature = "log")]
+            log::trace!("contents in given path [{}] will be overwritten", path.display());
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!("uploading file");
+
+        #[cfg(feature = "log")]
+        log::trace!("uploading file [{}]", path.display());
+
+        // ensure that the parent exists, if not, it'll attempt
+        // to create all paths in the given parent
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // write into a temporary file that is renamed over the destination once it was
+        // fully written, so a cancelled or failed upload never leaves a partially written
+        // file behind. The temporary file is removed if this future is dropped early.
+        let (temp, mut file) = TempFile::create(&path)?;
+        let data = options.data.clone();
+        let timestamps = options.timestamps();
+        rt::spawn_blocking("remi_fs::write", move || {
+            file.write_all(data.as_ref())?;
+            if let Some(timestamps) = timestamps {
+                set_timestamps(&file, timestamps)?;
+            }
+
+            file.sync_all()
+        })
+        .await?;
+
+        if self.config.verify_writes {
+            #[cfg(feature = "tracing")]
+            tracing::trace!("verifying written contents");
+
+            #[cfg(feature = "log")]
+            log::trace!("verifying written contents of file [{}]", path.display());
+
+            let written = fs::read(temp.path()).await?;
+            if Md5::digest(&written) != Md5::digest(options.data.as_ref()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("contents written to [{}] didn't match its MD5 digest", path.display()),
+                ));
+            }
+        }
+
+        // another upload could've created the file in the meantime, which `persist_new`
+        // fails on instead of replacing it
+        if exclusive {
+            temp.persist_new(&path).map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    remi::AlreadyExists::new(format!("fs://{}", path.display())),
+                ),
+
+                _ => e,
+            })?;
+        } else {
+            temp.persist(&path)?;
         }
 
-        Ok(Some(path.to_path_buf()))
+        self.publish(StorageEvent::uploaded(
+            format!("fs://{}", path.display()),
+            &options,
+            existed,
+        ));
+
+        Ok(())
     }
 
     async fn create_file(&self, path: &Path) -> io::Result<File> {
@@ -156,19 +499,21 @@ impl StorageService {
             Err(_) => None,
         };
 
-        let created_at = match metadata {
-            Ok(ref m) => Some(
-                m.created()?
+        // not every platform and filesystem keeps the creation time of files
+        let created_at = match metadata.as_ref().map(|m| m.created()) {
+            Ok(Ok(created)) => Some(
+                created
                     .duration_since(SystemTime::UNIX_EPOCH)
                     .map_err(|_| io::Error::new(io::ErrorKind::Other, "clock went backwards?!"))?
                     .as_millis(),
             ),
 
-            Err(_) => None,
+            _ => None,
         };
 
         let bytes = self.open(path).await?.map_or(Bytes::new(), |x| x);
         let content_type = self.resolver.resolve(bytes.as_ref());
+        let checksum = ChecksumCache::checksum(bytes.as_ref());
 
         Ok(File {
             last_modified_at,
@@ -176,15 +521,28 @@ impl StorageService {
             metadata: Default::default(),
             created_at,
             is_symlink,
+            etag: Some(checksum.clone()),
+            checksum: Some(checksum),
+            version: None,
+            expires_at: None,
+            extras: Default::default(),
             data: bytes,
-            name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
             path: format!("fs://{}", path.display()),
             size: size as usize,
         })
     }
 
-    async fn create_file_from_entry(&self, path: &Path, entry: fs::DirEntry) -> io::Result<File> {
-        let metadata = entry.metadata().await;
+    async fn create_file_from_entry(
+        &self,
+        path: &Path,
+        entry: std::fs::DirEntry,
+        metadata_only: bool,
+    ) -> io::Result<File> {
+        let metadata = fs::symlink_metadata(entry.path()).await;
         let is_symlink = metadata.as_ref().map(|m| m.is_symlink()).unwrap_or(false);
         let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
         let last_modified_at = match metadata {
@@ -198,19 +556,34 @@ impl StorageService {
             Err(_) => None,
         };
 
-        let created_at = match metadata {
-            Ok(ref m) => Some(
-                m.created()?
+        // not every platform and filesystem keeps the creation time of files
+        let created_at = match metadata.as_ref().map(|m| m.created()) {
+            Ok(Ok(created)) => Some(
+                created
                     .duration_since(SystemTime::UNIX_EPOCH)
                     .map_err(|_| io::Error::new(io::ErrorKind::Other, "clock went backwards?!"))?
                     .as_millis(),
             ),
 
-            Err(_) => None,
+            _ => None,
         };
 
-        let bytes = self.open(path).await?.map_or(Bytes::new(), |x| x);
-        let content_type = self.resolver.resolve(bytes.as_ref());
+        let (content_type, bytes) = if metadata_only {
+            // only the start of the file is read, so documents that are detected by parsing
+            // them as a whole (like JSON) can resolve to a more generic content type
+            let head = self
+                .open_range(path, 0, Some(CONTENT_TYPE_SNIFF_LEN))
+                .await?
+                .unwrap_or_default();
+
+            (self.resolver.resolve(head.as_ref()), Bytes::new())
+        } else {
+            let bytes = self.open(path).await?.map_or(Bytes::new(), |x| x);
+            (self.resolver.resolve(bytes.as_ref()), bytes)
+        };
+
+        // the checksum can only be computed if the whole file was read
+        let checksum = (!metadata_only).then(|| ChecksumCache::checksum(bytes.as_ref()));
 
         Ok(File {
             last_modified_at,
@@ -218,12 +591,138 @@ impl StorageService {
             metadata: Default::default(),
             created_at,
             is_symlink,
+            etag: checksum.clone(),
+            checksum,
+            version: None,
+            expires_at: None,
+            extras: Default::default(),
             data: bytes,
             name: entry.file_name().to_string_lossy().into_owned(),
             path: format!("fs://{}", path.display()),
             size: size as usize,
         })
     }
+
+    /// Lists the entries in `path` in order of their names, returning at most `limit` blobs.
+    async fn list(
+        &self,
+        path: Option<&Path>,
+        options: &ListBlobsRequest,
+        limit: Option<usize>,
+    ) -> io::Result<Page<Blob>> {
+        let prefix = options.prefix.clone().unwrap_or_default();
+        let path = self.normalize(path.unwrap_or(&self.config.directory))?;
+
+        if path.is_file() {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("path given was a file, not a directory");
+
+            #[cfg(feature = "log")]
+            log::warn!("path given [{}] was a file, not a directory", path.display());
+
+            return Ok(Page::default());
+        }
+
+        let search = format!("{}{prefix}", path.display());
+        #[cfg(feature = "tracing")]
+        tracing::trace!(%search, "attempting to search all blobs in given path");
+
+        #[cfg(feature = "log")]
+        log::trace!(
+            "attempting to search in [{search}] for all blobs in given path [{}]",
+            path.display()
+        );
+
+        // `read_dir` doesn't return entries in any particular order, so they are sorted by
+        // name to list them the same way every time, which `start_after` relies on
+        let mut files = fs::read_dir(search).await?;
+        files.sort_by_key(|entry| entry.file_name());
+
+        let mut blobs = vec![];
+        for entry in files {
+            let key = entry.path().to_string_lossy().into_owned();
+            if [&options.start_after, &options.continuation]
+                .into_iter()
+                .flatten()
+                .any(|after| key.as_str() <= after.as_str())
+            {
+                continue;
+            }
+
+            // the key of the last blob in a full page continues the listing after it
+            if limit.is_some_and(|limit| blobs.len() == limit) {
+                let last = match blobs.last() {
+                    Some(Blob::File(file)) => &file.path,
+                    Some(Blob::Directory(dir)) => &dir.path,
+                    None => unreachable!("page to be full"),
+                };
+
+                let continuation = last.trim_start_matches("fs://").to_owned();
+                return Ok(Page::new(blobs, Some(continuation)));
+            }
+
+            if entry.path().is_dir() {
+                if !options.include_dirs {
+                    continue;
+                }
+
+                blobs.push(Blob::Directory(Directory {
+                    created_at: match fs::symlink_metadata(entry.path()).await {
+                        Ok(sys) => Some(
+                            sys.created()?
+                                .duration_since(SystemTime::UNIX_EPOCH)
+                                .map_err(|_| io::Error::new(io::ErrorKind::Other, "clock went backwards?!"))?
+                                .as_millis(),
+                        ),
+
+                        Err(_) => None,
+                    },
+
+                    name: path
+                        .file_name()
+                        .map(|s| s.to_string_lossy())
+                        .unwrap_or(Cow::Borrowed("<root or relative path>"))
+                        .to_string(),
+
+                    path: format!("fs://{}", entry.path().display()),
+                }));
+
+                continue;
+            }
+
+            let path = entry.path();
+
+            // symbolic links are followed, so links to special files are skipped too
+            if let Some(kind) = fs::metadata(&path).await.ok().as_ref().and_then(special_file_kind) {
+                if self.config.error_on_special_files {
+                    return Err(special_file_error(&path, kind));
+                }
+
+                #[cfg(feature = "tracing")]
+                tracing::warn!(path = %path.display(), kind, "skipping special file");
+
+                #[cfg(feature = "log")]
+                log::warn!("skipping {kind} [{}] since it isn't a regular file", path.display());
+
+                continue;
+            }
+
+            let ext_allowed = match path.extension() {
+                Some(s) => options.is_ext_allowed(s.to_str().expect("valid utf-8 in path extension")),
+                None => true,
+            };
+
+            if !ext_allowed {
+                continue;
+            }
+
+            blobs.push(Blob::File(
+                self.create_file_from_entry(&path, entry, options.metadata_only).await?,
+            ));
+        }
+
+        Ok(Page::last(blobs))
+    }
 }
 
 #[async_trait]
@@ -231,7 +730,7 @@ impl remi::StorageService for StorageService {
     type Error = io::Error;
 
     fn name(&self) -> Cow<'static, str> {
-        Cow::Borrowed("remi:gridfs")
+        Cow::Borrowed("remi:fs")
     }
 
     #[cfg_attr(
@@ -246,40 +745,63 @@ impl remi::StorageService for StorageService {
         )
     )]
     async fn init(&self) -> io::Result<()> {
-        if !self.config.directory.try_exists()? {
+        // concurrent callers wait for the first one instead of racing to create the directory,
+        // and a failed initialization is retried by the next caller
+        self.initialized.get_or_try_init(|| self.initialize()).await.copied()
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.filesystem.open",
+            skip_all,
+            fields(
+                remi.service = "fs",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<Bytes>> {
+        check_key(path.as_ref())?;
+        let path = path.as_ref();
+        let path = self.resolve(path).await?;
+
+        if !path.try_exists()? {
             #[cfg(feature = "tracing")]
-            tracing::info!("creating directory since it doesn't exist");
+            tracing::warn!("path doesn't exist");
 
             #[cfg(feature = "log")]
-            log::info!(
-                "creating directory [{}] since it doesn't exist",
-                self.config.directory.display(),
-            );
+            log::warn!("path [{}] doesn't exist", path.display());
 
-            fs::create_dir_all(&self.config.directory).await?;
+            return Ok(None);
         }
 
-        if !self.config.directory.is_dir() {
-            #[cfg(not(no_io_errorkind))]
-            return Err(Error::new(
-                io::ErrorKind::NotADirectory,
-                format!("path [{}] is a file, not a directory", self.config.directory.display()),
-            ));
-
-            #[cfg(no_io_errorkind)]
+        if path.is_dir() {
             return Err(Error::new(
-                io::ErrorKind::InvalidData,
+                NOT_A_DIRECTORY,
                 format!("path [{}] is a file, not a directory", self.config.directory.display()),
             ));
         }
 
-        Ok(())
+        check_not_special(&path).await?;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!("attempting to open file");
+
+        #[cfg(feature = "log")]
+        log::trace!("attempting to open file [{}]", path.display());
+
+        let size = fs::metadata(&path).await?.len();
+        let _permit = self.reserve(usize::try_from(size).unwrap_or(usize::MAX)).await?;
+
+        let buffer = fs::read(&path).await?;
+        Ok(Some(Bytes::from(buffer)))
     }
 
     #[cfg_attr(
         feature = "tracing",
         instrument(
-            name = "remi.filesystem.open",
+            name = "remi.filesystem.open_range",
             skip_all,
             fields(
                 remi.service = "fs",
@@ -287,17 +809,75 @@ impl remi::StorageService for StorageService {
             )
         )
     )]
-    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<Bytes>> {
-        let path = path.as_ref();
-        let Some(path) = self.normalize(path)? else {
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> io::Result<Option<Bytes>> {
+        check_key(path.as_ref())?;
+        let path = self.resolve(path.as_ref()).await?;
+
+        if !path.try_exists()? {
             #[cfg(feature = "tracing")]
-            tracing::warn!("path given couldn't be normalized");
+            tracing::warn!("path doesn't exist");
 
             #[cfg(feature = "log")]
-            log::warn!("path given [{}] was a file, not a directory", path.display());
+            log::warn!("path [{}] doesn't exist", path.display());
 
             return Ok(None);
-        };
+        }
+
+        if path.is_dir() {
+            return Err(Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("path [{}] is a directory, not a file", path.display()),
+            ));
+        }
+
+        check_not_special(&path).await?;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(start, end = ?end, "attempting to open range of file");
+
+        #[cfg(feature = "log")]
+        log::trace!(
+            "attempting to open range [{start}..{end:?}] of file [{}]",
+            path.display()
+        );
+
+        let len = fs::metadata(&path).await?.len();
+        let (start, end) = remi::clamp_range(start, end, len);
+        let _permit = self.reserve(usize::try_from(end - start).unwrap_or(usize::MAX)).await?;
+
+        let buffer = rt::spawn_blocking("remi_fs::open_range", move || {
+            let mut file = std::fs::File::open(path)?;
+            file.seek(SeekFrom::Start(start))?;
+
+            let mut buffer = Vec::with_capacity((end - start) as usize);
+            file.take(end - start).read_to_end(&mut buffer)?;
+
+            Ok::<_, io::Error>(buffer)
+        })
+        .await?;
+
+        Ok(Some(Bytes::from(buffer)))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.filesystem.open_stream",
+            skip_all,
+            fields(
+                remi.service = "fs",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open_stream<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<ContentStream<io::Error>>> {
+        check_key(path.as_ref())?;
+        let path = self.resolve(path.as_ref()).await?;
 
         if !path.try_exists()? {
             #[cfg(feature = "tracing")]
@@ -310,40 +890,33 @@ impl remi::StorageService for StorageService {
         }
 
         if path.is_dir() {
-            #[cfg(not(no_io_errorkind))]
-            return Err(Error::new(
-                io::ErrorKind::NotADirectory,
-                format!("path [{}] is a file, not a directory", self.config.directory.display()),
-            ));
-
-            #[cfg(no_io_errorkind)]
             return Err(Error::new(
-                io::ErrorKind::InvalidData,
-                format!("path [{}] is a file, not a directory", self.config.directory.display()),
+                io::ErrorKind::InvalidInput,
+                format!("path [{}] is a directory, not a file", path.display()),
             ));
         }
 
+        check_not_special(&path).await?;
+
         #[cfg(feature = "tracing")]
-        tracing::trace!("attempting to open file");
+        tracing::trace!("attempting to open file as a stream");
 
         #[cfg(feature = "log")]
-        log::trace!("attempting to open file [{}]", path.display());
+        log::trace!("attempting to open file [{}] as a stream", path.display());
 
-        let mut file = fs::OpenOptions::new()
-            .create(false)
-            .write(false)
-            .read(true)
-            .open(&path)
-            .await?;
-
-        let metadata = file.metadata().await?;
-        let size = metadata.len();
-        let mut buffer = vec![0; size as usize];
+        let file = rt::spawn_blocking("remi_fs::open_stream", move || std::fs::File::open(path)).await?;
+        let stream = stream::try_unfold(file, |mut file| async move {
+            rt::spawn_blocking("remi_fs::open_stream::read", move || {
+                let mut buffer = vec![0; STREAM_CHUNK_SIZE];
+                let read = file.read(&mut buffer)?;
+                buffer.truncate(read);
 
-        buffer.resize(size as usize, 0);
-        file.read_exact(&mut buffer).await?;
+                Ok((read > 0).then(|| (Bytes::from(buffer), file)))
+            })
+            .await
+        });
 
-        Ok(Some(Bytes::from(buffer)))
+        Ok(Some(stream.boxed()))
     }
 
     #[cfg_attr(
@@ -359,15 +932,7 @@ impl remi::StorageService for StorageService {
     )]
     async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<Blob>> {
         let path = path.as_ref();
-        let Some(path) = self.normalize(path)? else {
-            #[cfg(feature = "tracing")]
-            tracing::warn!("path given couldn't be normalized");
-
-            #[cfg(feature = "log")]
-            log::warn!("path given [{}] couldn't be normalized", path.display());
-
-            return Ok(None);
-        };
+        let path = self.resolve(path).await?;
 
         if path.is_dir() {
             let metadata = path.metadata()?;
@@ -394,6 +959,12 @@ impl remi::StorageService for StorageService {
             })));
         }
 
+        check_key(&path)?;
+        if !path.try_exists()? {
+            return Ok(None);
+        }
+
+        check_not_special(&path).await?;
         Ok(Some(Blob::File(self.create_file(&path).await?)))
     }
 
@@ -414,85 +985,126 @@ impl remi::StorageService for StorageService {
         options: Option<ListBlobsRequest>,
     ) -> io::Result<Vec<Blob>> {
         let options = options.unwrap_or_default();
-        let prefix = options.prefix.clone().unwrap_or_default();
-        let path = match path {
-            Some(ref p) => p.as_ref(),
-            None => &self.config.directory,
-        };
-
-        let Some(path) = self.normalize(path)? else {
-            #[cfg(feature = "tracing")]
-            tracing::warn!("path given couldn't be normalized");
-
-            #[cfg(feature = "log")]
-            log::warn!("path given [{}] was a file, not a directory", path.display());
-
-            return Ok(vec![]);
-        };
-
-        if path.is_file() {
-            #[cfg(feature = "tracing")]
-            tracing::warn!("path given was a file, not a directory");
+        self.list(path.as_ref().map(AsRef::as_ref), &options, None)
+            .await
+            .map(|page| page.items)
+    }
 
-            #[cfg(feature = "log")]
-            log::warn!("path given [{}] was a file, not a directory", path.display());
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.filesystem.blobs_page",
+            skip_all,
+            fields(
+                remi.service = "fs",
+                path = ?path.as_ref().map(|path| path.as_ref().display())
+            )
+        )
+    )]
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> io::Result<Page<Blob>> {
+        let options = options.unwrap_or_default();
+        let limit = options.page_size_or(LIST_PAGE_SIZE, u32::MAX) as usize;
+        self.list(path.as_ref().map(AsRef::as_ref), &options, Some(limit)).await
+    }
 
-            return Ok(vec![]);
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.filesystem.copy",
+            skip_all,
+            fields(
+                remi.service = "fs",
+                src = %src.as_ref().display(),
+                dst = %dst.as_ref().display()
+            )
+        )
+    )]
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> io::Result<bool> {
+        self.check_writable("copy")?;
+        check_key(src.as_ref())?;
+        check_key(dst.as_ref())?;
+
+        let src = self.resolve(src.as_ref()).await?;
+        if !src.is_file() {
+            return Ok(false);
         }
 
-        let search = format!("{}{prefix}", path.display());
         #[cfg(feature = "tracing")]
-        tracing::trace!(%search, "attempting to search all blobs in given path");
+        tracing::trace!("copying file");
 
         #[cfg(feature = "log")]
-        log::trace!(
-            "attempting to search in [{search}] for all blobs in given path [{}]",
-            path.display()
-        );
+        log::trace!("copying file [{}] to [{}]", src.display(), dst.as_ref().display());
 
-        let mut files = fs::read_dir(search).await?;
-        let mut blobs = vec![];
+        // the copy is written like an upload of the source file, so it's checked against
+        // `max_object_size` and the interceptors and keeps the source's modification time
+        let file = self.create_file(&src).await?;
+        self.write(dst.as_ref(), UploadRequest::from_file(&file), false).await?;
 
-        while let Some(entry) = files.next_entry().await? {
-            if entry.path().is_dir() && options.include_dirs {
-                blobs.push(Blob::Directory(Directory {
-                    created_at: match entry.metadata().await {
-                        Ok(sys) => Some(
-                            sys.created()?
-                                .duration_since(SystemTime::UNIX_EPOCH)
-                                .map_err(|_| io::Error::new(io::ErrorKind::Other, "clock went backwards?!"))?
-                                .as_millis(),
-                        ),
+        Ok(true)
+    }
 
-                        Err(_) => None,
-                    },
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.filesystem.rename",
+            skip_all,
+            fields(
+                remi.service = "fs",
+                from = %from.as_ref().display(),
+                to = %to.as_ref().display()
+            )
+        )
+    )]
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> io::Result<bool> {
+        self.check_writable("rename")?;
+        check_key(from.as_ref())?;
+        check_key(to.as_ref())?;
+
+        let from = self.resolve(from.as_ref()).await?;
+        if !from.is_file() {
+            return Ok(false);
+        }
 
-                    name: path
-                        .file_name()
-                        .map(|s| s.to_string_lossy())
-                        .unwrap_or(Cow::Borrowed("<root or relative path>"))
-                        .to_string(),
+        let to = self.normalize(to)?;
+        if from == to {
+            return Ok(true);
+        }
 
-                    path: format!("fs://{}", entry.path().display()),
-                }));
+        #[cfg(feature = "tracing")]
+        tracing::trace!("renaming file");
 
-                continue;
-            }
+        #[cfg(feature = "log")]
+        log::trace!("renaming file [{}] to [{}]", from.display(), to.display());
 
-            let path = entry.path();
-            let ext_allowed = match path.extension() {
-                Some(s) => options.is_ext_allowed(s.to_str().expect("valid utf-8 in path extension")),
-                None => true,
-            };
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).await?;
+        }
 
-            if !ext_allowed {
-                continue;
-            }
+        let existed = to.try_exists()?;
+        let size = fs::metadata(&from).await?.len();
+        fs::rename(&from, &to).await?;
 
-            blobs.push(Blob::File(self.create_file_from_entry(&path, entry).await?));
-        }
+        self.publish(StorageEvent::Deleted {
+            path: format!("fs://{}", from.display()),
+        });
+
+        let event = ObjectEvent {
+            path: format!("fs://{}", to.display()),
+            size: usize::try_from(size).unwrap_or(usize::MAX),
+            content_type: None,
+            metadata: Default::default(),
+        };
+
+        self.publish(match existed {
+            true => StorageEvent::Updated(event),
+            false => StorageEvent::Created(event),
+        });
 
-        Ok(blobs)
+        Ok(true)
     }
 
     #[cfg_attr(
@@ -506,15 +1118,10 @@ impl remi::StorageService for StorageService {
             )
         )
     )]
-    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<()> {
-        let path = path.as_ref();
-        let Some(path) = self.normalize(path)? else {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "unable to normalize given path",
-            ));
-        };
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<bool> {
+        self.check_writable("delete")?;
 
+        let path = self.resolve(path.as_ref()).await?;
         if path.is_dir() {
             #[cfg(feature = "tracing")]
             tracing::trace!("deleting directory");
@@ -522,8 +1129,12 @@ impl remi::StorageService for StorageService {
             #[cfg(feature = "log")]
             log::trace!("deleting directory [{}]", path.display());
 
-            fs::remove_dir(path).await?;
-            return Ok(());
+            fs::remove_dir(&path).await?;
+            self.publish(StorageEvent::Deleted {
+                path: format!("fs://{}", path.display()),
+            });
+
+            return Ok(true);
         }
 
         #[cfg(feature = "tracing")]
@@ -532,7 +1143,91 @@ impl remi::StorageService for StorageService {
         #[cfg(feature = "log")]
         log::trace!("deleting file [{}]...", path.display());
 
-        fs::remove_file(path).await
+        match fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        }
+
+        self.publish(StorageEvent::Deleted {
+            path: format!("fs://{}", path.display()),
+        });
+
+        Ok(true)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.filesystem.delete_prefix",
+            skip_all,
+            fields(
+                remi.service = "fs",
+                prefix = %prefix.as_ref().display()
+            )
+        )
+    )]
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> io::Result<DeleteSummary> {
+        self.check_writable("delete")?;
+
+        let prefix = self.normalize(prefix)?;
+
+        let mut summary = DeleteSummary::default();
+        if !prefix.try_exists()? {
+            return Ok(summary);
+        }
+
+        if !prefix.is_dir() {
+            return Err(io::Error::new(
+                NOT_A_DIRECTORY,
+                format!("path [{}] is not a directory", prefix.display()),
+            ));
+        }
+
+        let mut pending = vec![prefix.clone()];
+        let mut directories = vec![];
+        while let Some(directory) = pending.pop() {
+            for entry in fs::read_dir(&directory).await? {
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+
+                match fs::remove_file(&path).await {
+                    Ok(()) => {
+                        summary.deleted += 1;
+                        self.publish(StorageEvent::Deleted {
+                            path: format!("fs://{}", path.display()),
+                        });
+                    }
+
+                    #[allow(unused)]
+                    Err(e) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(error = %e, path = %path.display(), "failed to delete file");
+
+                        #[cfg(feature = "log")]
+                        log::warn!("failed to delete file [{}]: {e}", path.display());
+
+                        summary.failed.push(path.display().to_string());
+                    }
+                }
+            }
+
+            directories.push(directory);
+        }
+
+        // directories that still have files that failed to be deleted are kept, and the
+        // root directory of this storage service is never removed
+        let root = self.normalize(&self.config.directory)?;
+        for directory in directories.into_iter().rev() {
+            if directory != root {
+                let _ = fs::remove_dir(&directory).await;
+            }
+        }
+
+        Ok(summary)
     }
 
     #[cfg_attr(
@@ -548,12 +1243,7 @@ impl remi::StorageService for StorageService {
     )]
     async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<bool> {
         let path = path.as_ref();
-        let Some(path) = self.normalize(path)? else {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "unable to normalize given path",
-            ));
-        };
+        let path = self.resolve(path).await?;
 
         path.try_exists()
     }
@@ -571,58 +1261,168 @@ impl remi::StorageService for StorageService {
     )]
     async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> io::Result<()> {
         let path = path.as_ref();
-        let Some(path) = self.normalize(path)? else {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "unable to normalize given path",
-            ));
-        };
-
-        if path.try_exists()? {
-            #[cfg(feature = "tracing")]
-            tracing::warn!("contents in given path will be overwritten");
+        match options.collision() {
+            None | Some(CollisionStrategy::Overwrite) => self.write(path, options, false).await,
+            Some(CollisionStrategy::Error) => self.write(path, options, true).await,
+            Some(CollisionStrategy::NewVersion) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the local filesystem can't keep previous versions of files",
+            )),
+
+            Some(strategy @ CollisionStrategy::Suffix(_)) => {
+                for candidate in strategy.candidates(path) {
+                    match self.write(&candidate, options.clone(), true).await {
+                        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                        result => return result,
+                    }
+                }
 
-            #[cfg(feature = "log")]
-            log::trace!("contents in given path [{}] will be overwritten", path.display());
+                Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    remi::AlreadyExists::new(format!("fs://{}", path.display())),
+                ))
+            }
         }
+    }
 
-        #[cfg(feature = "tracing")]
-        tracing::warn!("uploading file");
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.filesystem.create_exclusive",
+            skip_all,
+            fields(
+                remi.service = "fs",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn create_exclusive<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> io::Result<()> {
+        self.write(path.as_ref(), options, true).await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.filesystem.create_dir",
+            skip_all,
+            fields(
+                remi.service = "fs",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<()> {
+        self.check_writable("create a directory")?;
+
+        let path = path.as_ref();
+        let path = self.normalize(path)?;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!("creating directory");
 
         #[cfg(feature = "log")]
-        log::trace!("uploading file [{}]", path.display());
+        log::trace!("creating directory [{}]", path.display());
 
-        // ensure that the parent exists, if not, it'll attempt
-        // to create all paths in the given parent
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).await?;
-        }
+        fs::create_dir_all(path).await
+    }
 
-        let mut file = fs::OpenOptions::new();
-        file.write(true);
+    fn is_read_only(&self) -> bool {
+        self.config.read_only
+    }
 
-        if !path.try_exists()? {
-            // atomically create the file if it doesn't exist
-            file.create_new(true);
-        }
+    fn info(&self) -> ServiceInfo {
+        ServiceInfo::new(self.name())
+            .with_crate_version(env!("CARGO_PKG_VERSION"))
+            .with_location(Some(self.config.directory.display().to_string()))
+            .with_capabilities(Capabilities {
+                range_reads: true,
+                streaming: true,
+                pagination: true,
+                server_side_copy: true,
+                atomic_rename: true,
+                ..Default::default()
+            })
+    }
+
+    async fn debug_validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let directory = match self.config.directory.try_exists() {
+            Ok(true) if self.config.directory.is_dir() => Ok(()),
+            Ok(true) => Err(format!(
+                "path [{}] is a file, not a directory",
+                self.config.directory.display()
+            )),
+            Ok(false) => Err(format!("directory [{}] doesn't exist", self.config.directory.display())),
+            Err(e) => Err(e.to_string()),
+        };
 
-        let mut file = file.open(path).await?;
-        file.write_all(options.data.as_ref()).await?;
-        file.flush().await?;
+        if !report.record(
+            "directory exists",
+            &directory,
+            "call `StorageService::init` to create the directory or check `StorageConfig::directory`",
+        ) {
+            report.skip("write probe object");
+            report.skip("read probe object");
+            report.skip("delete probe object");
 
-        Ok(())
+            return report;
+        }
+
+        remi::probe_read_write(self, &mut report).await;
+        report
     }
 
-    #[cfg(feature = "unstable")]
-    #[cfg_attr(any(noeldoc, docsrs), doc(cfg(feature = "unstable")))]
-    async fn healthcheck(&self) -> io::Result<()> {
-        Ok(())
+    /// Opens the directory to check that it can be read and, unless the storage service is
+    /// read-only, writes a temporary file in it that is removed right after.
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.filesystem.healthcheck",
+            skip_all,
+            fields(
+                remi.service = "fs",
+                directory = %self.config.directory.display()
+            )
+        )
+    )]
+    async fn healthcheck(&self) -> HealthReport {
+        let directory = self.config.directory.clone();
+        let read_only = self.config.read_only;
+
+        rt::spawn_blocking("remi_fs::healthcheck", move || {
+            let started = Instant::now();
+            if let Err(e) = std::fs::read_dir(&directory) {
+                return match e.kind() {
+                    io::ErrorKind::PermissionDenied => HealthReport::reachable(started.elapsed())
+                        .with_read(false)
+                        .with_error(e),
+
+                    _ => HealthReport::unreachable(started.elapsed(), e),
+                };
+            }
+
+            if read_only {
+                return HealthReport::reachable(started.elapsed()).with_read(true);
+            }
+
+            // the temporary file is removed when its guard is dropped
+            let written = TempFile::create(&directory.join("healthcheck"))
+                .and_then(|(_temp, mut file)| file.write_all(b"remi probe").and_then(|_| file.sync_data()));
+
+            let report = HealthReport::reachable(started.elapsed()).with_read(true);
+            match written {
+                Ok(()) => report.with_write(true),
+                Err(e) => report.with_write(false).with_error(e),
+            }
+        })
+        .await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use remi::DiffEntry;
 
     // built to not repeat setup functionality
     macro_rules! build_testcases {
@@ -640,7 +1440,7 @@ mod tests {
                     let $storage = $crate::StorageService::new(&tempdir);
                     ($storage).init().await.expect("initialization part to be successful");
 
-                    assert!(fs::try_exists(tempdir).await.expect("should actually exist?!"));
+                    assert!(fs::try_exists(&tempdir).await.expect("should actually exist?!"));
 
                     let __ret: ::std::io::Result<()> = $code;
                     __ret
@@ -654,6 +1454,1013 @@ mod tests {
             Ok(())
         }
 
+        healthcheck(storage) {
+            let report = storage.healthcheck().await;
+            assert!(report.is_healthy(), "{report}");
+            assert_eq!(report.permissions.read, Some(true));
+            assert_eq!(report.permissions.write, Some(true));
+
+            // the temporary file was removed
+            assert!(storage.blobs(None::<&str>, None).await?.is_empty());
+
+            let report = StorageService::with_config(StorageConfig {
+                read_only: true,
+                ..StorageConfig::new(&storage.config.directory)
+            })
+            .healthcheck()
+            .await;
+
+            assert!(report.is_healthy(), "{report}");
+            assert_eq!(report.permissions.write, None);
+
+            let report = StorageService::new(storage.config.directory.join("missing")).healthcheck().await;
+            assert!(!report.reachable);
+            assert!(!report.is_healthy());
+
+            Ok(())
+        }
+
+        info(storage) {
+            let info = storage.info();
+            assert_eq!(info.name, "remi:fs");
+            assert_eq!(info.crate_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+            assert_eq!(info.location, Some(storage.config.directory.display().to_string()));
+            assert!(info.capabilities.range_reads);
+            assert!(!info.capabilities.presigning);
+
+            Ok(())
+        }
+
+        upload_overwrites_existing_contents(storage) {
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow fluff")).await?;
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+
+            assert_eq!(storage.open("./weow.txt").await?, Some(Bytes::from_static(b"weow")));
+            Ok(())
+        }
+
+        create_exclusive(storage) {
+            storage.create_exclusive("./lock", UploadRequest::default().with_data("a")).await?;
+
+            let error = storage
+                .create_exclusive("./lock", UploadRequest::default().with_data("b"))
+                .await
+                .unwrap_err();
+
+            assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+            assert!(error.get_ref().is_some_and(|e| e.is::<remi::AlreadyExists>()));
+            assert_eq!(storage.open("./lock").await?, Some(Bytes::from_static(b"a")));
+            Ok(())
+        }
+
+        open_missing_and_empty_files(storage) {
+            storage.upload("./empty.txt", UploadRequest::default()).await?;
+
+            assert_eq!(storage.open("./empty.txt").await?, Some(Bytes::new()));
+            assert_eq!(storage.open("./missing.txt").await?, None);
+            assert_eq!(
+                storage.open("./weow/").await.unwrap_err().kind(),
+                io::ErrorKind::InvalidInput
+            );
+
+            Ok(())
+        }
+
+        diff_against_another_directory(storage) {
+            let tempdir = ::tempfile::tempdir()?;
+            let replica = StorageService::new(&tempdir);
+            replica.init().await?;
+
+            storage.upload("./backups/a.txt", UploadRequest::default().with_data("a")).await?;
+            storage.upload("./backups/b.txt", UploadRequest::default().with_data("b")).await?;
+            storage.upload("./backups/c.txt", UploadRequest::default().with_data("c")).await?;
+            replica.upload("./backups/b.txt", UploadRequest::default().with_data("b")).await?;
+            replica.upload("./backups/c.txt", UploadRequest::default().with_data("cc")).await?;
+            replica.upload("./backups/d.txt", UploadRequest::default().with_data("d")).await?;
+
+            let mut diff = remi::diff(&storage, "./backups", &replica, "./backups");
+            let mut entries = vec![];
+            while let Some(entry) = diff.next().await.map_err(io::Error::other)? {
+                entries.push(entry);
+            }
+
+            assert!(matches!(&entries[..], [
+                DiffEntry::Removed { key: a, .. },
+                DiffEntry::Changed { key: c, .. },
+                DiffEntry::Added { key: d, .. },
+            ] if a == "a.txt" && c == "c.txt" && d == "d.txt"));
+
+            Ok(())
+        }
+
+        upload_collisions(storage) {
+            storage.upload("./a.txt", UploadRequest::default().with_data("a")).await?;
+
+            let request = UploadRequest::default().with_collision(CollisionStrategy::Suffix(2));
+            storage.upload("./a.txt", request.clone().with_data("b")).await?;
+            storage.upload("./a.txt", request.clone().with_data("c")).await?;
+            assert_eq!(
+                storage.upload("./a.txt", request.with_data("d")).await.unwrap_err().kind(),
+                io::ErrorKind::AlreadyExists
+            );
+
+            assert_eq!(storage.open("./a.txt").await?, Some(Bytes::from_static(b"a")));
+            assert_eq!(storage.open("./a-1.txt").await?, Some(Bytes::from_static(b"b")));
+            assert_eq!(storage.open("./a-2.txt").await?, Some(Bytes::from_static(b"c")));
+
+            let request = UploadRequest::default().with_data("e");
+            let error = storage
+                .upload("./a.txt", request.clone().with_collision(CollisionStrategy::Error))
+                .await
+                .unwrap_err();
+
+            assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+            assert_eq!(
+                storage.upload("./a.txt", request.with_collision(CollisionStrategy::NewVersion)).await.unwrap_err().kind(),
+                io::ErrorKind::Unsupported
+            );
+
+            Ok(())
+        }
+
+        conditional_opens(storage) {
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+
+            let Some(Blob::File(file)) = storage.blob("./weow.txt").await? else {
+                panic!("expected `weow.txt` to be a file");
+            };
+
+            let etag = file.etag.expect("`weow.txt` should have an entity tag");
+            assert_eq!(file.checksum.as_deref(), Some(ChecksumCache::checksum(b"weow").as_str()));
+            assert!(matches!(
+                storage.open_if_none_match("./weow.txt", &format!("\"{etag}\"")).await?,
+                Some(remi::Conditional::NotModified)
+            ));
+
+            storage.upload("./weow.txt", UploadRequest::default().with_data("fluff")).await?;
+            assert!(matches!(
+                storage.open_if_none_match("./weow.txt", &etag).await?,
+                Some(remi::Conditional::Modified(file)) if file.data == "fluff"
+            ));
+
+            assert!(storage.open_if_none_match("./missing.txt", &etag).await?.is_none());
+            Ok(())
+        }
+
+        delete_prefix(storage) {
+            storage.upload("./images/a.png", UploadRequest::default().with_data("a")).await?;
+            storage.upload("./images/nested/b.png", UploadRequest::default().with_data("b")).await?;
+            storage.upload("./images2/c.png", UploadRequest::default().with_data("c")).await?;
+
+            let summary = storage.delete_prefix("./images").await?;
+            assert_eq!(summary.deleted, 2);
+            assert!(summary.is_complete());
+
+            assert!(!storage.exists("./images").await?);
+            assert!(storage.exists("./images2/c.png").await?);
+            assert_eq!(storage.delete_prefix("./images").await?, DeleteSummary::default());
+            Ok(())
+        }
+
+        create_dir(storage) {
+            storage.create_dir("./empty/nested").await?;
+
+            assert!(storage.exists("./empty/nested").await?);
+            assert!(matches!(storage.blob("./empty/nested").await?, Some(Blob::Directory(_))));
+            Ok(())
+        }
+
+        cancelled_uploads_keep_existing_contents(storage) {
+            use std::{future::{poll_fn, Future}, task::Poll};
+
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+
+            // poll the upload a few times and then drop it, which cancels it somewhere midway
+            let data = Bytes::from(vec![b'a'; 4 * 1024 * 1024]);
+            let mut upload = Box::pin(storage.upload("./weow.txt", UploadRequest::default().with_data(data.clone())));
+
+            let mut polls = 0;
+            poll_fn(|cx| {
+                let _ = upload.as_mut().poll(cx);
+                polls += 1;
+
+                match polls {
+                    3 => Poll::Ready(()),
+                    _ => {
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                }
+            })
+            .await;
+
+            drop(upload);
+
+            let contents = storage.open("./weow.txt").await?.expect("file to exist");
+            assert!(contents == Bytes::from_static(b"weow") || contents == data);
+
+            let directory = storage.normalize(&storage.config.directory)?;
+            let leftover = std::fs::read_dir(directory)?
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+                .count();
+
+            assert_eq!(leftover, 0);
+            Ok(())
+        }
+
+        rejects_invalid_keys(storage) {
+            // `blob` used to panic on `file_name().unwrap()` for keys without a file name
+            for key in ["", "./missing/.."] {
+                let err = storage.blob(key).await.unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{key}");
+            }
+
+            let err = storage.upload("./weow/", UploadRequest::default()).await.unwrap_err();
+            assert_eq!(
+                err.get_ref().and_then(|e| e.downcast_ref::<remi::InvalidKey>()),
+                Some(&remi::InvalidKey::TrailingSlash(String::from("./weow/")))
+            );
+
+            assert!(storage.open("./").await.is_err());
+            assert!(matches!(storage.blob("./").await?, Some(Blob::Directory(_))));
+
+            Ok(())
+        }
+
+        case_insensitive_lookup(storage) {
+            storage.upload("./docs/README.md", UploadRequest::default().with_data("weow")).await?;
+
+            let storage = StorageService::with_config(StorageConfig {
+                case_insensitive_lookup: true,
+                ..storage.config.clone()
+            });
+
+            assert!(storage.exists("./docs/Readme.MD").await?);
+            assert_eq!(storage.open("./docs/Readme.MD").await?, Some(Bytes::from_static(b"weow")));
+            assert_eq!(storage.open("./docs/CHANGELOG.md").await?, None);
+
+            Ok(())
+        }
+
+        case_insensitive_delete(storage) {
+            storage.upload("./docs/README.md", UploadRequest::default().with_data("weow")).await?;
+
+            let storage = StorageService::with_config(StorageConfig {
+                case_insensitive_lookup: true,
+                ..storage.config.clone()
+            });
+
+            assert!(storage.delete("./docs/Readme.MD").await?);
+            assert!(!storage.exists("./docs/README.md").await?);
+            assert!(!storage.delete("./docs/Readme.MD").await?);
+
+            Ok(())
+        }
+
+        read_only_rejects_mutations(storage) {
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+
+            let storage = StorageService::with_config(StorageConfig {
+                read_only: true,
+                ..storage.config.clone()
+            });
+
+            assert!(storage.is_read_only());
+            assert_eq!(storage.open("./weow.txt").await?, Some(Bytes::from_static(b"weow")));
+
+            let err = storage.delete("./weow.txt").await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+            assert_eq!(
+                err.get_ref().and_then(|e| e.downcast_ref::<remi::ReadOnly>()),
+                Some(&remi::ReadOnly { operation: "delete" })
+            );
+
+            assert!(storage.upload("./fluff.txt", UploadRequest::default()).await.is_err());
+            assert!(storage.create_dir("./fluff").await.is_err());
+            assert!(storage.exists("./weow.txt").await?);
+            assert!(!storage.exists("./fluff.txt").await?);
+
+            Ok(())
+        }
+
+        rejects_too_large_uploads(storage) {
+            let storage = StorageService::with_config(StorageConfig {
+                max_object_size: Some(4),
+                ..storage.config.clone()
+            });
+
+            let err = storage
+                .upload("./weow.txt", UploadRequest::default().with_data("fluff"))
+                .await
+                .unwrap_err();
+
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(
+                err.get_ref().and_then(|e| e.downcast_ref::<remi::TooLarge>()),
+                Some(&remi::TooLarge { size: 5, max: 4 })
+            );
+
+            assert!(!storage.exists("./weow.txt").await?);
+            Ok(())
+        }
+
+        enforces_strict_memory_limit(storage) {
+            let storage = StorageService::with_config(StorageConfig {
+                strict_memory_limit: Some(4),
+                memory_limit_fail_fast: true,
+                ..storage.config.clone()
+            });
+
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+            assert_eq!(storage.open("./weow.txt").await?, Some(Bytes::from_static(b"weow")));
+
+            let err = storage
+                .upload("./fluff.txt", UploadRequest::default().with_data("fluff"))
+                .await
+                .unwrap_err();
+
+            assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+            assert_eq!(
+                err.get_ref().and_then(|e| e.downcast_ref::<remi::MemoryLimitExceeded>()),
+                Some(&remi::MemoryLimitExceeded { requested: 5, available: 4, limit: 4 })
+            );
+
+            assert!(!storage.exists("./fluff.txt").await?);
+            Ok(())
+        }
+
+        rejects_uploads_by_policy(storage) {
+            let storage = storage.with_interceptor(|_: &str, request: &UploadRequest| {
+                match request.data.starts_with(b"MZ") {
+                    true => Err(String::from("executables are not allowed")),
+                    false => Ok(()),
+                }
+            });
+
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+
+            let err = storage
+                .upload(
+                    "./weow.exe",
+                    UploadRequest::default().with_data(Bytes::from_static(b"MZ\x90\x00")),
+                )
+                .await
+                .unwrap_err();
+
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+            assert!(err
+                .get_ref()
+                .and_then(|e| e.downcast_ref::<remi::RejectedByPolicy>())
+                .is_some_and(|e| e.reason == "executables are not allowed" && e.path.ends_with("weow.exe")));
+
+            assert!(!storage.exists("./weow.exe").await?);
+            Ok(())
+        }
+
+        publishes_events(storage) {
+            let (tx, mut rx) = broadcast::channel(4);
+            let storage = storage.with_events(tx);
+
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+            storage.upload("./weow.txt", UploadRequest::default().with_data("fluff")).await?;
+            storage.delete("./weow.txt").await?;
+
+            assert!(matches!(rx.try_recv(), Ok(StorageEvent::Created(event)) if event.size == 4));
+            assert!(matches!(rx.try_recv(), Ok(StorageEvent::Updated(event)) if event.size == 5));
+            assert!(matches!(rx.try_recv(), Ok(StorageEvent::Deleted { path }) if path.ends_with("weow.txt")));
+            Ok(())
+        }
+
+        copy_files(storage) {
+            let (tx, mut rx) = broadcast::channel(4);
+            let storage = storage.with_events(tx);
+
+            assert!(!storage.copy("./missing.txt", "./copy.txt").await?);
+            assert!(!storage.exists("./copy.txt").await?);
+
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+            let _ = rx.try_recv();
+
+            assert!(storage.copy("./weow.txt", "./nested/copy.txt").await?);
+            assert_eq!(storage.open("./nested/copy.txt").await?, Some(Bytes::from_static(b"weow")));
+            assert_eq!(storage.open("./weow.txt").await?, Some(Bytes::from_static(b"weow")));
+            assert!(matches!(rx.try_recv(), Ok(StorageEvent::Created(event)) if event.size == 4));
+            Ok(())
+        }
+
+        copy_keeps_file_timestamps(storage) {
+            let timestamps = remi::FileTimestamps { modified: Some(1_000_000_123_456), created: None };
+            storage
+                .upload("./weow.txt", UploadRequest::default().with_data("weow").with_timestamps(timestamps))
+                .await?;
+
+            assert!(storage.copy("./weow.txt", "./fluff.txt").await?);
+            let Some(Blob::File(copy)) = storage.blob("./fluff.txt").await? else {
+                panic!("expected a file");
+            };
+
+            assert_eq!(copy.last_modified_at, Some(1_000_000_123_456));
+            Ok(())
+        }
+
+        copy_rejects_too_large_files(storage) {
+            storage.upload("./weow.txt", UploadRequest::default().with_data("fluff")).await?;
+
+            let storage = StorageService::with_config(StorageConfig {
+                max_object_size: Some(4),
+                ..storage.config.clone()
+            });
+
+            let err = storage.copy("./weow.txt", "./fluff.txt").await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(
+                err.get_ref().and_then(|e| e.downcast_ref::<remi::TooLarge>()),
+                Some(&remi::TooLarge { size: 5, max: 4 })
+            );
+
+            assert!(!storage.exists("./fluff.txt").await?);
+            Ok(())
+        }
+
+        copy_rejects_files_by_policy(storage) {
+            storage
+                .upload("./weow.txt", UploadRequest::default().with_data(Bytes::from_static(b"MZ\x90\x00")))
+                .await?;
+
+            let storage = storage.with_interceptor(|_: &str, request: &UploadRequest| {
+                match request.data.starts_with(b"MZ") {
+                    true => Err(String::from("executables are not allowed")),
+                    false => Ok(()),
+                }
+            });
+
+            let err = storage.copy("./weow.txt", "./weow.exe").await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+            assert!(err.get_ref().is_some_and(|e| e.is::<remi::RejectedByPolicy>()));
+
+            assert!(!storage.exists("./weow.exe").await?);
+            Ok(())
+        }
+
+        rename_files(storage) {
+            let (tx, mut rx) = broadcast::channel(4);
+            let storage = storage.with_events(tx);
+
+            assert!(!storage.rename("./missing.txt", "./renamed.txt").await?);
+
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+            let _ = rx.try_recv();
+
+            assert!(storage.rename("./weow.txt", "./weow.txt").await?);
+            assert!(storage.rename("./weow.txt", "./nested/renamed.txt").await?);
+            assert_eq!(storage.open("./nested/renamed.txt").await?, Some(Bytes::from_static(b"weow")));
+            assert!(!storage.exists("./weow.txt").await?);
+            assert!(matches!(rx.try_recv(), Ok(StorageEvent::Deleted { path }) if path.ends_with("weow.txt")));
+            assert!(matches!(rx.try_recv(), Ok(StorageEvent::Created(event)) if event.size == 4));
+            Ok(())
+        }
+
+        open_range_reads_part_of_a_file(storage) {
+            assert_eq!(storage.open_range("./weow.txt", 0, None).await?, None);
+
+            storage.upload("./weow.txt", UploadRequest::default().with_data("hello, world")).await?;
+            assert_eq!(storage.open_range("./weow.txt", 7, Some(12)).await?, Some(Bytes::from_static(b"world")));
+            assert_eq!(storage.open_range("./weow.txt", 7, None).await?, Some(Bytes::from_static(b"world")));
+            assert_eq!(storage.open_range("./weow.txt", 0, Some(5)).await?, Some(Bytes::from_static(b"hello")));
+            assert_eq!(storage.open_range("./weow.txt", 7, Some(100)).await?, Some(Bytes::from_static(b"world")));
+            assert_eq!(storage.open_range("./weow.txt", 100, None).await?, Some(Bytes::new()));
+            Ok(())
+        }
+
+        open_stream_reads_in_chunks(storage) {
+            use futures_util::TryStreamExt;
+
+            assert!(storage.open_stream("./weow.bin").await?.is_none());
+
+            let data = (0..STREAM_CHUNK_SIZE * 2 + 1).map(|i| i as u8).collect::<Vec<_>>();
+            storage.upload("./weow.bin", UploadRequest::default().with_data(data.clone())).await?;
+
+            let chunks = storage
+                .open_stream("./weow.bin")
+                .await?
+                .expect("`weow.bin` to exist")
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            assert_eq!(chunks.len(), 3);
+            assert_eq!(chunks.concat(), data);
+            Ok(())
+        }
+
+        filters_by_extensions(storage) {
+            for name in ["./a.txt", "./b.json", "./c.tar.gz", "./README"] {
+                storage.upload(name, UploadRequest::default().with_data(name)).await?;
+            }
+
+            let request = ListBlobsRequest::default().with_extensions(["txt", ".gz"].into_iter());
+            let mut names = storage
+                .blobs(None::<&str>, Some(request))
+                .await?
+                .into_iter()
+                .filter_map(|blob| match blob {
+                    Blob::File(file) => Some(file.name),
+                    Blob::Directory(_) => None,
+                })
+                .collect::<Vec<_>>();
+
+            names.sort();
+            assert_eq!(names, ["README", "a.txt", "c.tar.gz"]);
+            Ok(())
+        }
+
+        lists_in_stable_order(storage) {
+            for name in ["./d.txt", "./b.txt", "./a.txt", "./c.txt"] {
+                storage.upload(name, UploadRequest::default().with_data(name)).await?;
+            }
+
+            let list = |request: Option<ListBlobsRequest>| async {
+                let blobs = storage.blobs(None::<&str>, request).await?;
+                Ok::<_, io::Error>(
+                    blobs
+                        .into_iter()
+                        .filter_map(|blob| match blob {
+                            Blob::File(file) => Some(file),
+                            Blob::Directory(_) => None,
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            };
+
+            let files = list(None).await?;
+            let names = files.iter().map(|file| file.name.as_str()).collect::<Vec<_>>();
+            assert_eq!(names, ["a.txt", "b.txt", "c.txt", "d.txt"]);
+
+            let after = files[1].path.trim_start_matches("fs://").to_owned();
+            let rest = list(Some(ListBlobsRequest::default().with_start_after(Some(after)))).await?;
+            let names = rest.iter().map(|file| file.name.as_str()).collect::<Vec<_>>();
+            assert_eq!(names, ["c.txt", "d.txt"]);
+            Ok(())
+        }
+
+        blobs_page_continues_listing(storage) {
+            for name in ["./c.txt", "./a.txt", "./d.txt", "./b.txt"] {
+                storage.upload(name, UploadRequest::default().with_data(name)).await?;
+            }
+
+            let names = |page: &Page<Blob>| {
+                page.items
+                    .iter()
+                    .filter_map(|blob| match blob {
+                        Blob::File(file) => Some(file.name.clone()),
+                        Blob::Directory(_) => None,
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            let request = ListBlobsRequest::default().with_page_size(std::num::NonZeroU32::new(2));
+            let first = storage.blobs_page(None::<&str>, Some(request.clone())).await?;
+            assert_eq!(names(&first), ["a.txt", "b.txt"]);
+            assert!(!first.is_last());
+
+            let request = request.with_continuation(first.continuation.clone());
+            let second = storage.blobs_page(None::<&str>, Some(request)).await?;
+            assert_eq!(names(&second), ["c.txt", "d.txt"]);
+
+            let page = storage.blobs_page(None::<&str>, None).await?;
+            assert_eq!(names(&page), ["a.txt", "b.txt", "c.txt", "d.txt"]);
+            assert!(page.is_last());
+            Ok(())
+        }
+
+        blobs_page_order_is_deterministic(storage) {
+            let mut expected = vec![];
+            for i in [7, 2, 9, 0, 4, 8, 1, 6, 3, 5] {
+                let name = format!("{i}.txt");
+                storage.upload(format!("./{name}"), UploadRequest::default().with_data(name.clone())).await?;
+                expected.push(name);
+            }
+
+            expected.sort();
+
+            let pages = || async {
+                let mut names = vec![];
+                let mut request = ListBlobsRequest::default().with_page_size(std::num::NonZeroU32::new(3));
+                loop {
+                    let page = storage.blobs_page(None::<&str>, Some(request.clone())).await?;
+                    names.extend(page.items.into_iter().filter_map(|blob| match blob {
+                        Blob::File(file) => Some(file.name),
+                        Blob::Directory(_) => None,
+                    }));
+
+                    match page.continuation {
+                        Some(token) => request = request.with_continuation(Some(token)),
+                        None => break,
+                    }
+                }
+
+                Ok::<_, io::Error>(names)
+            };
+
+            // every page continues where the last one stopped, in the same order every time
+            assert_eq!(pages().await?, expected);
+            assert_eq!(pages().await?, expected);
+            Ok(())
+        }
+
+        list_metadata_only(storage) {
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow fluff")).await?;
+
+            let request = ListBlobsRequest::default().with_metadata_only(true);
+            let blobs = storage.blobs(None::<&str>, Some(request)).await?;
+            let Some(Blob::File(file)) = blobs.first() else {
+                panic!("expected a file to be listed");
+            };
+
+            assert!(file.data.is_empty());
+            assert_eq!(file.size, 10);
+            assert!(file.content_type.is_some());
+
+            let blobs = storage.blobs(None::<&str>, None).await?;
+            let Some(Blob::File(file)) = blobs.first() else {
+                panic!("expected a file to be listed");
+            };
+
+            assert_eq!(file.data, "weow fluff");
+            Ok(())
+        }
+
+        snapshot_view_rejects_changed_files(storage) {
+            for name in ["./artifacts/a.txt", "./artifacts/b.txt"] {
+                storage.upload(name, UploadRequest::default().with_data(name)).await?;
+            }
+
+            let snapshot = remi::freeze(storage.clone(), "./artifacts").await?;
+            assert_eq!(snapshot.manifest().iter().map(|(key, _)| key).collect::<Vec<_>>(), ["a.txt", "b.txt"]);
+            assert_eq!(snapshot.blobs(None::<&str>, None).await.unwrap().len(), 2);
+            assert_eq!(
+                snapshot.open("a.txt").await.unwrap(),
+                Some(Bytes::from_static(b"./artifacts/a.txt"))
+            );
+
+            // files that weren't frozen don't exist in the snapshot
+            storage.upload("./artifacts/c.txt", UploadRequest::default().with_data("c")).await?;
+            assert_eq!(snapshot.open("c.txt").await.unwrap(), None);
+
+            storage.upload("./artifacts/b.txt", UploadRequest::default().with_data("changed")).await?;
+            assert!(matches!(
+                snapshot.open("b.txt").await,
+                Err(remi::SnapshotError::Changed { key }) if key == "b.txt"
+            ));
+
+            storage.delete("./artifacts/a.txt").await?;
+            assert!(matches!(snapshot.exists("a.txt").await, Err(remi::SnapshotError::Missing { .. })));
+            assert!(matches!(
+                snapshot.upload("a.txt", UploadRequest::default()).await,
+                Err(remi::SnapshotError::ReadOnly(_))
+            ));
+
+            Ok(())
+        }
+
+        #[cfg(unix)]
+        skips_special_files(storage) {
+            storage.upload("./a.txt", UploadRequest::default().with_data("a")).await?;
+
+            let fifo = storage.config.directory.join("pipe");
+            assert!(std::process::Command::new("mkfifo").arg(&fifo).status()?.success());
+            let _socket = std::os::unix::net::UnixListener::bind(storage.config.directory.join("socket"))?;
+
+            let names = storage
+                .blobs(None::<&str>, None)
+                .await?
+                .into_iter()
+                .filter_map(|blob| match blob {
+                    Blob::File(file) => Some(file.name),
+                    Blob::Directory(_) => None,
+                })
+                .collect::<Vec<_>>();
+
+            assert_eq!(names, ["a.txt"]);
+
+            // opening a FIFO would block until something writes to it
+            for key in ["./pipe", "./socket"] {
+                assert_eq!(storage.open(key).await.unwrap_err().kind(), io::ErrorKind::InvalidInput, "{key}");
+                assert_eq!(storage.blob(key).await.unwrap_err().kind(), io::ErrorKind::InvalidInput, "{key}");
+            }
+
+            let storage = StorageService::with_config(StorageConfig {
+                error_on_special_files: true,
+                ..StorageConfig::new(&storage.config.directory)
+            });
+
+            assert_eq!(
+                storage.blobs(None::<&str>, None).await.unwrap_err().kind(),
+                io::ErrorKind::InvalidInput
+            );
+
+            Ok(())
+        }
+
+        blob_stream_yields_every_blob(storage) {
+            use futures_util::TryStreamExt;
+
+            for name in ["./b.txt", "./a.txt", "./nested/c.txt"] {
+                storage.upload(name, UploadRequest::default().with_data(name)).await?;
+            }
+
+            let blobs = storage
+                .blob_stream(None::<&str>, None)
+                .into_stream()
+                .try_collect::<Vec<_>>()
+                .await?;
+            let names = blobs
+                .iter()
+                .map(|blob| match blob {
+                    Blob::File(file) => file.name.as_str(),
+                    Blob::Directory(_) => "<dir>",
+                })
+                .collect::<Vec<_>>();
+
+            // directories aren't included by default
+            assert_eq!(names, ["a.txt", "b.txt"]);
+
+            let mut request = ListBlobsRequest::default();
+            request.with_include_dirs(true);
+
+            let blobs = storage
+                .blob_stream(None::<&str>, Some(request))
+                .into_stream()
+                .try_collect::<Vec<_>>()
+                .await?;
+            assert_eq!(blobs.len(), 3);
+            assert!(matches!(blobs[2], Blob::Directory(_)));
+            Ok(())
+        }
+
+        blob_stream_resumes_from_checkpoint(storage) {
+            for name in ["./c.txt", "./a.txt", "./b.txt"] {
+                storage.upload(name, UploadRequest::default().with_data(name)).await?;
+            }
+
+            let mut stream = storage.blob_stream(None::<&str>, None);
+            let Some(Blob::File(first)) = stream.next().await? else {
+                panic!("expected a file");
+            };
+
+            assert_eq!(first.name, "a.txt");
+
+            let checkpoint = stream.checkpoint().cloned();
+            let mut stream = storage.blob_stream(None::<&str>, Some(ListBlobsRequest::default().with_start_after(checkpoint)));
+            let mut names = Vec::new();
+            while let Some(blob) = stream.next().await? {
+                if let Blob::File(file) = blob {
+                    names.push(file.name);
+                }
+            }
+
+            assert_eq!(names, ["b.txt", "c.txt"]);
+            Ok(())
+        }
+
+        lease_lock_is_exclusive(storage) {
+            use remi::LeaseLock;
+            use std::time::Duration;
+
+            let lock = LeaseLock::new(storage.clone(), "./locks/migrations").with_ttl(Duration::from_millis(300));
+            let other = LeaseLock::new(storage.clone(), "./locks/migrations");
+
+            let guard = lock.try_acquire().await?.expect("lock to be free");
+            assert_eq!(guard.fencing_token(), 1);
+            assert!(other.try_acquire().await?.is_none());
+
+            // the lease is renewed in the background while the guard is alive
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            assert!(!guard.is_lost());
+            assert!(other.try_acquire().await?.is_none());
+
+            guard.release().await?;
+
+            let guard = other.try_acquire().await?.expect("released lock to be free");
+            assert_eq!(guard.fencing_token(), 2);
+            guard.release().await?;
+
+            Ok(())
+        }
+
+        usage_watcher_alerts_over_thresholds(storage) {
+            for name in ["./a.txt", "./b.txt"] {
+                storage.upload(name, UploadRequest::default().with_data("weow")).await?;
+            }
+
+            assert_eq!(
+                remi::usage(&storage, None::<&str>).await?,
+                remi::Usage { objects: 2, bytes: 8 }
+            );
+
+            let watcher = remi::UsageWatcher::new(storage.clone())
+                .with_max_bytes(4)
+                .with_max_objects(2);
+
+            let alerts = watcher.check().await?;
+            assert_eq!(alerts.len(), 1);
+            assert_eq!(alerts[0].limit, remi::UsageLimit::Bytes(4));
+            assert_eq!(alerts[0].to_string(), "storage usage of 8 bytes is over 4 bytes");
+
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let handle = watcher
+                .with_interval(std::time::Duration::from_millis(10))
+                .on_alert(move |alert| {
+                    let _ = tx.send(*alert);
+                })
+                .spawn();
+
+            assert_eq!(rx.recv().await.map(|alert| alert.limit), Some(remi::UsageLimit::Bytes(4)));
+            handle.stop();
+            Ok(())
+        }
+
+        init_runs_once(storage) {
+            let storage = StorageService::with_config(StorageConfig::new(storage.config.directory.join("nested")));
+            let (a, b, c) = tokio::join!(storage.init(), storage.init(), storage.init());
+            a?;
+            b?;
+            c?;
+
+            assert!(storage.config.directory.is_dir());
+
+            // clones share the guard, so the directory isn't checked again
+            fs::remove_dir(&storage.config.directory).await?;
+            storage.clone().init().await?;
+            assert!(!storage.config.directory.exists());
+
+            Ok(())
+        }
+
+        init_is_idempotent(storage) {
+            let replica = StorageService::with_config(StorageConfig::new(&storage.config.directory));
+            replica.init().await?;
+            replica.init().await?;
+
+            storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+            StorageService::with_config(StorageConfig::new(&storage.config.directory)).init().await?;
+            assert_eq!(storage.open("./weow.txt").await?, Some(Bytes::from_static(b"weow")));
+
+            Ok(())
+        }
+
+        init_without_provisioning(storage) {
+            let config = StorageConfig {
+                skip_provisioning: true,
+                ..StorageConfig::new(storage.config.directory.join("nested"))
+            };
+
+            let error = StorageService::with_config(config.clone()).init().await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::NotFound);
+            assert!(error.get_ref().is_some_and(|e| e.is::<remi::ResourceMissing>()));
+            assert!(!config.directory.exists());
+
+            fs::create_dir_all(&config.directory).await?;
+            StorageService::with_config(config).init().await?;
+            Ok(())
+        }
+
+        init_is_retried_after_failure(storage) {
+            let config = StorageConfig {
+                skip_provisioning: true,
+                ..StorageConfig::new(storage.config.directory.join("nested"))
+            };
+
+            // a failed initialization isn't remembered by the guard
+            let storage = StorageService::with_config(config);
+            assert_eq!(storage.init().await.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+            fs::create_dir_all(&storage.config.directory).await?;
+            storage.init().await?;
+            Ok(())
+        }
+
+        concurrent_init_from_separate_services(storage) {
+            let directory = storage.config.directory.join("a/b/c");
+            let services = (0..8)
+                .map(|_| StorageService::with_config(StorageConfig::new(&directory)))
+                .collect::<Vec<_>>();
+
+            // every service creates the directory, so they race each other
+            let results = futures_util::future::join_all(services.iter().map(|service| service.init())).await;
+            for result in results {
+                result?;
+            }
+
+            assert!(directory.is_dir());
+            Ok(())
+        }
+
+        preserves_file_timestamps(storage) {
+            let timestamps = remi::FileTimestamps { modified: Some(1_000_000_123_456), created: None };
+            storage
+                .upload("./weow.txt", UploadRequest::default().with_data("weow").with_timestamps(timestamps))
+                .await?;
+
+            let Some(Blob::File(file)) = storage.blob("./weow.txt").await? else {
+                panic!("expected a file");
+            };
+
+            assert_eq!(file.last_modified_at, Some(1_000_000_123_456));
+
+            // copying a file keeps its modification time
+            storage.upload("./fluff.txt", UploadRequest::from_file(&file)).await?;
+            let Some(Blob::File(copy)) = storage.blob("./fluff.txt").await? else {
+                panic!("expected a file");
+            };
+
+            assert_eq!(copy.last_modified_at, Some(1_000_000_123_456));
+            Ok(())
+        }
+
+        for_each_blob_concurrent_visits_every_file(storage) {
+            use std::sync::Mutex;
+
+            for name in ["./a.txt", "./b.txt", "./c.txt"] {
+                storage.upload(name, UploadRequest::default().with_data(name)).await?;
+            }
+
+            let names = Mutex::new(Vec::new());
+            storage
+                .for_each_blob_concurrent(None::<&str>, 2, |blob| async {
+                    if let Blob::File(file) = blob {
+                        names.lock().unwrap().push(file.name);
+                    }
+
+                    Ok::<_, io::Error>(())
+                })
+                .await?;
+
+            let mut names = names.into_inner().unwrap();
+            names.sort();
+            assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+
+            let err = storage
+                .for_each_blob_concurrent(None::<&str>, 2, |_| async {
+                    Err(io::Error::new(io::ErrorKind::Other, "weow"))
+                })
+                .await
+                .unwrap_err();
+
+            assert_eq!(err.to_string(), "weow");
+            Ok(())
+        }
+
+        download_many_respects_byte_budget(storage) {
+            for name in ["./a.txt", "./b.txt", "./c.txt"] {
+                storage.upload(name, UploadRequest::default().with_data("weow")).await?;
+            }
+
+            let mut downloads = storage.download_many(["./a.txt", "./missing.txt", "./b.txt"], 2);
+            let mut results = Vec::new();
+            while let Some((key, result)) = downloads.next().await {
+                results.push((key, result?));
+            }
+
+            results.sort();
+            assert_eq!(results, [
+                ("./a.txt", Some(Bytes::from_static(b"weow"))),
+                ("./b.txt", Some(Bytes::from_static(b"weow"))),
+                ("./missing.txt", None),
+            ]);
+
+            let mut downloads = storage.download_many(["./a.txt", "./b.txt", "./c.txt"], 1).with_byte_budget(1);
+            assert!(downloads.next().await.is_some());
+            assert!(downloads.next().await.is_none());
+            assert!(downloads.is_over_budget());
+            assert_eq!(downloads.into_remaining(), ["./b.txt", "./c.txt"]);
+
+            Ok(())
+        }
+
+        tower_service_dispatches_requests(storage) {
+            use remi::{StorageRequest, StorageResponse, TowerService};
+            use tower_service::Service;
+
+            let mut service = TowerService::new(storage);
+            let response = service
+                .call(StorageRequest::Upload("./weow.txt".into(), UploadRequest::default().with_data("weow")))
+                .await?;
+
+            assert!(matches!(response, StorageResponse::Upload));
+            assert!(matches!(
+                service.call(StorageRequest::Exists("./weow.txt".into())).await?,
+                StorageResponse::Exists(true)
+            ));
+
+            match service.call(StorageRequest::Open("./weow.txt".into())).await? {
+                StorageResponse::Open(data) => assert_eq!(data, Some(Bytes::from_static(b"weow"))),
+                response => panic!("expected `StorageResponse::Open`, received {response:?}"),
+            }
+
+            Ok(())
+        }
+
         // open(storage) {
         //     #[cfg(feature = "tracing")]
         //     use tracing_subscriber::prelude::*;
@@ -677,4 +2484,12 @@ mod tests {
         //     Ok(())
         // }
     }
+
+    remi_conformance::testsuite! {
+        setup(storage) {
+            let tempdir = ::tempfile::tempdir().expect("failed to create tempdir");
+            let storage = StorageService::new(&tempdir);
+            storage.init().await.expect("initialization part to be successful");
+        }
+    }
 }
@@ -0,0 +1,136 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Temporary file that is written next to its destination and atomically renamed over it
+/// with [`TempFile::persist`]. If it is dropped before it was persisted (i.e, the future
+/// that was writing it was cancelled), the temporary file is removed and the destination
+/// is left untouched.
+///
+/// Creating and renaming the file is done synchronously so that dropping the guard can
+/// never race with a background task that is still creating or renaming it.
+#[derive(Debug)]
+pub(crate) struct TempFile {
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl TempFile {
+    /// Creates a new, empty temporary file in the same directory as `destination` and
+    /// returns the guard with the opened file, which should be written to with
+    /// [`remi::rt::spawn_blocking`].
+    pub(crate) fn create(destination: &Path) -> io::Result<(TempFile, std::fs::File)> {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+
+        let name = destination
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let path = destination.with_file_name(format!(
+            ".{name}.{}-{nanos:x}-{}.tmp",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let file = std::fs::OpenOptions::new().write(true).create_new(true).open(&path)?;
+        Ok((TempFile { path, persisted: false }, file))
+    }
+
+    /// Returns the path of this temporary file.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Atomically replaces `destination` with this temporary file.
+    pub(crate) fn persist(mut self, destination: &Path) -> io::Result<()> {
+        std::fs::rename(&self.path, destination)?;
+        self.persisted = true;
+
+        Ok(())
+    }
+
+    /// Atomically links this temporary file to `destination`, failing with
+    /// [`io::ErrorKind::AlreadyExists`] if `destination` already exists.
+    pub(crate) fn persist_new(self, destination: &Path) -> io::Result<()> {
+        // the temporary file itself is removed when `self` is dropped
+        std::fs::hard_link(&self.path, destination)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TempFile;
+    use std::io::Write;
+
+    #[test]
+    fn removed_unless_persisted() -> std::io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let destination = tempdir.path().join("weow.txt");
+
+        let (temp, mut file) = TempFile::create(&destination)?;
+        file.write_all(b"weow")?;
+
+        let path = temp.path().to_path_buf();
+        drop(temp);
+
+        assert!(!path.exists());
+        assert!(!destination.exists());
+
+        let (temp, mut file) = TempFile::create(&destination)?;
+        file.write_all(b"weow")?;
+        file.flush()?;
+        temp.persist(&destination)?;
+
+        assert_eq!(std::fs::read(&destination)?, b"weow");
+        assert_eq!(std::fs::read_dir(tempdir.path())?.count(), 1);
+
+        let (temp, _) = TempFile::create(&destination)?;
+        assert_eq!(
+            temp.persist_new(&destination).unwrap_err().kind(),
+            std::io::ErrorKind::AlreadyExists
+        );
+
+        assert_eq!(std::fs::read_dir(tempdir.path())?.count(), 1);
+
+        Ok(())
+    }
+}
@@ -20,6 +20,7 @@
 // SOFTWARE.
 
 use mongodb::options::{ClientOptions, GridFsBucketOptions, ReadConcern, SelectionCriteria, WriteConcern};
+use std::borrow::Cow;
 
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -54,15 +55,144 @@ pub struct StorageConfig {
     #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
     pub chunk_size: Option<u32>,
 
+    /// Maximum size (in bytes) of a file that can be uploaded. Uploads that are larger fail
+    /// with a custom error that holds a [`TooLarge`][remi::TooLarge] error before any bytes are
+    /// sent, which can be retrieved with [`Error::get_custom`][mongodb::error::Error::get_custom].
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub max_object_size: Option<usize>,
+
+    /// Whether if this storage service is read-only. All operations that would modify the
+    /// bucket fail with a [`ReadOnly`][remi::ReadOnly] error before anything is sent.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub read_only: bool,
+
+    /// Whether if a body whose length doesn't match the size that GridFS reported should only
+    /// emit a warning instead of failing with a custom error that
+    /// holds a [`TruncatedRead`][remi::TruncatedRead] error.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warn_on_truncated_reads: bool,
+
+    /// Whether if a file that doesn't exist should be looked up again by listing the files under its parent prefix and
+    /// matching the file name case-insensitively, which emits a warning if it was found under a
+    /// different case. This helps with data that was migrated from case-insensitive filesystems,
+    /// where `Readme.MD` and `README.md` were the same file.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub case_insensitive_lookup: bool,
+
+    /// Maximum amount of bytes (in bytes) that can be buffered in memory across all concurrent
+    /// operations, which is useful for embedded deployments where opening many large files at
+    /// once would otherwise run out of memory. Operations that would go over the limit wait
+    /// until enough bytes were released, unless [`memory_limit_fail_fast`][StorageConfig::memory_limit_fail_fast]
+    /// is set. See [`MemoryLimit`][remi::MemoryLimit] for more information.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub strict_memory_limit: Option<usize>,
+
+    /// Whether if operations that would go over the [`strict_memory_limit`][StorageConfig::strict_memory_limit]
+    /// should fail right away with an error that wraps a [`MemoryLimitExceeded`][remi::MemoryLimitExceeded]
+    /// error instead of waiting.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub memory_limit_fail_fast: bool,
+
+    /// Whether if [`init`][remi::StorageService::init] should verify that the files collection of
+    /// the bucket exists, which fails with a custom error that wraps a [`ResourceMissing`][remi::ResourceMissing]
+    /// error if it doesn't. GridFS buckets are created when the first file is uploaded, so nothing is
+    /// created otherwise. This can also be enabled with the [`REMI_SKIP_PROVISIONING`][remi::SKIP_PROVISIONING_ENV]
+    /// environment variable.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub skip_provisioning: bool,
+
+    /// Where the filename, content type, and user metadata are kept in the documents of the
+    /// files collection, for buckets that were created by other drivers with a non-standard
+    /// layout.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fields: FieldMapping,
+
     /// Database to connect to if [`client_options`][StorageConfig::client_options] was set. It will default
     /// to the default database.
     #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
     pub database: Option<String>,
 
+    /// Prefix that the filenames of files are kept under in the bucket, so that a bucket
+    /// can be shared between storage services.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub prefix: Option<String>,
+
+    /// Whether if the names and paths of returned files should keep the [`prefix`][StorageConfig::prefix].
+    /// By default, the prefix is stripped so that they are the same paths that were given to
+    /// the storage service.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub include_prefix: bool,
+
+    /// Which revision of a file is read when there is more than one file with the same
+    /// filename, since every upload to a filename adds a new revision. This will default
+    /// to [`RevisionPolicy::Latest`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub revision_policy: RevisionPolicy,
+
     /// Bucket name that holds all the GridFS datastore blobs.
     pub bucket: String,
 }
 
+/// Which revision of a file is read by [`open`][remi::StorageService::open],
+/// [`blob`][remi::StorageService::blob], and the other methods that read a single file.
+/// Revisions are ordered by their `uploadDate`, and other revisions can be read with
+/// [`StorageService::open_revision`][crate::StorageService::open_revision].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum RevisionPolicy {
+    /// Reads the most recent revision.
+    #[default]
+    Latest,
+
+    /// Reads the original file, which is the oldest revision.
+    Original,
+}
+
+/// Dotted paths (like `meta.mime`) to the fields of a document in the files collection
+/// that `remi-gridfs` reads and writes. The length and upload date of a file always use
+/// the fields from the GridFS specification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct FieldMapping {
+    /// Path to the filename of a file. Files that are uploaded by `remi-gridfs` also keep the
+    /// standard `filename` field. This will default to `filename`.
+    pub filename: Cow<'static, str>,
+
+    /// Path to the document of user metadata. This will default to `metadata`.
+    pub metadata: Cow<'static, str>,
+
+    /// Path to the content type of a file. If it isn't found, the deprecated top-level
+    /// `contentType` field that older drivers wrote is used instead. This will default
+    /// to `metadata.contentType`.
+    pub content_type: Cow<'static, str>,
+}
+
+impl FieldMapping {
+    /// Field mapping of the GridFS specification.
+    pub const STANDARD: FieldMapping = FieldMapping {
+        filename: Cow::Borrowed("filename"),
+        metadata: Cow::Borrowed("metadata"),
+        content_type: Cow::Borrowed("metadata.contentType"),
+    };
+
+    /// Returns the key of the content type in the document of user metadata, if the
+    /// content type is kept directly in it.
+    pub(crate) fn content_type_key(&self) -> Option<&str> {
+        self.content_type
+            .strip_prefix(self.metadata.as_ref())
+            .and_then(|rest| rest.strip_prefix('.'))
+            .filter(|key| !key.contains('.'))
+    }
+}
+
+impl Default for FieldMapping {
+    fn default() -> Self {
+        FieldMapping::STANDARD
+    }
+}
+
 impl From<StorageConfig> for GridFsBucketOptions {
     fn from(value: StorageConfig) -> Self {
         GridFsBucketOptions::builder()
@@ -29,6 +29,15 @@ mod service;
 pub use config::*;
 pub use service::*;
 
+// Compile-time checks so that a change which makes these types no longer thread-safe
+// fails to build here instead of breaking downstream crates.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    assert_send_sync::<StorageService>();
+    assert_send_sync::<StorageConfig>();
+};
+
 /// Exports the [`mongodb`] crate without specifying the dependency yourself.
 #[cfg(feature = "export-crates")]
 #[cfg_attr(any(noeldoc, docsrs), doc(cfg(feature = "export-crates")))]
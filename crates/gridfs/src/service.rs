@@ -19,18 +19,30 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::StorageConfig;
+use crate::{FieldMapping, RevisionPolicy, StorageConfig};
 use async_trait::async_trait;
 use bytes::{Bytes, BytesMut};
-use futures_util::{AsyncWriteExt, StreamExt};
+use futures_util::{AsyncReadExt, AsyncWriteExt, StreamExt, TryStreamExt};
 use mongodb::{
-    bson::{doc, raw::ValueAccessErrorKind, Bson, Document, RawDocument},
+    bson::{doc, oid::ObjectId, raw::ValueAccessErrorKind, Bson, Document, RawBsonRef, RawDocument, RawDocumentBuf},
+    error::{ErrorKind, GridFsErrorKind, WriteFailure},
     gridfs::GridFsBucket,
-    options::GridFsUploadOptions,
-    Client, Database,
+    options::{GridFsUploadOptions, IndexOptions},
+    Client, Collection, Database, IndexModel,
 };
-use remi::{Blob, File, ListBlobsRequest, UploadRequest};
-use std::{borrow::Cow, collections::HashMap, io, path::Path};
+use remi::{
+    Blob, Capabilities, CollisionStrategy, ContentStream, DeleteSummary, Extensions, File, HealthReport,
+    ListBlobsRequest, Page, ServiceInfo, StorageEvent, UploadRequest, ValidationReport,
+};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    io,
+    path::Path,
+    sync::Arc,
+    time::Instant,
+};
+use tokio::sync::{broadcast, OnceCell};
 use tokio_util::{compat::FuturesAsyncReadCompatExt, io::ReaderStream};
 
 fn value_access_err_to_error(error: mongodb::bson::raw::ValueAccessError) -> mongodb::error::Error {
@@ -51,38 +63,99 @@ fn value_access_err_to_error(error: mongodb::bson::raw::ValueAccessError) -> mon
     }
 }
 
-fn document_to_blob(bytes: Bytes, doc: &RawDocument) -> Result<File, mongodb::error::Error> {
-    let filename = doc.get_str("filename").map_err(value_access_err_to_error)?;
+/// Looks up the value in `doc` at the dotted `path`, like `metadata.contentType`.
+fn lookup<'a>(doc: &'a RawDocument, path: &str) -> Result<Option<RawBsonRef<'a>>, mongodb::error::Error> {
+    let mut doc = doc;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        let Some(value) = doc.get(key)? else {
+            return Ok(None);
+        };
+
+        if keys.peek().is_none() {
+            return Ok(Some(value));
+        }
+
+        match value {
+            RawBsonRef::Document(inner) => doc = inner,
+            _ => return Ok(None),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Looks up the string in `doc` at the dotted `path`.
+fn lookup_str<'a>(doc: &'a RawDocument, path: &str) -> Result<Option<&'a str>, mongodb::error::Error> {
+    match lookup(doc, path)? {
+        Some(RawBsonRef::String(value)) => Ok(Some(value)),
+        Some(value) => Err(mongodb::error::Error::custom(format!(
+            "expected BSON type 'String', actual type for key [{path}] is '{:?}'",
+            value.element_type()
+        ))),
+
+        None => Ok(None),
+    }
+}
+
+/// Returns the filename of `doc` from the field that `fields` points to.
+fn filename_of<'a>(doc: &'a RawDocument, fields: &FieldMapping) -> Result<&'a str, mongodb::error::Error> {
+    lookup_str(doc, &fields.filename)?
+        .ok_or_else(|| mongodb::error::Error::custom(format!("key [{}] was not found", fields.filename)))
+}
+
+fn document_to_blob(bytes: Bytes, doc: &RawDocument, fields: &FieldMapping) -> Result<File, mongodb::error::Error> {
+    let filename = filename_of(doc, fields)?;
     let length = doc.get_i64("length").map_err(value_access_err_to_error)?;
     let created_at = doc.get_datetime("uploadDate").map_err(value_access_err_to_error)?;
-    let metadata = doc.get_document("metadata").map_err(value_access_err_to_error)?;
-
-    let content_type = match metadata.get_str("contentType") {
-        Ok(res) => Some(res),
-        Err(e) => match e.kind {
-            ValueAccessErrorKind::NotPresent => match metadata.get_str("contentType") {
-                Ok(res) => Some(res),
-                Err(e) => return Err(value_access_err_to_error(e)),
-            },
-            _ => return Err(value_access_err_to_error(e)),
-        },
+
+    // files that were uploaded without any metadata don't have a metadata document
+    let metadata = match lookup(doc, &fields.metadata)? {
+        Some(RawBsonRef::Document(metadata)) => Some(metadata),
+        Some(value) => {
+            return Err(mongodb::error::Error::custom(format!(
+                "expected BSON type 'EmbeddedDocument', actual type for key [{}] is '{:?}'",
+                fields.metadata,
+                value.element_type()
+            )))
+        }
+
+        None => None,
+    };
+
+    // drivers that follow older revisions of the GridFS specification keep the
+    // content type in the top-level `contentType` field
+    let content_type = match lookup_str(doc, &fields.content_type)? {
+        Some(ct) => Some(ct),
+        None => lookup_str(doc, "contentType")?,
     };
 
     // Convert `doc` into a HashMap that doesn't contain the properties we expect
     // in a GridFS object.
     //
     // For brevity and compatibility with other storage services, we only use strings
-    // when including metadata.
+    // when including metadata. The full document is kept in the file's extras when
+    // the `bson` feature is enabled.
+    let content_type_key = fields.content_type_key();
     let mut map = HashMap::new();
-    for ref_ in metadata.into_iter() {
+    for ref_ in metadata.into_iter().flatten() {
         let (name, doc) = ref_?;
-        if name != "contentType" {
+        if Some(name) != content_type_key {
             if let Some(s) = doc.as_str() {
                 map.insert(name.into(), s.into());
             }
         }
     }
 
+    #[cfg_attr(not(feature = "bson"), allow(unused_mut))]
+    let mut extras = Extensions::default();
+
+    #[cfg(feature = "bson")]
+    extras.insert(remi::RawMetadata(match metadata {
+        Some(metadata) => Document::try_from(metadata)?,
+        None => Document::new(),
+    }));
+
     Ok(File {
         last_modified_at: None,
         content_type: content_type.map(String::from),
@@ -103,6 +176,16 @@ fn document_to_blob(bytes: Bytes, doc: &RawDocument) -> Result<File, mongodb::er
         },
 
         is_symlink: false,
+
+        // drivers that follow older revisions of the GridFS specification keep the MD5
+        // digest of the contents in the top-level `md5` field
+        checksum: doc.get_str("md5").ok().map(String::from),
+
+        // every revision of a file is a new document, so its id changes with its contents
+        etag: doc.get_object_id("_id").ok().map(|oid| oid.to_hex()),
+        version: None,
+        expires_at: None,
+        extras,
         data: bytes,
         name: filename.to_owned(),
         path: format!("gridfs://{filename}"),
@@ -116,7 +199,37 @@ fn document_to_blob(bytes: Bytes, doc: &RawDocument) -> Result<File, mongodb::er
     })
 }
 
-fn resolve_path(path: &Path) -> Result<String, mongodb::error::Error> {
+/// Returns the sort order of `uploadDate` and how many documents to skip to find the given
+/// revision of a file. Revisions follow the GridFS specification: `0` is the original file,
+/// `1` is the first revision, and `-1` is the most recent revision.
+fn revision_query(revision: i32) -> (i32, u64) {
+    match revision {
+        rev if rev >= 0 => (1, u64::from(rev.unsigned_abs())),
+        rev => (-1, u64::from(rev.unsigned_abs()) - 1),
+    }
+}
+
+/// Escapes the characters of `value` that have a special meaning in regular expressions.
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if "\\^$.|?*+()[]{}".contains(ch) {
+            escaped.push('\\');
+        }
+
+        escaped.push(ch);
+    }
+
+    escaped
+}
+
+/// Returns a regular expression that matches every filename in the `prefix` pseudo-directory.
+fn prefix_pattern(prefix: &str) -> String {
+    format!("^{}/", escape_regex(prefix.trim_end_matches('/')))
+}
+
+/// Resolves `path` into the filename of a file that is kept under `prefix`.
+fn resolve_path(prefix: Option<&str>, path: &Path) -> Result<String, mongodb::error::Error> {
     let path = path.to_str().ok_or_else(|| {
         <mongodb::error::Error as From<io::Error>>::from(io::Error::new(
             io::ErrorKind::InvalidData,
@@ -126,14 +239,114 @@ fn resolve_path(path: &Path) -> Result<String, mongodb::error::Error> {
 
     // trim `./` and `~/` since Gridfs doesn't accept ./ or ~/ as valid paths
     let path = path.trim_start_matches("~/").trim_start_matches("./");
+    match prefix {
+        Some(prefix) => Ok(format!("{prefix}/{path}")),
+        None => Ok(path.to_owned()),
+    }
+}
+
+/// Entrypoints for the fuzz targets in `fuzz/`, which only exist when built with `cargo fuzz`.
+#[cfg(fuzzing)]
+#[doc(hidden)]
+pub mod __fuzzing {
+    use std::path::Path;
+
+    pub fn resolve_path(prefix: Option<&str>, path: &Path) -> Result<String, mongodb::error::Error> {
+        super::resolve_path(prefix, path)
+    }
+}
+
+/// Metadata key that marks files that were created with
+/// [`create_exclusive`][remi::StorageService::create_exclusive], which are unique by filename.
+const EXCLUSIVE_KEY: &str = "remiExclusive";
+
+/// Amount of files in a page that is returned from [`blobs_page`][remi::StorageService::blobs_page].
+/// This is also the cursor's batch size if [`ListBlobsRequest::page_size`] isn't set.
+const LIST_PAGE_SIZE: u32 = 1000;
+
+/// Field mapping that is used for storage services without a [`StorageConfig`].
+static STANDARD_FIELDS: FieldMapping = FieldMapping::STANDARD;
+
+/// What [`StorageService::put`] does with the existing revisions of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnConflict {
+    /// Keeps them, so the upload becomes the newest revision.
+    Revise,
+
+    /// Deletes them after the upload.
+    Replace,
+
+    /// Fails with [`AlreadyExists`][remi::AlreadyExists] if there are any.
+    Fail,
+}
+
+/// Returns an [`AlreadyExists`][remi::AlreadyExists] error for the file `path`.
+fn already_exists(path: &str) -> mongodb::error::Error {
+    mongodb::error::Error::custom(remi::AlreadyExists::new(format!("gridfs://{path}")))
+}
 
-    Ok(path.to_owned())
+/// Checks if an error from closing an upload stream was caused by a duplicate key.
+fn is_duplicate_key(error: &io::Error) -> bool {
+    error
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<mongodb::error::Error>())
+        .is_some_and(|e| matches!(*e.kind, ErrorKind::Write(WriteFailure::WriteError(ref e)) if e.code == 11000))
+}
+
+/// Converts `error` into a [`remi::Error`], which keeps `error` as its source.
+///
+/// * since: 0.11.0
+pub fn into_remi_error(error: mongodb::error::Error) -> remi::Error {
+    let variant = match *error.kind {
+        ErrorKind::Custom(_) if error.get_custom::<remi::AlreadyExists>().is_some() => remi::Error::AlreadyExists,
+        ErrorKind::Custom(_) if error.get_custom::<remi::ResourceMissing>().is_some() => remi::Error::NotFound,
+        ErrorKind::Custom(_) if error.get_custom::<remi::ReadOnly>().is_some() => remi::Error::PermissionDenied,
+        ErrorKind::Custom(_)
+            if error.get_custom::<remi::InvalidKey>().is_some()
+                || error.get_custom::<remi::TooLarge>().is_some()
+                || error.get_custom::<remi::RejectedByPolicy>().is_some() =>
+        {
+            remi::Error::InvalidInput
+        }
+
+        ErrorKind::GridFs(GridFsErrorKind::FileNotFound { .. } | GridFsErrorKind::RevisionNotFound { .. }) => {
+            remi::Error::NotFound
+        }
+
+        ErrorKind::Write(WriteFailure::WriteError(ref e)) if e.code == 11000 => remi::Error::AlreadyExists,
+        ErrorKind::Authentication { .. } => remi::Error::PermissionDenied,
+
+        // 13 is the `Unauthorized` error code, and 50 is `MaxTimeMSExpired`
+        ErrorKind::Command(ref command) if command.code == 13 => remi::Error::PermissionDenied,
+        ErrorKind::Command(ref command) if command.code == 50 => remi::Error::Timeout,
+        ErrorKind::InvalidArgument { .. } => remi::Error::InvalidInput,
+        ErrorKind::Io(ref e) if e.kind() == io::ErrorKind::TimedOut => remi::Error::Timeout,
+        ErrorKind::ServerSelection { .. }
+        | ErrorKind::Io(_)
+        | ErrorKind::DnsResolve { .. }
+        | ErrorKind::ConnectionPoolCleared { .. } => remi::Error::Unavailable,
+
+        _ => remi::Error::Backend,
+    };
+
+    variant(Box::new(error))
+}
+
+/// Checks that `path` can be used as the key of a file, see [`InvalidKey`][remi::InvalidKey].
+fn check_key(path: &Path) -> Result<(), mongodb::error::Error> {
+    remi::InvalidKey::check(path).map_err(mongodb::error::Error::custom)
 }
 
 #[derive(Debug, Clone)]
 pub struct StorageService {
     config: Option<StorageConfig>,
     bucket: GridFsBucket,
+    files: Option<Collection<Document>>,
+    chunks: Option<Collection<RawDocumentBuf>>,
+    exclusive_index: Arc<OnceCell<()>>,
+    events: Option<broadcast::Sender<StorageEvent>>,
+    interceptors: remi::UploadInterceptors,
+    memory: Option<remi::MemoryLimit>,
 }
 
 impl StorageService {
@@ -142,8 +355,19 @@ impl StorageService {
     pub fn new(db: Database, config: StorageConfig) -> StorageService {
         let bucket = db.gridfs_bucket(Some(config.clone().into()));
         StorageService {
+            files: Some(db.collection(&format!("{}.files", config.bucket))),
+            chunks: Some(db.collection(&format!("{}.chunks", config.bucket))),
+            memory: config
+                .strict_memory_limit
+                .map(|limit| match config.memory_limit_fail_fast {
+                    true => remi::MemoryLimit::fail_fast(limit),
+                    false => remi::MemoryLimit::new(limit),
+                }),
             config: Some(config),
             bucket,
+            exclusive_index: Arc::default(),
+            events: None,
+            interceptors: remi::UploadInterceptors::default(),
         }
     }
 
@@ -164,28 +388,903 @@ impl StorageService {
         Ok(Self::from_client(&client, config))
     }
 
-    /// Uses a preconfigured [`GridFsBucket`] as the underlying bucket.
-    pub fn with_bucket(bucket: GridFsBucket) -> StorageService {
-        StorageService { config: None, bucket }
-    }
+    /// Uses a preconfigured [`GridFsBucket`] as the underlying bucket.
+    pub fn with_bucket(bucket: GridFsBucket) -> StorageService {
+        StorageService {
+            config: None,
+            bucket,
+            files: None,
+            chunks: None,
+            exclusive_index: Arc::default(),
+            events: None,
+            interceptors: remi::UploadInterceptors::default(),
+            memory: None,
+        }
+    }
+
+    /// Downloads the contents of a [`File`] into [`File::data`]. Files that are returned from
+    /// [`blobs`][remi::StorageService::blobs] don't include their contents so that listing a
+    /// bucket doesn't need to keep every file in memory at once.
+    pub async fn hydrate(&self, file: &mut File) -> Result<(), mongodb::error::Error> {
+        use remi::StorageService as _;
+
+        if let Some(data) = self.open(self.relative_name(&file.name)).await? {
+            file.data = data;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes a single revision of the file in `path` and returns `false` if that revision
+    /// doesn't exist. Revisions follow the GridFS specification: `0` is the original file,
+    /// `1` is the first revision, and `-1` is the most recent revision.
+    ///
+    /// [`delete`][remi::StorageService::delete] deletes every revision of a file instead.
+    pub async fn delete_revision<P: AsRef<Path>>(&self, path: P, revision: i32) -> Result<bool, mongodb::error::Error> {
+        self.check_writable("delete")?;
+
+        let path = self.resolve_path(path)?;
+        let (sort, skip) = revision_query(revision);
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(file = %path, revision, "deleting revision of file");
+
+        #[cfg(feature = "log")]
+        ::log::info!("deleting revision {} of file [{}]", revision, path);
+
+        let mut cursor = self
+            .bucket
+            .find(doc! { (self.fields().filename.as_ref()): &path })
+            .sort(doc! { "uploadDate": sort })
+            .skip(skip)
+            .limit(1)
+            .await?;
+
+        if !cursor.advance().await? {
+            return Ok(false);
+        }
+
+        let oid = cursor
+            .current()
+            .get_object_id("_id")
+            .map_err(value_access_err_to_error)?;
+
+        self.bucket.delete(Bson::ObjectId(oid)).await?;
+        if self.has_subscribers()
+            && self
+                .bucket
+                .find_one(doc! { (self.fields().filename.as_ref()): &path })
+                .await?
+                .is_none()
+        {
+            self.publish(StorageEvent::Deleted {
+                path: format!("gridfs://{path}"),
+            });
+        }
+
+        Ok(true)
+    }
+
+    /// Opens a single revision of the file in `path` and returns `None` if that revision
+    /// doesn't exist. Revisions follow the GridFS specification: `0` is the original file,
+    /// `1` is the first revision, and `-1` is the most recent revision.
+    ///
+    /// [`open`][remi::StorageService::open] opens the revision that [`StorageConfig::revision_policy`]
+    /// points to instead.
+    pub async fn open_revision<P: AsRef<Path>>(
+        &self,
+        path: P,
+        revision: i32,
+    ) -> Result<Option<Bytes>, mongodb::error::Error> {
+        check_key(path.as_ref())?;
+
+        let path = self.resolve_path(path)?;
+        let (sort, skip) = revision_query(revision);
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(file = %path, revision, "opening revision of file");
+
+        #[cfg(feature = "log")]
+        ::log::info!("opening revision {} of file [{}]", revision, path);
+
+        let mut cursor = self
+            .bucket
+            .find(doc! { (self.fields().filename.as_ref()): &path })
+            .sort(doc! { "uploadDate": sort })
+            .skip(skip)
+            .limit(1)
+            .await?;
+
+        if !cursor.advance().await? {
+            return Ok(None);
+        }
+
+        self.download(&path, cursor.current()).await.map(Some)
+    }
+
+    /// Returns every revision of the file in `path`, from the original file to the most recent
+    /// revision, so the file at index `n` is revision `n` and its [`version`][File::version] is
+    /// `n`. Their contents aren't downloaded, use [`open_revision`][StorageService::open_revision]
+    /// to download the contents of a revision, and [`delete_revision`][StorageService::delete_revision]
+    /// to prune the revisions that aren't needed anymore.
+    pub async fn revisions<P: AsRef<Path>>(&self, path: P) -> Result<Vec<File>, mongodb::error::Error> {
+        check_key(path.as_ref())?;
+
+        let path = self.resolve_path(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(file = %path, "listing revisions of file");
+
+        #[cfg(feature = "log")]
+        ::log::info!("listing revisions of file [{}]", path);
+
+        let mut cursor = self
+            .bucket
+            .find(doc! { (self.fields().filename.as_ref()): &path })
+            .sort(doc! { "uploadDate": 1 })
+            .await?;
+
+        let mut revisions = Vec::new();
+        while cursor.advance().await? {
+            let mut file = self.file_from_document(Bytes::new(), cursor.current())?;
+            file.version = Some(revisions.len().to_string());
+            revisions.push(file);
+        }
+
+        Ok(revisions)
+    }
+
+    /// Registers an [`UploadInterceptor`][remi::UploadInterceptor] that can inspect and reject
+    /// uploads before they are written, which fail with a custom error that holds a
+    /// [`RejectedByPolicy`][remi::RejectedByPolicy] error. Interceptors run in the
+    /// order that they were registered.
+    pub fn with_interceptor<I: remi::UploadInterceptor + 'static>(mut self, interceptor: I) -> StorageService {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Publishes a [`StorageEvent`] to the given channel after every successful upload or
+    /// deletion. Uploads will check if a file with the same name already existed only when
+    /// the channel has receivers, to tell [`StorageEvent::Created`] and [`StorageEvent::Updated`] apart.
+    pub fn with_events(mut self, events: broadcast::Sender<StorageEvent>) -> StorageService {
+        self.events = Some(events);
+        self
+    }
+
+    fn has_subscribers(&self) -> bool {
+        self.events.as_ref().is_some_and(|events| events.receiver_count() > 0)
+    }
+
+    /// Rejects `operation` with a [`ReadOnly`][remi::ReadOnly] error if this storage service is read-only.
+    fn check_writable(&self, operation: &'static str) -> Result<(), mongodb::error::Error> {
+        remi::ReadOnly::check(self.config.as_ref().is_some_and(|config| config.read_only), operation)
+            .map_err(mongodb::error::Error::custom)
+    }
+
+    /// Reserves `bytes` bytes from the [`strict_memory_limit`][StorageConfig::strict_memory_limit],
+    /// which are released once the returned permit is dropped.
+    async fn reserve(&self, bytes: usize) -> Result<Option<remi::MemoryPermit>, mongodb::error::Error> {
+        let Some(ref limit) = self.memory else {
+            return Ok(None);
+        };
+
+        limit
+            .acquire(bytes)
+            .await
+            .map(Some)
+            .map_err(mongodb::error::Error::custom)
+    }
+
+    /// Returns the sort order of `uploadDate` that puts the revision that
+    /// [`StorageConfig::revision_policy`] points to first.
+    fn revision_sort(&self) -> Document {
+        let policy = self
+            .config
+            .as_ref()
+            .map(|config| config.revision_policy)
+            .unwrap_or_default();
+
+        match policy {
+            RevisionPolicy::Latest => doc! { "uploadDate": -1 },
+            RevisionPolicy::Original => doc! { "uploadDate": 1 },
+        }
+    }
+
+    /// Downloads the contents of the file whose document of the files collection is `doc`.
+    async fn download(&self, path: &str, doc: &RawDocument) -> Result<Bytes, mongodb::error::Error> {
+        let length = doc.get_i64("length").map_err(value_access_err_to_error)?;
+        let _permit = self.reserve(usize::try_from(length).unwrap_or_default()).await?;
+        let stream = self
+            .bucket
+            .open_download_stream(Bson::ObjectId(
+                doc.get_object_id("_id").map_err(value_access_err_to_error)?,
+            ))
+            .await?;
+
+        let mut bytes = BytesMut::new();
+        let mut reader = ReaderStream::new(stream.compat());
+        while let Some(raw) = reader.next().await {
+            match raw {
+                Ok(b) => bytes.extend(b),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        self.check_length(path, length, bytes.len())?;
+        Ok(bytes.into())
+    }
+
+    fn publish(&self, event: StorageEvent) {
+        if let Some(ref events) = self.events {
+            // an error means that there are no receivers, which is fine
+            let _ = events.send(event);
+        }
+    }
+
+    /// Checks that the contents of a file that was read matches the `length` of its document, or
+    /// emits a warning instead if [`StorageConfig::warn_on_truncated_reads`] is enabled.
+    #[cfg_attr(not(any(feature = "tracing", feature = "log")), allow(unused_variables))]
+    fn check_length(&self, path: &str, length: i64, actual: usize) -> Result<(), mongodb::error::Error> {
+        let Ok(expected) = u64::try_from(length) else {
+            return Ok(());
+        };
+
+        match remi::TruncatedRead::check(expected, actual) {
+            Ok(()) => Ok(()),
+            Err(err)
+                if self
+                    .config
+                    .as_ref()
+                    .is_some_and(|config| config.warn_on_truncated_reads) =>
+            {
+                #[cfg(feature = "tracing")]
+                ::tracing::warn!(
+                    file = %path,
+                    expected = err.expected,
+                    actual = err.actual,
+                    "contents of file doesn't match its reported length"
+                );
+
+                #[cfg(feature = "log")]
+                ::log::warn!("contents of file [{}] doesn't match its reported length: {}", path, err);
+
+                Ok(())
+            }
+
+            Err(err) => Err(mongodb::error::Error::custom(err)),
+        }
+    }
+
+    /// Uploads `options` into `path`, where `on_conflict` decides what happens to the
+    /// existing revisions of `path`.
+    async fn put(
+        &self,
+        path: &Path,
+        options: UploadRequest,
+        on_conflict: OnConflict,
+    ) -> Result<(), mongodb::error::Error> {
+        self.check_writable("upload")?;
+
+        let exclusive = on_conflict == OnConflict::Fail;
+
+        check_key(path)?;
+
+        remi::TooLarge::check(
+            options.data.len(),
+            self.config.as_ref().and_then(|config| config.max_object_size),
+        )
+        .map_err(mongodb::error::Error::custom)?;
+
+        let _permit = self.reserve(options.data.len()).await?;
+        let path = self.resolve_path(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            file = %path,
+            "uploading file to GridFS..."
+        );
+
+        #[cfg(feature = "log")]
+        ::log::info!("uploading file [{}] to GridFS", path);
+
+        self.interceptors
+            .check(&format!("gridfs://{path}"), &options)
+            .await
+            .map_err(mongodb::error::Error::custom)?;
+
+        if exclusive {
+            self.ensure_exclusive_index().await?;
+            if self
+                .bucket
+                .find_one(doc! { (self.fields().filename.as_ref()): &path })
+                .await?
+                .is_some()
+            {
+                return Err(already_exists(&path));
+            }
+        }
+
+        let event = match self.has_subscribers() {
+            true => Some(StorageEvent::uploaded(
+                format!("gridfs://{path}"),
+                &options,
+                !exclusive
+                    && self
+                        .bucket
+                        .find_one(doc! { (self.fields().filename.as_ref()): &path })
+                        .await?
+                        .is_some(),
+            )),
+
+            false => None,
+        };
+
+        let fields = self.fields();
+        let mut metadata = Document::new();
+
+        // fields that aren't in the standard layout are written once the file was uploaded
+        let mut set = Document::new();
+        if fields.filename != STANDARD_FIELDS.filename {
+            set.insert(fields.filename.as_ref(), &path);
+        }
+
+        #[cfg(feature = "bson")]
+        if let Some(raw) = options.raw_metadata() {
+            metadata.extend(raw.clone());
+        }
+
+        metadata.extend(
+            options
+                .metadata
+                .into_iter()
+                .map(|(key, value)| (key, Bson::String(value))),
+        );
+
+        if let Some(ct) = options.content_type {
+            match fields.content_type_key() {
+                Some(key) => metadata.insert(key, ct),
+                None => set.insert(fields.content_type.as_ref(), ct),
+            };
+        }
+
+        if fields.metadata != STANDARD_FIELDS.metadata {
+            set.insert(fields.metadata.as_ref(), std::mem::take(&mut metadata));
+        }
+
+        if exclusive {
+            metadata.insert(EXCLUSIVE_KEY, true);
+        }
+
+        let opts = GridFsUploadOptions::builder()
+            .chunk_size_bytes(Some(
+                self.config.clone().unwrap_or_default().chunk_size.unwrap_or(255 * 1024),
+            ))
+            .metadata(metadata)
+            .build();
+
+        let mut stream = self.bucket.open_upload_stream(&path).with_options(opts).await?;
+        let id = stream.id().clone();
+        stream.write_all(&options.data[..]).await?;
+
+        // the unique index rejects the file if another exclusive upload created it in the
+        // meantime, and the driver removes the chunks that were already written
+        stream.close().await.map_err(|e| match is_duplicate_key(&e) {
+            true if exclusive => already_exists(&path),
+            _ => e.into(),
+        })?;
+
+        if !set.is_empty() {
+            let Some(ref files) = self.files else {
+                return Err(mongodb::error::Error::custom(
+                    "custom field mappings require the storage service to be created from a `Database`",
+                ));
+            };
+
+            files.update_one(doc! { "_id": &id }, doc! { "$set": set }).await?;
+        }
+
+        // older revisions are only deleted once the new one was fully written, so the
+        // file never disappears in between
+        if on_conflict == OnConflict::Replace {
+            let mut cursor = self
+                .bucket
+                .find(doc! { (self.fields().filename.as_ref()): &path, "_id": { "$ne": &id } })
+                .await?;
+
+            let mut revisions = Vec::new();
+            while cursor.advance().await? {
+                revisions.push(
+                    cursor
+                        .current()
+                        .get_object_id("_id")
+                        .map_err(value_access_err_to_error)?,
+                );
+            }
+
+            for oid in revisions {
+                self.bucket.delete(Bson::ObjectId(oid)).await?;
+            }
+        }
+
+        if let Some(event) = event {
+            self.publish(event);
+        }
+
+        Ok(())
+    }
+
+    /// Creates the unique index that [`create_exclusive`][remi::StorageService::create_exclusive]
+    /// relies on for files that were created with it, once per storage service.
+    async fn ensure_exclusive_index(&self) -> Result<(), mongodb::error::Error> {
+        let Some(ref files) = self.files else {
+            return Err(mongodb::error::Error::custom(
+                "`create_exclusive` requires the storage service to be created from a `Database`",
+            ));
+        };
+
+        self.exclusive_index
+            .get_or_try_init(|| async {
+                let index = IndexModel::builder()
+                    .keys(doc! { "filename": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .name(String::from("remi_exclusive_filename"))
+                            .unique(true)
+                            .partial_filter_expression(doc! { format!("metadata.{EXCLUSIVE_KEY}"): true })
+                            .build(),
+                    )
+                    .build();
+
+                files.create_index(index).await.map(|_| ())
+            })
+            .await
+            .copied()
+    }
+
+    /// Looks for a file next to `path` whose filename only differs in case if
+    /// [`StorageConfig::case_insensitive_lookup`] is enabled.
+    async fn find_case_insensitive(&self, path: &str) -> Result<Option<String>, mongodb::error::Error> {
+        if !self
+            .config
+            .as_ref()
+            .is_some_and(|config| config.case_insensitive_lookup)
+        {
+            return Ok(None);
+        }
+
+        let (parent, name) = match path.rsplit_once('/') {
+            Some((parent, name)) => (format!("{parent}/"), name),
+            None => (String::new(), path),
+        };
+
+        let pattern = match parent.is_empty() {
+            true => String::from("^[^/]+$"),
+            false => format!("{}[^/]+$", prefix_pattern(&parent)),
+        };
+
+        let mut cursor = self
+            .bucket
+            .find(doc! { (self.fields().filename.as_ref()): { "$regex": pattern } })
+            .await?;
+        let mut names = BTreeSet::new();
+        while cursor.advance().await? {
+            let filename = filename_of(cursor.current(), self.fields())?;
+            if let Some(candidate) = filename.strip_prefix(parent.as_str()) {
+                if candidate != name {
+                    names.insert(candidate.to_owned());
+                }
+            }
+        }
+
+        let Some(found) = remi::find_case_insensitive(name, names.iter().map(String::as_str)) else {
+            return Ok(None);
+        };
+
+        let found = format!("{parent}{found}");
+
+        #[cfg(feature = "tracing")]
+        ::tracing::warn!(file = %path, found, "file was found under a different case");
+
+        #[cfg(feature = "log")]
+        ::log::warn!("file [{}] was found under a different case: [{}]", path, found);
+
+        // `resolve_path` prepends the prefix of this storage service again
+        Ok(Some(self.strip_prefix(&found).to_owned()))
+    }
+
+    fn fields(&self) -> &FieldMapping {
+        self.config.as_ref().map_or(&STANDARD_FIELDS, |config| &config.fields)
+    }
+
+    fn resolve_path<P: AsRef<Path>>(&self, path: P) -> Result<String, mongodb::error::Error> {
+        resolve_path(self.prefix(), path.as_ref())
+    }
+
+    /// Returns [`StorageConfig::prefix`] without any leading or trailing slashes, or `None`
+    /// if it isn't set or is empty.
+    fn prefix(&self) -> Option<&str> {
+        self.config
+            .as_ref()
+            .and_then(|config| config.prefix.as_deref())
+            .map(|prefix| {
+                prefix
+                    .trim_start_matches("./")
+                    .trim_start_matches("~/")
+                    .trim_matches('/')
+            })
+            .filter(|prefix| !prefix.is_empty())
+    }
+
+    /// Returns `filename` relative to [`StorageConfig::prefix`], which can be passed back into
+    /// the methods of [`remi::StorageService`].
+    fn strip_prefix<'a>(&self, filename: &'a str) -> &'a str {
+        self.prefix()
+            .and_then(|prefix| filename.strip_prefix(prefix))
+            .and_then(|rest| rest.strip_prefix('/'))
+            .unwrap_or(filename)
+    }
+
+    /// Whether if the names of returned files keep the prefix, see [`StorageConfig::include_prefix`].
+    fn include_prefix(&self) -> bool {
+        self.config.as_ref().is_some_and(|config| config.include_prefix)
+    }
+
+    /// Returns the name of a file that was returned by this storage service relative to
+    /// [`StorageConfig::prefix`].
+    fn relative_name<'a>(&self, name: &'a str) -> &'a str {
+        match self.include_prefix() {
+            true => self.strip_prefix(name),
+            false => name,
+        }
+    }
+
+    /// Converts a document of the files collection into a [`File`] whose name and path are
+    /// relative to the prefix of this storage service, unless [`StorageConfig::include_prefix`]
+    /// is set.
+    fn file_from_document(&self, bytes: Bytes, doc: &RawDocument) -> Result<File, mongodb::error::Error> {
+        let mut file = document_to_blob(bytes, doc, self.fields())?;
+        if !self.include_prefix() {
+            let name = self.strip_prefix(&file.name);
+            if name.len() != file.name.len() {
+                file.path = format!("gridfs://{name}");
+                file.name = name.to_owned();
+            }
+        }
+
+        Ok(file)
+    }
+
+    /// Builds the filter of the files collection that lists every file in the `path` pseudo-directory,
+    /// or in the whole bucket if `path` is `None`, that matches `request`.
+    ///
+    /// Excluded names are compared with the names of the files that are returned, and files
+    /// without an extension are always listed, like with the other storage services.
+    fn list_filter(&self, path: Option<&Path>, request: &ListBlobsRequest) -> Result<Document, mongodb::error::Error> {
+        let filename = self.fields().filename.as_ref();
+        let dir = match path {
+            Some(path) => Some(self.resolve_path(path)?),
+            None => self.prefix().map(String::from),
+        }
+        .filter(|dir| !dir.trim_end_matches('/').is_empty());
+
+        let mut pattern = dir.as_deref().map_or_else(|| String::from("^"), prefix_pattern);
+        if let Some(ref prefix) = request.prefix {
+            pattern.push_str(&escape_regex(prefix));
+        }
+
+        let mut conditions = Vec::new();
+        if pattern != "^" {
+            conditions.push(doc! { (filename): { "$regex": pattern } });
+        }
+
+        if !request.extensions.is_empty() {
+            // only the last extension of the file name is compared, like `Path::extension`
+            let extensions = request
+                .extensions
+                .iter()
+                .map(|ext| escape_regex(ext.trim_start_matches('.')))
+                .collect::<Vec<_>>()
+                .join("|");
+
+            conditions.push(doc! {
+                "$or": [
+                    { (filename): { "$regex": format!("[^/]\\.(?:{extensions})$") } },
+                    { (filename): { "$not": { "$regex": "[^/]\\.[^/.]*$" } } },
+                ]
+            });
+        }
+
+        let mut excluded = Vec::new();
+        for name in &request.excluded {
+            match name.strip_prefix("dir:") {
+                Some(dir) => conditions.push(doc! {
+                    (filename): { "$not": { "$regex": prefix_pattern(&self.resolve_path(self.relative_name(dir))?) } }
+                }),
+
+                None => excluded.push(self.resolve_path(self.relative_name(name))?),
+            }
+        }
+
+        if !excluded.is_empty() {
+            conditions.push(doc! { (filename): { "$nin": excluded } });
+        }
+
+        Ok(match conditions.len() {
+            0 => Document::new(),
+            1 => conditions.remove(0),
+            _ => doc! { "$and": conditions },
+        })
+    }
+
+    /// Lists the files in `path` that match `request` in order of their `_id`, starting after the
+    /// file whose `_id` is the request's continuation token, and returns at most one page of files
+    /// if `paginate` is set.
+    async fn list(
+        &self,
+        path: Option<&Path>,
+        request: &ListBlobsRequest,
+        paginate: bool,
+    ) -> Result<Page<Blob>, mongodb::error::Error> {
+        let batch_size = request.page_size_or(LIST_PAGE_SIZE, u32::MAX);
+        let limit = paginate.then_some(batch_size as usize);
+        let mut filter = self.list_filter(path, request)?;
+        if let Some(token) = request.continuation.as_deref() {
+            let id = ObjectId::parse_str(token).map_err(|e| {
+                mongodb::error::Error::custom(format!("continuation token [{token}] is not valid: {e}"))
+            })?;
+
+            filter.insert("_id", doc! { "$gt": id });
+        }
+
+        // one more file than what fits in the page is fetched to know if there is another page
+        let mut find = self.bucket.find(filter).sort(doc! { "_id": 1 });
+        if let Some(limit) = limit {
+            find = find
+                .limit(i64::try_from(limit + 1).unwrap_or(i64::MAX))
+                .batch_size(batch_size.saturating_add(1));
+        } else {
+            find = find.batch_size(batch_size);
+        }
+
+        // contents aren't downloaded when listing, use `StorageService::hydrate`
+        // to download the contents of a file.
+        let mut cursor = find.await?;
+        let mut blobs = vec![];
+        let mut last = None;
+        let mut fetched = 0;
+        while cursor.advance().await? {
+            fetched += 1;
+
+            let doc = cursor.current();
+            if limit.is_some_and(|limit| blobs.len() == limit) {
+                return Ok(Page::new(blobs, last.map(|id: ObjectId| id.to_hex())).with_requests(1));
+            }
+
+            last = Some(doc.get_object_id("_id").map_err(value_access_err_to_error)?);
+            match self.file_from_document(Bytes::new(), doc) {
+                Ok(blob) => blobs.push(Blob::File(blob)),
+
+                #[cfg(any(feature = "tracing", feature = "log"))]
+                Err(e) => {
+                    #[cfg(feature = "tracing")]
+                    ::tracing::error!(error = %e, "unable to convert to a file");
+
+                    #[cfg(feature = "log")]
+                    ::log::error!("unable to convert to a file: {e}");
+                }
+
+                #[cfg(not(any(feature = "tracing", feature = "log")))]
+                Err(_e) => {}
+            }
+        }
+
+        // every batch after the first one is fetched with a `getMore` command
+        let requests = (fetched as u64).div_ceil(u64::from(batch_size)).max(1);
+        Ok(Page::last(blobs).with_requests(requests))
+    }
+}
+
+#[async_trait]
+impl remi::StorageService for StorageService {
+    type Error = mongodb::error::Error;
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("remi:gridfs")
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.gridfs.init",
+            skip_all,
+            fields(
+                remi.service = "gridfs"
+            )
+        )
+    )]
+    async fn init(&self) -> Result<(), Self::Error> {
+        // GridFS buckets are created by the first upload, so there is nothing to provision; the
+        // files collection can only be verified if the bucket wasn't preconfigured
+        let (Some(config), Some(files)) = (self.config.as_ref(), self.files.as_ref()) else {
+            return Ok(());
+        };
+
+        if !remi::skip_provisioning(config.skip_provisioning) {
+            return Ok(());
+        }
+
+        let namespace = files.namespace();
+        let names = files
+            .client()
+            .database(&namespace.db)
+            .list_collection_names()
+            .filter(doc! { "name": &namespace.coll })
+            .await?;
+
+        if names.is_empty() {
+            return Err(mongodb::error::Error::custom(remi::ResourceMissing::new(
+                "bucket",
+                &config.bucket,
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.gridfs.open",
+            skip_all,
+            fields(
+                remi.service = "gridfs",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        check_key(path.as_ref())?;
+        let path = self.resolve_path(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(file = %path, "opening file");
+
+        #[cfg(feature = "log")]
+        ::log::info!("opening file [{}]", path);
+
+        let mut cursor = self
+            .bucket
+            .find(doc! { (self.fields().filename.as_ref()): &path })
+            .sort(self.revision_sort())
+            .limit(1)
+            .await?;
+        let advanced = cursor.advance().await?;
+        if !advanced {
+            #[cfg(feature = "tracing")]
+            ::tracing::warn!(
+                file = %path,
+                "file doesn't exist in GridFS"
+            );
+
+            #[cfg(feature = "log")]
+            ::log::warn!("file [{}] doesn't exist in GridFS", path);
+
+            return match self.find_case_insensitive(&path).await? {
+                Some(found) => self.open(found).await,
+                None => Ok(None),
+            };
+        }
+
+        self.download(&path, cursor.current()).await.map(Some)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.gridfs.open_range",
+            skip_all,
+            fields(
+                remi.service = "gridfs",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        check_key(path.as_ref())?;
+        let path = self.resolve_path(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(file = %path, start, end = ?end, "opening range of file");
+
+        #[cfg(feature = "log")]
+        ::log::info!("opening range [{start}..{end:?}] of file [{}]", path);
+
+        let mut cursor = self
+            .bucket
+            .find(doc! { (self.fields().filename.as_ref()): &path })
+            .sort(self.revision_sort())
+            .limit(1)
+            .await?;
+
+        if !cursor.advance().await? {
+            #[cfg(feature = "tracing")]
+            ::tracing::warn!(
+                file = %path,
+                "file doesn't exist in GridFS"
+            );
+
+            #[cfg(feature = "log")]
+            ::log::warn!("file [{}] doesn't exist in GridFS", path);
+
+            return match self.find_case_insensitive(&path).await? {
+                Some(found) => self.open_range(found, start, end).await,
+                None => Ok(None),
+            };
+        }
+
+        let doc = cursor.current();
+        let id = doc.get_object_id("_id").map_err(value_access_err_to_error)?;
+        let length = doc.get_i64("length").map_err(value_access_err_to_error)?;
+        let (start, end) = remi::clamp_range(start, end, u64::try_from(length).unwrap_or_default());
+        if start == end {
+            return Ok(Some(Bytes::new()));
+        }
+
+        let _permit = self.reserve(usize::try_from(end - start).unwrap_or(usize::MAX)).await?;
+        let mut bytes = BytesMut::with_capacity(usize::try_from(end - start).unwrap_or_default());
 
-    fn resolve_path<P: AsRef<Path>>(&self, path: P) -> Result<String, mongodb::error::Error> {
-        resolve_path(path.as_ref())
-    }
-}
+        let Some(ref chunks) = self.chunks else {
+            // the chunks collection isn't known for a preconfigured bucket, so the
+            // file is downloaded up to the end of the range instead
+            let mut stream = self.bucket.open_download_stream(Bson::ObjectId(id)).await?.take(end);
+            let mut buffer = Vec::new();
+            stream.read_to_end(&mut buffer).await?;
 
-#[async_trait]
-impl remi::StorageService for StorageService {
-    type Error = mongodb::error::Error;
+            bytes.extend_from_slice(buffer.get(start as usize..).unwrap_or_default());
+            return Ok(Some(bytes.freeze()));
+        };
 
-    fn name(&self) -> Cow<'static, str> {
-        Cow::Borrowed("remi:gridfs")
+        // only the chunks that overlap with the range are fetched
+        let chunk_size = u64::try_from(doc.get_i32("chunkSize").map_err(value_access_err_to_error)?)
+            .unwrap_or_default()
+            .max(1);
+
+        let first = i64::try_from(start / chunk_size).unwrap_or(i64::MAX);
+        let last = i64::try_from((end - 1) / chunk_size).unwrap_or(i64::MAX);
+        let mut cursor = chunks
+            .find(doc! { "files_id": id, "n": { "$gte": first, "$lte": last } })
+            .sort(doc! { "n": 1 })
+            .await?;
+
+        while cursor.advance().await? {
+            let chunk = cursor.current();
+            let n = match chunk.get("n")? {
+                Some(RawBsonRef::Int32(n)) => u64::try_from(n).unwrap_or_default(),
+                Some(RawBsonRef::Int64(n)) => u64::try_from(n).unwrap_or_default(),
+                _ => return Err(mongodb::error::Error::custom("chunk is missing its index")),
+            };
+
+            let data = chunk.get_binary("data").map_err(value_access_err_to_error)?.bytes;
+            let offset = n * chunk_size;
+            let from = usize::try_from(start.saturating_sub(offset)).unwrap_or(usize::MAX);
+            let to = usize::try_from(end - offset).unwrap_or(usize::MAX).min(data.len());
+
+            bytes.extend_from_slice(data.get(from..to).unwrap_or_default());
+        }
+
+        self.check_length(&path, i64::try_from(end - start).unwrap_or(i64::MAX), bytes.len())?;
+        Ok(Some(bytes.freeze()))
     }
 
     #[cfg_attr(
         feature = "tracing",
         tracing::instrument(
-            name = "remi.gridfs.open",
+            name = "remi.gridfs.open_stream",
             skip_all,
             fields(
                 remi.service = "gridfs",
@@ -193,18 +1292,27 @@ impl remi::StorageService for StorageService {
             )
         )
     )]
-    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+    async fn open_stream<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+    ) -> Result<Option<ContentStream<Self::Error>>, Self::Error> {
+        check_key(path.as_ref())?;
         let path = self.resolve_path(path)?;
 
         #[cfg(feature = "tracing")]
-        ::tracing::info!(file = %path, "opening file");
+        ::tracing::info!(file = %path, "opening file as a stream");
 
         #[cfg(feature = "log")]
-        ::log::info!("opening file [{}]", path);
+        ::log::info!("opening file [{}] as a stream", path);
 
-        let mut cursor = self.bucket.find(doc! { "filename": &path }).await?;
-        let advanced = cursor.advance().await?;
-        if !advanced {
+        let mut cursor = self
+            .bucket
+            .find(doc! { (self.fields().filename.as_ref()): &path })
+            .sort(self.revision_sort())
+            .limit(1)
+            .await?;
+
+        if !cursor.advance().await? {
             #[cfg(feature = "tracing")]
             ::tracing::warn!(
                 file = %path,
@@ -214,27 +1322,24 @@ impl remi::StorageService for StorageService {
             #[cfg(feature = "log")]
             ::log::warn!("file [{}] doesn't exist in GridFS", path);
 
-            return Ok(None);
+            return match self.find_case_insensitive(&path).await? {
+                Some(found) => self.open_stream(found).await,
+                None => Ok(None),
+            };
         }
 
-        let doc = cursor.current();
         let stream = self
             .bucket
             .open_download_stream(Bson::ObjectId(
-                doc.get_object_id("_id").map_err(value_access_err_to_error)?,
+                cursor
+                    .current()
+                    .get_object_id("_id")
+                    .map_err(value_access_err_to_error)?,
             ))
             .await?;
 
-        let mut bytes = BytesMut::new();
-        let mut reader = ReaderStream::new(stream.compat());
-        while let Some(raw) = reader.next().await {
-            match raw {
-                Ok(b) => bytes.extend(b),
-                Err(e) => return Err(e.into()),
-            }
-        }
-
-        Ok(Some(bytes.into()))
+        // chunks are read from GridFS as the stream is polled
+        Ok(Some(ReaderStream::new(stream.compat()).map_err(From::from).boxed()))
     }
 
     #[cfg_attr(
@@ -249,11 +1354,13 @@ impl remi::StorageService for StorageService {
         )
     )]
     async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
-        let path = self.resolve_path(path)?;
-        let Some(bytes) = self.open(&path).await? else {
+        check_key(path.as_ref())?;
+        let Some(bytes) = self.open(path.as_ref()).await? else {
             return Ok(None);
         };
 
+        let path = self.resolve_path(path)?;
+
         #[cfg(feature = "tracing")]
         ::tracing::info!(
             file = %path,
@@ -266,13 +1373,20 @@ impl remi::StorageService for StorageService {
         let mut cursor = self
             .bucket
             .find(doc! {
-                "filename": &path,
+                (self.fields().filename.as_ref()): &path,
             })
+            .sort(self.revision_sort())
+            .limit(1)
             .await?;
 
         // has_advanced returns false if there is no entries that have that filename
         let has_advanced = cursor.advance().await?;
         if !has_advanced {
+            // `open` might've found the file under a different case
+            if let Some(found) = self.find_case_insensitive(&path).await? {
+                return self.blob(found).await;
+            }
+
             #[cfg(feature = "tracing")]
             ::tracing::warn!(file = %path, "file doesn't exist");
 
@@ -283,7 +1397,7 @@ impl remi::StorageService for StorageService {
         }
 
         let doc = cursor.current();
-        document_to_blob(bytes, doc).map(|doc| Some(Blob::File(doc)))
+        self.file_from_document(bytes, doc).map(|doc| Some(Blob::File(doc)))
     }
 
     #[cfg_attr(
@@ -299,65 +1413,31 @@ impl remi::StorageService for StorageService {
     async fn blobs<P: AsRef<Path> + Send>(
         &self,
         path: Option<P>,
-        _request: Option<ListBlobsRequest>,
+        request: Option<ListBlobsRequest>,
     ) -> Result<Vec<Blob>, Self::Error> {
-        // TODO(@auguwu): support filtering files, for now we should probably
-        // heavily test this
-        #[allow(unused)]
-        if let Some(path) = path {
-            #[cfg(feature = "tracing")]
-            ::tracing::warn!(
-                file = %path.as_ref().display(),
-                "using blobs() with a given file name is not supported",
-            );
-
-            #[cfg(feature = "log")]
-            ::log::warn!(
-                "using blobs() with a given file name [{}] is not supported",
-                path.as_ref().display()
-            );
-
-            return Ok(vec![]);
-        }
-
-        let mut cursor = self.bucket.find(doc!()).await?;
-        let mut blobs = vec![];
-        while cursor.advance().await? {
-            let doc = cursor.current();
-            let stream = self
-                .bucket
-                .open_download_stream(Bson::ObjectId(
-                    doc.get_object_id("_id").map_err(value_access_err_to_error)?,
-                ))
-                .await?;
-
-            let mut bytes = BytesMut::new();
-            let mut reader = ReaderStream::new(stream.compat());
-            while let Some(raw) = reader.next().await {
-                match raw {
-                    Ok(b) => bytes.extend(b),
-                    Err(e) => return Err(e.into()),
-                }
-            }
-
-            match document_to_blob(bytes.into(), doc) {
-                Ok(blob) => blobs.push(Blob::File(blob)),
-
-                #[cfg(any(feature = "tracing", feature = "log"))]
-                Err(e) => {
-                    #[cfg(feature = "tracing")]
-                    ::tracing::error!(error = %e, "unable to convert to a file");
-
-                    #[cfg(feature = "log")]
-                    ::log::error!("unable to convert to a file: {e}");
-                }
-
-                #[cfg(not(any(feature = "tracing", feature = "log")))]
-                Err(_e) => {}
-            }
-        }
+        let request = request.unwrap_or_default();
+        self.list(path.as_ref().map(AsRef::as_ref), &request, false)
+            .await
+            .map(|page| page.items)
+    }
 
-        Ok(blobs)
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.gridfs.blobs_page",
+            skip_all,
+            fields(
+                remi.service = "gridfs"
+            )
+        )
+    )]
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        request: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        let request = request.unwrap_or_default();
+        self.list(path.as_ref().map(AsRef::as_ref), &request, true).await
     }
 
     #[cfg_attr(
@@ -371,7 +1451,9 @@ impl remi::StorageService for StorageService {
             )
         )
     )]
-    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.check_writable("delete")?;
+
         let path = self.resolve_path(path)?;
 
         #[cfg(feature = "tracing")]
@@ -383,26 +1465,73 @@ impl remi::StorageService for StorageService {
         let mut cursor = self
             .bucket
             .find(doc! {
-                "filename": &path,
+                (self.fields().filename.as_ref()): &path,
             })
             .await?;
 
-        // has_advanced returns false if there is no entries that have that filename
-        let has_advanced = cursor.advance().await?;
-        if !has_advanced {
+        // GridFS keeps every upload of the same filename as a revision, so all of them
+        // need to be deleted or the older revisions would still be returned.
+        let mut revisions = Vec::new();
+        while cursor.advance().await? {
+            revisions.push(
+                cursor
+                    .current()
+                    .get_object_id("_id")
+                    .map_err(value_access_err_to_error)?,
+            );
+        }
+
+        if revisions.is_empty() {
             #[cfg(feature = "tracing")]
             ::tracing::warn!(file = %path, "file doesn't exist");
 
             #[cfg(feature = "log")]
             ::log::warn!("file [{}] doesn't exist", path);
 
-            return Ok(());
+            return Ok(false);
         }
 
-        let doc = cursor.current();
-        let oid = doc.get_object_id("_id").map_err(value_access_err_to_error)?;
+        for oid in revisions {
+            self.bucket.delete(Bson::ObjectId(oid)).await?;
+        }
+
+        self.publish(StorageEvent::Deleted {
+            path: format!("gridfs://{path}"),
+        });
+
+        Ok(true)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.gridfs.delete_prefix",
+            skip_all,
+            fields(
+                remi.service = "gridfs",
+                prefix = %prefix.as_ref().display()
+            )
+        )
+    )]
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        self.check_writable("delete")?;
+
+        let prefix = self.resolve_path(prefix)?;
+        let mut cursor = self
+            .bucket
+            .find(doc! {
+                (self.fields().filename.as_ref()): { "$regex": prefix_pattern(&prefix) },
+            })
+            .await?;
+
+        // every revision of a file has the same filename, which are all deleted by `delete`
+        let mut filenames = BTreeSet::new();
+        while cursor.advance().await? {
+            let filename = filename_of(cursor.current(), self.fields())?;
+            filenames.insert(self.strip_prefix(filename).to_owned());
+        }
 
-        self.bucket.delete(Bson::ObjectId(oid)).await
+        Ok(remi::delete_keys(self, filenames, remi::DEFAULT_DELETE_CONCURRENCY).await)
     }
 
     #[cfg_attr(
@@ -436,37 +1565,401 @@ impl remi::StorageService for StorageService {
         )
     )]
     async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
-        let path = self.resolve_path(path)?;
+        let path = path.as_ref();
+        match options.collision() {
+            None | Some(CollisionStrategy::NewVersion) => self.put(path, options, OnConflict::Revise).await,
+            Some(CollisionStrategy::Overwrite) => self.put(path, options, OnConflict::Replace).await,
+            Some(CollisionStrategy::Error) => self.put(path, options, OnConflict::Fail).await,
+            Some(strategy @ CollisionStrategy::Suffix(_)) => {
+                for candidate in strategy.candidates(path) {
+                    match self.put(&candidate, options.clone(), OnConflict::Fail).await {
+                        Err(e) if e.get_custom::<remi::AlreadyExists>().is_some() => continue,
+                        result => return result,
+                    }
+                }
 
+                Err(already_exists(&self.resolve_path(path)?))
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "remi.gridfs.create_exclusive",
+            skip_all,
+            fields(
+                remi.service = "gridfs",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        self.put(path.as_ref(), options, OnConflict::Fail).await
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.config.as_ref().is_some_and(|config| config.read_only)
+    }
+
+    fn info(&self) -> ServiceInfo {
+        // the bucket's name isn't known for a preconfigured bucket
+        let location = self.files.as_ref().zip(self.config.as_ref()).map(|(files, config)| {
+            let namespace = files.namespace();
+            format!("{}.{}", namespace.db, config.bucket)
+        });
+
+        ServiceInfo::new(self.name())
+            .with_crate_version(env!("CARGO_PKG_VERSION"))
+            .with_location(location)
+            .with_capabilities(Capabilities {
+                range_reads: self.chunks.is_some(),
+                streaming: true,
+                pagination: true,
+                versioning: true,
+                ..Default::default()
+            })
+    }
+
+    async fn debug_validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        if !report.record(
+            "query files collection",
+            &self.bucket.find_one(doc! {}).await,
+            "check that the MongoDB server is reachable and the credentials can read from the database",
+        ) {
+            report.skip("write probe object");
+            report.skip("read probe object");
+            report.skip("delete probe object");
+
+            return report;
+        }
+
+        remi::probe_read_write(self, &mut report).await;
+        report
+    }
+
+    /// Sends a `ping` command to the database, which authenticates the connection that it
+    /// is sent over with the client's credentials. For a preconfigured bucket, whose database
+    /// isn't known, a file is queried instead.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "remi.gridfs.verify_credentials", skip_all)
+    )]
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
         #[cfg(feature = "tracing")]
-        ::tracing::info!(
-            file = %path,
-            "uploading file to GridFS..."
-        );
+        ::tracing::trace!("verifying credentials");
 
         #[cfg(feature = "log")]
-        ::log::info!("uploading file [{}] to GridFS", path);
+        ::log::trace!("verifying credentials");
 
-        let mut metadata = options
-            .metadata
-            .into_iter()
-            .map(|(key, value)| (key, Bson::String(value)))
-            .collect::<Document>();
+        match self.files {
+            Some(ref files) => files
+                .client()
+                .database(&files.namespace().db)
+                .run_command(doc! { "ping": 1 })
+                .await
+                .map(|_| ()),
 
-        if let Some(ct) = options.content_type {
-            metadata.insert("contentType", ct);
+            None => self.bucket.find_one(doc! {}).await.map(|_| ()),
         }
+    }
 
-        let opts = GridFsUploadOptions::builder()
-            .chunk_size_bytes(Some(
-                self.config.clone().unwrap_or_default().chunk_size.unwrap_or(255 * 1024),
-            ))
-            .metadata(metadata)
-            .build();
+    /// Sends a `ping` command and looks up the bucket's files collection, which authenticates
+    /// the connection and needs permission to read the database. For a preconfigured bucket,
+    /// whose database isn't known, a file is queried instead.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "remi.gridfs.healthcheck", skip_all))]
+    async fn healthcheck(&self) -> HealthReport {
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!("performing healthcheck");
 
-        let mut stream = self.bucket.open_upload_stream(path).with_options(opts).await?;
-        stream.write_all(&options.data[..]).await?;
-        stream.close().await.map_err(From::from)
+        #[cfg(feature = "log")]
+        ::log::trace!("performing healthcheck");
+
+        let started = Instant::now();
+        let result = async {
+            match self.files {
+                Some(ref files) => {
+                    let namespace = files.namespace();
+                    let db = files.client().database(&namespace.db);
+
+                    db.run_command(doc! { "ping": 1 }).await?;
+                    db.list_collection_names()
+                        .filter(doc! { "name": &namespace.coll })
+                        .await
+                        .map(|_| ())
+                }
+
+                None => self.bucket.find_one(doc! {}).await.map(|_| ()),
+            }
+        }
+        .await;
+
+        let latency = started.elapsed();
+        let Err(e) = result else {
+            return HealthReport::reachable(latency).with_read(true);
+        };
+
+        match *e.kind {
+            ErrorKind::Authentication { .. } => HealthReport::reachable(latency).with_read(false).with_error(e),
+
+            // 13 is the `Unauthorized` error code
+            ErrorKind::Command(ref command) if command.code == 13 => {
+                HealthReport::reachable(latency).with_read(false).with_error(e)
+            }
+
+            ErrorKind::ServerSelection { .. }
+            | ErrorKind::Io(_)
+            | ErrorKind::DnsResolve { .. }
+            | ErrorKind::ConnectionPoolCleared { .. } => HealthReport::unreachable(latency, e),
+
+            _ => HealthReport::reachable(latency).with_error(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod query_tests {
+    use super::{document_to_blob, into_remi_error, prefix_pattern, revision_query, StorageService};
+    use crate::{FieldMapping, RevisionPolicy, StorageConfig};
+    use bytes::Bytes;
+    use mongodb::{
+        bson::{doc, oid::ObjectId, rawdoc, DateTime},
+        Client,
+    };
+    use remi::ListBlobsRequest;
+    use std::path::Path;
+
+    #[test]
+    fn test_into_remi_error() {
+        let error = into_remi_error(mongodb::error::Error::custom(remi::AlreadyExists::new(
+            "gridfs://weow.txt",
+        )));
+        assert!(matches!(error, remi::Error::AlreadyExists(_)));
+        assert!(error.downcast_ref::<mongodb::error::Error>().is_some());
+
+        let error = into_remi_error(mongodb::error::Error::custom(remi::ReadOnly { operation: "upload" }));
+        assert!(matches!(error, remi::Error::PermissionDenied(_)));
+        assert!(matches!(
+            into_remi_error(mongodb::error::Error::custom("weow")),
+            remi::Error::Backend(_)
+        ));
+    }
+
+    #[test]
+    fn test_revision_query() {
+        assert_eq!(revision_query(0), (1, 0));
+        assert_eq!(revision_query(2), (1, 2));
+        assert_eq!(revision_query(-1), (-1, 0));
+        assert_eq!(revision_query(-3), (-1, 2));
+    }
+
+    #[test]
+    fn test_prefix_pattern() {
+        assert_eq!(prefix_pattern("images"), "^images/");
+        assert_eq!(prefix_pattern("images/"), "^images/");
+        assert_eq!(prefix_pattern("a.b/(c)"), "^a\\.b/\\(c\\)/");
+    }
+
+    #[tokio::test]
+    async fn test_revision_sort() {
+        // the client only connects once it is used
+        let client = Client::with_uri_str("mongodb://127.0.0.1:27017").await.unwrap();
+        let config = StorageConfig {
+            bucket: String::from("fs"),
+            ..Default::default()
+        };
+
+        let storage = StorageService::from_client(&client, config.clone());
+        assert_eq!(storage.revision_sort(), doc! { "uploadDate": -1 });
+
+        let storage = StorageService::from_client(
+            &client,
+            StorageConfig {
+                revision_policy: RevisionPolicy::Original,
+                ..config
+            },
+        );
+
+        assert_eq!(storage.revision_sort(), doc! { "uploadDate": 1 });
+    }
+
+    #[tokio::test]
+    async fn test_list_filter() {
+        // the client only connects once it is used
+        let client = Client::with_uri_str("mongodb://127.0.0.1:27017").await.unwrap();
+        let storage = StorageService::from_client(
+            &client,
+            StorageConfig {
+                bucket: String::from("fs"),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            storage.list_filter(None, &ListBlobsRequest::default()).unwrap(),
+            doc! {}
+        );
+
+        assert_eq!(
+            storage
+                .list_filter(Some(Path::new("./images")), &ListBlobsRequest::default())
+                .unwrap(),
+            doc! { "filename": { "$regex": "^images/" } }
+        );
+
+        let request = ListBlobsRequest::default()
+            .with_prefix(Some("weow."))
+            .exclude(["images/a.png", "dir:images/old"].into_iter());
+
+        assert_eq!(
+            storage.list_filter(Some(Path::new("images")), &request).unwrap(),
+            doc! {
+                "$and": [
+                    { "filename": { "$regex": "^images/weow\\." } },
+                    { "filename": { "$not": { "$regex": "^images/old/" } } },
+                    { "filename": { "$nin": ["images/a.png"] } },
+                ]
+            }
+        );
+
+        let request = ListBlobsRequest::default().with_extensions(["txt"].into_iter());
+        assert_eq!(
+            storage.list_filter(None, &request).unwrap(),
+            doc! {
+                "$or": [
+                    { "filename": { "$regex": "[^/]\\.(?:txt)$" } },
+                    { "filename": { "$not": { "$regex": "[^/]\\.[^/.]*$" } } },
+                ]
+            }
+        );
+
+        // listings are kept under the prefix
+        let storage = StorageService::from_client(
+            &client,
+            StorageConfig {
+                prefix: Some(String::from("charts")),
+                bucket: String::from("fs"),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            storage.list_filter(None, &ListBlobsRequest::default()).unwrap(),
+            doc! { "filename": { "$regex": "^charts/" } }
+        );
+
+        assert_eq!(
+            storage
+                .list_filter(None, &ListBlobsRequest::default().exclude(["weow.txt"].into_iter()))
+                .unwrap(),
+            doc! {
+                "$and": [
+                    { "filename": { "$regex": "^charts/" } },
+                    { "filename": { "$nin": ["charts/weow.txt"] } },
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_document_to_blob_with_field_mapping() {
+        let fields = FieldMapping {
+            filename: "name".into(),
+            metadata: "meta".into(),
+            content_type: "meta.mime".into(),
+        };
+
+        let doc = rawdoc! {
+            "filename": "weow.txt",
+            "name": "images/weow.txt",
+            "length": 4_i64,
+            "uploadDate": DateTime::from_millis(1000),
+            "meta": { "mime": "text/plain", "owner": "noel" },
+        };
+
+        let file = document_to_blob(Bytes::new(), &doc, &fields).unwrap();
+        assert_eq!(file.name, "images/weow.txt");
+        assert_eq!(file.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(file.metadata.len(), 1);
+        assert_eq!(file.metadata.get("owner").map(String::as_str), Some("noel"));
+        assert_eq!(fields.content_type_key(), Some("mime"));
+    }
+
+    #[test]
+    fn test_document_to_blob_with_legacy_content_type() {
+        let doc = rawdoc! {
+            "filename": "weow.png",
+            "length": 4_i64,
+            "uploadDate": DateTime::from_millis(1000),
+            "contentType": "image/png",
+        };
+
+        let file = document_to_blob(Bytes::new(), &doc, &FieldMapping::default()).unwrap();
+        assert_eq!(file.name, "weow.png");
+        assert_eq!(file.content_type.as_deref(), Some("image/png"));
+        assert!(file.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_document_to_blob_with_checksum() {
+        let id = ObjectId::new();
+        let doc = rawdoc! {
+            "_id": id,
+            "filename": "weow.txt",
+            "length": 4_i64,
+            "uploadDate": DateTime::from_millis(1000),
+            "md5": "a3bde2e4a8c0ab87ae4a2e2d2c7fa6e0",
+        };
+
+        let file = document_to_blob(Bytes::new(), &doc, &FieldMapping::default()).unwrap();
+        assert_eq!(file.checksum.as_deref(), Some("a3bde2e4a8c0ab87ae4a2e2d2c7fa6e0"));
+        assert_eq!(file.etag, Some(id.to_hex()));
+    }
+
+    #[tokio::test]
+    async fn test_prefix() {
+        // the client only connects once it is used
+        let client = Client::with_uri_str("mongodb://127.0.0.1:27017").await.unwrap();
+        let config = StorageConfig {
+            prefix: Some(String::from("/charts/")),
+            bucket: String::from("fs"),
+            ..Default::default()
+        };
+
+        let doc = rawdoc! {
+            "filename": "charts/weow/fluff.txt",
+            "length": 4_i64,
+            "uploadDate": DateTime::from_millis(1000),
+        };
+
+        let storage = StorageService::from_client(&client, config.clone());
+        assert_eq!(
+            storage.resolve_path("./weow/fluff.txt").unwrap(),
+            "charts/weow/fluff.txt"
+        );
+
+        let file = storage.file_from_document(Bytes::new(), &doc).unwrap();
+        assert_eq!(file.name, "weow/fluff.txt");
+        assert_eq!(file.path, "gridfs://weow/fluff.txt");
+        assert_eq!(storage.relative_name(&file.name), "weow/fluff.txt");
+
+        // names keep the prefix if it was asked for
+        let storage = StorageService::from_client(
+            &client,
+            StorageConfig {
+                include_prefix: true,
+                ..config
+            },
+        );
+
+        let file = storage.file_from_document(Bytes::new(), &doc).unwrap();
+        assert_eq!(file.name, "charts/weow/fluff.txt");
+        assert_eq!(storage.relative_name(&file.name), "weow/fluff.txt");
     }
 }
 
@@ -580,6 +2073,33 @@ impl remi::StorageService for StorageService {
 //             ));
 //         }
 
+//         async fn delete_removes_every_revision(storage) {
+//             for contents in ["weow", "fluff"] {
+//                 storage.upload("./weow.txt", UploadRequest::default().with_data(contents))
+//                     .await
+//                     .expect("failed to upload revision");
+//             }
+
+//             storage.delete("./weow.txt").await.expect("failed to delete file");
+//             assert!(storage.open("./weow.txt").await.expect("failed to open file").is_none());
+//         }
+
+//         async fn delete_single_revision(storage) {
+//             for contents in ["weow", "fluff", "mooo"] {
+//                 storage.upload("./weow.txt", UploadRequest::default().with_data(contents))
+//                     .await
+//                     .expect("failed to upload revision");
+//             }
+
+//             assert!(storage.delete_revision("./weow.txt", -1).await.expect("failed to delete revision"));
+//             assert!(storage.delete_revision("./weow.txt", 0).await.expect("failed to delete revision"));
+//             assert!(!storage.delete_revision("./weow.txt", 5).await.expect("failed to delete revision"));
+//             assert_eq!(
+//                 storage.open("./weow.txt").await.expect("failed to open file"),
+//                 Some(remi::Bytes::from_static(b"fluff"))
+//             );
+//         }
+
 //         async fn query_single_blob(storage) {
 //             for i in 0..100 {
 //                 let contents: remi::Bytes = format!("{{\"blob\":{i}}}").into();
@@ -0,0 +1,882 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use dashmap::{mapref::entry::Entry, DashMap};
+use remi::{
+    Blob, Capabilities, CollisionStrategy, DeleteSummary, Directory, File, ListBlobsRequest, Page, ServiceInfo,
+    UploadRequest, DEFAULT_DIRECTORY_MARKER,
+};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io,
+    path::Path,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+/// Content type of files that were uploaded without one.
+pub const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Amount of blobs in a page that is returned from [`blobs_page`][remi::StorageService::blobs_page]
+/// if [`ListBlobsRequest::page_size`] isn't set.
+const LIST_PAGE_SIZE: u32 = 1000;
+
+/// Converts a path that was given to a storage service into the key of a pseudo-directory,
+/// where the root of the storage service is an empty string.
+fn prefix(path: &Path) -> io::Result<String> {
+    let Some(path) = path.to_str() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("path [{}] is not valid UTF-8", path.display()),
+        ));
+    };
+
+    let path = path.trim_start_matches("./").trim_start_matches("~/").trim_matches('/');
+    Ok(match path {
+        "." | "~" => "",
+        path => path,
+    }
+    .to_owned())
+}
+
+/// Converts a path that was given to a storage service into the key of a file, see
+/// [`InvalidKey`][remi::InvalidKey].
+fn key(path: &Path) -> io::Result<String> {
+    remi::InvalidKey::check(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    match prefix(path)? {
+        key if key.is_empty() => Err(io::Error::new(io::ErrorKind::InvalidInput, remi::InvalidKey::Empty)),
+        key => Ok(key),
+    }
+}
+
+/// Returns the current time in milliseconds since the Unix epoch.
+fn now() -> Option<u128> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|elapsed| elapsed.as_millis())
+}
+
+fn already_exists(key: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        remi::AlreadyExists::new(format!("memory://{key}")),
+    )
+}
+
+/// Represents an implementation of [`StorageService`][remi::StorageService] that keeps
+/// every file in memory, which is useful to unit test code that is written against remi
+/// without touching the disk or starting containers.
+///
+/// Files are kept with their content type and metadata, and listing blobs only returns
+/// what is directly in the given pseudo-directory, like the local filesystem. Cloning a
+/// [`StorageService`] is cheap and the clones share the same files.
+#[derive(Debug, Clone, Default)]
+pub struct StorageService {
+    files: Arc<DashMap<String, File>>,
+    interceptors: remi::UploadInterceptors,
+    read_only: bool,
+}
+
+impl StorageService {
+    /// Creates a new, empty [`StorageService`].
+    pub fn new() -> StorageService {
+        StorageService::default()
+    }
+
+    /// Registers an [`UploadInterceptor`][remi::UploadInterceptor] that can inspect and reject
+    /// uploads before they are written, which fail with a [`PermissionDenied`][io::ErrorKind::PermissionDenied]
+    /// error that holds a [`RejectedByPolicy`][remi::RejectedByPolicy] error. Interceptors run in the
+    /// order that they were registered.
+    pub fn with_interceptor<I: remi::UploadInterceptor + 'static>(mut self, interceptor: I) -> StorageService {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Makes this storage service read-only, where every operation that would modify its files
+    /// fails with a [`PermissionDenied`][io::ErrorKind::PermissionDenied] error that holds a
+    /// [`ReadOnly`][remi::ReadOnly] error. Clones that aren't read-only can still modify them.
+    pub fn with_read_only(mut self, read_only: bool) -> StorageService {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Returns the amount of files that are kept.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns `true` if no files are kept.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Removes every file, which is useful to reuse a [`StorageService`] between tests.
+    pub fn clear(&self) {
+        self.files.clear();
+    }
+
+    /// Rejects `operation` with a [`ReadOnly`][remi::ReadOnly] error if this storage service is read-only.
+    fn check_writable(&self, operation: &'static str) -> io::Result<()> {
+        remi::ReadOnly::check(self.read_only, operation).map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))
+    }
+
+    /// Writes `options` into `key`, which fails with [`AlreadyExists`][remi::AlreadyExists]
+    /// instead of replacing an existing file if `exclusive` is `true`.
+    async fn write(&self, key: String, options: UploadRequest, exclusive: bool) -> io::Result<()> {
+        self.check_writable("upload")?;
+        self.interceptors
+            .check(&format!("memory://{key}"), &options)
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(%key, "uploading file");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("uploading file [{key}]");
+
+        let timestamps = options.timestamps().unwrap_or_default();
+        let modified = timestamps.modified.or_else(now);
+        let file = File {
+            last_modified_at: modified,
+            content_type: Some(options.content_type.unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_owned())),
+            created_at: timestamps.created.or(modified),
+            metadata: options.metadata,
+            is_symlink: false,
+            checksum: None,
+            etag: None,
+            version: None,
+            expires_at: None,
+            extras: Default::default(),
+            name: key.rsplit('/').next().unwrap_or_default().to_owned(),
+            path: format!("memory://{key}"),
+            size: options.data.len(),
+            data: options.data,
+        };
+
+        // the entry keeps its shard locked, so another upload can't create the file in the meantime
+        match self.files.entry(key) {
+            Entry::Occupied(entry) if exclusive => Err(already_exists(entry.key())),
+            Entry::Occupied(mut entry) => {
+                entry.insert(file);
+                Ok(())
+            }
+
+            Entry::Vacant(entry) => {
+                entry.insert(file);
+                Ok(())
+            }
+        }
+    }
+
+    /// Lists the blobs in `path` in order of their keys, returning at most `limit` blobs.
+    fn list(&self, path: Option<&Path>, options: &ListBlobsRequest, limit: Option<usize>) -> io::Result<Page<Blob>> {
+        let base = match path {
+            Some(path) => match prefix(path)? {
+                dir if dir.is_empty() => dir,
+                dir => format!("{dir}/"),
+            },
+
+            None => String::new(),
+        };
+
+        let name_prefix = options.prefix.as_deref().unwrap_or_default();
+
+        // `DashMap` doesn't keep its keys in any particular order, so the blobs are collected
+        // by their keys to list them the same way every time, which `start_after` relies on
+        let mut blobs = BTreeMap::new();
+        for entry in self.files.iter() {
+            let Some(rest) = entry.key().strip_prefix(base.as_str()) else {
+                continue;
+            };
+
+            if !rest.starts_with(name_prefix) {
+                continue;
+            }
+
+            // keys that are nested deeper are resolved into the pseudo-directory that they are in,
+            // which is also what directory markers are resolved into
+            if let Some((name, _)) = rest.split_once('/') {
+                let key = format!("{base}{name}");
+                if !options.include_dirs || options.is_excluded(format!("dir:{name}")) || blobs.contains_key(&key) {
+                    continue;
+                }
+
+                blobs.insert(
+                    key.clone(),
+                    Blob::Directory(Directory {
+                        created_at: None,
+                        name: name.to_owned(),
+                        path: format!("memory://{key}"),
+                    }),
+                );
+
+                continue;
+            }
+
+            if rest == DEFAULT_DIRECTORY_MARKER || options.is_excluded(rest) {
+                continue;
+            }
+
+            if let Some(ext) = Path::new(rest).extension() {
+                if !options.is_ext_allowed(ext.to_string_lossy()) {
+                    continue;
+                }
+            }
+
+            let mut file = entry.value().clone();
+            if options.metadata_only {
+                file.data = Bytes::new();
+            }
+
+            blobs.insert(entry.key().clone(), Blob::File(file));
+        }
+
+        let mut blobs = blobs.into_iter().filter(|(key, _)| {
+            [&options.start_after, &options.continuation]
+                .into_iter()
+                .flatten()
+                .all(|after| key.as_str() > after.as_str())
+        });
+
+        let Some(limit) = limit else {
+            return Ok(Page::last(blobs.map(|(_, blob)| blob).collect()));
+        };
+
+        let mut items = Vec::new();
+        let mut last = None;
+        for (key, blob) in blobs.by_ref().take(limit) {
+            items.push(blob);
+            last = Some(key);
+        }
+
+        // the key of the last blob in a full page continues the listing after it
+        match blobs.next() {
+            Some(_) => Ok(Page::new(items, last)),
+            None => Ok(Page::last(items)),
+        }
+    }
+}
+
+#[async_trait]
+impl remi::StorageService for StorageService {
+    type Error = io::Error;
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("remi:inmemory")
+    }
+
+    fn info(&self) -> ServiceInfo {
+        ServiceInfo::new(self.name())
+            .with_crate_version(env!("CARGO_PKG_VERSION"))
+            .with_capabilities(Capabilities {
+                range_reads: true,
+                pagination: true,
+                server_side_copy: true,
+                ..Default::default()
+            })
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.open",
+            skip_all,
+            fields(
+                remi.service = "inmemory",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<Bytes>> {
+        let key = key(path.as_ref())?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(%key, "opening file");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("opening file [{key}]");
+
+        Ok(self.files.get(&key).map(|file| file.data.clone()))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.blob",
+            skip_all,
+            fields(
+                remi.service = "inmemory",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<Blob>> {
+        let key = key(path.as_ref())?;
+        if let Some(file) = self.files.get(&key) {
+            return Ok(Some(Blob::File(file.clone())));
+        }
+
+        let dir = format!("{key}/");
+        if !self.files.iter().any(|entry| entry.key().starts_with(&dir)) {
+            return Ok(None);
+        }
+
+        Ok(Some(Blob::Directory(Directory {
+            created_at: None,
+            name: key.rsplit('/').next().unwrap_or_default().to_owned(),
+            path: format!("memory://{key}"),
+        })))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.blobs",
+            skip_all,
+            fields(
+                remi.service = "inmemory"
+            )
+        )
+    )]
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> io::Result<Vec<Blob>> {
+        let options = options.unwrap_or_default();
+        self.list(path.as_ref().map(AsRef::as_ref), &options, None)
+            .map(|page| page.items)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.blobs_page",
+            skip_all,
+            fields(
+                remi.service = "inmemory"
+            )
+        )
+    )]
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> io::Result<Page<Blob>> {
+        let options = options.unwrap_or_default();
+        let limit = options.page_size_or(LIST_PAGE_SIZE, u32::MAX) as usize;
+        self.list(path.as_ref().map(AsRef::as_ref), &options, Some(limit))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.delete",
+            skip_all,
+            fields(
+                remi.service = "inmemory",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<bool> {
+        self.check_writable("delete")?;
+        let key = key(path.as_ref())?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(%key, "deleting file");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("deleting file [{key}]");
+
+        Ok(self.files.remove(&key).is_some())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.delete_prefix",
+            skip_all,
+            fields(
+                remi.service = "inmemory",
+                prefix = %prefix.as_ref().display()
+            )
+        )
+    )]
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> io::Result<DeleteSummary> {
+        self.check_writable("delete")?;
+        let base = match self::prefix(prefix.as_ref())? {
+            dir if dir.is_empty() => dir,
+            dir => format!("{dir}/"),
+        };
+
+        let before = self.files.len();
+        self.files.retain(|key, _| !key.starts_with(&base));
+
+        Ok(DeleteSummary {
+            deleted: before.saturating_sub(self.files.len()) as u64,
+            failed: Vec::new(),
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.copy",
+            skip_all,
+            fields(
+                remi.service = "inmemory",
+                src = %src.as_ref().display(),
+                dst = %dst.as_ref().display()
+            )
+        )
+    )]
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> io::Result<bool> {
+        self.check_writable("copy")?;
+        let from = key(src.as_ref())?;
+        let to = key(dst.as_ref())?;
+
+        // the guard is dropped before inserting, since both keys could be in the same shard
+        let Some(mut file) = self.files.get(&from).map(|file| file.clone()) else {
+            return Ok(false);
+        };
+
+        file.name = to.rsplit('/').next().unwrap_or_default().to_owned();
+        file.path = format!("memory://{to}");
+        file.last_modified_at = now();
+        file.created_at = file.last_modified_at;
+        self.files.insert(to, file);
+
+        Ok(true)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.rename",
+            skip_all,
+            fields(
+                remi.service = "inmemory",
+                from = %from.as_ref().display(),
+                to = %to.as_ref().display()
+            )
+        )
+    )]
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> io::Result<bool> {
+        self.check_writable("rename")?;
+        let from = key(from.as_ref())?;
+        let to = key(to.as_ref())?;
+        if from == to {
+            return Ok(self.files.contains_key(&from));
+        }
+
+        let Some((_, mut file)) = self.files.remove(&from) else {
+            return Ok(false);
+        };
+
+        file.name = to.rsplit('/').next().unwrap_or_default().to_owned();
+        file.path = format!("memory://{to}");
+        self.files.insert(to, file);
+
+        Ok(true)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.exists",
+            skip_all,
+            fields(
+                remi.service = "inmemory",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<bool> {
+        let key = prefix(path.as_ref())?;
+        if key.is_empty() || self.files.contains_key(&key) {
+            return Ok(true);
+        }
+
+        // `key` might be a pseudo-directory instead
+        let dir = format!("{key}/");
+        Ok(self.files.iter().any(|entry| entry.key().starts_with(&dir)))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.upload",
+            skip_all,
+            fields(
+                remi.service = "inmemory",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> io::Result<()> {
+        let path = path.as_ref();
+        match options.collision() {
+            None | Some(CollisionStrategy::Overwrite) => self.write(key(path)?, options, false).await,
+            Some(CollisionStrategy::Error) => self.write(key(path)?, options, true).await,
+            Some(CollisionStrategy::NewVersion) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the in-memory storage service can't keep previous versions of files",
+            )),
+
+            Some(strategy @ CollisionStrategy::Suffix(_)) => {
+                for candidate in strategy.candidates(path) {
+                    match self.write(key(&candidate)?, options.clone(), true).await {
+                        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                        result => return result,
+                    }
+                }
+
+                Err(already_exists(&key(path)?))
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.inmemory.create_exclusive",
+            skip_all,
+            fields(
+                remi.service = "inmemory",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn create_exclusive<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> io::Result<()> {
+        self.write(key(path.as_ref())?, options, true).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StorageService, DEFAULT_CONTENT_TYPE};
+    use bytes::Bytes;
+    use remi::{Blob, CollisionStrategy, ListBlobsRequest, StorageService as _, UploadRequest};
+    use std::{collections::HashMap, io, num::NonZeroU32};
+
+    fn names(blobs: Vec<Blob>) -> Vec<String> {
+        blobs
+            .into_iter()
+            .map(|blob| match blob {
+                Blob::File(file) => file.name,
+                Blob::Directory(dir) => format!("dir:{}", dir.name),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn roundtrip() {
+        let storage = StorageService::new();
+        assert_eq!(storage.open("./weow.txt").await.unwrap(), None);
+        assert!(storage.blob("./weow.txt").await.unwrap().is_none());
+
+        storage
+            .upload(
+                "./weow.txt",
+                UploadRequest::default()
+                    .with_content_type(Some("text/plain"))
+                    .with_metadata(HashMap::from([(String::from("owner"), String::from("noel"))]))
+                    .with_data("weow"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        let Some(Blob::File(file)) = storage.blob("./weow.txt").await.unwrap() else {
+            panic!("expected `weow.txt` to be a file");
+        };
+
+        assert_eq!(file.name, "weow.txt");
+        assert_eq!(file.path, "memory://weow.txt");
+        assert_eq!(file.size, 4);
+        assert_eq!(file.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(file.metadata.get("owner").map(String::as_str), Some("noel"));
+        assert!(file.last_modified_at.is_some());
+
+        storage.upload("./empty", UploadRequest::default()).await.unwrap();
+        let Some(Blob::File(file)) = storage.blob("./empty").await.unwrap() else {
+            panic!("expected `empty` to be a file");
+        };
+
+        assert_eq!(file.content_type.as_deref(), Some(DEFAULT_CONTENT_TYPE));
+
+        assert!(storage.exists("./weow.txt").await.unwrap());
+        storage.delete("./weow.txt").await.unwrap();
+        assert!(!storage.exists("./weow.txt").await.unwrap());
+
+        let err = storage.open("./weow/").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn info() {
+        let info = StorageService::new().info();
+        assert_eq!(info.name, "remi:inmemory");
+        assert_eq!(info.crate_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert!(info.capabilities.pagination);
+        assert!(!info.capabilities.versioning);
+    }
+
+    #[tokio::test]
+    async fn clones_share_files() {
+        let storage = StorageService::new();
+        let clone = storage.clone();
+
+        storage.upload("./weow.txt", UploadRequest::default()).await.unwrap();
+        assert!(clone.exists("./weow.txt").await.unwrap());
+        assert_eq!(clone.len(), 1);
+
+        clone.clear();
+        assert!(storage.is_empty());
+    }
+
+    #[tokio::test]
+    async fn exclusive_uploads() {
+        let storage = StorageService::new();
+        storage
+            .create_exclusive("./lock", UploadRequest::default().with_data("a"))
+            .await
+            .unwrap();
+
+        let err = storage
+            .create_exclusive("./lock", UploadRequest::default().with_data("b"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(err.get_ref().is_some_and(|e| e.is::<remi::AlreadyExists>()));
+        assert_eq!(storage.open("./lock").await.unwrap(), Some(Bytes::from_static(b"a")));
+
+        storage
+            .upload(
+                "./lock",
+                UploadRequest::default()
+                    .with_data("c")
+                    .with_collision(CollisionStrategy::Suffix(1)),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(storage.open("./lock-1").await.unwrap(), Some(Bytes::from_static(b"c")));
+
+        let err = storage
+            .upload(
+                "./lock",
+                UploadRequest::default().with_collision(CollisionStrategy::NewVersion),
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[tokio::test]
+    async fn list_blobs() {
+        let storage = StorageService::new();
+        for path in ["./a.txt", "./b/c.txt", "./b/d.json", "./b/e/f.txt"] {
+            storage
+                .upload(path, UploadRequest::default().with_data("weow"))
+                .await
+                .unwrap();
+        }
+
+        storage.create_dir("./g").await.unwrap();
+
+        assert_eq!(
+            names(storage.blobs(Some("./b"), None).await.unwrap()),
+            ["c.txt", "d.json"]
+        );
+
+        let mut request = ListBlobsRequest::default();
+        request.with_include_dirs(true);
+
+        assert_eq!(
+            names(storage.blobs(None::<&str>, Some(request.clone())).await.unwrap()),
+            ["a.txt", "dir:b", "dir:g"]
+        );
+
+        assert!(storage.blobs(Some("./g"), None).await.unwrap().is_empty());
+        assert!(matches!(storage.blob("./b/e").await.unwrap(), Some(Blob::Directory(_))));
+        assert!(storage.exists("./b/e").await.unwrap());
+
+        let request = request
+            .exclude(["c.txt", "dir:e"].into_iter())
+            .with_extensions(["txt"].into_iter());
+
+        assert!(storage.blobs(Some("./b"), Some(request)).await.unwrap().is_empty());
+
+        let request = ListBlobsRequest::default().with_prefix(Some("d"));
+        assert_eq!(
+            names(storage.blobs(Some("./b"), Some(request)).await.unwrap()),
+            ["d.json"]
+        );
+
+        let request = ListBlobsRequest::default().with_metadata_only(true);
+        for blob in storage.blobs(Some("./b"), Some(request)).await.unwrap() {
+            let Blob::File(file) = blob else {
+                panic!("expected only files");
+            };
+
+            assert!(file.data.is_empty());
+            assert_eq!(file.size, 4);
+        }
+    }
+
+    #[tokio::test]
+    async fn list_pages() {
+        let storage = StorageService::new();
+        for path in ["./a", "./b", "./c", "./d", "./e"] {
+            storage.upload(path, UploadRequest::default()).await.unwrap();
+        }
+
+        let request = ListBlobsRequest::default().with_page_size(NonZeroU32::new(2));
+        let first = storage.blobs_page(None::<&str>, Some(request.clone())).await.unwrap();
+        assert_eq!(first.continuation.as_deref(), Some("b"));
+
+        let second = storage
+            .blobs_page(
+                None::<&str>,
+                Some(request.clone().with_continuation(first.continuation.clone())),
+            )
+            .await
+            .unwrap();
+
+        let third = storage
+            .blobs_page(
+                None::<&str>,
+                Some(request.with_continuation(second.continuation.clone())),
+            )
+            .await
+            .unwrap();
+
+        assert!(third.is_last());
+        assert_eq!(
+            [names(first.items), names(second.items), names(third.items)].concat(),
+            ["a", "b", "c", "d", "e"]
+        );
+
+        let request = ListBlobsRequest::default().with_start_after(Some("c"));
+        assert_eq!(
+            names(storage.blobs(None::<&str>, Some(request)).await.unwrap()),
+            ["d", "e"]
+        );
+    }
+
+    #[tokio::test]
+    async fn copy_and_rename() {
+        let storage = StorageService::new();
+        assert!(!storage.copy("./weow.txt", "./copied.txt").await.unwrap());
+
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        assert!(storage.copy("./weow.txt", "./copied.txt").await.unwrap());
+        assert!(storage.rename("./copied.txt", "./moved/weow.txt").await.unwrap());
+        assert!(!storage.exists("./copied.txt").await.unwrap());
+
+        let Some(Blob::File(file)) = storage.blob("./moved/weow.txt").await.unwrap() else {
+            panic!("expected `moved/weow.txt` to be a file");
+        };
+
+        assert_eq!(file.name, "weow.txt");
+        assert_eq!(file.path, "memory://moved/weow.txt");
+        assert_eq!(file.data, Bytes::from_static(b"weow"));
+    }
+
+    #[tokio::test]
+    async fn delete_prefix() {
+        let storage = StorageService::new();
+        for path in ["./images/a.png", "./images/b/c.png", "./imagesx.png"] {
+            storage.upload(path, UploadRequest::default()).await.unwrap();
+        }
+
+        let summary = storage.delete_prefix("./images").await.unwrap();
+        assert_eq!(summary.deleted, 2);
+        assert!(summary.is_complete());
+        assert!(storage.exists("./imagesx.png").await.unwrap());
+        assert!(!storage.exists("./images").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_only() {
+        let storage = StorageService::new();
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        let storage = storage.clone().with_read_only(true);
+        assert!(storage.is_read_only());
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        let err = storage.delete("./weow.txt").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            err.get_ref().and_then(|e| e.downcast_ref::<remi::ReadOnly>()),
+            Some(&remi::ReadOnly { operation: "delete" })
+        );
+
+        assert!(storage
+            .upload("./fluff.txt", UploadRequest::default().with_data("fluff"))
+            .await
+            .is_err());
+
+        assert!(storage.copy("./weow.txt", "./copied.txt").await.is_err());
+        assert!(storage.rename("./weow.txt", "./renamed.txt").await.is_err());
+        assert!(storage.delete_prefix("./").await.is_err());
+        assert!(storage.create_dir("./fluff").await.is_err());
+        assert_eq!(storage.len(), 1);
+        assert!(storage.exists("./weow.txt").await.unwrap());
+    }
+
+    remi_conformance::testsuite! {
+        setup(storage) {
+            let storage = StorageService::new();
+        }
+    }
+}
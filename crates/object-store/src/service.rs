@@ -0,0 +1,855 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use object_store::{
+    path::Path as Location, Attribute, AttributeValue, Attributes, GetOptions, ObjectMeta, ObjectStore, PutMode,
+    PutOptions, PutPayload,
+};
+use remi::{
+    Blob, Capabilities, CollisionStrategy, Conditional, ContentStream, Directory, File, ListBlobsRequest, ServiceInfo,
+    UploadRequest,
+};
+use std::{borrow::Cow, collections::HashMap, path::Path, sync::Arc};
+
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+/// Name that errors which are created by this crate use as their `store`.
+pub(crate) const STORE: &str = "remi";
+
+/// Creates an [`object_store::Error::Generic`] error from this crate.
+pub(crate) fn generic<E: Into<Box<dyn std::error::Error + Send + Sync + 'static>>>(error: E) -> object_store::Error {
+    object_store::Error::Generic {
+        store: STORE,
+        source: error.into(),
+    }
+}
+
+/// Converts a path that was given to a storage service into the [`Location`] of a
+/// pseudo-directory, where the root of the store is `None`.
+pub(crate) fn prefix(path: &Path) -> object_store::Result<Option<Location>> {
+    let Some(path) = path.to_str() else {
+        return Err(generic(format!("path [{}] is not valid UTF-8", path.display())));
+    };
+
+    let path = path.trim_start_matches("./").trim_start_matches("~/").trim_matches('/');
+
+    match path {
+        "" | "." | "~" => Ok(None),
+        path => Location::parse(path).map(Some).map_err(From::from),
+    }
+}
+
+/// Converts a path that was given to a storage service into the [`Location`] of a file,
+/// see [`InvalidKey`][remi::InvalidKey].
+pub(crate) fn key(path: &Path) -> object_store::Result<Location> {
+    remi::InvalidKey::check(path).map_err(generic)?;
+    prefix(path)?.ok_or_else(|| generic(remi::InvalidKey::Empty))
+}
+
+/// Converts the metadata and attributes of an object into a [`File`].
+fn to_file(meta: ObjectMeta, attributes: &Attributes, data: Bytes) -> File {
+    let mut content_type = None;
+    let mut metadata = HashMap::new();
+    for (attribute, value) in attributes {
+        match attribute {
+            Attribute::ContentType => content_type = Some(value.to_string()),
+            Attribute::Metadata(key) => {
+                metadata.insert(key.to_string(), value.to_string());
+            }
+
+            _ => {}
+        }
+    }
+
+    File {
+        last_modified_at: u128::try_from(meta.last_modified.timestamp_millis()).ok(),
+        content_type,
+        created_at: None,
+        metadata,
+        is_symlink: false,
+        checksum: None,
+        etag: meta.e_tag.map(|etag| etag.trim_matches('"').to_owned()),
+        version: meta.version,
+        expires_at: None,
+        extras: Default::default(),
+        data,
+        name: meta.location.filename().unwrap_or_default().to_owned(),
+        path: format!("object_store://{}", meta.location),
+        size: meta.size,
+    }
+}
+
+/// Represents an implementation of [`StorageService`][remi::StorageService] over any
+/// [`ObjectStore`], so that stores which were configured for the `object_store` ecosystem
+/// can be used by code that was written against remi.
+///
+/// Listing blobs only returns what is directly in the given pseudo-directory, like the
+/// local filesystem. Uploads keep their content type and metadata as [`Attributes`] for
+/// the stores that support them.
+#[derive(Debug, Clone)]
+pub struct StorageService {
+    store: Arc<dyn ObjectStore>,
+    interceptors: remi::UploadInterceptors,
+    read_only: bool,
+}
+
+impl StorageService {
+    /// Creates a new [`StorageService`] over the given [`ObjectStore`].
+    pub fn new<O: ObjectStore>(store: O) -> StorageService {
+        Self::from_arc(Arc::new(store))
+    }
+
+    /// Creates a new [`StorageService`] over an [`ObjectStore`] that is already shared.
+    pub fn from_arc(store: Arc<dyn ObjectStore>) -> StorageService {
+        StorageService {
+            store,
+            interceptors: remi::UploadInterceptors::default(),
+            read_only: false,
+        }
+    }
+
+    /// Registers an [`UploadInterceptor`][remi::UploadInterceptor] that can inspect and reject
+    /// uploads before they are written, which fail with a generic error that holds a
+    /// [`RejectedByPolicy`][remi::RejectedByPolicy] error. Interceptors run in the order that
+    /// they were registered.
+    pub fn with_interceptor<I: remi::UploadInterceptor + 'static>(mut self, interceptor: I) -> StorageService {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Makes this storage service read-only, where every operation that would modify the
+    /// [`ObjectStore`] fails with a generic error that holds a [`ReadOnly`][remi::ReadOnly] error.
+    pub fn with_read_only(mut self, read_only: bool) -> StorageService {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Returns the [`ObjectStore`] that this storage service uses.
+    pub fn store(&self) -> &Arc<dyn ObjectStore> {
+        &self.store
+    }
+
+    /// Rejects `operation` with a [`ReadOnly`][remi::ReadOnly] error if this storage service is read-only.
+    fn check_writable(&self, operation: &'static str) -> object_store::Result<()> {
+        remi::ReadOnly::check(self.read_only, operation).map_err(generic)
+    }
+
+    /// Uploads `options` into `location` with the given [`PutMode`].
+    async fn put(&self, location: &Location, options: UploadRequest, mode: PutMode) -> object_store::Result<()> {
+        self.check_writable("upload")?;
+        self.interceptors
+            .check(&format!("object_store://{location}"), &options)
+            .await
+            .map_err(generic)?;
+
+        let mut attributes = Attributes::new();
+        if let Some(content_type) = options.content_type {
+            attributes.insert(Attribute::ContentType, AttributeValue::from(content_type));
+        }
+
+        for (key, value) in options.metadata {
+            attributes.insert(Attribute::Metadata(Cow::Owned(key)), AttributeValue::from(value));
+        }
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(%location, "uploading object");
+
+        #[cfg(feature = "log")]
+        ::log::info!("uploading object [{location}]");
+
+        let payload = PutPayload::from(options.data);
+        let options = PutOptions {
+            mode,
+            attributes,
+            ..Default::default()
+        };
+
+        match self.store.put_opts(location, payload, options).await {
+            Ok(_) => Ok(()),
+            Err(object_store::Error::AlreadyExists { path, .. }) => Err(object_store::Error::AlreadyExists {
+                source: Box::new(remi::AlreadyExists::new(format!("object_store://{path}"))),
+                path,
+            }),
+
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[async_trait]
+impl remi::StorageService for StorageService {
+    type Error = object_store::Error;
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("remi:object_store")
+    }
+
+    fn info(&self) -> ServiceInfo {
+        ServiceInfo::new(self.name())
+            .with_crate_version(env!("CARGO_PKG_VERSION"))
+            .with_location(Some(self.store.to_string()))
+            .with_capabilities(Capabilities {
+                range_reads: true,
+                streaming: true,
+                server_side_copy: true,
+                ..Default::default()
+            })
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.open",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> object_store::Result<Option<Bytes>> {
+        let location = key(path.as_ref())?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(%location, "opening object");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("opening object [{location}]");
+
+        match self.store.get(&location).await {
+            Ok(result) => result.bytes().await.map(Some),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.open_range",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> object_store::Result<Option<Bytes>> {
+        let location = key(path.as_ref())?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(%location, start, end = ?end, "opening range of object");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("opening range [{start}..{end:?}] of object [{location}]");
+
+        // the size is needed up front since ranges that go past the end of an object are an error
+        let meta = match self.store.head(&location).await {
+            Ok(meta) => meta,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let (start, end) = remi::clamp_range(start, end, meta.size as u64);
+        if start == end {
+            return Ok(Some(Bytes::new()));
+        }
+
+        self.store
+            .get_range(&location, start as usize..end as usize)
+            .await
+            .map(Some)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.open_stream",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open_stream<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+    ) -> object_store::Result<Option<ContentStream<object_store::Error>>> {
+        let location = key(path.as_ref())?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(%location, "opening object as a stream");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("opening object [{location}] as a stream");
+
+        match self.store.get(&location).await {
+            Ok(result) => Ok(Some(result.into_stream())),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.blob",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> object_store::Result<Option<Blob>> {
+        let location = key(path.as_ref())?;
+        let result = match self.store.get(&location).await {
+            Ok(result) => result,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let meta = result.meta.clone();
+        let attributes = result.attributes.clone();
+        let data = result.bytes().await?;
+
+        Ok(Some(Blob::File(to_file(meta, &attributes, data))))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.open_if_none_match",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open_if_none_match<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        etag: &str,
+    ) -> object_store::Result<Option<Conditional>> {
+        let location = key(path.as_ref())?;
+        let options = GetOptions {
+            if_none_match: Some(etag.to_owned()),
+            ..Default::default()
+        };
+
+        let result = match self.store.get_opts(&location, options).await {
+            Ok(result) => result,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(object_store::Error::NotModified { .. }) => return Ok(Some(Conditional::NotModified)),
+            Err(e) => return Err(e),
+        };
+
+        let meta = result.meta.clone();
+        let attributes = result.attributes.clone();
+        let data = result.bytes().await?;
+
+        // object stores compare entity tags exactly, so one that was only quoted differently
+        // still needs to be compared here
+        Ok(Conditional::of(
+            Some(Blob::File(to_file(meta, &attributes, data))),
+            etag,
+        ))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.blobs",
+            skip_all,
+            fields(
+                remi.service = "object_store"
+            )
+        )
+    )]
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> object_store::Result<Vec<Blob>> {
+        let options = options.unwrap_or_default();
+        let prefix = match path {
+            Some(path) => prefix(path.as_ref())?,
+            None => None,
+        };
+
+        let listing = self.store.list_with_delimiter(prefix.as_ref()).await?;
+        let mut blobs = Vec::new();
+        if options.include_dirs {
+            for dir in listing.common_prefixes {
+                let name = dir.filename().unwrap_or_default().to_owned();
+                if options.is_excluded(format!("dir:{name}")) {
+                    continue;
+                }
+
+                blobs.push(Blob::Directory(Directory {
+                    created_at: None,
+                    path: format!("object_store://{dir}"),
+                    name,
+                }));
+            }
+        }
+
+        for meta in listing.objects {
+            if options.is_excluded(meta.location.filename().unwrap_or_default()) {
+                continue;
+            }
+
+            if let Some(ext) = meta.location.extension() {
+                if !options.is_ext_allowed(ext) {
+                    continue;
+                }
+            }
+
+            blobs.push(Blob::File(to_file(meta, &Attributes::new(), Bytes::new())));
+        }
+
+        Ok(blobs)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.delete",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> object_store::Result<bool> {
+        self.check_writable("delete")?;
+        let location = key(path.as_ref())?;
+
+        // most object stores don't say whether if a deletion deleted anything, so the
+        // object needs to be checked first
+        match self.store.head(&location).await {
+            Ok(_) => {}
+            Err(object_store::Error::NotFound { .. }) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+
+        match self.store.delete(&location).await {
+            Ok(()) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.copy",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                src = %src.as_ref().display(),
+                dst = %dst.as_ref().display()
+            )
+        )
+    )]
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> object_store::Result<bool> {
+        self.check_writable("copy")?;
+        let from = key(src.as_ref())?;
+        let to = key(dst.as_ref())?;
+        match self.store.copy(&from, &to).await {
+            Ok(()) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.rename",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                from = %from.as_ref().display(),
+                to = %to.as_ref().display()
+            )
+        )
+    )]
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> object_store::Result<bool> {
+        self.check_writable("rename")?;
+        let from = key(from.as_ref())?;
+        let to = key(to.as_ref())?;
+        if from == to {
+            return self.exists(from.as_ref()).await;
+        }
+
+        match self.store.rename(&from, &to).await {
+            Ok(()) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.exists",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> object_store::Result<bool> {
+        let Some(location) = prefix(path.as_ref())? else {
+            return Ok(true);
+        };
+
+        match self.store.head(&location).await {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => {
+                // `location` might be a pseudo-directory instead
+                let listing = self.store.list_with_delimiter(Some(&location)).await?;
+                Ok(!listing.objects.is_empty() || !listing.common_prefixes.is_empty())
+            }
+
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.upload",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> object_store::Result<()> {
+        let path = path.as_ref();
+        match options.collision() {
+            None | Some(CollisionStrategy::Overwrite) => self.put(&key(path)?, options, PutMode::Overwrite).await,
+            Some(CollisionStrategy::Error) => self.put(&key(path)?, options, PutMode::Create).await,
+            Some(CollisionStrategy::NewVersion) => Err(object_store::Error::NotSupported {
+                source: "object_store can't keep previous versions of objects".into(),
+            }),
+
+            Some(strategy @ CollisionStrategy::Suffix(_)) => {
+                for candidate in strategy.candidates(path) {
+                    match self.put(&key(&candidate)?, options.clone(), PutMode::Create).await {
+                        Err(object_store::Error::AlreadyExists { .. }) => continue,
+                        result => return result,
+                    }
+                }
+
+                let location = key(path)?;
+                Err(object_store::Error::AlreadyExists {
+                    source: Box::new(remi::AlreadyExists::new(format!("object_store://{location}"))),
+                    path: location.to_string(),
+                })
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.object_store.create_exclusive",
+            skip_all,
+            fields(
+                remi.service = "object_store",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> object_store::Result<()> {
+        self.put(&key(path.as_ref())?, options, PutMode::Create).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StorageService;
+    use bytes::Bytes;
+    use object_store::memory::InMemory;
+    use remi::{Blob, CollisionStrategy, Conditional, StorageService as _, UploadRequest};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn roundtrip() {
+        let storage = StorageService::new(InMemory::new());
+        assert_eq!(storage.open("./weow.txt").await.unwrap(), None);
+
+        storage
+            .upload(
+                "./weow.txt",
+                UploadRequest::default()
+                    .with_content_type(Some("text/plain"))
+                    .with_metadata(HashMap::from([(String::from("owner"), String::from("noel"))]))
+                    .with_data("weow"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        let Some(Blob::File(file)) = storage.blob("./weow.txt").await.unwrap() else {
+            panic!("expected `weow.txt` to be a file");
+        };
+
+        assert_eq!(file.name, "weow.txt");
+        assert_eq!(file.size, 4);
+        assert_eq!(file.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(file.metadata.get("owner").map(String::as_str), Some("noel"));
+
+        let etag = file.etag.expect("`weow.txt` should have an entity tag");
+        assert!(matches!(
+            storage.open_if_none_match("./weow.txt", &etag).await.unwrap(),
+            Some(Conditional::NotModified)
+        ));
+
+        assert!(matches!(
+            storage.open_if_none_match("./weow.txt", "\"nope\"").await.unwrap(),
+            Some(Conditional::Modified(file)) if file.data == "weow"
+        ));
+
+        assert!(storage.exists("./weow.txt").await.unwrap());
+        storage.delete("./weow.txt").await.unwrap();
+        assert!(!storage.exists("./weow.txt").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn exclusive_uploads() {
+        let storage = StorageService::new(InMemory::new());
+        storage
+            .create_exclusive("./lock", UploadRequest::default().with_data("a"))
+            .await
+            .unwrap();
+
+        let err = storage
+            .create_exclusive("./lock", UploadRequest::default().with_data("b"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, object_store::Error::AlreadyExists { .. }));
+
+        storage
+            .upload(
+                "./lock",
+                UploadRequest::default()
+                    .with_data("c")
+                    .with_collision(CollisionStrategy::Suffix(1)),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(storage.open("./lock-1").await.unwrap(), Some(Bytes::from_static(b"c")));
+    }
+
+    #[tokio::test]
+    async fn list_blobs() {
+        let storage = StorageService::new(InMemory::new());
+        for path in ["./a.txt", "./b/c.txt", "./b/d.json"] {
+            storage.upload(path, UploadRequest::default()).await.unwrap();
+        }
+
+        let mut names = storage
+            .blobs(Some("./b"), None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|blob| match blob {
+                Blob::File(file) => file.name,
+                Blob::Directory(dir) => dir.name,
+            })
+            .collect::<Vec<_>>();
+
+        names.sort();
+        assert_eq!(names, ["c.txt", "d.json"]);
+
+        let mut request = remi::ListBlobsRequest::default();
+        request.with_include_dirs(true);
+
+        let blobs = storage.blobs(None::<&str>, Some(request)).await.unwrap();
+        assert_eq!(blobs.len(), 2);
+        assert!(blobs
+            .iter()
+            .any(|blob| matches!(blob, Blob::Directory(dir) if dir.name == "b")));
+
+        assert!(blobs
+            .iter()
+            .any(|blob| matches!(blob, Blob::File(file) if file.name == "a.txt")));
+    }
+
+    #[tokio::test]
+    async fn filters_by_extensions() {
+        let storage = StorageService::new(InMemory::new());
+        for path in ["./a.txt", "./b.json", "./c.tar.gz", "./README"] {
+            storage.upload(path, UploadRequest::default()).await.unwrap();
+        }
+
+        let request = remi::ListBlobsRequest::default().with_extensions(["txt", ".gz"].into_iter());
+        let mut names = storage
+            .blobs(None::<&str>, Some(request))
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|blob| match blob {
+                Blob::File(file) => Some(file.name),
+                Blob::Directory(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        names.sort();
+        assert_eq!(names, ["README", "a.txt", "c.tar.gz"]);
+    }
+
+    #[tokio::test]
+    async fn open_range() {
+        let storage = StorageService::new(InMemory::new());
+        assert_eq!(storage.open_range("./weow.txt", 0, None).await.unwrap(), None);
+
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("hello, world"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.open_range("./weow.txt", 7, Some(12)).await.unwrap(),
+            Some(Bytes::from_static(b"world"))
+        );
+
+        assert_eq!(
+            storage.open_range("./weow.txt", 7, None).await.unwrap(),
+            Some(Bytes::from_static(b"world"))
+        );
+
+        assert_eq!(
+            storage.open_range("./weow.txt", 100, None).await.unwrap(),
+            Some(Bytes::new())
+        );
+    }
+
+    #[tokio::test]
+    async fn open_stream() {
+        use futures_util::TryStreamExt;
+
+        let storage = StorageService::new(InMemory::new());
+        assert!(storage.open_stream("./weow.txt").await.unwrap().is_none());
+
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        let chunks = storage
+            .open_stream("./weow.txt")
+            .await
+            .unwrap()
+            .expect("`weow.txt` to exist")
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(chunks.concat(), b"weow");
+    }
+
+    #[tokio::test]
+    async fn copy() {
+        let storage = StorageService::new(InMemory::new());
+        assert!(!storage.copy("./weow.txt", "./copied.txt").await.unwrap());
+
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        assert!(storage.copy("./weow.txt", "./copied.txt").await.unwrap());
+        assert_eq!(
+            storage.open("./copied.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+    }
+
+    #[tokio::test]
+    async fn read_only() {
+        let storage = StorageService::new(InMemory::new());
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        let storage = StorageService::from_arc(storage.store().clone()).with_read_only(true);
+        assert!(storage.is_read_only());
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        let err = storage.delete("./weow.txt").await.unwrap_err();
+        let object_store::Error::Generic { source, .. } = err else {
+            panic!("expected a generic error, received {err:?}");
+        };
+
+        assert_eq!(
+            source.downcast_ref::<remi::ReadOnly>(),
+            Some(&remi::ReadOnly { operation: "delete" })
+        );
+
+        assert!(storage
+            .upload("./fluff.txt", UploadRequest::default().with_data("fluff"))
+            .await
+            .is_err());
+
+        assert!(storage.copy("./weow.txt", "./copied.txt").await.is_err());
+        assert!(storage.rename("./weow.txt", "./renamed.txt").await.is_err());
+        assert!(storage.create_dir("./fluff").await.is_err());
+        assert!(storage.exists("./weow.txt").await.unwrap());
+        assert!(!storage.exists("./fluff.txt").await.unwrap());
+        assert!(!storage.exists("./copied.txt").await.unwrap());
+    }
+}
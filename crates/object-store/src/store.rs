@@ -0,0 +1,566 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::service::generic;
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures_util::{
+    future,
+    stream::{self, BoxStream},
+    FutureExt, StreamExt,
+};
+use object_store::{
+    path::Path as Location, Attribute, AttributeValue, Attributes, GetOptions, GetRange, GetResult, GetResultPayload,
+    ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMode, PutMultipartOpts, PutOptions, PutPayload, PutResult,
+    UploadPart,
+};
+use remi::{Blob, File, ListBlobsRequest, StorageService, UploadRequest};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashSet, VecDeque},
+    fmt::{Debug, Display},
+    ops::Range,
+    sync::Arc,
+};
+
+/// Returns the path that `location` is given to a storage service as, which is relative
+/// to the root of the storage service.
+fn path(location: &Location) -> String {
+    format!("./{location}")
+}
+
+/// Returns the [`Location`] of a blob called `name` that was listed in `directory`. Storage
+/// services either name blobs after their full key (like Amazon S3) or after the last
+/// segment of it (like the local filesystem), so a name with a `/` is taken as a full key.
+fn child(directory: Option<&Location>, name: &str) -> object_store::Result<Location> {
+    let name = name.trim_matches('/');
+    match directory {
+        Some(directory) if !name.contains('/') => Ok(directory.child(name)),
+        _ => Location::parse(name).map_err(From::from),
+    }
+}
+
+/// Converts milliseconds from January 1st, 1970 into a [`DateTime`], where files without
+/// a modification time are treated as modified at the epoch.
+fn last_modified(millis: Option<u128>) -> DateTime<Utc> {
+    millis
+        .and_then(|millis| i64::try_from(millis).ok())
+        .and_then(DateTime::from_timestamp_millis)
+        .unwrap_or_default()
+}
+
+/// Resolves a [`GetRange`] against an object of `len` bytes.
+fn resolve_range(range: &GetRange, len: usize) -> object_store::Result<Range<usize>> {
+    match *range {
+        GetRange::Bounded(ref range) if range.start < range.end && range.start < len => {
+            Ok(range.start..range.end.min(len))
+        }
+
+        GetRange::Offset(offset) if offset < len => Ok(offset..len),
+        GetRange::Suffix(n) => Ok(len.saturating_sub(n)..len),
+        ref range => Err(generic(format!(
+            "range {range:?} can't be read from an object of {len} bytes"
+        ))),
+    }
+}
+
+/// Converts the attributes of an upload into an [`UploadRequest`] with the given data.
+fn to_upload_request(attributes: &Attributes, data: Bytes) -> UploadRequest {
+    let mut request = UploadRequest::default().with_data(data);
+    for (attribute, value) in attributes {
+        match attribute {
+            Attribute::ContentType => request.content_type = Some(value.to_string()),
+            Attribute::Metadata(key) => {
+                request.metadata.insert(key.to_string(), value.to_string());
+            }
+
+            _ => {}
+        }
+    }
+
+    request
+}
+
+/// Returns the error for an object at `location` that doesn't exist.
+fn not_found(location: &Location) -> object_store::Error {
+    object_store::Error::NotFound {
+        path: location.to_string(),
+        source: "object was not found".into(),
+    }
+}
+
+/// Represents an implementation of [`ObjectStore`] over any remi
+/// [`StorageService`][remi::StorageService], so that code which was written against
+/// `object_store` can use storage services from remi.
+///
+/// Some things don't map onto remi and are handled like so:
+///
+/// - Listing walks pseudo-directories one at a time with [`blobs`][remi::StorageService::blobs],
+///   since storage services don't agree on whether listing is recursive.
+/// - Multipart uploads are buffered in memory and uploaded at once when they complete.
+/// - Conditional requests and [`PutMode::Update`] aren't supported.
+/// - Objects don't have an ETag.
+pub struct RemiStore<S> {
+    service: Arc<S>,
+}
+
+impl<S> RemiStore<S> {
+    /// Creates a new [`RemiStore`] over the given storage service.
+    pub fn new(service: S) -> RemiStore<S> {
+        Self::from_arc(Arc::new(service))
+    }
+
+    /// Creates a new [`RemiStore`] over a storage service that is already shared.
+    pub fn from_arc(service: Arc<S>) -> RemiStore<S> {
+        RemiStore { service }
+    }
+
+    /// Returns the storage service that this store uses.
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+}
+
+impl<S> Clone for RemiStore<S> {
+    fn clone(&self) -> Self {
+        RemiStore {
+            service: self.service.clone(),
+        }
+    }
+}
+
+impl<S: Debug> Debug for RemiStore<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemiStore").field("service", &self.service).finish()
+    }
+}
+
+impl<S: StorageService> Display for RemiStore<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RemiStore({})", self.service.name())
+    }
+}
+
+impl<S> RemiStore<S>
+where
+    S: StorageService + Debug + 'static,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    /// Uploads `request` into `location` only if nothing exists there yet.
+    async fn create(&self, location: &Location, request: UploadRequest) -> object_store::Result<()> {
+        let path = path(location);
+        match self.service.create_exclusive(&path, request).await {
+            Ok(()) => Ok(()),
+
+            // storage services don't share an error type, so this checks if it
+            // failed because something was already there
+            Err(e) => match self.service.exists(&path).await {
+                Ok(true) => Err(object_store::Error::AlreadyExists {
+                    path: location.to_string(),
+                    source: Box::new(e),
+                }),
+
+                _ => Err(generic(e)),
+            },
+        }
+    }
+
+    /// Lists the objects and pseudo-directories that are directly in `directory`.
+    async fn list_directory(&self, directory: Option<&Location>) -> object_store::Result<ListResult> {
+        let mut request = ListBlobsRequest::default();
+        request.with_include_dirs(true);
+
+        let blobs = self
+            .service
+            .blobs(directory.map(path), Some(request))
+            .await
+            .map_err(generic)?;
+
+        let mut listing = ListResult {
+            common_prefixes: Vec::new(),
+            objects: Vec::new(),
+        };
+
+        for blob in blobs {
+            match blob {
+                Blob::File(file) => listing.objects.push(ObjectMeta {
+                    location: child(directory, &file.name)?,
+                    last_modified: last_modified(file.last_modified_at),
+                    size: file.size,
+                    e_tag: None,
+                    version: file.version,
+                }),
+
+                Blob::Directory(dir) => listing.common_prefixes.push(child(directory, &dir.name)?),
+            }
+        }
+
+        Ok(listing)
+    }
+}
+
+#[async_trait]
+impl<S> ObjectStore for RemiStore<S>
+where
+    S: StorageService + Debug + 'static,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    async fn put_opts(
+        &self,
+        location: &Location,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        let request = to_upload_request(&opts.attributes, payload.into());
+        match opts.mode {
+            PutMode::Overwrite => self.service.upload(path(location), request).await.map_err(generic)?,
+
+            PutMode::Create => self.create(location, request).await?,
+            PutMode::Update(_) => {
+                return Err(object_store::Error::NotSupported {
+                    source: "remi storage services don't support conditional updates".into(),
+                })
+            }
+        }
+
+        Ok(PutResult {
+            e_tag: None,
+            version: None,
+        })
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Location,
+        opts: PutMultipartOpts,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        Ok(Box::new(BufferedUpload {
+            store: self.clone(),
+            location: location.clone(),
+            attributes: opts.attributes,
+            parts: Vec::new(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Location, options: GetOptions) -> object_store::Result<GetResult> {
+        if options.if_match.is_some()
+            || options.if_none_match.is_some()
+            || options.if_modified_since.is_some()
+            || options.if_unmodified_since.is_some()
+            || options.version.is_some()
+        {
+            return Err(object_store::Error::NotSupported {
+                source: "remi storage services don't support conditional requests".into(),
+            });
+        }
+
+        let Some(Blob::File(file)) = self.service.blob(path(location)).await.map_err(generic)? else {
+            return Err(not_found(location));
+        };
+
+        let File {
+            last_modified_at,
+            content_type,
+            metadata,
+            version,
+            data,
+            ..
+        } = file;
+
+        let range = match options.range {
+            Some(ref range) => resolve_range(range, data.len())?,
+            None => 0..data.len(),
+        };
+
+        let mut attributes = Attributes::new();
+        if let Some(content_type) = content_type {
+            attributes.insert(Attribute::ContentType, AttributeValue::from(content_type));
+        }
+
+        for (key, value) in metadata {
+            attributes.insert(Attribute::Metadata(Cow::Owned(key)), AttributeValue::from(value));
+        }
+
+        let body = match options.head {
+            true => Bytes::new(),
+            false => data.slice(range.clone()),
+        };
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(future::ready(Ok(body))).boxed()),
+            meta: ObjectMeta {
+                location: location.clone(),
+                last_modified: last_modified(last_modified_at),
+                size: data.len(),
+                e_tag: None,
+                version,
+            },
+            range,
+            attributes,
+        })
+    }
+
+    async fn delete(&self, location: &Location) -> object_store::Result<()> {
+        self.service.delete(path(location)).await.map(|_| ()).map_err(generic)
+    }
+
+    fn list(&self, prefix: Option<&Location>) -> BoxStream<'_, object_store::Result<ObjectMeta>> {
+        struct Walk {
+            directories: VecDeque<Option<Location>>,
+            objects: VecDeque<ObjectMeta>,
+            seen: HashSet<Location>,
+        }
+
+        let walk = Walk {
+            directories: VecDeque::from([prefix.cloned()]),
+            objects: VecDeque::new(),
+            seen: HashSet::new(),
+        };
+
+        stream::unfold(walk, move |mut walk| async move {
+            loop {
+                if let Some(object) = walk.objects.pop_front() {
+                    return Some((Ok(object), walk));
+                }
+
+                let directory = walk.directories.pop_front()?;
+                match self.list_directory(directory.as_ref()).await {
+                    Ok(listing) => {
+                        // storage services that list recursively return objects
+                        // again when their pseudo-directories are walked
+                        for object in listing.objects {
+                            if walk.seen.insert(object.location.clone()) {
+                                walk.objects.push_back(object);
+                            }
+                        }
+
+                        for prefix in listing.common_prefixes {
+                            if walk.seen.insert(prefix.clone()) {
+                                walk.directories.push_back(Some(prefix));
+                            }
+                        }
+                    }
+
+                    Err(e) => {
+                        walk.directories.clear();
+                        return Some((Err(e), walk));
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Location>) -> object_store::Result<ListResult> {
+        let mut listing = self.list_directory(prefix).await?;
+
+        // storage services that list recursively return objects that are nested deeper
+        // than `prefix`, which are folded into the pseudo-directory that they're in
+        let depth = prefix.map_or(0, |prefix| prefix.parts().count()) + 1;
+        let mut prefixes = listing.common_prefixes.drain(..).collect::<BTreeSet<_>>();
+        listing.objects.retain(|object| {
+            let parts = object.location.parts().collect::<Vec<_>>();
+            if parts.len() > depth {
+                prefixes.insert(parts.into_iter().take(depth).collect());
+                return false;
+            }
+
+            true
+        });
+
+        listing.common_prefixes = prefixes.into_iter().collect();
+        Ok(listing)
+    }
+
+    async fn copy(&self, from: &Location, to: &Location) -> object_store::Result<()> {
+        match self.service.copy(path(from), path(to)).await.map_err(generic)? {
+            true => Ok(()),
+            false => Err(not_found(from)),
+        }
+    }
+
+    async fn copy_if_not_exists(&self, from: &Location, to: &Location) -> object_store::Result<()> {
+        let Some(Blob::File(file)) = self.service.blob(path(from)).await.map_err(generic)? else {
+            return Err(not_found(from));
+        };
+
+        self.create(to, file.to_upload_request()).await
+    }
+}
+
+/// [`MultipartUpload`] that buffers every part in memory and uploads them at once with
+/// [`upload`][remi::StorageService::upload], since remi doesn't chunk uploads.
+struct BufferedUpload<S> {
+    store: RemiStore<S>,
+    location: Location,
+    attributes: Attributes,
+    parts: Vec<PutPayload>,
+}
+
+impl<S: Debug> Debug for BufferedUpload<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferedUpload")
+            .field("store", &self.store)
+            .field("location", &self.location)
+            .field("parts", &self.parts.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<S> MultipartUpload for BufferedUpload<S>
+where
+    S: StorageService + Debug + 'static,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.parts.push(data);
+        future::ready(Ok(())).boxed()
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        let payload = self.parts.drain(..).flatten().collect::<PutPayload>();
+        let request = to_upload_request(&self.attributes, payload.into());
+        self.store
+            .service
+            .upload(path(&self.location), request)
+            .await
+            .map_err(generic)?;
+
+        Ok(PutResult {
+            e_tag: None,
+            version: None,
+        })
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        self.parts.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RemiStore;
+    use bytes::Bytes;
+    use futures_util::TryStreamExt;
+    use object_store::{
+        memory::InMemory, path::Path as Location, GetOptions, GetRange, ObjectStore, PutMode, PutPayload,
+    };
+
+    fn store() -> RemiStore<crate::StorageService> {
+        RemiStore::new(crate::StorageService::new(InMemory::new()))
+    }
+
+    #[tokio::test]
+    async fn get_and_put() {
+        let store = store();
+        let location = Location::from("a/weow.txt");
+        assert!(matches!(
+            store.get(&location).await,
+            Err(object_store::Error::NotFound { .. })
+        ));
+
+        store
+            .put(&location, PutPayload::from_static(b"weow fluff"))
+            .await
+            .unwrap();
+        let result = store.get(&location).await.unwrap();
+        assert_eq!(result.meta.size, 10);
+        assert_eq!(result.bytes().await.unwrap(), Bytes::from_static(b"weow fluff"));
+
+        let options = GetOptions {
+            range: Some(GetRange::Bounded(5..100)),
+            ..Default::default()
+        };
+
+        let result = store.get_opts(&location, options).await.unwrap();
+        assert_eq!(result.range, 5..10);
+        assert_eq!(result.bytes().await.unwrap(), Bytes::from_static(b"fluff"));
+
+        assert_eq!(
+            store.get_range(&location, 0..4).await.unwrap(),
+            Bytes::from_static(b"weow")
+        );
+
+        let err = store
+            .put_opts(&location, PutPayload::from_static(b"b"), PutMode::Create.into())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, object_store::Error::AlreadyExists { .. }));
+
+        store.delete(&location).await.unwrap();
+        assert!(store.head(&location).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn listing() {
+        let store = store();
+        for location in ["a.txt", "b/c.txt", "b/d/e.txt"] {
+            store
+                .put(&Location::from(location), PutPayload::from_static(b"weow"))
+                .await
+                .unwrap();
+        }
+
+        let mut locations = store
+            .list(None)
+            .map_ok(|meta| meta.location.to_string())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        locations.sort();
+        assert_eq!(locations, ["a.txt", "b/c.txt", "b/d/e.txt"]);
+
+        let listing = store.list_with_delimiter(Some(&Location::from("b"))).await.unwrap();
+        assert_eq!(listing.common_prefixes, [Location::from("b/d")]);
+        assert_eq!(
+            listing
+                .objects
+                .iter()
+                .map(|meta| meta.location.to_string())
+                .collect::<Vec<_>>(),
+            ["b/c.txt"]
+        );
+    }
+
+    #[tokio::test]
+    async fn multipart_and_copy() {
+        let store = store();
+        let location = Location::from("weow.txt");
+
+        let mut upload = store.put_multipart(&location).await.unwrap();
+        upload.put_part(PutPayload::from_static(b"weow ")).await.unwrap();
+        upload.put_part(PutPayload::from_static(b"fluff")).await.unwrap();
+        upload.complete().await.unwrap();
+
+        let copy = Location::from("copy.txt");
+        store.copy_if_not_exists(&location, &copy).await.unwrap();
+        assert!(store.copy_if_not_exists(&location, &copy).await.is_err());
+        assert_eq!(
+            store.get(&copy).await.unwrap().bytes().await.unwrap(),
+            Bytes::from_static(b"weow fluff")
+        );
+    }
+}
@@ -20,13 +20,18 @@
 // SOFTWARE.
 
 use aws_sdk_s3::{
+    error::ProvideErrorMetadata,
     operation::{
-        create_bucket::CreateBucketError, delete_object::DeleteObjectError, get_object::GetObjectError,
-        head_bucket::HeadBucketError, head_object::HeadObjectError, list_buckets::ListBucketsError,
-        list_objects_v2::ListObjectsV2Error, put_object::PutObjectError,
+        copy_object::CopyObjectError, create_bucket::CreateBucketError, delete_object::DeleteObjectError,
+        delete_objects::DeleteObjectsError, get_bucket_versioning::GetBucketVersioningError,
+        get_object::GetObjectError, get_object_tagging::GetObjectTaggingError, head_bucket::HeadBucketError,
+        head_object::HeadObjectError, list_buckets::ListBucketsError, list_objects_v2::ListObjectsV2Error,
+        put_object::PutObjectError,
     },
+    presigning::PresigningConfigError,
     primitives::SdkBody,
 };
+use aws_sdk_sts::operation::get_caller_identity::GetCallerIdentityError;
 use aws_smithy_runtime_api::{
     client::result::SdkError,
     client::result::{ConstructionFailure, DispatchFailure, ResponseError, TimeoutError},
@@ -68,8 +73,9 @@ pub enum Error {
     ListBuckets(ListBucketsError),
 
     /// Amazon S3 was unable to create the bucket for some reason, this will never hit the
-    /// [`CreateBucketError::BucketAlreadyExists`] or [`CreateBucketError::BucketAlreadyOwnedByYou`]
-    /// variants, something might've been unhandled and is probably isn't your fault.
+    /// [`CreateBucketError::BucketAlreadyOwnedByYou`] variant since a bucket that was created
+    /// by another caller in the meantime is treated as a success. [`CreateBucketError::BucketAlreadyExists`]
+    /// means that the bucket name was taken by another AWS account.
     ///
     /// * this would be thrown from the [`StorageService::init`][remi::StorageService::init]
     ///   trait method
@@ -104,6 +110,11 @@ pub enum Error {
     /// * this would be thrown from the [`StorageService::delete`][remi::StorageService::delete] trait method.
     DeleteObject(DeleteObjectError),
 
+    /// Amazon S3 was unable to delete a batch of objects from the service.
+    ///
+    /// * this would be thrown from the [`StorageService::delete_prefix`][remi::StorageService::delete_prefix] trait method.
+    DeleteObjects(DeleteObjectsError),
+
     /// Amazon S3 was unable to check the existence of an object. This will never
     /// reach the [`HeadObjectError::NotFound`] state as it'll return `Ok(false)`.
     ///
@@ -115,13 +126,83 @@ pub enum Error {
     /// * this would be thrown from the [`StorageService::upload`][remi::StorageService::upload] trait method.
     PutObject(PutObjectError),
 
+    /// Amazon S3 was unable to copy an object.
+    ///
+    /// * this would be thrown from the [`StorageService::copy`][remi::StorageService::copy] trait method.
+    CopyObject(CopyObjectError),
+
+    /// Amazon S3 was unable to return the tags of an object.
+    ///
+    /// * this would be thrown from [`StorageService::fetch_tags`][crate::StorageService::fetch_tags]
+    ///   or when listing objects with the [`IncludeTags`][crate::IncludeTags] request extension.
+    GetObjectTagging(GetObjectTaggingError),
+
     /// Occurs when an error occurred when transforming AWS S3's responses.
     ByteStream(aws_sdk_s3::primitives::ByteStreamError),
 
+    /// Occurs when `remi-s3` cannot check if versioning is enabled on the current bucket, which
+    /// uploads with [`CollisionStrategy::NewVersion`][remi::CollisionStrategy::NewVersion] rely on.
+    GetBucketVersioning(GetBucketVersioningError),
+
     /// Occurs when `remi-s3` cannot perform a HEAD request to the current bucket. This is mainly
     /// used in healthchecks to determine if the storage service is ok.
     HeadBucket(HeadBucketError),
 
+    /// Occurs when Amazon STS didn't accept the credentials that `remi-s3` was configured with.
+    ///
+    /// * this would be thrown from the [`StorageService::verify_credentials`][remi::StorageService::verify_credentials]
+    ///   trait method.
+    GetCallerIdentity(GetCallerIdentityError),
+
+    /// Occurs when [`StorageConfig::verify_writes`][crate::StorageConfig::verify_writes] is enabled
+    /// and the `ETag` that Amazon S3 returned didn't match the MD5 digest of the uploaded data.
+    ChecksumMismatch {
+        /// MD5 digest (in hexadecimal) of the data that was uploaded.
+        expected: String,
+
+        /// `ETag` that Amazon S3 returned.
+        actual: String,
+    },
+
+    /// Occurs when an upload is larger than [`StorageConfig::max_object_size`][crate::StorageConfig::max_object_size].
+    TooLarge(remi::TooLarge),
+
+    /// Occurs when a key can't refer to a file.
+    InvalidKey(remi::InvalidKey),
+
+    /// Occurs when [`StorageService::create_exclusive`][remi::StorageService::create_exclusive] is
+    /// called on a key that already has an object.
+    AlreadyExists(remi::AlreadyExists),
+
+    /// Occurs when a mutating operation is called while [`StorageConfig::read_only`][crate::StorageConfig::read_only]
+    /// is enabled.
+    ReadOnly(remi::ReadOnly),
+
+    /// Occurs when the body of an object doesn't match its reported `Content-Length`.
+    TruncatedRead(remi::TruncatedRead),
+
+    /// Occurs when an operation would buffer more bytes than what
+    /// [`StorageConfig::strict_memory_limit`][crate::StorageConfig::strict_memory_limit] allows.
+    MemoryLimitExceeded(remi::MemoryLimitExceeded),
+
+    /// Occurs when an [`UploadInterceptor`][remi::UploadInterceptor] rejected an upload.
+    RejectedByPolicy(remi::RejectedByPolicy),
+
+    /// Occurs when [`StorageService::init`][remi::StorageService::init] was called with
+    /// [`StorageConfig::skip_provisioning`][crate::StorageConfig::skip_provisioning] enabled
+    /// and the bucket doesn't exist.
+    ResourceMissing(remi::ResourceMissing),
+
+    /// Occurs when a [`StorageConfig`][crate::StorageConfig] can't be turned into an SDK client.
+    Config(ConfigError),
+
+    /// Occurs when [`PresignOptions::expires_in`][remi::PresignOptions::expires_in] isn't a
+    /// duration that Amazon S3 can presign a request for, which is at most a week.
+    ///
+    /// * this would be thrown from the [`PresignableStorageService::presign`][remi::PresignableStorageService::presign]
+    ///   trait method.
+    Presigning(PresigningConfigError),
+
     /// Something that `remi-s3` has emitted on its own.
     Library(Cow<'static, str>),
 }
@@ -145,14 +226,36 @@ impl Display for Error {
                 "request failed during dispatch, an HTTP response was not received. the request MAY have been set.",
             ),
 
+            E::CopyObject(err) => Display::fmt(err, f),
+            E::GetObjectTagging(err) => Display::fmt(err, f),
             E::CreateBucket(err) => Display::fmt(err, f),
             E::DeleteObject(err) => Display::fmt(err, f),
+            E::DeleteObjects(err) => Display::fmt(err, f),
+            E::GetBucketVersioning(err) => Display::fmt(err, f),
             E::GetObject(err) => Display::fmt(err, f),
             E::HeadObject(err) => Display::fmt(err, f),
             E::ListBuckets(err) => Display::fmt(err, f),
             E::ListObjectsV2(err) => Display::fmt(err, f),
             E::PutObject(err) => Display::fmt(err, f),
             E::HeadBucket(err) => Display::fmt(err, f),
+            E::GetCallerIdentity(err) => Display::fmt(err, f),
+            E::ChecksumMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "uploaded object's etag [{actual}] didn't match the expected MD5 digest [{expected}]"
+                )
+            }
+
+            E::TooLarge(err) => Display::fmt(err, f),
+            E::TruncatedRead(err) => Display::fmt(err, f),
+            E::MemoryLimitExceeded(err) => Display::fmt(err, f),
+            E::RejectedByPolicy(err) => Display::fmt(err, f),
+            E::ReadOnly(err) => Display::fmt(err, f),
+            E::InvalidKey(err) => Display::fmt(err, f),
+            E::AlreadyExists(err) => Display::fmt(err, f),
+            E::ResourceMissing(err) => Display::fmt(err, f),
+            E::Config(err) => Display::fmt(err, f),
+            E::Presigning(err) => Display::fmt(err, f),
             E::Library(msg) => f.write_str(msg),
         }
     }
@@ -160,6 +263,198 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
+/// Error codes that Amazon S3 (and S3-compatible services) respond with when a request
+/// failed on their end or was throttled.
+const RETRYABLE_CODES: &[&str] = &[
+    "InternalError",
+    "ServiceUnavailable",
+    "SlowDown",
+    "RequestTimeout",
+    "Throttling",
+    "ThrottlingException",
+    "RequestThrottled",
+    "RequestLimitExceeded",
+    "TooManyRequestsException",
+];
+
+/// Error codes that Amazon S3 responds with when a bucket or object doesn't exist.
+const NOT_FOUND_CODES: &[&str] = &["NoSuchBucket", "NoSuchKey", "NotFound"];
+
+/// Error codes that Amazon S3 responds with when the credentials were rejected or don't
+/// allow the request.
+const PERMISSION_DENIED_CODES: &[&str] = &[
+    "AccessDenied",
+    "AllAccessDisabled",
+    "ExpiredToken",
+    "InvalidAccessKeyId",
+    "InvalidClientTokenId",
+    "InvalidToken",
+    "SignatureDoesNotMatch",
+];
+
+impl Error {
+    /// Returns `true` if this error is likely to go away when the request is retried, like
+    /// timeouts, connections that were dropped, and server errors or throttling from Amazon S3.
+    /// This can be used as the classifier of a [`RetryingStorageService`][remi::RetryingStorageService].
+    ///
+    /// * since: 0.11.0
+    pub fn is_retryable(&self) -> bool {
+        use Error as E;
+
+        match self {
+            E::TimeoutError(_) | E::Response(_) | E::ByteStream(_) | E::TruncatedRead(_) => true,
+            E::ChecksumMismatch { .. } => true,
+            E::DispatchFailure(err) => err.is_io() || err.is_timeout(),
+            _ => self.code().is_some_and(|code| RETRYABLE_CODES.contains(&code)),
+        }
+    }
+
+    /// Returns the error code that Amazon S3 responded with, if this error is from a response.
+    fn code(&self) -> Option<&str> {
+        use Error as E;
+
+        match self {
+            E::ListBuckets(err) => err.code(),
+            E::CreateBucket(err) => err.code(),
+            E::GetObject(err) => err.code(),
+            E::ListObjectsV2(err) => err.code(),
+            E::DeleteObject(err) => err.code(),
+            E::DeleteObjects(err) => err.code(),
+            E::HeadObject(err) => err.code(),
+            E::PutObject(err) => err.code(),
+            E::CopyObject(err) => err.code(),
+            E::GetObjectTagging(err) => err.code(),
+            E::GetBucketVersioning(err) => err.code(),
+            E::HeadBucket(err) => err.code(),
+            E::GetCallerIdentity(err) => err.code(),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for remi::Error {
+    fn from(error: Error) -> Self {
+        use Error as E;
+
+        let variant = match &error {
+            E::AlreadyExists(_) => remi::Error::AlreadyExists,
+            E::ResourceMissing(_) => remi::Error::NotFound,
+            E::ReadOnly(_) => remi::Error::PermissionDenied,
+            E::InvalidKey(_) | E::TooLarge(_) | E::RejectedByPolicy(_) | E::Config(_) | E::Presigning(_) => {
+                remi::Error::InvalidInput
+            }
+
+            E::TimeoutError(_) => remi::Error::Timeout,
+            E::DispatchFailure(err) if err.is_timeout() => remi::Error::Timeout,
+            err if err.code() == Some("RequestTimeout") => remi::Error::Timeout,
+            err if err.code().is_some_and(|code| NOT_FOUND_CODES.contains(&code)) => remi::Error::NotFound,
+            err if err.code().is_some_and(|code| PERMISSION_DENIED_CODES.contains(&code)) => {
+                remi::Error::PermissionDenied
+            }
+
+            err if err.is_retryable() => remi::Error::Unavailable,
+            _ => remi::Error::Backend,
+        };
+
+        variant(Box::new(error))
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(error: ConfigError) -> Self {
+        Error::Config(error)
+    }
+}
+
+impl From<PresigningConfigError> for Error {
+    fn from(error: PresigningConfigError) -> Self {
+        Error::Presigning(error)
+    }
+}
+
+/// Represents an error when turning a [`StorageConfig`][crate::StorageConfig] into
+/// an [`aws_sdk_s3::Config`], which happens in [`StorageService::new`][crate::StorageService::new].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// [`StorageConfig::app_name`][crate::StorageConfig::app_name] is not a valid app name, which
+    /// has to be 50 characters or less and only contain alphanumeric characters or `` !#$%&'*+-.^_`|~ ``.
+    InvalidAppName(String),
+
+    /// Neither [`StorageConfig::region`][crate::StorageConfig::region] nor
+    /// [`StorageConfig::partition`][crate::StorageConfig::partition] was configured, which
+    /// is required to sign requests, even for S3-compatible endpoints. The region can also be
+    /// discovered from the environment with [`StorageService::from_env`][crate::StorageService::from_env].
+    MissingRegion,
+
+    /// [`StorageConfig::access_key_id`][crate::StorageConfig::access_key_id] or
+    /// [`StorageConfig::secret_access_key`][crate::StorageConfig::secret_access_key] is empty.
+    InvalidCredentials(&'static str),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::InvalidAppName(name) => write!(f, "app name [{name}] is not valid"),
+            ConfigError::MissingRegion => {
+                f.write_str("a region or partition is required, but neither `region` or `partition` was configured")
+            }
+
+            ConfigError::InvalidCredentials(reason) => write!(f, "invalid credentials: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<remi::TooLarge> for Error {
+    fn from(error: remi::TooLarge) -> Self {
+        Error::TooLarge(error)
+    }
+}
+
+impl From<remi::InvalidKey> for Error {
+    fn from(error: remi::InvalidKey) -> Self {
+        Error::InvalidKey(error)
+    }
+}
+
+impl From<remi::AlreadyExists> for Error {
+    fn from(error: remi::AlreadyExists) -> Self {
+        Error::AlreadyExists(error)
+    }
+}
+
+impl From<remi::ReadOnly> for Error {
+    fn from(error: remi::ReadOnly) -> Self {
+        Error::ReadOnly(error)
+    }
+}
+
+impl From<remi::MemoryLimitExceeded> for Error {
+    fn from(error: remi::MemoryLimitExceeded) -> Self {
+        Error::MemoryLimitExceeded(error)
+    }
+}
+
+impl From<remi::RejectedByPolicy> for Error {
+    fn from(error: remi::RejectedByPolicy) -> Self {
+        Error::RejectedByPolicy(error)
+    }
+}
+
+impl From<remi::ResourceMissing> for Error {
+    fn from(error: remi::ResourceMissing) -> Self {
+        Error::ResourceMissing(error)
+    }
+}
+
+impl From<remi::TruncatedRead> for Error {
+    fn from(error: remi::TruncatedRead) -> Self {
+        Error::TruncatedRead(error)
+    }
+}
+
 impl From<SdkError<ListBucketsError, Response<SdkBody>>> for Error {
     fn from(error: SdkError<ListBucketsError, Response<SdkBody>>) -> Self {
         match error {
@@ -226,6 +521,30 @@ impl From<SdkError<DeleteObjectError, Response<SdkBody>>> for Error {
     }
 }
 
+impl From<SdkError<DeleteObjectsError, Response<SdkBody>>> for Error {
+    fn from(error: SdkError<DeleteObjectsError, Response<SdkBody>>) -> Self {
+        match error {
+            SdkError::ConstructionFailure(err) => Self::ConstructionFailure(err),
+            SdkError::DispatchFailure(err) => Self::DispatchFailure(err),
+            SdkError::TimeoutError(err) => Self::TimeoutError(err),
+            SdkError::ResponseError(err) => Self::Response(err),
+            err => Error::DeleteObjects(err.into_service_error()),
+        }
+    }
+}
+
+impl From<SdkError<GetBucketVersioningError, Response<SdkBody>>> for Error {
+    fn from(error: SdkError<GetBucketVersioningError, Response<SdkBody>>) -> Self {
+        match error {
+            SdkError::ConstructionFailure(err) => Self::ConstructionFailure(err),
+            SdkError::DispatchFailure(err) => Self::DispatchFailure(err),
+            SdkError::TimeoutError(err) => Self::TimeoutError(err),
+            SdkError::ResponseError(err) => Self::Response(err),
+            err => Error::GetBucketVersioning(err.into_service_error()),
+        }
+    }
+}
+
 impl From<SdkError<HeadObjectError, Response<SdkBody>>> for Error {
     fn from(error: SdkError<HeadObjectError, Response<SdkBody>>) -> Self {
         match error {
@@ -256,6 +575,30 @@ impl From<SdkError<PutObjectError, Response<SdkBody>>> for Error {
     }
 }
 
+impl From<SdkError<CopyObjectError, Response<SdkBody>>> for Error {
+    fn from(error: SdkError<CopyObjectError, Response<SdkBody>>) -> Self {
+        match error {
+            SdkError::ConstructionFailure(err) => Self::ConstructionFailure(err),
+            SdkError::DispatchFailure(err) => Self::DispatchFailure(err),
+            SdkError::TimeoutError(err) => Self::TimeoutError(err),
+            SdkError::ResponseError(err) => Self::Response(err),
+            err => Error::CopyObject(err.into_service_error()),
+        }
+    }
+}
+
+impl From<SdkError<GetObjectTaggingError, Response<SdkBody>>> for Error {
+    fn from(error: SdkError<GetObjectTaggingError, Response<SdkBody>>) -> Self {
+        match error {
+            SdkError::ConstructionFailure(err) => Self::ConstructionFailure(err),
+            SdkError::DispatchFailure(err) => Self::DispatchFailure(err),
+            SdkError::TimeoutError(err) => Self::TimeoutError(err),
+            SdkError::ResponseError(err) => Self::Response(err),
+            err => Error::GetObjectTagging(err.into_service_error()),
+        }
+    }
+}
+
 impl From<SdkError<HeadBucketError, Response<SdkBody>>> for Error {
     fn from(value: SdkError<HeadBucketError, Response<SdkBody>>) -> Self {
         match value {
@@ -268,8 +611,56 @@ impl From<SdkError<HeadBucketError, Response<SdkBody>>> for Error {
     }
 }
 
+impl From<SdkError<GetCallerIdentityError, Response<SdkBody>>> for Error {
+    fn from(value: SdkError<GetCallerIdentityError, Response<SdkBody>>) -> Self {
+        match value {
+            SdkError::ConstructionFailure(err) => Self::ConstructionFailure(err),
+            SdkError::DispatchFailure(err) => Self::DispatchFailure(err),
+            SdkError::TimeoutError(err) => Self::TimeoutError(err),
+            SdkError::ResponseError(err) => Self::Response(err),
+            err => Error::GetCallerIdentity(err.into_service_error()),
+        }
+    }
+}
+
 impl From<aws_sdk_s3::primitives::ByteStreamError> for Error {
     fn from(value: aws_sdk_s3::primitives::ByteStreamError) -> Self {
         Self::ByteStream(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use aws_sdk_s3::{error::ErrorMetadata, operation::get_object::GetObjectError};
+
+    #[test]
+    fn test_is_retryable() {
+        let error = |code: &str| Error::GetObject(GetObjectError::generic(ErrorMetadata::builder().code(code).build()));
+
+        assert!(error("SlowDown").is_retryable());
+        assert!(error("InternalError").is_retryable());
+        assert!(!error("AccessDenied").is_retryable());
+        assert!(Error::TruncatedRead(remi::TruncatedRead { expected: 2, actual: 1 }).is_retryable());
+        assert!(!Error::Library("weow".into()).is_retryable());
+    }
+
+    #[test]
+    fn test_into_remi_error() {
+        let error = |code: &str| {
+            remi::Error::from(Error::GetObject(GetObjectError::generic(
+                ErrorMetadata::builder().code(code).build(),
+            )))
+        };
+
+        assert!(matches!(error("NoSuchBucket"), remi::Error::NotFound(_)));
+        assert!(matches!(error("AccessDenied"), remi::Error::PermissionDenied(_)));
+        assert!(matches!(error("RequestTimeout"), remi::Error::Timeout(_)));
+        assert!(matches!(error("SlowDown"), remi::Error::Unavailable(_)));
+        assert!(matches!(error("InvalidObjectState"), remi::Error::Backend(_)));
+
+        let error = remi::Error::from(Error::AlreadyExists(remi::AlreadyExists::new("s3://weow.txt")));
+        assert!(matches!(error, remi::Error::AlreadyExists(_)));
+        assert!(matches!(error.downcast_ref::<Error>(), Some(Error::AlreadyExists(_))));
+    }
+}
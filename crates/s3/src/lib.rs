@@ -29,13 +29,27 @@
 )]
 
 mod config;
+mod credentials;
 mod error;
+mod network;
 mod service;
 
 pub use config::*;
+pub use credentials::*;
 pub use error::*;
+pub use network::*;
 pub use service::*;
 
+// Compile-time checks so that a change which makes these types no longer thread-safe
+// fails to build here instead of breaking downstream crates.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    assert_send_sync::<StorageService>();
+    assert_send_sync::<StorageConfig>();
+    assert_send_sync::<Error>();
+};
+
 /// Exports the [`aws_sdk_s3`], [`aws_credential_types`], and [`aws_config`] crate without
 /// specifying the dependencies yourself.
 #[cfg(feature = "export-crates")]
@@ -0,0 +1,235 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use aws_smithy_http_client::{
+    tls::{rustls_provider::CryptoMode, Provider},
+    Connector,
+};
+use aws_smithy_runtime::client::dns::TokioDnsResolver;
+use aws_smithy_runtime_api::client::{
+    dns::{DnsFuture, ResolveDns, ResolveDnsError, SharedDnsResolver},
+    http::{HttpClient, HttpConnectorSettings, SharedHttpClient, SharedHttpConnector},
+    runtime_components::RuntimeComponents,
+};
+use std::{collections::HashMap, net::IpAddr, sync::Mutex, time::Duration};
+
+/// Connector-level options for environments with IPv6-only networks or internal DNS.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkConfig {
+    /// Which IP address family connections should use.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ip_family: IpFamily,
+
+    /// Whether if connections should race IPv6 and IPv4 addresses ("Happy Eyeballs") when
+    /// a hostname resolves to both. If this is disabled, only addresses of the family that
+    /// was resolved first are used.
+    #[cfg_attr(feature = "serde", serde(default = "__truthy"))]
+    pub happy_eyeballs: bool,
+
+    /// Network interface to bind all sockets to (i.e, `eth1`). This is only supported on
+    /// Linux, Android, and Fuchsia and is ignored everywhere else.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub interface: Option<String>,
+
+    /// Custom DNS resolver to use instead of the system's resolver.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub resolver: Option<SharedDnsResolver>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            ip_family: IpFamily::default(),
+            happy_eyeballs: true,
+            interface: None,
+            resolver: None,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Whether if the default HTTP client of the AWS SDK can be used.
+    pub(crate) fn is_default(&self) -> bool {
+        self.ip_family == IpFamily::Any && self.happy_eyeballs && self.interface.is_none() && self.resolver.is_none()
+    }
+
+    /// Sets a custom DNS resolver.
+    pub fn with_resolver<R: ResolveDns + 'static>(mut self, resolver: R) -> Self {
+        self.resolver = Some(SharedDnsResolver::new(resolver));
+        self
+    }
+
+    pub(crate) fn into_http_client(self) -> SharedHttpClient {
+        SharedHttpClient::new(NetworkClient {
+            config: self,
+            connectors: Mutex::default(),
+        })
+    }
+}
+
+/// IP address family that connections can be restricted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum IpFamily {
+    /// Use both IPv4 and IPv6 addresses.
+    #[default]
+    Any,
+
+    /// Only use IPv4 addresses.
+    Ipv4,
+
+    /// Only use IPv6 addresses.
+    Ipv6,
+}
+
+impl IpFamily {
+    /// Whether if `addr` belongs to this address family.
+    pub fn matches(&self, addr: &IpAddr) -> bool {
+        match self {
+            IpFamily::Any => true,
+            IpFamily::Ipv4 => addr.is_ipv4(),
+            IpFamily::Ipv6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// [`ResolveDns`] that filters resolved addresses with a [`NetworkConfig`].
+#[derive(Debug, Clone)]
+struct Resolver {
+    inner: SharedDnsResolver,
+    ip_family: IpFamily,
+    happy_eyeballs: bool,
+}
+
+impl ResolveDns for Resolver {
+    fn resolve_dns<'a>(&'a self, name: &'a str) -> DnsFuture<'a> {
+        DnsFuture::new(async move {
+            let addrs = filter_addrs(self.inner.resolve_dns(name).await?, self.ip_family, self.happy_eyeballs);
+            if addrs.is_empty() {
+                return Err(ResolveDnsError::new(std::io::Error::new(
+                    std::io::ErrorKind::AddrNotAvailable,
+                    format!("host [{name}] didn't resolve to any usable address"),
+                )));
+            }
+
+            Ok(addrs)
+        })
+    }
+}
+
+fn filter_addrs(addrs: Vec<IpAddr>, ip_family: IpFamily, happy_eyeballs: bool) -> Vec<IpAddr> {
+    let mut addrs = addrs
+        .into_iter()
+        .filter(|addr| ip_family.matches(addr))
+        .collect::<Vec<_>>();
+    if !happy_eyeballs {
+        if let Some(first) = addrs.first().copied() {
+            addrs.retain(|addr| addr.is_ipv6() == first.is_ipv6());
+        }
+    }
+
+    addrs
+}
+
+/// Connect and read timeouts of the connector settings.
+type ConnectorKey = (Option<Duration>, Option<Duration>);
+
+/// [`HttpClient`] that creates connectors from a [`NetworkConfig`]. Connectors are cached
+/// per timeout settings so that the connection pool is reused between requests.
+#[derive(Debug)]
+struct NetworkClient {
+    config: NetworkConfig,
+    connectors: Mutex<HashMap<ConnectorKey, SharedHttpConnector>>,
+}
+
+impl HttpClient for NetworkClient {
+    fn http_connector(&self, settings: &HttpConnectorSettings, components: &RuntimeComponents) -> SharedHttpConnector {
+        let key = (settings.connect_timeout(), settings.read_timeout());
+        let mut connectors = self.connectors.lock().expect("connector cache to not be poisoned");
+
+        connectors
+            .entry(key)
+            .or_insert_with(|| {
+                let mut builder = Connector::builder().connector_settings(settings.clone());
+                builder.set_sleep_impl(components.sleep_impl());
+
+                #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+                if let Some(ref interface) = self.config.interface {
+                    builder.set_interface(interface);
+                }
+
+                let resolver = Resolver {
+                    inner: self
+                        .config
+                        .resolver
+                        .clone()
+                        .unwrap_or_else(|| SharedDnsResolver::new(TokioDnsResolver::new())),
+                    ip_family: self.config.ip_family,
+                    happy_eyeballs: self.config.happy_eyeballs,
+                };
+
+                SharedHttpConnector::new(
+                    builder
+                        .tls_provider(Provider::Rustls(CryptoMode::AwsLc))
+                        .build_with_resolver(resolver),
+                )
+            })
+            .clone()
+    }
+}
+
+#[cfg(feature = "serde")]
+const fn __truthy() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn test_filter_addrs() {
+        let addrs = vec![
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        ];
+
+        assert_eq!(filter_addrs(addrs.clone(), IpFamily::Any, true), addrs);
+        assert_eq!(
+            filter_addrs(addrs.clone(), IpFamily::Ipv4, true),
+            [IpAddr::V4(Ipv4Addr::LOCALHOST)]
+        );
+
+        assert_eq!(
+            filter_addrs(addrs.clone(), IpFamily::Ipv6, true),
+            [IpAddr::V6(Ipv6Addr::LOCALHOST), IpAddr::V6(Ipv6Addr::UNSPECIFIED)]
+        );
+
+        assert_eq!(
+            filter_addrs(addrs, IpFamily::Any, false),
+            [IpAddr::V6(Ipv6Addr::LOCALHOST), IpAddr::V6(Ipv6Addr::UNSPECIFIED)]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::path::PathBuf;
+
+/// Represents the main configuration struct to configure a [`StorageService`][crate::StorageService].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageConfig {
+    /// Hostname or IP address of the SFTP server.
+    pub host: String,
+
+    /// Port of the SFTP server, which is `22` by default.
+    #[cfg_attr(feature = "serde", serde(default = "__default_port"))]
+    pub port: u16,
+
+    /// Name of the user to authenticate as.
+    pub username: String,
+
+    /// How to authenticate as [`username`][StorageConfig::username].
+    pub credentials: Credentials,
+
+    /// Directory on the SFTP server where files are kept, which keys with the `./` prefix are
+    /// relative to. Relative directories are resolved from the user's login directory, which
+    /// is also what an empty directory refers to.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub directory: PathBuf,
+
+    /// Path to an OpenSSH `known_hosts` file that the SFTP server's host key is verified against,
+    /// which fails to connect with a [`PermissionDenied`][std::io::ErrorKind::PermissionDenied]
+    /// error if the host key isn't in it or doesn't match.
+    ///
+    /// If this isn't set, then any host key is accepted, which makes connections vulnerable
+    /// to man-in-the-middle attacks and should only be used in trusted networks.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub known_hosts: Option<PathBuf>,
+
+    /// Timeout (in milliseconds) of blocking calls to the SFTP server. If this isn't set,
+    /// calls can wait forever.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub timeout: Option<u32>,
+
+    /// Whether if this storage service is read-only. All operations that would modify the
+    /// directory fail with a [`ReadOnly`][remi::ReadOnly] error before anything is sent.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub read_only: bool,
+
+    /// Whether if [`init`][remi::StorageService::init] should only verify that the directory exists instead
+    /// of creating it, which fails with a [`NotFound`][std::io::ErrorKind::NotFound] error that wraps
+    /// a [`ResourceMissing`][remi::ResourceMissing] error if it doesn't. This can also be enabled
+    /// with the [`REMI_SKIP_PROVISIONING`][remi::SKIP_PROVISIONING_ENV] environment variable.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub skip_provisioning: bool,
+}
+
+impl StorageConfig {
+    /// Creates a new [`StorageConfig`] that connects to `host` on the default port.
+    pub fn new<H: Into<String>, U: Into<String>>(host: H, username: U, credentials: Credentials) -> StorageConfig {
+        StorageConfig {
+            host: host.into(),
+            port: 22,
+            username: username.into(),
+            credentials,
+            directory: PathBuf::new(),
+            known_hosts: None,
+            timeout: None,
+            read_only: false,
+            skip_provisioning: false,
+        }
+    }
+}
+
+/// Ways to authenticate with an SFTP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Credentials {
+    /// Authenticates with a password, which is redacted when this configuration is
+    /// printed or serialized.
+    Password(remi::Secret),
+
+    /// Authenticates with a private key that is read from a file.
+    PrivateKey {
+        /// Path to the private key.
+        path: PathBuf,
+
+        /// Passphrase of the private key if it is encrypted, which is redacted when this
+        /// configuration is printed or serialized.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        passphrase: Option<remi::Secret>,
+    },
+
+    /// Authenticates with the keys of a running SSH agent.
+    Agent,
+}
+
+#[cfg(feature = "serde")]
+const fn __default_port() -> u16 {
+    22
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Credentials, StorageConfig};
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let config = StorageConfig::new(
+            "localhost",
+            "noel",
+            Credentials::Password(remi::Secret::from("hunter2")),
+        );
+        assert!(!format!("{config:?}").contains("hunter2"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        let config: StorageConfig = serde_json::from_str(
+            r#"{"host":"localhost","username":"noel","credentials":{"private_key":{"path":"/home/noel/.ssh/id_ed25519"}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.port, 22);
+        assert_eq!(
+            config.credentials,
+            Credentials::PrivateKey {
+                path: "/home/noel/.ssh/id_ed25519".into(),
+                passphrase: None,
+            }
+        );
+    }
+}
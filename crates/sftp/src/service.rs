@@ -0,0 +1,1097 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{Credentials, StorageConfig};
+use async_trait::async_trait;
+use bytes::Bytes;
+use remi::{
+    Blob, Capabilities, CollisionStrategy, DeleteSummary, Directory, File, ListBlobsRequest, Page, ServiceInfo,
+    UploadRequest,
+};
+use ssh2::{CheckResult, FileStat, FileType, KnownHostFileKind, OpenFlags, OpenType, RenameFlags, Session, Sftp};
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug},
+    io::{self, Read, Seek, SeekFrom, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "tracing")]
+use tracing::instrument;
+
+/// Amount of blobs in a page that is returned from [`blobs_page`][remi::StorageService::blobs_page]
+/// if [`ListBlobsRequest::page_size`] isn't set.
+const LIST_PAGE_SIZE: u32 = 1000;
+
+/// Checks that `path` can be used as the key of a file, see [`InvalidKey`][remi::InvalidKey].
+fn check_key(path: &Path) -> io::Result<()> {
+    remi::InvalidKey::check(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        remi::AlreadyExists::new(format!("sftp://{}", path.display())),
+    )
+}
+
+/// Returns `true` if `error` might have been caused by a broken SSH session instead of
+/// the SFTP server rejecting the operation, in which case the session is reconnected.
+fn is_connection_error(error: &io::Error) -> bool {
+    !matches!(
+        error.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::AlreadyExists
+            | io::ErrorKind::InvalidInput
+            | io::ErrorKind::PermissionDenied
+            | io::ErrorKind::Unsupported
+    )
+}
+
+/// Connects and authenticates to the SFTP server that `config` points to.
+fn connect(config: &StorageConfig) -> io::Result<Sftp> {
+    #[cfg(feature = "tracing")]
+    ::tracing::debug!(host = %config.host, port = config.port, "connecting to SFTP server");
+
+    #[cfg(feature = "log")]
+    ::log::debug!("connecting to SFTP server [{}:{}]", config.host, config.port);
+
+    let tcp = match config.timeout {
+        Some(timeout) => {
+            let addr = (config.host.as_str(), config.port)
+                .to_socket_addrs()?
+                .next()
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("host [{}] didn't resolve to any address", config.host),
+                    )
+                })?;
+
+            TcpStream::connect_timeout(&addr, Duration::from_millis(timeout.into()))?
+        }
+
+        None => TcpStream::connect((config.host.as_str(), config.port))?,
+    };
+
+    let mut session = Session::new()?;
+    if let Some(timeout) = config.timeout {
+        session.set_timeout(timeout);
+    }
+
+    session.set_tcp_stream(tcp);
+    session.handshake()?;
+    verify_host_key(&session, config)?;
+
+    match &config.credentials {
+        Credentials::Password(password) => session.userauth_password(&config.username, password.expose())?,
+        Credentials::PrivateKey { path, passphrase } => session.userauth_pubkey_file(
+            &config.username,
+            None,
+            path,
+            passphrase.as_ref().map(|passphrase| passphrase.expose().as_str()),
+        )?,
+
+        Credentials::Agent => session.userauth_agent(&config.username)?,
+    }
+
+    if !session.authenticated() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("failed to authenticate as [{}]", config.username),
+        ));
+    }
+
+    Ok(session.sftp()?)
+}
+
+/// Verifies the host key of the SFTP server against [`StorageConfig::known_hosts`].
+fn verify_host_key(session: &Session, config: &StorageConfig) -> io::Result<()> {
+    let Some(ref known_hosts) = config.known_hosts else {
+        #[cfg(feature = "tracing")]
+        ::tracing::warn!(host = %config.host, "host key isn't verified since `known_hosts` isn't set");
+
+        #[cfg(feature = "log")]
+        ::log::warn!(
+            "host key of [{}] isn't verified since `known_hosts` isn't set",
+            config.host
+        );
+
+        return Ok(());
+    };
+
+    let mut hosts = session.known_hosts()?;
+    hosts.read_file(known_hosts, KnownHostFileKind::OpenSSH)?;
+
+    let Some((key, _)) = session.host_key() else {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("[{}:{}] didn't send a host key", config.host, config.port),
+        ));
+    };
+
+    match hosts.check_port(&config.host, config.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::NotFound => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "host key of [{}:{}] isn't in [{}]",
+                config.host,
+                config.port,
+                known_hosts.display()
+            ),
+        )),
+
+        CheckResult::Mismatch => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "host key of [{}:{}] doesn't match the one in [{}], which could mean that the connection was intercepted",
+                config.host,
+                config.port,
+                known_hosts.display()
+            ),
+        )),
+
+        CheckResult::Failure => Err(io::Error::other(format!(
+            "failed to check the host key of [{}:{}]",
+            config.host, config.port
+        ))),
+    }
+}
+
+/// Returns the [`FileStat`] of `path`, or `None` if it doesn't exist. Symbolic links are followed.
+fn stat(sftp: &Sftp, path: &Path) -> io::Result<Option<FileStat>> {
+    match sftp.stat(path).map_err(io::Error::from) {
+        Ok(stat) => Ok(Some(stat)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Creates `dir` and all of its parents if they don't exist.
+fn create_dir_all(sftp: &Sftp, dir: &Path) -> io::Result<()> {
+    if dir.as_os_str().is_empty() || stat(sftp, dir)?.is_some() {
+        return Ok(());
+    }
+
+    if let Some(parent) = dir.parent() {
+        create_dir_all(sftp, parent)?;
+    }
+
+    match sftp.mkdir(dir, 0o755) {
+        Ok(()) => Ok(()),
+
+        // another upload could've created it in the meantime
+        Err(_) if stat(sftp, dir)?.is_some_and(|stat| stat.is_dir()) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Moves `from` to `to`, replacing `to` if it exists.
+fn rename_over(sftp: &Sftp, from: &Path, to: &Path) -> io::Result<()> {
+    let flags = RenameFlags::OVERWRITE | RenameFlags::ATOMIC | RenameFlags::NATIVE;
+    match sftp.rename(from, to, Some(flags)) {
+        Ok(()) => Ok(()),
+
+        // SFTP servers that only speak version 3 of the protocol (like OpenSSH) ignore the
+        // flags and refuse to rename over an existing file, so it has to be removed first
+        Err(_) if stat(sftp, to)?.is_some() => {
+            sftp.unlink(to)?;
+            sftp.rename(from, to, None).map_err(From::from)
+        }
+
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads the bytes from `start` up to `end` of the file in `path`, or the whole file
+/// if no range is given.
+fn read(sftp: &Sftp, path: &Path, stat: &FileStat, range: Option<(u64, Option<u64>)>) -> io::Result<Bytes> {
+    if !stat.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("path [{}] is not a file", path.display()),
+        ));
+    }
+
+    let size = stat.size.unwrap_or_default();
+    let (start, end) = match range {
+        Some((start, end)) => remi::clamp_range(start, end, size),
+        None => (0, size),
+    };
+
+    if start == end {
+        return Ok(Bytes::new());
+    }
+
+    let mut file = sftp.open(path)?;
+    if start > 0 {
+        file.seek(SeekFrom::Start(start))?;
+    }
+
+    let mut buffer = Vec::with_capacity(usize::try_from(end - start).unwrap_or_default());
+    file.take(end - start).read_to_end(&mut buffer)?;
+
+    Ok(Bytes::from(buffer))
+}
+
+fn to_file(path: &Path, stat: &FileStat, data: Bytes) -> File {
+    File {
+        last_modified_at: stat.mtime.map(|secs| u128::from(secs) * 1000),
+        content_type: None,
+        created_at: None,
+        metadata: Default::default(),
+        is_symlink: false,
+        checksum: None,
+        etag: None,
+        version: None,
+        expires_at: None,
+        extras: Default::default(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: format!("sftp://{}", path.display()),
+        size: stat.size.unwrap_or_default() as usize,
+        data,
+    }
+}
+
+/// Represents an implementation of [`StorageService`][remi::StorageService] over an SFTP server,
+/// which keeps files in [`StorageConfig::directory`] like the local filesystem would.
+///
+/// Every call is run on Tokio's thread pool for blocking work over a single SSH session that
+/// is shared between clones of a [`StorageService`]. The session is connected when it is first
+/// needed and reconnected after an operation fails because of it.
+#[derive(Clone)]
+pub struct StorageService {
+    config: Arc<StorageConfig>,
+    sftp: Arc<Mutex<Option<Arc<Sftp>>>>,
+    interceptors: remi::UploadInterceptors,
+}
+
+impl Debug for StorageService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StorageService")
+            .field("config", &self.config)
+            .field("interceptors", &self.interceptors)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StorageService {
+    /// Creates a new [`StorageService`] with the given [`StorageConfig`]. This doesn't connect
+    /// to the SFTP server until it is first needed, see [`init`][remi::StorageService::init].
+    pub fn new(config: StorageConfig) -> StorageService {
+        StorageService {
+            config: Arc::new(config),
+            sftp: Arc::default(),
+            interceptors: remi::UploadInterceptors::default(),
+        }
+    }
+
+    /// Registers an [`UploadInterceptor`][remi::UploadInterceptor] that can inspect and reject
+    /// uploads before they are written, which fail with a [`PermissionDenied`][io::ErrorKind::PermissionDenied]
+    /// error that holds a [`RejectedByPolicy`][remi::RejectedByPolicy] error. Interceptors run in the
+    /// order that they were registered.
+    pub fn with_interceptor<I: remi::UploadInterceptor + 'static>(mut self, interceptor: I) -> StorageService {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Returns the [`StorageConfig`] that this storage service was created with.
+    pub fn config(&self) -> &StorageConfig {
+        &self.config
+    }
+
+    fn check_writable(&self, operation: &'static str) -> io::Result<()> {
+        remi::ReadOnly::check(self.config.read_only, operation)
+            .map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))
+    }
+
+    /// Resolves `path` into a path on the SFTP server, relative to [`StorageConfig::directory`].
+    fn resolve<P: AsRef<Path>>(&self, path: P) -> io::Result<PathBuf> {
+        let path = path.as_ref();
+        let relative = path
+            .strip_prefix("./")
+            .or_else(|_| path.strip_prefix("~/"))
+            .or_else(|_| path.strip_prefix("/"))
+            .unwrap_or(path);
+
+        if relative.components().any(|component| component == Component::ParentDir) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "path [{}] can't refer to a file outside of the directory",
+                    path.display()
+                ),
+            ));
+        }
+
+        match self.config.directory.join(relative) {
+            resolved if resolved.as_os_str().is_empty() => Ok(PathBuf::from(".")),
+            resolved => Ok(resolved),
+        }
+    }
+
+    /// Runs `f` with the SFTP session on the thread pool for blocking work, connecting
+    /// it first if it isn't connected.
+    async fn run<T, F>(&self, name: &'static str, f: F) -> io::Result<T>
+    where
+        F: FnOnce(&Sftp) -> io::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let config = self.config.clone();
+        let connection = self.sftp.clone();
+        remi::rt::spawn_blocking(name, move || {
+            let sftp = {
+                let mut guard = connection.lock().unwrap_or_else(PoisonError::into_inner);
+                match &*guard {
+                    Some(sftp) => sftp.clone(),
+                    None => guard.insert(Arc::new(connect(&config)?)).clone(),
+                }
+            };
+
+            let result = f(&sftp);
+            if result.as_ref().is_err_and(is_connection_error) {
+                // the session is only dropped if another call didn't reconnect it already
+                let mut guard = connection.lock().unwrap_or_else(PoisonError::into_inner);
+                if guard.as_ref().is_some_and(|current| Arc::ptr_eq(current, &sftp)) {
+                    *guard = None;
+                }
+            }
+
+            result
+        })
+        .await
+    }
+
+    /// Lists the entries in `path` in order of their names, returning at most `limit` blobs.
+    async fn list(
+        &self,
+        path: Option<&Path>,
+        options: ListBlobsRequest,
+        limit: Option<usize>,
+    ) -> io::Result<Page<Blob>> {
+        let dir = self.resolve(path.unwrap_or(Path::new("./")))?;
+        self.run("remi_sftp::list", move |sftp| {
+            let mut entries = match sftp.readdir(&dir).map_err(io::Error::from) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Page::default()),
+                Err(e) => return Err(e),
+            };
+
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            let prefix = options.prefix.as_deref().unwrap_or_default();
+            let mut blobs = Vec::new();
+            for (path, mut stat) in entries {
+                let key = path.to_string_lossy();
+                if [&options.start_after, &options.continuation]
+                    .into_iter()
+                    .flatten()
+                    .any(|after| key.as_ref() <= after.as_str())
+                {
+                    continue;
+                }
+
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                if !name.starts_with(prefix) {
+                    continue;
+                }
+
+                // symbolic links are followed, and skipped if they're broken
+                if stat.file_type() == FileType::Symlink {
+                    match stat_or_skip(sftp, &path)? {
+                        Some(target) => stat = target,
+                        None => continue,
+                    }
+                }
+
+                // the key of the last blob in a full page continues the listing after it
+                if limit.is_some_and(|limit| blobs.len() == limit) {
+                    let last = match blobs.last() {
+                        Some(Blob::File(file)) => &file.path,
+                        Some(Blob::Directory(dir)) => &dir.path,
+                        None => unreachable!("page to be full"),
+                    };
+
+                    let continuation = last.trim_start_matches("sftp://").to_owned();
+                    return Ok(Page::new(blobs, Some(continuation)));
+                }
+
+                if stat.is_dir() {
+                    if options.include_dirs && !options.is_excluded(format!("dir:{name}")) {
+                        blobs.push(Blob::Directory(Directory {
+                            created_at: None,
+                            path: format!("sftp://{}", path.display()),
+                            name,
+                        }));
+                    }
+
+                    continue;
+                }
+
+                // special files (like sockets or FIFOs) can't be read as files
+                if !stat.is_file() || options.is_excluded(&name) {
+                    continue;
+                }
+
+                if let Some(ext) = path.extension() {
+                    if !options.is_ext_allowed(ext.to_string_lossy()) {
+                        continue;
+                    }
+                }
+
+                let data = match options.metadata_only {
+                    true => Bytes::new(),
+                    false => read(sftp, &path, &stat, None)?,
+                };
+
+                blobs.push(Blob::File(to_file(&path, &stat, data)));
+            }
+
+            Ok(Page::last(blobs))
+        })
+        .await
+    }
+
+    /// Writes `options` into `path`, which fails with [`AlreadyExists`][remi::AlreadyExists]
+    /// instead of replacing an existing file if `exclusive` is `true`.
+    async fn write(&self, path: &Path, options: UploadRequest, exclusive: bool) -> io::Result<()> {
+        self.check_writable("upload")?;
+        check_key(path)?;
+
+        let path = self.resolve(path)?;
+        self.interceptors
+            .check(&format!("sftp://{}", path.display()), &options)
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(path = %path.display(), "uploading file");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("uploading file [{}]", path.display());
+
+        let modified = options
+            .timestamps()
+            .and_then(|timestamps| timestamps.modified)
+            .map(|millis| u64::try_from(millis / 1000).unwrap_or(u64::MAX));
+
+        let data = options.data;
+        self.run("remi_sftp::write", move |sftp| {
+            if let Some(parent) = path.parent() {
+                create_dir_all(sftp, parent)?;
+            }
+
+            // exclusive files are created in place, since the server is what checks that
+            // nothing exists there yet
+            if exclusive {
+                let mut file =
+                    match sftp.open_mode(&path, OpenFlags::WRITE | OpenFlags::EXCLUSIVE, 0o644, OpenType::File) {
+                        Ok(file) => file,
+                        Err(_) if stat(sftp, &path)?.is_some() => return Err(already_exists(&path)),
+                        Err(e) => return Err(e.into()),
+                    };
+
+                let result = file.write_all(&data).and_then(|_| set_modified(sftp, &path, modified));
+                if result.is_err() {
+                    drop(file);
+                    let _ = sftp.unlink(&path);
+                }
+
+                return result;
+            }
+
+            // write into a temporary file that is renamed over the destination once it was
+            // fully written, so a failed upload never leaves a partially written file behind
+            let temp = temp_path(&path);
+            let result = sftp
+                .create(&temp)
+                .map_err(io::Error::from)
+                .and_then(|mut file| file.write_all(&data))
+                .and_then(|_| set_modified(sftp, &temp, modified))
+                .and_then(|_| rename_over(sftp, &temp, &path));
+
+            if result.is_err() {
+                let _ = sftp.unlink(&temp);
+            }
+
+            result
+        })
+        .await
+    }
+}
+
+/// Like [`stat`], but a symbolic link whose target can't be read is skipped instead of failing.
+fn stat_or_skip(sftp: &Sftp, path: &Path) -> io::Result<Option<FileStat>> {
+    match stat(sftp, path) {
+        Ok(stat) => Ok(stat),
+        Err(e) if is_connection_error(&e) => Err(e),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Sets the modification time (in seconds since the Unix epoch) of the file in `path`.
+fn set_modified(sftp: &Sftp, path: &Path, modified: Option<u64>) -> io::Result<()> {
+    let Some(modified) = modified else {
+        return Ok(());
+    };
+
+    sftp.setstat(
+        path,
+        FileStat {
+            size: None,
+            uid: None,
+            gid: None,
+            perm: None,
+            atime: Some(modified),
+            mtime: Some(modified),
+        },
+    )
+    .map_err(From::from)
+}
+
+/// Returns a path in the same directory as `path` that a file can be written into before it
+/// is renamed to `path`.
+fn temp_path(path: &Path) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default();
+
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}-{nanos}.remi-tmp", std::process::id()))
+}
+
+#[async_trait]
+impl remi::StorageService for StorageService {
+    type Error = io::Error;
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("remi:sftp")
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.init",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                directory = %self.config.directory.display()
+            )
+        )
+    )]
+    async fn init(&self) -> io::Result<()> {
+        let directory = self.config.directory.clone();
+        let skip_provisioning = remi::skip_provisioning(self.config.skip_provisioning);
+        self.run("remi_sftp::init", move |sftp| {
+            if directory.as_os_str().is_empty() || stat(sftp, &directory)?.is_some() {
+                return Ok(());
+            }
+
+            if skip_provisioning {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    remi::ResourceMissing::new("directory", directory.display().to_string()),
+                ));
+            }
+
+            #[cfg(feature = "tracing")]
+            ::tracing::info!(directory = %directory.display(), "creating directory");
+
+            #[cfg(feature = "log")]
+            ::log::info!("creating directory [{}]", directory.display());
+
+            create_dir_all(sftp, &directory)
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.open",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<Bytes>> {
+        check_key(path.as_ref())?;
+        let path = self.resolve(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(path = %path.display(), "opening file");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("opening file [{}]", path.display());
+
+        self.run("remi_sftp::open", move |sftp| match stat(sftp, &path)? {
+            Some(stat) => read(sftp, &path, &stat, None).map(Some),
+            None => Ok(None),
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.open_range",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> io::Result<Option<Bytes>> {
+        check_key(path.as_ref())?;
+        let path = self.resolve(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(path = %path.display(), start, end = ?end, "opening range of file");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("opening range [{start}..{end:?}] of file [{}]", path.display());
+
+        self.run("remi_sftp::open_range", move |sftp| match stat(sftp, &path)? {
+            Some(stat) => read(sftp, &path, &stat, Some((start, end))).map(Some),
+            None => Ok(None),
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.blob",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<Blob>> {
+        check_key(path.as_ref())?;
+        let path = self.resolve(path)?;
+        self.run("remi_sftp::blob", move |sftp| {
+            let Some(stat) = stat(sftp, &path)? else {
+                return Ok(None);
+            };
+
+            if stat.is_dir() {
+                return Ok(Some(Blob::Directory(Directory {
+                    created_at: None,
+                    name: path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    path: format!("sftp://{}", path.display()),
+                })));
+            }
+
+            let data = read(sftp, &path, &stat, None)?;
+            Ok(Some(Blob::File(to_file(&path, &stat, data))))
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.blobs",
+            skip_all,
+            fields(
+                remi.service = "sftp"
+            )
+        )
+    )]
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> io::Result<Vec<Blob>> {
+        self.list(path.as_ref().map(AsRef::as_ref), options.unwrap_or_default(), None)
+            .await
+            .map(|page| page.items)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.blobs_page",
+            skip_all,
+            fields(
+                remi.service = "sftp"
+            )
+        )
+    )]
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> io::Result<Page<Blob>> {
+        let options = options.unwrap_or_default();
+        let limit = options.page_size_or(LIST_PAGE_SIZE, u32::MAX) as usize;
+        self.list(path.as_ref().map(AsRef::as_ref), options, Some(limit))
+            .await
+            .map(|page| page.with_requests(1))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.delete",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<bool> {
+        self.check_writable("delete")?;
+        check_key(path.as_ref())?;
+
+        let path = self.resolve(path)?;
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!(path = %path.display(), "deleting file");
+
+        #[cfg(feature = "log")]
+        ::log::trace!("deleting file [{}]", path.display());
+
+        self.run("remi_sftp::delete", move |sftp| {
+            match sftp.unlink(&path).map_err(io::Error::from) {
+                Ok(()) => Ok(true),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+                Err(e) => Err(e),
+            }
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.delete_prefix",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                prefix = %prefix.as_ref().display()
+            )
+        )
+    )]
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> io::Result<DeleteSummary> {
+        self.check_writable("delete")?;
+
+        let root = self.resolve("./")?;
+        let prefix = self.resolve(prefix)?;
+        self.run("remi_sftp::delete_prefix", move |sftp| {
+            let mut summary = DeleteSummary::default();
+            match stat(sftp, &prefix)? {
+                None => return Ok(summary),
+                Some(stat) if !stat.is_dir() => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("path [{}] is not a directory", prefix.display()),
+                    ));
+                }
+
+                Some(_) => {}
+            }
+
+            let mut pending = vec![prefix];
+            let mut directories = vec![];
+            while let Some(directory) = pending.pop() {
+                for (path, stat) in sftp.readdir(&directory)? {
+                    if stat.is_dir() {
+                        pending.push(path);
+                        continue;
+                    }
+
+                    match sftp.unlink(&path) {
+                        Ok(()) => summary.deleted += 1,
+
+                        #[allow(unused)]
+                        Err(e) => {
+                            #[cfg(feature = "tracing")]
+                            ::tracing::warn!(error = %e, path = %path.display(), "failed to delete file");
+
+                            #[cfg(feature = "log")]
+                            ::log::warn!("failed to delete file [{}]: {e}", path.display());
+
+                            summary.failed.push(path.display().to_string());
+                        }
+                    }
+                }
+
+                directories.push(directory);
+            }
+
+            // directories that still have files that failed to be deleted are kept, and the
+            // directory of this storage service is never removed
+            for directory in directories.into_iter().rev() {
+                if directory != root {
+                    let _ = sftp.rmdir(&directory);
+                }
+            }
+
+            Ok(summary)
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.rename",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                from = %from.as_ref().display(),
+                to = %to.as_ref().display()
+            )
+        )
+    )]
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> io::Result<bool> {
+        self.check_writable("rename")?;
+        check_key(from.as_ref())?;
+        check_key(to.as_ref())?;
+
+        let from = self.resolve(from)?;
+        let to = self.resolve(to)?;
+        self.run("remi_sftp::rename", move |sftp| {
+            if !stat(sftp, &from)?.is_some_and(|stat| stat.is_file()) {
+                return Ok(false);
+            }
+
+            if from == to {
+                return Ok(true);
+            }
+
+            if let Some(parent) = to.parent() {
+                create_dir_all(sftp, parent)?;
+            }
+
+            rename_over(sftp, &from, &to).map(|_| true)
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.exists",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<bool> {
+        let path = self.resolve(path)?;
+        self.run("remi_sftp::exists", move |sftp| {
+            stat(sftp, &path).map(|stat| stat.is_some())
+        })
+        .await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.upload",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> io::Result<()> {
+        let path = path.as_ref();
+        match options.collision() {
+            None | Some(CollisionStrategy::Overwrite) => self.write(path, options, false).await,
+            Some(CollisionStrategy::Error) => self.write(path, options, true).await,
+            Some(CollisionStrategy::NewVersion) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "SFTP servers can't keep previous versions of files",
+            )),
+
+            Some(strategy @ CollisionStrategy::Suffix(_)) => {
+                for candidate in strategy.candidates(path) {
+                    match self.write(&candidate, options.clone(), true).await {
+                        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                        result => return result,
+                    }
+                }
+
+                Err(already_exists(&self.resolve(path)?))
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.create_exclusive",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn create_exclusive<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> io::Result<()> {
+        self.write(path.as_ref(), options, true).await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.create_dir",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                path = %path.as_ref().display()
+            )
+        )
+    )]
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<()> {
+        self.check_writable("create_dir")?;
+
+        let path = self.resolve(path)?;
+        self.run("remi_sftp::create_dir", move |sftp| create_dir_all(sftp, &path))
+            .await
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.config.read_only
+    }
+
+    fn info(&self) -> ServiceInfo {
+        ServiceInfo::new(self.name())
+            .with_crate_version(env!("CARGO_PKG_VERSION"))
+            .with_provider_endpoint(Some(format!("sftp://{}:{}", self.config.host, self.config.port)))
+            .with_location(Some(self.config.directory.display().to_string()))
+            .with_capabilities(Capabilities {
+                range_reads: true,
+                pagination: true,
+                ..Default::default()
+            })
+    }
+
+    /// Connects and authenticates a new SSH session, which is closed right after, so that
+    /// the session that is shared between clones of this storage service isn't reused.
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = "remi.sftp.verify_credentials",
+            skip_all,
+            fields(
+                remi.service = "sftp",
+                host = %self.config.host
+            )
+        )
+    )]
+    async fn verify_credentials(&self) -> io::Result<()> {
+        let config = self.config.clone();
+        remi::rt::spawn_blocking("remi_sftp::verify_credentials", move || connect(&config).map(drop)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StorageService;
+    use crate::{Credentials, StorageConfig};
+    use remi::{StorageService as _, UploadRequest};
+    use std::{io, path::PathBuf};
+
+    fn storage(directory: &str) -> StorageService {
+        StorageService::new(StorageConfig {
+            directory: directory.into(),
+            ..StorageConfig::new("localhost", "noel", Credentials::Agent)
+        })
+    }
+
+    #[test]
+    fn resolve() {
+        let storage = storage("/srv/data");
+        assert_eq!(
+            storage.resolve("./a/b.txt").unwrap(),
+            PathBuf::from("/srv/data/a/b.txt")
+        );
+        assert_eq!(storage.resolve("~/a.txt").unwrap(), PathBuf::from("/srv/data/a.txt"));
+        assert_eq!(storage.resolve("/a.txt").unwrap(), PathBuf::from("/srv/data/a.txt"));
+        assert_eq!(storage.resolve("a.txt").unwrap(), PathBuf::from("/srv/data/a.txt"));
+
+        let err = storage.resolve("./a/../../etc/passwd").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // relative to the login directory
+        let storage = self::storage("");
+        assert_eq!(storage.resolve("./").unwrap(), PathBuf::from("."));
+        assert_eq!(storage.resolve("./a.txt").unwrap(), PathBuf::from("a.txt"));
+    }
+
+    #[tokio::test]
+    async fn read_only() {
+        let storage = StorageService::new(StorageConfig {
+            read_only: true,
+            ..StorageConfig::new("localhost", "noel", Credentials::Agent)
+        });
+
+        // rejected before connecting to the server
+        let err = storage.upload("./a.txt", UploadRequest::default()).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(err.get_ref().is_some_and(|e| e.is::<remi::ReadOnly>()));
+
+        let err = storage.delete("./a.txt").await.unwrap_err();
+        assert!(err.get_ref().is_some_and(|e| e.is::<remi::ReadOnly>()));
+    }
+
+    #[test]
+    fn info() {
+        let info = storage("/srv/data").info();
+        assert_eq!(info.name, "remi:sftp");
+        assert_eq!(info.provider_endpoint.as_deref(), Some("sftp://localhost:22"));
+        assert_eq!(info.location.as_deref(), Some("/srv/data"));
+    }
+}
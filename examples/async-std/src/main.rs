@@ -0,0 +1,57 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `remi-async-std` uses a filesystem storage service on the [async-std](https://async.rs)
+//! runtime, without depending on Tokio:
+//!
+//! ```shell
+//! $ cargo run -p remi-async-std
+//! ```
+
+use remi::{StorageService as _, UploadRequest};
+use remi_fs::StorageService;
+use std::io;
+
+fn main() -> io::Result<()> {
+    async_std::task::block_on(async {
+        let directory = std::env::temp_dir().join("remi-async-std");
+        let storage = StorageService::new(&directory);
+        storage.init().await?;
+
+        storage
+            .upload(
+                "./weow.txt",
+                UploadRequest::default()
+                    .with_content_type(Some("text/plain"))
+                    .with_data("weow fluff"),
+            )
+            .await?;
+
+        let contents = storage.open("./weow.txt").await?.expect("`weow.txt` to exist");
+        println!(
+            "wrote {} bytes into [{}]",
+            contents.len(),
+            directory.join("weow.txt").display()
+        );
+
+        Ok(())
+    })
+}
@@ -0,0 +1,61 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `remi-console` keeps a filesystem storage service busy so that its tasks can be inspected
+//! with [tokio-console](https://github.com/tokio-rs/console). Task names are only recorded
+//! when compiled with `--cfg tokio_unstable`:
+//!
+//! ```shell
+//! $ RUSTFLAGS="--cfg tokio_unstable" cargo run -p remi-console
+//! $ tokio-console
+//! ```
+//!
+//! Every task that remi spawns is named after the subsystem that owns it, like
+//! `remi_fs::write` or `remi::rt::fs::read`.
+
+use remi::{StorageService as _, UploadRequest};
+use remi_fs::StorageService;
+use std::{io, time::Duration};
+
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    console_subscriber::init();
+
+    let directory = std::env::temp_dir().join("remi-console");
+    let storage = StorageService::new(&directory);
+    storage.init().await?;
+
+    eprintln!("writing files into [{}], press Ctrl+C to exit", directory.display());
+
+    let mut round = 0usize;
+    loop {
+        let path = format!("./file-{}.txt", round % 16);
+        storage
+            .upload(&path, UploadRequest::default().with_data(format!("round {round}")))
+            .await?;
+
+        storage.open(&path).await?;
+        storage.blobs::<&str>(None, None).await?;
+
+        round += 1;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
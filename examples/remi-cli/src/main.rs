@@ -0,0 +1,249 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `remi-cli` is an example CLI that can exercise any official storage service from
+//! a storage URI. It is used as living documentation and as a manual integration test
+//! harness:
+//!
+//! ```shell
+//! $ cargo run -p remi-cli -- --storage fs://./data put ./Cargo.toml ./Cargo.toml
+//! $ cargo run -p remi-cli -- --storage fs://./data ls
+//! ```
+
+mod storage;
+
+use clap::{Parser, Subcommand};
+use remi::{Blob, ListBlobsRequest, PresignMethod, PresignOptions, UploadRequest};
+use std::{path::PathBuf, process::ExitCode, time::Duration};
+use storage::{Error, Storage};
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Parser)]
+#[command(name = "remi-cli", about = "🐻‍❄️🧶 Exercise any official remi-rs storage service")]
+struct Program {
+    /// Storage URI to connect to, i.e, `fs://./data`, `s3://bucket/prefix`, `gridfs://bucket`,
+    /// or `azure://account/container`.
+    #[arg(long, short = 's', env = "REMI_STORAGE")]
+    storage: String,
+
+    /// Calls `StorageService::init` before running the command, which might create the
+    /// bucket, container, or directory.
+    #[arg(long, default_value_t = false)]
+    init: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Lists all blobs in the storage service.
+    Ls {
+        /// Path to list blobs from.
+        path: Option<PathBuf>,
+
+        /// Prefix to filter blobs with.
+        #[arg(long)]
+        prefix: Option<String>,
+
+        /// Whether if directories should be included.
+        #[arg(long, short = 'd', default_value_t = false)]
+        dirs: bool,
+    },
+
+    /// Retrieves the contents of a file and writes it to stdout or to a local file.
+    Get {
+        /// Path to the file in the storage service.
+        path: PathBuf,
+
+        /// Local file to write the contents into, otherwise the contents are written to stdout.
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+
+    /// Uploads a local file into the storage service.
+    Put {
+        /// Local file to upload.
+        file: PathBuf,
+
+        /// Path to upload the file as in the storage service.
+        path: PathBuf,
+
+        /// Content type of the file.
+        #[arg(long)]
+        content_type: Option<String>,
+    },
+
+    /// Deletes a file from the storage service.
+    Rm {
+        /// Path to the file in the storage service.
+        path: PathBuf,
+    },
+
+    /// Prints the metadata of a file or directory.
+    Stat {
+        /// Path to the file in the storage service.
+        path: PathBuf,
+    },
+
+    /// Generates a presigned URL for a file, which is only supported by Azure and S3.
+    Presign {
+        /// Path to the file in the storage service.
+        path: PathBuf,
+
+        /// How long the URL is valid for, in seconds.
+        #[arg(long, default_value_t = 900)]
+        expires_in: u64,
+
+        /// Whether if the URL should be used to upload the file with a `PUT` request instead
+        /// of downloading it.
+        #[arg(long, default_value_t = false)]
+        put: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let program = Program::parse();
+    match run(program).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("[remi-cli] error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(program: Program) -> Result<(), Error> {
+    let storage = Storage::connect(&program.storage).await?;
+    if program.init {
+        storage.init().await?;
+    }
+
+    match program.command {
+        Command::Ls { path, prefix, dirs } => {
+            let mut request = ListBlobsRequest::default().with_prefix(prefix);
+            request.with_include_dirs(dirs);
+
+            for blob in storage.blobs(path.as_deref(), request).await? {
+                match blob {
+                    Blob::Directory(dir) => println!("{dir}"),
+                    Blob::File(file) => println!("{file}"),
+                }
+            }
+
+            Ok(())
+        }
+
+        Command::Get { path, output } => {
+            let Some(data) = storage.open(&path).await? else {
+                return Err(format!("file [{}] doesn't exist", path.display()).into());
+            };
+
+            match output {
+                Some(output) => tokio::fs::write(output, data).await?,
+                None => {
+                    let mut stdout = tokio::io::stdout();
+                    stdout.write_all(&data).await?;
+                    stdout.flush().await?;
+                }
+            }
+
+            Ok(())
+        }
+
+        Command::Put {
+            file,
+            path,
+            content_type,
+        } => {
+            let data = tokio::fs::read(&file).await?;
+            storage
+                .upload(
+                    &path,
+                    UploadRequest::default().with_content_type(content_type).with_data(data),
+                )
+                .await?;
+
+            println!("uploaded [{}] ~> [{}]", file.display(), path.display());
+            Ok(())
+        }
+
+        Command::Rm { path } => {
+            match storage.delete(&path).await? {
+                true => println!("deleted [{}]", path.display()),
+                false => println!("file [{}] doesn't exist", path.display()),
+            }
+
+            Ok(())
+        }
+
+        Command::Stat { path } => match storage.blob(&path).await? {
+            Some(Blob::Directory(dir)) => {
+                println!("{dir}");
+                Ok(())
+            }
+
+            Some(Blob::File(file)) => {
+                println!("{file}");
+                println!("  name:          {}", file.name);
+                println!("  size:          {} bytes", file.size);
+                println!(
+                    "  content type:  {}",
+                    file.content_type.as_deref().unwrap_or("<unknown>")
+                );
+                println!("  created at:    {:?}", file.created_at);
+                println!("  last modified: {:?}", file.last_modified_at);
+                println!("  checksum:      {}", file.checksum.as_deref().unwrap_or("<none>"));
+                println!("  etag:          {}", file.etag.as_deref().unwrap_or("<none>"));
+                println!("  version:       {}", file.version.as_deref().unwrap_or("<none>"));
+                if let Some(expires_at) = file.expires_at {
+                    println!("  expires at:    {expires_at}");
+                }
+
+                for (key, value) in &file.metadata {
+                    println!("  metadata[{key}] = {value}");
+                }
+
+                Ok(())
+            }
+
+            None => Err(format!("file [{}] doesn't exist", path.display()).into()),
+        },
+
+        Command::Presign { path, expires_in, put } => {
+            let method = if put { PresignMethod::Put } else { PresignMethod::Get };
+            let presigned = storage
+                .presign(
+                    &path,
+                    PresignOptions::new(Duration::from_secs(expires_in)).with_method(method),
+                )
+                .await?;
+
+            println!("{presigned}");
+            for (name, value) in &presigned.headers {
+                println!("  {name}: {value}");
+            }
+
+            Ok(())
+        }
+    }
+}
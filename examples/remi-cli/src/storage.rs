@@ -0,0 +1,212 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use remi::{Blob, Bytes, ListBlobsRequest, PresignOptions, PresignedUrl, StorageService as _, UploadRequest};
+use std::{env, path::Path};
+
+#[cfg(any(feature = "azure", feature = "s3"))]
+use remi::PresignableStorageService as _;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Represents one of the official storage services, selected from a storage URI.
+#[allow(clippy::large_enum_variant)] // only one is ever constructed
+pub enum Storage {
+    #[cfg(feature = "azure")]
+    Azure(remi_azure::StorageService),
+
+    #[cfg(feature = "gridfs")]
+    Gridfs(remi_gridfs::StorageService),
+
+    #[cfg(feature = "fs")]
+    Filesystem(remi_fs::StorageService),
+
+    #[cfg(feature = "s3")]
+    S3(remi_s3::StorageService),
+}
+
+// Since `remi::StorageService` isn't object-safe, we dispatch each operation
+// to the underlying storage service by hand.
+macro_rules! dispatch {
+    ($self:ident, |$storage:ident| $code:expr) => {
+        match $self {
+            #[cfg(feature = "azure")]
+            Storage::Azure($storage) => $code.await.map_err(Error::from),
+
+            #[cfg(feature = "gridfs")]
+            Storage::Gridfs($storage) => $code.await.map_err(Error::from),
+
+            #[cfg(feature = "fs")]
+            Storage::Filesystem($storage) => $code.await.map_err(Error::from),
+
+            #[cfg(feature = "s3")]
+            Storage::S3($storage) => $code.await.map_err(Error::from),
+        }
+    };
+}
+
+impl Storage {
+    /// Creates a [`Storage`] from a storage URI:
+    ///
+    /// * `fs://{directory}` ~ uses the local filesystem, i.e, `fs://./data` or `fs:///var/lib/data`
+    /// * `s3://{bucket}[/{prefix}]` ~ uses the `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, `AWS_REGION`
+    ///   and `AWS_ENDPOINT_URL` environment variables
+    /// * `gridfs://{bucket}` ~ uses the `REMI_MONGODB_URI` and `REMI_MONGODB_DATABASE` environment variables
+    /// * `azure://{account}/{container}` ~ uses the `AZURE_STORAGE_ACCESS_KEY` or `AZURE_STORAGE_SAS_TOKEN`
+    ///   environment variables, otherwise anonymous access is used
+    pub async fn connect(uri: &str) -> Result<Storage, Error> {
+        let Some((scheme, rest)) = uri.split_once("://") else {
+            return Err(format!("storage uri [{uri}] is missing a scheme (i.e, `fs://`)").into());
+        };
+
+        match scheme {
+            #[cfg(feature = "fs")]
+            "fs" | "file" => Ok(Storage::Filesystem(remi_fs::StorageService::new(rest))),
+
+            #[cfg(feature = "s3")]
+            "s3" => {
+                let (bucket, prefix) = match rest.split_once('/') {
+                    Some((bucket, prefix)) if !prefix.is_empty() => (bucket, Some(prefix.to_owned())),
+                    Some((bucket, _)) => (bucket, None),
+                    None => (rest, None),
+                };
+
+                Ok(Storage::S3(remi_s3::StorageService::new(remi_s3::StorageConfig {
+                    access_key_id: env::var("AWS_ACCESS_KEY_ID").unwrap_or_default(),
+                    secret_access_key: env::var("AWS_SECRET_ACCESS_KEY").unwrap_or_default().into(),
+                    enforce_path_access_style: env::var("AWS_ENDPOINT_URL").is_ok(),
+                    endpoint: env::var("AWS_ENDPOINT_URL").ok(),
+                    region: Some(remi_s3::aws::s3::config::Region::new(
+                        env::var("AWS_REGION").unwrap_or_else(|_| String::from("us-east-1")),
+                    )),
+
+                    bucket: bucket.to_owned(),
+                    prefix,
+
+                    ..Default::default()
+                })?))
+            }
+
+            #[cfg(feature = "gridfs")]
+            "gridfs" => {
+                let conn = env::var("REMI_MONGODB_URI").unwrap_or_else(|_| String::from("mongodb://localhost:27017"));
+                let service = remi_gridfs::StorageService::from_conn_string(
+                    conn,
+                    remi_gridfs::StorageConfig {
+                        database: env::var("REMI_MONGODB_DATABASE").ok(),
+                        bucket: rest.to_owned(),
+
+                        ..Default::default()
+                    },
+                )
+                .await?;
+
+                Ok(Storage::Gridfs(service))
+            }
+
+            #[cfg(feature = "azure")]
+            "azure" => {
+                let Some((account, container)) = rest.split_once('/') else {
+                    return Err("expected storage uri to be in the form of `azure://{account}/{container}`".into());
+                };
+
+                let credentials = if let Ok(access_key) = env::var("AZURE_STORAGE_ACCESS_KEY") {
+                    remi_azure::Credential::AccessKey {
+                        account: account.to_owned(),
+                        access_key: access_key.into(),
+                    }
+                } else if let Ok(token) = env::var("AZURE_STORAGE_SAS_TOKEN") {
+                    remi_azure::Credential::SASToken(token.into())
+                } else {
+                    remi_azure::Credential::Anonymous
+                };
+
+                Ok(Storage::Azure(remi_azure::StorageService::new(
+                    remi_azure::StorageConfig {
+                        credentials,
+                        location: remi_azure::CloudLocation::Public(account.to_owned()),
+                        container: container.to_owned(),
+                        containers: Vec::new(),
+                        prefix: None,
+                        include_prefix: false,
+                        directory_marker: None,
+                        max_results: None,
+                        delimiter: None,
+                        access_tier: None,
+                        verify_writes: false,
+                        max_object_size: None,
+                        read_only: false,
+                        warn_on_truncated_reads: false,
+                        case_insensitive_lookup: false,
+                        strict_memory_limit: None,
+                        memory_limit_fail_fast: false,
+                        skip_provisioning: false,
+                        network: Default::default(),
+                    },
+                )?))
+            }
+
+            scheme => Err(format!("unknown or disabled storage scheme `{scheme}://`").into()),
+        }
+    }
+
+    pub async fn init(&self) -> Result<(), Error> {
+        dispatch!(self, |storage| storage.init())
+    }
+
+    pub async fn open(&self, path: &Path) -> Result<Option<Bytes>, Error> {
+        dispatch!(self, |storage| storage.open(path))
+    }
+
+    pub async fn blob(&self, path: &Path) -> Result<Option<Blob>, Error> {
+        dispatch!(self, |storage| storage.blob(path))
+    }
+
+    pub async fn blobs(&self, path: Option<&Path>, request: ListBlobsRequest) -> Result<Vec<Blob>, Error> {
+        dispatch!(self, |storage| storage.blobs(path, Some(request)))
+    }
+
+    pub async fn delete(&self, path: &Path) -> Result<bool, Error> {
+        dispatch!(self, |storage| storage.delete(path))
+    }
+
+    pub async fn upload(&self, path: &Path, request: UploadRequest) -> Result<(), Error> {
+        dispatch!(self, |storage| storage.upload(path, request))
+    }
+
+    /// Presigns a URL for the file in `path`, which only Azure and S3 support.
+    pub async fn presign(&self, path: &Path, options: PresignOptions) -> Result<PresignedUrl, Error> {
+        match self {
+            #[cfg(feature = "azure")]
+            Storage::Azure(storage) => storage.presign(path, options).await.map_err(Error::from),
+
+            #[cfg(feature = "s3")]
+            Storage::S3(storage) => storage.presign(path, options).await.map_err(Error::from),
+
+            #[allow(unreachable_patterns)]
+            _ => Err(format!(
+                "unable to presign [{}]: storage service doesn't support presigned URLs",
+                path.display()
+            )
+            .into()),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use remi_fs::StorageService;
+use std::{path::PathBuf, sync::OnceLock};
+
+static STORAGE: OnceLock<(StorageService, PathBuf)> = OnceLock::new();
+
+fuzz_target!(|data: &[u8]| {
+    let (storage, directory) = STORAGE.get_or_init(|| {
+        let directory = std::env::temp_dir().canonicalize().unwrap();
+        (StorageService::new(&directory), directory)
+    });
+
+    let path = remi_fuzz::path(data);
+    let Ok(normalized) = storage.normalize(path) else {
+        return;
+    };
+
+    if !path.starts_with("./") || remi_fuzz::has_parent_segment(path) {
+        return;
+    }
+
+    assert!(
+        normalized.starts_with(directory),
+        "{} normalized to {}, which escapes {}",
+        path.display(),
+        normalized.display(),
+        directory.display()
+    );
+
+    if remi::InvalidKey::check(path).is_ok() {
+        assert_ne!(
+            &normalized,
+            directory,
+            "file key {} normalized to the root",
+            path.display()
+        );
+    }
+});
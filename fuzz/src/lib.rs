@@ -0,0 +1,69 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Helpers that are shared between the fuzz targets of remi-rs. Every target turns the
+//! raw input into a [`Path`] with [`path`], so that paths that aren't valid UTF-8 are
+//! fuzzed as well.
+
+use std::{
+    ffi::OsStr,
+    os::unix::ffi::OsStrExt,
+    path::{Component, Path},
+};
+
+/// Returns the raw fuzzer input as a [`Path`].
+pub fn path(data: &[u8]) -> &Path {
+    Path::new(OsStr::from_bytes(data))
+}
+
+/// Whether if `path` has a `..` segment anywhere, which is the only way that a path
+/// can escape the root or prefix of a storage service.
+pub fn has_parent_segment(path: &Path) -> bool {
+    path.components()
+        .any(|component| matches!(component, Component::ParentDir))
+}
+
+/// Checks the key that a storage service resolved `path` into, where `root` is the prefix
+/// that every key must start with. A path without any `..` segments must not gain one and
+/// a path that is a valid file key must never resolve to a directory.
+pub fn check_resolved(root: &str, path: &Path, resolved: &str) {
+    assert!(
+        resolved.starts_with(root),
+        "{} resolved to [{resolved}], which escapes [{root}]",
+        path.display()
+    );
+
+    if !has_parent_segment(path) {
+        assert!(
+            !resolved.split('/').any(|segment| segment == ".."),
+            "{} resolved to [{resolved}], which has a `..` segment",
+            path.display()
+        );
+    }
+
+    if remi::InvalidKey::check(path).is_ok() {
+        assert!(
+            !resolved.ends_with('/') && resolved != root,
+            "file key {} resolved to directory [{resolved}]",
+            path.display()
+        );
+    }
+}
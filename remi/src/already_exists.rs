@@ -0,0 +1,45 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fmt::Display;
+
+/// Error that storage services return from [`StorageService::create_exclusive`][crate::StorageService::create_exclusive]
+/// when a file already exists in the path that it was going to be created in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlreadyExists {
+    /// Path of the file that already exists, usually `{service}://{full filepath}`.
+    pub path: String,
+}
+
+impl AlreadyExists {
+    /// Creates a new [`AlreadyExists`] error for a file in `path`.
+    pub fn new<I: Into<String>>(path: I) -> AlreadyExists {
+        AlreadyExists { path: path.into() }
+    }
+}
+
+impl Display for AlreadyExists {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "file [{}] already exists", self.path)
+    }
+}
+
+impl std::error::Error for AlreadyExists {}
@@ -0,0 +1,316 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Helpers that package a local directory as a single archive in a storage service and
+//! extract it again, for artifact bundles that are made out of many small files.
+
+use crate::{rt, StorageService, UploadRequest};
+use bytes::Bytes;
+use futures_util::TryStreamExt;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+};
+
+/// Format of an archive that is created by [`upload_archive`].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ArchiveFormat {
+    /// gzip-compressed tarball (`.tar.gz`), which keeps Unix permissions.
+    #[default]
+    TarGz,
+
+    /// ZIP archive with every file compressed with DEFLATE.
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Returns the content type that the archive is uploaded with.
+    pub const fn content_type(self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "application/gzip",
+            ArchiveFormat::Zip => "application/zip",
+        }
+    }
+
+    /// Detects the format of an archive from its first bytes, or returns `None` if
+    /// it isn't an archive that can be extracted.
+    pub fn detect(data: &[u8]) -> Option<ArchiveFormat> {
+        match data {
+            [0x1f, 0x8b, ..] => Some(ArchiveFormat::TarGz),
+            [b'P', b'K', 0x03, 0x04, ..] | [b'P', b'K', 0x05, 0x06, ..] => Some(ArchiveFormat::Zip),
+            _ => None,
+        }
+    }
+}
+
+/// Error from [`upload_archive`] and [`extract_archive`].
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub enum ArchiveError<E> {
+    /// The storage service failed to upload or download the archive.
+    Storage(E),
+
+    /// Reading or writing the local directory, or the archive itself, failed.
+    Io(io::Error),
+
+    /// The archive at the given path doesn't exist in the storage service.
+    NotFound(PathBuf),
+}
+
+impl<E: Display> Display for ArchiveError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Storage(e) => Display::fmt(e, f),
+            ArchiveError::Io(e) => write!(f, "failed to package archive: {e}"),
+            ArchiveError::NotFound(path) => write!(f, "archive {} doesn't exist", path.display()),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for ArchiveError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ArchiveError::Storage(e) => Some(e),
+            ArchiveError::Io(e) => Some(e),
+            ArchiveError::NotFound(_) => None,
+        }
+    }
+}
+
+impl<E> From<io::Error> for ArchiveError<E> {
+    fn from(e: io::Error) -> Self {
+        ArchiveError::Io(e)
+    }
+}
+
+/// Packages every file in `local_dir` into a single archive and uploads it to `remote_path`.
+///
+/// The archive is built on a blocking task while the directory is walked, so nothing is
+/// written to a temporary file. Because [`UploadRequest`] carries its data as one [`Bytes`]
+/// container, the whole archive is kept in memory until it's uploaded.
+///
+/// Symbolic links are archived as links and aren't followed.
+///
+/// * since: 0.11.0
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{upload_archive, ArchiveFormat};
+/// #
+/// upload_archive(&storage, "./target/doc", "./bundles/docs.tar.gz", ArchiveFormat::TarGz).await?;
+/// ```
+pub async fn upload_archive<S, P, Q>(
+    service: &S,
+    local_dir: P,
+    remote_path: Q,
+    format: ArchiveFormat,
+) -> Result<(), ArchiveError<S::Error>>
+where
+    S: StorageService,
+    P: AsRef<Path>,
+    Q: AsRef<Path> + Send,
+{
+    let local_dir = local_dir.as_ref().to_owned();
+    let data = rt::spawn_blocking("remi::archive::pack", move || pack(&local_dir, format)).await?;
+
+    service
+        .upload(
+            remote_path,
+            UploadRequest::default()
+                .with_content_type(Some(format.content_type()))
+                .with_data(data),
+        )
+        .await
+        .map_err(ArchiveError::Storage)
+}
+
+/// Downloads the archive at `remote_path` and extracts it into `local_dir`, which is
+/// created if it doesn't exist. The format is detected from the archive's contents.
+///
+/// The archive is read with [`open_stream`][StorageService::open_stream] and extracted on
+/// a blocking task without being written to a temporary file. Entries that would be written
+/// outside of `local_dir` (like `../etc/passwd`) are skipped.
+///
+/// * since: 0.11.0
+pub async fn extract_archive<S, P, Q>(service: &S, remote_path: P, local_dir: Q) -> Result<(), ArchiveError<S::Error>>
+where
+    S: StorageService,
+    S::Error: Send + 'static,
+    P: AsRef<Path> + Send,
+    Q: AsRef<Path>,
+{
+    let remote_path = remote_path.as_ref();
+    let Some(stream) = service.open_stream(remote_path).await.map_err(ArchiveError::Storage)? else {
+        return Err(ArchiveError::NotFound(remote_path.to_owned()));
+    };
+
+    let chunks = stream.try_collect::<Vec<_>>().await.map_err(ArchiveError::Storage)?;
+    let data = chunks.concat();
+
+    let local_dir = local_dir.as_ref().to_owned();
+    rt::spawn_blocking("remi::archive::unpack", move || unpack(&data, &local_dir)).await?;
+
+    Ok(())
+}
+
+fn pack(dir: &Path, format: ArchiveFormat) -> io::Result<Bytes> {
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files)?;
+
+    let data = match format {
+        ArchiveFormat::TarGz => {
+            let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            builder.follow_symlinks(false);
+
+            for (name, path) in files {
+                builder.append_path_with_name(path, name)?;
+            }
+
+            builder.into_inner()?.finish()?
+        }
+
+        ArchiveFormat::Zip => {
+            let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+            let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+            for (name, path) in files {
+                let name = name.to_string_lossy().replace('\\', "/");
+                writer.start_file(name, options).map_err(zip_error)?;
+                io::copy(&mut fs::File::open(path)?, &mut writer)?;
+            }
+
+            writer.finish().map_err(zip_error)?.into_inner()
+        }
+    };
+
+    Ok(Bytes::from(data))
+}
+
+/// Collects every file under `dir` with its path relative to `root`, sorted so that
+/// the same directory always produces the same archive.
+fn walk(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            walk(root, &path, files)?;
+            continue;
+        }
+
+        let name = path.strip_prefix(root).map_err(io::Error::other)?.to_owned();
+        files.push((name, path));
+    }
+
+    Ok(())
+}
+
+fn unpack(data: &[u8], dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    match ArchiveFormat::detect(data) {
+        Some(ArchiveFormat::TarGz) => tar::Archive::new(flate2::read::GzDecoder::new(data)).unpack(dir),
+        Some(ArchiveFormat::Zip) => {
+            let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(zip_error)?;
+            for i in 0..archive.len() {
+                let mut entry = archive.by_index(i).map_err(zip_error)?;
+                let Some(name) = entry.enclosed_name() else {
+                    continue;
+                };
+
+                let path = dir.join(name);
+                if entry.is_dir() {
+                    fs::create_dir_all(&path)?;
+                    continue;
+                }
+
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                io::copy(&mut entry, &mut fs::File::create(&path)?)?;
+            }
+
+            Ok(())
+        }
+
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "file is not a .tar.gz or .zip archive",
+        )),
+    }
+}
+
+fn zip_error(e: zip::result::ZipError) -> io::Error {
+    match e {
+        zip::result::ZipError::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pack, unpack, ArchiveFormat};
+    use std::{fs, path::PathBuf};
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("remi-archive-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn detect_formats() {
+        assert_eq!(ArchiveFormat::detect(&[0x1f, 0x8b, 0x08]), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::detect(b"PK\x03\x04"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::detect(b"hello"), None);
+        assert_eq!(ArchiveFormat::detect(&[]), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in [ArchiveFormat::TarGz, ArchiveFormat::Zip] {
+            let source = scratch(&format!("{format:?}-source"));
+            fs::create_dir_all(source.join("nested/deeper")).unwrap();
+            fs::write(source.join("a.txt"), "a").unwrap();
+            fs::write(source.join("nested/deeper/b.txt"), "b").unwrap();
+
+            let data = pack(&source, format).unwrap();
+            assert_eq!(ArchiveFormat::detect(&data), Some(format));
+
+            let target = scratch(&format!("{format:?}-target"));
+            unpack(&data, &target).unwrap();
+            assert_eq!(fs::read_to_string(target.join("a.txt")).unwrap(), "a");
+            assert_eq!(fs::read_to_string(target.join("nested/deeper/b.txt")).unwrap(), "b");
+
+            fs::remove_dir_all(source).unwrap();
+            fs::remove_dir_all(target).unwrap();
+        }
+    }
+}
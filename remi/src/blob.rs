@@ -19,11 +19,13 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use crate::{Extensions, Metadata, UploadRequest};
 use bytes::Bytes;
 use std::{collections::HashMap, fmt::Display};
 
 /// Represents a file or directory from any storage service.
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // boxing `File` would be a breaking change
 pub enum Blob {
     /// Represents a directory that was located somewhere.
     Directory(Directory),
@@ -32,6 +34,18 @@ pub enum Blob {
     File(File),
 }
 
+impl From<File> for Blob {
+    fn from(file: File) -> Self {
+        Blob::File(file)
+    }
+}
+
+impl From<Directory> for Blob {
+    fn from(directory: Directory) -> Self {
+        Blob::Directory(directory)
+    }
+}
+
 /// Representation of a [`Blob`] that is a file.
 #[derive(Debug, Clone)]
 pub struct File {
@@ -55,6 +69,29 @@ pub struct File {
     /// in the filesystem crate of remi.
     pub is_symlink: bool,
 
+    /// Checksum of the file's contents, if the storage service provides one. This is the
+    /// MD5 digest of the contents in hexadecimal unless the storage service documents
+    /// otherwise.
+    pub checksum: Option<String>,
+
+    /// Entity tag of the file, which changes whenever its contents change and can be
+    /// compared with [`StorageService::open_if_none_match`][crate::StorageService::open_if_none_match].
+    /// It doesn't include the surrounding quotes of an HTTP `ETag` header.
+    ///
+    /// * since: 0.11.0
+    pub etag: Option<String>,
+
+    /// Version identifier of the file, if the storage service supports versioning.
+    pub version: Option<String>,
+
+    /// Returns a `u128` of when the storage service will delete this file because of a
+    /// lifecycle or expiry policy, in milliseconds from January 1st, 1970.
+    pub expires_at: Option<u128>,
+
+    /// Storage service-specific data that was attached to this file, like the full
+    /// metadata document of a GridFS file.
+    pub extras: Extensions,
+
     /// Given [`Bytes`] container that is the actual data in the file.
     pub data: Bytes,
 
@@ -68,6 +105,51 @@ pub struct File {
     pub size: usize,
 }
 
+impl File {
+    /// Returns the [`Metadata`] of this file without cloning its contents.
+    pub fn to_metadata(&self) -> Metadata {
+        Metadata::from(self)
+    }
+
+    /// Returns an [`UploadRequest`] that re-uploads this file with its data, content type,
+    /// and metadata. See [`UploadRequest::from_file`].
+    pub fn to_upload_request(&self) -> UploadRequest {
+        UploadRequest::from_file(self)
+    }
+
+    /// Returns the full metadata document of this file if the storage service stores
+    /// metadata as BSON. Unlike [`metadata`][File::metadata], this keeps values that
+    /// aren't strings.
+    ///
+    /// * since: 0.11.0
+    #[cfg(feature = "bson")]
+    pub fn raw_metadata(&self) -> Option<&bson::Document> {
+        self.extras.get::<crate::RawMetadata>().map(|raw| &raw.0)
+    }
+
+    /// Splits this file into its [`Metadata`] and the [`Bytes`] container of its contents.
+    pub fn into_parts(self) -> (Metadata, Bytes) {
+        (
+            Metadata {
+                last_modified_at: self.last_modified_at,
+                content_type: self.content_type,
+                created_at: self.created_at,
+                metadata: self.metadata,
+                is_symlink: self.is_symlink,
+                checksum: self.checksum,
+                etag: self.etag,
+                version: self.version,
+                expires_at: self.expires_at,
+                extras: self.extras,
+                name: self.name,
+                path: self.path,
+                size: self.size,
+            },
+            self.data,
+        )
+    }
+}
+
 impl Display for File {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // file "file:///assets/openapi.json" (12345 bytes) | application/json; charset=utf-8
@@ -99,3 +181,41 @@ impl Display for Directory {
         write!(f, "directory {}", self.path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Metadata;
+    use bytes::Bytes;
+    use std::collections::HashMap;
+
+    #[cfg(feature = "bson")]
+    #[test]
+    fn raw_metadata_roundtrip() {
+        let mut file = Metadata::default().with_data(Bytes::new());
+        file.extras.insert(crate::RawMetadata(
+            bson::doc! { "downloads": 42_i64, "tags": ["a", "b"] },
+        ));
+
+        let request = file.to_upload_request();
+        assert_eq!(file.raw_metadata(), request.raw_metadata());
+        assert_eq!(
+            request.raw_metadata().and_then(|doc| doc.get_i64("downloads").ok()),
+            Some(42)
+        );
+    }
+
+    #[test]
+    fn to_upload_request() {
+        let file = Metadata {
+            content_type: Some(String::from("application/json")),
+            metadata: HashMap::from([(String::from("owner"), String::from("noel"))]),
+            ..Default::default()
+        }
+        .with_data(Bytes::from_static(b"{}"));
+
+        let request = file.to_upload_request();
+        assert_eq!(request.content_type, file.content_type);
+        assert_eq!(request.metadata, file.metadata);
+        assert_eq!(request.data, file.data);
+    }
+}
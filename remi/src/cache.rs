@@ -0,0 +1,693 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, clamp_range, Blob, CollisionStrategy, ContentStream, HealthReport, ListBlobsRequest, Page,
+    ServiceInfo, Stats, StatsRecorder, StorageService, UploadRequest,
+};
+use bytes::Bytes;
+use futures_util::{future, stream, StreamExt};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::{self, Debug, Display},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// A store that a [`CachedStorageService`] keeps the contents of files in, keyed by the
+/// path that they were opened or uploaded with. Paths are normalized before they are given
+/// to the store, so `./weow.txt`, `weow.txt` and `/weow.txt` are the same file.
+///
+/// A store can evict files whenever it wants to, and [`CachedStorageService`] only logs
+/// the errors that it returns, since a cache that fails should never fail a request.
+///
+/// * since: 0.11.0
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// Returns the cached contents of the file in `path`, if there are any.
+    async fn get(&self, path: &Path) -> io::Result<Option<Bytes>>;
+
+    /// Caches `data` as the contents of the file in `path`, replacing what was cached before.
+    async fn insert(&self, path: &Path, data: Bytes) -> io::Result<()>;
+
+    /// Removes the cached contents of the file in `path`, so that it is read from the
+    /// storage service next time.
+    async fn invalidate(&self, path: &Path) -> io::Result<()>;
+}
+
+#[async_trait]
+impl<C: CacheStore + ?Sized> CacheStore for Arc<C> {
+    async fn get(&self, path: &Path) -> io::Result<Option<Bytes>> {
+        (**self).get(path).await
+    }
+
+    async fn insert(&self, path: &Path, data: Bytes) -> io::Result<()> {
+        (**self).insert(path, data).await
+    }
+
+    async fn invalidate(&self, path: &Path) -> io::Result<()> {
+        (**self).invalidate(path).await
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<PathBuf, Entry>,
+    size: u64,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    data: Bytes,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+/// [`CacheStore`] that keeps files in memory, with a least recently used eviction policy once
+/// the cache holds more than [`max_bytes`][MemoryCache::new] and an optional time to live.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{CachedStorageService, MemoryCache};
+/// # use std::time::Duration;
+/// #
+/// let cache = MemoryCache::new(64 * 1024 * 1024).with_ttl(Duration::from_secs(300));
+/// let storage = CachedStorageService::new(storage, cache);
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub struct MemoryCache {
+    max_bytes: u64,
+    ttl: Option<Duration>,
+    state: Mutex<State>,
+}
+
+impl MemoryCache {
+    /// Creates a new [`MemoryCache`] that holds at most `max_bytes`. Files that are larger
+    /// than that aren't cached.
+    pub fn new(max_bytes: u64) -> MemoryCache {
+        MemoryCache {
+            max_bytes,
+            ttl: None,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Sets how long a file stays cached after it was inserted. By default, files stay
+    /// cached until they are evicted.
+    pub fn with_ttl(mut self, ttl: Duration) -> MemoryCache {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Returns how many bytes are stored in the cache, including files that expired but
+    /// weren't looked up since.
+    pub fn size(&self) -> u64 {
+        self.state().size
+    }
+
+    /// Returns how many files are stored in the cache.
+    pub fn len(&self) -> usize {
+        self.state().entries.len()
+    }
+
+    /// Whether if the cache has no files.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("cache state to not be poisoned")
+    }
+}
+
+impl State {
+    fn remove(&mut self, path: &Path) -> Option<Entry> {
+        let entry = self.entries.remove(path)?;
+        self.size -= entry.data.len() as u64;
+
+        Some(entry)
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryCache {
+    async fn get(&self, path: &Path) -> io::Result<Option<Bytes>> {
+        let mut state = self.state();
+        let expired = match state.entries.get(path) {
+            Some(entry) => self.ttl.is_some_and(|ttl| entry.inserted_at.elapsed() >= ttl),
+            None => return Ok(None),
+        };
+
+        if expired {
+            state.remove(path);
+            return Ok(None);
+        }
+
+        state.tick += 1;
+        let tick = state.tick;
+        let entry = state.entries.get_mut(path).expect("entry to exist");
+        entry.last_used = tick;
+
+        Ok(Some(entry.data.clone()))
+    }
+
+    async fn insert(&self, path: &Path, data: Bytes) -> io::Result<()> {
+        let mut state = self.state();
+        state.remove(path);
+
+        let size = data.len() as u64;
+        if size > self.max_bytes {
+            return Ok(());
+        }
+
+        while state.size + size > self.max_bytes {
+            let Some(victim) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+            else {
+                break;
+            };
+
+            state.remove(&victim);
+        }
+
+        state.tick += 1;
+        state.size += size;
+
+        let last_used = state.tick;
+        state.entries.insert(
+            path.to_owned(),
+            Entry {
+                data,
+                inserted_at: Instant::now(),
+                last_used,
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn invalidate(&self, path: &Path) -> io::Result<()> {
+        self.state().remove(path);
+        Ok(())
+    }
+}
+
+type Observer = Arc<dyn Fn(&Path, bool) + Send + Sync>;
+
+/// Returns the path that `path` is cached with, which doesn't have a leading `./` or `/`,
+/// empty or `.` segments, or a trailing `/`.
+fn key(path: &Path) -> PathBuf {
+    let path = path.to_string_lossy();
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect::<Vec<_>>();
+
+    PathBuf::from(segments.join("/"))
+}
+
+/// A [`StorageService`] that caches the files of the storage service it wraps in a
+/// [`CacheStore`], which speeds up repeated reads of the same files.
+///
+/// Files are assumed to not be modified outside of this storage service: uploads and
+/// deletions made through it keep the cache up to date, but changes made directly on the
+/// remote are only picked up after calling [`CachedStorageService::invalidate`] (or when
+/// the cache evicts them). Failures of the cache are logged and never fail a request.
+///
+/// Cache hits and misses are counted in [`StorageService::stats`], and can be observed
+/// as they happen with [`CachedStorageService::with_observer`].
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{CachedStorageService, MemoryCache};
+/// #
+/// let storage = CachedStorageService::new(s3, MemoryCache::new(64 * 1024 * 1024));
+/// storage.open("./weow.txt").await?;
+/// ```
+///
+/// * since: 0.11.0
+pub struct CachedStorageService<S, C> {
+    remote: S,
+    cache: Arc<C>,
+    recorder: Arc<StatsRecorder>,
+    observer: Option<Observer>,
+}
+
+impl<S: Clone, C> Clone for CachedStorageService<S, C> {
+    fn clone(&self) -> Self {
+        CachedStorageService {
+            remote: self.remote.clone(),
+            cache: self.cache.clone(),
+            recorder: self.recorder.clone(),
+            observer: self.observer.clone(),
+        }
+    }
+}
+
+impl<S: Debug, C: Debug> Debug for CachedStorageService<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedStorageService")
+            .field("remote", &self.remote)
+            .field("cache", &self.cache)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: StorageService, C: CacheStore> CachedStorageService<S, C> {
+    /// Creates a new [`CachedStorageService`] that caches files from `remote` in `cache`.
+    pub fn new<I: Into<Arc<C>>>(remote: S, cache: I) -> CachedStorageService<S, C> {
+        CachedStorageService {
+            remote,
+            cache: cache.into(),
+            recorder: Arc::default(),
+            observer: None,
+        }
+    }
+
+    /// Calls `observer` with the path and whether if it was a cache hit every time that a
+    /// file is looked up in the cache, which can be used to export the hit ratio to a
+    /// metrics stack.
+    pub fn with_observer<F: Fn(&Path, bool) + Send + Sync + 'static>(mut self, observer: F) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Returns the storage service that files are cached from.
+    pub fn remote(&self) -> &S {
+        &self.remote
+    }
+
+    /// Returns the [`CacheStore`] of this storage service.
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    /// Removes `path` from the cache, so that it is read from the remote next time it is opened.
+    pub async fn invalidate<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.cache.invalidate(&key(path.as_ref())).await
+    }
+
+    async fn cached(&self, path: &Path) -> Option<Bytes> {
+        let cached = self
+            .cache
+            .get(&key(path))
+            .await
+            .inspect_err(|e| {
+                #[cfg(feature = "tracing")]
+                ::tracing::warn!(error = %e, path = %path.display(), "failed to read file from cache");
+
+                #[cfg(feature = "log")]
+                ::log::warn!("failed to read file [{}] from cache: {e}", path.display());
+
+                let _ = e;
+            })
+            .ok()
+            .flatten();
+
+        self.recorder.record_cache(cached.is_some());
+        if let Some(observer) = &self.observer {
+            observer(path, cached.is_some());
+        }
+
+        cached
+    }
+
+    async fn store(&self, path: &Path, data: Bytes) {
+        if let Err(e) = self.cache.insert(&key(path), data).await {
+            #[cfg(feature = "tracing")]
+            ::tracing::warn!(error = %e, path = %path.display(), "failed to store file in cache");
+
+            #[cfg(feature = "log")]
+            ::log::warn!("failed to store file [{}] in cache: {e}", path.display());
+
+            let _ = e;
+        }
+    }
+
+    async fn forget(&self, path: &Path) {
+        if let Err(e) = self.cache.invalidate(&key(path)).await {
+            #[cfg(feature = "tracing")]
+            ::tracing::warn!(error = %e, path = %path.display(), "failed to remove file from cache");
+
+            #[cfg(feature = "log")]
+            ::log::warn!("failed to remove file [{}] from cache: {e}", path.display());
+
+            let _ = e;
+        }
+    }
+}
+
+#[async_trait]
+impl<S: StorageService, C: CacheStore> StorageService for CachedStorageService<S, C> {
+    type Error = S::Error;
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Owned(format!("remi:cache({})", self.remote.name()))
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        self.remote.init().await
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        let path = path.as_ref();
+        if let Some(data) = self.cached(path).await {
+            return Ok(Some(data));
+        }
+
+        let data = self.remote.open(path).await?;
+        match data {
+            Some(data) => {
+                self.store(path, data.clone()).await;
+                Ok(Some(data))
+            }
+
+            None => {
+                self.forget(path).await;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        let path = path.as_ref();
+
+        // only the range is read from the storage service, so it isn't stored in the cache
+        match self.cached(path).await {
+            Some(data) => {
+                let (start, end) = clamp_range(start, end, data.len() as u64);
+                Ok(Some(data.slice(start as usize..end as usize)))
+            }
+
+            None => self.remote.open_range(path, start, end).await,
+        }
+    }
+
+    async fn open_stream<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+    ) -> Result<Option<ContentStream<Self::Error>>, Self::Error>
+    where
+        Self::Error: Send + 'static,
+    {
+        let path = path.as_ref();
+
+        // streamed files aren't held in memory, so they aren't stored in the cache
+        match self.cached(path).await {
+            Some(data) => Ok(Some(stream::once(future::ready(Ok(data))).boxed())),
+            None => self.remote.open_stream(path).await,
+        }
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        self.remote.blob(path).await
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        self.remote.blobs(path, options).await
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        self.remote.blobs_page(path, options).await
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        let path = path.as_ref();
+        let deleted = self.remote.delete(path).await?;
+        self.forget(path).await;
+
+        Ok(deleted)
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        let dst = dst.as_ref();
+        let copied = self.remote.copy(src, dst).await?;
+        if copied {
+            self.forget(dst).await;
+        }
+
+        Ok(copied)
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        let renamed = self.remote.rename(from, to).await?;
+        if renamed {
+            self.forget(from).await;
+            self.forget(to).await;
+        }
+
+        Ok(renamed)
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.remote.exists(path).await
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        let path = path.as_ref();
+        let data = options.data.clone();
+
+        // suffixed uploads don't replace the file in `path` if it already exists
+        let suffixed = matches!(options.collision(), Some(CollisionStrategy::Suffix(_)));
+        self.remote.upload(path, options).await?;
+        if !suffixed {
+            self.store(path, data).await;
+        }
+
+        Ok(())
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        let path = path.as_ref();
+        let data = options.data.clone();
+
+        self.remote.create_exclusive(path, options).await?;
+        self.store(path, data).await;
+
+        Ok(())
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        self.remote.create_dir(path).await
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.remote.is_read_only()
+    }
+
+    fn stats(&self) -> Stats {
+        let cache = self.recorder.snapshot();
+        Stats {
+            cache_hits: cache.cache_hits,
+            cache_misses: cache.cache_misses,
+            ..self.remote.stats()
+        }
+    }
+
+    fn info(&self) -> ServiceInfo {
+        ServiceInfo {
+            name: self.name().into_owned(),
+            ..self.remote.info()
+        }
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        self.remote.verify_credentials().await
+    }
+
+    async fn healthcheck(&self) -> HealthReport
+    where
+        Self::Error: Display + Send,
+    {
+        self.remote.healthcheck().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{key, CacheStore, CachedStorageService, MemoryCache};
+    use crate::{testing::TestStorage, StorageService, UploadRequest};
+    use bytes::Bytes;
+    use futures_util::FutureExt;
+    use std::{path::Path, time::Duration};
+
+    fn get(cache: &MemoryCache, path: &str) -> Option<Bytes> {
+        cache.get(Path::new(path)).now_or_never().unwrap().unwrap()
+    }
+
+    fn cached() -> CachedStorageService<TestStorage, MemoryCache> {
+        CachedStorageService::new(TestStorage::new(), MemoryCache::new(1024))
+    }
+
+    fn request(data: &'static str) -> UploadRequest {
+        UploadRequest::default().with_data(data)
+    }
+
+    fn insert(cache: &MemoryCache, path: &str, data: &'static str) {
+        cache
+            .insert(Path::new(path), Bytes::from_static(data.as_bytes()))
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = MemoryCache::new(10);
+        insert(&cache, "./a", "aaaa");
+        insert(&cache, "./b", "bbbb");
+        assert_eq!(cache.size(), 8);
+
+        // `./a` is now more recently used than `./b`
+        assert!(get(&cache, "./a").is_some());
+
+        insert(&cache, "./c", "cccc");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(get(&cache, "./b"), None);
+        assert_eq!(get(&cache, "./a"), Some(Bytes::from_static(b"aaaa")));
+
+        // replacing a file doesn't count it twice
+        insert(&cache, "./a", "aa");
+        assert_eq!(cache.size(), 6);
+
+        // files larger than the cache are never stored
+        insert(&cache, "./d", "this is way too large");
+        assert_eq!(get(&cache, "./d"), None);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn normalizes_paths() {
+        for path in [
+            "./a/b.txt",
+            "a/b.txt",
+            "/a/b.txt",
+            "a//b.txt",
+            "./a/./b.txt",
+            "a/b.txt/",
+        ] {
+            assert_eq!(key(Path::new(path)), Path::new("a/b.txt"), "{path}");
+        }
+    }
+
+    #[test]
+    fn expires_files() {
+        let cache = MemoryCache::new(10).with_ttl(Duration::ZERO);
+        insert(&cache, "./a", "aaaa");
+
+        assert_eq!(get(&cache, "./a"), None);
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn caches_opened_files() {
+        let storage = cached();
+        storage.remote().upload("./weow.txt", request("weow")).await.unwrap();
+
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        // changes made directly on the remote aren't picked up until the file is invalidated
+        storage.remote().upload("./weow.txt", request("fluff")).await.unwrap();
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        storage.invalidate("weow.txt").await.unwrap();
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"fluff"))
+        );
+
+        let stats = storage.stats();
+        assert_eq!((stats.cache_hits, stats.cache_misses), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn paths_to_the_same_file_share_an_entry() {
+        let storage = cached();
+        storage.upload("./dir/weow.txt", request("weow")).await.unwrap();
+
+        for path in ["dir/weow.txt", "/dir/weow.txt", "./dir//weow.txt", "./dir/./weow.txt"] {
+            assert_eq!(
+                storage.open(path).await.unwrap(),
+                Some(Bytes::from_static(b"weow")),
+                "{path}"
+            );
+        }
+
+        assert_eq!(storage.cache().len(), 1);
+        assert_eq!(storage.stats().cache_misses, 0);
+
+        storage.upload("dir//weow.txt", request("fluff")).await.unwrap();
+        assert_eq!(
+            storage.open("./dir/weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"fluff"))
+        );
+
+        assert!(storage.delete("/dir/weow.txt").await.unwrap());
+        assert!(storage.cache().is_empty());
+        assert_eq!(storage.open("./dir/weow.txt").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn renames_invalidate_both_paths() {
+        let storage = cached();
+        storage.upload("./a.txt", request("a")).await.unwrap();
+        storage.upload("./b.txt", request("b")).await.unwrap();
+
+        assert!(storage.rename("a.txt", "/b.txt").await.unwrap());
+        assert!(storage.cache().is_empty());
+
+        assert_eq!(storage.open("./a.txt").await.unwrap(), None);
+        assert_eq!(storage.open("./b.txt").await.unwrap(), Some(Bytes::from_static(b"a")));
+    }
+}
@@ -0,0 +1,104 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::path::{Path, PathBuf};
+
+/// Strategy of an upload for when a file already exists at its key, which is set with
+/// [`UploadRequest::with_collision`][crate::UploadRequest::with_collision]. Uploads that
+/// don't set a strategy keep the storage service's default behavior.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollisionStrategy {
+    /// Replaces the existing file.
+    Overwrite,
+
+    /// Fails with [`AlreadyExists`][crate::AlreadyExists], like
+    /// [`create_exclusive`][crate::StorageService::create_exclusive].
+    Error,
+
+    /// Uploads to the first key of [`CollisionStrategy::candidates`] that doesn't exist
+    /// yet, trying at most the given amount of suffixed keys before failing with
+    /// [`AlreadyExists`][crate::AlreadyExists].
+    Suffix(usize),
+
+    /// Keeps the existing file as a previous version or revision, which fails on storage
+    /// services that can't keep previous versions.
+    NewVersion,
+}
+
+impl CollisionStrategy {
+    /// Returns the keys that an upload to `path` is attempted at, in order. Only
+    /// [`CollisionStrategy::Suffix`] tries more than one key, by appending `-{n}` to
+    /// the file stem: `images/a.png` is followed by `images/a-1.png`, `images/a-2.png`
+    /// and so on.
+    pub fn candidates<P: AsRef<Path>>(&self, path: P) -> Vec<PathBuf> {
+        let path = path.as_ref();
+        let attempts = match self {
+            CollisionStrategy::Suffix(attempts) => *attempts,
+            _ => 0,
+        };
+
+        let mut candidates = vec![path.to_path_buf()];
+        let Some(stem) = path.file_stem() else {
+            return candidates;
+        };
+
+        for n in 1..=attempts {
+            let mut name = stem.to_os_string();
+            name.push(format!("-{n}"));
+            if let Some(extension) = path.extension() {
+                name.push(".");
+                name.push(extension);
+            }
+
+            candidates.push(path.with_file_name(name));
+        }
+
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CollisionStrategy;
+    use std::path::PathBuf;
+
+    #[test]
+    fn candidates() {
+        assert_eq!(
+            CollisionStrategy::Suffix(2).candidates("./images/a.png"),
+            ["./images/a.png", "./images/a-1.png", "./images/a-2.png"]
+                .map(PathBuf::from)
+                .to_vec()
+        );
+
+        assert_eq!(
+            CollisionStrategy::Suffix(1).candidates("LICENSE"),
+            ["LICENSE", "LICENSE-1"].map(PathBuf::from).to_vec()
+        );
+
+        assert_eq!(
+            CollisionStrategy::Overwrite.candidates("a.png"),
+            vec![PathBuf::from("a.png")]
+        );
+    }
+}
@@ -0,0 +1,683 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, Blob, DeleteSummary, File, ListBlobsRequest, Page, ServiceInfo, Stats, StorageService, UploadRequest,
+};
+use bytes::Bytes;
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Display},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+/// Metadata key of the [`CompressionCodec`] that a file was compressed with.
+pub const COMPRESSION_METADATA: &str = "remi_compression";
+
+/// Metadata key of the size of a file before it was compressed.
+pub const UNCOMPRESSED_SIZE_METADATA: &str = "remi_uncompressed_size";
+
+/// Metadata key of the content type that a file was uploaded with before it was compressed.
+pub const CONTENT_TYPE_METADATA: &str = "remi_content_type";
+
+/// Magic bytes at the start of every file that a [`CompressedStorageService`] compressed,
+/// which are followed by the id of the codec.
+const MAGIC: &[u8] = b"REMICMP1";
+
+/// Content types of files that are already compressed, which aren't compressed again.
+const COMPRESSED_CONTENT_TYPES: &[&str] = &[
+    "application/gzip",
+    "application/x-gzip",
+    "application/zstd",
+    "application/zip",
+    "application/x-7z-compressed",
+    "application/x-bzip2",
+    "application/x-rar-compressed",
+    "application/x-xz",
+    "application/vnd.rar",
+    "font/woff",
+    "font/woff2",
+    "image/avif",
+    "image/gif",
+    "image/heic",
+    "image/jpeg",
+    "image/png",
+    "image/webp",
+];
+
+/// Returns `true` if files with the given content type are already compressed, like images,
+/// videos, and archives, which don't get any smaller by compressing them again.
+///
+/// * since: 0.11.0
+pub fn is_compressed_content_type(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    essence.starts_with("audio/") || essence.starts_with("video/") || COMPRESSED_CONTENT_TYPES.contains(&&*essence)
+}
+
+/// Algorithm that a [`CompressedStorageService`] compresses files with.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompressionCodec {
+    /// gzip, which is available everywhere.
+    Gzip,
+
+    /// Zstandard, which compresses better and faster than gzip. Requires the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    #[cfg_attr(any(noeldoc, docsrs), doc(cfg(feature = "zstd")))]
+    Zstd,
+}
+
+impl CompressionCodec {
+    /// Returns the name of this codec, which is what [`COMPRESSION_METADATA`] is set to.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompressionCodec::Gzip => "gzip",
+
+            #[cfg(feature = "zstd")]
+            CompressionCodec::Zstd => "zstd",
+        }
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            CompressionCodec::Gzip => 1,
+
+            #[cfg(feature = "zstd")]
+            CompressionCodec::Zstd => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> io::Result<CompressionCodec> {
+        match id {
+            1 => Ok(CompressionCodec::Gzip),
+
+            #[cfg(feature = "zstd")]
+            2 => Ok(CompressionCodec::Zstd),
+
+            #[cfg(not(feature = "zstd"))]
+            2 => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "file was compressed with zstd, but the `zstd` feature of remi isn't enabled",
+            )),
+
+            id => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown compression codec #{id}"),
+            )),
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut compressed = MAGIC.to_vec();
+        compressed.push(self.id());
+
+        match self {
+            CompressionCodec::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(compressed, flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+
+            #[cfg(feature = "zstd")]
+            CompressionCodec::Zstd => {
+                zstd::stream::copy_encode(data, &mut compressed, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+                Ok(compressed)
+            }
+        }
+    }
+}
+
+/// Decompresses `data` if it was compressed by a [`CompressedStorageService`], or returns
+/// `None` if it wasn't.
+fn decompress(data: &[u8]) -> Option<io::Result<Vec<u8>>> {
+    let (&id, compressed) = data.strip_prefix(MAGIC)?.split_first()?;
+    let codec = match CompressionCodec::from_id(id) {
+        Ok(codec) => codec,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let mut decompressed = Vec::new();
+    let result = match codec {
+        CompressionCodec::Gzip => flate2::read::GzDecoder::new(compressed)
+            .read_to_end(&mut decompressed)
+            .map(drop),
+
+        #[cfg(feature = "zstd")]
+        CompressionCodec::Zstd => zstd::stream::copy_decode(compressed, &mut decompressed),
+    };
+
+    Some(result.map(|()| decompressed))
+}
+
+/// Error from a [`CompressedStorageService`].
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub enum CompressionError<E> {
+    /// The storage service that is being wrapped returned an error.
+    Storage(E),
+
+    /// The data of an upload couldn't be compressed.
+    Compress(io::Error),
+
+    /// The file in the given path couldn't be decompressed, because it is corrupted or
+    /// was compressed with a codec that isn't enabled.
+    Decompress {
+        /// Path of the file.
+        path: String,
+
+        /// Error from decompressing the file.
+        error: io::Error,
+    },
+}
+
+impl<E: Display> Display for CompressionError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionError::Storage(e) => Display::fmt(e, f),
+            CompressionError::Compress(e) => write!(f, "failed to compress data: {e}"),
+            CompressionError::Decompress { path, error } => write!(f, "failed to decompress file [{path}]: {error}"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for CompressionError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CompressionError::Storage(e) => Some(e),
+            CompressionError::Compress(e) => Some(e),
+            CompressionError::Decompress { error, .. } => Some(error),
+        }
+    }
+}
+
+/// A [`StorageService`] that compresses the contents of files before they are uploaded to the
+/// storage service it wraps and decompresses them when they are opened, which saves storage
+/// space and bandwidth for files that compress well (like JSON or logs).
+///
+/// Only uploads that are at least as large as the [threshold][CompressedStorageService::with_threshold]
+/// are compressed, and files with a content type that is already compressed (see
+/// [`is_compressed_content_type`]) are uploaded as-is. Files are also uploaded as-is if
+/// compressing them doesn't make them smaller.
+///
+/// Compressed files are marked with the [`CompressionCodec`] they were compressed with in their
+/// metadata ([`COMPRESSION_METADATA`]), so the storage service that is wrapped has to keep
+/// metadata. Files without it are returned as-is, even if their contents happen to look
+/// compressed. The uncompressed size ([`UNCOMPRESSED_SIZE_METADATA`]) and the content type
+/// that the file was uploaded with ([`CONTENT_TYPE_METADATA`]) are kept as well, so listings
+/// that don't return the contents of files report the uncompressed size and
+/// [`blob`][StorageService::blob] returns the original content type.
+///
+/// A few things to keep in mind:
+///
+/// - Storage services can't detect the content type of compressed data, so uploads should
+///   set [`UploadRequest::content_type`].
+/// - [`open`][StorageService::open] looks up the file's metadata with
+///   [`blob`][StorageService::blob] before it is decompressed.
+/// - [`open_range`][StorageService::open_range] and [`open_stream`][StorageService::open_stream]
+///   read and decompress the whole file.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{CompressedStorageService, CompressionCodec};
+/// #
+/// let storage = CompressedStorageService::new(storage, CompressionCodec::Gzip).with_threshold(4096);
+/// storage.upload("./logs/app.log", UploadRequest::default().with_data(logs)).await?;
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct CompressedStorageService<S> {
+    inner: S,
+    codec: CompressionCodec,
+    threshold: usize,
+}
+
+impl<S: StorageService> CompressedStorageService<S> {
+    /// Creates a new [`CompressedStorageService`] that compresses the files of `inner` with
+    /// `codec` if they are at least 1KiB large.
+    pub fn new(inner: S, codec: CompressionCodec) -> CompressedStorageService<S> {
+        CompressedStorageService {
+            inner,
+            codec,
+            threshold: 1024,
+        }
+    }
+
+    /// Sets the size (in bytes) that uploads have to be before they are compressed.
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Returns the storage service whose files are compressed.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the [`CompressionCodec`] that files are compressed with.
+    pub fn codec(&self) -> CompressionCodec {
+        self.codec
+    }
+
+    fn compress(&self, mut options: UploadRequest) -> Result<UploadRequest, CompressionError<S::Error>> {
+        let size = options.data.len();
+        if size < self.threshold || options.content_type.as_deref().is_some_and(is_compressed_content_type) {
+            return Ok(options);
+        }
+
+        let compressed = self.codec.compress(&options.data).map_err(CompressionError::Compress)?;
+        if compressed.len() >= size {
+            return Ok(options);
+        }
+
+        options.data = Bytes::from(compressed);
+        options
+            .metadata
+            .insert(COMPRESSION_METADATA.to_owned(), self.codec.name().to_owned());
+
+        options
+            .metadata
+            .insert(UNCOMPRESSED_SIZE_METADATA.to_owned(), size.to_string());
+
+        if let Some(content_type) = options.content_type.clone() {
+            options.metadata.insert(CONTENT_TYPE_METADATA.to_owned(), content_type);
+        }
+
+        Ok(options)
+    }
+
+    fn decompress(&self, path: &str, data: &[u8]) -> Result<Bytes, CompressionError<S::Error>> {
+        let error = match decompress(data) {
+            Some(Ok(decompressed)) => return Ok(Bytes::from(decompressed)),
+            Some(Err(error)) => error,
+            None => io::Error::new(io::ErrorKind::InvalidData, "file is missing its compression header"),
+        };
+
+        Err(CompressionError::Decompress {
+            path: path.to_owned(),
+            error,
+        })
+    }
+
+    fn decompress_blob(&self, blob: Blob) -> Result<Blob, CompressionError<S::Error>> {
+        let Blob::File(mut file) = blob else {
+            return Ok(blob);
+        };
+
+        if file.metadata.remove(COMPRESSION_METADATA).is_none() {
+            return Ok(Blob::File(file));
+        }
+
+        let size = file.metadata.remove(UNCOMPRESSED_SIZE_METADATA);
+
+        // the content type that the storage service has is of the compressed data
+        file.content_type = file.metadata.remove(CONTENT_TYPE_METADATA);
+        file.checksum = None;
+
+        // listings that don't return the contents of files
+        if file.data.is_empty() {
+            if let Some(size) = size.and_then(|size| size.parse().ok()) {
+                file.size = size;
+            }
+
+            return Ok(Blob::File(file));
+        }
+
+        let data = self.decompress(&file.path, &file.data)?;
+        Ok(Blob::from(File {
+            size: data.len(),
+            data,
+            ..file
+        }))
+    }
+}
+
+#[async_trait]
+impl<S: StorageService> StorageService for CompressedStorageService<S>
+where
+    S::Error: Send,
+{
+    type Error = CompressionError<S::Error>;
+
+    fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        self.inner.init().await.map_err(CompressionError::Storage)
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        let path = path.as_ref();
+        let file = match self.inner.blob(path).await.map_err(CompressionError::Storage)? {
+            Some(Blob::File(file)) => file,
+            Some(Blob::Directory(_)) => return self.inner.open(path).await.map_err(CompressionError::Storage),
+            None => return Ok(None),
+        };
+
+        // storage services that don't return the contents of files with their metadata
+        let data = if file.data.is_empty() && file.size > 0 {
+            match self.inner.open(path).await.map_err(CompressionError::Storage)? {
+                Some(data) => data,
+                None => return Ok(None),
+            }
+        } else {
+            file.data
+        };
+
+        if !file.metadata.contains_key(COMPRESSION_METADATA) {
+            return Ok(Some(data));
+        }
+
+        self.decompress(&path.display().to_string(), &data).map(Some)
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        self.inner
+            .blob(path)
+            .await
+            .map_err(CompressionError::Storage)?
+            .map(|blob| self.decompress_blob(blob))
+            .transpose()
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        self.inner
+            .blobs(path, options)
+            .await
+            .map_err(CompressionError::Storage)?
+            .into_iter()
+            .map(|blob| self.decompress_blob(blob))
+            .collect()
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        let page = self
+            .inner
+            .blobs_page(path, options)
+            .await
+            .map_err(CompressionError::Storage)?;
+
+        Ok(Page {
+            items: page
+                .items
+                .into_iter()
+                .map(|blob| self.decompress_blob(blob))
+                .collect::<Result<_, _>>()?,
+            ..page
+        })
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.inner.delete(path).await.map_err(CompressionError::Storage)
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        self.inner
+            .delete_prefix(prefix)
+            .await
+            .map_err(CompressionError::Storage)
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        self.inner.copy(src, dst).await.map_err(CompressionError::Storage)
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        self.inner.rename(from, to).await.map_err(CompressionError::Storage)
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.inner.exists(path).await.map_err(CompressionError::Storage)
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        let options = self.compress(options)?;
+        self.inner
+            .upload(path, options)
+            .await
+            .map_err(CompressionError::Storage)
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        let options = self.compress(options)?;
+        self.inner
+            .create_exclusive(path, options)
+            .await
+            .map_err(CompressionError::Storage)
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        self.inner.create_dir(path).await.map_err(CompressionError::Storage)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.inner.is_read_only()
+    }
+
+    fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    fn info(&self) -> ServiceInfo {
+        self.inner.info()
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        self.inner.verify_credentials().await.map_err(CompressionError::Storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decompress, is_compressed_content_type, CompressedStorageService, CompressionCodec, CompressionError,
+        COMPRESSION_METADATA, MAGIC,
+    };
+    use crate::{testing::TestStorage, Blob, ListBlobsRequest, StorageService, UploadRequest};
+    use bytes::Bytes;
+    use std::collections::HashMap;
+
+    const CODECS: &[CompressionCodec] = &[
+        CompressionCodec::Gzip,
+        #[cfg(feature = "zstd")]
+        CompressionCodec::Zstd,
+    ];
+
+    #[test]
+    fn compress_and_decompress() {
+        let data = "weow ".repeat(1000);
+
+        for &codec in CODECS {
+            let compressed = codec.compress(data.as_bytes()).unwrap();
+            assert!(compressed.len() < data.len(), "{}", codec.name());
+            assert_eq!(decompress(&compressed).unwrap().unwrap(), data.as_bytes());
+        }
+    }
+
+    #[test]
+    fn uncompressed_data() {
+        assert!(decompress(b"weow").is_none());
+        assert!(decompress(b"").is_none());
+        assert!(decompress(MAGIC).is_none());
+
+        let mut unknown = MAGIC.to_vec();
+        unknown.push(255);
+        assert!(decompress(&unknown).unwrap().is_err());
+
+        let mut corrupted = CompressionCodec::Gzip.compress(b"weow").unwrap();
+        corrupted.truncate(corrupted.len() - 4);
+        assert!(decompress(&corrupted).unwrap().is_err());
+    }
+
+    #[test]
+    fn compressed_content_types() {
+        assert!(is_compressed_content_type("image/png"));
+        assert!(is_compressed_content_type("video/mp4"));
+        assert!(is_compressed_content_type("Application/Gzip; charset=binary"));
+        assert!(!is_compressed_content_type("application/json; charset=utf-8"));
+        assert!(!is_compressed_content_type("text/plain"));
+    }
+
+    fn compressed() -> CompressedStorageService<TestStorage> {
+        CompressedStorageService::new(TestStorage::new(), CompressionCodec::Gzip).with_threshold(16)
+    }
+
+    fn logs() -> String {
+        "[info] weow\n".repeat(100)
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let storage = compressed();
+        storage
+            .upload(
+                "./app.log",
+                UploadRequest::default()
+                    .with_data(logs())
+                    .with_content_type(Some("text/plain")),
+            )
+            .await
+            .unwrap();
+
+        let stored = storage.get_ref().open("./app.log").await.unwrap().unwrap();
+        assert!(stored.len() < logs().len());
+
+        assert_eq!(storage.open("./app.log").await.unwrap(), Some(Bytes::from(logs())));
+
+        let Some(Blob::File(file)) = storage.blob("./app.log").await.unwrap() else {
+            panic!("expected a file");
+        };
+
+        assert_eq!(file.data, Bytes::from(logs()));
+        assert_eq!(file.size, logs().len());
+        assert_eq!(file.content_type.as_deref(), Some("text/plain"));
+        assert!(file.metadata.is_empty());
+    }
+
+    #[tokio::test]
+    async fn listings_report_uncompressed_sizes() {
+        let storage = compressed();
+        storage
+            .upload("./app.log", UploadRequest::default().with_data(logs()))
+            .await
+            .unwrap();
+
+        let blobs = storage
+            .blobs(None::<&str>, Some(ListBlobsRequest::default().with_metadata_only(true)))
+            .await
+            .unwrap();
+
+        let [Blob::File(file)] = blobs.as_slice() else {
+            panic!("expected a single file");
+        };
+
+        assert_eq!(file.size, logs().len());
+        assert!(file.data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn small_files_are_uploaded_as_is() {
+        let storage = compressed();
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.get_ref().open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+    }
+
+    #[tokio::test]
+    async fn files_that_look_compressed_are_returned_as_is() {
+        let storage = compressed();
+        let data = Bytes::from_static(b"REMICMP1\x01weow");
+        storage
+            .get_ref()
+            .upload("./weow.bin", UploadRequest::default().with_data(data.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(storage.open("./weow.bin").await.unwrap(), Some(data.clone()));
+
+        let Some(Blob::File(file)) = storage.blob("./weow.bin").await.unwrap() else {
+            panic!("expected a file");
+        };
+
+        assert_eq!(file.data, data);
+    }
+
+    #[tokio::test]
+    async fn marked_files_without_a_header_fail_to_decompress() {
+        let storage = compressed();
+        storage
+            .get_ref()
+            .upload(
+                "./app.log",
+                UploadRequest::default()
+                    .with_data(logs())
+                    .with_metadata(HashMap::from([(COMPRESSION_METADATA.to_owned(), "gzip".to_owned())])),
+            )
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            storage.open("./app.log").await,
+            Err(CompressionError::Decompress { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn missing_files() {
+        let storage = compressed();
+        assert_eq!(storage.open("./weow.txt").await.unwrap(), None);
+        assert!(storage.blob("./weow.txt").await.unwrap().is_none());
+    }
+}
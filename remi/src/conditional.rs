@@ -0,0 +1,134 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{Blob, File};
+
+/// Result of [`StorageService::open_if_none_match`][crate::StorageService::open_if_none_match]
+/// for a file that exists.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // mirrors `Blob`, which isn't boxed either
+pub enum Conditional {
+    /// The file's entity tag matched, so its contents weren't downloaded. This is what an
+    /// HTTP `304 Not Modified` response is sent for.
+    NotModified,
+
+    /// The file's entity tag didn't match or the file doesn't have one, so it was downloaded.
+    Modified(File),
+}
+
+impl Conditional {
+    /// Compares the [entity tag][File::etag] of a blob that was returned from
+    /// [`StorageService::blob`][crate::StorageService::blob] with `etag`, which is what
+    /// [`StorageService::open_if_none_match`][crate::StorageService::open_if_none_match]
+    /// does by default. Directories are treated like files that don't exist.
+    pub fn of(blob: Option<Blob>, etag: &str) -> Option<Conditional> {
+        match blob? {
+            Blob::File(file) if file.etag.as_deref().is_some_and(|tag| etag_matches(etag, tag)) => {
+                Some(Conditional::NotModified)
+            }
+
+            Blob::File(file) => Some(Conditional::Modified(file)),
+            Blob::Directory(_) => None,
+        }
+    }
+}
+
+/// Returns whether if `condition`, like the value of an HTTP `If-None-Match` header, matches
+/// the entity tag of a file. The condition is either `*`, which matches every entity tag, or
+/// a comma-separated list of entity tags that can be quoted or weak (`W/"..."`); entity tags
+/// are compared with the weak comparison of [RFC 9110].
+///
+/// ## Example
+/// ```rust
+/// # use remi::etag_matches;
+/// #
+/// assert!(etag_matches(r#"W/"abc", "def""#, "abc"));
+/// assert!(etag_matches("*", "abc"));
+/// assert!(!etag_matches(r#""def""#, "abc"));
+/// ```
+///
+/// * since: 0.11.0
+///
+/// [RFC 9110]: https://www.rfc-editor.org/rfc/rfc9110#section-8.8.3.2
+pub fn etag_matches(condition: &str, etag: &str) -> bool {
+    let etag = normalize(etag);
+    condition
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || normalize(candidate) == etag)
+}
+
+fn normalize(etag: &str) -> &str {
+    let etag = etag.trim();
+    etag.strip_prefix("W/").unwrap_or(etag).trim_matches('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{etag_matches, Conditional};
+    use crate::{Blob, Directory, File, Metadata};
+
+    fn file(etag: Option<&str>) -> Option<Blob> {
+        Some(Blob::File(File::from(Metadata {
+            etag: etag.map(String::from),
+            ..Default::default()
+        })))
+    }
+
+    #[test]
+    fn compares_entity_tags() {
+        assert!(etag_matches("abc", "abc"));
+        assert!(etag_matches(r#""abc""#, r#""abc""#));
+        assert!(etag_matches(r#"W/"abc""#, "abc"));
+        assert!(etag_matches(r#""def", "abc""#, "abc"));
+        assert!(etag_matches("*", "abc"));
+        assert!(!etag_matches(r#""def""#, "abc"));
+        assert!(!etag_matches("", "abc"));
+    }
+
+    #[test]
+    fn of_blob() {
+        assert!(matches!(
+            Conditional::of(file(Some("abc")), "\"abc\""),
+            Some(Conditional::NotModified)
+        ));
+        assert!(matches!(
+            Conditional::of(file(Some("abc")), "\"def\""),
+            Some(Conditional::Modified(_))
+        ));
+        assert!(matches!(
+            Conditional::of(file(None), "*"),
+            Some(Conditional::Modified(_))
+        ));
+        assert!(Conditional::of(None, "*").is_none());
+        assert!(Conditional::of(
+            Some(Blob::Directory(Directory {
+                created_at: None,
+                name: String::from("a"),
+                path: String::from("memory://a"),
+            })),
+            "*"
+        )
+        .is_none());
+    }
+}
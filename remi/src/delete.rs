@@ -0,0 +1,149 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::StorageService;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use std::path::Path;
+
+/// Amount of objects that [`StorageService::delete_prefix`] deletes at the same time.
+pub const DEFAULT_DELETE_CONCURRENCY: usize = 16;
+
+/// Result of a [`StorageService::delete_prefix`] call.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeleteSummary {
+    /// Amount of objects that were deleted.
+    pub deleted: u64,
+
+    /// Keys of the objects that couldn't be deleted. Deleting them again with
+    /// [`StorageService::delete`] will return the reason why.
+    pub failed: Vec<String>,
+}
+
+impl DeleteSummary {
+    /// Returns `true` if every object was deleted.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Deletes every key in `keys` with [`StorageService::delete`], with at most `concurrency`
+/// deletions running at the same time. Storage services can use this to implement
+/// [`StorageService::delete_prefix`] after listing the keys under the prefix.
+///
+/// * since: 0.11.0
+pub async fn delete_keys<S, I>(service: &S, keys: I, concurrency: usize) -> DeleteSummary
+where
+    S: StorageService,
+    I: IntoIterator<Item = String>,
+{
+    let mut keys = keys.into_iter();
+    let mut in_flight = FuturesUnordered::new();
+    let mut summary = DeleteSummary::default();
+
+    loop {
+        while in_flight.len() < concurrency.max(1) {
+            let Some(key) = keys.next() else {
+                break;
+            };
+
+            in_flight.push(async move {
+                let result = service.delete(Path::new(&key)).await;
+                (key, result)
+            });
+        }
+
+        match in_flight.next().await {
+            Some((_, Ok(true))) => summary.deleted += 1,
+
+            // it was deleted by someone else in the meantime
+            Some((_, Ok(false))) => {}
+            Some((key, Err(_))) => summary.failed.push(key),
+            None => break,
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delete_keys, DeleteSummary};
+    use crate::{
+        testing::{Operation, TestStorage},
+        StorageService, UploadRequest,
+    };
+    use std::io;
+
+    async fn upload(storage: &TestStorage) {
+        for path in ["./dir/a.txt", "./dir/b.txt", "./dir/c.txt", "./weow.txt"] {
+            storage
+                .upload(path, UploadRequest::default().with_data("weow"))
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn deletes_keys() {
+        let storage = TestStorage::new();
+        upload(&storage).await;
+
+        let keys = ["dir/a.txt", "dir/c.txt", "dir/missing.txt"].map(String::from);
+        let summary = delete_keys(&storage, keys, 2).await;
+
+        // keys that don't exist aren't counted as deleted or failed
+        assert_eq!(
+            summary,
+            DeleteSummary {
+                deleted: 2,
+                failed: Vec::new()
+            }
+        );
+
+        assert!(summary.is_complete());
+        assert_eq!(storage.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reports_failed_deletions() {
+        let storage = TestStorage::new().failing(|operation, key| {
+            (operation == Operation::Delete && key == "dir/b.txt")
+                .then(|| io::Error::new(io::ErrorKind::PermissionDenied, "permission denied"))
+        });
+
+        upload(&storage).await;
+
+        let summary = storage.delete_prefix("./dir").await.unwrap();
+        assert_eq!(
+            summary,
+            DeleteSummary {
+                deleted: 2,
+                failed: vec![String::from("dir/b.txt")]
+            }
+        );
+
+        assert!(!summary.is_complete());
+        assert!(storage.exists("./dir/b.txt").await.unwrap());
+        assert!(storage.exists("./weow.txt").await.unwrap());
+    }
+}
@@ -0,0 +1,311 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{Blob, BlobStream, Metadata, StorageService};
+use std::{cmp::Ordering, error::Error, fmt::Display, path::PathBuf};
+
+/// Difference between a key in the source and target storage services of a [`Diff`].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)] // entries are yielded one at a time
+pub enum DiffEntry {
+    /// The key only exists in the target storage service.
+    Added { key: String, target: Metadata },
+
+    /// The key only exists in the source storage service.
+    Removed { key: String, source: Metadata },
+
+    /// The key exists in both storage services, but the files differ.
+    Changed {
+        key: String,
+        source: Metadata,
+        target: Metadata,
+    },
+}
+
+impl DiffEntry {
+    /// Returns the key of this entry, relative to the prefix that was diffed.
+    pub fn key(&self) -> &str {
+        match self {
+            DiffEntry::Added { key, .. } | DiffEntry::Removed { key, .. } | DiffEntry::Changed { key, .. } => key,
+        }
+    }
+}
+
+/// Error from listing either side of a [`Diff`].
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub enum DiffError<S, T> {
+    /// Listing the source storage service failed.
+    Source(S),
+
+    /// Listing the target storage service failed.
+    Target(T),
+}
+
+impl<S: Display, T: Display> Display for DiffError<S, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffError::Source(e) => write!(f, "failed to list source: {e}"),
+            DiffError::Target(e) => write!(f, "failed to list target: {e}"),
+        }
+    }
+}
+
+impl<S: Error + 'static, T: Error + 'static> Error for DiffError<S, T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DiffError::Source(e) => Some(e),
+            DiffError::Target(e) => Some(e),
+        }
+    }
+}
+
+/// Compares the files under a prefix of two storage services from their listings, which
+/// is created with [`diff`]. Directories are skipped and the contents of files are never
+/// read, so only keys that were added, removed, or changed are yielded.
+///
+/// Keys are compared relative to the prefix that was diffed on each side: the `{service}://`
+/// scheme and everything up to (and including) the prefix is stripped from the path of
+/// every file. Storage services that list absolute paths (like **remi-fs**) should be
+/// diffed under a prefix so that their keys line up with the other side.
+///
+/// Files that exist on both sides are changed if:
+/// * their sizes differ,
+/// * both have a checksum and the checksums differ, or
+/// * neither has a checksum and the target was last modified before the source, or at
+///   a different time at all with [`with_exact_times`][Diff::with_exact_times].
+///
+/// Checksums are only comparable between storage services that compute them the same way.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::DiffEntry;
+/// #
+/// let mut diff = remi::diff(&primary, "backups", &replica, "backups");
+/// while let Some(entry) = diff.next().await? {
+///     match entry {
+///         DiffEntry::Removed { key, .. } => println!("{key} is missing from the replica"),
+///         DiffEntry::Changed { key, .. } => println!("{key} is outdated in the replica"),
+///         DiffEntry::Added { .. } => {}
+///     }
+/// }
+/// ```
+///
+/// * since: 0.11.0
+pub struct Diff<'a, S: StorageService, T: StorageService> {
+    source: Side<'a, S>,
+    target: Side<'a, T>,
+    exact_times: bool,
+}
+
+/// Creates a [`Diff`] between the files in `source_prefix` of `source` and the files in
+/// `target_prefix` of `target`. An empty prefix diffs the root of a storage service.
+///
+/// * since: 0.11.0
+pub fn diff<'a, S: StorageService, T: StorageService>(
+    source: &'a S,
+    source_prefix: &str,
+    target: &'a T,
+    target_prefix: &str,
+) -> Diff<'a, S, T> {
+    Diff {
+        source: Side::new(source, source_prefix),
+        target: Side::new(target, target_prefix),
+        exact_times: false,
+    }
+}
+
+impl<'a, S: StorageService, T: StorageService> Diff<'a, S, T> {
+    /// Treats files whose modification times differ in either direction as changed, for
+    /// targets that keep the modification times of the files that were copied to them
+    /// (like **remi-fs** with [`FileTimestamps`][crate::FileTimestamps]). A target that
+    /// was modified after the source then means that the target was changed on its own.
+    pub fn with_exact_times(mut self) -> Self {
+        self.exact_times = true;
+        self
+    }
+
+    /// Returns the next difference, or `None` if both listings are exhausted.
+    pub async fn next(&mut self) -> Result<Option<DiffEntry>, DiffError<S::Error, T::Error>>
+    where
+        S: Sized,
+        T: Sized,
+    {
+        loop {
+            self.source.fill().await.map_err(DiffError::Source)?;
+            self.target.fill().await.map_err(DiffError::Target)?;
+
+            let ordering = match (&self.source.peeked, &self.target.peeked) {
+                (None, None) => return Ok(None),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((source, _)), Some((target, _))) => source.cmp(target),
+            };
+
+            match ordering {
+                Ordering::Less => {
+                    let (key, source) = self.source.peeked.take().unwrap();
+                    return Ok(Some(DiffEntry::Removed { key, source }));
+                }
+
+                Ordering::Greater => {
+                    let (key, target) = self.target.peeked.take().unwrap();
+                    return Ok(Some(DiffEntry::Added { key, target }));
+                }
+
+                Ordering::Equal => {
+                    let (key, source) = self.source.peeked.take().unwrap();
+                    let (_, target) = self.target.peeked.take().unwrap();
+                    if is_changed(&source, &target, self.exact_times) {
+                        return Ok(Some(DiffEntry::Changed { key, source, target }));
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Side<'a, S: StorageService> {
+    stream: BlobStream<'a, S>,
+    prefix: String,
+    peeked: Option<(String, Metadata)>,
+}
+
+impl<'a, S: StorageService> Side<'a, S> {
+    fn new(service: &'a S, prefix: &str) -> Side<'a, S> {
+        let path = (!prefix.is_empty()).then(|| PathBuf::from(prefix));
+        Side {
+            stream: BlobStream::new(service, path, None),
+            prefix: prefix.to_owned(),
+            peeked: None,
+        }
+    }
+
+    /// Peeks the next file of the listing, if nothing is peeked yet.
+    async fn fill(&mut self) -> Result<(), S::Error>
+    where
+        S: Sized,
+    {
+        while self.peeked.is_none() {
+            match self.stream.next().await? {
+                Some(Blob::File(file)) => {
+                    let (metadata, _) = file.into_parts();
+                    self.peeked = Some((relative_key(&metadata.path, &self.prefix), metadata));
+                }
+
+                Some(Blob::Directory(_)) => continue,
+                None => break,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the key of `path` relative to `prefix`, see [`Diff`].
+pub(crate) fn relative_key(path: &str, prefix: &str) -> String {
+    let key = path.split_once("://").map(|(_, key)| key).unwrap_or(path);
+    let prefix = prefix
+        .trim_start_matches("./")
+        .trim_start_matches("~/")
+        .trim_matches('/');
+
+    if !prefix.is_empty() {
+        let needle = format!("{prefix}/");
+        let found = key
+            .match_indices(&needle)
+            .find(|(at, _)| *at == 0 || key.as_bytes()[at - 1] == b'/');
+
+        if let Some((at, _)) = found {
+            return key[at + needle.len()..].to_owned();
+        }
+    }
+
+    key.trim_start_matches('/').to_owned()
+}
+
+/// Checks if `target` differs from `source`, see [`Diff`].
+pub(crate) fn is_changed(source: &Metadata, target: &Metadata, exact_times: bool) -> bool {
+    if source.size != target.size {
+        return true;
+    }
+
+    match (&source.checksum, &target.checksum) {
+        (Some(source), Some(target)) => source != target,
+        _ => match (source.last_modified_at, target.last_modified_at) {
+            (Some(source), Some(target)) if exact_times => target != source,
+            (Some(source), Some(target)) => target < source,
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_changed, relative_key};
+    use crate::Metadata;
+
+    #[test]
+    fn relative_keys() {
+        assert_eq!(relative_key("s3://images/a/b.png", "images"), "a/b.png");
+        assert_eq!(relative_key("s3:///images/a.png", "./images/"), "a.png");
+        assert_eq!(
+            relative_key("fs:///tmp/x/images/images/a.png", "images"),
+            "images/a.png"
+        );
+        assert_eq!(relative_key("fs:///tmp/myimages/images/a.png", "images"), "a.png");
+        assert_eq!(relative_key("gridfs://a.png", ""), "a.png");
+        assert_eq!(relative_key("azure://other/a.png", "images"), "other/a.png");
+    }
+
+    #[test]
+    fn changed_files() {
+        let file = |size, checksum: Option<&str>, modified| Metadata {
+            size,
+            checksum: checksum.map(String::from),
+            last_modified_at: modified,
+            ..Default::default()
+        };
+
+        assert!(is_changed(&file(1, None, None), &file(2, None, None), false));
+        assert!(is_changed(&file(1, Some("a"), None), &file(1, Some("b"), None), false));
+        assert!(!is_changed(
+            &file(1, Some("a"), Some(2)),
+            &file(1, Some("a"), Some(1)),
+            false
+        ));
+        assert!(is_changed(&file(1, None, Some(2)), &file(1, None, Some(1)), false));
+        assert!(!is_changed(&file(1, None, Some(1)), &file(1, None, Some(2)), false));
+        assert!(!is_changed(&file(1, None, None), &file(1, Some("a"), Some(1)), false));
+
+        // with exact times, a target that was modified after the source was changed too
+        assert!(is_changed(&file(1, None, Some(1)), &file(1, None, Some(2)), true));
+        assert!(!is_changed(&file(1, None, Some(1)), &file(1, None, Some(1)), true));
+        assert!(!is_changed(
+            &file(1, Some("a"), Some(1)),
+            &file(1, Some("a"), Some(2)),
+            true
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{File, StorageService, UploadRequest};
+use sha2::{Digest as _, Sha256};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    path::Path,
+    str::FromStr,
+};
+
+/// Metadata key of the [`ContentDigest`] of a file that was uploaded with [`upload_with_digest`].
+pub const DIGEST_METADATA: &str = "remi_digest";
+
+/// SHA-256 digest of a file's contents, which is formatted like the digests of OCI
+/// content descriptors (`sha256:{hex}`).
+///
+/// ## Example
+/// ```rust
+/// # use remi::ContentDigest;
+/// #
+/// let digest = ContentDigest::of(b"weow");
+/// assert_eq!(digest.to_string().parse::<ContentDigest>(), Ok(digest));
+/// assert!(digest.to_string().starts_with("sha256:"));
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContentDigest([u8; 32]);
+
+impl ContentDigest {
+    /// Computes the [`ContentDigest`] of `data`.
+    pub fn of<B: AsRef<[u8]>>(data: B) -> ContentDigest {
+        ContentDigest(Sha256::digest(data.as_ref()).into())
+    }
+
+    /// Returns the [`ContentDigest`] that was stored in the metadata of a file that was
+    /// uploaded with [`upload_with_digest`], if it has one.
+    pub fn of_file(file: &File) -> Option<ContentDigest> {
+        file.metadata.get(DIGEST_METADATA)?.parse().ok()
+    }
+
+    /// Returns the raw bytes of this digest.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Returns this digest in hexadecimal, without the `sha256:` prefix.
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+impl Display for ContentDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sha256:{}", self.to_hex())
+    }
+}
+
+/// Error when a string isn't a valid [`ContentDigest`].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidDigest(String);
+
+impl Display for InvalidDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid SHA-256 digest [{}]", self.0)
+    }
+}
+
+impl Error for InvalidDigest {}
+
+impl FromStr for ContentDigest {
+    type Err = InvalidDigest;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidDigest(s.to_owned());
+        let hex = s.strip_prefix("sha256:").ok_or_else(invalid)?;
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0; 32];
+        for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+
+        Ok(ContentDigest(bytes))
+    }
+}
+
+/// Uploads a file like [`upload`][StorageService::upload] and returns the SHA-256
+/// [`ContentDigest`] of its contents, which is also stored in its metadata under
+/// [`DIGEST_METADATA`] so it can be read back with [`ContentDigest::of_file`]. This is
+/// useful for content-addressable storage, like blobs of OCI artifacts that are
+/// referred to by their digest.
+///
+/// The digest is computed over the contents that were given to the storage service, so
+/// wrappers that transform contents (like compression or encryption) should wrap the
+/// storage service that this is called with.
+///
+/// * since: 0.11.0
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{upload_with_digest, UploadRequest};
+/// #
+/// let digest = upload_with_digest(&storage, "./blobs/layer", UploadRequest::default().with_data(layer)).await?;
+/// storage.rename("./blobs/layer", format!("./blobs/{}", digest.to_hex())).await?;
+/// ```
+pub async fn upload_with_digest<S, P>(
+    service: &S,
+    path: P,
+    mut options: UploadRequest,
+) -> Result<ContentDigest, S::Error>
+where
+    S: StorageService,
+    P: AsRef<Path> + Send,
+{
+    let digest = ContentDigest::of(&options.data);
+    options.metadata.insert(DIGEST_METADATA.to_owned(), digest.to_string());
+    service.upload(path, options).await?;
+
+    Ok(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContentDigest, InvalidDigest};
+
+    #[test]
+    fn formats_and_parses_digests() {
+        let digest = ContentDigest::of(b"");
+        assert_eq!(
+            digest.to_string(),
+            "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        assert_eq!(digest.to_string().parse(), Ok(digest));
+        assert_eq!(
+            "md5:d41d8cd98f00b204e9800998ecf8427e".parse::<ContentDigest>(),
+            Err(InvalidDigest(String::from("md5:d41d8cd98f00b204e9800998ecf8427e")))
+        );
+
+        assert!("sha256:abc".parse::<ContentDigest>().is_err());
+        assert!(format!("sha256:{}", "zz".repeat(32)).parse::<ContentDigest>().is_err());
+    }
+}
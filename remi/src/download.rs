@@ -0,0 +1,267 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::StorageService;
+use bytes::Bytes;
+use futures_util::{stream::FuturesUnordered, Stream};
+use std::{
+    collections::VecDeque,
+    future::{poll_fn, Future},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+type Download<'s, K, E> = Pin<Box<dyn Future<Output = (K, Result<Option<Bytes>, E>)> + Send + 's>>;
+
+/// Stream of parallel downloads from a storage service, created from [`StorageService::download_many`].
+///
+/// Downloads are only started when the stream is polled and at most `concurrency` of them run
+/// at the same time, so a consumer that stops polling stops any new downloads from being
+/// started. Results are yielded in the order that the downloads finish, not in the order
+/// of the keys that were given.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::StorageService;
+/// #
+/// let mut downloads = storage
+///     .download_many(["./a.txt", "./b.txt", "./c.txt"], 2)
+///     .with_retries(3)
+///     .with_byte_budget(64 * 1024 * 1024);
+///
+/// while let Some((key, result)) = downloads.next().await {
+///     let data = result?;
+/// }
+///
+/// // keys that weren't downloaded because the byte budget was exhausted
+/// let remaining = downloads.into_remaining();
+/// ```
+pub struct DownloadMany<'s, S: StorageService, K> {
+    service: &'s S,
+    pending: VecDeque<K>,
+    in_flight: FuturesUnordered<Download<'s, K, S::Error>>,
+    concurrency: usize,
+    retries: usize,
+    budget: Option<u64>,
+    downloaded: u64,
+}
+
+impl<'s, S: StorageService, K> DownloadMany<'s, S, K> {
+    /// Creates a new [`DownloadMany`] stream that downloads every key with at most
+    /// `concurrency` downloads at once. A concurrency of `0` is treated as `1`.
+    pub fn new<I: IntoIterator<Item = K>>(service: &'s S, keys: I, concurrency: usize) -> DownloadMany<'s, S, K> {
+        DownloadMany {
+            service,
+            pending: keys.into_iter().collect(),
+            in_flight: FuturesUnordered::new(),
+            concurrency: concurrency.max(1),
+            retries: 0,
+            budget: None,
+            downloaded: 0,
+        }
+    }
+
+    /// Retries a failed download up to `retries` more times before its error is yielded.
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Stops starting new downloads once `bytes` have been downloaded in total, so that
+    /// callers that collect thousands of objects don't run out of memory. Downloads that
+    /// were already started will still be yielded.
+    pub fn with_byte_budget(mut self, bytes: u64) -> Self {
+        self.budget = Some(bytes);
+        self
+    }
+
+    /// Returns the total amount of bytes that were downloaded so far.
+    pub fn downloaded(&self) -> u64 {
+        self.downloaded
+    }
+
+    /// Returns `true` if the byte budget was exhausted and no new downloads will be started.
+    pub fn is_over_budget(&self) -> bool {
+        self.budget.is_some_and(|budget| self.downloaded >= budget)
+    }
+
+    /// Consumes this stream and returns the keys whose downloads were never started.
+    pub fn into_remaining(self) -> Vec<K> {
+        self.pending.into()
+    }
+}
+
+impl<'s, S, K> DownloadMany<'s, S, K>
+where
+    S: StorageService,
+    S::Error: Send + 's,
+    K: AsRef<Path> + Send + Sync + Unpin + 's,
+{
+    /// Returns the next finished download, or `None` if there are no more downloads to run.
+    pub async fn next(&mut self) -> Option<(K, Result<Option<Bytes>, S::Error>)> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    fn start(&mut self) {
+        while self.in_flight.len() < self.concurrency && !self.is_over_budget() {
+            let Some(key) = self.pending.pop_front() else {
+                break;
+            };
+
+            let service = self.service;
+            let retries = self.retries;
+            self.in_flight.push(Box::pin(async move {
+                let mut attempt = 0;
+                loop {
+                    match service.open(key.as_ref()).await {
+                        Err(_) if attempt < retries => attempt += 1,
+                        result => break (key, result),
+                    }
+                }
+            }));
+        }
+    }
+}
+
+impl<'s, S, K> Stream for DownloadMany<'s, S, K>
+where
+    S: StorageService,
+    S::Error: Send + 's,
+    K: AsRef<Path> + Send + Sync + Unpin + 's,
+{
+    type Item = (K, Result<Option<Bytes>, S::Error>);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.start();
+
+        let item = match Pin::new(&mut self.in_flight).poll_next(cx) {
+            Poll::Ready(Some(item)) => item,
+
+            // `FuturesUnordered` returns `None` when it is empty, which only happens when
+            // every key was downloaded or the byte budget was exhausted.
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        if let (_, Ok(Some(data))) = &item {
+            self.downloaded += data.len() as u64;
+        }
+
+        Poll::Ready(Some(item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let in_flight = self.in_flight.len();
+        match self.budget {
+            Some(_) => (0, Some(in_flight + self.pending.len())),
+            None => (in_flight + self.pending.len(), Some(in_flight + self.pending.len())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{Operation, TestStorage},
+        StorageService, UploadRequest,
+    };
+    use bytes::Bytes;
+    use std::{
+        collections::BTreeMap,
+        io,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    /// Returns a storage service with `files` files, whose opens fail while `failures` is above zero.
+    async fn storage(files: usize, failures: Arc<AtomicUsize>) -> TestStorage {
+        let storage = TestStorage::new().failing(move |operation, _| {
+            let failed = operation == Operation::Open
+                && failures
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |failures| failures.checked_sub(1))
+                    .is_ok();
+
+            failed.then(|| io::Error::new(io::ErrorKind::ConnectionReset, "connection reset"))
+        });
+
+        for i in 0..files {
+            storage
+                .upload(format!("./{i}.txt"), UploadRequest::default().with_data("weow"))
+                .await
+                .unwrap();
+        }
+
+        storage
+    }
+
+    #[tokio::test]
+    async fn downloads_every_key() {
+        let storage = storage(10, Arc::default()).await;
+        let keys = (0..10)
+            .map(|i| format!("./{i}.txt"))
+            .chain([String::from("./missing.txt")]);
+
+        let mut downloads = storage.download_many(keys, 3);
+        let mut downloaded = BTreeMap::new();
+        while let Some((key, result)) = downloads.next().await {
+            downloaded.insert(key, result.unwrap());
+        }
+
+        assert_eq!(downloaded.len(), 11);
+        assert_eq!(downloaded["./missing.txt"], None);
+        assert_eq!(downloaded["./7.txt"], Some(Bytes::from_static(b"weow")));
+        assert_eq!(downloads.downloaded(), 40);
+        assert_eq!(storage.max_running(), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_failed_downloads() {
+        let storage = storage(2, Arc::new(AtomicUsize::new(2))).await;
+
+        let mut downloads = storage.download_many(["./0.txt", "./1.txt"], 1).with_retries(1);
+        let (_, first) = downloads.next().await.unwrap();
+        let (_, second) = downloads.next().await.unwrap();
+
+        // the first download used up both failures, since it was retried once
+        assert!(first.is_err());
+        assert_eq!(second.unwrap(), Some(Bytes::from_static(b"weow")));
+        assert!(downloads.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn stops_once_over_budget() {
+        let storage = storage(5, Arc::default()).await;
+        let keys = (0..5).map(|i| format!("./{i}.txt"));
+
+        let mut downloads = storage.download_many(keys, 1).with_byte_budget(8);
+        let mut downloaded = 0;
+        while downloads.next().await.is_some() {
+            downloaded += 1;
+        }
+
+        assert_eq!(downloaded, 2);
+        assert!(downloads.is_over_budget());
+        assert_eq!(downloads.into_remaining(), ["./2.txt", "./3.txt", "./4.txt"]);
+    }
+}
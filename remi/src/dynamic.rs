@@ -0,0 +1,374 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, Blob, Conditional, ContentStream, DeleteSummary, HealthReport, ListBlobsRequest, Page, ServiceInfo,
+    Stats, StorageService, UploadRequest,
+};
+use bytes::Bytes;
+use futures_util::{StreamExt, TryStreamExt};
+use std::{borrow::Cow, error::Error, path::Path, sync::Arc};
+
+/// Type-erased error that a [`DynStorageService`] returns.
+///
+/// * since: 0.11.0
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Object-safe version of [`StorageService`], which takes paths as [`&Path`][Path] and
+/// returns type-erased errors, so that storage services that are picked at runtime can
+/// be kept as an `Arc<dyn DynStorageService<Error = BoxError>>`.
+///
+/// Every [`StorageService`] whose error implements [`Error`] implements this trait, and
+/// `Arc<dyn DynStorageService<Error = BoxError>>` implements [`StorageService`], so it can
+/// be wrapped in the other storage services of this crate (like a [`RoutingStorageService`]
+/// that routes to different types of storage services).
+///
+/// The methods have the same names as the ones from [`StorageService`], so only one of the two
+/// traits should be imported where the methods are called.
+///
+/// [`RoutingStorageService`]: crate::RoutingStorageService
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{BoxError, DynStorageService, StorageService};
+/// # use std::sync::Arc;
+/// #
+/// let storage: Arc<dyn DynStorageService<Error = BoxError>> = match config.storage {
+///     Config::Filesystem(config) => Arc::new(remi_fs::StorageService::with_config(config)),
+///     Config::S3(config) => Arc::new(remi_s3::StorageService::new(config)),
+/// };
+///
+/// storage.init().await?;
+/// storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+/// ```
+///
+/// * since: 0.11.0
+#[async_trait]
+pub trait DynStorageService: Send + Sync {
+    /// Error that every operation can return.
+    type Error;
+
+    /// See [`StorageService::name`].
+    fn name(&self) -> Cow<'static, str>;
+
+    /// See [`StorageService::init`].
+    async fn init(&self) -> Result<(), Self::Error>;
+
+    /// See [`StorageService::open`].
+    async fn open(&self, path: &Path) -> Result<Option<Bytes>, Self::Error>;
+
+    /// See [`StorageService::open_stream`].
+    async fn open_stream(&self, path: &Path) -> Result<Option<ContentStream<Self::Error>>, Self::Error>;
+
+    /// See [`StorageService::open_range`].
+    async fn open_range(&self, path: &Path, start: u64, end: Option<u64>) -> Result<Option<Bytes>, Self::Error>;
+
+    /// See [`StorageService::open_if_none_match`].
+    async fn open_if_none_match(&self, path: &Path, etag: &str) -> Result<Option<Conditional>, Self::Error>;
+
+    /// See [`StorageService::blob`].
+    async fn blob(&self, path: &Path) -> Result<Option<Blob>, Self::Error>;
+
+    /// See [`StorageService::blobs`].
+    async fn blobs(&self, path: Option<&Path>, options: Option<ListBlobsRequest>) -> Result<Vec<Blob>, Self::Error>;
+
+    /// See [`StorageService::blobs_page`].
+    async fn blobs_page(
+        &self,
+        path: Option<&Path>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error>;
+
+    /// See [`StorageService::delete`].
+    async fn delete(&self, path: &Path) -> Result<bool, Self::Error>;
+
+    /// See [`StorageService::delete_prefix`].
+    async fn delete_prefix(&self, prefix: &Path) -> Result<DeleteSummary, Self::Error>;
+
+    /// See [`StorageService::copy`].
+    async fn copy(&self, src: &Path, dst: &Path) -> Result<bool, Self::Error>;
+
+    /// See [`StorageService::rename`].
+    async fn rename(&self, from: &Path, to: &Path) -> Result<bool, Self::Error>;
+
+    /// See [`StorageService::exists`].
+    async fn exists(&self, path: &Path) -> Result<bool, Self::Error>;
+
+    /// See [`StorageService::upload`].
+    async fn upload(&self, path: &Path, options: UploadRequest) -> Result<(), Self::Error>;
+
+    /// See [`StorageService::create_exclusive`].
+    async fn create_exclusive(&self, path: &Path, options: UploadRequest) -> Result<(), Self::Error>;
+
+    /// See [`StorageService::create_dir`].
+    async fn create_dir(&self, path: &Path) -> Result<(), Self::Error>;
+
+    /// See [`StorageService::is_read_only`].
+    fn is_read_only(&self) -> bool;
+
+    /// See [`StorageService::stats`].
+    fn stats(&self) -> Stats;
+
+    /// See [`StorageService::info`].
+    fn info(&self) -> ServiceInfo;
+
+    /// See [`StorageService::verify_credentials`].
+    async fn verify_credentials(&self) -> Result<(), Self::Error>;
+
+    /// See [`StorageService::healthcheck`].
+    async fn healthcheck(&self) -> HealthReport;
+}
+
+#[async_trait]
+impl<S: StorageService> DynStorageService for S
+where
+    S::Error: Error + Send + Sync + 'static,
+{
+    type Error = BoxError;
+
+    fn name(&self) -> Cow<'static, str> {
+        StorageService::name(self)
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        Ok(StorageService::init(self).await?)
+    }
+
+    async fn open(&self, path: &Path) -> Result<Option<Bytes>, Self::Error> {
+        Ok(StorageService::open(self, path).await?)
+    }
+
+    async fn open_stream(&self, path: &Path) -> Result<Option<ContentStream<Self::Error>>, Self::Error> {
+        Ok(StorageService::open_stream(self, path)
+            .await?
+            .map(|stream| stream.map_err(BoxError::from).boxed()))
+    }
+
+    async fn open_range(&self, path: &Path, start: u64, end: Option<u64>) -> Result<Option<Bytes>, Self::Error> {
+        Ok(StorageService::open_range(self, path, start, end).await?)
+    }
+
+    async fn open_if_none_match(&self, path: &Path, etag: &str) -> Result<Option<Conditional>, Self::Error> {
+        Ok(StorageService::open_if_none_match(self, path, etag).await?)
+    }
+
+    async fn blob(&self, path: &Path) -> Result<Option<Blob>, Self::Error> {
+        Ok(StorageService::blob(self, path).await?)
+    }
+
+    async fn blobs(&self, path: Option<&Path>, options: Option<ListBlobsRequest>) -> Result<Vec<Blob>, Self::Error> {
+        Ok(StorageService::blobs(self, path, options).await?)
+    }
+
+    async fn blobs_page(
+        &self,
+        path: Option<&Path>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        Ok(StorageService::blobs_page(self, path, options).await?)
+    }
+
+    async fn delete(&self, path: &Path) -> Result<bool, Self::Error> {
+        Ok(StorageService::delete(self, path).await?)
+    }
+
+    async fn delete_prefix(&self, prefix: &Path) -> Result<DeleteSummary, Self::Error> {
+        Ok(StorageService::delete_prefix(self, prefix).await?)
+    }
+
+    async fn copy(&self, src: &Path, dst: &Path) -> Result<bool, Self::Error> {
+        Ok(StorageService::copy(self, src, dst).await?)
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<bool, Self::Error> {
+        Ok(StorageService::rename(self, from, to).await?)
+    }
+
+    async fn exists(&self, path: &Path) -> Result<bool, Self::Error> {
+        Ok(StorageService::exists(self, path).await?)
+    }
+
+    async fn upload(&self, path: &Path, options: UploadRequest) -> Result<(), Self::Error> {
+        Ok(StorageService::upload(self, path, options).await?)
+    }
+
+    async fn create_exclusive(&self, path: &Path, options: UploadRequest) -> Result<(), Self::Error> {
+        Ok(StorageService::create_exclusive(self, path, options).await?)
+    }
+
+    async fn create_dir(&self, path: &Path) -> Result<(), Self::Error> {
+        Ok(StorageService::create_dir(self, path).await?)
+    }
+
+    fn is_read_only(&self) -> bool {
+        StorageService::is_read_only(self)
+    }
+
+    fn stats(&self) -> Stats {
+        StorageService::stats(self)
+    }
+
+    fn info(&self) -> ServiceInfo {
+        StorageService::info(self)
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        Ok(StorageService::verify_credentials(self).await?)
+    }
+
+    async fn healthcheck(&self) -> HealthReport {
+        StorageService::healthcheck(self).await
+    }
+}
+
+#[async_trait]
+impl StorageService for Arc<dyn DynStorageService<Error = BoxError>> {
+    type Error = BoxError;
+
+    fn name(&self) -> Cow<'static, str> {
+        (**self).name()
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        (**self).init().await
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        (**self).open(path.as_ref()).await
+    }
+
+    async fn open_stream<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+    ) -> Result<Option<ContentStream<Self::Error>>, Self::Error> {
+        (**self).open_stream(path.as_ref()).await
+    }
+
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        (**self).open_range(path.as_ref(), start, end).await
+    }
+
+    async fn open_if_none_match<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        etag: &str,
+    ) -> Result<Option<Conditional>, Self::Error> {
+        (**self).open_if_none_match(path.as_ref(), etag).await
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        (**self).blob(path.as_ref()).await
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        (**self).blobs(path.as_ref().map(AsRef::as_ref), options).await
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        (**self).blobs_page(path.as_ref().map(AsRef::as_ref), options).await
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        (**self).delete(path.as_ref()).await
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        (**self).delete_prefix(prefix.as_ref()).await
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        (**self).copy(src.as_ref(), dst.as_ref()).await
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        (**self).rename(from.as_ref(), to.as_ref()).await
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        (**self).exists(path.as_ref()).await
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        (**self).upload(path.as_ref(), options).await
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        (**self).create_exclusive(path.as_ref(), options).await
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        (**self).create_dir(path.as_ref()).await
+    }
+
+    fn is_read_only(&self) -> bool {
+        (**self).is_read_only()
+    }
+
+    fn stats(&self) -> Stats {
+        (**self).stats()
+    }
+
+    fn info(&self) -> ServiceInfo {
+        (**self).info()
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        (**self).verify_credentials().await
+    }
+
+    async fn healthcheck(&self) -> HealthReport {
+        (**self).healthcheck().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoxError, DynStorageService};
+    use crate::{RoutingStorageService, StorageService};
+    use std::sync::Arc;
+
+    const _DYN_STORAGE_SERVICE: Option<&dyn DynStorageService<Error = BoxError>> = None;
+
+    fn assert_storage_service<S: StorageService>() {}
+
+    #[test]
+    fn arc_is_a_storage_service() {
+        assert_storage_service::<Arc<dyn DynStorageService<Error = BoxError>>>();
+        assert_storage_service::<RoutingStorageService<Arc<dyn DynStorageService<Error = BoxError>>>>();
+    }
+}
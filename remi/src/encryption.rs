@@ -0,0 +1,695 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, Blob, DeleteSummary, File, ListBlobsRequest, Page, Secret, ServiceInfo, Stats, StorageService,
+    UploadRequest,
+};
+use bytes::Bytes;
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Display},
+    path::Path,
+    sync::Arc,
+};
+
+/// Metadata key of the id of the [`EncryptionKey`] that a file was encrypted with.
+pub const ENCRYPTION_KEY_ID_METADATA: &str = "remi_encryption_key_id";
+
+/// Metadata key of the nonce (in hexadecimal) that the contents of a file were encrypted with.
+pub const ENCRYPTION_NONCE_METADATA: &str = "remi_encryption_nonce";
+
+/// Magic bytes at the start of every file that an [`EncryptedStorageService`] wrote.
+const MAGIC: &[u8] = b"REMIENC1";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// Returns how many bytes larger a file that was encrypted with the key `key_id` is.
+fn overhead(key_id: &str) -> usize {
+    MAGIC.len() + 1 + key_id.len() + NONCE_LEN + KEY_LEN + TAG_LEN + NONCE_LEN + TAG_LEN
+}
+
+fn to_nonce(bytes: &[u8]) -> Nonce {
+    let bytes: [u8; NONCE_LEN] = bytes.try_into().expect("nonce to be 12 bytes");
+    Nonce::from(bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Key-encrypting key of an [`EncryptedStorageService`], which encrypts the random key that
+/// every file is encrypted with. The id of the key is stored with every file, so that files
+/// can still be decrypted after the key was rotated.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct EncryptionKey {
+    id: String,
+    key: Secret<[u8; KEY_LEN]>,
+}
+
+impl EncryptionKey {
+    /// Creates a new [`EncryptionKey`] from 32 bytes of key material.
+    ///
+    /// ## Panics
+    /// This panics if `id` is longer than 255 bytes.
+    pub fn new<I: Into<String>>(id: I, key: [u8; KEY_LEN]) -> EncryptionKey {
+        let id = id.into();
+        assert!(
+            id.len() <= u8::MAX as usize,
+            "encryption key id [{id}] is longer than 255 bytes"
+        );
+
+        EncryptionKey {
+            id,
+            key: Secret::new(key),
+        }
+    }
+
+    /// Generates a new random [`EncryptionKey`] with the operating system's random number
+    /// generator. The key has to be kept somewhere safe, since files that were encrypted
+    /// with it can't be decrypted without it.
+    ///
+    /// ## Panics
+    /// This panics if `id` is longer than 255 bytes.
+    pub fn generate<I: Into<String>>(id: I) -> EncryptionKey {
+        EncryptionKey::new(id, ChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    /// Returns the id of this key.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the key material of this key.
+    pub fn expose(&self) -> &[u8; KEY_LEN] {
+        self.key.expose()
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(&Key::from(*self.key.expose()))
+    }
+}
+
+/// Reasons why a file couldn't be decrypted.
+#[derive(Debug, PartialEq, Eq)]
+enum Failure {
+    Invalid,
+    UnknownKey(String),
+}
+
+/// Error from an [`EncryptedStorageService`].
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub enum EncryptionError<E> {
+    /// The storage service that is being wrapped returned an error.
+    Storage(E),
+
+    /// The data of an upload couldn't be encrypted, which only happens if it is too large.
+    Encrypt,
+
+    /// The file in the given path wasn't encrypted by an [`EncryptedStorageService`] or
+    /// was modified after it was encrypted.
+    Decrypt(String),
+
+    /// The file in the given path was encrypted with a key that the storage service doesn't
+    /// have, see [`EncryptedStorageService::with_decryption_key`].
+    UnknownKey {
+        /// Path of the file.
+        path: String,
+
+        /// Id of the key that the file was encrypted with.
+        key_id: String,
+    },
+}
+
+impl<E: Display> Display for EncryptionError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncryptionError::Storage(e) => Display::fmt(e, f),
+            EncryptionError::Encrypt => f.write_str("failed to encrypt data"),
+            EncryptionError::Decrypt(path) => {
+                write!(
+                    f,
+                    "file [{path}] isn't encrypted or was modified after it was encrypted"
+                )
+            }
+
+            EncryptionError::UnknownKey { path, key_id } => {
+                write!(f, "file [{path}] was encrypted with unknown key [{key_id}]")
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for EncryptionError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EncryptionError::Storage(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Keyring {
+    primary: EncryptionKey,
+    others: Vec<EncryptionKey>,
+}
+
+impl Keyring {
+    /// Encrypts `data` with a random data key, which is encrypted with the primary key and
+    /// stored in front of the encrypted data with everything that is needed to decrypt it:
+    ///
+    /// ```text
+    /// magic | key id length (u8) | key id | key nonce | encrypted data key | nonce | encrypted data
+    /// ```
+    ///
+    /// Everything in front of the encrypted data is authenticated along with it. Returns the
+    /// encrypted data and the nonce that it was encrypted with.
+    fn encrypt(&self, data: &[u8]) -> Option<(Vec<u8>, Nonce)> {
+        let key = &self.primary;
+        let data_key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        let key_nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let encrypted_key = key
+            .cipher()
+            .encrypt(
+                &key_nonce,
+                Payload {
+                    msg: &data_key,
+                    aad: key.id.as_bytes(),
+                },
+            )
+            .ok()?;
+
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut encrypted = Vec::with_capacity(overhead(&key.id) + data.len());
+        encrypted.extend_from_slice(MAGIC);
+        encrypted.push(key.id.len() as u8);
+        encrypted.extend_from_slice(key.id.as_bytes());
+        encrypted.extend_from_slice(&key_nonce);
+        encrypted.extend_from_slice(&encrypted_key);
+        encrypted.extend_from_slice(&nonce);
+
+        let ciphertext = ChaCha20Poly1305::new(&data_key)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: data,
+                    aad: &encrypted,
+                },
+            )
+            .ok()?;
+
+        encrypted.extend_from_slice(&ciphertext);
+        Some((encrypted, nonce))
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, Failure> {
+        let rest = data.strip_prefix(MAGIC).ok_or(Failure::Invalid)?;
+        let (&id_len, rest) = rest.split_first().ok_or(Failure::Invalid)?;
+
+        let id_len = id_len as usize;
+        if rest.len() < id_len + NONCE_LEN + KEY_LEN + TAG_LEN + NONCE_LEN {
+            return Err(Failure::Invalid);
+        }
+
+        let (id, rest) = rest.split_at(id_len);
+        let (key_nonce, rest) = rest.split_at(NONCE_LEN);
+        let (encrypted_key, rest) = rest.split_at(KEY_LEN + TAG_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let header = &data[..data.len() - ciphertext.len()];
+
+        let id = std::str::from_utf8(id).map_err(|_| Failure::Invalid)?;
+        let key = std::iter::once(&self.primary)
+            .chain(&self.others)
+            .find(|key| key.id == id)
+            .ok_or_else(|| Failure::UnknownKey(id.to_owned()))?;
+
+        let data_key = key
+            .cipher()
+            .decrypt(
+                &to_nonce(key_nonce),
+                Payload {
+                    msg: encrypted_key,
+                    aad: id.as_bytes(),
+                },
+            )
+            .map_err(|_| Failure::Invalid)?;
+
+        ChaCha20Poly1305::new_from_slice(&data_key)
+            .map_err(|_| Failure::Invalid)?
+            .decrypt(
+                &to_nonce(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| Failure::Invalid)
+    }
+}
+
+/// A [`StorageService`] that encrypts the contents of files before they are uploaded to the
+/// storage service it wraps and decrypts them when they are opened, so that the storage
+/// provider never sees them. This works with every storage service, since everything that
+/// is needed to decrypt a file is stored with its contents.
+///
+/// Every file is encrypted with ChaCha20-Poly1305 and a random key, which is itself encrypted
+/// with an [`EncryptionKey`] (envelope encryption). The id of that key and the nonce of the
+/// file are also kept in the file's metadata ([`ENCRYPTION_KEY_ID_METADATA`] and
+/// [`ENCRYPTION_NONCE_METADATA`]) for storage services that keep metadata. Files that were
+/// modified after they were encrypted fail to decrypt.
+///
+/// A few things to keep in mind:
+///
+/// - Storage services can't detect the content type of encrypted data, so uploads should
+///   set [`UploadRequest::content_type`].
+/// - [`open_range`][StorageService::open_range] and [`open_stream`][StorageService::open_stream]
+///   read and decrypt the whole file.
+/// - Checksums of files are removed, since the storage service computed them over the
+///   encrypted contents. Listings that don't return the contents of files report the
+///   decrypted size only if the storage service keeps metadata.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{EncryptedStorageService, EncryptionKey};
+/// #
+/// let storage = EncryptedStorageService::new(storage, EncryptionKey::new("2024-10", key))
+///     // files that were encrypted before the key was rotated
+///     .with_decryption_key(EncryptionKey::new("2024-01", old_key));
+///
+/// storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct EncryptedStorageService<S> {
+    inner: S,
+    keys: Arc<Keyring>,
+}
+
+impl<S: StorageService> EncryptedStorageService<S> {
+    /// Creates a new [`EncryptedStorageService`] that encrypts the files of `inner` with `key`.
+    pub fn new(inner: S, key: EncryptionKey) -> EncryptedStorageService<S> {
+        EncryptedStorageService {
+            inner,
+            keys: Arc::new(Keyring {
+                primary: key,
+                others: Vec::new(),
+            }),
+        }
+    }
+
+    /// Adds a key that files are only decrypted with, like the key that was used before it
+    /// was rotated.
+    pub fn with_decryption_key(mut self, key: EncryptionKey) -> Self {
+        Arc::make_mut(&mut self.keys).others.push(key);
+        self
+    }
+
+    /// Returns the storage service whose files are encrypted.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    fn encrypt(&self, mut options: UploadRequest) -> Result<UploadRequest, EncryptionError<S::Error>> {
+        let (encrypted, nonce) = self.keys.encrypt(&options.data).ok_or(EncryptionError::Encrypt)?;
+
+        options.data = Bytes::from(encrypted);
+        options
+            .metadata
+            .insert(ENCRYPTION_KEY_ID_METADATA.to_owned(), self.keys.primary.id.clone());
+
+        options
+            .metadata
+            .insert(ENCRYPTION_NONCE_METADATA.to_owned(), hex(&nonce));
+
+        Ok(options)
+    }
+
+    fn decrypt(&self, path: &str, data: &[u8]) -> Result<Bytes, EncryptionError<S::Error>> {
+        match self.keys.decrypt(data) {
+            Ok(data) => Ok(Bytes::from(data)),
+            Err(Failure::Invalid) => Err(EncryptionError::Decrypt(path.to_owned())),
+            Err(Failure::UnknownKey(key_id)) => Err(EncryptionError::UnknownKey {
+                path: path.to_owned(),
+                key_id,
+            }),
+        }
+    }
+
+    fn decrypt_blob(&self, blob: Blob) -> Result<Blob, EncryptionError<S::Error>> {
+        let Blob::File(mut file) = blob else {
+            return Ok(blob);
+        };
+
+        let key_id = file.metadata.remove(ENCRYPTION_KEY_ID_METADATA);
+        file.metadata.remove(ENCRYPTION_NONCE_METADATA);
+
+        // listings that don't return the contents of files
+        if file.data.is_empty() {
+            if let Some(key_id) = key_id {
+                file.size = file.size.saturating_sub(overhead(&key_id));
+            }
+
+            return Ok(Blob::File(file));
+        }
+
+        let data = self.decrypt(&file.path, &file.data)?;
+        Ok(Blob::File(File {
+            size: data.len(),
+            checksum: None,
+            data,
+            ..file
+        }))
+    }
+}
+
+#[async_trait]
+impl<S: StorageService> StorageService for EncryptedStorageService<S>
+where
+    S::Error: Send,
+{
+    type Error = EncryptionError<S::Error>;
+
+    fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        self.inner.init().await.map_err(EncryptionError::Storage)
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        let path = path.as_ref();
+        match self.inner.open(path).await.map_err(EncryptionError::Storage)? {
+            Some(data) => self.decrypt(&path.display().to_string(), &data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        self.inner
+            .blob(path)
+            .await
+            .map_err(EncryptionError::Storage)?
+            .map(|blob| self.decrypt_blob(blob))
+            .transpose()
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        self.inner
+            .blobs(path, options)
+            .await
+            .map_err(EncryptionError::Storage)?
+            .into_iter()
+            .map(|blob| self.decrypt_blob(blob))
+            .collect()
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        let page = self
+            .inner
+            .blobs_page(path, options)
+            .await
+            .map_err(EncryptionError::Storage)?;
+
+        Ok(Page {
+            items: page
+                .items
+                .into_iter()
+                .map(|blob| self.decrypt_blob(blob))
+                .collect::<Result<_, _>>()?,
+            ..page
+        })
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.inner.delete(path).await.map_err(EncryptionError::Storage)
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        self.inner.delete_prefix(prefix).await.map_err(EncryptionError::Storage)
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        self.inner.copy(src, dst).await.map_err(EncryptionError::Storage)
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        self.inner.rename(from, to).await.map_err(EncryptionError::Storage)
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.inner.exists(path).await.map_err(EncryptionError::Storage)
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        let options = self.encrypt(options)?;
+        self.inner.upload(path, options).await.map_err(EncryptionError::Storage)
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        let options = self.encrypt(options)?;
+        self.inner
+            .create_exclusive(path, options)
+            .await
+            .map_err(EncryptionError::Storage)
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        self.inner.create_dir(path).await.map_err(EncryptionError::Storage)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.inner.is_read_only()
+    }
+
+    fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    fn info(&self) -> ServiceInfo {
+        self.inner.info()
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        self.inner.verify_credentials().await.map_err(EncryptionError::Storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{overhead, EncryptedStorageService, EncryptionError, EncryptionKey, Failure, Keyring};
+    use crate::{testing::TestStorage, Blob, ListBlobsRequest, StorageService, UploadRequest};
+    use bytes::Bytes;
+
+    fn request(data: &'static str) -> UploadRequest {
+        UploadRequest::default()
+            .with_data(data)
+            .with_content_type(Some("text/plain"))
+    }
+
+    fn keyring(key: EncryptionKey) -> Keyring {
+        Keyring {
+            primary: key,
+            others: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let keys = keyring(EncryptionKey::generate("weow"));
+        let (encrypted, _) = keys.encrypt(b"hello, world!").unwrap();
+
+        assert_eq!(encrypted.len(), overhead("weow") + 13);
+        assert!(!encrypted.windows(13).any(|window| window == b"hello, world!"));
+        assert_eq!(keys.decrypt(&encrypted).unwrap(), b"hello, world!");
+
+        let (empty, _) = keys.encrypt(b"").unwrap();
+        assert_eq!(keys.decrypt(&empty).unwrap(), b"");
+    }
+
+    #[test]
+    fn detects_modifications() {
+        let keys = keyring(EncryptionKey::generate("weow"));
+        let (encrypted, _) = keys.encrypt(b"hello, world!").unwrap();
+
+        for i in 0..encrypted.len() {
+            let mut modified = encrypted.clone();
+            modified[i] ^= 1;
+
+            assert!(keys.decrypt(&modified).is_err(), "byte #{i} was modified");
+        }
+
+        assert_eq!(keys.decrypt(&encrypted[..encrypted.len() - 1]), Err(Failure::Invalid));
+        assert_eq!(keys.decrypt(b"hello, world!"), Err(Failure::Invalid));
+        assert_eq!(keys.decrypt(b""), Err(Failure::Invalid));
+    }
+
+    #[test]
+    fn rotated_keys() {
+        let old = EncryptionKey::generate("old");
+        let (encrypted, _) = keyring(old.clone()).encrypt(b"weow").unwrap();
+
+        let mut keys = keyring(EncryptionKey::generate("new"));
+        assert_eq!(keys.decrypt(&encrypted), Err(Failure::UnknownKey(String::from("old"))));
+
+        keys.others.push(old);
+        assert_eq!(keys.decrypt(&encrypted).unwrap(), b"weow");
+
+        // a key with the same id, but different key material
+        let keys = keyring(EncryptionKey::generate("old"));
+        assert_eq!(keys.decrypt(&encrypted), Err(Failure::Invalid));
+    }
+
+    #[test]
+    fn redacts_keys() {
+        let key = EncryptionKey::new("weow", [7; 32]);
+        assert!(!format!("{key:?}").contains("7, 7"));
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let storage = EncryptedStorageService::new(TestStorage::new(), EncryptionKey::generate("2024-10"));
+        storage.upload("./weow.txt", request("weow")).await.unwrap();
+
+        let stored = storage.get_ref().open("./weow.txt").await.unwrap().unwrap();
+        assert!(!stored.windows(4).any(|window| window == b"weow"));
+
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        let Some(Blob::File(file)) = storage.blob("./weow.txt").await.unwrap() else {
+            panic!("expected a file");
+        };
+
+        assert_eq!(file.data, Bytes::from_static(b"weow"));
+        assert_eq!(file.size, 4);
+        assert_eq!(file.content_type.as_deref(), Some("text/plain"));
+        assert!(file.metadata.is_empty());
+
+        let blobs = storage
+            .blobs(None::<&str>, Some(ListBlobsRequest::default().with_metadata_only(true)))
+            .await
+            .unwrap();
+
+        let [Blob::File(file)] = blobs.as_slice() else {
+            panic!("expected a single file");
+        };
+
+        assert_eq!(file.size, 4);
+    }
+
+    #[tokio::test]
+    async fn decrypts_with_rotated_keys() {
+        let inner = TestStorage::new();
+        let old = EncryptionKey::generate("2024-01");
+        EncryptedStorageService::new(inner.clone(), old.clone())
+            .upload("./weow.txt", request("weow"))
+            .await
+            .unwrap();
+
+        let storage = EncryptedStorageService::new(inner, EncryptionKey::generate("2024-10")).with_decryption_key(old);
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_key() {
+        let inner = TestStorage::new();
+        EncryptedStorageService::new(inner.clone(), EncryptionKey::generate("2024-01"))
+            .upload("./weow.txt", request("weow"))
+            .await
+            .unwrap();
+
+        let storage = EncryptedStorageService::new(inner, EncryptionKey::generate("2024-10"));
+        match storage.open("./weow.txt").await {
+            Err(EncryptionError::UnknownKey { key_id, .. }) => assert_eq!(key_id, "2024-01"),
+            other => panic!("expected an unknown key, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn wrong_key() {
+        let inner = TestStorage::new();
+        EncryptedStorageService::new(inner.clone(), EncryptionKey::generate("weow"))
+            .upload("./weow.txt", request("weow"))
+            .await
+            .unwrap();
+
+        // same id, different key
+        let storage = EncryptedStorageService::new(inner, EncryptionKey::generate("weow"));
+        assert!(matches!(
+            storage.open("./weow.txt").await,
+            Err(EncryptionError::Decrypt(_))
+        ));
+
+        assert!(matches!(
+            storage.blob("./weow.txt").await,
+            Err(EncryptionError::Decrypt(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn tampered_ciphertext() {
+        let storage = EncryptedStorageService::new(TestStorage::new(), EncryptionKey::generate("weow"));
+        storage.upload("./weow.txt", request("weow")).await.unwrap();
+
+        let mut data = storage.get_ref().open("./weow.txt").await.unwrap().unwrap().to_vec();
+        *data.last_mut().unwrap() ^= 1;
+        storage
+            .get_ref()
+            .upload("./weow.txt", UploadRequest::default().with_data(data))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            storage.open("./weow.txt").await,
+            Err(EncryptionError::Decrypt(_))
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    AlreadyExists, BoxError, InvalidKey, ReadOnly, RejectedByPolicy, ResourceMissing, TooLarge, TruncatedRead,
+};
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display},
+    io,
+};
+
+/// Error that the errors of every official storage service can be converted into, so that
+/// code which works with any storage service can handle errors without knowing which one
+/// it is talking to.
+///
+/// Every variant keeps the storage service's own error, which is returned by
+/// [`source`][StdError::source] and can be downcasted with [`Error::downcast_ref`].
+///
+/// * [`std::io::Error`] (from `remi-fs`, `remi-inmemory`, and `remi-sftp`) converts with [`From`].
+/// * `remi_s3::Error` converts with [`From`].
+/// * `azure_core::Error` converts with `remi_azure::into_remi_error`.
+/// * `mongodb::error::Error` converts with `remi_gridfs::into_remi_error`.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::StorageService;
+/// #
+/// async fn read<S: StorageService>(storage: &S) -> Result<(), remi::Error>
+/// where
+///     S::Error: Into<remi::Error>,
+/// {
+///     match storage.open("./weow.txt").await.map_err(Into::into) {
+///         Ok(_) => Ok(()),
+///         Err(remi::Error::PermissionDenied(_)) => Ok(()),
+///         Err(e) => Err(e),
+///     }
+/// }
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A file or a resource that the storage service relies on (like a bucket) doesn't exist.
+    NotFound(BoxError),
+
+    /// A file already exists, like from [`StorageService::create_exclusive`][crate::StorageService::create_exclusive].
+    AlreadyExists(BoxError),
+
+    /// The storage provider rejected the credentials or the operation isn't allowed with them,
+    /// or the storage service is read-only.
+    PermissionDenied(BoxError),
+
+    /// The path, data, or configuration of the operation isn't valid, like an invalid key or
+    /// an upload that is too large.
+    InvalidInput(BoxError),
+
+    /// The operation took too long.
+    Timeout(BoxError),
+
+    /// The storage provider couldn't be reached or is overloaded, which is likely to go away
+    /// if the operation is retried.
+    Unavailable(BoxError),
+
+    /// Any other error from the storage service.
+    Backend(BoxError),
+}
+
+impl Error {
+    /// Returns the variant that `error` is converted into if it is one of the errors from
+    /// this crate, like [`Error::AlreadyExists`] for [`AlreadyExists`] or [`Error::PermissionDenied`]
+    /// for [`ReadOnly`]. Storage services use this for their errors that wrap them.
+    pub fn variant_of(error: &(dyn StdError + 'static)) -> Option<fn(BoxError) -> Error> {
+        if error.is::<AlreadyExists>() {
+            return Some(Error::AlreadyExists);
+        }
+
+        if error.is::<ResourceMissing>() {
+            return Some(Error::NotFound);
+        }
+
+        if error.is::<ReadOnly>() {
+            return Some(Error::PermissionDenied);
+        }
+
+        if error.is::<InvalidKey>() || error.is::<TooLarge>() || error.is::<RejectedByPolicy>() {
+            return Some(Error::InvalidInput);
+        }
+
+        if error.is::<TruncatedRead>() {
+            return Some(Error::Unavailable);
+        }
+
+        None
+    }
+
+    /// Returns the storage service's own error.
+    pub fn get_ref(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        match self {
+            Error::NotFound(e)
+            | Error::AlreadyExists(e)
+            | Error::PermissionDenied(e)
+            | Error::InvalidInput(e)
+            | Error::Timeout(e)
+            | Error::Unavailable(e)
+            | Error::Backend(e) => &**e,
+        }
+    }
+
+    /// Returns the storage service's own error.
+    pub fn into_inner(self) -> BoxError {
+        match self {
+            Error::NotFound(e)
+            | Error::AlreadyExists(e)
+            | Error::PermissionDenied(e)
+            | Error::InvalidInput(e)
+            | Error::Timeout(e)
+            | Error::Unavailable(e)
+            | Error::Backend(e) => e,
+        }
+    }
+
+    /// Returns the storage service's own error if it is a `T`.
+    pub fn downcast_ref<T: StdError + 'static>(&self) -> Option<&T> {
+        self.get_ref().downcast_ref()
+    }
+
+    /// Returns `true` if the operation is likely to succeed when retried, which is the case
+    /// for [`Error::Timeout`] and [`Error::Unavailable`].
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::Timeout(_) | Error::Unavailable(_))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::NotFound(_) => "not found",
+            Error::AlreadyExists(_) => "already exists",
+            Error::PermissionDenied(_) => "permission denied",
+            Error::InvalidInput(_) => "invalid input",
+            Error::Timeout(_) => "operation timed out",
+            Error::Unavailable(_) => "storage provider is unavailable",
+            Error::Backend(_) => "storage service failed",
+        })
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.get_ref())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        if let Some(variant) = error.get_ref().and_then(|e| Error::variant_of(e)) {
+            return variant(Box::new(error));
+        }
+
+        let variant = match error.kind() {
+            io::ErrorKind::NotFound => Error::NotFound,
+            io::ErrorKind::AlreadyExists => Error::AlreadyExists,
+            io::ErrorKind::PermissionDenied => Error::PermissionDenied,
+            io::ErrorKind::InvalidInput => Error::InvalidInput,
+            io::ErrorKind::TimedOut => Error::Timeout,
+            io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::UnexpectedEof => Error::Unavailable,
+            _ => Error::Backend,
+        };
+
+        variant(Box::new(error))
+    }
+}
+
+impl From<BoxError> for Error {
+    fn from(error: BoxError) -> Self {
+        let error = match error.downcast::<Error>() {
+            Ok(error) => return *error,
+            Err(error) => error,
+        };
+
+        let error = match error.downcast::<io::Error>() {
+            Ok(error) => return Error::from(*error),
+            Err(error) => error,
+        };
+
+        match Error::variant_of(&*error) {
+            Some(variant) => variant(error),
+            None => Error::Backend(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use crate::{AlreadyExists, BoxError, ReadOnly};
+    use std::{error::Error as _, io};
+
+    #[test]
+    fn io_errors() {
+        let error = Error::from(io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(error, Error::NotFound(_)));
+        assert_eq!(
+            error.downcast_ref::<io::Error>().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        let error = Error::from(io::Error::other(AlreadyExists::new("fs://weow.txt")));
+        assert!(matches!(error, Error::AlreadyExists(_)));
+        assert!(error.source().is_some());
+
+        assert!(Error::from(io::Error::from(io::ErrorKind::ConnectionReset)).is_retryable());
+        assert!(matches!(Error::from(io::Error::other("weow")), Error::Backend(_)));
+    }
+
+    #[test]
+    fn boxed_errors() {
+        let error = Error::from(BoxError::from(ReadOnly { operation: "upload" }));
+        assert!(matches!(error, Error::PermissionDenied(_)));
+        assert!(error.downcast_ref::<ReadOnly>().is_some());
+
+        let error = Error::from(BoxError::from(io::Error::from(io::ErrorKind::TimedOut)));
+        assert!(matches!(error, Error::Timeout(_)));
+
+        let error = Error::from(BoxError::from(Error::NotFound("weow".into())));
+        assert!(matches!(error, Error::NotFound(_)));
+        assert_eq!(error.get_ref().to_string(), "weow");
+    }
+}
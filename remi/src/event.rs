@@ -0,0 +1,148 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::UploadRequest;
+use std::collections::HashMap;
+
+/// Represents a mutation of an object in a storage service that was successful. Storage
+/// services that were configured with an event channel publish these after an upload
+/// or deletion was successful, so applications can update indexes or bust caches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageEvent {
+    /// An object was uploaded to a path that didn't exist before.
+    Created(ObjectEvent),
+
+    /// An object was uploaded to a path that already existed.
+    Updated(ObjectEvent),
+
+    /// An object was deleted.
+    Deleted {
+        /// Path of the object, usually `{service}://{full filepath}`
+        path: String,
+    },
+}
+
+impl StorageEvent {
+    /// Creates a [`StorageEvent::Created`] or [`StorageEvent::Updated`] event for an upload.
+    pub fn uploaded(path: String, request: &UploadRequest, existed: bool) -> StorageEvent {
+        let event = ObjectEvent {
+            path,
+            size: request.data.len(),
+            content_type: request.content_type.clone(),
+            metadata: request.metadata.clone(),
+        };
+
+        if existed {
+            StorageEvent::Updated(event)
+        } else {
+            StorageEvent::Created(event)
+        }
+    }
+
+    /// Returns the path of the object that this event is for.
+    pub fn path(&self) -> &str {
+        match self {
+            StorageEvent::Created(event) | StorageEvent::Updated(event) => &event.path,
+            StorageEvent::Deleted { path } => path,
+        }
+    }
+}
+
+/// Information about an object that was uploaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectEvent {
+    /// Path of the object, usually `{service}://{full filepath}`
+    pub path: String,
+
+    /// Size of the object (in bytes)
+    pub size: usize,
+
+    /// Content type of the object, if one was given.
+    pub content_type: Option<String>,
+
+    /// Metadata that was uploaded with the object.
+    pub metadata: HashMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StorageEvent;
+    use crate::{testing::TestStorage, StorageService, UploadRequest};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn publishes_mutations() {
+        let storage = TestStorage::new();
+        let metadata = HashMap::from([(String::from("owner"), String::from("noel"))]);
+
+        storage
+            .upload(
+                "weow.txt",
+                UploadRequest::default()
+                    .with_data("weow")
+                    .with_content_type(Some("text/plain"))
+                    .with_metadata(metadata.clone()),
+            )
+            .await
+            .unwrap();
+
+        storage
+            .upload("weow.txt", UploadRequest::default().with_data("fluff"))
+            .await
+            .unwrap();
+
+        assert!(storage.delete("weow.txt").await.unwrap());
+        assert!(!storage.delete("weow.txt").await.unwrap());
+
+        let events = storage.take_events();
+        let [StorageEvent::Created(created), StorageEvent::Updated(updated), StorageEvent::Deleted { path }] =
+            events.as_slice()
+        else {
+            panic!("expected a created, updated, and deleted event, got {events:?}");
+        };
+
+        assert_eq!(created.path, "memory://weow.txt");
+        assert_eq!(created.size, 4);
+        assert_eq!(created.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(created.metadata, metadata);
+
+        assert_eq!(updated.size, 5);
+        assert_eq!(updated.content_type, None);
+        assert_eq!(path, "memory://weow.txt");
+        assert!(events.iter().all(|event| event.path() == "memory://weow.txt"));
+    }
+
+    #[tokio::test]
+    async fn failed_mutations_are_not_published() {
+        let storage = TestStorage::new();
+        storage
+            .create_exclusive("weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        assert!(storage
+            .create_exclusive("weow.txt", UploadRequest::default().with_data("fluff"))
+            .await
+            .is_err());
+
+        assert!(matches!(storage.take_events().as_slice(), [StorageEvent::Created(event)] if event.size == 4));
+    }
+}
@@ -0,0 +1,157 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+};
+
+/// Type map of extra, storage service-specific options that can be attached to a request,
+/// like which bucket an upload should go into.
+///
+/// Storage services will ignore any extension that they don't know about.
+///
+/// ## Example
+/// ```rust
+/// # use remi::Extensions;
+/// #
+/// #[derive(Clone, PartialEq, Debug)]
+/// struct Bucket(&'static str);
+///
+/// let mut extensions = Extensions::default();
+/// assert!(extensions.insert(Bucket("charts")).is_none());
+/// assert_eq!(extensions.get::<Bucket>(), Some(&Bucket("charts")));
+/// ```
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn AnyClone>>,
+}
+
+impl Extensions {
+    /// Inserts an extension, returning the previous extension of the same type.
+    pub fn insert<T: Clone + Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|prev| AnyClone::into_any(prev).downcast().ok().map(|boxed| *boxed))
+    }
+
+    /// Returns a reference to the extension of type `T`, if one was inserted.
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any().downcast_ref())
+    }
+
+    /// Returns a mutable reference to the extension of type `T`, if one was inserted.
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any_mut().downcast_mut())
+    }
+
+    /// Removes the extension of type `T` and returns it, if one was inserted.
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| AnyClone::into_any(value).downcast().ok().map(|boxed| *boxed))
+    }
+
+    /// Whether if an extension of type `T` was inserted.
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns how many extensions were inserted.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether if no extensions were inserted.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl Clone for Extensions {
+    fn clone(&self) -> Self {
+        Extensions {
+            map: self
+                .map
+                .iter()
+                .map(|(id, value)| (*id, (**value).clone_box()))
+                .collect(),
+        }
+    }
+}
+
+impl Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Extensions").field("len", &self.map.len()).finish()
+    }
+}
+
+trait AnyClone: Any + Send + Sync {
+    fn clone_box(&self) -> Box<dyn AnyClone>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Clone + Send + Sync + 'static> AnyClone for T {
+    fn clone_box(&self) -> Box<dyn AnyClone> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Extensions;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Bucket(&'static str);
+
+    #[test]
+    fn insert_get_remove() {
+        let mut extensions = Extensions::default();
+        assert!(extensions.insert(Bucket("charts")).is_none());
+        assert_eq!(extensions.insert(Bucket("images")), Some(Bucket("charts")));
+
+        let cloned = extensions.clone();
+        extensions.get_mut::<Bucket>().unwrap().0 = "avatars";
+
+        assert_eq!(cloned.get::<Bucket>(), Some(&Bucket("images")));
+        assert_eq!(extensions.remove::<Bucket>(), Some(Bucket("avatars")));
+        assert!(extensions.is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{fmt::Display, time::Duration};
+
+/// Key that the default [`StorageService::healthcheck`][crate::StorageService::healthcheck]
+/// checks the existence of, which doesn't need to exist.
+pub const HEALTHCHECK_KEY: &str = "./.remi/probes/healthcheck";
+
+/// Represents a report from [`StorageService::healthcheck`][crate::StorageService::healthcheck],
+/// which describes whether the storage provider could be reached, how long it took to respond,
+/// and what the storage service was allowed to do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthReport {
+    /// Whether the storage provider responded to the healthcheck, even if it was
+    /// to deny access.
+    pub reachable: bool,
+
+    /// How long the healthcheck took, including every request that it sent.
+    pub latency: Duration,
+
+    /// Permissions that the healthcheck has probed.
+    pub permissions: Permissions,
+
+    /// Error message of the probe that failed, if any.
+    pub error: Option<String>,
+}
+
+/// Permissions that a [`HealthReport`] has probed. Permissions are `None` if the healthcheck
+/// didn't probe them, like writes on storage services that shouldn't write files to check
+/// their health.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Permissions {
+    /// Whether files can be read.
+    pub read: Option<bool>,
+
+    /// Whether files can be written.
+    pub write: Option<bool>,
+}
+
+impl HealthReport {
+    /// Creates a [`HealthReport`] for a storage provider that responded after `latency`.
+    pub fn reachable(latency: Duration) -> HealthReport {
+        HealthReport {
+            reachable: true,
+            latency,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a [`HealthReport`] for a storage provider that couldn't be reached because of `error`.
+    pub fn unreachable<E: Display>(latency: Duration, error: E) -> HealthReport {
+        HealthReport {
+            latency,
+            error: Some(error.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Sets whether files can be read.
+    pub fn with_read(mut self, allowed: bool) -> Self {
+        self.permissions.read = Some(allowed);
+        self
+    }
+
+    /// Sets whether files can be written.
+    pub fn with_write(mut self, allowed: bool) -> Self {
+        self.permissions.write = Some(allowed);
+        self
+    }
+
+    /// Sets the error message of the probe that failed.
+    pub fn with_error<E: Display>(mut self, error: E) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+
+    /// Returns `true` if the storage provider was reachable, no probe failed, and no
+    /// permission was denied.
+    pub fn is_healthy(&self) -> bool {
+        self.reachable
+            && self.error.is_none()
+            && self.permissions.read != Some(false)
+            && self.permissions.write != Some(false)
+    }
+}
+
+impl Display for HealthReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match (self.is_healthy(), self.reachable) {
+            (true, _) => "healthy",
+            (false, true) => "unhealthy",
+            (false, false) => "unreachable",
+        };
+
+        write!(f, "{status} in {}ms", self.latency.as_millis())?;
+        for (name, allowed) in [("read", self.permissions.read), ("write", self.permissions.write)] {
+            match allowed {
+                Some(true) => write!(f, ", can {name}")?,
+                Some(false) => write!(f, ", can't {name}")?,
+                None => {}
+            }
+        }
+
+        if let Some(ref error) = self.error {
+            write!(f, ": {error}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HealthReport;
+    use std::time::Duration;
+
+    #[test]
+    fn report() {
+        let report = HealthReport::reachable(Duration::from_millis(12)).with_read(true);
+        assert!(report.is_healthy());
+        assert_eq!(report.to_string(), "healthy in 12ms, can read");
+
+        let report = HealthReport::reachable(Duration::from_millis(3))
+            .with_read(true)
+            .with_write(false)
+            .with_error("access denied");
+
+        assert!(!report.is_healthy());
+        assert_eq!(
+            report.to_string(),
+            "unhealthy in 3ms, can read, can't write: access denied"
+        );
+
+        let report = HealthReport::unreachable(Duration::from_secs(1), "connection refused");
+        assert!(!report.is_healthy());
+        assert_eq!(report.to_string(), "unreachable in 1000ms: connection refused");
+    }
+}
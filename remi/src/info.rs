@@ -0,0 +1,119 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Identity of a storage service and what it supports, for apps that want to enumerate their
+//! configured storage services (like on a diagnostics endpoint) without reaching into their
+//! configuration.
+
+/// Describes a storage service, see [`StorageService::info`][crate::StorageService::info].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServiceInfo {
+    /// Name of the storage service, which is the same as [`StorageService::name`][crate::StorageService::name].
+    pub name: String,
+
+    /// Version of the crate that implements the storage service, or `None` if it isn't
+    /// reported by the storage service.
+    pub crate_version: Option<String>,
+
+    /// Endpoint of the storage provider that requests are sent to, or `None` if there
+    /// isn't one (like the local filesystem) or it is resolved by the provider's SDK.
+    pub provider_endpoint: Option<String>,
+
+    /// Where files are kept in the storage provider, like the name of a bucket, container,
+    /// or directory.
+    pub location: Option<String>,
+
+    /// What the storage service supports.
+    pub capabilities: Capabilities,
+}
+
+impl ServiceInfo {
+    /// Creates a [`ServiceInfo`] with the given name, which is what
+    /// [`StorageService::info`][crate::StorageService::info] returns by default.
+    pub fn new<N: Into<String>>(name: N) -> ServiceInfo {
+        ServiceInfo {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the [`ServiceInfo::crate_version`], which is usually `env!("CARGO_PKG_VERSION")`.
+    pub fn with_crate_version<V: Into<String>>(mut self, version: V) -> Self {
+        self.crate_version = Some(version.into());
+        self
+    }
+
+    /// Sets the [`ServiceInfo::provider_endpoint`].
+    pub fn with_provider_endpoint<E: Into<String>>(mut self, endpoint: Option<E>) -> Self {
+        self.provider_endpoint = endpoint.map(Into::into);
+        self
+    }
+
+    /// Sets the [`ServiceInfo::location`].
+    pub fn with_location<L: Into<String>>(mut self, location: Option<L>) -> Self {
+        self.location = location.map(Into::into);
+        self
+    }
+
+    /// Sets the [`ServiceInfo::capabilities`].
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+}
+
+/// Operations that a storage service does natively with its storage provider, instead of
+/// relying on the default implementations of [`StorageService`][crate::StorageService]
+/// which might download whole files or aren't atomic.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities {
+    /// Whether if [`open_range`][crate::StorageService::open_range] only reads the
+    /// requested range.
+    pub range_reads: bool,
+
+    /// Whether if [`open_stream`][crate::StorageService::open_stream] streams files from the
+    /// storage provider instead of reading them whole.
+    pub streaming: bool,
+
+    /// Whether if [`blobs_page`][crate::StorageService::blobs_page] returns more than one
+    /// page, so large listings aren't kept in memory at once.
+    pub pagination: bool,
+
+    /// Whether if [`copy`][crate::StorageService::copy] copies files on the storage
+    /// provider's side.
+    pub server_side_copy: bool,
+
+    /// Whether if [`rename`][crate::StorageService::rename] moves files atomically.
+    pub atomic_rename: bool,
+
+    /// Whether if uploads can keep the previous file with
+    /// [`CollisionStrategy::NewVersion`][crate::CollisionStrategy::NewVersion].
+    pub versioning: bool,
+
+    /// Whether if the storage service implements [`PresignableStorageService`][crate::PresignableStorageService].
+    pub presigning: bool,
+}
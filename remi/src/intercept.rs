@@ -0,0 +1,164 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use crate::UploadRequest;
+use async_trait::async_trait;
+use std::{fmt::Display, sync::Arc};
+
+/// Error that storage services return when an [`UploadInterceptor`] rejected an upload
+/// before it was written.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RejectedByPolicy {
+    /// Path of the file that was rejected, usually `{service}://{full filepath}`.
+    pub path: String,
+
+    /// Reason that the interceptor gave for rejecting the upload.
+    pub reason: String,
+}
+
+impl RejectedByPolicy {
+    /// Creates a new [`RejectedByPolicy`] error for a file in `path`.
+    pub fn new<P: Into<String>, R: Into<String>>(path: P, reason: R) -> RejectedByPolicy {
+        RejectedByPolicy {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl Display for RejectedByPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "upload to [{}] was rejected: {}", self.path, self.reason)
+    }
+}
+
+impl std::error::Error for RejectedByPolicy {}
+
+/// Inspects uploads before a storage service writes them, which can be used to scan
+/// payloads for viruses or to block files with executable magic bytes.
+///
+/// Synchronous closures that take the path and the [`UploadRequest`] implement this trait
+/// as well.
+///
+/// ## Example
+/// ```rust
+/// # use remi::{Bytes, UploadInterceptors, UploadRequest};
+/// # use futures_util::FutureExt;
+/// #
+/// let mut interceptors = UploadInterceptors::default();
+/// interceptors.push(|_: &str, request: &UploadRequest| match request.data.starts_with(b"MZ") {
+///     true => Err(String::from("executables are not allowed")),
+///     false => Ok(()),
+/// });
+///
+/// let request = UploadRequest::default().with_data(Bytes::from_static(b"MZ\x90\x00"));
+/// let error = interceptors.check("fs://weow.exe", &request).now_or_never().unwrap().unwrap_err();
+/// assert_eq!(error.reason, "executables are not allowed");
+/// ```
+#[async_trait]
+pub trait UploadInterceptor: Send + Sync {
+    /// Inspects the upload of `request` into `path`, which is usually
+    /// `{service}://{full filepath}`. Returning an error rejects the upload with a
+    /// [`RejectedByPolicy`] error that carries it as the reason.
+    async fn intercept(&self, path: &str, request: &UploadRequest) -> Result<(), String>;
+}
+
+#[async_trait]
+impl<F> UploadInterceptor for F
+where
+    F: Fn(&str, &UploadRequest) -> Result<(), String> + Send + Sync,
+{
+    async fn intercept(&self, path: &str, request: &UploadRequest) -> Result<(), String> {
+        (self)(path, request)
+    }
+}
+
+/// List of [`UploadInterceptor`]s that a storage service runs in the order that they
+/// were registered before every upload.
+#[derive(Clone, Default)]
+pub struct UploadInterceptors(Vec<Arc<dyn UploadInterceptor>>);
+
+impl UploadInterceptors {
+    /// Registers an [`UploadInterceptor`] that runs after all the ones that were
+    /// registered before it.
+    pub fn push<I: UploadInterceptor + 'static>(&mut self, interceptor: I) {
+        self.0.push(Arc::new(interceptor));
+    }
+
+    /// Returns `true` if no interceptors were registered.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Runs every interceptor on the upload of `request` into `path` and stops at the
+    /// first one that rejected it.
+    pub async fn check(&self, path: &str, request: &UploadRequest) -> Result<(), RejectedByPolicy> {
+        for interceptor in &self.0 {
+            interceptor
+                .intercept(path, request)
+                .await
+                .map_err(|reason| RejectedByPolicy::new(path, reason))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for UploadInterceptors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("UploadInterceptors").field(&self.0.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UploadInterceptors;
+    use crate::UploadRequest;
+    use futures_util::FutureExt;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn stops_at_first_rejection() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut interceptors = UploadInterceptors::default();
+        interceptors.push(|_: &str, _: &UploadRequest| Ok(()));
+        interceptors.push(|_: &str, _: &UploadRequest| Err(String::from("nope")));
+
+        let counter = calls.clone();
+        interceptors.push(move |_: &str, _: &UploadRequest| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+
+        let error = interceptors
+            .check("fs://weow.txt", &UploadRequest::default())
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+
+        assert_eq!(error.path, "fs://weow.txt");
+        assert_eq!(error.reason, "nope");
+        assert_eq!(error.to_string(), "upload to [fs://weow.txt] was rejected: nope");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,1052 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, Blob, CollisionStrategy, DeleteSummary, ListBlobsRequest, Page, ServiceInfo, Stats, StorageService,
+    UploadRequest, DEFAULT_DIRECTORY_MARKER,
+};
+use bytes::Bytes;
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Display},
+    future::Future,
+    io,
+    path::Path,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Prefix of where a [`JournaledStorageService`] stages the data of uploads in the storage
+/// service that it wraps, until the upload has completed.
+///
+/// * since: 0.11.0
+pub const JOURNAL_STAGING_PREFIX: &str = "./.remi/staged";
+
+/// Prefix of where a [`StorageJournal`] keeps its entries, unless another one is given.
+///
+/// * since: 0.11.0
+pub const JOURNAL_PREFIX: &str = "./.remi/journal";
+
+/// Mutation that a [`JournaledStorageService`] records in its [`Journal`] before it is performed.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalOperation {
+    /// A file is uploaded into `path` from the data that was staged into `staged`.
+    Upload {
+        /// Key that the file is uploaded into.
+        path: String,
+
+        /// Key that the data of the upload was staged into, see [`JOURNAL_STAGING_PREFIX`].
+        staged: String,
+
+        /// Whether if the upload must not replace an existing file, like with
+        /// [`create_exclusive`][StorageService::create_exclusive].
+        exclusive: bool,
+    },
+
+    /// The file in `path` is deleted.
+    Delete {
+        /// Key of the file.
+        path: String,
+    },
+
+    /// Every file under `prefix` is deleted.
+    DeletePrefix {
+        /// Prefix of the files.
+        prefix: String,
+    },
+
+    /// The file in `src` is copied into `dst`.
+    Copy {
+        /// Key of the file that is copied.
+        src: String,
+
+        /// Key that the file is copied into.
+        dst: String,
+    },
+
+    /// The file in `from` is renamed to `to`.
+    Rename {
+        /// Key of the file that is renamed.
+        from: String,
+
+        /// Key that the file is renamed to.
+        to: String,
+    },
+}
+
+/// A [`JournalOperation`] with the unique identifier that it was recorded with.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Unique identifier of this entry. Identifiers of entries that were recorded by the
+    /// same process sort in the order that they were recorded in.
+    pub id: String,
+
+    /// Mutation that was recorded.
+    pub operation: JournalOperation,
+}
+
+impl JournalEntry {
+    /// Creates a new [`JournalEntry`] of `operation` with a unique identifier.
+    pub fn new(operation: JournalOperation) -> JournalEntry {
+        JournalEntry {
+            id: next_id(),
+            operation,
+        }
+    }
+
+    /// Encodes this entry into a single line, which can be decoded again with [`JournalEntry::decode`].
+    pub fn encode(&self) -> String {
+        let fields: Vec<&str> = match &self.operation {
+            JournalOperation::Upload {
+                path,
+                staged,
+                exclusive,
+            } => vec![
+                "upload",
+                &self.id,
+                path,
+                staged,
+                if *exclusive { "exclusive" } else { "overwrite" },
+            ],
+
+            JournalOperation::Delete { path } => vec!["delete", &self.id, path],
+            JournalOperation::DeletePrefix { prefix } => vec!["delete_prefix", &self.id, prefix],
+            JournalOperation::Copy { src, dst } => vec!["copy", &self.id, src, dst],
+            JournalOperation::Rename { from, to } => vec!["rename", &self.id, from, to],
+        };
+
+        fields.into_iter().map(escape).collect::<Vec<_>>().join("\t")
+    }
+
+    /// Decodes an entry that was encoded with [`JournalEntry::encode`], returning `None` if
+    /// `line` isn't a valid entry.
+    pub fn decode(line: &str) -> Option<JournalEntry> {
+        let fields = line.split('\t').map(unescape).collect::<Option<Vec<_>>>()?;
+        let (kind, id, args) = match fields.as_slice() {
+            [kind, id, args @ ..] => (kind.as_str(), id.clone(), args),
+            _ => return None,
+        };
+
+        let operation = match (kind, args) {
+            ("upload", [path, staged, mode]) => JournalOperation::Upload {
+                path: path.clone(),
+                staged: staged.clone(),
+                exclusive: match mode.as_str() {
+                    "exclusive" => true,
+                    "overwrite" => false,
+                    _ => return None,
+                },
+            },
+
+            ("delete", [path]) => JournalOperation::Delete { path: path.clone() },
+            ("delete_prefix", [prefix]) => JournalOperation::DeletePrefix { prefix: prefix.clone() },
+            ("copy", [src, dst]) => JournalOperation::Copy {
+                src: src.clone(),
+                dst: dst.clone(),
+            },
+
+            ("rename", [from, to]) => JournalOperation::Rename {
+                from: from.clone(),
+                to: to.clone(),
+            },
+
+            _ => return None,
+        };
+
+        Some(JournalEntry { id, operation })
+    }
+}
+
+/// Returns a unique identifier for a [`JournalEntry`].
+fn next_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    format!(
+        "{nanos:032x}-{:08x}-{:016x}",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+fn escape(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(field: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+
+    Some(unescaped)
+}
+
+/// Durable record of the mutations that a [`JournaledStorageService`] intends to perform.
+///
+/// An entry is [recorded][Journal::record] before its mutation is performed and
+/// [completed][Journal::complete] after it has finished, so the entries that are
+/// [pending][Journal::pending] after a crash are the mutations that might only be
+/// half-finished. Implementations must only return from [`Journal::record`] once the
+/// entry is durable.
+///
+/// * since: 0.11.0
+#[async_trait]
+pub trait Journal: Send + Sync {
+    /// Records `entry`, which is pending until it is completed.
+    async fn record(&self, entry: &JournalEntry) -> io::Result<()>;
+
+    /// Completes the entry with the given identifier. Completing an entry that isn't
+    /// pending does nothing.
+    async fn complete(&self, id: &str) -> io::Result<()>;
+
+    /// Returns every entry that was recorded but not completed, in the order of their identifiers.
+    async fn pending(&self) -> io::Result<Vec<JournalEntry>>;
+}
+
+/// [`Journal`] that keeps each entry as a file (in [`JOURNAL_PREFIX`] by default) of a storage
+/// service, which is deleted when the entry is completed.
+///
+/// The storage service can be the same one that is being journaled, as long as it isn't
+/// a [`JournaledStorageService`] itself.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct StorageJournal<S> {
+    storage: S,
+    prefix: String,
+}
+
+impl<S: StorageService> StorageJournal<S>
+where
+    S::Error: Error + Send + Sync + 'static,
+{
+    /// Creates a new [`StorageJournal`] that keeps its entries in [`JOURNAL_PREFIX`] of `storage`.
+    pub fn new(storage: S) -> StorageJournal<S> {
+        StorageJournal {
+            storage,
+            prefix: JOURNAL_PREFIX.to_owned(),
+        }
+    }
+
+    /// Keeps the entries of this journal in `prefix` instead of [`JOURNAL_PREFIX`].
+    pub fn with_prefix<I: Into<String>>(mut self, prefix: I) -> StorageJournal<S> {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn key(&self, id: &str) -> String {
+        format!("{}/{id}", self.prefix.trim_end_matches('/'))
+    }
+}
+
+#[async_trait]
+impl<S: StorageService> Journal for StorageJournal<S>
+where
+    S::Error: Error + Send + Sync + 'static,
+{
+    async fn record(&self, entry: &JournalEntry) -> io::Result<()> {
+        self.storage
+            .upload(
+                self.key(&entry.id),
+                UploadRequest::default()
+                    .with_content_type(Some("text/plain; charset=utf-8"))
+                    .with_data(entry.encode()),
+            )
+            .await
+            .map_err(io::Error::other)
+    }
+
+    async fn complete(&self, id: &str) -> io::Result<()> {
+        self.storage
+            .delete(self.key(id))
+            .await
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+
+    async fn pending(&self) -> io::Result<Vec<JournalEntry>> {
+        let blobs = self
+            .storage
+            .blobs(Some(&self.prefix), None)
+            .await
+            .map_err(io::Error::other)?;
+
+        let mut entries = Vec::with_capacity(blobs.len());
+        for blob in blobs {
+            let Blob::File(file) = blob else {
+                continue;
+            };
+
+            // some storage services (like S3) return the full key as the name of a file
+            let name = file.name.rsplit('/').next().unwrap_or(&file.name);
+            if name == DEFAULT_DIRECTORY_MARKER {
+                continue;
+            }
+
+            // listings don't return the contents of files on every storage service
+            let Some(data) = self.storage.open(self.key(name)).await.map_err(io::Error::other)? else {
+                // the entry was completed after it was listed
+                continue;
+            };
+
+            let entry = std::str::from_utf8(&data)
+                .ok()
+                .and_then(JournalEntry::decode)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("journal entry [{}] is corrupted", file.path),
+                    )
+                })?;
+
+            entries.push(entry);
+        }
+
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(entries)
+    }
+}
+
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+pub use file::FileJournal;
+
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+mod file {
+    use super::{Journal, JournalEntry};
+    use crate::{async_trait, rt};
+    use std::{
+        collections::BTreeMap,
+        fs::OpenOptions,
+        io::{self, Write},
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex, PoisonError},
+    };
+
+    /// [`Journal`] that appends its entries to a file on the local filesystem, which is
+    /// synced to disk after every write.
+    ///
+    /// Recorded entries are written as `+<entry>` lines and completions as `-<id>` lines. Once
+    /// no entries are pending, the file is truncated so that it doesn't keep growing.
+    ///
+    /// * since: 0.11.0
+    #[derive(Debug, Clone)]
+    #[cfg_attr(any(noeldoc, docsrs), doc(cfg(any(feature = "rt-tokio", feature = "rt-async-std"))))]
+    pub struct FileJournal {
+        path: PathBuf,
+
+        // serializes writes from clones of this journal, and keeps track of how many
+        // entries are pending so the file can be truncated once there are none
+        pending: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl FileJournal {
+        /// Creates a [`FileJournal`] that appends to the file in `path`, which is created
+        /// when the first entry is recorded.
+        pub fn new<P: AsRef<Path>>(path: P) -> FileJournal {
+            FileJournal {
+                path: path.as_ref().to_owned(),
+                pending: Arc::default(),
+            }
+        }
+
+        /// Returns the path of the file that this journal appends to.
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        async fn append(&self, line: String, delta: isize) -> io::Result<()> {
+            let path = self.path.clone();
+            let pending = self.pending.clone();
+            rt::spawn_blocking("remi::journal::append", move || {
+                let mut pending = pending.lock().unwrap_or_else(PoisonError::into_inner);
+                let count = match *pending {
+                    Some(count) => count,
+                    None => read(&path)?.len(),
+                };
+
+                let count = count.saturating_add_signed(delta);
+                let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+                if count == 0 {
+                    file.set_len(0)?;
+                } else {
+                    file.write_all(format!("{line}\n").as_bytes())?;
+                }
+
+                file.sync_data()?;
+                *pending = Some(count);
+
+                Ok(())
+            })
+            .await
+        }
+    }
+
+    /// Reads the pending entries of the journal in `path`.
+    fn read(path: &Path) -> io::Result<Vec<JournalEntry>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries = BTreeMap::new();
+        for line in contents.lines() {
+            if let Some(id) = line.strip_prefix('-') {
+                entries.remove(id);
+                continue;
+            }
+
+            // the last line could've been cut off by a crash while it was written, in which
+            // case its mutation was never performed
+            if let Some(entry) = line.strip_prefix('+').and_then(JournalEntry::decode) {
+                entries.insert(entry.id.clone(), entry);
+            }
+        }
+
+        Ok(entries.into_values().collect())
+    }
+
+    #[async_trait]
+    impl Journal for FileJournal {
+        async fn record(&self, entry: &JournalEntry) -> io::Result<()> {
+            self.append(format!("+{}", entry.encode()), 1).await
+        }
+
+        async fn complete(&self, id: &str) -> io::Result<()> {
+            self.append(format!("-{id}"), -1).await
+        }
+
+        async fn pending(&self) -> io::Result<Vec<JournalEntry>> {
+            let path = self.path.clone();
+            let pending = self.pending.clone();
+            rt::spawn_blocking("remi::journal::pending", move || {
+                let _guard = pending.lock().unwrap_or_else(PoisonError::into_inner);
+                read(&path)
+            })
+            .await
+        }
+    }
+}
+
+/// Error from a [`JournaledStorageService`].
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub enum JournalError<E> {
+    /// The storage service that is being journaled returned an error.
+    Storage(E),
+
+    /// The journal couldn't be read or written.
+    Journal(io::Error),
+}
+
+impl<E: Display> Display for JournalError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Storage(e) => Display::fmt(e, f),
+            JournalError::Journal(e) => write!(f, "failed to write journal: {e}"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for JournalError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JournalError::Storage(e) => Some(e),
+            JournalError::Journal(e) => Some(e),
+        }
+    }
+}
+
+/// A [`StorageService`] that records every mutation in a [`Journal`] before it is performed
+/// and completes it afterwards, so that the mutations which were interrupted by a crash can
+/// be finished with [`JournaledStorageService::recover`] when the app restarts.
+///
+/// The data of an upload is staged into [`JOURNAL_STAGING_PREFIX`] first, so an interrupted
+/// upload can be replayed without the caller. This writes every upload twice, which is the
+/// price of the stronger guarantee. Mutations that return an error are completed as well,
+/// since the caller already knows that they failed.
+///
+/// Reads, listings, and [`create_dir`][StorageService::create_dir] aren't journaled.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{FileJournal, JournaledStorageService};
+/// #
+/// let storage = JournaledStorageService::new(storage, FileJournal::new("/var/lib/app/remi.journal"));
+///
+/// // finish what the last run of the app was doing before it crashed
+/// storage.recover().await?;
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct JournaledStorageService<S, J> {
+    inner: S,
+    journal: J,
+}
+
+impl<S: StorageService, J: Journal> JournaledStorageService<S, J> {
+    /// Creates a new [`JournaledStorageService`] that records the mutations of `inner` in `journal`.
+    pub fn new(inner: S, journal: J) -> JournaledStorageService<S, J> {
+        JournaledStorageService { inner, journal }
+    }
+
+    /// Returns the storage service that is being journaled.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the [`Journal`] that mutations are recorded in.
+    pub fn journal(&self) -> &J {
+        &self.journal
+    }
+
+    /// Replays every pending entry of the journal in the order that they were recorded
+    /// and returns how many were replayed. This should be called when the app starts,
+    /// before any other mutation is performed.
+    ///
+    /// Every mutation is replayed in a way that is safe if it had already finished:
+    ///
+    /// - Uploads are uploaded again from their staged data, with the content type and
+    ///   metadata that the staged file kept. Exclusive uploads are skipped if the file
+    ///   already exists.
+    /// - Deletions are performed again.
+    /// - Copies and renames are performed again if their source still exists.
+    ///
+    /// If replaying an entry fails, the error is returned and the entry (and every entry
+    /// after it) stays pending.
+    pub async fn recover(&self) -> Result<usize, JournalError<S::Error>> {
+        let pending = self.journal.pending().await.map_err(JournalError::Journal)?;
+        for entry in &pending {
+            #[cfg(feature = "tracing")]
+            ::tracing::info!(id = %entry.id, operation = ?entry.operation, "replaying journal entry");
+
+            #[cfg(feature = "log")]
+            ::log::info!("replaying journal entry [{}]: {:?}", entry.id, entry.operation);
+
+            self.replay(&entry.operation).await.map_err(JournalError::Storage)?;
+            self.journal.complete(&entry.id).await.map_err(JournalError::Journal)?;
+
+            if let JournalOperation::Upload { staged, .. } = &entry.operation {
+                self.cleanup(staged).await;
+            }
+        }
+
+        Ok(pending.len())
+    }
+
+    async fn replay(&self, operation: &JournalOperation) -> Result<(), S::Error> {
+        match operation {
+            JournalOperation::Upload {
+                path,
+                staged,
+                exclusive,
+            } => {
+                if *exclusive && self.inner.exists(path).await? {
+                    return Ok(());
+                }
+
+                // the staged data is only deleted after the entry was completed
+                let Some(Blob::File(file)) = self.inner.blob(staged).await? else {
+                    return Ok(());
+                };
+
+                let request = UploadRequest::default()
+                    .with_content_type(file.content_type)
+                    .with_metadata(file.metadata)
+                    .with_data(file.data);
+
+                match exclusive {
+                    true => self.inner.create_exclusive(path, request).await,
+                    false => self.inner.upload(path, request).await,
+                }
+            }
+
+            JournalOperation::Delete { path } => self.inner.delete(path).await.map(|_| ()),
+            JournalOperation::DeletePrefix { prefix } => self.inner.delete_prefix(prefix).await.map(|_| ()),
+            JournalOperation::Copy { src, dst } => match self.inner.exists(src).await? {
+                true => self.inner.copy(src, dst).await.map(|_| ()),
+                false => Ok(()),
+            },
+
+            JournalOperation::Rename { from, to } => match self.inner.exists(from).await? {
+                true => self.inner.rename(from, to).await.map(|_| ()),
+                false => Ok(()),
+            },
+        }
+    }
+
+    /// Records `operation`, performs it with `f`, and completes it.
+    async fn journaled<T, F>(&self, operation: JournalOperation, f: F) -> Result<T, JournalError<S::Error>>
+    where
+        F: Future<Output = Result<T, S::Error>> + Send,
+    {
+        let entry = JournalEntry::new(operation);
+        self.journal.record(&entry).await.map_err(JournalError::Journal)?;
+
+        let result = f.await.map_err(JournalError::Storage);
+        self.journal.complete(&entry.id).await.map_err(JournalError::Journal)?;
+
+        result
+    }
+
+    /// Stages the data of an upload, and then records and performs it.
+    async fn upload_journaled(
+        &self,
+        path: &Path,
+        options: UploadRequest,
+        exclusive: bool,
+    ) -> Result<(), JournalError<S::Error>> {
+        let id = next_id();
+        let staged = format!("{JOURNAL_STAGING_PREFIX}/{id}");
+
+        // only what a replay can restore is staged, so the staged upload doesn't collide
+        // with anything or create new versions
+        let request = UploadRequest::default()
+            .with_content_type(options.content_type.clone())
+            .with_metadata(options.metadata.clone())
+            .with_data(options.data.clone());
+
+        self.inner
+            .upload(&staged, request)
+            .await
+            .map_err(JournalError::Storage)?;
+
+        let entry = JournalEntry {
+            id,
+            operation: JournalOperation::Upload {
+                path: path.to_string_lossy().into_owned(),
+                staged: staged.clone(),
+                exclusive,
+            },
+        };
+
+        if let Err(e) = self.journal.record(&entry).await {
+            self.cleanup(&staged).await;
+            return Err(JournalError::Journal(e));
+        }
+
+        let result = match exclusive {
+            true => self.inner.create_exclusive(path, options).await,
+            false => self.inner.upload(path, options).await,
+        };
+
+        self.journal.complete(&entry.id).await.map_err(JournalError::Journal)?;
+
+        self.cleanup(&staged).await;
+        result.map_err(JournalError::Storage)
+    }
+
+    /// Deletes staged data. This is best-effort, since leftovers don't affect the journal.
+    async fn cleanup(&self, staged: &str) {
+        let _ = self.inner.delete(staged).await;
+    }
+}
+
+fn key<P: AsRef<Path>>(path: P) -> String {
+    path.as_ref().to_string_lossy().into_owned()
+}
+
+#[async_trait]
+impl<S: StorageService, J: Journal> StorageService for JournaledStorageService<S, J>
+where
+    S::Error: Send,
+{
+    type Error = JournalError<S::Error>;
+
+    fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        self.inner.init().await.map_err(JournalError::Storage)
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        self.inner.open(path).await.map_err(JournalError::Storage)
+    }
+
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        self.inner
+            .open_range(path, start, end)
+            .await
+            .map_err(JournalError::Storage)
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        self.inner.blob(path).await.map_err(JournalError::Storage)
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        self.inner.blobs(path, options).await.map_err(JournalError::Storage)
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        self.inner
+            .blobs_page(path, options)
+            .await
+            .map_err(JournalError::Storage)
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        let operation = JournalOperation::Delete { path: key(&path) };
+        self.journaled(operation, self.inner.delete(path)).await
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        let operation = JournalOperation::DeletePrefix { prefix: key(&prefix) };
+        self.journaled(operation, self.inner.delete_prefix(prefix)).await
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        let operation = JournalOperation::Copy {
+            src: key(&src),
+            dst: key(&dst),
+        };
+
+        self.journaled(operation, self.inner.copy(src, dst)).await
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        let operation = JournalOperation::Rename {
+            from: key(&from),
+            to: key(&to),
+        };
+
+        self.journaled(operation, self.inner.rename(from, to)).await
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.inner.exists(path).await.map_err(JournalError::Storage)
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        let exclusive = matches!(
+            options.collision(),
+            Some(CollisionStrategy::Error | CollisionStrategy::Suffix(_))
+        );
+
+        self.upload_journaled(path.as_ref(), options, exclusive).await
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        self.upload_journaled(path.as_ref(), options, true).await
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        self.inner.create_dir(path).await.map_err(JournalError::Storage)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.inner.is_read_only()
+    }
+
+    fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    fn info(&self) -> ServiceInfo {
+        self.inner.info()
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        self.inner.verify_credentials().await.map_err(JournalError::Storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Journal, JournalEntry, JournalOperation, JournaledStorageService, StorageJournal, JOURNAL_STAGING_PREFIX,
+    };
+    use crate::{testing::TestStorage, Blob, MeteredStorageService, StorageService, UploadRequest};
+    use bytes::Bytes;
+    use std::io;
+
+    /// Records the entries of mutations that were interrupted by a crash, and returns them
+    /// in the order that they were recorded.
+    async fn interrupted<J: Journal>(storage: &TestStorage, journal: &J) -> Vec<JournalEntry> {
+        storage
+            .upload(
+                "./.remi/staged/1",
+                UploadRequest::default()
+                    .with_content_type(Some("text/plain"))
+                    .with_data("weow"),
+            )
+            .await
+            .unwrap();
+
+        storage
+            .upload("./c.txt", UploadRequest::default().with_data("fluff"))
+            .await
+            .unwrap();
+
+        let entries = [
+            JournalOperation::Upload {
+                path: String::from("./a.txt"),
+                staged: String::from("./.remi/staged/1"),
+                exclusive: false,
+            },
+            JournalOperation::Copy {
+                src: String::from("./a.txt"),
+                dst: String::from("./b.txt"),
+            },
+            JournalOperation::Delete {
+                path: String::from("./c.txt"),
+            },
+        ]
+        .map(JournalEntry::new);
+
+        for entry in &entries {
+            journal.record(entry).await.unwrap();
+        }
+
+        entries.into()
+    }
+
+    async fn assert_recovered<J: Journal>(storage: &TestStorage, service: &JournaledStorageService<TestStorage, J>) {
+        assert_eq!(service.recover().await.unwrap(), 3);
+
+        assert_eq!(
+            storage.open("./a.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+        assert_eq!(
+            storage.open("./b.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+        assert!(!storage.exists("./c.txt").await.unwrap());
+        assert!(!storage.exists("./.remi/staged/1").await.unwrap());
+
+        let Some(Blob::File(file)) = storage.blob("./a.txt").await.unwrap() else {
+            panic!("expected `a.txt` to be a file");
+        };
+
+        assert_eq!(file.content_type.as_deref(), Some("text/plain"));
+        assert!(service.journal().pending().await.unwrap().is_empty());
+        assert_eq!(service.recover().await.unwrap(), 0);
+    }
+
+    #[test]
+    fn encode_and_decode() {
+        let operations = [
+            JournalOperation::Upload {
+                path: String::from("./weow.txt"),
+                staged: String::from("./.remi/staged/1"),
+                exclusive: true,
+            },
+            JournalOperation::Delete {
+                path: String::from("./with\tab\tand\nnewline\\.txt"),
+            },
+            JournalOperation::DeletePrefix {
+                prefix: String::from("./a\tb/\r"),
+            },
+            JournalOperation::Copy {
+                src: String::from("./a.txt"),
+                dst: String::from("./b.txt"),
+            },
+            JournalOperation::Rename {
+                from: String::from("./a.txt"),
+                to: String::from("./c.txt"),
+            },
+        ];
+
+        for operation in operations {
+            let entry = JournalEntry::new(operation);
+            let line = entry.encode();
+
+            assert!(!line.contains('\n'), "{line}");
+            assert_eq!(JournalEntry::decode(&line), Some(entry));
+        }
+    }
+
+    #[test]
+    fn decode_invalid() {
+        for line in [
+            "",
+            "upload",
+            "upload\t1\t./a.txt",
+            "delete\t1\t./a\\x",
+            "unknown\t1\t./a.txt",
+        ] {
+            assert_eq!(JournalEntry::decode(line), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn ids_are_ordered() {
+        let a = JournalEntry::new(JournalOperation::Delete {
+            path: String::from("a"),
+        });
+        let b = JournalEntry::new(JournalOperation::Delete {
+            path: String::from("b"),
+        });
+        assert!(a.id < b.id);
+    }
+
+    #[tokio::test]
+    async fn storage_journal_recovers_pending_entries() {
+        let storage = TestStorage::new();
+
+        // entries are opened instead of read from listings, which don't have their contents
+        let journal = StorageJournal::new(TestStorage::new().with_full_keys(true));
+
+        let entries = interrupted(&storage, &journal).await;
+        assert_eq!(journal.pending().await.unwrap(), entries);
+
+        assert_recovered(&storage, &JournaledStorageService::new(storage.clone(), journal)).await;
+    }
+
+    #[tokio::test]
+    async fn storage_journal_rejects_corrupted_entries() {
+        let journal_storage = TestStorage::new().with_full_keys(true);
+        journal_storage
+            .upload("./.remi/journal/1", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        let journal = StorageJournal::new(journal_storage);
+        assert_eq!(journal.pending().await.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+    #[tokio::test]
+    async fn file_journal_recovers_pending_entries() {
+        use super::FileJournal;
+        use std::{fs, process};
+
+        let path = std::env::temp_dir().join(format!("remi-journal-{}.log", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let storage = TestStorage::new();
+        let journal = FileJournal::new(&path);
+        let mut entries = interrupted(&storage, &journal).await;
+
+        // completed entries aren't pending anymore
+        let completed = JournalEntry::new(JournalOperation::Delete {
+            path: String::from("./a.txt"),
+        });
+
+        journal.record(&completed).await.unwrap();
+        journal.complete(&completed.id).await.unwrap();
+
+        // a half-written line from a crash is ignored
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "+upload\t2").unwrap();
+
+        // the journal is read again from the file after a restart
+        let journal = FileJournal::new(&path);
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(journal.pending().await.unwrap(), entries);
+
+        assert_recovered(&storage, &JournaledStorageService::new(storage.clone(), journal)).await;
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn completes_journaled_mutations() {
+        let storage = TestStorage::new();
+        let journal = StorageJournal::new(storage.clone());
+        let service = JournaledStorageService::new(storage.clone(), journal.clone());
+
+        service
+            .upload("./a.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        assert!(service.copy("./a.txt", "./b.txt").await.unwrap());
+        assert!(service.delete("./a.txt").await.unwrap());
+
+        assert_eq!(
+            storage.open("./b.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+        assert!(journal.pending().await.unwrap().is_empty());
+        assert!(storage
+            .blobs(Some(JOURNAL_STAGING_PREFIX), None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn forwards_stats() {
+        let storage = MeteredStorageService::new(TestStorage::new());
+        let service = JournaledStorageService::new(storage, StorageJournal::new(TestStorage::new()));
+
+        service
+            .upload("./a.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        service.open("./a.txt").await.unwrap();
+
+        let stats = service.stats();
+        assert_eq!(stats.bytes_read, 4);
+        assert_eq!(stats, service.get_ref().stats());
+    }
+}
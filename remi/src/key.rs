@@ -0,0 +1,133 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{fmt::Display, path::Path};
+
+/// Error that storage services return when a key can't refer to a file. Every storage
+/// service follows the same semantics for the keys given to file operations (like
+/// [`open`][crate::StorageService::open] and [`upload`][crate::StorageService::upload]):
+///
+/// - A key can't be empty or only refer to the root of the storage service, like `""`, `"./"`, `"~/"`, or `"/"`.
+/// - A key can't end with a `/`, since it refers to a directory rather than a file.
+/// - The last segment of a key can't be `.` or `..`.
+///
+/// Listing blobs or checking if a path exists is allowed on any of these paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidKey {
+    /// The key was empty or only referred to the root of the storage service.
+    Empty,
+
+    /// The key ended with a `/`, which refers to a directory rather than a file.
+    TrailingSlash(String),
+
+    /// The last segment of the key was `.` or `..`, which doesn't refer to a file.
+    DotSegment(String),
+}
+
+impl InvalidKey {
+    /// Checks that `path` can be used as a key of a file.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use remi::InvalidKey;
+    /// #
+    /// assert!(InvalidKey::check("./weow.txt").is_ok());
+    /// assert_eq!(InvalidKey::check("./"), Err(InvalidKey::Empty));
+    /// assert_eq!(InvalidKey::check("weow/"), Err(InvalidKey::TrailingSlash(String::from("weow/"))));
+    /// assert_eq!(InvalidKey::check("weow/.."), Err(InvalidKey::DotSegment(String::from("weow/.."))));
+    /// ```
+    pub fn check<P: AsRef<Path>>(path: P) -> Result<(), InvalidKey> {
+        let key = path.as_ref().to_string_lossy();
+        let trimmed = key
+            .trim_start_matches("./")
+            .trim_start_matches("~/")
+            .trim_start_matches('/');
+
+        if trimmed.is_empty() || trimmed == "." || trimmed == "~" {
+            return Err(InvalidKey::Empty);
+        }
+
+        if key.ends_with('/') {
+            return Err(InvalidKey::TrailingSlash(key.into_owned()));
+        }
+
+        match key.rsplit('/').next() {
+            Some("." | "..") => Err(InvalidKey::DotSegment(key.into_owned())),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for InvalidKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidKey::Empty => f.write_str("key can't be empty or refer to the root of the storage service"),
+            InvalidKey::TrailingSlash(key) => write!(f, "key [{key}] ends with a `/`, which refers to a directory"),
+            InvalidKey::DotSegment(key) => write!(f, "key [{key}] can't end with a `.` or `..` segment"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidKey {}
+
+/// Returns the name in `names` that is equal to `name` when ignoring case, which storage
+/// services use to find the actual key of a file with a case-insensitive lookup. If more
+/// than one name matches, the lexicographically smallest one is returned.
+///
+/// * since: 0.11.0
+pub fn find_case_insensitive<'a, I: IntoIterator<Item = &'a str>>(name: &str, names: I) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    names
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase() == name)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_case_insensitive, InvalidKey};
+
+    #[test]
+    fn case_insensitive() {
+        let names = ["readme.txt", "README.md", "Readme.md", "LICENSE"];
+        assert_eq!(find_case_insensitive("Readme.MD", names), Some("README.md"));
+        assert_eq!(find_case_insensitive("license", names), Some("LICENSE"));
+        assert_eq!(find_case_insensitive("CHANGELOG.md", names), None);
+    }
+
+    #[test]
+    fn check() {
+        for key in ["weow.txt", "./weow.txt", "~/weow/fluff.txt", "/weow.txt", "weow/.fluff"] {
+            assert_eq!(InvalidKey::check(key), Ok(()), "{key}");
+        }
+
+        for key in ["", ".", "./", "~", "~/", "/", "././"] {
+            assert_eq!(InvalidKey::check(key), Err(InvalidKey::Empty), "{key}");
+        }
+
+        assert!(matches!(
+            InvalidKey::check("./weow/"),
+            Err(InvalidKey::TrailingSlash(_))
+        ));
+        assert!(matches!(InvalidKey::check("./weow/."), Err(InvalidKey::DotSegment(_))));
+        assert!(matches!(InvalidKey::check(".."), Err(InvalidKey::DotSegment(_))));
+    }
+}
@@ -40,8 +40,17 @@
 //! - [**remi-azure**](https://crates.io/crates/remi-azure)
 //! - [**remi-s3**](https://crates.io/crates/remi-s3)
 //! - [**remi-fs**](https://crates.io/crates/remi-fs)
+//! - [**remi-inmemory**](https://crates.io/crates/remi-inmemory)
+//! - [**remi-sftp**](https://crates.io/crates/remi-sftp)
 
-use std::{borrow::Cow, path::Path};
+use futures_util::{future, StreamExt, TryStreamExt};
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    future::Future,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
 // re-export (just in case!~)
 #[doc(hidden)]
@@ -50,12 +59,143 @@ pub use async_trait::async_trait;
 #[doc(hidden)]
 pub use bytes::Bytes;
 
+mod already_exists;
+#[cfg(all(feature = "archive", any(feature = "rt-tokio", feature = "rt-async-std")))]
+mod archive;
 mod blob;
+mod cache;
+mod collision;
+#[cfg(feature = "compression")]
+mod compression;
+mod conditional;
+mod delete;
+mod diff;
+#[cfg(feature = "digest")]
+mod digest;
+mod download;
+mod dynamic;
+#[cfg(feature = "encryption")]
+mod encryption;
+mod error;
+mod event;
+mod extensions;
+mod health;
+mod info;
+mod intercept;
+mod journal;
+mod key;
+mod limit;
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+mod lock;
+mod memory;
 mod metadata;
+mod mirror;
 mod options;
+mod page;
+mod presign;
+mod provisioning;
+mod range;
+mod read_only;
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+mod retry;
+mod routing;
+mod secret;
+mod snapshot;
+mod stats;
+mod stream;
+#[cfg(test)]
+mod testing;
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+mod timeout;
+#[cfg(feature = "tower")]
+mod tower;
+mod transaction;
+mod usage;
+mod validate;
+
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(any(feature = "rt-tokio", feature = "rt-async-std"))))]
+pub mod rt;
 
+pub use already_exists::*;
+#[cfg(all(feature = "archive", any(feature = "rt-tokio", feature = "rt-async-std")))]
+#[cfg_attr(
+    any(noeldoc, docsrs),
+    doc(cfg(all(feature = "archive", any(feature = "rt-tokio", feature = "rt-async-std"))))
+)]
+pub use archive::*;
 pub use blob::*;
+pub use cache::*;
+pub use collision::*;
+#[cfg(feature = "compression")]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(feature = "compression")))]
+pub use compression::*;
+pub use conditional::*;
+pub use delete::*;
+pub use diff::*;
+#[cfg(feature = "digest")]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(feature = "digest")))]
+pub use digest::*;
+pub use download::*;
+pub use dynamic::*;
+#[cfg(feature = "encryption")]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(feature = "encryption")))]
+pub use encryption::*;
+pub use error::*;
+pub use event::*;
+pub use extensions::*;
+pub use health::*;
+pub use info::*;
+pub use intercept::*;
+pub use journal::*;
+pub use key::*;
+pub use limit::*;
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(any(feature = "rt-tokio", feature = "rt-async-std"))))]
+pub use lock::*;
+pub use memory::*;
+pub use metadata::*;
+pub use mirror::*;
 pub use options::*;
+pub use page::*;
+pub use presign::*;
+pub use provisioning::*;
+pub use range::*;
+pub use read_only::*;
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(any(feature = "rt-tokio", feature = "rt-async-std"))))]
+pub use retry::*;
+pub use routing::*;
+pub use secret::*;
+pub use snapshot::*;
+pub use stats::*;
+pub use stream::*;
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(any(feature = "rt-tokio", feature = "rt-async-std"))))]
+pub use timeout::*;
+#[cfg(feature = "tower")]
+pub use tower::*;
+pub use transaction::*;
+pub use usage::*;
+pub use validate::*;
+
+// Compile-time checks so that a change which makes these types no longer thread-safe
+// fails to build here instead of breaking downstream crates.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    assert_send_sync::<Blob>();
+    assert_send_sync::<File>();
+    assert_send_sync::<Metadata>();
+    assert_send_sync::<UploadRequest>();
+    assert_send_sync::<ListBlobsRequest>();
+    assert_send_sync::<Extensions>();
+    assert_send_sync::<StorageEvent>();
+};
+
+/// Name of the zero-byte marker file that object storage providers use to
+/// represent empty directories, since they don't have a concept of directories.
+pub const DEFAULT_DIRECTORY_MARKER: &str = ".keep";
 
 /// A storage service is a base primitive of `remi-rs`: it is the way to interact
 /// with the storage providers in ways that you would commonly use files: open, deleting,
@@ -76,6 +216,10 @@ pub trait StorageService: Send + Sync {
     /// Optionally initialize this [`StorageService`] if it requires initialization,
     /// like creating a directory if it doesn't exist.
     ///
+    /// Calling this more than once is safe. If [`skip_provisioning`] returns `true`, storage
+    /// services only verify that their resources exist and fail with an error that holds a
+    /// [`ResourceMissing`] error if they don't, instead of creating them.
+    ///
     /// * since 0.1.0
     async fn init(&self) -> Result<(), Self::Error>
     where
@@ -87,11 +231,87 @@ pub trait StorageService: Send + Sync {
     /// Opens a file in the specified `path` and returns the contents as [`Bytes`] if it existed, otherwise
     /// `None` will be returned to indicate that file doesn't exist.
     ///
+    /// `None` is reserved for objects that don't exist: a file that exists but has no contents
+    /// is returned as empty [`Bytes`], and a `path` that is not a valid key or can't be resolved
+    /// by the storage service is an error.
+    ///
     /// * since 0.1.0
     async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error>
     where
         Self: Sized;
 
+    /// Opens a file in the specified `path` and returns a [`ContentStream`] of its contents if it
+    /// existed, otherwise `None` will be returned to indicate that file doesn't exist. Unlike
+    /// [`open`][StorageService::open], the contents are read in chunks as the stream is polled,
+    /// so large files can be sent to somewhere else (like a HTTP response) without holding
+    /// the whole file in memory.
+    ///
+    /// By default, this will [`open`][StorageService::open] the whole file and return it as
+    /// a single chunk; storage services should override this to stream from the storage
+    /// provider.
+    ///
+    /// * since: 0.11.0
+    async fn open_stream<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+    ) -> Result<Option<ContentStream<Self::Error>>, Self::Error>
+    where
+        Self: Sized,
+        Self::Error: Send + 'static,
+    {
+        Ok(self
+            .open(path)
+            .await?
+            .map(|data| futures_util::stream::once(future::ready(Ok(data))).boxed()))
+    }
+
+    /// Opens the bytes from `start` up to (but not including) `end` of a file in the specified
+    /// `path`, or up to the end of the file if `end` is `None`, which is useful for serving
+    /// HTTP `Range` requests. `None` will be returned if the file doesn't exist.
+    ///
+    /// A range that goes past the end of the file is cut off at the end of the file, so a
+    /// range that starts at or after the end of the file returns empty [`Bytes`].
+    ///
+    /// By default, this will [`open`][StorageService::open] the whole file and slice it;
+    /// storage services should override this to only read the range from the storage provider.
+    ///
+    /// * since: 0.11.0
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error>
+    where
+        Self: Sized,
+    {
+        Ok(self.open(path).await?.map(|data| {
+            let (start, end) = clamp_range(start, end, data.len() as u64);
+            data.slice(start as usize..end as usize)
+        }))
+    }
+
+    /// Opens a file in the given `path` only if its [entity tag][File::etag] doesn't match `etag`,
+    /// which can be the value of an HTTP `If-None-Match` header (see [`etag_matches`]), so cached
+    /// copies can be revalidated without downloading files that didn't change. `None` will be
+    /// returned if the file doesn't exist, and files without an entity tag are always downloaded.
+    ///
+    /// By default, this gets the [`blob`][StorageService::blob] and compares its entity tag;
+    /// storage services should override this to send a conditional request to the storage
+    /// provider instead.
+    ///
+    /// * since: 0.11.0
+    async fn open_if_none_match<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        etag: &str,
+    ) -> Result<Option<Conditional>, Self::Error>
+    where
+        Self: Sized,
+    {
+        Ok(Conditional::of(self.blob(path).await?, etag))
+    }
+
     /// Open a file in the given `path` and returns a [`Blob`] structure if the path existed, otherwise
     /// `None` will be returned to indiciate that a file doesn't exist.
     ///
@@ -111,14 +331,193 @@ pub trait StorageService: Send + Sync {
     where
         Self: Sized;
 
-    /// Deletes a file in a specified `path`. At the moment, `()` is returned but `bool` might be
-    /// returned to indicate if it actually deleted itself or not.
+    /// Returns a single [`Page`] of the blobs in `path`, starting from the page that
+    /// [`ListBlobsRequest::continuation`] points to (or the first page if it is `None`).
+    /// This is like [`blobs`][StorageService::blobs], but doesn't keep every blob in
+    /// memory at once, which matters for listings with millions of keys.
+    ///
+    /// By default, every blob is returned in a single page; storage services whose
+    /// providers paginate their listings should override this.
+    ///
+    /// * since: 0.11.0
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error>
+    where
+        Self: Sized,
+    {
+        self.blobs(path, options).await.map(Page::last)
+    }
+
+    /// Returns a resumable [`BlobStream`] over the blobs in `path`, which fetches the listing
+    /// one [`Page`] at a time with [`blobs_page`][StorageService::blobs_page] so consumers
+    /// can process large listings incrementally. Blobs are yielded in lexicographical order,
+    /// and [`BlobStream::checkpoint`] can be persisted and passed back in with
+    /// [`ListBlobsRequest::with_start_after`] to resume a listing.
+    ///
+    /// ## Example
+    /// ```rust,ignore
+    /// # use remi::StorageService;
+    /// # use futures_util::TryStreamExt;
+    /// #
+    /// let mut blobs = storage.blob_stream(Some("./images"), None).into_stream();
+    /// while let Some(blob) = blobs.try_next().await? {
+    ///     index(blob).await?;
+    /// }
+    /// ```
+    ///
+    /// * since: 0.11.0
+    fn blob_stream<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> BlobStream<'_, Self>
+    where
+        Self: Sized,
+    {
+        BlobStream::new(self, path.map(|p| PathBuf::from(p.as_ref())), options)
+    }
+
+    /// Runs `f` on every blob in `path` with at most `limit` calls running at once, which is
+    /// a shortcut for the common pattern of processing a whole listing. Fetching the listing
+    /// is retried up to [`DEFAULT_LIST_RETRIES`] times, and the first error from either the
+    /// listing or `f` stops any new calls from being started and is returned. A limit of `0`
+    /// is treated as `1`.
+    ///
+    /// ## Example
+    /// ```rust,ignore
+    /// # use remi::{Blob, StorageService};
+    /// #
+    /// storage
+    ///     .for_each_blob_concurrent(Some("./images"), 8, |blob| async move {
+    ///         if let Blob::File(file) = blob {
+    ///             generate_thumbnail(&file.name).await?;
+    ///         }
+    ///
+    ///         Ok::<_, MyError>(())
+    ///     })
+    ///     .await?;
+    /// ```
+    ///
+    /// * since: 0.11.0
+    async fn for_each_blob_concurrent<P, F, Fut, E>(&self, path: Option<P>, limit: usize, f: F) -> Result<(), E>
+    where
+        Self: Sized,
+        Self::Error: Send,
+        P: AsRef<Path> + Send,
+        F: FnMut(Blob) -> Fut + Send,
+        Fut: Future<Output = Result<(), E>> + Send,
+        E: From<Self::Error> + Send,
+    {
+        self.blob_stream(path, None)
+            .with_retries(DEFAULT_LIST_RETRIES)
+            .into_stream()
+            .map_err(E::from)
+            .try_for_each_concurrent(limit.max(1), f)
+            .await
+    }
+
+    /// Downloads every key in `keys` with at most `concurrency` downloads running at once and
+    /// returns a [`DownloadMany`] stream of each key with its contents, in the order that the
+    /// downloads finish. Downloads are only started as the stream is polled.
+    ///
+    /// * since: 0.11.0
+    fn download_many<K, I>(&self, keys: I, concurrency: usize) -> DownloadMany<'_, Self, K>
+    where
+        Self: Sized,
+        I: IntoIterator<Item = K>,
+    {
+        DownloadMany::new(self, keys, concurrency)
+    }
+
+    /// Deletes a file in a specified `path`, and returns `true` if it was deleted or `false`
+    /// if it didn't exist, so callers don't need to check with [`exists`][StorageService::exists]
+    /// first (which could race with other deletions).
     ///
     /// * since 0.1.0
-    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error>
+    /// * returns `bool` since 0.11.0
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error>
     where
         Self: Sized;
 
+    /// Deletes every object in the `prefix` pseudo-directory, like `rm -r` would. Objects
+    /// that fail to be deleted don't stop the others from being deleted, and are returned
+    /// in [`DeleteSummary::failed`] instead.
+    ///
+    /// By default, this will list the files with [`blobs`][StorageService::blobs] and delete
+    /// them with [`delete_keys`]; storage services should override this with a more
+    /// efficient implementation that doesn't read every file.
+    ///
+    /// * since: 0.11.0
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error>
+    where
+        Self: Sized,
+    {
+        let keys = self
+            .blobs(Some(prefix), None)
+            .await?
+            .into_iter()
+            .filter_map(|blob| match blob {
+                Blob::File(file) => Some(
+                    file.path
+                        .split_once("://")
+                        .map(|(_, key)| key.to_owned())
+                        .unwrap_or(file.path),
+                ),
+                Blob::Directory(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(delete_keys(self, keys, DEFAULT_DELETE_CONCURRENCY).await)
+    }
+
+    /// Copies the file in `src` to `dst`, replacing the file in `dst` if it exists. Returns
+    /// `false` if `src` doesn't exist (or isn't a file), in which case nothing is copied.
+    ///
+    /// By default, this will download the file with [`blob`][StorageService::blob] and upload
+    /// it again; storage services should override this to copy the file on the storage provider's
+    /// side without sending its contents through the client. Since the contents aren't sent,
+    /// [`UploadInterceptor`]s aren't run for copies that are done on the storage provider's side.
+    ///
+    /// * since: 0.11.0
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error>
+    where
+        Self: Sized,
+    {
+        let Some(Blob::File(file)) = self.blob(src).await? else {
+            return Ok(false);
+        };
+
+        self.upload(dst, UploadRequest::from_file(&file)).await.map(|_| true)
+    }
+
+    /// Moves the file in `from` to `to`, replacing the file in `to` if it exists. Returns
+    /// `false` if `from` doesn't exist (or isn't a file), in which case nothing is moved.
+    ///
+    /// By default, this will [`copy`][StorageService::copy] the file and then
+    /// [`delete`][StorageService::delete] the original, which isn't atomic: if the deletion
+    /// fails, the file will exist in both places. Storage services that can move files
+    /// natively should override this.
+    ///
+    /// * since: 0.11.0
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error>
+    where
+        Self: Sized,
+    {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        if from == to {
+            return self.exists(from).await;
+        }
+
+        if !self.copy(from, to).await? {
+            return Ok(false);
+        }
+
+        self.delete(from).await.map(|_| true)
+    }
+
     /// Checks the existence of the file by the specified path.
     ///
     /// * since: 0.1.0
@@ -133,12 +532,121 @@ pub trait StorageService: Send + Sync {
     where
         Self: Sized;
 
-    #[cfg(feature = "unstable")]
-    #[cfg_attr(any(noeldoc, docsrs), doc(cfg(feature = "unstable")))]
-    /// Performs any healthchecks to determine the storage service's health.
-    async fn healthcheck(&self) -> Result<(), Self::Error> {
+    /// Uploads a file to the given `path` only if nothing exists there yet, which can be used
+    /// to implement locks or leader election on top of a storage service. If a file already
+    /// exists, this fails with an [`AlreadyExists`] error (wrapped in [`Self::Error`]) and
+    /// the existing file is left untouched.
+    ///
+    /// Storage services must implement this atomically with the storage provider's own
+    /// conditional writes, which is why this has no default implementation.
+    ///
+    /// * since: 0.11.0
+    async fn create_exclusive<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error>
+    where
+        Self: Sized;
+
+    /// Creates an empty directory in the given `path`. Storage services that don't have
+    /// a concept of directories will upload a zero-byte marker file (named
+    /// [`DEFAULT_DIRECTORY_MARKER`] by default) inside of it instead, which is resolved
+    /// back into a [`Blob::Directory`] when listing blobs.
+    ///
+    /// * since: 0.11.0
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        self.upload(path.as_ref().join(DEFAULT_DIRECTORY_MARKER), UploadRequest::default())
+            .await
+    }
+
+    /// Returns `true` if this storage service was configured to be read-only, which means
+    /// that every operation that would modify it fails with a [`ReadOnly`] error. Storage
+    /// services that wrap another storage service should return the inner service's flag.
+    ///
+    /// * since: 0.11.0
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// Returns a [`Stats`] snapshot of the operations that this storage service has
+    /// performed. Storage services don't keep any counters by default, wrap them in a
+    /// [`MeteredStorageService`] to collect them; storage services that wrap another
+    /// storage service should return the inner service's stats.
+    ///
+    /// * since: 0.11.0
+    fn stats(&self) -> Stats {
+        Stats::default()
+    }
+
+    /// Returns a [`ServiceInfo`] that describes this storage service, like where it keeps
+    /// files and what it supports, which can be exposed on a diagnostics endpoint. Storage
+    /// services that wrap another storage service should return the inner service's info.
+    ///
+    /// By default, this only includes the storage service's [`name`][StorageService::name].
+    ///
+    /// * since: 0.11.0
+    fn info(&self) -> ServiceInfo
+    where
+        Self: Sized,
+    {
+        ServiceInfo::new(self.name())
+    }
+
+    /// Performs a series of cheap probes against the storage service to diagnose
+    /// misconfiguration (wrong endpoint, bad credentials, missing bucket, etc.) and returns
+    /// a [`ValidationReport`] of what passed and failed, with remediation hints.
+    ///
+    /// By default, this will write, read, and delete a probe object with [`probe_read_write`].
+    ///
+    /// * since: 0.11.0
+    async fn debug_validate(&self) -> ValidationReport
+    where
+        Self: Sized,
+        Self::Error: Display + Send,
+    {
+        let mut report = ValidationReport::default();
+        probe_read_write(self, &mut report).await;
+
+        report
+    }
+
+    /// Checks that the storage provider accepts the storage service's credentials with a
+    /// single cheap request that doesn't touch any files. Unlike [`debug_validate`][StorageService::debug_validate],
+    /// this doesn't need permissions to write files, so automation that rotates credentials
+    /// can check new credentials with a storage service that was created from them before
+    /// swapping it in.
+    ///
+    /// By default, this does nothing since the storage service has no credentials.
+    ///
+    /// * since: 0.11.0
+    async fn verify_credentials(&self) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
         Ok(())
     }
+
+    /// Probes the storage provider to determine the storage service's health, and returns a
+    /// [`HealthReport`] of whether it could be reached, how long it took, and which permissions
+    /// were confirmed. Unlike [`debug_validate`][StorageService::debug_validate], this is cheap
+    /// enough to be called periodically, like from a readiness probe.
+    ///
+    /// By default, this checks whether [`HEALTHCHECK_KEY`] exists, which confirms that files
+    /// can be read. A storage service whose error can't tell whether the storage provider was
+    /// reached is reported as unreachable if the check fails.
+    ///
+    /// * since: 0.11.0
+    async fn healthcheck(&self) -> HealthReport
+    where
+        Self: Sized,
+        Self::Error: Display + Send,
+    {
+        let started = Instant::now();
+        match self.exists(HEALTHCHECK_KEY).await {
+            Ok(_) => HealthReport::reachable(started.elapsed()).with_read(true),
+            Err(e) => HealthReport::unreachable(started.elapsed(), e),
+        }
+    }
 }
 
 #[cfg(test)]
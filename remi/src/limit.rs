@@ -0,0 +1,170 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fmt::Display;
+
+/// Error that storage services return when an upload is larger than the configured
+/// maximum object size. The upload is rejected before any bytes are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooLarge {
+    /// Size of the object (in bytes)
+    pub size: usize,
+
+    /// Maximum size that was configured (in bytes)
+    pub max: usize,
+}
+
+impl TooLarge {
+    /// Checks that an object of `size` bytes doesn't exceed `max` bytes. If `max` is `None`,
+    /// then any size is allowed.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use remi::TooLarge;
+    /// #
+    /// assert!(TooLarge::check(1024, None).is_ok());
+    /// assert!(TooLarge::check(1024, Some(1024)).is_ok());
+    /// assert_eq!(TooLarge::check(1025, Some(1024)), Err(TooLarge { size: 1025, max: 1024 }));
+    /// ```
+    pub fn check(size: usize, max: Option<usize>) -> Result<(), TooLarge> {
+        match max {
+            Some(max) if size > max => Err(TooLarge { size, max }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "object is {} bytes, which exceeds the maximum object size of {} bytes",
+            self.size, self.max
+        )
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Error that storage services return when the body of an object that was read doesn't
+/// match the size that the storage service reported for it, which usually means that the
+/// connection was cut off while reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncatedRead {
+    /// Size that the storage service reported (in bytes)
+    pub expected: u64,
+
+    /// Size of the body that was actually read (in bytes)
+    pub actual: u64,
+}
+
+impl TruncatedRead {
+    /// Checks that a body of `actual` bytes matches the `expected` size that the storage
+    /// service reported.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use remi::TruncatedRead;
+    /// #
+    /// assert!(TruncatedRead::check(1024, 1024).is_ok());
+    /// assert_eq!(TruncatedRead::check(1024, 512), Err(TruncatedRead { expected: 1024, actual: 512 }));
+    /// ```
+    pub fn check(expected: u64, actual: usize) -> Result<(), TruncatedRead> {
+        let actual = actual as u64;
+        match expected == actual {
+            true => Ok(()),
+            false => Err(TruncatedRead { expected, actual }),
+        }
+    }
+}
+
+impl Display for TruncatedRead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "read {} bytes, but the storage service reported a size of {} bytes",
+            self.actual, self.expected
+        )
+    }
+}
+
+impl std::error::Error for TruncatedRead {}
+
+#[cfg(test)]
+mod tests {
+    use super::{TooLarge, TruncatedRead};
+    use crate::{testing::TestStorage, StorageService, UploadRequest};
+
+    #[test]
+    fn checks() {
+        assert_eq!(TooLarge::check(4, None), Ok(()));
+        assert_eq!(TooLarge::check(4, Some(4)), Ok(()));
+        assert_eq!(TooLarge::check(5, Some(4)), Err(TooLarge { size: 5, max: 4 }));
+
+        assert_eq!(TruncatedRead::check(4, 4), Ok(()));
+        assert_eq!(
+            TruncatedRead::check(4, 3),
+            Err(TruncatedRead { expected: 4, actual: 3 })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_large_uploads() {
+        let storage = TestStorage::new().with_max_object_size(Some(4));
+
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        let error = storage
+            .create_exclusive("./fluff.txt", UploadRequest::default().with_data("fluff"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.get_ref().and_then(|e| e.downcast_ref::<TooLarge>()),
+            Some(&TooLarge { size: 5, max: 4 })
+        );
+
+        assert!(matches!(crate::Error::from(error), crate::Error::InvalidInput(_)));
+        assert!(!storage.exists("./fluff.txt").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn rejects_truncated_reads() {
+        let storage = TestStorage::new().with_truncated_reads(true);
+
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        let error = storage.open("./weow.txt").await.unwrap_err();
+        assert_eq!(
+            error.get_ref().and_then(|e| e.downcast_ref::<TruncatedRead>()),
+            Some(&TruncatedRead { expected: 4, actual: 3 })
+        );
+
+        // truncated reads are likely to go away if they are retried
+        assert!(matches!(crate::Error::from(error), crate::Error::Unavailable(_)));
+    }
+}
@@ -0,0 +1,455 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use crate::{rt, Blob, StorageService, UploadRequest};
+use futures_util::lock::Mutex;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
+/// How long a lease lasts without being renewed by default.
+pub const DEFAULT_LEASE_TTL: Duration = Duration::from_secs(30);
+
+/// Cross-process lock on top of any storage service that implements
+/// [`create_exclusive`][StorageService::create_exclusive], which is useful for things like
+/// making sure that only one instance runs database migrations.
+///
+/// Every acquisition creates a new generation of the lock as a file in the lock's
+/// pseudo-directory, named after its zero-padded fencing token. Since only one caller can
+/// create a generation, the fencing token is unique and only ever increases, so it can be
+/// passed to other systems to reject writes from a holder whose lease already expired.
+///
+/// While a [`LeaseGuard`] is alive, its lease is renewed in the background every third
+/// of its TTL. A lease that wasn't renewed in time (for example, because the process
+/// crashed) can be taken over by another caller. Expiry times are compared with the
+/// local clock of each caller, so clocks should be reasonably in sync.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::LeaseLock;
+/// #
+/// let lock = LeaseLock::new(storage.clone(), "./locks/migrations");
+/// let guard = lock.acquire().await?;
+///
+/// run_migrations(guard.fencing_token()).await?;
+/// guard.release().await?;
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct LeaseLock<S> {
+    service: S,
+    path: PathBuf,
+    holder: String,
+    ttl: Duration,
+    retry_interval: Duration,
+}
+
+impl<S> LeaseLock<S>
+where
+    S: StorageService + Clone + 'static,
+    S::Error: Send,
+{
+    /// Creates a new [`LeaseLock`] that keeps its generations in the `path` pseudo-directory,
+    /// which shouldn't be used for anything else.
+    pub fn new<P: AsRef<Path>>(service: S, path: P) -> LeaseLock<S> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        LeaseLock {
+            service,
+            path: path.as_ref().to_path_buf(),
+            holder: format!(
+                "{}-{}-{}",
+                process::id(),
+                now(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ),
+            ttl: DEFAULT_LEASE_TTL,
+            retry_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Sets how long a lease lasts without being renewed. This will default to [`DEFAULT_LEASE_TTL`].
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets how long [`acquire`][LeaseLock::acquire] waits between attempts. This will
+    /// default to one second.
+    pub fn with_retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Sets the name that identifies the holder of this lock, which must be unique between
+    /// every caller of the lock. This will default to a name that is generated from the
+    /// process ID and the time that the [`LeaseLock`] was created.
+    pub fn with_holder<I: Into<String>>(mut self, holder: I) -> Self {
+        self.holder = holder.into();
+        self
+    }
+
+    /// Acquires the lock, waiting until the current lease is released or has expired.
+    pub async fn acquire(&self) -> Result<LeaseGuard<S>, S::Error> {
+        loop {
+            if let Some(guard) = self.try_acquire().await? {
+                return Ok(guard);
+            }
+
+            rt::sleep(self.retry_interval).await;
+        }
+    }
+
+    /// Acquires the lock if nobody holds a lease on it, or returns `None` otherwise.
+    pub async fn try_acquire(&self) -> Result<Option<LeaseGuard<S>>, S::Error> {
+        let generations = generations(&self.service, &self.path).await?;
+        let latest = generations.last().copied();
+        if let Some(token) = latest {
+            let lease = read(&self.service, &generation_key(&self.path, token)).await?;
+            if lease.is_some_and(|lease| lease.is_live()) {
+                return Ok(None);
+            }
+        }
+
+        let token = latest.map_or(1, |token| token + 1);
+        let key = generation_key(&self.path, token);
+        let lease = Lease {
+            token,
+            holder: self.holder.clone(),
+            expires_at: now() + self.ttl.as_millis(),
+        };
+
+        if let Err(e) = self.service.create_exclusive(&key, lease.to_request()).await {
+            // another caller created this generation first
+            return match read(&self.service, &key).await {
+                Ok(Some(lease)) if lease.holder != self.holder => Ok(None),
+                _ => Err(e),
+            };
+        }
+
+        // older generations aren't needed anymore, the latest one is always kept around
+        // so that the next fencing token is higher than this one
+        for older in generations {
+            let _ = self.service.delete(generation_key(&self.path, older)).await;
+        }
+
+        let shared = Arc::new(Shared {
+            service: self.service.clone(),
+            path: self.path.clone(),
+            key,
+            lease: Mutex::new(Some(lease)),
+            ttl: self.ttl,
+            lost: AtomicBool::new(false),
+        });
+
+        rt::spawn("remi::lock::renew", renew(shared.clone()));
+        Ok(Some(LeaseGuard {
+            shared,
+            token,
+            released: false,
+        }))
+    }
+}
+
+/// Lease on a [`LeaseLock`] that is renewed in the background until it is released or
+/// dropped. Dropping it releases the lease in the background.
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub struct LeaseGuard<S: StorageService + 'static>
+where
+    S::Error: Send,
+{
+    shared: Arc<Shared<S>>,
+    token: u64,
+    released: bool,
+}
+
+impl<S: StorageService + 'static> LeaseGuard<S>
+where
+    S::Error: Send,
+{
+    /// Returns the fencing token of this lease, which is higher than the tokens of
+    /// every lease that was acquired before it.
+    pub fn fencing_token(&self) -> u64 {
+        self.token
+    }
+
+    /// Returns `true` if the lease couldn't be renewed before it expired or another
+    /// caller took it over, which means that the lock isn't held anymore.
+    pub fn is_lost(&self) -> bool {
+        self.shared.lost.load(Ordering::SeqCst)
+    }
+
+    /// Releases the lease so that another caller can acquire the lock right away.
+    pub async fn release(mut self) -> Result<(), S::Error> {
+        self.released = true;
+        self.shared.release().await
+    }
+}
+
+impl<S: StorageService + 'static> Drop for LeaseGuard<S>
+where
+    S::Error: Send,
+{
+    fn drop(&mut self) {
+        if !self.released {
+            let shared = self.shared.clone();
+            rt::spawn("remi::lock::release", async move {
+                let _ = shared.release().await;
+            });
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared<S> {
+    service: S,
+    path: PathBuf,
+    key: PathBuf,
+    ttl: Duration,
+    lost: AtomicBool,
+
+    /// Lease that is currently held, or `None` once it was released. Renewing and releasing
+    /// hold the lock while writing, so a renewal can't overwrite a released lease.
+    lease: Mutex<Option<Lease>>,
+}
+
+impl<S: StorageService + 'static> Shared<S>
+where
+    S::Error: Send,
+{
+    async fn release(&self) -> Result<(), S::Error> {
+        let Some(mut lease) = self.lease.lock().await.take() else {
+            return Ok(());
+        };
+
+        if self.lost.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        lease.expires_at = 0;
+        self.service.upload(&self.key, lease.to_request()).await
+    }
+}
+
+/// Renews the lease of `shared` until it is released or lost.
+async fn renew<S>(shared: Arc<Shared<S>>)
+where
+    S: StorageService + 'static,
+    S::Error: Send,
+{
+    loop {
+        rt::sleep(shared.ttl / 3).await;
+
+        let mut lease = shared.lease.lock().await;
+        let Some(ref mut current) = *lease else {
+            return;
+        };
+
+        // another caller took over the lock after the lease expired
+        let superseded = match generations(&shared.service, &shared.path).await {
+            Ok(generations) => generations.last().is_some_and(|latest| *latest > current.token),
+            Err(_) => false,
+        };
+
+        if superseded || !current.is_live() {
+            shared.lost.store(true, Ordering::SeqCst);
+            return;
+        }
+
+        let renewed = Lease {
+            expires_at: now() + shared.ttl.as_millis(),
+            ..current.clone()
+        };
+
+        // a failed renewal is retried until the lease expires
+        if shared.service.upload(&shared.key, renewed.to_request()).await.is_ok() {
+            *current = renewed;
+        }
+    }
+}
+
+/// Contents of a generation of a [`LeaseLock`].
+#[derive(Debug, Clone)]
+struct Lease {
+    token: u64,
+    holder: String,
+    expires_at: u128,
+}
+
+impl Lease {
+    fn is_live(&self) -> bool {
+        self.expires_at > now()
+    }
+
+    /// Keeps the lease in the contents of the file, since not every storage service keeps
+    /// metadata, and in the metadata for storage services that can show it.
+    fn to_request(&self) -> UploadRequest {
+        UploadRequest::default()
+            .with_content_type(Some("text/plain"))
+            .with_metadata(HashMap::from([
+                (String::from("fencing-token"), self.token.to_string()),
+                (String::from("holder"), self.holder.clone()),
+                (String::from("expires-at"), self.expires_at.to_string()),
+            ]))
+            .with_data(format!("{}\n{}\n{}", self.token, self.holder, self.expires_at))
+    }
+
+    fn parse(data: &[u8]) -> Option<Lease> {
+        let data = std::str::from_utf8(data).ok()?;
+        let mut lines = data.lines();
+
+        Some(Lease {
+            token: lines.next()?.parse().ok()?,
+            holder: lines.next()?.to_owned(),
+            expires_at: lines.next()?.parse().ok()?,
+        })
+    }
+}
+
+/// Returns the fencing tokens of every generation in `path`, in ascending order.
+async fn generations<S: StorageService>(service: &S, path: &Path) -> Result<Vec<u64>, S::Error>
+where
+    S::Error: Send,
+{
+    let blobs = match service.blobs(Some(path), None).await {
+        Ok(blobs) => blobs,
+
+        // storage services with real directories fail to list a directory that doesn't exist yet
+        Err(e) => match service.exists(path).await {
+            Ok(false) => return Ok(Vec::new()),
+            _ => return Err(e),
+        },
+    };
+
+    let mut tokens = blobs
+        .into_iter()
+        .filter_map(|blob| match blob {
+            // some storage services (like S3) return the full key as the name of a file
+            Blob::File(file) => file.name.rsplit('/').next().and_then(|name| name.parse().ok()),
+            Blob::Directory(_) => None,
+        })
+        .collect::<Vec<u64>>();
+
+    tokens.sort_unstable();
+    Ok(tokens)
+}
+
+/// Reads the generation in `key`. Generations that can't be parsed are treated as expired.
+async fn read<S: StorageService>(service: &S, key: &Path) -> Result<Option<Lease>, S::Error>
+where
+    S::Error: Send,
+{
+    Ok(service.open(key).await?.and_then(|data| Lease::parse(&data)))
+}
+
+fn generation_key(path: &Path, token: u64) -> PathBuf {
+    path.join(format!("{token:020}"))
+}
+
+/// Returns the current time in milliseconds since the Unix epoch.
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LeaseLock;
+    use crate::{testing::TestStorage, StorageService, UploadRequest};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn lease_lock_is_exclusive() {
+        let storage = TestStorage::new();
+        let lock = LeaseLock::new(storage.clone(), "./locks/migrations").with_ttl(Duration::from_millis(300));
+        let other = LeaseLock::new(storage.clone(), "./locks/migrations");
+
+        let guard = lock.try_acquire().await.unwrap().expect("lock to be free");
+        assert_eq!(guard.fencing_token(), 1);
+        assert!(other.try_acquire().await.unwrap().is_none());
+
+        // the lease is renewed in the background while the guard is alive
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!guard.is_lost());
+        assert!(other.try_acquire().await.unwrap().is_none());
+
+        guard.release().await.unwrap();
+
+        let guard = other.try_acquire().await.unwrap().expect("released lock to be free");
+        assert_eq!(guard.fencing_token(), 2);
+        guard.release().await.unwrap();
+
+        // only the latest generation is kept around
+        assert_eq!(storage.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_leases_are_taken_over() {
+        let storage = TestStorage::new();
+
+        // generation of a holder that crashed without releasing its lease
+        storage
+            .upload(
+                "./locks/migrations/00000000000000000007",
+                UploadRequest::default().with_data("7\ncrashed\n0"),
+            )
+            .await
+            .unwrap();
+
+        let lock = LeaseLock::new(storage.clone(), "./locks/migrations");
+        let guard = lock
+            .try_acquire()
+            .await
+            .unwrap()
+            .expect("expired lease to be taken over");
+        assert_eq!(guard.fencing_token(), 8);
+        assert!(!storage.exists("./locks/migrations/00000000000000000007").await.unwrap());
+
+        guard.release().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lease_lock_with_full_keys() {
+        let storage = TestStorage::new().with_full_keys(true);
+        let lock = LeaseLock::new(storage.clone(), "./locks/migrations");
+        let other = LeaseLock::new(storage.clone(), "./locks/migrations");
+
+        let guard = lock.try_acquire().await.unwrap().expect("lock to be free");
+        assert_eq!(guard.fencing_token(), 1);
+        assert!(other.try_acquire().await.unwrap().is_none());
+        guard.release().await.unwrap();
+
+        let guard = other.try_acquire().await.unwrap().expect("released lock to be free");
+        assert_eq!(guard.fencing_token(), 2);
+        guard.release().await.unwrap();
+    }
+}
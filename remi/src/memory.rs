@@ -0,0 +1,372 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+};
+
+/// Error that storage services return when an operation would buffer more bytes than
+/// what the configured memory limit allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimitExceeded {
+    /// Amount of bytes that the operation wanted to buffer
+    pub requested: usize,
+
+    /// Amount of bytes that were available when the operation was rejected
+    pub available: usize,
+
+    /// Maximum amount of bytes that can be buffered at once
+    pub limit: usize,
+}
+
+impl Display for MemoryLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "operation needs to buffer {} bytes, but only {} of {} bytes are available",
+            self.requested, self.available, self.limit
+        )
+    }
+}
+
+impl std::error::Error for MemoryLimitExceeded {}
+
+/// Caps the total amount of bytes that storage services buffer across concurrent operations.
+///
+/// Each operation acquires a [`MemoryPermit`] weighted by the size of its payload before it
+/// buffers anything and releases it once it is done. Depending on how it was created, an
+/// operation that doesn't fit either waits until enough bytes were released or fails
+/// right away with a [`MemoryLimitExceeded`] error. An operation that is larger than the
+/// limit itself always fails, since it would wait forever.
+///
+/// Waiting operations are served in the order that they arrived in, so a large operation
+/// isn't starved by a stream of smaller ones.
+///
+/// Cloning a [`MemoryLimit`] is cheap and the clones share the same budget.
+///
+/// ## Example
+/// ```rust
+/// # use remi::MemoryLimit;
+/// #
+/// # futures_util::FutureExt::now_or_never(async {
+/// let limit = MemoryLimit::new(1024);
+/// let permit = limit.acquire(1000).await.unwrap();
+/// assert_eq!(limit.available(), 24);
+///
+/// drop(permit);
+/// assert_eq!(limit.available(), 1024);
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MemoryLimit {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    limit: usize,
+    fail_fast: bool,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    available: usize,
+    next_id: u64,
+    waiters: VecDeque<Waiter>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    waker: Option<Waker>,
+}
+
+impl State {
+    /// Wakes up the operation that is at the front of the queue, if there is one.
+    fn wake_front(&mut self) {
+        if let Some(waker) = self.waiters.front_mut().and_then(|waiter| waiter.waker.take()) {
+            waker.wake();
+        }
+    }
+}
+
+impl MemoryLimit {
+    /// Creates a new [`MemoryLimit`] that allows `limit` bytes to be buffered at once, where
+    /// operations that don't fit wait until enough bytes were released.
+    pub fn new(limit: usize) -> MemoryLimit {
+        MemoryLimit {
+            inner: Arc::new(Inner {
+                limit,
+                fail_fast: false,
+                state: Mutex::new(State {
+                    available: limit,
+                    ..Default::default()
+                }),
+            }),
+        }
+    }
+
+    /// Creates a new [`MemoryLimit`] that allows `limit` bytes to be buffered at once, where
+    /// operations that don't fit fail right away with a [`MemoryLimitExceeded`] error.
+    pub fn fail_fast(limit: usize) -> MemoryLimit {
+        let mut this = MemoryLimit::new(limit);
+        Arc::get_mut(&mut this.inner)
+            .expect("memory limit was just created")
+            .fail_fast = true;
+
+        this
+    }
+
+    /// Maximum amount of bytes that can be buffered at once.
+    pub fn limit(&self) -> usize {
+        self.inner.limit
+    }
+
+    /// Amount of bytes that can be buffered right now.
+    pub fn available(&self) -> usize {
+        self.state().available
+    }
+
+    /// Acquires a permit to buffer `bytes` bytes, which are released when the permit
+    /// is dropped.
+    ///
+    /// If the limit was created with [`MemoryLimit::fail_fast`], this behaves like
+    /// [`MemoryLimit::try_acquire`].
+    pub async fn acquire(&self, bytes: usize) -> Result<MemoryPermit, MemoryLimitExceeded> {
+        if self.inner.fail_fast || bytes > self.inner.limit {
+            return self.try_acquire(bytes);
+        }
+
+        Acquire {
+            limit: self,
+            bytes,
+            id: None,
+        }
+        .await;
+
+        Ok(MemoryPermit {
+            limit: self.clone(),
+            bytes,
+        })
+    }
+
+    /// Acquires a permit to buffer `bytes` bytes without waiting. This fails if there are
+    /// other operations waiting, or if not enough bytes are available right now.
+    pub fn try_acquire(&self, bytes: usize) -> Result<MemoryPermit, MemoryLimitExceeded> {
+        let mut state = self.state();
+        if bytes > state.available || !state.waiters.is_empty() {
+            return Err(MemoryLimitExceeded {
+                requested: bytes,
+                available: state.available,
+                limit: self.inner.limit,
+            });
+        }
+
+        state.available -= bytes;
+        Ok(MemoryPermit {
+            limit: self.clone(),
+            bytes,
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // the state is always left consistent, so a poisoned lock is still usable
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Permit that was acquired from a [`MemoryLimit`], which releases its bytes once
+/// it is dropped.
+#[derive(Debug)]
+#[must_use = "the bytes are released as soon as the permit is dropped"]
+pub struct MemoryPermit {
+    limit: MemoryLimit,
+    bytes: usize,
+}
+
+impl MemoryPermit {
+    /// Amount of bytes that this permit holds.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl Drop for MemoryPermit {
+    fn drop(&mut self) {
+        if self.bytes == 0 {
+            return;
+        }
+
+        let mut state = self.limit.state();
+        state.available += self.bytes;
+        state.wake_front();
+    }
+}
+
+struct Acquire<'a> {
+    limit: &'a MemoryLimit,
+    bytes: usize,
+    id: Option<u64>,
+}
+
+impl Future for Acquire<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let limit = self.limit;
+        let mut state = limit.state();
+        let at_front = match self.id {
+            Some(id) => state.waiters.front().map(|waiter| waiter.id) == Some(id),
+            None => state.waiters.is_empty(),
+        };
+
+        if at_front && state.available >= self.bytes {
+            state.available -= self.bytes;
+            if self.id.take().is_some() {
+                state.waiters.pop_front();
+
+                // the next operation might fit into what is left
+                state.wake_front();
+            }
+
+            return Poll::Ready(());
+        }
+
+        match self.id {
+            Some(id) => {
+                if let Some(waiter) = state.waiters.iter_mut().find(|waiter| waiter.id == id) {
+                    waiter.waker = Some(cx.waker().clone());
+                }
+            }
+
+            None => {
+                let id = state.next_id;
+                state.next_id += 1;
+                state.waiters.push_back(Waiter {
+                    id,
+                    waker: Some(cx.waker().clone()),
+                });
+
+                self.id = Some(id);
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        let Some(id) = self.id else {
+            return;
+        };
+
+        let mut state = self.limit.state();
+        let was_front = state.waiters.front().map(|waiter| waiter.id) == Some(id);
+        state.waiters.retain(|waiter| waiter.id != id);
+        if was_front {
+            state.wake_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryLimit, MemoryLimitExceeded};
+    use futures_util::{task::noop_waker_ref, FutureExt};
+    use std::{
+        future::Future,
+        task::{Context, Poll},
+    };
+
+    #[test]
+    fn fail_fast() {
+        let limit = MemoryLimit::fail_fast(10);
+        let permit = limit.try_acquire(6).unwrap();
+
+        assert_eq!(
+            limit.acquire(6).now_or_never().unwrap().unwrap_err(),
+            MemoryLimitExceeded {
+                requested: 6,
+                available: 4,
+                limit: 10
+            }
+        );
+
+        drop(permit);
+        assert!(limit.acquire(6).now_or_never().unwrap().is_ok());
+    }
+
+    #[test]
+    fn larger_than_limit() {
+        let limit = MemoryLimit::new(10);
+        assert!(limit.acquire(11).now_or_never().unwrap().is_err());
+    }
+
+    #[test]
+    fn waits_in_order() {
+        let limit = MemoryLimit::new(10);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let first = limit.try_acquire(8).unwrap();
+
+        let mut large = Box::pin(limit.acquire(10));
+        let mut small = Box::pin(limit.acquire(1));
+        assert!(large.as_mut().poll(&mut cx).is_pending());
+
+        // `small` would fit, but it has to wait behind `large`
+        assert!(small.as_mut().poll(&mut cx).is_pending());
+        assert!(limit.try_acquire(1).is_err());
+
+        drop(first);
+        let Poll::Ready(Ok(large_permit)) = large.as_mut().poll(&mut cx) else {
+            panic!("expected the large operation to be ready");
+        };
+
+        assert!(small.as_mut().poll(&mut cx).is_pending());
+        drop(large_permit);
+        let Poll::Ready(Ok(_small_permit)) = small.as_mut().poll(&mut cx) else {
+            panic!("expected the small operation to be ready");
+        };
+
+        assert_eq!(limit.available(), 9);
+    }
+
+    #[test]
+    fn cancelled_waiters_are_removed() {
+        let limit = MemoryLimit::new(10);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let first = limit.try_acquire(8).unwrap();
+
+        let mut large = Box::pin(limit.acquire(10));
+        assert!(large.as_mut().poll(&mut cx).is_pending());
+        drop(large);
+
+        assert!(limit.try_acquire(2).is_ok());
+        drop(first);
+        assert_eq!(limit.available(), 10);
+    }
+}
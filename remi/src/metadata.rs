@@ -19,6 +19,204 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-// This file is intentionally empty since we don't really know if we should
-// do a `Metadata` struct that is just a `HashMap` of key/value pairs with
-// a `Value` enum to wrap possible values that a storage service can do.
+use crate::{Extensions, File};
+use bytes::Bytes;
+use std::collections::HashMap;
+
+/// Represents the metadata of a [`File`] without its contents. This is shared by APIs
+/// that only need to stat or list files so they don't need to carry a [`Bytes`] container
+/// around with them.
+///
+/// Two [`Metadata`]s are equal if all of their fields besides [`extras`][Metadata::extras]
+/// are, since extensions can't be compared.
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    /// Returns a `u128` of when this file was last modified, in milliseconds
+    /// from January 1st, 1970.
+    pub last_modified_at: Option<u128>,
+
+    /// Returns the `Content-Type` header of this file, which should represent
+    /// what type of file this is.
+    pub content_type: Option<String>,
+
+    /// Returns a `u128` of when this file was last created, in milliseconds
+    /// from January 1st, 1970.
+    pub created_at: Option<u128>,
+
+    /// Mapping of a file's metadata that the file can retrieve and be used for
+    /// external applications.
+    pub metadata: HashMap<String, String>,
+
+    /// Whether or not if this file was a symlink or not. This is only used
+    /// in the filesystem crate of remi.
+    pub is_symlink: bool,
+
+    /// Checksum of the file's contents, if the storage service provides one. This is the
+    /// MD5 digest of the contents in hexadecimal unless the storage service documents
+    /// otherwise.
+    pub checksum: Option<String>,
+
+    /// Entity tag of the file, which changes whenever its contents change and can be
+    /// compared with [`StorageService::open_if_none_match`][crate::StorageService::open_if_none_match].
+    /// It doesn't include the surrounding quotes of an HTTP `ETag` header.
+    ///
+    /// * since: 0.11.0
+    pub etag: Option<String>,
+
+    /// Version identifier of the file, if the storage service supports versioning.
+    pub version: Option<String>,
+
+    /// Returns a `u128` of when the storage service will delete this file because of a
+    /// lifecycle or expiry policy, in milliseconds from January 1st, 1970.
+    pub expires_at: Option<u128>,
+
+    /// Storage service-specific data that was attached to the file, like the full
+    /// metadata document of a GridFS file or its [`FileTimestamps`][crate::FileTimestamps].
+    ///
+    /// * since: 0.11.0
+    pub extras: Extensions,
+
+    /// File name
+    pub name: String,
+
+    /// File path, usually `{service}://{full filepath}`
+    pub path: String,
+
+    /// file length (in bytes)
+    pub size: usize,
+}
+
+impl Metadata {
+    /// Attaches a [`Bytes`] container to this metadata and returns a [`File`].
+    pub fn with_data<B: Into<Bytes>>(self, data: B) -> File {
+        File {
+            last_modified_at: self.last_modified_at,
+            content_type: self.content_type,
+            created_at: self.created_at,
+            metadata: self.metadata,
+            is_symlink: self.is_symlink,
+            checksum: self.checksum,
+            etag: self.etag,
+            version: self.version,
+            expires_at: self.expires_at,
+            extras: self.extras,
+            data: data.into(),
+            name: self.name,
+            path: self.path,
+            size: self.size,
+        }
+    }
+}
+
+impl PartialEq for Metadata {
+    fn eq(&self, other: &Metadata) -> bool {
+        self.last_modified_at == other.last_modified_at
+            && self.content_type == other.content_type
+            && self.created_at == other.created_at
+            && self.metadata == other.metadata
+            && self.is_symlink == other.is_symlink
+            && self.checksum == other.checksum
+            && self.etag == other.etag
+            && self.version == other.version
+            && self.expires_at == other.expires_at
+            && self.name == other.name
+            && self.path == other.path
+            && self.size == other.size
+    }
+}
+
+impl Eq for Metadata {}
+
+impl From<File> for Metadata {
+    fn from(file: File) -> Metadata {
+        file.into_parts().0
+    }
+}
+
+impl From<&File> for Metadata {
+    fn from(file: &File) -> Metadata {
+        Metadata {
+            last_modified_at: file.last_modified_at,
+            content_type: file.content_type.clone(),
+            created_at: file.created_at,
+            metadata: file.metadata.clone(),
+            is_symlink: file.is_symlink,
+            checksum: file.checksum.clone(),
+            etag: file.etag.clone(),
+            version: file.version.clone(),
+            expires_at: file.expires_at,
+            extras: file.extras.clone(),
+            name: file.name.clone(),
+            path: file.path.clone(),
+            size: file.size,
+        }
+    }
+}
+
+impl From<Metadata> for File {
+    fn from(metadata: Metadata) -> File {
+        metadata.with_data(Bytes::new())
+    }
+}
+
+/// Full metadata document of a file for storage services that store metadata as BSON,
+/// which is kept in a [`File`]'s or [`UploadRequest`][crate::UploadRequest]'s extras.
+///
+/// The string map in [`File::metadata`] is a lossy view of this document since it only
+/// includes values that are strings.
+///
+/// * since: 0.11.0
+#[cfg(feature = "bson")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawMetadata(pub bson::Document);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileTimestamps;
+
+    #[test]
+    fn file_roundtrip() {
+        let metadata = Metadata {
+            content_type: Some(String::from("text/plain")),
+            name: String::from("hello.txt"),
+            path: String::from("fs://hello.txt"),
+            size: 5,
+            ..Default::default()
+        };
+
+        let file = metadata.clone().with_data(Bytes::from_static(b"hello"));
+        assert_eq!(file.to_metadata(), metadata);
+
+        let (parts, data) = file.into_parts();
+        assert_eq!(parts, metadata);
+        assert_eq!(data, Bytes::from_static(b"hello"));
+        assert!(File::from(metadata).data.is_empty());
+    }
+
+    #[test]
+    fn extras_roundtrip() {
+        let timestamps = FileTimestamps {
+            modified: Some(1_000_000_123_456),
+            created: None,
+        };
+
+        let mut metadata = Metadata {
+            name: String::from("hello.txt"),
+            ..Default::default()
+        };
+
+        metadata.extras.insert(timestamps);
+
+        let file = metadata.with_data(Bytes::from_static(b"hello"));
+        assert_eq!(file.extras.get::<FileTimestamps>(), Some(&timestamps));
+        assert_eq!(file.to_metadata().extras.get::<FileTimestamps>(), Some(&timestamps));
+
+        let (metadata, _) = file.into_parts();
+        assert_eq!(metadata.extras.get::<FileTimestamps>(), Some(&timestamps));
+
+        let file = File::from(metadata);
+        assert_eq!(file.extras.get::<FileTimestamps>(), Some(&timestamps));
+        assert_eq!(Metadata::from(file).extras.get::<FileTimestamps>(), Some(&timestamps));
+    }
+}
@@ -0,0 +1,690 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, Blob, CollisionStrategy, DeleteSummary, ListBlobsRequest, Page, ServiceInfo, Stats, StorageService,
+    UploadRequest,
+};
+use bytes::Bytes;
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+/// Describes which storage services a [`MirroredStorageService`] writes to.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WritePolicy {
+    /// Writes go to the primary and then the secondary, and fail if either fails. This
+    /// keeps both storage services in sync while migrating from one to the other.
+    #[default]
+    Both,
+
+    /// Writes only go to the primary, which leaves the secondary as a read-only fallback.
+    PrimaryOnly,
+}
+
+/// Describes when a [`MirroredStorageService`] reads from the secondary storage service.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadFallback {
+    /// Reads only go to the primary.
+    Never,
+
+    /// Reads go to the secondary if the file doesn't exist in the primary, which lets files
+    /// be read before they were backfilled into the primary.
+    #[default]
+    OnMissing,
+
+    /// Reads go to the secondary if the file doesn't exist in the primary or if the primary
+    /// failed, which also keeps files readable while the primary is unavailable.
+    OnMissingOrError,
+}
+
+/// Error from a [`MirroredStorageService`], which says which of the two storage services failed.
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub enum MirrorError<A, B> {
+    /// The primary storage service returned an error.
+    Primary(A),
+
+    /// The secondary storage service returned an error.
+    Secondary(B),
+}
+
+impl<A: Display, B: Display> Display for MirrorError<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MirrorError::Primary(e) => write!(f, "primary storage service failed: {e}"),
+            MirrorError::Secondary(e) => write!(f, "secondary storage service failed: {e}"),
+        }
+    }
+}
+
+impl<A: Error + 'static, B: Error + 'static> Error for MirrorError<A, B> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MirrorError::Primary(e) => Some(e),
+            MirrorError::Secondary(e) => Some(e),
+        }
+    }
+}
+
+/// A [`StorageService`] that mirrors writes to two storage services and reads from the
+/// primary with a fallback to the secondary, which is used to migrate from one storage
+/// service to another without downtime.
+///
+/// Which storage services are written to is decided by the [`WritePolicy`], and writes
+/// always go to the primary first so a failed write never only lands in the secondary.
+/// [`create_exclusive`][StorageService::create_exclusive] and uploads with a
+/// [`CollisionStrategy`] only check the primary for an existing file, and upload the file
+/// to the secondary into the same key. Deletions, copies, and renames are mirrored even if
+/// the file only exists in the secondary, since it might not have been backfilled yet.
+/// The storage service is read-only if the primary is, or if writes are mirrored and the
+/// secondary is.
+///
+/// [`open`][StorageService::open], [`open_range`][StorageService::open_range],
+/// [`blob`][StorageService::blob], and [`exists`][StorageService::exists] fall back to the
+/// secondary as decided by the [`ReadFallback`]. Listings, [`stats`][StorageService::stats],
+/// and [`info`][StorageService::info] only come from the primary.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{MirroredStorageService, ReadFallback};
+/// #
+/// // files are written to both, and read from the filesystem until they were
+/// // backfilled into S3
+/// let storage = MirroredStorageService::new(s3, fs).with_read_fallback(ReadFallback::OnMissing);
+/// storage.upload("./weow.txt", UploadRequest::default().with_data("weow")).await?;
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct MirroredStorageService<A, B> {
+    primary: A,
+    secondary: B,
+    write_policy: WritePolicy,
+    read_fallback: ReadFallback,
+}
+
+impl<A: StorageService, B: StorageService> MirroredStorageService<A, B> {
+    /// Creates a new [`MirroredStorageService`] that writes to both `primary` and `secondary`,
+    /// and reads from `secondary` if a file doesn't exist in `primary`.
+    pub fn new(primary: A, secondary: B) -> MirroredStorageService<A, B> {
+        MirroredStorageService {
+            primary,
+            secondary,
+            write_policy: WritePolicy::default(),
+            read_fallback: ReadFallback::default(),
+        }
+    }
+
+    /// Sets which storage services are written to.
+    pub fn with_write_policy(mut self, policy: WritePolicy) -> Self {
+        self.write_policy = policy;
+        self
+    }
+
+    /// Sets when files are read from the secondary storage service.
+    pub fn with_read_fallback(mut self, fallback: ReadFallback) -> Self {
+        self.read_fallback = fallback;
+        self
+    }
+
+    /// Returns the primary storage service.
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    /// Returns the secondary storage service.
+    pub fn secondary(&self) -> &B {
+        &self.secondary
+    }
+
+    fn mirrors_writes(&self) -> bool {
+        self.write_policy == WritePolicy::Both
+    }
+
+    /// Deletes every object in the `prefix` pseudo-directory from the primary, and from the
+    /// secondary if writes are mirrored, and returns the [`DeleteSummary`] of each storage
+    /// service. The secondary's summary is `None` if it wasn't written to.
+    ///
+    /// [`delete_prefix`][StorageService::delete_prefix] adds both counts together, which
+    /// counts files that exist in both storage services twice.
+    pub async fn delete_prefix_each<P: AsRef<Path> + Send>(
+        &self,
+        prefix: P,
+    ) -> Result<(DeleteSummary, Option<DeleteSummary>), MirrorError<A::Error, B::Error>> {
+        let prefix = prefix.as_ref();
+        let summary = self.primary.delete_prefix(prefix).await.map_err(MirrorError::Primary)?;
+        if !self.mirrors_writes() {
+            return Ok((summary, None));
+        }
+
+        let mirrored = self
+            .secondary
+            .delete_prefix(prefix)
+            .await
+            .map_err(MirrorError::Secondary)?;
+
+        Ok((summary, Some(mirrored)))
+    }
+
+    /// Returns the first key of `strategy`'s candidates that doesn't exist in the primary,
+    /// or the last candidate if all of them exist, so that creating it fails.
+    async fn free_key(&self, strategy: CollisionStrategy, path: &Path) -> Result<PathBuf, A::Error> {
+        let mut candidates = strategy.candidates(path);
+        let last = candidates.pop().unwrap_or_else(|| path.to_path_buf());
+        for candidate in candidates {
+            if !self.primary.exists(&candidate).await? {
+                return Ok(candidate);
+            }
+        }
+
+        Ok(last)
+    }
+
+    /// Decides whether a read that returned `result` from the primary should be retried on
+    /// the secondary, where `missing` tells if the primary's result is a missing file.
+    fn falls_back<T>(&self, path: &Path, result: &Result<T, A::Error>, missing: impl FnOnce(&T) -> bool) -> bool
+    where
+        A::Error: Display,
+    {
+        let fallback = match (self.read_fallback, result) {
+            (ReadFallback::Never, _) => false,
+            (_, Ok(value)) => missing(value),
+            (ReadFallback::OnMissing, Err(_)) => false,
+            (ReadFallback::OnMissingOrError, Err(e)) => {
+                #[cfg(feature = "tracing")]
+                ::tracing::warn!(error = %e, path = %path.display(), "primary storage service failed, reading from secondary");
+
+                #[cfg(feature = "log")]
+                ::log::warn!(
+                    "primary storage service failed to read [{}], reading from secondary: {e}",
+                    path.display()
+                );
+
+                let _ = e;
+                true
+            }
+        };
+
+        let _ = path;
+        fallback
+    }
+}
+
+#[async_trait]
+impl<A: StorageService, B: StorageService> StorageService for MirroredStorageService<A, B>
+where
+    A::Error: Display + Send,
+    B::Error: Send,
+{
+    type Error = MirrorError<A::Error, B::Error>;
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Owned(format!(
+            "remi:mirror({}, {})",
+            self.primary.name(),
+            self.secondary.name()
+        ))
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        self.primary.init().await.map_err(MirrorError::Primary)?;
+        self.secondary.init().await.map_err(MirrorError::Secondary)
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        let path = path.as_ref();
+        let result = self.primary.open(path).await;
+        if self.falls_back(path, &result, Option::is_none) {
+            return self.secondary.open(path).await.map_err(MirrorError::Secondary);
+        }
+
+        result.map_err(MirrorError::Primary)
+    }
+
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        let path = path.as_ref();
+        let result = self.primary.open_range(path, start, end).await;
+        if self.falls_back(path, &result, Option::is_none) {
+            return self
+                .secondary
+                .open_range(path, start, end)
+                .await
+                .map_err(MirrorError::Secondary);
+        }
+
+        result.map_err(MirrorError::Primary)
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        let path = path.as_ref();
+        let result = self.primary.blob(path).await;
+        if self.falls_back(path, &result, Option::is_none) {
+            return self.secondary.blob(path).await.map_err(MirrorError::Secondary);
+        }
+
+        result.map_err(MirrorError::Primary)
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        self.primary.blobs(path, options).await.map_err(MirrorError::Primary)
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        self.primary
+            .blobs_page(path, options)
+            .await
+            .map_err(MirrorError::Primary)
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        let path = path.as_ref();
+        let deleted = self.primary.delete(path).await.map_err(MirrorError::Primary)?;
+        if !self.mirrors_writes() {
+            return Ok(deleted);
+        }
+
+        // files that weren't backfilled yet only exist in one of them
+        let mirrored = self.secondary.delete(path).await.map_err(MirrorError::Secondary)?;
+        Ok(deleted || mirrored)
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        let (mut summary, mirrored) = self.delete_prefix_each(prefix).await?;
+        if let Some(mirrored) = mirrored {
+            summary.deleted += mirrored.deleted;
+            for key in mirrored.failed {
+                if !summary.failed.contains(&key) {
+                    summary.failed.push(key);
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let copied = self.primary.copy(src, dst).await.map_err(MirrorError::Primary)?;
+        if !self.mirrors_writes() {
+            return Ok(copied);
+        }
+
+        // files that weren't backfilled yet only exist in one of them
+        let mirrored = self.secondary.copy(src, dst).await.map_err(MirrorError::Secondary)?;
+        Ok(copied || mirrored)
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        let renamed = self.primary.rename(from, to).await.map_err(MirrorError::Primary)?;
+        if !self.mirrors_writes() {
+            return Ok(renamed);
+        }
+
+        let mirrored = self.secondary.rename(from, to).await.map_err(MirrorError::Secondary)?;
+        Ok(renamed || mirrored)
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        let path = path.as_ref();
+        let result = self.primary.exists(path).await;
+        if self.falls_back(path, &result, |exists| !exists) {
+            return self.secondary.exists(path).await.map_err(MirrorError::Secondary);
+        }
+
+        result.map_err(MirrorError::Primary)
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        let path = path.as_ref();
+        if !self.mirrors_writes() {
+            return self.primary.upload(path, options).await.map_err(MirrorError::Primary);
+        }
+
+        // the primary decides which key a file is uploaded into, otherwise the secondary could
+        // pick another suffix (or reject the upload) if it has files the primary doesn't have
+        let (path, options) = match options.collision() {
+            Some(strategy @ (CollisionStrategy::Error | CollisionStrategy::Suffix(_))) => {
+                let path = self.free_key(strategy, path).await.map_err(MirrorError::Primary)?;
+                self.primary
+                    .create_exclusive(&path, options.clone())
+                    .await
+                    .map_err(MirrorError::Primary)?;
+
+                (path, options.with_collision(CollisionStrategy::Overwrite))
+            }
+
+            _ => {
+                self.primary
+                    .upload(path, options.clone())
+                    .await
+                    .map_err(MirrorError::Primary)?;
+
+                (path.to_path_buf(), options)
+            }
+        };
+
+        self.secondary
+            .upload(path, options)
+            .await
+            .map_err(MirrorError::Secondary)
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        let path = path.as_ref();
+        if !self.mirrors_writes() {
+            return self
+                .primary
+                .create_exclusive(path, options)
+                .await
+                .map_err(MirrorError::Primary);
+        }
+
+        self.primary
+            .create_exclusive(path, options.clone())
+            .await
+            .map_err(MirrorError::Primary)?;
+
+        // the primary decides whether if the file already exists, the secondary
+        // might still have a copy of a file that was deleted before the migration
+        self.secondary
+            .upload(path, options.with_collision(CollisionStrategy::Overwrite))
+            .await
+            .map_err(MirrorError::Secondary)
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        let path = path.as_ref();
+        self.primary.create_dir(path).await.map_err(MirrorError::Primary)?;
+        if self.mirrors_writes() {
+            self.secondary.create_dir(path).await.map_err(MirrorError::Secondary)?;
+        }
+
+        Ok(())
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.primary.is_read_only() || (self.mirrors_writes() && self.secondary.is_read_only())
+    }
+
+    fn stats(&self) -> Stats {
+        self.primary.stats()
+    }
+
+    fn info(&self) -> ServiceInfo {
+        ServiceInfo {
+            name: self.name().into_owned(),
+            ..self.primary.info()
+        }
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        self.primary.verify_credentials().await.map_err(MirrorError::Primary)?;
+        self.secondary
+            .verify_credentials()
+            .await
+            .map_err(MirrorError::Secondary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MirrorError, MirroredStorageService, ReadFallback, WritePolicy};
+    use crate::{testing::TestStorage, Blob, CollisionStrategy, DeleteSummary, StorageService, UploadRequest};
+    use bytes::Bytes;
+    use std::io;
+
+    /// Storage service that fails every operation, like a primary that is unavailable.
+    fn unavailable() -> TestStorage {
+        TestStorage::new().failing(|_, _| Some(io::Error::new(io::ErrorKind::ConnectionRefused, "unavailable")))
+    }
+
+    fn mirror() -> MirroredStorageService<TestStorage, TestStorage> {
+        MirroredStorageService::new(TestStorage::new(), TestStorage::new())
+    }
+
+    fn request(data: &'static str) -> UploadRequest {
+        UploadRequest::default().with_data(data)
+    }
+
+    #[tokio::test]
+    async fn mirrors_writes() {
+        let storage = mirror();
+        storage.upload("./a.txt", request("weow")).await.unwrap();
+        assert!(storage.copy("./a.txt", "./b.txt").await.unwrap());
+        assert!(storage.rename("./b.txt", "./dir/c.txt").await.unwrap());
+
+        for service in [storage.primary(), storage.secondary()] {
+            assert_eq!(
+                service.open("./a.txt").await.unwrap(),
+                Some(Bytes::from_static(b"weow"))
+            );
+            assert_eq!(
+                service.open("./dir/c.txt").await.unwrap(),
+                Some(Bytes::from_static(b"weow"))
+            );
+            assert!(!service.exists("./b.txt").await.unwrap());
+        }
+
+        assert!(storage.delete("./a.txt").await.unwrap());
+
+        // the file is counted once for each storage service it was deleted from
+        assert_eq!(
+            storage.delete_prefix("./dir").await.unwrap(),
+            DeleteSummary {
+                deleted: 2,
+                failed: Vec::new()
+            }
+        );
+
+        assert!(storage.primary().is_empty());
+        assert!(storage.secondary().is_empty());
+    }
+
+    #[tokio::test]
+    async fn primary_only_writes() {
+        let storage = mirror().with_write_policy(WritePolicy::PrimaryOnly);
+        storage.secondary().upload("./a.txt", request("fluff")).await.unwrap();
+
+        storage.upload("./b.txt", request("weow")).await.unwrap();
+        assert!(!storage.delete("./a.txt").await.unwrap());
+
+        assert!(storage.primary().exists("./b.txt").await.unwrap());
+        assert!(!storage.secondary().exists("./b.txt").await.unwrap());
+        assert!(storage.secondary().exists("./a.txt").await.unwrap());
+
+        let (summary, mirrored) = storage.delete_prefix_each("./").await.unwrap();
+        assert_eq!(summary.deleted, 1);
+        assert_eq!(mirrored, None);
+    }
+
+    #[tokio::test]
+    async fn read_only() {
+        let snapshot = || async { crate::freeze(TestStorage::new(), "").await.unwrap() };
+
+        assert!(!mirror().is_read_only());
+        assert!(MirroredStorageService::new(snapshot().await, TestStorage::new()).is_read_only());
+
+        // the secondary is only written to if writes are mirrored
+        let storage = MirroredStorageService::new(TestStorage::new(), snapshot().await);
+        assert!(storage.is_read_only());
+        assert!(!storage.with_write_policy(WritePolicy::PrimaryOnly).is_read_only());
+    }
+
+    #[tokio::test]
+    async fn mirrors_writes_of_files_that_only_exist_in_the_secondary() {
+        let storage = mirror();
+        for path in ["./dir/a.txt", "./dir/b.txt", "./dir/c.txt"] {
+            storage.secondary().upload(path, request("weow")).await.unwrap();
+        }
+
+        storage.primary().upload("./dir/a.txt", request("weow")).await.unwrap();
+
+        assert!(storage.copy("./dir/b.txt", "./dir/d.txt").await.unwrap());
+        assert!(storage.rename("./dir/c.txt", "./dir/e.txt").await.unwrap());
+        assert!(storage.secondary().exists("./dir/d.txt").await.unwrap());
+        assert!(storage.secondary().exists("./dir/e.txt").await.unwrap());
+        assert!(!storage.secondary().exists("./dir/c.txt").await.unwrap());
+
+        // `a.txt` is deleted from both, the rest only from the secondary
+        let (summary, mirrored) = storage.delete_prefix_each("./dir").await.unwrap();
+        assert_eq!(summary.deleted, 1);
+        assert_eq!(mirrored.map(|summary| summary.deleted), Some(4));
+
+        assert!(storage.primary().is_empty());
+        assert!(storage.secondary().is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_fallback() {
+        let storage = mirror();
+        storage.secondary().upload("./a.txt", request("weow")).await.unwrap();
+
+        assert_eq!(
+            storage.open("./a.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+        assert_eq!(
+            storage.open_range("./a.txt", 1, Some(3)).await.unwrap(),
+            Some(Bytes::from_static(b"eo"))
+        );
+
+        assert!(storage.exists("./a.txt").await.unwrap());
+        assert!(matches!(storage.blob("./a.txt").await.unwrap(), Some(Blob::File(_))));
+
+        // files in the primary are preferred
+        storage.primary().upload("./a.txt", request("fluff")).await.unwrap();
+        assert_eq!(
+            storage.open("./a.txt").await.unwrap(),
+            Some(Bytes::from_static(b"fluff"))
+        );
+
+        let storage = mirror().with_read_fallback(ReadFallback::Never);
+        storage.secondary().upload("./a.txt", request("weow")).await.unwrap();
+        assert_eq!(storage.open("./a.txt").await.unwrap(), None);
+        assert!(!storage.exists("./a.txt").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_fallback_on_error() {
+        let secondary = TestStorage::new();
+        secondary.upload("./a.txt", request("weow")).await.unwrap();
+
+        let storage = MirroredStorageService::new(unavailable(), secondary.clone());
+        assert!(matches!(storage.open("./a.txt").await, Err(MirrorError::Primary(_))));
+
+        let storage = storage.with_read_fallback(ReadFallback::OnMissingOrError);
+        assert_eq!(
+            storage.open("./a.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        // writes never only land in the secondary
+        assert!(matches!(
+            storage.upload("./b.txt", request("weow")).await,
+            Err(MirrorError::Primary(_))
+        ));
+
+        assert!(!secondary.exists("./b.txt").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn primary_decides_collisions() {
+        let storage = mirror();
+        storage.primary().upload("./a.txt", request("weow")).await.unwrap();
+        storage.secondary().upload("./b.txt", request("weow")).await.unwrap();
+
+        // the suffix is picked by the primary, even though `a.txt` doesn't exist in the secondary
+        storage
+            .upload("./a.txt", request("fluff").with_collision(CollisionStrategy::Suffix(2)))
+            .await
+            .unwrap();
+
+        for service in [storage.primary(), storage.secondary()] {
+            assert_eq!(
+                service.open("./a-1.txt").await.unwrap(),
+                Some(Bytes::from_static(b"fluff"))
+            );
+        }
+
+        assert!(!storage.secondary().exists("./a.txt").await.unwrap());
+
+        // existing files in the primary reject the upload before the secondary is written to
+        let error = storage
+            .upload("./a.txt", request("fluff").with_collision(CollisionStrategy::Error))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, MirrorError::Primary(ref e) if e.kind() == io::ErrorKind::AlreadyExists));
+        assert!(!storage.secondary().exists("./a.txt").await.unwrap());
+
+        let error = storage
+            .upload("./a.txt", request("fluff").with_collision(CollisionStrategy::Suffix(1)))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, MirrorError::Primary(ref e) if e.kind() == io::ErrorKind::AlreadyExists));
+
+        // files that only exist in the secondary are replaced
+        storage
+            .upload("./b.txt", request("fluff").with_collision(CollisionStrategy::Error))
+            .await
+            .unwrap();
+
+        storage.create_exclusive("./c.txt", request("fluff")).await.unwrap();
+        for service in [storage.primary(), storage.secondary()] {
+            assert_eq!(
+                service.open("./b.txt").await.unwrap(),
+                Some(Bytes::from_static(b"fluff"))
+            );
+            assert_eq!(
+                service.open("./c.txt").await.unwrap(),
+                Some(Bytes::from_static(b"fluff"))
+            );
+        }
+    }
+}
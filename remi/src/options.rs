@@ -19,8 +19,13 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use crate::{CollisionStrategy, Extensions, File};
 use bytes::Bytes;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroU32,
+    ops::Deref,
+};
 
 /// Represents the request options for querying blobs from a storage service.
 #[derive(Debug, Clone, Default)]
@@ -32,6 +37,10 @@ pub struct ListBlobsRequest {
 
     /// A list of extensions to filter for. By default, this will
     /// include all file extensions if no entries exist.
+    ///
+    /// Extensions are stored with a leading dot (`.txt`), which is what
+    /// [`ListBlobsRequest::with_extensions`] normalizes them to. Only the last extension
+    /// of a file name is compared, so `archive.tar.gz` is matched by `.gz`.
     pub extensions: HashSet<String>,
 
     /// List of file names to exclude from the returned entry. This can
@@ -40,6 +49,52 @@ pub struct ListBlobsRequest {
 
     /// Optional prefix to set when querying for blobs.
     pub prefix: Option<String>,
+
+    /// Only return blobs whose key sorts after this key. This is usually a
+    /// [`Checkpoint`][crate::Checkpoint] from a [`BlobStream`][crate::BlobStream]
+    /// that is being resumed.
+    ///
+    /// - S3: This is sent as the `StartAfter` parameter.
+    /// - Filesystem: Entries are sorted by name and filtered while listing the directory.
+    /// - Others: Blobs are filtered by [`BlobStream`][crate::BlobStream] after being listed.
+    pub start_after: Option<String>,
+
+    /// Continuation token from the previous [`Page`][crate::Page] that was returned
+    /// from [`StorageService::blobs_page`][crate::StorageService::blobs_page], which
+    /// continues the listing from where that page ended.
+    pub continuation: Option<String>,
+
+    /// Whether if files should include their user metadata and content type.
+    ///
+    /// - S3: Listing objects doesn't return them, so they are fetched with a `HeadObject`
+    ///   request per object with bounded concurrency, which is why this is opt-in.
+    /// - Others: They are always included.
+    pub include_metadata: bool,
+
+    /// Whether if files should be listed without downloading their contents, which leaves
+    /// [`File::data`][crate::File::data] empty. Their size, content type, and metadata are
+    /// still included.
+    ///
+    /// - Azure: Every blob is downloaded while listing unless this is set.
+    /// - Filesystem: Every file is read while listing unless this is set, in which case only
+    ///   the first few kilobytes are read to resolve the content type.
+    /// - S3, GridFS: Contents are never downloaded while listing.
+    pub metadata_only: bool,
+
+    /// Maximum amount of blobs to request from the storage provider at a time, which trades
+    /// the amount of requests that a listing takes for the size of each response. Storage
+    /// services clamp this to what their provider supports, see [`ListBlobsRequest::page_size_or`].
+    ///
+    /// - S3: Sent as `MaxKeys`, up to 1,000.
+    /// - Azure: Sent as `maxresults`, up to 5,000. This takes precedence over the
+    ///   container's configured `max_results`.
+    /// - GridFS: Used as the cursor's batch size.
+    /// - Filesystem: Amount of directory entries in a [`Page`][crate::Page].
+    pub page_size: Option<NonZeroU32>,
+
+    /// Storage service-specific options for this request. Extensions that a
+    /// storage service doesn't know about are ignored.
+    pub extras: Extensions,
 }
 
 impl ListBlobsRequest {
@@ -55,12 +110,69 @@ impl ListBlobsRequest {
         self
     }
 
+    /// Inserts a storage service-specific [extension][Extensions] into this request.
+    pub fn with_extra<T: Clone + Send + Sync + 'static>(mut self, extra: T) -> Self {
+        self.extras.insert(extra);
+        self
+    }
+
+    /// Resumes a listing after the given key, usually a [`Checkpoint`][crate::Checkpoint].
+    pub fn with_start_after<I: Into<String>>(mut self, key: Option<I>) -> Self {
+        self.start_after = key.map(Into::into);
+        self
+    }
+
+    /// Continues a listing from the continuation token of a [`Page`][crate::Page].
+    pub fn with_continuation<I: Into<String>>(mut self, token: Option<I>) -> Self {
+        self.continuation = token.map(Into::into);
+        self
+    }
+
+    /// Whether if files should include their user metadata and content type, see
+    /// [`ListBlobsRequest::include_metadata`].
+    pub fn with_metadata(mut self, yes: bool) -> Self {
+        self.include_metadata = yes;
+        self
+    }
+
+    /// Sets the maximum amount of blobs to request from the storage provider at a time, see
+    /// [`ListBlobsRequest::page_size`].
+    pub fn with_page_size(mut self, size: Option<NonZeroU32>) -> Self {
+        self.page_size = size;
+        self
+    }
+
+    /// Returns [`ListBlobsRequest::page_size`] clamped to `max`, or `default` if no page size
+    /// was set. Storage services use this with the limits of their provider.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use remi::ListBlobsRequest;
+    /// # use std::num::NonZeroU32;
+    /// #
+    /// let request = ListBlobsRequest::default();
+    /// assert_eq!(request.page_size_or(1000, 1000), 1000);
+    ///
+    /// let request = request.with_page_size(NonZeroU32::new(5000));
+    /// assert_eq!(request.page_size_or(1000, 1000), 1000);
+    /// assert_eq!(request.page_size_or(100, 10000), 5000);
+    /// ```
+    pub fn page_size_or(&self, default: u32, max: u32) -> u32 {
+        self.page_size.map_or(default, |size| size.get().min(max))
+    }
+
+    /// Whether if files should be listed without downloading their contents, see
+    /// [`ListBlobsRequest::metadata_only`].
+    pub fn with_metadata_only(mut self, yes: bool) -> Self {
+        self.metadata_only = yes;
+        self
+    }
+
     /// Appends a list of extensions that can be use to filter files from
-    /// in the given directory that items were found.
+    /// in the given directory that items were found. Extensions can be given
+    /// with or without a leading dot, so both `txt` and `.txt` are the same.
     pub fn with_extensions<'a, I: Iterator<Item = &'a str>>(mut self, exts: I) -> Self {
-        self.extensions
-            .extend(exts.filter(|x| x.starts_with('.')).map(String::from));
-
+        self.extensions.extend(exts.filter_map(normalize_extension));
         self
     }
 
@@ -101,6 +213,7 @@ impl ListBlobsRequest {
     ///
     /// assert!(!req.is_ext_allowed(".json"));
     /// assert!(req.is_ext_allowed(".txt"));
+    /// assert!(req.is_ext_allowed("txt"));
     ///
     /// let req = ListBlobsRequest::default();
     /// assert!(req.is_ext_allowed(".json"));
@@ -110,10 +223,41 @@ impl ListBlobsRequest {
             return true;
         }
 
-        self.extensions.contains(ext.as_ref())
+        // `extensions` is public, so entries that weren't normalized are also compared
+        let ext = ext.as_ref().trim_start_matches('.');
+        self.extensions
+            .iter()
+            .any(|allowed| allowed.trim_start_matches('.') == ext)
     }
 }
 
+/// Normalizes `ext` to have a leading dot, returning `None` if it is empty.
+fn normalize_extension(ext: &str) -> Option<String> {
+    match ext.trim().trim_start_matches('.') {
+        "" => None,
+        ext => Some(format!(".{ext}")),
+    }
+}
+
+/// Modification and creation times (in milliseconds since the Unix epoch) that an upload
+/// should keep, like [`File::last_modified_at`] and [`File::created_at`]. This is set with
+/// [`UploadRequest::with_timestamps`], and [`UploadRequest::from_file`] carries them over
+/// from the file that is re-uploaded.
+///
+/// - Filesystem: The modification time is set on the written file. The creation time is
+///   only set on Windows and macOS, since other platforms don't allow changing it.
+/// - Everything else: This will not do anything.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FileTimestamps {
+    /// When the file was last modified.
+    pub modified: Option<u128>,
+
+    /// When the file was created.
+    pub created: Option<u128>,
+}
+
 /// Represents a request object that allows users who interact with the storage service
 /// API to create objects with a [`Bytes`] container.
 #[derive(Debug, Clone, Default)]
@@ -134,9 +278,34 @@ pub struct UploadRequest {
     /// [`Bytes`] container of the given data to send to the service
     /// or to write to local disk (with `remi_fs`).
     pub data: Bytes,
+
+    /// Storage service-specific options for this request. Extensions that a
+    /// storage service doesn't know about are ignored.
+    pub extras: Extensions,
 }
 
 impl UploadRequest {
+    /// Creates an [`UploadRequest`] that re-uploads the given [`File`] with its data,
+    /// content type, and metadata, so that a blob can be copied between storage services
+    /// without losing anything.
+    ///
+    /// ## Example
+    /// ```rust,ignore
+    /// # use remi::{Blob, UploadRequest};
+    /// #
+    /// if let Some(Blob::File(file)) = source.blob("./charts/index.yaml").await? {
+    ///     destination.upload("./charts/index.yaml", UploadRequest::from_file(&file)).await?;
+    /// }
+    /// ```
+    pub fn from_file(file: &File) -> UploadRequest {
+        UploadRequest {
+            content_type: file.content_type.clone(),
+            metadata: file.metadata.clone(),
+            data: file.data.clone(),
+            extras: extras_of(file),
+        }
+    }
+
     /// Overrides the content type when the request is sent.
     ///
     /// ## Example
@@ -155,13 +324,103 @@ impl UploadRequest {
         self
     }
 
+    /// Inserts a storage service-specific [extension][Extensions] into this request.
+    pub fn with_extra<T: Clone + Send + Sync + 'static>(mut self, extra: T) -> Self {
+        self.extras.insert(extra);
+        self
+    }
+
     /// Appends new metadata to this request.
     pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
         self.metadata.extend(metadata);
         self
     }
 
-    /// Overrides the data container for this request to a new container provided.
+    /// Sets the structured metadata document to insert for storage services that store
+    /// metadata as BSON. Keys from [`metadata`][UploadRequest::metadata] take precedence
+    /// over keys in this document.
+    ///
+    /// - Gridfs: This will be used as the `$metadata` field of the MongoDB document.
+    /// - Everything else: This will not do anything.
+    ///
+    /// * since: 0.11.0
+    #[cfg(feature = "bson")]
+    pub fn with_raw_metadata(self, metadata: bson::Document) -> Self {
+        self.with_extra(crate::RawMetadata(metadata))
+    }
+
+    /// Returns the structured metadata document that was set with
+    /// [`with_raw_metadata`][UploadRequest::with_raw_metadata].
+    ///
+    /// * since: 0.11.0
+    #[cfg(feature = "bson")]
+    pub fn raw_metadata(&self) -> Option<&bson::Document> {
+        self.extras.get::<crate::RawMetadata>().map(|raw| &raw.0)
+    }
+
+    /// Sets the [`CollisionStrategy`] of this request, which decides what happens when a
+    /// file already exists at the key that is uploaded to.
+    ///
+    /// * since: 0.11.0
+    pub fn with_collision(self, strategy: CollisionStrategy) -> Self {
+        self.with_extra(strategy)
+    }
+
+    /// Sets whether if the upload should replace a file that already exists at its key,
+    /// which is [`CollisionStrategy::Overwrite`] if `true` or [`CollisionStrategy::Error`]
+    /// otherwise.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use remi::{CollisionStrategy, UploadRequest};
+    /// #
+    /// let req = UploadRequest::default().with_overwrite(false);
+    /// assert_eq!(req.collision(), Some(CollisionStrategy::Error));
+    /// ```
+    ///
+    /// * since: 0.11.0
+    pub fn with_overwrite(self, overwrite: bool) -> Self {
+        self.with_collision(match overwrite {
+            true => CollisionStrategy::Overwrite,
+            false => CollisionStrategy::Error,
+        })
+    }
+
+    /// Sets whether if the upload should fail with [`AlreadyExists`][crate::AlreadyExists]
+    /// when a file already exists at its key, which is the opposite of
+    /// [`with_overwrite`][UploadRequest::with_overwrite].
+    ///
+    /// * since: 0.11.0
+    pub fn with_fail_if_exists(self, fail: bool) -> Self {
+        self.with_overwrite(!fail)
+    }
+
+    /// Returns the [`CollisionStrategy`] that was set with
+    /// [`with_collision`][UploadRequest::with_collision], if any.
+    ///
+    /// * since: 0.11.0
+    pub fn collision(&self) -> Option<CollisionStrategy> {
+        self.extras.get::<CollisionStrategy>().copied()
+    }
+
+    /// Sets the [`FileTimestamps`] that the uploaded file should keep.
+    ///
+    /// * since: 0.11.0
+    pub fn with_timestamps(self, timestamps: FileTimestamps) -> Self {
+        self.with_extra(timestamps)
+    }
+
+    /// Returns the [`FileTimestamps`] that were set with
+    /// [`with_timestamps`][UploadRequest::with_timestamps], if any.
+    ///
+    /// * since: 0.11.0
+    pub fn timestamps(&self) -> Option<FileTimestamps> {
+        self.extras.get::<FileTimestamps>().copied()
+    }
+
+    /// Overrides the data container for this request to a new container provided. Buffers
+    /// that [`Bytes`] can't take ownership of can be used with [`with_shared_data`][UploadRequest::with_shared_data]
+    /// instead.
     ///
     /// ## Example
     /// ```rust,ignore
@@ -178,4 +437,134 @@ impl UploadRequest {
         self.data = container.into();
         self
     }
+
+    /// Creates an [`UploadRequest`] for data that lives for the entire program, like assets
+    /// that were embedded with [`include_bytes!`], without copying it.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use remi::UploadRequest;
+    /// #
+    /// let req = UploadRequest::from_static(b"{}").with_content_type(Some("application/json"));
+    /// assert_eq!(req.data, &b"{}"[..]);
+    /// ```
+    ///
+    /// * since: 0.11.0
+    pub fn from_static(data: &'static [u8]) -> UploadRequest {
+        UploadRequest::default().with_data(Bytes::from_static(data))
+    }
+
+    /// Overrides the data container for this request with a buffer that is owned by something
+    /// other than [`Bytes`], like an `Arc<Vec<u8>>` that is shared with the rest of the application,
+    /// a `Cow<'static, [u8]>`, or a memory-mapped file. The buffer is kept alive by the request and
+    /// isn't copied, unlike converting it into a `Vec<u8>` first.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use remi::UploadRequest;
+    /// # use std::sync::Arc;
+    /// #
+    /// let buffer = Arc::new(vec![0x12, 0x13]);
+    /// let req = UploadRequest::default().with_shared_data(buffer.clone());
+    /// assert_eq!(req.data.as_ptr(), buffer.as_ptr());
+    /// ```
+    ///
+    /// * since: 0.11.0
+    pub fn with_shared_data<T>(self, owner: T) -> Self
+    where
+        T: Deref + Send + 'static,
+        T::Target: AsRef<[u8]>,
+    {
+        self.with_data(Bytes::from_owner(SharedData(owner)))
+    }
+}
+
+/// Buffer that dereferences into bytes, which [`Bytes::from_owner`] can take since
+/// `Arc<Vec<u8>>` only implements `AsRef<Vec<u8>>`.
+struct SharedData<T>(T);
+
+impl<T: Deref> AsRef<[u8]> for SharedData<T>
+where
+    T::Target: AsRef<[u8]>,
+{
+    fn as_ref(&self) -> &[u8] {
+        (*self.0).as_ref()
+    }
+}
+
+impl From<File> for UploadRequest {
+    fn from(file: File) -> Self {
+        UploadRequest {
+            extras: extras_of(&file),
+            content_type: file.content_type,
+            metadata: file.metadata,
+            data: file.data,
+        }
+    }
+}
+
+/// Carries over the extras of a [`File`] that can be uploaded again as-is.
+fn extras_of(file: &File) -> Extensions {
+    let mut extras = Extensions::default();
+    if file.last_modified_at.is_some() || file.created_at.is_some() {
+        extras.insert(FileTimestamps {
+            modified: file.last_modified_at,
+            created: file.created_at,
+        });
+    }
+
+    #[cfg(feature = "bson")]
+    if let Some(raw) = file.extras.get::<crate::RawMetadata>() {
+        extras.insert(raw.clone());
+    }
+
+    extras
+}
+
+impl From<&File> for UploadRequest {
+    fn from(file: &File) -> Self {
+        UploadRequest::from_file(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ListBlobsRequest, UploadRequest};
+    use std::{borrow::Cow, sync::Arc};
+
+    #[test]
+    fn shared_data_is_not_copied() {
+        let buffer: Cow<'static, [u8]> = Cow::Owned(vec![1, 2, 3]);
+        let ptr = buffer.as_ptr();
+
+        let request = UploadRequest::default().with_shared_data(buffer);
+        assert_eq!(request.data.as_ptr(), ptr);
+        assert_eq!(request.data, &[1, 2, 3][..]);
+
+        let buffer = Arc::new(vec![4, 5, 6]);
+        let request = UploadRequest::default().with_shared_data(buffer.clone());
+        assert_eq!(request.data.as_ptr(), buffer.as_ptr());
+
+        static DATA: &[u8] = b"weow";
+
+        let request = UploadRequest::from_static(DATA);
+        assert_eq!(request.data.as_ptr(), DATA.as_ptr());
+    }
+
+    #[test]
+    fn extensions_are_normalized() {
+        let request = ListBlobsRequest::default().with_extensions(["txt", ".json", "", "."].into_iter());
+        let mut extensions = request.extensions.iter().map(String::as_str).collect::<Vec<_>>();
+        extensions.sort();
+
+        assert_eq!(extensions, [".json", ".txt"]);
+        assert!(request.is_ext_allowed("txt"));
+        assert!(request.is_ext_allowed(".txt"));
+        assert!(request.is_ext_allowed("json"));
+        assert!(!request.is_ext_allowed("md"));
+
+        let mut request = ListBlobsRequest::default();
+        request.extensions.insert(String::from("txt"));
+        assert!(request.is_ext_allowed(".txt"));
+    }
 }
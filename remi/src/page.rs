@@ -0,0 +1,205 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+/// A single page of a listing that was returned from [`StorageService::blobs_page`][crate::StorageService::blobs_page].
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{ListBlobsRequest, StorageService as _};
+/// #
+/// let mut request = ListBlobsRequest::default();
+/// loop {
+///     let page = storage.blobs_page(None::<&str>, Some(request.clone())).await?;
+///     for blob in page.items {
+///         process(blob).await?;
+///     }
+///
+///     match page.continuation {
+///         Some(token) => request = request.with_continuation(Some(token)),
+///         None => break,
+///     }
+/// }
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    /// Items in this page.
+    pub items: Vec<T>,
+
+    /// Opaque token that fetches the next page when it is passed to
+    /// [`ListBlobsRequest::with_continuation`][crate::ListBlobsRequest::with_continuation],
+    /// or `None` if this is the last page. Tokens are only valid for the storage service
+    /// that returned them.
+    pub continuation: Option<String>,
+
+    /// Amount of requests that were sent to the storage provider to fetch this page,
+    /// including the ones that fetched metadata or contents of files. This is `0` for
+    /// storage services that don't send requests, like **remi-fs**.
+    pub requests: u64,
+}
+
+impl<T> Page<T> {
+    /// Creates a [`Page`] with `items` that has another page after it if `continuation` is set.
+    pub fn new(items: Vec<T>, continuation: Option<String>) -> Page<T> {
+        Page {
+            items,
+            continuation,
+            requests: 0,
+        }
+    }
+
+    /// Creates the last [`Page`] of a listing.
+    pub fn last(items: Vec<T>) -> Page<T> {
+        Page::new(items, None)
+    }
+
+    /// Sets the amount of requests that were sent to fetch this page, see [`Page::requests`].
+    pub fn with_requests(mut self, requests: u64) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    /// Returns `true` if there are no pages after this one.
+    pub fn is_last(&self) -> bool {
+        self.continuation.is_none()
+    }
+}
+
+impl<T> Default for Page<T> {
+    fn default() -> Self {
+        Page::last(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Page;
+    use crate::{testing::TestStorage, Blob, ListBlobsRequest, MeteredStorageService, StorageService, UploadRequest};
+    use futures_util::TryStreamExt;
+    use std::{io, num::NonZeroU32};
+
+    async fn upload<S: StorageService<Error = io::Error>>(storage: &S, files: usize) {
+        for i in 0..files {
+            storage
+                .upload(format!("./dir/{i}.txt"), UploadRequest::default().with_data("weow"))
+                .await
+                .unwrap();
+        }
+    }
+
+    fn names(blobs: Vec<Blob>) -> Vec<String> {
+        blobs
+            .into_iter()
+            .map(|blob| match blob {
+                Blob::File(file) => file.name,
+                Blob::Directory(dir) => dir.name,
+            })
+            .collect()
+    }
+
+    fn page_size(size: u32) -> ListBlobsRequest {
+        ListBlobsRequest::default().with_page_size(NonZeroU32::new(size))
+    }
+
+    #[test]
+    fn pages() {
+        let page = Page::new(vec![1, 2], Some(String::from("2"))).with_requests(1);
+        assert!(!page.is_last());
+        assert_eq!(page.requests, 1);
+
+        assert!(Page::last(vec![3]).is_last());
+        assert_eq!(Page::<u8>::default(), Page::last(Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn follows_continuations() {
+        let storage = TestStorage::new().with_pagination(true);
+        upload(&storage, 5).await;
+
+        let mut request = page_size(2);
+        let mut listed = Vec::new();
+        loop {
+            let page = storage.blobs_page(Some("./dir"), Some(request.clone())).await.unwrap();
+            assert!(page.items.len() <= 2);
+
+            listed.extend(names(page.items));
+            match page.continuation {
+                Some(continuation) => request = request.with_continuation(Some(continuation)),
+                None => break,
+            }
+        }
+
+        assert_eq!(listed, ["0.txt", "1.txt", "2.txt", "3.txt", "4.txt"]);
+    }
+
+    #[tokio::test]
+    async fn streams_every_page() {
+        let storage = TestStorage::new().with_pagination(true);
+        upload(&storage, 5).await;
+
+        let blobs = storage
+            .blob_stream(Some("./dir"), Some(page_size(2)))
+            .into_stream()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(names(blobs), ["0.txt", "1.txt", "2.txt", "3.txt", "4.txt"]);
+    }
+
+    #[tokio::test]
+    async fn streams_one_page_at_a_time() {
+        let storage = MeteredStorageService::new(TestStorage::new().with_pagination(true));
+        upload(&storage, 5).await;
+
+        let uploads = storage.stats().operations;
+        let mut stream = storage.blob_stream(Some("./dir"), Some(page_size(2)));
+
+        // the second page is only requested once the first one was yielded
+        for expected in [1, 1, 2, 2, 3] {
+            assert!(stream.next().await.unwrap().is_some());
+            assert_eq!(storage.stats().operations - uploads, expected);
+        }
+
+        assert!(stream.next().await.unwrap().is_none());
+        assert_eq!(storage.stats().operations - uploads, 3);
+    }
+
+    #[tokio::test]
+    async fn lists_everything_in_one_page_by_default() {
+        let storage = TestStorage::new();
+        upload(&storage, 5).await;
+
+        let page = storage.blobs_page(Some("./dir"), Some(page_size(2))).await.unwrap();
+        assert!(page.is_last());
+        assert_eq!(page.items.len(), 5);
+
+        let blobs = storage
+            .blob_stream(Some("./dir"), Some(page_size(2)))
+            .into_stream()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(blobs.len(), 5);
+    }
+}
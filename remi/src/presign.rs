@@ -0,0 +1,219 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{async_trait, StorageService};
+use std::{
+    fmt::{self, Display},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// HTTP method that a [`PresignedUrl`] can be used with.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum PresignMethod {
+    /// Downloads the file with a `GET` request.
+    #[default]
+    Get,
+
+    /// Uploads a file with a `PUT` request, replacing the file if it exists.
+    Put,
+}
+
+impl PresignMethod {
+    /// Returns the name of the HTTP method, like `GET`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            PresignMethod::Get => "GET",
+            PresignMethod::Put => "PUT",
+        }
+    }
+}
+
+impl Display for PresignMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Options for [`PresignableStorageService::presign`].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresignOptions {
+    /// How long the URL is valid for after it was created. Storage providers cap how
+    /// long a URL can be valid for (like 7 days for Amazon S3) and reject longer durations.
+    pub expires_in: Duration,
+
+    /// HTTP method that the URL can be used with.
+    pub method: PresignMethod,
+}
+
+impl PresignOptions {
+    /// Creates [`PresignOptions`] for a download URL that is valid for `expires_in`.
+    pub fn new(expires_in: Duration) -> PresignOptions {
+        PresignOptions {
+            expires_in,
+            method: PresignMethod::Get,
+        }
+    }
+
+    /// Sets the HTTP method that the URL can be used with.
+    pub fn with_method(mut self, method: PresignMethod) -> Self {
+        self.method = method;
+        self
+    }
+}
+
+/// Time-limited URL that can be handed out to clients to download or upload a file
+/// without having credentials of their own, created by [`PresignableStorageService::presign`].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresignedUrl {
+    /// The URL itself, which includes the signature.
+    pub url: String,
+
+    /// HTTP method that the URL can be used with.
+    pub method: PresignMethod,
+
+    /// Headers that have to be sent along with the request for the signature to be valid.
+    pub headers: Vec<(String, String)>,
+
+    /// When the URL stops being valid.
+    pub expires_at: SystemTime,
+}
+
+impl Display for PresignedUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.url)
+    }
+}
+
+/// A [`StorageService`] whose storage provider can sign URLs that grant temporary access
+/// to a single file, which lets clients download or upload files directly instead of
+/// sending them through the application.
+///
+/// Presigning is done locally with the storage service's credentials, so it doesn't check
+/// that the file exists.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{PresignableStorageService, PresignMethod, PresignOptions};
+/// # use std::time::Duration;
+/// #
+/// let options = PresignOptions::new(Duration::from_secs(15 * 60)).with_method(PresignMethod::Put);
+/// let presigned = storage.presign("./charts/index.yaml", options).await?;
+///
+/// println!("upload your chart with `curl -X PUT --upload-file index.yaml '{}'`", presigned.url);
+/// ```
+///
+/// * since: 0.11.0
+#[async_trait]
+pub trait PresignableStorageService: StorageService {
+    /// Returns a [`PresignedUrl`] for the file in `path`.
+    ///
+    /// * since: 0.11.0
+    async fn presign<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: PresignOptions,
+    ) -> Result<PresignedUrl, Self::Error>
+    where
+        Self: Sized;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PresignMethod, PresignOptions, PresignableStorageService};
+    use crate::{testing::TestStorage, MeteredStorageService, StorageService};
+    use std::time::{Duration, SystemTime};
+
+    #[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+    use crate::{TimeoutPolicy, TimeoutStorageService};
+
+    #[test]
+    fn options() {
+        let options = PresignOptions::new(Duration::from_secs(900));
+        assert_eq!(options.method, PresignMethod::Get);
+        assert_eq!(options.with_method(PresignMethod::Put).method, PresignMethod::Put);
+        assert_eq!(PresignMethod::Put.to_string(), "PUT");
+    }
+
+    #[tokio::test]
+    async fn presigns_urls() {
+        let storage = TestStorage::new();
+        let before = SystemTime::now();
+
+        let url = storage
+            .presign("./weow.txt", PresignOptions::new(Duration::from_secs(900)))
+            .await
+            .unwrap();
+
+        assert_eq!(url.to_string(), "GET memory://weow.txt?expires_in=900");
+        assert!(url.headers.is_empty());
+        assert!(url.expires_at >= before + Duration::from_secs(900));
+
+        let url = storage
+            .presign(
+                "./weow.txt",
+                PresignOptions::new(Duration::from_secs(60)).with_method(PresignMethod::Put),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(url.method, PresignMethod::Put);
+        assert_eq!(url.headers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn decorators_forward_presigning() {
+        let storage = MeteredStorageService::new(TestStorage::new());
+        let url = storage
+            .presign("./weow.txt", PresignOptions::new(Duration::from_secs(900)))
+            .await
+            .unwrap();
+
+        assert_eq!(url.url, "memory://weow.txt?expires_in=900");
+        assert!(storage
+            .presign("./", PresignOptions::new(Duration::from_secs(900)))
+            .await
+            .is_err());
+
+        let stats = storage.stats();
+        assert_eq!((stats.operations, stats.errors), (2, 1));
+    }
+
+    #[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+    #[tokio::test]
+    async fn timeouts_forward_presigning() {
+        let storage = TimeoutStorageService::new(TestStorage::new(), TimeoutPolicy::new(Duration::from_secs(10)));
+        let url = storage
+            .presign("./weow.txt", PresignOptions::new(Duration::from_secs(900)))
+            .await
+            .unwrap();
+
+        assert_eq!(url.url, "memory://weow.txt?expires_in=900");
+    }
+}
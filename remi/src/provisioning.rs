@@ -0,0 +1,122 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fmt::Display;
+
+/// Environment variable that makes [`StorageService::init`][crate::StorageService::init] of every
+/// storage service only verify that its resources exist instead of creating them, which is
+/// useful for locked-down environments where the credentials aren't allowed to create them.
+pub const SKIP_PROVISIONING_ENV: &str = "REMI_SKIP_PROVISIONING";
+
+/// Returns whether storage services should skip creating their resources in
+/// [`StorageService::init`][crate::StorageService::init], which is the case if `configured`
+/// is `true` or if the [`REMI_SKIP_PROVISIONING`][SKIP_PROVISIONING_ENV] environment variable
+/// is set to `1`, `true`, or `yes`.
+pub fn skip_provisioning(configured: bool) -> bool {
+    configured
+        || std::env::var(SKIP_PROVISIONING_ENV)
+            .is_ok_and(|value| matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// Error that storage services return from [`StorageService::init`][crate::StorageService::init]
+/// when provisioning was skipped (see [`skip_provisioning`]) and a resource that the storage
+/// service needs, like a bucket or directory, doesn't exist.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResourceMissing {
+    /// Kind of resource that is missing, like `bucket` or `directory`.
+    pub kind: &'static str,
+
+    /// Name of the resource that is missing.
+    pub name: String,
+}
+
+impl ResourceMissing {
+    /// Creates a new [`ResourceMissing`] error for a resource of `kind` that is called `name`.
+    pub fn new<I: Into<String>>(kind: &'static str, name: I) -> ResourceMissing {
+        ResourceMissing {
+            kind,
+            name: name.into(),
+        }
+    }
+}
+
+impl Display for ResourceMissing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] doesn't exist and provisioning is disabled",
+            self.kind, self.name
+        )
+    }
+}
+
+impl std::error::Error for ResourceMissing {}
+
+#[cfg(test)]
+mod tests {
+    use super::{skip_provisioning, ResourceMissing, SKIP_PROVISIONING_ENV};
+    use crate::{testing::TestStorage, StorageService};
+    use std::{env, io};
+
+    #[tokio::test]
+    async fn verifies_resources_if_provisioning_is_skipped() {
+        let storage = TestStorage::new().with_provisioning(true);
+
+        let error = storage.init().await.unwrap_err();
+        assert_eq!(
+            error.get_ref().and_then(|e| e.downcast_ref::<ResourceMissing>()),
+            Some(&ResourceMissing::new("directory", "uploads"))
+        );
+
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(matches!(crate::Error::from(error), crate::Error::NotFound(_)));
+        assert!(storage.is_empty());
+
+        storage.create_dir("./uploads").await.unwrap();
+        storage.init().await.unwrap();
+    }
+
+    // this is the only test that reads the environment variable, since tests run in parallel
+    #[tokio::test]
+    async fn environment_variable() {
+        env::remove_var(SKIP_PROVISIONING_ENV);
+        assert!(!skip_provisioning(false));
+        assert!(skip_provisioning(true));
+
+        let storage = TestStorage::new().with_provisioning(false);
+        storage.init().await.unwrap();
+        assert!(storage.exists("./uploads").await.unwrap());
+
+        for value in ["1", "true", " YES "] {
+            env::set_var(SKIP_PROVISIONING_ENV, value);
+            assert!(skip_provisioning(false), "{value}");
+            assert!(
+                TestStorage::new().with_provisioning(false).init().await.is_err(),
+                "{value}"
+            );
+        }
+
+        env::set_var(SKIP_PROVISIONING_ENV, "0");
+        assert!(!skip_provisioning(false));
+
+        env::remove_var(SKIP_PROVISIONING_ENV);
+    }
+}
@@ -0,0 +1,44 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+/// Clamps the range of [`StorageService::open_range`][crate::StorageService::open_range] to
+/// a file that is `len` bytes long, which returns an empty range at the end of the file if
+/// `start` is at or after the end of the file.
+///
+/// * since: 0.11.0
+pub fn clamp_range(start: u64, end: Option<u64>, len: u64) -> (u64, u64) {
+    let end = end.map_or(len, |end| end.min(len));
+    (start.min(end), end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clamp_range;
+
+    #[test]
+    fn clamps_to_the_file_length() {
+        assert_eq!(clamp_range(2, Some(5), 10), (2, 5));
+        assert_eq!(clamp_range(2, None, 10), (2, 10));
+        assert_eq!(clamp_range(2, Some(50), 10), (2, 10));
+        assert_eq!(clamp_range(20, None, 10), (10, 10));
+        assert_eq!(clamp_range(5, Some(2), 10), (2, 2));
+    }
+}
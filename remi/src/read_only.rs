@@ -0,0 +1,127 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fmt::Display;
+
+/// Error that storage services return when a mutating operation (like uploading or deleting
+/// a file) is called on a storage service that was configured to be read-only. The operation
+/// is rejected before anything is sent to the storage service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOnly {
+    /// Name of the operation that was rejected, like `upload` or `delete`.
+    pub operation: &'static str,
+}
+
+impl ReadOnly {
+    /// Checks that `operation` can be performed on a storage service, which fails if
+    /// `read_only` is `true`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use remi::ReadOnly;
+    /// #
+    /// assert!(ReadOnly::check(false, "upload").is_ok());
+    /// assert_eq!(ReadOnly::check(true, "upload"), Err(ReadOnly { operation: "upload" }));
+    /// ```
+    pub fn check(read_only: bool, operation: &'static str) -> Result<(), ReadOnly> {
+        match read_only {
+            true => Err(ReadOnly { operation }),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Display for ReadOnly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot {}: storage service is read-only", self.operation)
+    }
+}
+
+impl std::error::Error for ReadOnly {}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadOnly;
+    use crate::{testing::TestStorage, RoutingStorageService, StorageService, UploadRequest};
+    use bytes::Bytes;
+
+    async fn storage(read_only: bool) -> TestStorage {
+        let storage = TestStorage::new();
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        storage.with_read_only(read_only)
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(ReadOnly::check(false, "upload"), Ok(()));
+        assert_eq!(ReadOnly::check(true, "upload"), Err(ReadOnly { operation: "upload" }));
+        assert_eq!(
+            ReadOnly { operation: "upload" }.to_string(),
+            "cannot upload: storage service is read-only"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_mutations() {
+        let storage = storage(true).await;
+        assert!(storage.is_read_only());
+
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        let error = storage
+            .upload("./weow.txt", UploadRequest::default().with_data("fluff"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.get_ref().and_then(|e| e.downcast_ref::<ReadOnly>()),
+            Some(&ReadOnly { operation: "upload" })
+        );
+
+        assert!(matches!(crate::Error::from(error), crate::Error::PermissionDenied(_)));
+        assert!(storage.copy("./weow.txt", "./fluff.txt").await.is_err());
+        assert!(storage.rename("./weow.txt", "./fluff.txt").await.is_err());
+
+        // the default `delete_prefix` goes through `delete`, which is rejected
+        assert_eq!(storage.delete_prefix("./").await.unwrap().failed, ["weow.txt"]);
+        assert_eq!(storage.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn routing_is_read_only_if_every_route_is() {
+        let routing = RoutingStorageService::new()
+            .route("images", storage(true).await)
+            .route("tmp", storage(false).await);
+
+        assert!(!routing.is_read_only());
+        assert!(RoutingStorageService::new()
+            .route("", storage(true).await)
+            .is_read_only());
+        assert!(!RoutingStorageService::<TestStorage>::new().is_read_only());
+    }
+}
@@ -0,0 +1,444 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, rt, Blob, CollisionStrategy, ContentStream, DeleteSummary, HealthReport, ListBlobsRequest, Page,
+    PresignOptions, PresignableStorageService, PresignedUrl, ServiceInfo, Stats, StorageService, UploadRequest,
+    ValidationReport,
+};
+use bytes::Bytes;
+use std::{
+    borrow::Cow,
+    collections::hash_map::RandomState,
+    fmt::{self, Debug, Display},
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    io,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Describes how many times, and how long to wait between, retries of an operation that
+/// failed with a retryable error.
+///
+/// The delay before the `n`th retry is [`initial_backoff`][RetryPolicy::initial_backoff]
+/// doubled `n - 1` times, capped at [`max_backoff`][RetryPolicy::max_backoff]. With
+/// [`jitter`][RetryPolicy::jitter], a random delay between half and all of it is used
+/// instead, so that clients that failed at the same time don't retry at the same time.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum amount of attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+
+    /// Maximum delay between two attempts.
+    pub max_backoff: Duration,
+
+    /// Whether if delays are randomized.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Sets the maximum amount of attempts, including the first one.
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the maximum delay between two attempts.
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Sets whether if delays are randomized.
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns how long to wait before the `retry`th retry, starting at `1`.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .checked_mul(1 << retry.saturating_sub(1).min(31))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        if !self.jitter {
+            return backoff;
+        }
+
+        // `RandomState` is seeded randomly, which is enough for spreading out retries
+        let random = RandomState::new().build_hasher().finish();
+        let half = backoff / 2;
+
+        half + half.mul_f64((random >> 11) as f64 / (1u64 << 53) as f64)
+    }
+}
+
+/// Returns `true` if an [`io::Error`] is likely to go away when the operation is retried, like
+/// timeouts and connections that were reset. This can be used as the classifier of a
+/// [`RetryingStorageService`] that wraps a storage service whose error is an [`io::Error`].
+///
+/// * since: 0.11.0
+pub fn is_transient_io_error(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+type Classifier<E> = Arc<dyn Fn(&E) -> bool + Send + Sync>;
+
+/// A [`StorageService`] that retries the operations of the storage service it wraps when they
+/// fail with an error that the classifier considers retryable, waiting between attempts as
+/// described by a [`RetryPolicy`].
+///
+/// Operations that can't be safely repeated aren't retried, since the first attempt could have
+/// succeeded even if it returned an error (i.e, because the response timed out):
+///
+/// - [`rename`][StorageService::rename], which would return `false` when it is repeated.
+/// - [`create_exclusive`][StorageService::create_exclusive], which would fail with
+///   [`AlreadyExists`][crate::AlreadyExists].
+/// - [`upload`][StorageService::upload] with a [`CollisionStrategy`] other than
+///   [`Overwrite`][CollisionStrategy::Overwrite], which could fail or upload the file twice.
+///
+/// Streams returned from [`open_stream`][StorageService::open_stream] are opened with retries,
+/// but reading from them isn't retried.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{RetryPolicy, RetryingStorageService};
+/// #
+/// let storage = RetryingStorageService::new(storage, RetryPolicy::default(), remi_s3::Error::is_retryable);
+/// storage.open("./weow.txt").await?;
+/// ```
+///
+/// * since: 0.11.0
+pub struct RetryingStorageService<S: StorageService> {
+    inner: S,
+    policy: RetryPolicy,
+    classifier: Classifier<S::Error>,
+}
+
+impl<S: StorageService + Clone> Clone for RetryingStorageService<S> {
+    fn clone(&self) -> Self {
+        RetryingStorageService {
+            inner: self.inner.clone(),
+            policy: self.policy,
+            classifier: self.classifier.clone(),
+        }
+    }
+}
+
+impl<S: StorageService + Debug> Debug for RetryingStorageService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryingStorageService")
+            .field("inner", &self.inner)
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: StorageService> RetryingStorageService<S> {
+    /// Creates a new [`RetryingStorageService`] that retries the operations of `inner` which
+    /// fail with an error that `classifier` returns `true` for.
+    pub fn new<F>(inner: S, policy: RetryPolicy, classifier: F) -> RetryingStorageService<S>
+    where
+        F: Fn(&S::Error) -> bool + Send + Sync + 'static,
+    {
+        RetryingStorageService {
+            inner,
+            policy,
+            classifier: Arc::new(classifier),
+        }
+    }
+
+    /// Returns the storage service whose operations are retried.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the [`RetryPolicy`] of this storage service.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    async fn retry<T, F, Fut>(&self, operation: &'static str, mut f: F) -> Result<T, S::Error>
+    where
+        F: FnMut() -> Fut + Send,
+        Fut: Future<Output = Result<T, S::Error>> + Send,
+    {
+        let mut retry = 1;
+        loop {
+            // the error is dropped before sleeping, since it doesn't have to be `Send`
+            match f().await {
+                Err(e) if retry < self.policy.max_attempts && (self.classifier)(&e) => {}
+                result => return result,
+            }
+
+            let backoff = self.policy.backoff(retry);
+
+            #[cfg(feature = "tracing")]
+            ::tracing::warn!(
+                remi.service = %self.inner.name(),
+                operation,
+                retry,
+                backoff.ms = backoff.as_millis() as u64,
+                "retrying operation after a retryable error"
+            );
+
+            #[cfg(feature = "log")]
+            ::log::warn!(
+                "retrying operation [{operation}] on [{}] in {}ms (retry #{retry})",
+                self.inner.name(),
+                backoff.as_millis()
+            );
+
+            #[cfg(not(any(feature = "tracing", feature = "log")))]
+            let _ = operation;
+
+            rt::sleep(backoff).await;
+            retry += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl<S: StorageService> StorageService for RetryingStorageService<S> {
+    type Error = S::Error;
+
+    fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        self.retry("init", || self.inner.init()).await
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        let path = path.as_ref();
+        self.retry("open", || self.inner.open(path)).await
+    }
+
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        let path = path.as_ref();
+        self.retry("open_range", || self.inner.open_range(path, start, end))
+            .await
+    }
+
+    async fn open_stream<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+    ) -> Result<Option<ContentStream<Self::Error>>, Self::Error>
+    where
+        Self::Error: Send + 'static,
+    {
+        let path = path.as_ref();
+        self.retry("open_stream", || self.inner.open_stream(path)).await
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        let path = path.as_ref();
+        self.retry("blob", || self.inner.blob(path)).await
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        let path = path.as_ref().map(AsRef::as_ref);
+        self.retry("blobs", || self.inner.blobs(path, options.clone())).await
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        let path = path.as_ref().map(AsRef::as_ref);
+        self.retry("blobs_page", || self.inner.blobs_page(path, options.clone()))
+            .await
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        let path = path.as_ref();
+        self.retry("delete", || self.inner.delete(path)).await
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        let prefix = prefix.as_ref();
+        self.retry("delete_prefix", || self.inner.delete_prefix(prefix)).await
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        self.retry("copy", || self.inner.copy(src, dst)).await
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        self.inner.rename(from, to).await
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        let path = path.as_ref();
+        self.retry("exists", || self.inner.exists(path)).await
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        if !matches!(options.collision(), None | Some(CollisionStrategy::Overwrite)) {
+            return self.inner.upload(path, options).await;
+        }
+
+        let path = path.as_ref();
+        self.retry("upload", || self.inner.upload(path, options.clone())).await
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        self.inner.create_exclusive(path, options).await
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        let path = path.as_ref();
+        self.retry("create_dir", || self.inner.create_dir(path)).await
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.inner.is_read_only()
+    }
+
+    fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    fn info(&self) -> ServiceInfo {
+        self.inner.info()
+    }
+
+    async fn debug_validate(&self) -> ValidationReport
+    where
+        Self::Error: Display + Send,
+    {
+        self.inner.debug_validate().await
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        self.retry("verify_credentials", || self.inner.verify_credentials())
+            .await
+    }
+
+    async fn healthcheck(&self) -> HealthReport
+    where
+        Self::Error: Display + Send,
+    {
+        self.inner.healthcheck().await
+    }
+}
+
+#[async_trait]
+impl<S: PresignableStorageService> PresignableStorageService for RetryingStorageService<S> {
+    async fn presign<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: PresignOptions,
+    ) -> Result<PresignedUrl, Self::Error> {
+        self.inner.presign(path, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_transient_io_error, RetryPolicy};
+    use std::{io, time::Duration};
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::default()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_secs(1))
+            .with_jitter(false);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+
+        let policy = policy.with_jitter(true);
+        for retry in 1..10 {
+            let backoff = policy.backoff(retry);
+            let max = policy.with_jitter(false).backoff(retry);
+
+            assert!(backoff >= max / 2 && backoff <= max, "{backoff:?} for retry #{retry}");
+        }
+    }
+
+    #[test]
+    fn transient_io_errors() {
+        assert!(is_transient_io_error(&io::Error::from(io::ErrorKind::TimedOut)));
+        assert!(is_transient_io_error(&io::Error::from(io::ErrorKind::ConnectionReset)));
+        assert!(!is_transient_io_error(&io::Error::from(io::ErrorKind::NotFound)));
+        assert!(!is_transient_io_error(&io::Error::from(
+            io::ErrorKind::PermissionDenied
+        )));
+    }
+}
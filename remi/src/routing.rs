@@ -0,0 +1,558 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, Blob, DeleteSummary, ListBlobsRequest, Page, ServiceInfo, Stats, StorageService, UploadRequest,
+};
+use bytes::Bytes;
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Display},
+    path::Path,
+};
+
+/// Error from a [`RoutingStorageService`].
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub enum RoutingError<E> {
+    /// The storage service that the path was routed to returned an error.
+    Storage(E),
+
+    /// No route matched the path, and there is no route with an empty prefix to fall back to.
+    NoRoute(String),
+}
+
+impl<E: Display> Display for RoutingError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingError::Storage(e) => Display::fmt(e, f),
+            RoutingError::NoRoute(path) => write!(f, "no storage service was routed for path [{path}]"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for RoutingError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RoutingError::Storage(e) => Some(e),
+            RoutingError::NoRoute(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Route<S> {
+    prefix: String,
+    rewrite: Option<String>,
+    service: S,
+}
+
+impl<S> Route<S> {
+    /// Returns the path that `path` (whose [`key`] is `key`) has in this route's storage
+    /// service. A leading `./` or `/` is kept, since some storage services (like the local
+    /// filesystem) resolve paths differently without it, and the prefix itself is rewritten
+    /// to the root of the storage service (`./`) if `rewrite` is empty.
+    fn rewrite(&self, path: &str, key: &str) -> String {
+        let Some(rewrite) = &self.rewrite else {
+            return path.to_owned();
+        };
+
+        let lead = &path[..path.len() - path.trim_start_matches("./").trim_start_matches('/').len()];
+        let rest = key[self.prefix.len()..].trim_start_matches('/');
+        match (rewrite.is_empty(), rest.is_empty()) {
+            (true, true) if lead.is_empty() => String::from("./"),
+            (true, true) => lead.to_owned(),
+            (true, false) => format!("{lead}{rest}"),
+            (false, true) => format!("{lead}{rewrite}"),
+            (false, false) => format!("{lead}{rewrite}/{rest}"),
+        }
+    }
+}
+
+/// A [`StorageService`] that sends every operation to one of many storage services,
+/// depending on which route's prefix the path starts with, so that one storage service
+/// can front multiple stores (e.g. `images/` to Amazon S3 and `tmp/` to the local
+/// filesystem).
+///
+/// Prefixes match whole path segments, and the route with the longest matching prefix
+/// is used; a route with an empty prefix matches every path and is used as the default.
+/// Paths are passed to the storage service of a route as-is, unless the route was added
+/// with [`route_with_rewrite`][RoutingStorageService::route_with_rewrite], which replaces
+/// the prefix of paths first.
+///
+/// Copying and renaming files between two routes opens the file in one storage service
+/// and uploads it to the other. Listings without a path use the default route, and the
+/// paths of listed files are what the route's storage service returned.
+///
+/// Every route has the same type of storage service, so storage services of different
+/// types can be routed to as an [`Arc<dyn DynStorageService<Error = BoxError>>`][crate::DynStorageService].
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::RoutingStorageService;
+/// #
+/// let storage = RoutingStorageService::new()
+///     .route("images", s3)
+///     .route_with_rewrite("tmp", "scratch", fs);
+///
+/// // uploaded to `scratch/upload.bin` in the filesystem
+/// storage.upload("./tmp/upload.bin", UploadRequest::default().with_data("weow")).await?;
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct RoutingStorageService<S> {
+    routes: Vec<Route<S>>,
+}
+
+impl<S> Default for RoutingStorageService<S> {
+    fn default() -> Self {
+        RoutingStorageService { routes: Vec::new() }
+    }
+}
+
+impl<S> RoutingStorageService<S> {
+    /// Creates a new [`RoutingStorageService`] without any routes.
+    pub fn new() -> RoutingStorageService<S> {
+        RoutingStorageService::default()
+    }
+
+    /// Routes every path that starts with `prefix` to `service`, which receives the path as-is.
+    pub fn route<P: AsRef<str>>(mut self, prefix: P, service: S) -> Self {
+        self.routes.push(Route {
+            prefix: key(prefix.as_ref()),
+            rewrite: None,
+            service,
+        });
+
+        self
+    }
+
+    /// Routes every path that starts with `prefix` to `service`, which receives the path with
+    /// `prefix` replaced by `rewrite`. An empty `rewrite` removes the prefix from paths.
+    pub fn route_with_rewrite<P: AsRef<str>, R: AsRef<str>>(mut self, prefix: P, rewrite: R, service: S) -> Self {
+        self.routes.push(Route {
+            prefix: key(prefix.as_ref()),
+            rewrite: Some(key(rewrite.as_ref())),
+            service,
+        });
+
+        self
+    }
+
+    /// Returns the storage service that `path` is routed to, and the path that it has in it.
+    pub fn resolve<P: AsRef<Path>>(&self, path: P) -> Option<(&S, String)> {
+        self.select(path.as_ref()).map(|(route, path)| (&route.service, path))
+    }
+
+    /// Returns the storage services of every route, in the order that they were added.
+    pub fn services(&self) -> impl Iterator<Item = &S> {
+        self.routes.iter().map(|route| &route.service)
+    }
+
+    fn select(&self, path: &Path) -> Option<(&Route<S>, String)> {
+        let path = path.to_string_lossy();
+        let key = key(&path);
+        self.routes
+            .iter()
+            .filter(|route| {
+                route.prefix.is_empty()
+                    || key
+                        .strip_prefix(&*route.prefix)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|route| route.prefix.len())
+            .map(|route| (route, route.rewrite(&path, &key)))
+    }
+}
+
+impl<S: StorageService> RoutingStorageService<S> {
+    fn find(&self, path: &Path) -> Result<(&Route<S>, String), RoutingError<S::Error>> {
+        self.select(path)
+            .ok_or_else(|| RoutingError::NoRoute(path.display().to_string()))
+    }
+
+    /// Same as [`find`][RoutingStorageService::find], but for listings, which list the
+    /// root of the route's storage service if the rewritten path is its root.
+    fn find_dir<P: AsRef<Path>>(&self, path: Option<P>) -> Result<(&S, Option<String>), RoutingError<S::Error>> {
+        let (route, path) = match path {
+            Some(path) => self.find(path.as_ref())?,
+            None => self.find(Path::new(""))?,
+        };
+
+        Ok((&route.service, Some(path).filter(|path| !key(path).is_empty())))
+    }
+}
+
+/// Returns `path` without leading `./` or `/` and trailing `/`, which is how prefixes
+/// and paths are compared.
+fn key(path: &str) -> String {
+    path.trim_start_matches("./")
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .to_owned()
+}
+
+#[async_trait]
+impl<S: StorageService> StorageService for RoutingStorageService<S>
+where
+    S::Error: Send,
+{
+    type Error = RoutingError<S::Error>;
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("remi:routing")
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        for service in self.services() {
+            service.init().await.map_err(RoutingError::Storage)?;
+        }
+
+        Ok(())
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        let (route, path) = self.find(path.as_ref())?;
+        let service = &route.service;
+        service.open(path).await.map_err(RoutingError::Storage)
+    }
+
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        let (route, path) = self.find(path.as_ref())?;
+        let service = &route.service;
+        service
+            .open_range(path, start, end)
+            .await
+            .map_err(RoutingError::Storage)
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        let (route, path) = self.find(path.as_ref())?;
+        let service = &route.service;
+        service.blob(path).await.map_err(RoutingError::Storage)
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        let (service, path) = self.find_dir(path)?;
+        service.blobs(path, options).await.map_err(RoutingError::Storage)
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        let (service, path) = self.find_dir(path)?;
+        service.blobs_page(path, options).await.map_err(RoutingError::Storage)
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        let (route, path) = self.find(path.as_ref())?;
+        let service = &route.service;
+        service.delete(path).await.map_err(RoutingError::Storage)
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        let (route, prefix) = self.find(prefix.as_ref())?;
+        let service = &route.service;
+        service.delete_prefix(prefix).await.map_err(RoutingError::Storage)
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        let (from, src) = self.find(src.as_ref())?;
+        let (to, dst) = self.find(dst.as_ref())?;
+        if from.prefix == to.prefix {
+            return from.service.copy(src, dst).await.map_err(RoutingError::Storage);
+        }
+
+        // the file is uploaded as-is so its content type and metadata are kept
+        let Some(Blob::File(file)) = from.service.blob(&src).await.map_err(RoutingError::Storage)? else {
+            return Ok(false);
+        };
+
+        to.service
+            .upload(dst, UploadRequest::from_file(&file))
+            .await
+            .map_err(RoutingError::Storage)?;
+
+        Ok(true)
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        let (from, src) = self.find(from.as_ref())?;
+        let (to, dst) = self.find(to.as_ref())?;
+        if from.prefix == to.prefix {
+            return from.service.rename(src, dst).await.map_err(RoutingError::Storage);
+        }
+
+        // the file is uploaded as-is so its content type and metadata are kept
+        let Some(Blob::File(file)) = from.service.blob(&src).await.map_err(RoutingError::Storage)? else {
+            return Ok(false);
+        };
+
+        to.service
+            .upload(dst, UploadRequest::from_file(&file))
+            .await
+            .map_err(RoutingError::Storage)?;
+
+        from.service.delete(src).await.map_err(RoutingError::Storage)?;
+        Ok(true)
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        let (route, path) = self.find(path.as_ref())?;
+        let service = &route.service;
+        service.exists(path).await.map_err(RoutingError::Storage)
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        let (route, path) = self.find(path.as_ref())?;
+        let service = &route.service;
+        service.upload(path, options).await.map_err(RoutingError::Storage)
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        let (route, path) = self.find(path.as_ref())?;
+        let service = &route.service;
+        service
+            .create_exclusive(path, options)
+            .await
+            .map_err(RoutingError::Storage)
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        let (route, path) = self.find(path.as_ref())?;
+        let service = &route.service;
+        service.create_dir(path).await.map_err(RoutingError::Storage)
+    }
+
+    fn is_read_only(&self) -> bool {
+        !self.routes.is_empty() && self.services().all(StorageService::is_read_only)
+    }
+
+    fn stats(&self) -> Stats {
+        self.services().fold(Stats::default(), |total, service| {
+            let stats = service.stats();
+            Stats {
+                operations: total.operations + stats.operations,
+                errors: total.errors + stats.errors,
+                bytes_read: total.bytes_read + stats.bytes_read,
+                bytes_written: total.bytes_written + stats.bytes_written,
+                cache_hits: total.cache_hits + stats.cache_hits,
+                cache_misses: total.cache_misses + stats.cache_misses,
+                list_requests: total.list_requests + stats.list_requests,
+            }
+        })
+    }
+
+    fn info(&self) -> ServiceInfo {
+        ServiceInfo::new(self.name())
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        for service in self.services() {
+            service.verify_credentials().await.map_err(RoutingError::Storage)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RoutingError, RoutingStorageService};
+    use crate::{testing::TestStorage, Blob, StorageService, UploadRequest};
+    use bytes::Bytes;
+    use std::collections::HashMap;
+
+    fn routes() -> RoutingStorageService<u8> {
+        RoutingStorageService::default()
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let routing = routes().route("", 0).route("images", 1).route("./images/avatars/", 2);
+
+        let resolve = |path| routing.resolve(path).map(|(service, path)| (*service, path));
+        assert_eq!(resolve("./images/a.png"), Some((1, String::from("./images/a.png"))));
+        assert_eq!(
+            resolve("/images/avatars/a.png"),
+            Some((2, String::from("/images/avatars/a.png")))
+        );
+        assert_eq!(resolve("imagesweow/a.png"), Some((0, String::from("imagesweow/a.png"))));
+        assert_eq!(resolve("weow.txt"), Some((0, String::from("weow.txt"))));
+    }
+
+    #[test]
+    fn rewrites_prefixes() {
+        let routing = routes()
+            .route_with_rewrite("tmp", "scratch/uploads", 0)
+            .route_with_rewrite("images/", "", 1);
+
+        let resolve = |path| routing.resolve(path).map(|(_, path)| path);
+        assert_eq!(
+            resolve("./tmp/upload.bin"),
+            Some(String::from("./scratch/uploads/upload.bin"))
+        );
+        assert_eq!(resolve("tmp"), Some(String::from("scratch/uploads")));
+        assert_eq!(resolve("images/a.png"), Some(String::from("a.png")));
+        assert_eq!(resolve("./images"), Some(String::from("./")));
+        assert_eq!(resolve("/images/"), Some(String::from("/")));
+        assert_eq!(resolve("images"), Some(String::from("./")));
+        assert_eq!(resolve("weow.txt"), None);
+    }
+
+    fn request(data: &'static str) -> UploadRequest {
+        UploadRequest::default().with_data(data)
+    }
+
+    fn names(blobs: Vec<Blob>) -> Vec<String> {
+        blobs
+            .into_iter()
+            .map(|blob| match blob {
+                Blob::File(file) => file.name,
+                Blob::Directory(dir) => dir.name,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn routes_to_the_longest_prefix() {
+        let (default, images, tmp) = (TestStorage::new(), TestStorage::new(), TestStorage::new());
+        let storage = RoutingStorageService::new()
+            .route("", default.clone())
+            .route("images", images.clone())
+            .route_with_rewrite("tmp", "scratch", tmp.clone());
+
+        storage.upload("./weow.txt", request("weow")).await.unwrap();
+        storage.upload("./images/a.png", request("png")).await.unwrap();
+        storage.upload("./tmp/upload.bin", request("bin")).await.unwrap();
+
+        assert!(default.exists("./weow.txt").await.unwrap());
+        assert!(images.exists("./images/a.png").await.unwrap());
+        assert!(tmp.exists("./scratch/upload.bin").await.unwrap());
+        assert_eq!((default.len(), images.len(), tmp.len()), (1, 1, 1));
+
+        assert_eq!(
+            storage.open("./tmp/upload.bin").await.unwrap(),
+            Some(Bytes::from_static(b"bin"))
+        );
+    }
+
+    #[tokio::test]
+    async fn prefixes_rewritten_to_the_root() {
+        let images = TestStorage::new();
+        let storage = RoutingStorageService::new().route_with_rewrite("images", "", images.clone());
+
+        storage.upload("./images/a.png", request("a")).await.unwrap();
+        storage.upload("./images/avatars/b.png", request("b")).await.unwrap();
+        assert!(images.exists("./a.png").await.unwrap());
+
+        for path in ["./images", "images", "/images/"] {
+            let mut listed = names(storage.blobs(Some(path), None).await.unwrap());
+            listed.sort();
+
+            assert_eq!(listed, ["a.png"], "{path}");
+        }
+
+        // the prefix itself is the root of the storage service, which isn't a file
+        assert_eq!(
+            storage.resolve("./images").map(|(_, path)| path),
+            Some(String::from("./"))
+        );
+
+        assert!(storage.exists("./images").await.unwrap());
+        assert!(matches!(
+            storage.upload("./images", request("weow")).await,
+            Err(RoutingError::Storage(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn copies_and_renames_between_routes() {
+        let (images, tmp) = (TestStorage::new(), TestStorage::new());
+        let storage = RoutingStorageService::new()
+            .route("images", images.clone())
+            .route("tmp", tmp.clone());
+
+        let metadata = HashMap::from([(String::from("owner"), String::from("noel"))]);
+        storage
+            .upload(
+                "./tmp/a.png",
+                request("a")
+                    .with_content_type(Some("image/png"))
+                    .with_metadata(metadata.clone()),
+            )
+            .await
+            .unwrap();
+
+        assert!(storage.copy("./tmp/a.png", "./images/a.png").await.unwrap());
+        assert!(storage.rename("./tmp/a.png", "./images/b.png").await.unwrap());
+        assert!(!storage.copy("./tmp/a.png", "./images/c.png").await.unwrap());
+
+        assert!(tmp.is_empty());
+        assert_eq!(
+            images.open("./images/b.png").await.unwrap(),
+            Some(Bytes::from_static(b"a"))
+        );
+        assert_eq!(images.len(), 2);
+
+        for path in ["./images/a.png", "./images/b.png"] {
+            let Some(Blob::File(file)) = images.blob(path).await.unwrap() else {
+                panic!("expected a file in {path}");
+            };
+
+            assert_eq!(file.content_type.as_deref(), Some("image/png"));
+            assert_eq!(file.metadata, metadata);
+        }
+    }
+
+    #[tokio::test]
+    async fn paths_without_a_route() {
+        let storage = RoutingStorageService::new().route("images", TestStorage::new());
+
+        assert!(matches!(
+            storage.upload("./weow.txt", request("weow")).await,
+            Err(RoutingError::NoRoute(path)) if path == "./weow.txt"
+        ));
+
+        assert!(matches!(
+            storage.blobs(None::<&str>, None).await,
+            Err(RoutingError::NoRoute(_))
+        ));
+    }
+}
@@ -0,0 +1,229 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Small abstraction over the async runtime that storage services and decorators run on,
+//! so that crates like **remi-fs** aren't tied to Tokio.
+//!
+//! The runtime is picked with the `rt-tokio` or `rt-async-std` features. If both are
+//! enabled (which usually happens through feature unification), Tokio is used inside of
+//! a Tokio runtime and async-std everywhere else. If only `rt-tokio` is enabled, calling
+//! these functions outside of a Tokio runtime panics with a message that says which
+//! feature is missing.
+//!
+//! Every task is spawned with a name (like `remi::rt::fs::read`) that shows which part of
+//! remi owns it. With the `tokio-console` feature and `--cfg tokio_unstable`, the names
+//! are visible in [tokio-console](https://github.com/tokio-rs/console).
+//!
+//! * since: 0.11.0
+
+use std::{future::Future, time::Duration};
+
+/// Returns whether if the caller runs inside of a Tokio runtime, which is always expected to
+/// be the case if async-std can't be used instead.
+#[cfg(feature = "rt-tokio")]
+fn on_tokio() -> bool {
+    let on_tokio = tokio::runtime::Handle::try_current().is_ok();
+
+    #[cfg(not(feature = "rt-async-std"))]
+    assert!(
+        on_tokio,
+        "remi was called outside of a Tokio runtime, but only the `rt-tokio` feature is enabled; enable the \
+         `rt-async-std` feature (or the `async-std` feature of the storage service) to run on async-std"
+    );
+
+    on_tokio
+}
+
+/// Runs the blocking function `f` on the runtime's thread pool for blocking work and
+/// returns its result. Panics inside of `f` are propagated to the caller.
+pub async fn spawn_blocking<F, T>(name: &str, f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let _ = name;
+
+    #[cfg(feature = "rt-tokio")]
+    if on_tokio() {
+        #[cfg(all(feature = "tokio-console", tokio_unstable))]
+        let handle = tokio::task::Builder::new()
+            .name(name)
+            .spawn_blocking(f)
+            .expect("blocking task to be spawned");
+
+        #[cfg(not(all(feature = "tokio-console", tokio_unstable)))]
+        let handle = tokio::task::spawn_blocking(f);
+
+        return match handle.await {
+            Ok(value) => value,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        };
+    }
+
+    #[cfg(feature = "rt-async-std")]
+    {
+        async_std::task::spawn_blocking(f).await
+    }
+
+    #[cfg(not(feature = "rt-async-std"))]
+    unreachable!("`on_tokio` panics outside of a Tokio runtime")
+}
+
+/// Spawns `future` in the background. The task is detached, so its output is discarded.
+// the early return is only needed if async-std can be used as well
+#[cfg_attr(not(feature = "rt-async-std"), allow(clippy::needless_return))]
+pub fn spawn<F>(name: &str, future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let _ = name;
+
+    #[cfg(feature = "rt-tokio")]
+    if on_tokio() {
+        #[cfg(all(feature = "tokio-console", tokio_unstable))]
+        tokio::task::Builder::new()
+            .name(name)
+            .spawn(future)
+            .expect("task to be spawned");
+
+        #[cfg(not(all(feature = "tokio-console", tokio_unstable)))]
+        tokio::spawn(future);
+
+        return;
+    }
+
+    #[cfg(feature = "rt-async-std")]
+    async_std::task::Builder::new()
+        .name(name.to_owned())
+        .spawn(future)
+        .expect("task to be spawned");
+}
+
+/// Waits until `duration` has elapsed.
+// the early return is only needed if async-std can be used as well
+#[cfg_attr(not(feature = "rt-async-std"), allow(clippy::needless_return))]
+pub async fn sleep(duration: Duration) {
+    #[cfg(feature = "rt-tokio")]
+    if on_tokio() {
+        return tokio::time::sleep(duration).await;
+    }
+
+    #[cfg(feature = "rt-async-std")]
+    async_std::task::sleep(duration).await;
+}
+
+/// Waits for `future` to complete for at most `duration`, returning `None` if it didn't
+/// complete in time. The future is dropped when the time runs out.
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    #[cfg(feature = "rt-tokio")]
+    if on_tokio() {
+        return tokio::time::timeout(duration, future).await.ok();
+    }
+
+    #[cfg(feature = "rt-async-std")]
+    {
+        async_std::future::timeout(duration, future).await.ok()
+    }
+
+    #[cfg(not(feature = "rt-async-std"))]
+    unreachable!("`on_tokio` panics outside of a Tokio runtime")
+}
+
+/// Asynchronous versions of the [`std::fs`] functions, which run on the runtime's
+/// thread pool for blocking work.
+pub mod fs {
+    use super::spawn_blocking;
+    use std::{
+        fs::{DirEntry, Metadata},
+        io,
+        path::{Path, PathBuf},
+    };
+
+    /// Reads the entire contents of a file. See [`std::fs::read`].
+    pub async fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::read", move || std::fs::read(path)).await
+    }
+
+    /// Reads the entire contents of a file into a string. See [`std::fs::read_to_string`].
+    pub async fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::read_to_string", move || std::fs::read_to_string(path)).await
+    }
+
+    /// Writes `contents` as the entire contents of a file. See [`std::fs::write`].
+    pub async fn write<P: AsRef<Path>, C: AsRef<[u8]> + Send + 'static>(path: P, contents: C) -> io::Result<()> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::write", move || std::fs::write(path, contents)).await
+    }
+
+    /// Recursively creates a directory and all of its parents. See [`std::fs::create_dir_all`].
+    pub async fn create_dir_all<P: AsRef<Path>>(path: P) -> io::Result<()> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::create_dir_all", move || std::fs::create_dir_all(path)).await
+    }
+
+    /// Removes a file. See [`std::fs::remove_file`].
+    pub async fn remove_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::remove_file", move || std::fs::remove_file(path)).await
+    }
+
+    /// Removes an empty directory. See [`std::fs::remove_dir`].
+    pub async fn remove_dir<P: AsRef<Path>>(path: P) -> io::Result<()> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::remove_dir", move || std::fs::remove_dir(path)).await
+    }
+
+    /// Renames a file or directory, replacing `to` if it exists. See [`std::fs::rename`].
+    pub async fn rename<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()> {
+        let (from, to) = (from.as_ref().to_owned(), to.as_ref().to_owned());
+        spawn_blocking("remi::rt::fs::rename", move || std::fs::rename(from, to)).await
+    }
+
+    /// Returns the metadata of a path, following symbolic links. See [`std::fs::metadata`].
+    pub async fn metadata<P: AsRef<Path>>(path: P) -> io::Result<Metadata> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::metadata", move || std::fs::metadata(path)).await
+    }
+
+    /// Returns the metadata of a path without following symbolic links. See
+    /// [`std::fs::symlink_metadata`].
+    pub async fn symlink_metadata<P: AsRef<Path>>(path: P) -> io::Result<Metadata> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::symlink_metadata", move || {
+            std::fs::symlink_metadata(path)
+        })
+        .await
+    }
+
+    /// Returns `Ok(true)` if the path points to an existing entity. See [`Path::try_exists`].
+    pub async fn try_exists<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+        let path = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::try_exists", move || path.try_exists()).await
+    }
+
+    /// Returns every entry in a directory. See [`std::fs::read_dir`].
+    pub async fn read_dir<P: AsRef<Path>>(path: P) -> io::Result<Vec<DirEntry>> {
+        let path: PathBuf = path.as_ref().to_owned();
+        spawn_blocking("remi::rt::fs::read_dir", move || std::fs::read_dir(path)?.collect()).await
+    }
+}
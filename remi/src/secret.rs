@@ -0,0 +1,187 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    cell::Cell,
+    fmt::{Debug, Display},
+};
+
+thread_local! {
+    static REVEAL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Value that shouldn't end up in logs, like an access key or a token for a storage service.
+///
+/// A [`Secret`] is printed as `[REDACTED]` by its [`Debug`] and [`Display`] implementations
+/// and serialized as `[REDACTED]` with **serde**, so configuration structs that derive them
+/// can be logged without leaking credentials. The value itself is only available through
+/// [`Secret::expose`], or serialized as-is inside of [`Secret::reveal`].
+///
+/// Comparing two secrets takes the same amount of time no matter where they differ, so
+/// that comparing a credential against a known one doesn't leak how much of it matched.
+///
+/// ## Example
+/// ```rust
+/// # use remi::Secret;
+/// #
+/// let secret = Secret::from("hunter2");
+/// assert_eq!(format!("{secret:?}"), "[REDACTED]");
+/// assert_eq!(secret.expose(), "hunter2");
+/// assert_eq!(secret, Secret::from("hunter2"));
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Clone, Default)]
+pub struct Secret<T = String>(T);
+
+impl<T> Secret<T> {
+    /// Wraps `value` in a [`Secret`].
+    pub const fn new(value: T) -> Secret<T> {
+        Secret(value)
+    }
+
+    /// Returns the value of this secret. Be careful with where this ends up.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Returns the value of this secret, consuming it.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    /// Runs `f` with secrets being serialized as their actual value instead of `[REDACTED]`
+    /// on the current thread, which is useful for writing a configuration file back out.
+    ///
+    /// ## Example
+    /// ```rust,ignore
+    /// # use remi::Secret;
+    /// #
+    /// let secret = Secret::from("hunter2");
+    /// assert_eq!(serde_json::to_string(&secret).unwrap(), "\"[REDACTED]\"");
+    /// assert_eq!(Secret::<String>::reveal(|| serde_json::to_string(&secret)).unwrap(), "\"hunter2\"");
+    /// ```
+    pub fn reveal<R, F: FnOnce() -> R>(f: F) -> R {
+        struct Reset(bool);
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                REVEAL.with(|reveal| reveal.set(self.0));
+            }
+        }
+
+        let _reset = Reset(REVEAL.with(|reveal| reveal.replace(true)));
+        f()
+    }
+}
+
+impl<T> Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<T> Display for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl From<String> for Secret<String> {
+    fn from(value: String) -> Self {
+        Secret(value)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(value: &str) -> Self {
+        Secret(value.to_owned())
+    }
+}
+
+impl<T: AsRef<[u8]>> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(self.0.as_ref(), other.0.as_ref())
+    }
+}
+
+impl<T: AsRef<[u8]>> Eq for Secret<T> {}
+
+/// Compares `a` and `b` in time that only depends on their lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a.iter().zip(b).fold(0u8, |diff, (a, b)| diff | (a ^ b));
+
+    // keeps the compiler from short-circuiting the comparison
+    std::hint::black_box(diff) == 0
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Secret<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match REVEAL.with(Cell::get) {
+            true => self.0.serialize(serializer),
+            false => serializer.serialize_str("[REDACTED]"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Secret<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{constant_time_eq, Secret};
+
+    #[test]
+    fn redacted() {
+        let secret = Secret::from("hunter2");
+        assert_eq!(format!("{secret:?}"), "[REDACTED]");
+        assert_eq!(secret.to_string(), "[REDACTED]");
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[test]
+    fn compare() {
+        assert!(constant_time_eq(b"hunter2", b"hunter2"));
+        assert!(!constant_time_eq(b"hunter2", b"hunter3"));
+        assert!(!constant_time_eq(b"hunter2", b"hunter22"));
+        assert_ne!(Secret::from("a"), Secret::from("b"));
+    }
+
+    #[test]
+    fn reveal_is_scoped() {
+        let revealed = Secret::<String>::reveal(|| {
+            assert!(super::REVEAL.with(|reveal| reveal.get()));
+            Secret::<String>::reveal(|| ());
+            super::REVEAL.with(|reveal| reveal.get())
+        });
+
+        assert!(revealed);
+        assert!(!super::REVEAL.with(|reveal| reveal.get()));
+    }
+}
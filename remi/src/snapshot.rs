@@ -0,0 +1,415 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, diff, Blob, BlobStream, DeleteSummary, File, ListBlobsRequest, Metadata, ReadOnly, ServiceInfo,
+    StorageService, UploadRequest,
+};
+use bytes::Bytes;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+/// Keys and metadata of every file under a prefix at the time that it was [frozen][freeze],
+/// which a [`SnapshotView`] checks reads against.
+///
+/// Keys are relative to the prefix, the same way that [`Diff`][crate::Diff] compares them.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    prefix: String,
+    entries: BTreeMap<String, Metadata>,
+}
+
+impl Manifest {
+    /// Creates an empty [`Manifest`] of the files under `prefix`.
+    pub fn new<I: Into<String>>(prefix: I) -> Manifest {
+        Manifest {
+            prefix: prefix.into(),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Pins `key` (relative to the prefix) to the given [`Metadata`].
+    pub fn insert<K: Into<String>>(&mut self, key: K, metadata: Metadata) {
+        self.entries.insert(key.into(), metadata);
+    }
+
+    /// Returns the prefix that this manifest was captured from.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the [`Metadata`] that `key` was pinned to, if it is part of this manifest.
+    pub fn get(&self, key: &str) -> Option<&Metadata> {
+        self.entries.get(key)
+    }
+
+    /// Returns every key and the [`Metadata`] that it was pinned to, in order of their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Metadata)> {
+        self.entries.iter().map(|(key, metadata)| (key.as_str(), metadata))
+    }
+
+    /// Returns the amount of files in this manifest.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if this manifest has no files.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Error from a [`SnapshotView`].
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub enum SnapshotError<E> {
+    /// The storage service that is being viewed returned an error.
+    Storage(E),
+
+    /// The file was changed after the snapshot was taken.
+    Changed {
+        /// Key of the file, relative to the snapshot's prefix.
+        key: String,
+    },
+
+    /// The file was deleted after the snapshot was taken.
+    Missing {
+        /// Key of the file, relative to the snapshot's prefix.
+        key: String,
+    },
+
+    /// A mutating operation was called on the snapshot.
+    ReadOnly(ReadOnly),
+}
+
+impl<E: Display> Display for SnapshotError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Storage(e) => Display::fmt(e, f),
+            SnapshotError::Changed { key } => write!(f, "file [{key}] was changed after the snapshot was taken"),
+            SnapshotError::Missing { key } => write!(f, "file [{key}] was deleted after the snapshot was taken"),
+            SnapshotError::ReadOnly(e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for SnapshotError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Storage(e) => Some(e),
+            SnapshotError::ReadOnly(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Captures a [`Manifest`] of every file under `prefix` (or the root of the storage service
+/// if it is empty) and returns a [`SnapshotView`] that only serves those files.
+///
+/// Only the listing is read, so this is as cheap as [`blobs`][StorageService::blobs] is
+/// for the storage service.
+///
+/// ## Example
+/// ```rust,ignore
+/// let snapshot = remi::freeze(storage, "artifacts/v1.2.0").await?;
+///
+/// // fails with `SnapshotError::Changed` if the file was re-uploaded since
+/// let bundle = snapshot.open("bundle.tar.gz").await?;
+/// ```
+///
+/// * since: 0.11.0
+pub async fn freeze<S: StorageService>(service: S, prefix: &str) -> Result<SnapshotView<S>, S::Error> {
+    let mut manifest = Manifest::new(prefix);
+    let mut stream = BlobStream::new(&service, (!prefix.is_empty()).then(|| PathBuf::from(prefix)), None);
+    while let Some(blob) = stream.next().await? {
+        if let Blob::File(file) = blob {
+            let (metadata, _) = file.into_parts();
+            manifest.insert(diff::relative_key(&metadata.path, prefix), metadata);
+        }
+    }
+
+    Ok(SnapshotView::new(service, manifest))
+}
+
+/// Read-only view of the files in a [`Manifest`], created with [`freeze`].
+///
+/// Storage services don't have a common way of reading older versions of a file, so reads
+/// aren't served from the versions in the manifest: every read checks the file that is
+/// currently stored against its manifest entry instead, and fails with
+/// [`SnapshotError::Changed`] or [`SnapshotError::Missing`] if it no longer matches. A file
+/// has changed if its version, checksum, size, or modification time differs from the
+/// manifest, in that order.
+///
+/// Paths are relative to the manifest's prefix, files that aren't in the manifest don't
+/// exist, and listings are served from the manifest without including directories. Every
+/// operation that would modify the storage service fails with a [`ReadOnly`] error.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct SnapshotView<S> {
+    inner: S,
+    manifest: Manifest,
+}
+
+impl<S: StorageService> SnapshotView<S> {
+    /// Creates a [`SnapshotView`] of `inner` from a [`Manifest`] that was captured before.
+    pub fn new(inner: S, manifest: Manifest) -> SnapshotView<S> {
+        SnapshotView { inner, manifest }
+    }
+
+    /// Returns the [`Manifest`] of this snapshot.
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Returns the storage service that is being viewed.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Fetches the file in `key` and checks it against the manifest, returning `None` if
+    /// it isn't part of the snapshot.
+    async fn fetch(&self, key: &str) -> Result<Option<File>, SnapshotError<S::Error>> {
+        let Some(pinned) = self.manifest.get(key) else {
+            return Ok(None);
+        };
+
+        let path = match self.manifest.prefix() {
+            "" => PathBuf::from(key),
+            prefix => Path::new(prefix).join(key),
+        };
+
+        let file = match self.inner.blob(path).await.map_err(SnapshotError::Storage)? {
+            Some(Blob::File(file)) => file,
+            _ => return Err(SnapshotError::Missing { key: key.to_owned() }),
+        };
+
+        let current = file.to_metadata();
+        let changed = match (&pinned.version, &current.version) {
+            (Some(pinned), Some(current)) => pinned != current,
+            _ => diff::is_changed(pinned, &current, true),
+        };
+
+        if changed {
+            return Err(SnapshotError::Changed { key: key.to_owned() });
+        }
+
+        Ok(Some(file))
+    }
+}
+
+/// Returns the key of `path` in a [`Manifest`], which doesn't have a leading `./` or `/`.
+fn snapshot_key(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    path.trim_start_matches("./").trim_start_matches('/').to_owned()
+}
+
+#[async_trait]
+impl<S: StorageService> StorageService for SnapshotView<S> {
+    type Error = SnapshotError<S::Error>;
+
+    fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        Ok(self.fetch(&snapshot_key(path.as_ref())).await?.map(|file| file.data))
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        Ok(self.fetch(&snapshot_key(path.as_ref())).await?.map(Blob::File))
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        let options = options.unwrap_or_default();
+        let dir = path.map(|path| snapshot_key(path.as_ref())).unwrap_or_default();
+        let dir = dir.trim_end_matches('/');
+
+        let blobs = self
+            .manifest
+            .iter()
+            .filter(|(key, _)| dir.is_empty() || key.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/')))
+            .filter(|(key, _)| {
+                options.start_after.as_deref().map_or(true, |after| *key > after)
+                    && options.prefix.as_deref().map_or(true, |prefix| key.starts_with(prefix))
+            })
+            .filter(|(_, metadata)| {
+                !options.is_excluded(&metadata.name)
+                    && Path::new(&metadata.name)
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .map_or(true, |ext| options.is_ext_allowed(ext))
+            })
+            .map(|(_, metadata)| Blob::File(File::from(metadata.clone())))
+            .collect();
+
+        Ok(blobs)
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, _path: P) -> Result<bool, Self::Error> {
+        Err(SnapshotError::ReadOnly(ReadOnly { operation: "delete" }))
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, _prefix: P) -> Result<DeleteSummary, Self::Error> {
+        Err(SnapshotError::ReadOnly(ReadOnly {
+            operation: "delete_prefix",
+        }))
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        Ok(self.fetch(&snapshot_key(path.as_ref())).await?.is_some())
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, _path: P, _options: UploadRequest) -> Result<(), Self::Error> {
+        Err(SnapshotError::ReadOnly(ReadOnly { operation: "upload" }))
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        _path: P,
+        _options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        Err(SnapshotError::ReadOnly(ReadOnly {
+            operation: "create_exclusive",
+        }))
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn info(&self) -> ServiceInfo {
+        self.inner.info()
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        self.inner.verify_credentials().await.map_err(SnapshotError::Storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{freeze, SnapshotError};
+    use crate::{testing::TestStorage, Blob, StorageService, UploadRequest};
+    use bytes::Bytes;
+
+    fn request(data: &'static str) -> UploadRequest {
+        UploadRequest::default().with_data(data)
+    }
+
+    async fn storage() -> TestStorage {
+        let storage = TestStorage::new();
+        storage.upload("./artifacts/a.txt", request("weow")).await.unwrap();
+        storage.upload("./artifacts/b.txt", request("fluff")).await.unwrap();
+        storage.upload("./weow.txt", request("weow")).await.unwrap();
+
+        storage
+    }
+
+    #[tokio::test]
+    async fn serves_frozen_files() {
+        let snapshot = freeze(storage().await, "artifacts").await.unwrap();
+        assert_eq!(
+            snapshot.manifest().iter().map(|(key, _)| key).collect::<Vec<_>>(),
+            ["a.txt", "b.txt"]
+        );
+
+        assert_eq!(
+            snapshot.open("./a.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+
+        assert!(snapshot.exists("b.txt").await.unwrap());
+        assert!(!snapshot.exists("weow.txt").await.unwrap());
+        assert_eq!(snapshot.open("../weow.txt").await.unwrap(), None);
+
+        let names = snapshot
+            .blobs(None::<&str>, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|blob| match blob {
+                Blob::File(file) => file.name,
+                Blob::Directory(dir) => dir.name,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["a.txt", "b.txt"]);
+    }
+
+    #[tokio::test]
+    async fn changed_and_deleted_files() {
+        let storage = storage().await;
+        let snapshot = freeze(storage.clone(), "artifacts").await.unwrap();
+
+        storage.upload("./artifacts/a.txt", request("weow weow")).await.unwrap();
+        assert!(storage.delete("./artifacts/b.txt").await.unwrap());
+
+        // files that are uploaded after the snapshot was taken aren't part of it
+        storage.upload("./artifacts/c.txt", request("weow")).await.unwrap();
+
+        assert!(matches!(
+            snapshot.open("a.txt").await,
+            Err(SnapshotError::Changed { key }) if key == "a.txt"
+        ));
+
+        assert!(matches!(
+            snapshot.blob("b.txt").await,
+            Err(SnapshotError::Missing { key }) if key == "b.txt"
+        ));
+
+        assert_eq!(snapshot.open("c.txt").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn rejects_mutations() {
+        let snapshot = freeze(storage().await, "").await.unwrap();
+        assert_eq!(snapshot.manifest().len(), 1);
+        assert!(snapshot.is_read_only());
+
+        assert!(matches!(
+            snapshot.upload("./weow.txt", request("fluff")).await,
+            Err(SnapshotError::ReadOnly(_))
+        ));
+
+        assert!(matches!(
+            snapshot.delete("./weow.txt").await,
+            Err(SnapshotError::ReadOnly(_))
+        ));
+
+        assert_eq!(
+            snapshot.get_ref().open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+    }
+}
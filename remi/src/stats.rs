@@ -0,0 +1,417 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Lightweight counters of the operations that a storage service has performed, for apps
+//! that want to expose them (like on an admin endpoint) without running a metrics stack.
+
+use crate::{
+    async_trait, Blob, ContentStream, DeleteSummary, HealthReport, ListBlobsRequest, Page, PresignOptions,
+    PresignableStorageService, PresignedUrl, ServiceInfo, StorageService, UploadRequest, ValidationReport,
+};
+use bytes::Bytes;
+use futures_util::{StreamExt, TryStreamExt};
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// A point-in-time snapshot of the counters of a storage service, see [`StorageService::stats`].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    /// Amount of operations that were performed, including the ones that failed.
+    pub operations: u64,
+
+    /// Amount of operations that returned an error.
+    pub errors: u64,
+
+    /// Amount of bytes that were read from the storage service when opening files.
+    pub bytes_read: u64,
+
+    /// Amount of bytes that were written to the storage service when uploading files.
+    pub bytes_written: u64,
+
+    /// Amount of files that were served from a cache instead of the storage service.
+    pub cache_hits: u64,
+
+    /// Amount of files that weren't in a cache and had to be read from the storage service.
+    pub cache_misses: u64,
+
+    /// Amount of requests that were sent to the storage provider by listings, which is only
+    /// counted for listings that return how many requests they took, like
+    /// [`StorageService::blobs_page`].
+    pub list_requests: u64,
+}
+
+impl Stats {
+    /// Returns the ratio of files that were served from a cache, between `0.0` and `1.0`,
+    /// or `None` if no files were looked up in a cache.
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        match self.cache_hits + self.cache_misses {
+            0 => None,
+            lookups => Some(self.cache_hits as f64 / lookups as f64),
+        }
+    }
+}
+
+/// Thread-safe set of counters that can be shared between clones of a storage service
+/// and turned into a [`Stats`] snapshot.
+///
+/// * since: 0.11.0
+#[derive(Debug, Default)]
+pub struct StatsRecorder {
+    operations: AtomicU64,
+    errors: AtomicU64,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    list_requests: AtomicU64,
+}
+
+impl StatsRecorder {
+    /// Records that an operation was performed, and if it failed.
+    pub fn record<T, E>(&self, result: &Result<T, E>) {
+        self.operations.fetch_add(1, Ordering::Relaxed);
+        if result.is_err() {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Records that `bytes` were read from the storage service.
+    pub fn record_read(&self, bytes: usize) {
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records that `bytes` were written to the storage service.
+    pub fn record_written(&self, bytes: usize) {
+        self.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records whether a file was served from a cache or not.
+    pub fn record_cache(&self, hit: bool) {
+        match hit {
+            true => self.cache_hits.fetch_add(1, Ordering::Relaxed),
+            false => self.cache_misses.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    /// Records that a listing sent `requests` requests to the storage provider.
+    pub fn record_list_requests(&self, requests: u64) {
+        self.list_requests.fetch_add(requests, Ordering::Relaxed);
+    }
+
+    /// Returns a [`Stats`] snapshot of the current counters.
+    pub fn snapshot(&self) -> Stats {
+        Stats {
+            operations: self.operations.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            list_requests: self.list_requests.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A [`StorageService`] that counts the operations, errors, and bytes that go through the
+/// storage service it wraps, which are returned by [`StorageService::stats`].
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{MeteredStorageService, StorageService};
+/// #
+/// let storage = MeteredStorageService::new(storage);
+/// storage.open("./weow.txt").await?;
+///
+/// assert_eq!(storage.stats().operations, 1);
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct MeteredStorageService<S> {
+    inner: S,
+    recorder: Arc<StatsRecorder>,
+}
+
+impl<S: StorageService> MeteredStorageService<S> {
+    /// Creates a new [`MeteredStorageService`] that counts the operations of `inner`.
+    pub fn new(inner: S) -> MeteredStorageService<S> {
+        MeteredStorageService {
+            inner,
+            recorder: Arc::default(),
+        }
+    }
+
+    /// Returns the storage service that is being metered.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    fn record<T>(&self, result: Result<T, S::Error>) -> Result<T, S::Error> {
+        self.recorder.record(&result);
+        result
+    }
+}
+
+#[async_trait]
+impl<S: StorageService> StorageService for MeteredStorageService<S> {
+    type Error = S::Error;
+
+    fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        self.record(self.inner.init().await)
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        let result = self.record(self.inner.open(path).await);
+        if let Ok(Some(ref data)) = result {
+            self.recorder.record_read(data.len());
+        }
+
+        result
+    }
+
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        let result = self.record(self.inner.open_range(path, start, end).await);
+        if let Ok(Some(ref data)) = result {
+            self.recorder.record_read(data.len());
+        }
+
+        result
+    }
+
+    async fn open_stream<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+    ) -> Result<Option<ContentStream<Self::Error>>, Self::Error>
+    where
+        Self::Error: Send + 'static,
+    {
+        let stream = self.record(self.inner.open_stream(path).await)?;
+        let recorder = self.recorder.clone();
+
+        // bytes are counted as the chunks are read
+        Ok(stream.map(|stream| {
+            stream
+                .inspect_ok(move |chunk| recorder.record_read(chunk.len()))
+                .boxed()
+        }))
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        let result = self.record(self.inner.blob(path).await);
+        if let Ok(Some(Blob::File(ref file))) = result {
+            self.recorder.record_read(file.data.len());
+        }
+
+        result
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        self.record(self.inner.blobs(path, options).await)
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        let result = self.record(self.inner.blobs_page(path, options).await);
+        if let Ok(ref page) = result {
+            self.recorder.record_list_requests(page.requests);
+        }
+
+        result
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.record(self.inner.delete(path).await)
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        self.record(self.inner.delete_prefix(prefix).await)
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        self.record(self.inner.copy(src, dst).await)
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        self.record(self.inner.rename(from, to).await)
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.record(self.inner.exists(path).await)
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        let len = options.data.len();
+        let result = self.record(self.inner.upload(path, options).await);
+        if result.is_ok() {
+            self.recorder.record_written(len);
+        }
+
+        result
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        let len = options.data.len();
+        let result = self.record(self.inner.create_exclusive(path, options).await);
+        if result.is_ok() {
+            self.recorder.record_written(len);
+        }
+
+        result
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        self.record(self.inner.create_dir(path).await)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.inner.is_read_only()
+    }
+
+    fn stats(&self) -> Stats {
+        self.recorder.snapshot()
+    }
+
+    fn info(&self) -> ServiceInfo {
+        self.inner.info()
+    }
+
+    async fn debug_validate(&self) -> ValidationReport
+    where
+        Self::Error: Display + Send,
+    {
+        self.inner.debug_validate().await
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        self.record(self.inner.verify_credentials().await)
+    }
+
+    async fn healthcheck(&self) -> HealthReport
+    where
+        Self::Error: Display + Send,
+    {
+        self.inner.healthcheck().await
+    }
+}
+
+#[async_trait]
+impl<S: PresignableStorageService> PresignableStorageService for MeteredStorageService<S> {
+    async fn presign<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: PresignOptions,
+    ) -> Result<PresignedUrl, Self::Error> {
+        self.record(self.inner.presign(path, options).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MeteredStorageService, Stats};
+    use crate::{testing::TestStorage, StorageService, UploadRequest};
+
+    fn request(data: &'static str) -> UploadRequest {
+        UploadRequest::default().with_data(data)
+    }
+
+    #[tokio::test]
+    async fn counts_operations() {
+        let storage = MeteredStorageService::new(TestStorage::new());
+        storage.upload("./weow.txt", request("weow")).await.unwrap();
+        storage.create_exclusive("./fluff.txt", request("fluff")).await.unwrap();
+
+        storage.open("./weow.txt").await.unwrap();
+        storage.open_range("./fluff.txt", 1, Some(3)).await.unwrap();
+        storage.blob("./fluff.txt").await.unwrap();
+
+        // missing files aren't read
+        storage.open("./missing.txt").await.unwrap();
+
+        assert!(storage.exists("./weow.txt").await.unwrap());
+        assert!(storage.delete("./weow.txt").await.unwrap());
+
+        assert_eq!(
+            storage.stats(),
+            Stats {
+                operations: 8,
+                bytes_read: 4 + 2 + 5,
+                bytes_written: 4 + 5,
+                ..Stats::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn counts_errors() {
+        let storage = MeteredStorageService::new(TestStorage::new());
+        storage.create_exclusive("./weow.txt", request("weow")).await.unwrap();
+
+        // failed uploads aren't counted as written
+        assert!(storage.create_exclusive("./weow.txt", request("fluff")).await.is_err());
+        assert!(storage.open("./").await.is_err());
+
+        let stats = storage.stats();
+        assert_eq!((stats.operations, stats.errors), (3, 2));
+        assert_eq!(stats.bytes_written, 4);
+    }
+
+    #[tokio::test]
+    async fn only_counts_its_own_operations() {
+        let inner = TestStorage::new();
+        let storage = MeteredStorageService::new(inner.clone());
+
+        inner.upload("./weow.txt", request("weow")).await.unwrap();
+        assert_eq!(storage.stats(), Stats::default());
+
+        storage.clone().open("./weow.txt").await.unwrap();
+        assert_eq!(storage.stats().operations, 1);
+    }
+}
@@ -0,0 +1,304 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{Blob, ListBlobsRequest, StorageService};
+use bytes::Bytes;
+use futures_util::{stream, stream::BoxStream, Stream};
+use std::{collections::VecDeque, fmt::Display, path::PathBuf};
+
+/// Stream of the contents of a file in chunks, returned by [`StorageService::open_stream`].
+/// The size of each chunk is up to the storage service.
+///
+/// * since: 0.11.0
+pub type ContentStream<E> = BoxStream<'static, Result<Bytes, E>>;
+
+/// Amount of times that [`StorageService::for_each_blob_concurrent`] retries fetching a
+/// listing that failed before its error is returned.
+pub const DEFAULT_LIST_RETRIES: usize = 3;
+
+/// Position of a [`BlobStream`] that can be persisted and used to resume a listing
+/// from where it left off with [`ListBlobsRequest::with_start_after`].
+///
+/// A checkpoint is the key of the last blob that was yielded from the stream, without
+/// the `{service}://` prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Checkpoint(String);
+
+impl Checkpoint {
+    /// Creates a [`Checkpoint`] from a key that was previously persisted.
+    pub fn new<I: Into<String>>(key: I) -> Checkpoint {
+        Checkpoint(key.into())
+    }
+
+    /// Returns the key of the last blob that was yielded.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consumes this [`Checkpoint`] and returns the key of the last blob that was yielded.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+
+    fn from_blob(blob: &Blob) -> Checkpoint {
+        let path = match blob {
+            Blob::File(file) => &file.path,
+            Blob::Directory(dir) => &dir.path,
+        };
+
+        Checkpoint::new(path.split_once("://").map(|(_, key)| key).unwrap_or(path))
+    }
+}
+
+impl Display for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for Checkpoint {
+    fn from(value: String) -> Self {
+        Checkpoint(value)
+    }
+}
+
+impl From<Checkpoint> for String {
+    fn from(value: Checkpoint) -> Self {
+        value.0
+    }
+}
+
+/// Resumable listing of blobs from a storage service, created from [`StorageService::blob_stream`].
+///
+/// The listing is fetched one [`Page`][crate::Page] at a time with [`StorageService::blobs_page`],
+/// and the next page is only requested once every blob of the previous page was yielded, so
+/// large listings can be processed incrementally without keeping them in memory.
+///
+/// Blobs are yielded in lexicographical order of their keys, so the [`Checkpoint`] of the
+/// last yielded blob can be persisted and passed back in with [`ListBlobsRequest::with_start_after`]
+/// to resume the listing after a failure.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{ListBlobsRequest, StorageService};
+/// #
+/// let request = ListBlobsRequest::default().with_start_after(load_checkpoint()?);
+/// let mut stream = storage.blob_stream(None::<&str>, Some(request));
+///
+/// while let Some(blob) = stream.next().await? {
+///     migrate(blob).await?;
+///     save_checkpoint(stream.checkpoint())?;
+/// }
+/// ```
+pub struct BlobStream<'s, S: StorageService> {
+    service: &'s S,
+    path: Option<PathBuf>,
+    request: Option<ListBlobsRequest>,
+    buffered: VecDeque<Blob>,
+    checkpoint: Option<Checkpoint>,
+    retries: usize,
+}
+
+impl<'s, S: StorageService> BlobStream<'s, S> {
+    /// Creates a new [`BlobStream`] over the blobs in `path` (or the root of the
+    /// storage service if `None`).
+    pub fn new(service: &'s S, path: Option<PathBuf>, request: Option<ListBlobsRequest>) -> BlobStream<'s, S> {
+        let request = request.unwrap_or_default();
+        BlobStream {
+            service,
+            path,
+            checkpoint: request.start_after.clone().map(Checkpoint::new),
+            request: Some(request),
+            buffered: VecDeque::new(),
+            retries: 0,
+        }
+    }
+
+    /// Retries fetching a listing that failed up to `retries` more times before its error
+    /// is returned from [`next`][BlobStream::next].
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Returns the [`Checkpoint`] of the last blob that was yielded, or the one that the
+    /// stream was resumed from if no blobs were yielded yet.
+    pub fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_ref()
+    }
+
+    /// Returns the next [`Blob`] in the listing, or `None` if the listing is exhausted.
+    pub async fn next(&mut self) -> Result<Option<Blob>, S::Error>
+    where
+        S: Sized,
+    {
+        while self.buffered.is_empty() {
+            // the request is only advanced once its page was fetched, so a failed
+            // call to `next` can be retried.
+            let Some(request) = self.request.as_ref() else {
+                return Ok(None);
+            };
+
+            let mut attempt = 0;
+            let page = loop {
+                match self.service.blobs_page(self.path.as_ref(), Some(request.clone())).await {
+                    Err(_) if attempt < self.retries => attempt += 1,
+                    result => break result?,
+                }
+            };
+
+            let start_after = request.start_after.clone();
+            let mut blobs = page
+                .items
+                .into_iter()
+                .map(|blob| (Checkpoint::from_blob(&blob), blob))
+                .filter(|(key, _)| start_after.as_deref().map_or(true, |after| key.as_str() > after))
+                .collect::<Vec<_>>();
+
+            blobs.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+            self.buffered = blobs.into_iter().map(|(_, blob)| blob).collect();
+            self.request = page
+                .continuation
+                .map(|continuation| request.clone().with_continuation(Some(continuation)));
+        }
+
+        let Some(blob) = self.buffered.pop_front() else {
+            return Ok(None);
+        };
+
+        self.checkpoint = Some(Checkpoint::from_blob(&blob));
+        Ok(Some(blob))
+    }
+
+    /// Converts this [`BlobStream`] into a [`Stream`] of blobs, which can be used with the
+    /// combinators of [`TryStreamExt`][futures_util::TryStreamExt]. The stream ends after
+    /// the first error.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Blob, S::Error>> + 's
+    where
+        S: Sized,
+    {
+        stream::try_unfold(self, |mut stream| async move {
+            Ok(stream.next().await?.map(|blob| (blob, stream)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Checkpoint, DEFAULT_LIST_RETRIES};
+    use crate::{
+        testing::{Operation, TestStorage},
+        Blob, ListBlobsRequest, StorageService, UploadRequest,
+    };
+    use std::{
+        io,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    /// Returns a storage service with three files in `./dir`, whose listings fail while
+    /// `failures` is above zero.
+    async fn flaky(failures: Arc<AtomicUsize>) -> TestStorage {
+        let storage = TestStorage::new().failing(move |operation, _| {
+            let failed = operation == Operation::List
+                && failures
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |failures| failures.checked_sub(1))
+                    .is_ok();
+
+            failed.then(|| io::Error::new(io::ErrorKind::ConnectionReset, "connection reset"))
+        });
+
+        for name in ["c.txt", "a.txt", "b.txt"] {
+            storage
+                .upload(format!("./dir/{name}"), UploadRequest::default().with_data("weow"))
+                .await
+                .unwrap();
+        }
+
+        storage
+    }
+
+    fn name(blob: Blob) -> String {
+        match blob {
+            Blob::File(file) => file.name,
+            Blob::Directory(dir) => dir.name,
+        }
+    }
+
+    #[tokio::test]
+    async fn yields_blobs_in_order() {
+        let storage = flaky(Arc::default()).await;
+        let mut stream = storage.blob_stream(Some("./dir"), None);
+        assert_eq!(stream.checkpoint(), None);
+
+        let mut names = Vec::new();
+        while let Some(blob) = stream.next().await.unwrap() {
+            names.push(name(blob));
+        }
+
+        assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(stream.checkpoint(), Some(&Checkpoint::new("dir/c.txt")));
+    }
+
+    #[tokio::test]
+    async fn resumes_from_checkpoints() {
+        let storage = flaky(Arc::default()).await;
+        let mut stream = storage.blob_stream(Some("./dir"), None);
+        stream.next().await.unwrap();
+
+        let checkpoint = stream.checkpoint().cloned().unwrap();
+        assert_eq!(checkpoint.as_str(), "dir/a.txt");
+
+        let request = ListBlobsRequest::default().with_start_after(Some(checkpoint.clone()));
+        let mut resumed = storage.blob_stream(Some("./dir"), Some(request));
+        assert_eq!(resumed.checkpoint(), Some(&checkpoint));
+
+        assert_eq!(resumed.next().await.unwrap().map(name).as_deref(), Some("b.txt"));
+        assert_eq!(resumed.next().await.unwrap().map(name).as_deref(), Some("c.txt"));
+        assert!(resumed.next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn retries_failed_listings() {
+        let failures = Arc::new(AtomicUsize::new(0));
+        let storage = flaky(failures.clone()).await;
+        failures.store(DEFAULT_LIST_RETRIES, Ordering::SeqCst);
+
+        let mut stream = storage
+            .blob_stream(Some("./dir"), None)
+            .with_retries(DEFAULT_LIST_RETRIES);
+
+        assert_eq!(stream.next().await.unwrap().map(name).as_deref(), Some("a.txt"));
+    }
+
+    #[tokio::test]
+    async fn failed_listings_can_be_retried() {
+        let failures = Arc::new(AtomicUsize::new(0));
+        let storage = flaky(failures.clone()).await;
+        failures.store(2, Ordering::SeqCst);
+
+        let mut stream = storage.blob_stream(Some("./dir"), None).with_retries(1);
+        assert!(stream.next().await.is_err());
+        assert_eq!(stream.next().await.unwrap().map(name).as_deref(), Some("a.txt"));
+    }
+}
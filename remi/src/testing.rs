@@ -0,0 +1,506 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! In-memory storage service for the unit tests of this crate, which can't use `remi-inmemory`
+//! since it depends on a different build of this crate.
+
+// some of the helpers are only used by tests that are behind a feature flag
+#![allow(dead_code)]
+
+use crate::{
+    skip_provisioning, AlreadyExists, Blob, CollisionStrategy, Directory, File, InvalidKey, ListBlobsRequest, Page,
+    PresignMethod, PresignOptions, PresignableStorageService, PresignedUrl, ReadOnly, ResourceMissing, StorageEvent,
+    StorageService, TooLarge, TruncatedRead, UploadRequest, DEFAULT_DIRECTORY_MARKER,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    future, io, mem,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::SystemTime,
+};
+
+/// Operation that the failure hook of a [`TestStorage`] is called with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operation {
+    Open,
+    Blob,
+    List,
+    Delete,
+    Exists,
+    Upload,
+    Copy,
+    Rename,
+}
+
+type Failure = Arc<dyn Fn(Operation, &str) -> Option<io::Error> + Send + Sync>;
+
+/// Converts a path into the key of a pseudo-directory, where the root is an empty string.
+fn prefix(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let path = path.trim_start_matches("./").trim_matches('/');
+    match path {
+        "." => String::new(),
+        path => path.to_owned(),
+    }
+}
+
+/// Converts a path into the key of a file, see [`InvalidKey`].
+fn key(path: &Path) -> io::Result<String> {
+    InvalidKey::check(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    match prefix(path) {
+        key if key.is_empty() => Err(io::Error::new(io::ErrorKind::InvalidInput, InvalidKey::Empty)),
+        key => Ok(key),
+    }
+}
+
+fn name(key: &str) -> String {
+    key.rsplit('/').next().unwrap_or_default().to_owned()
+}
+
+/// Storage service that keeps its files in memory, like `remi-inmemory` does. Clones share
+/// the same files.
+#[derive(Clone, Default)]
+pub(crate) struct TestStorage {
+    files: Arc<Mutex<BTreeMap<String, File>>>,
+    events: Arc<Mutex<Vec<StorageEvent>>>,
+    running: Arc<AtomicUsize>,
+    max_running: Arc<AtomicUsize>,
+    failure: Option<Failure>,
+    max_object_size: Option<usize>,
+    truncate_reads: bool,
+    read_only: bool,
+    full_keys: bool,
+    provisioning: Option<bool>,
+    paginated: bool,
+    stalled: bool,
+}
+
+impl TestStorage {
+    pub(crate) fn new() -> TestStorage {
+        TestStorage::default()
+    }
+
+    /// Fails every operation that `failure` returns an error for. It is called with the
+    /// operation and the key that it was called with, which is empty for listings.
+    pub(crate) fn failing<F>(mut self, failure: F) -> TestStorage
+    where
+        F: Fn(Operation, &str) -> Option<io::Error> + Send + Sync + 'static,
+    {
+        self.failure = Some(Arc::new(failure));
+        self
+    }
+
+    /// Rejects uploads that are larger than `max` with a [`TooLarge`] error.
+    pub(crate) fn with_max_object_size(mut self, max: Option<usize>) -> TestStorage {
+        self.max_object_size = max;
+        self
+    }
+
+    /// Drops the last byte of every read, which fails with a [`TruncatedRead`] error.
+    pub(crate) fn with_truncated_reads(mut self, yes: bool) -> TestStorage {
+        self.truncate_reads = yes;
+        self
+    }
+
+    /// Rejects every operation that modifies the files with a [`ReadOnly`] error.
+    pub(crate) fn with_read_only(mut self, yes: bool) -> TestStorage {
+        self.read_only = yes;
+        self
+    }
+
+    /// Lists files with their full key as their name and without their contents, like S3
+    /// and GridFS do.
+    pub(crate) fn with_full_keys(mut self, yes: bool) -> TestStorage {
+        self.full_keys = yes;
+        self
+    }
+
+    /// Creates the `uploads` directory in [`init`][StorageService::init], or only checks that
+    /// it exists if provisioning is skipped, like the official storage services do.
+    pub(crate) fn with_provisioning(mut self, skip_provisioning: bool) -> TestStorage {
+        self.provisioning = Some(skip_provisioning);
+        self
+    }
+
+    /// Splits listings from [`blobs_page`][StorageService::blobs_page] into pages of
+    /// [`ListBlobsRequest::page_size`] blobs, instead of listing everything in one page.
+    pub(crate) fn with_pagination(mut self, yes: bool) -> TestStorage {
+        self.paginated = yes;
+        self
+    }
+
+    /// Never completes any operation, like requests that never get a response.
+    pub(crate) fn with_stalled(mut self, yes: bool) -> TestStorage {
+        self.stalled = yes;
+        self
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.files().len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.files().is_empty()
+    }
+
+    /// Returns the events of the uploads and deletions that succeeded since the last call,
+    /// like the official storage services publish.
+    pub(crate) fn take_events(&self) -> Vec<StorageEvent> {
+        mem::take(&mut *self.events.lock().unwrap())
+    }
+
+    /// Returns the most operations that ran at once.
+    pub(crate) fn max_running(&self) -> usize {
+        self.max_running.load(Ordering::SeqCst)
+    }
+
+    fn files(&self) -> MutexGuard<'_, BTreeMap<String, File>> {
+        self.files.lock().unwrap()
+    }
+
+    fn check_writable(&self, operation: &'static str) -> io::Result<()> {
+        ReadOnly::check(self.read_only, operation).map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e))
+    }
+
+    async fn enter(&self, operation: Operation, key: &str) -> io::Result<()> {
+        if self.stalled {
+            future::pending::<()>().await;
+        }
+
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+
+        // lets other operations start
+        tokio::task::yield_now().await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        match self.failure.as_ref().and_then(|failure| failure(operation, key)) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    async fn write(&self, key: String, options: UploadRequest, exclusive: bool) -> io::Result<()> {
+        self.check_writable("upload")?;
+        self.enter(Operation::Upload, &key).await?;
+        TooLarge::check(options.data.len(), self.max_object_size)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let mut files = self.files();
+        if exclusive && files.contains_key(&key) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                AlreadyExists::new(format!("memory://{key}")),
+            ));
+        }
+
+        let event = StorageEvent::uploaded(format!("memory://{key}"), &options, files.contains_key(&key));
+        let file = File {
+            last_modified_at: None,
+            content_type: options.content_type,
+            created_at: None,
+            metadata: options.metadata,
+            is_symlink: false,
+            checksum: None,
+            etag: None,
+            version: None,
+            expires_at: None,
+            extras: Default::default(),
+            name: name(&key),
+            path: format!("memory://{key}"),
+            size: options.data.len(),
+            data: options.data,
+        };
+
+        files.insert(key, file);
+        self.events.lock().unwrap().push(event);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageService for TestStorage {
+    type Error = io::Error;
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("remi:test")
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    async fn init(&self) -> io::Result<()> {
+        let Some(configured) = self.provisioning else {
+            return Ok(());
+        };
+
+        if !skip_provisioning(configured) {
+            return self.create_dir("./uploads").await;
+        }
+
+        match self.exists("./uploads").await? {
+            true => Ok(()),
+            false => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                ResourceMissing::new("directory", "uploads"),
+            )),
+        }
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<Bytes>> {
+        let key = key(path.as_ref())?;
+        self.enter(Operation::Open, &key).await?;
+
+        let Some(file) = self.files().get(&key).cloned() else {
+            return Ok(None);
+        };
+
+        let mut data = file.data;
+        if self.truncate_reads {
+            data.truncate(data.len().saturating_sub(1));
+        }
+
+        TruncatedRead::check(file.size as u64, data.len())
+            .map_err(|e| io::Error::new(io::ErrorKind::UnexpectedEof, e))?;
+
+        Ok(Some(data))
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<Option<Blob>> {
+        let key = key(path.as_ref())?;
+        self.enter(Operation::Blob, &key).await?;
+
+        let files = self.files();
+        if let Some(file) = files.get(&key) {
+            return Ok(Some(Blob::File(file.clone())));
+        }
+
+        let dir = format!("{key}/");
+        if !files.keys().any(|key| key.starts_with(&dir)) {
+            return Ok(None);
+        }
+
+        Ok(Some(Blob::Directory(Directory {
+            created_at: None,
+            name: name(&key),
+            path: format!("memory://{key}"),
+        })))
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> io::Result<Vec<Blob>> {
+        self.enter(Operation::List, "").await?;
+
+        let options = options.unwrap_or_default();
+        let base = match path.map(|path| prefix(path.as_ref())) {
+            Some(dir) if !dir.is_empty() => format!("{dir}/"),
+            _ => String::new(),
+        };
+
+        let name_prefix = options.prefix.as_deref().unwrap_or_default();
+        let mut blobs = BTreeMap::new();
+        for (key, file) in self.files().iter() {
+            let Some(rest) = key.strip_prefix(base.as_str()) else {
+                continue;
+            };
+
+            if !rest.starts_with(name_prefix) {
+                continue;
+            }
+
+            if let Some((name, _)) = rest.split_once('/') {
+                let key = format!("{base}{name}");
+                if options.include_dirs && !blobs.contains_key(&key) {
+                    let dir = Directory {
+                        created_at: None,
+                        name: name.to_owned(),
+                        path: format!("memory://{key}"),
+                    };
+
+                    blobs.insert(key, Blob::Directory(dir));
+                }
+
+                continue;
+            }
+
+            if rest == DEFAULT_DIRECTORY_MARKER {
+                continue;
+            }
+
+            let mut file = file.clone();
+            if options.metadata_only || self.full_keys {
+                file.data = Bytes::new();
+            }
+
+            if self.full_keys {
+                file.name = key.clone();
+            }
+
+            blobs.insert(key.clone(), Blob::File(file));
+        }
+
+        Ok(blobs
+            .into_iter()
+            .filter(|(key, _)| {
+                [&options.start_after, &options.continuation]
+                    .into_iter()
+                    .flatten()
+                    .all(|after| key.as_str() > after.as_str())
+            })
+            .map(|(_, blob)| blob)
+            .collect())
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> io::Result<Page<Blob>> {
+        let options = options.unwrap_or_default();
+        let mut blobs = self.blobs(path, Some(options.clone())).await?;
+
+        let limit = options.page_size_or(1000, u32::MAX) as usize;
+        if !self.paginated || blobs.len() <= limit {
+            return Ok(Page::last(blobs));
+        }
+
+        blobs.truncate(limit);
+        let continuation = match blobs.last() {
+            Some(Blob::File(file)) => file.path.trim_start_matches("memory://").to_owned(),
+            Some(Blob::Directory(dir)) => dir.path.trim_start_matches("memory://").to_owned(),
+            None => unreachable!("pages have at least one blob"),
+        };
+
+        Ok(Page::new(blobs, Some(continuation)))
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<bool> {
+        let key = key(path.as_ref())?;
+        self.check_writable("delete")?;
+        self.enter(Operation::Delete, &key).await?;
+
+        let deleted = self.files().remove(&key).is_some();
+        if deleted {
+            self.events.lock().unwrap().push(StorageEvent::Deleted {
+                path: format!("memory://{key}"),
+            });
+        }
+
+        Ok(deleted)
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> io::Result<bool> {
+        let (from, to) = (key(src.as_ref())?, key(dst.as_ref())?);
+        self.check_writable("copy")?;
+        self.enter(Operation::Copy, &to).await?;
+
+        let mut files = self.files();
+        let Some(mut file) = files.get(&from).cloned() else {
+            return Ok(false);
+        };
+
+        file.name = name(&to);
+        file.path = format!("memory://{to}");
+        files.insert(to, file);
+
+        Ok(true)
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> io::Result<bool> {
+        let (from, to) = (key(from.as_ref())?, key(to.as_ref())?);
+        self.check_writable("rename")?;
+        self.enter(Operation::Rename, &to).await?;
+
+        let mut files = self.files();
+        let Some(mut file) = files.remove(&from) else {
+            return Ok(false);
+        };
+
+        file.name = name(&to);
+        file.path = format!("memory://{to}");
+        files.insert(to, file);
+
+        Ok(true)
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> io::Result<bool> {
+        let key = prefix(path.as_ref());
+        self.enter(Operation::Exists, &key).await?;
+
+        let dir = format!("{key}/");
+        let files = self.files();
+        Ok(key.is_empty() || files.contains_key(&key) || files.keys().any(|key| key.starts_with(&dir)))
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> io::Result<()> {
+        let path = path.as_ref();
+        match options.collision() {
+            None | Some(CollisionStrategy::Overwrite) => self.write(key(path)?, options, false).await,
+            Some(CollisionStrategy::Error) => self.write(key(path)?, options, true).await,
+            Some(CollisionStrategy::NewVersion) => Err(io::Error::from(io::ErrorKind::Unsupported)),
+            Some(strategy @ CollisionStrategy::Suffix(_)) => {
+                for candidate in strategy.candidates(path) {
+                    match self.write(key(&candidate)?, options.clone(), true).await {
+                        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                        result => return result,
+                    }
+                }
+
+                Err(io::Error::from(io::ErrorKind::AlreadyExists))
+            }
+        }
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> io::Result<()> {
+        self.write(key(path.as_ref())?, options, true).await
+    }
+}
+
+/// Presigns `memory://` URLs, like a storage provider that can hand out URLs to its files.
+#[async_trait]
+impl PresignableStorageService for TestStorage {
+    async fn presign<P: AsRef<Path> + Send>(&self, path: P, options: PresignOptions) -> io::Result<PresignedUrl> {
+        let key = key(path.as_ref())?;
+        let headers = match options.method {
+            PresignMethod::Put => vec![(String::from("content-type"), String::from("application/octet-stream"))],
+            _ => Vec::new(),
+        };
+
+        Ok(PresignedUrl {
+            url: format!("memory://{key}?expires_in={}", options.expires_in.as_secs()),
+            method: options.method,
+            headers,
+            expires_at: SystemTime::now() + options.expires_in,
+        })
+    }
+}
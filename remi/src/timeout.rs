@@ -0,0 +1,458 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{
+    async_trait, rt, Blob, DeleteSummary, ListBlobsRequest, Page, PresignOptions, PresignableStorageService,
+    PresignedUrl, ServiceInfo, Stats, StorageService, UploadRequest,
+};
+use bytes::Bytes;
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Display},
+    future::Future,
+    path::Path,
+    time::Duration,
+};
+
+/// Describes how long the operations of a [`TimeoutStorageService`] can take before they
+/// fail with [`TimeoutError::Elapsed`].
+///
+/// Every operation is given [`timeout`][TimeoutPolicy::timeout], unless a more specific
+/// deadline was set for the kind of operation it is.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutPolicy {
+    /// Deadline of every operation that doesn't have a more specific one.
+    pub timeout: Duration,
+
+    /// Deadline of [`open`][StorageService::open], [`open_range`][StorageService::open_range],
+    /// [`open_stream`][StorageService::open_stream], [`blob`][StorageService::blob], and
+    /// [`exists`][StorageService::exists].
+    pub open: Option<Duration>,
+
+    /// Deadline of [`upload`][StorageService::upload] and
+    /// [`create_exclusive`][StorageService::create_exclusive].
+    pub upload: Option<Duration>,
+
+    /// Deadline of [`blobs`][StorageService::blobs] and [`blobs_page`][StorageService::blobs_page].
+    pub list: Option<Duration>,
+
+    /// Deadline of [`delete`][StorageService::delete] and [`delete_prefix`][StorageService::delete_prefix].
+    pub delete: Option<Duration>,
+}
+
+impl Default for TimeoutPolicy {
+    fn default() -> Self {
+        TimeoutPolicy::new(Duration::from_secs(30))
+    }
+}
+
+impl TimeoutPolicy {
+    /// Creates a new [`TimeoutPolicy`] that gives every operation `timeout`.
+    pub fn new(timeout: Duration) -> TimeoutPolicy {
+        TimeoutPolicy {
+            timeout,
+            open: None,
+            upload: None,
+            list: None,
+            delete: None,
+        }
+    }
+
+    /// Sets the deadline of operations that read a single file.
+    pub fn with_open(mut self, timeout: Duration) -> Self {
+        self.open = Some(timeout);
+        self
+    }
+
+    /// Sets the deadline of uploads.
+    pub fn with_upload(mut self, timeout: Duration) -> Self {
+        self.upload = Some(timeout);
+        self
+    }
+
+    /// Sets the deadline of listings.
+    pub fn with_list(mut self, timeout: Duration) -> Self {
+        self.list = Some(timeout);
+        self
+    }
+
+    /// Sets the deadline of deletions.
+    pub fn with_delete(mut self, timeout: Duration) -> Self {
+        self.delete = Some(timeout);
+        self
+    }
+}
+
+/// Error from a [`TimeoutStorageService`].
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub enum TimeoutError<E> {
+    /// The storage service that is being wrapped returned an error.
+    Storage(E),
+
+    /// The operation didn't complete before its deadline.
+    Elapsed {
+        /// Name of the operation, like `open` or `blobs`.
+        operation: &'static str,
+
+        /// Deadline that the operation was given.
+        timeout: Duration,
+    },
+}
+
+impl<E> TimeoutError<E> {
+    /// Returns `true` if the operation didn't complete before its deadline.
+    pub fn is_elapsed(&self) -> bool {
+        matches!(self, TimeoutError::Elapsed { .. })
+    }
+}
+
+impl<E: Display> Display for TimeoutError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeoutError::Storage(e) => Display::fmt(e, f),
+            TimeoutError::Elapsed { operation, timeout } => {
+                write!(f, "operation [{operation}] timed out after {}ms", timeout.as_millis())
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for TimeoutError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TimeoutError::Storage(e) => Some(e),
+            TimeoutError::Elapsed { .. } => None,
+        }
+    }
+}
+
+/// A [`StorageService`] that gives every operation of the storage service it wraps a
+/// deadline, so that an operation that is stuck (like a GridFS cursor that never yields
+/// or an S3 request that never gets a response) fails with [`TimeoutError::Elapsed`]
+/// instead of hanging forever. The operation is cancelled by dropping it once its
+/// deadline has passed.
+///
+/// Streams returned from [`open_stream`][StorageService::open_stream] are opened with a
+/// deadline, but reading from them isn't limited.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{TimeoutPolicy, TimeoutStorageService};
+/// # use std::time::Duration;
+/// #
+/// let policy = TimeoutPolicy::new(Duration::from_secs(10)).with_upload(Duration::from_secs(60));
+/// let storage = TimeoutStorageService::new(storage, policy);
+/// storage.open("./weow.txt").await?;
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub struct TimeoutStorageService<S> {
+    inner: S,
+    policy: TimeoutPolicy,
+}
+
+impl<S: StorageService> TimeoutStorageService<S> {
+    /// Creates a new [`TimeoutStorageService`] that gives the operations of `inner` the
+    /// deadlines of `policy`.
+    pub fn new(inner: S, policy: TimeoutPolicy) -> TimeoutStorageService<S> {
+        TimeoutStorageService { inner, policy }
+    }
+
+    /// Returns the storage service whose operations are given deadlines.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the [`TimeoutPolicy`] of this storage service.
+    pub fn policy(&self) -> &TimeoutPolicy {
+        &self.policy
+    }
+
+    async fn deadline<T, F>(
+        &self,
+        operation: &'static str,
+        timeout: Option<Duration>,
+        f: F,
+    ) -> Result<T, TimeoutError<S::Error>>
+    where
+        F: Future<Output = Result<T, S::Error>> + Send,
+    {
+        let timeout = timeout.unwrap_or(self.policy.timeout);
+        match rt::timeout(timeout, f).await {
+            Some(result) => result.map_err(TimeoutError::Storage),
+            None => {
+                #[cfg(feature = "tracing")]
+                ::tracing::warn!(
+                    remi.service = %self.inner.name(),
+                    operation,
+                    timeout.ms = timeout.as_millis() as u64,
+                    "operation timed out"
+                );
+
+                #[cfg(feature = "log")]
+                ::log::warn!(
+                    "operation [{operation}] on [{}] timed out after {}ms",
+                    self.inner.name(),
+                    timeout.as_millis()
+                );
+
+                Err(TimeoutError::Elapsed { operation, timeout })
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<S: StorageService> StorageService for TimeoutStorageService<S>
+where
+    S::Error: Send,
+{
+    type Error = TimeoutError<S::Error>;
+
+    fn name(&self) -> Cow<'static, str> {
+        self.inner.name()
+    }
+
+    async fn init(&self) -> Result<(), Self::Error> {
+        self.deadline("init", None, self.inner.init()).await
+    }
+
+    async fn open<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Bytes>, Self::Error> {
+        self.deadline("open", self.policy.open, self.inner.open(path)).await
+    }
+
+    async fn open_range<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        self.deadline("open_range", self.policy.open, self.inner.open_range(path, start, end))
+            .await
+    }
+
+    async fn blob<P: AsRef<Path> + Send>(&self, path: P) -> Result<Option<Blob>, Self::Error> {
+        self.deadline("blob", self.policy.open, self.inner.blob(path)).await
+    }
+
+    async fn blobs<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Vec<Blob>, Self::Error> {
+        self.deadline("blobs", self.policy.list, self.inner.blobs(path, options))
+            .await
+    }
+
+    async fn blobs_page<P: AsRef<Path> + Send>(
+        &self,
+        path: Option<P>,
+        options: Option<ListBlobsRequest>,
+    ) -> Result<Page<Blob>, Self::Error> {
+        self.deadline("blobs_page", self.policy.list, self.inner.blobs_page(path, options))
+            .await
+    }
+
+    async fn delete<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.deadline("delete", self.policy.delete, self.inner.delete(path))
+            .await
+    }
+
+    async fn delete_prefix<P: AsRef<Path> + Send>(&self, prefix: P) -> Result<DeleteSummary, Self::Error> {
+        self.deadline("delete_prefix", self.policy.delete, self.inner.delete_prefix(prefix))
+            .await
+    }
+
+    async fn copy<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, src: P, dst: Q) -> Result<bool, Self::Error> {
+        self.deadline("copy", None, self.inner.copy(src, dst)).await
+    }
+
+    async fn rename<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(&self, from: P, to: Q) -> Result<bool, Self::Error> {
+        self.deadline("rename", None, self.inner.rename(from, to)).await
+    }
+
+    async fn exists<P: AsRef<Path> + Send>(&self, path: P) -> Result<bool, Self::Error> {
+        self.deadline("exists", self.policy.open, self.inner.exists(path)).await
+    }
+
+    async fn upload<P: AsRef<Path> + Send>(&self, path: P, options: UploadRequest) -> Result<(), Self::Error> {
+        self.deadline("upload", self.policy.upload, self.inner.upload(path, options))
+            .await
+    }
+
+    async fn create_exclusive<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: UploadRequest,
+    ) -> Result<(), Self::Error> {
+        self.deadline(
+            "create_exclusive",
+            self.policy.upload,
+            self.inner.create_exclusive(path, options),
+        )
+        .await
+    }
+
+    async fn create_dir<P: AsRef<Path> + Send>(&self, path: P) -> Result<(), Self::Error> {
+        self.deadline("create_dir", None, self.inner.create_dir(path)).await
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.inner.is_read_only()
+    }
+
+    fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    fn info(&self) -> ServiceInfo {
+        self.inner.info()
+    }
+
+    async fn verify_credentials(&self) -> Result<(), Self::Error> {
+        self.deadline("verify_credentials", None, self.inner.verify_credentials())
+            .await
+    }
+}
+
+#[async_trait]
+impl<S: PresignableStorageService> PresignableStorageService for TimeoutStorageService<S>
+where
+    S::Error: Send,
+{
+    async fn presign<P: AsRef<Path> + Send>(
+        &self,
+        path: P,
+        options: PresignOptions,
+    ) -> Result<PresignedUrl, Self::Error> {
+        self.deadline("presign", None, self.inner.presign(path, options)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimeoutError, TimeoutPolicy, TimeoutStorageService};
+    use crate::{testing::TestStorage, StorageService, UploadRequest};
+    use bytes::Bytes;
+    use std::{io, time::Duration};
+
+    fn assert_elapsed<T: std::fmt::Debug>(
+        result: Result<T, TimeoutError<io::Error>>,
+        operation: &'static str,
+        timeout: Duration,
+    ) {
+        match result {
+            Err(TimeoutError::Elapsed {
+                operation: op,
+                timeout: elapsed,
+            }) => {
+                assert_eq!(op, operation);
+                assert_eq!(elapsed, timeout);
+            }
+
+            result => panic!("expected `{operation}` to time out, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn policy() {
+        let policy = TimeoutPolicy::new(Duration::from_secs(10)).with_upload(Duration::from_secs(60));
+        assert_eq!(policy.upload, Some(Duration::from_secs(60)));
+        assert_eq!(policy.open, None);
+        assert_eq!(TimeoutPolicy::default().timeout, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn display() {
+        let error = TimeoutError::<io::Error>::Elapsed {
+            operation: "open",
+            timeout: Duration::from_millis(1500),
+        };
+
+        assert!(error.is_elapsed());
+        assert_eq!(error.to_string(), "operation [open] timed out after 1500ms");
+        assert!(!TimeoutError::Storage(io::Error::from(io::ErrorKind::NotFound)).is_elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stalled_operations_time_out() {
+        let timeout = Duration::from_secs(30);
+        let storage = TimeoutStorageService::new(TestStorage::new().with_stalled(true), TimeoutPolicy::new(timeout));
+
+        assert_elapsed(storage.open("./weow.txt").await, "open", timeout);
+        assert_elapsed(storage.blob("./weow.txt").await, "blob", timeout);
+        assert_elapsed(storage.blobs(None::<&str>, None).await, "blobs", timeout);
+        assert_elapsed(storage.delete("./weow.txt").await, "delete", timeout);
+        assert_elapsed(
+            storage
+                .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+                .await,
+            "upload",
+            timeout,
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn operations_use_their_own_deadlines() {
+        let policy = TimeoutPolicy::new(Duration::from_secs(30))
+            .with_open(Duration::from_secs(1))
+            .with_upload(Duration::from_secs(2))
+            .with_list(Duration::from_secs(3))
+            .with_delete(Duration::from_secs(4));
+
+        let storage = TimeoutStorageService::new(TestStorage::new().with_stalled(true), policy);
+        let started = tokio::time::Instant::now();
+
+        assert_elapsed(storage.open("./weow.txt").await, "open", Duration::from_secs(1));
+        assert_elapsed(
+            storage.create_exclusive("./weow.txt", UploadRequest::default()).await,
+            "create_exclusive",
+            Duration::from_secs(2),
+        );
+
+        assert_elapsed(storage.blobs(None::<&str>, None).await, "blobs", Duration::from_secs(3));
+        assert_elapsed(storage.delete("./weow.txt").await, "delete", Duration::from_secs(4));
+
+        assert_eq!(started.elapsed(), Duration::from_secs(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn completed_operations_are_returned() {
+        let storage = TimeoutStorageService::new(TestStorage::new(), TimeoutPolicy::default());
+        storage
+            .upload("./weow.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.open("./weow.txt").await.unwrap(),
+            Some(Bytes::from_static(b"weow"))
+        );
+        assert!(storage.delete("./weow.txt").await.unwrap());
+        assert!(!storage.delete("./weow.txt").await.unwrap());
+    }
+}
@@ -0,0 +1,242 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Adapter that turns a [`StorageService`] into a [`tower_service::Service`] so that
+//! existing tower layers (timeouts, rate limits, retries, load shedding) can be reused.
+
+use crate::{Blob, ListBlobsRequest, StorageService, UploadRequest};
+use bytes::Bytes;
+use std::{
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// A request to a storage service operation that can be sent through a [`TowerService`].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+pub enum StorageRequest {
+    /// Calls [`StorageService::open`].
+    Open(PathBuf),
+
+    /// Calls [`StorageService::blob`].
+    Blob(PathBuf),
+
+    /// Calls [`StorageService::blobs`].
+    Blobs(Option<PathBuf>, Option<ListBlobsRequest>),
+
+    /// Calls [`StorageService::delete`].
+    Delete(PathBuf),
+
+    /// Calls [`StorageService::exists`].
+    Exists(PathBuf),
+
+    /// Calls [`StorageService::upload`].
+    Upload(PathBuf, UploadRequest),
+}
+
+/// Response of a [`StorageRequest`], which has the same variant as the request that was sent.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // mirrors `Blob`, which isn't boxed either
+pub enum StorageResponse {
+    /// Response of [`StorageRequest::Open`].
+    Open(Option<Bytes>),
+
+    /// Response of [`StorageRequest::Blob`].
+    Blob(Option<Blob>),
+
+    /// Response of [`StorageRequest::Blobs`].
+    Blobs(Vec<Blob>),
+
+    /// Response of [`StorageRequest::Delete`], which is `true` if the file was deleted.
+    Delete(bool),
+
+    /// Response of [`StorageRequest::Exists`].
+    Exists(bool),
+
+    /// Response of [`StorageRequest::Upload`].
+    Upload,
+}
+
+/// [`tower_service::Service`] implementation over a [`StorageService`]. Storage services
+/// are always ready to accept requests, so any backpressure comes from the layers that
+/// wrap this service.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{StorageRequest, TowerService};
+/// # use std::time::Duration;
+/// # use tower::{ServiceBuilder, ServiceExt};
+/// #
+/// let mut service = ServiceBuilder::new()
+///     .timeout(Duration::from_secs(5))
+///     .service(TowerService::new(storage));
+///
+/// let response = service.ready().await?.call(StorageRequest::Open("./weow.txt".into())).await?;
+/// ```
+///
+/// * since: 0.11.0
+#[derive(Debug)]
+pub struct TowerService<S> {
+    service: Arc<S>,
+}
+
+impl<S> TowerService<S> {
+    /// Creates a new [`TowerService`] that owns the given storage service.
+    pub fn new(service: S) -> TowerService<S> {
+        TowerService {
+            service: Arc::new(service),
+        }
+    }
+
+    /// Creates a new [`TowerService`] from a storage service that is already shared.
+    pub fn from_arc(service: Arc<S>) -> TowerService<S> {
+        TowerService { service }
+    }
+
+    /// Returns a reference to the inner storage service.
+    pub fn get_ref(&self) -> &S {
+        &self.service
+    }
+}
+
+impl<S> Clone for TowerService<S> {
+    fn clone(&self) -> Self {
+        TowerService {
+            service: Arc::clone(&self.service),
+        }
+    }
+}
+
+impl<S> tower_service::Service<StorageRequest> for TowerService<S>
+where
+    S: StorageService + 'static,
+    S::Error: Send + 'static,
+{
+    type Response = StorageResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<StorageResponse, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: StorageRequest) -> Self::Future {
+        let service = Arc::clone(&self.service);
+        Box::pin(async move {
+            match req {
+                StorageRequest::Open(path) => service.open(path).await.map(StorageResponse::Open),
+                StorageRequest::Blob(path) => service.blob(path).await.map(StorageResponse::Blob),
+                StorageRequest::Blobs(path, options) => service.blobs(path, options).await.map(StorageResponse::Blobs),
+                StorageRequest::Delete(path) => service.delete(path).await.map(StorageResponse::Delete),
+                StorageRequest::Exists(path) => service.exists(path).await.map(StorageResponse::Exists),
+                StorageRequest::Upload(path, request) => {
+                    service.upload(path, request).await.map(|_| StorageResponse::Upload)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StorageRequest, StorageResponse, TowerService};
+    use crate::{testing::TestStorage, Blob, StorageService, UploadRequest};
+    use bytes::Bytes;
+    use std::{future::poll_fn, path::PathBuf};
+    use tower_service::Service;
+
+    async fn call(service: &mut TowerService<TestStorage>, request: StorageRequest) -> StorageResponse {
+        poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+        service.call(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn dispatches_requests() {
+        let mut service = TowerService::new(TestStorage::new());
+        let path = PathBuf::from("./weow.txt");
+
+        let response = call(
+            &mut service,
+            StorageRequest::Upload(path.clone(), UploadRequest::default().with_data("weow")),
+        )
+        .await;
+
+        assert!(matches!(response, StorageResponse::Upload));
+        assert!(matches!(
+            call(&mut service, StorageRequest::Exists(path.clone())).await,
+            StorageResponse::Exists(true)
+        ));
+
+        match call(&mut service, StorageRequest::Open(path.clone())).await {
+            StorageResponse::Open(data) => assert_eq!(data, Some(Bytes::from_static(b"weow"))),
+            response => panic!("expected an open response, got {response:?}"),
+        }
+
+        match call(&mut service, StorageRequest::Blob(path.clone())).await {
+            StorageResponse::Blob(Some(Blob::File(file))) => assert_eq!(file.name, "weow.txt"),
+            response => panic!("expected a file, got {response:?}"),
+        }
+
+        match call(&mut service, StorageRequest::Blobs(None, None)).await {
+            StorageResponse::Blobs(blobs) => assert_eq!(blobs.len(), 1),
+            response => panic!("expected a listing, got {response:?}"),
+        }
+
+        assert!(matches!(
+            call(&mut service, StorageRequest::Delete(path.clone())).await,
+            StorageResponse::Delete(true)
+        ));
+
+        assert!(service.get_ref().is_empty());
+    }
+
+    #[tokio::test]
+    async fn clones_share_the_storage_service() {
+        let mut service = TowerService::new(TestStorage::new());
+        let mut clone = service.clone();
+
+        call(
+            &mut clone,
+            StorageRequest::Upload(PathBuf::from("./weow.txt"), UploadRequest::default().with_data("weow")),
+        )
+        .await;
+
+        assert!(service.get_ref().exists("./weow.txt").await.unwrap());
+        assert!(matches!(
+            call(&mut service, StorageRequest::Delete(PathBuf::from("./missing.txt"))).await,
+            StorageResponse::Delete(false)
+        ));
+    }
+
+    #[tokio::test]
+    async fn returns_errors() {
+        let mut service = TowerService::new(TestStorage::new());
+        poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+
+        assert!(service.call(StorageRequest::Open(PathBuf::from("./"))).await.is_err());
+    }
+}
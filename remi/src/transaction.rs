@@ -0,0 +1,476 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{StorageService, UploadRequest};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Prefix of where staged objects from a [`Transaction`] are uploaded into.
+pub const TRANSACTION_STAGING_PREFIX: &str = "./.remi/transactions";
+
+/// Error from committing a [`Transaction`].
+#[derive(Debug)]
+pub enum TransactionError<E> {
+    /// The storage service returned an error.
+    Storage(E),
+
+    /// The staged object of an upload was deleted before it could be committed.
+    Missing {
+        /// Key that the upload was staged into.
+        key: String,
+    },
+
+    /// Committing failed and some of the objects that were replaced couldn't be restored,
+    /// so they were kept in the transaction's staging prefix.
+    Partial {
+        /// Error that made the commit fail.
+        error: Box<TransactionError<E>>,
+
+        /// Keys in the staging prefix that the replaced objects were kept in.
+        kept: Vec<String>,
+    },
+}
+
+impl<E: Display> Display for TransactionError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::Storage(e) => Display::fmt(e, f),
+            TransactionError::Missing { key } => write!(f, "staged upload [{key}] was deleted before it was committed"),
+            TransactionError::Partial { error, kept } => {
+                write!(f, "{error} (replaced objects were kept in [{}])", kept.join(", "))
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for TransactionError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TransactionError::Storage(e) => Some(e),
+            TransactionError::Missing { .. } => None,
+            TransactionError::Partial { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Represents a group of uploads that should be visible together. Each upload is staged
+/// into a temporary key (in [`TRANSACTION_STAGING_PREFIX`]) and is only
+/// [renamed][StorageService::rename] into its final location when [`Transaction::commit`]
+/// is called, so nothing is uploaded twice.
+///
+/// Objects that are replaced by a commit are moved aside into the staging prefix first. If
+/// staging or committing fails, the objects that were already committed are deleted, the
+/// replaced objects are moved back and all staged keys are removed. This is "atomic-ish":
+/// the committed objects were visible in the meantime, and if a replaced object can't be
+/// moved back, it is kept in the staging prefix and [`TransactionError::Partial`] is returned.
+///
+/// Dropping a transaction without committing or [aborting][Transaction::abort] it keeps its
+/// staged objects in `./.remi/transactions/{id}` since they can't be deleted without an
+/// async context. They can be removed with [`StorageService::delete_prefix`].
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::{Transaction, UploadRequest};
+/// #
+/// let mut txn = Transaction::new(&storage);
+/// txn.upload("./releases/1.0.0/index.yaml", UploadRequest::default().with_data("...")).await?;
+/// txn.upload("./releases/1.0.0/chart.tgz", UploadRequest::default().with_data(tarball)).await?;
+///
+/// txn.commit().await?;
+/// ```
+pub struct Transaction<'s, S: StorageService> {
+    service: &'s S,
+    staged: Vec<Staged>,
+    id: String,
+}
+
+struct Staged {
+    staged: PathBuf,
+    path: PathBuf,
+}
+
+/// Upload that was (partially) committed, and where the object it replaced was moved to.
+struct Committed<'a> {
+    upload: &'a Staged,
+    backup: Option<PathBuf>,
+    renamed: bool,
+}
+
+impl<'s, S: StorageService> Transaction<'s, S> {
+    /// Creates a new, empty [`Transaction`] for the given storage service.
+    pub fn new(service: &'s S) -> Transaction<'s, S> {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+
+        Transaction {
+            service,
+            staged: Vec::new(),
+            id: format!(
+                "{nanos:x}-{:x}-{}",
+                process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ),
+        }
+    }
+
+    /// Returns the unique identifier of this transaction.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns how many uploads were staged in this transaction.
+    pub fn len(&self) -> usize {
+        self.staged.len()
+    }
+
+    /// Returns `true` if nothing was staged in this transaction.
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Stages an upload into a temporary key. If the upload fails, then every staged
+    /// key will be removed and the transaction can no longer be used.
+    pub async fn upload<P: AsRef<Path>>(&mut self, path: P, request: UploadRequest) -> Result<(), S::Error> {
+        let staged = PathBuf::from(format!(
+            "{TRANSACTION_STAGING_PREFIX}/{}/{}",
+            self.id,
+            self.staged.len()
+        ));
+
+        if let Err(e) = self.service.upload(&staged, request).await {
+            self.cleanup().await;
+            return Err(e);
+        }
+
+        self.staged.push(Staged {
+            path: path.as_ref().to_path_buf(),
+            staged,
+        });
+
+        Ok(())
+    }
+
+    /// Commits all staged uploads by renaming them into their final locations, after the
+    /// objects they replace are moved aside. If a staged upload can't be committed, the
+    /// uploads that were already committed are deleted again, the replaced objects are
+    /// restored and the remaining staged keys are removed.
+    pub async fn commit(mut self) -> Result<(), TransactionError<S::Error>> {
+        let staged = std::mem::take(&mut self.staged);
+        let mut committed = Vec::with_capacity(staged.len());
+
+        for (i, upload) in staged.iter().enumerate() {
+            let backup = PathBuf::from(format!("{TRANSACTION_STAGING_PREFIX}/{}/{i}.orig", self.id));
+            let mut current = Committed {
+                upload,
+                backup: None,
+                renamed: false,
+            };
+
+            let result = match self.service.rename(&upload.path, &backup).await {
+                Ok(moved) => {
+                    current.backup = moved.then_some(backup);
+                    self.service.rename(&upload.staged, &upload.path).await
+                }
+
+                Err(e) => Err(e),
+            };
+
+            let error = match result {
+                Ok(true) => {
+                    current.renamed = true;
+                    committed.push(current);
+
+                    continue;
+                }
+
+                Ok(false) => TransactionError::Missing {
+                    key: upload.staged.to_string_lossy().into_owned(),
+                },
+
+                Err(e) => TransactionError::Storage(e),
+            };
+
+            committed.push(current);
+            let kept = self.restore(committed).await;
+
+            self.staged = staged.into_iter().skip(i).collect();
+            self.cleanup().await;
+
+            if kept.is_empty() {
+                return Err(error);
+            }
+
+            return Err(TransactionError::Partial {
+                error: Box::new(error),
+                kept,
+            });
+        }
+
+        for backup in committed.into_iter().filter_map(|committed| committed.backup) {
+            // best-effort, the transaction was already committed.
+            let _ = self.service.delete(&backup).await;
+        }
+
+        Ok(())
+    }
+
+    /// Discards all staged uploads without committing them.
+    pub async fn abort(mut self) {
+        self.cleanup().await;
+    }
+
+    /// Deletes the committed uploads and moves the objects they replaced back, in reverse
+    /// order. Returns the keys of the replaced objects that couldn't be moved back.
+    async fn restore(&self, committed: Vec<Committed<'_>>) -> Vec<String> {
+        let mut kept = Vec::new();
+        for committed in committed.into_iter().rev() {
+            match committed.backup {
+                Some(backup) => {
+                    let restored = self.service.rename(&backup, &committed.upload.path).await;
+                    if !matches!(restored, Ok(true)) {
+                        kept.push(backup.to_string_lossy().into_owned());
+                    }
+                }
+
+                // best-effort, like removing the staged keys.
+                None if committed.renamed => {
+                    let _ = self.service.delete(&committed.upload.path).await;
+                }
+
+                None => {}
+            }
+        }
+
+        kept
+    }
+
+    async fn cleanup(&mut self) {
+        for staged in self.staged.drain(..) {
+            // best-effort, a staged key that couldn't be deleted shouldn't
+            // hide the actual error that happened.
+            let _ = self.service.delete(&staged.staged).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Transaction, TransactionError, TRANSACTION_STAGING_PREFIX};
+    use crate::{
+        testing::{Operation, TestStorage},
+        StorageService, UploadRequest,
+    };
+    use bytes::Bytes;
+    use std::{
+        io,
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    };
+
+    const PATHS: [&str; 3] = ["./a.txt", "./b.txt", "./c.txt"];
+
+    /// Storage service that fails the first rename into `./b.txt`, which commits it.
+    fn failing_rename() -> TestStorage {
+        let failed = AtomicBool::new(false);
+        TestStorage::new().failing(move |op, key| {
+            (op == Operation::Rename && key == "b.txt" && !failed.swap(true, Ordering::SeqCst))
+                .then(|| io::Error::other("weow"))
+        })
+    }
+
+    async fn stage<S: StorageService>(txn: &mut Transaction<'_, S>) {
+        for path in PATHS {
+            txn.upload(path, UploadRequest::default().with_data(path))
+                .await
+                .unwrap_or_else(|_| panic!("to stage `{path}`"));
+        }
+
+        assert_eq!(txn.len(), 3);
+    }
+
+    async fn seed(storage: &TestStorage) {
+        for path in PATHS {
+            storage
+                .upload(path, UploadRequest::default().with_data("old"))
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn assert_nothing_staged(storage: &TestStorage) {
+        assert!(storage
+            .blobs(Some(TRANSACTION_STAGING_PREFIX), None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn commit() {
+        let storage = TestStorage::new();
+        let mut txn = Transaction::new(&storage);
+        stage(&mut txn).await;
+
+        // staged uploads aren't visible until the transaction is committed
+        assert!(!storage.exists("./a.txt").await.unwrap());
+        assert_eq!(storage.len(), 3);
+
+        txn.commit().await.unwrap();
+        for path in PATHS {
+            assert_eq!(storage.open(path).await.unwrap(), Some(Bytes::from(path)));
+        }
+
+        assert_eq!(storage.len(), 3);
+        assert_nothing_staged(&storage).await;
+    }
+
+    #[tokio::test]
+    async fn commit_replaces_objects() {
+        let storage = TestStorage::new();
+        seed(&storage).await;
+
+        let mut txn = Transaction::new(&storage);
+        stage(&mut txn).await;
+        txn.commit().await.unwrap();
+
+        for path in PATHS {
+            assert_eq!(storage.open(path).await.unwrap(), Some(Bytes::from(path)));
+        }
+
+        // the replaced objects aren't kept after a successful commit
+        assert_eq!(storage.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn abort() {
+        let storage = TestStorage::new();
+        let mut txn = Transaction::new(&storage);
+        stage(&mut txn).await;
+
+        txn.abort().await;
+        assert!(storage.is_empty());
+    }
+
+    #[tokio::test]
+    async fn dropped_transactions_keep_staged_uploads() {
+        let storage = TestStorage::new();
+        let mut txn = Transaction::new(&storage);
+        stage(&mut txn).await;
+
+        let prefix = format!("{TRANSACTION_STAGING_PREFIX}/{}", txn.id());
+        drop(txn);
+
+        assert_eq!(storage.len(), 3);
+        assert_eq!(storage.delete_prefix(&prefix).await.unwrap().deleted, 3);
+        assert!(storage.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_commit_deletes_committed_uploads() {
+        let storage = failing_rename();
+        let mut txn = Transaction::new(&storage);
+        stage(&mut txn).await;
+
+        let error = txn.commit().await.unwrap_err();
+        assert!(matches!(error, TransactionError::Storage(ref e) if e.to_string() == "weow"));
+        assert!(storage.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_commit_restores_replaced_objects() {
+        let storage = failing_rename();
+        seed(&storage).await;
+
+        let mut txn = Transaction::new(&storage);
+        stage(&mut txn).await;
+
+        // `./a.txt` was already committed and `./b.txt` was moved aside when the second rename fails
+        let error = txn.commit().await.unwrap_err();
+        assert!(matches!(error, TransactionError::Storage(ref e) if e.to_string() == "weow"));
+
+        for path in PATHS {
+            assert_eq!(storage.open(path).await.unwrap(), Some(Bytes::from_static(b"old")));
+        }
+
+        assert_eq!(storage.len(), 3);
+        assert_nothing_staged(&storage).await;
+    }
+
+    #[tokio::test]
+    async fn replaced_objects_that_cant_be_restored_are_kept() {
+        // fails to commit `./b.txt`, and to restore `./a.txt` after it was committed
+        let (a, b) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let storage = TestStorage::new().failing(move |op, key| match (op, key) {
+            (Operation::Rename, "a.txt") if a.fetch_add(1, Ordering::SeqCst) == 1 => Some(io::Error::other("fluff")),
+            (Operation::Rename, "b.txt") if b.fetch_add(1, Ordering::SeqCst) == 0 => Some(io::Error::other("weow")),
+            _ => None,
+        });
+
+        seed(&storage).await;
+
+        let mut txn = Transaction::new(&storage);
+        stage(&mut txn).await;
+
+        let backup = format!("{TRANSACTION_STAGING_PREFIX}/{}/0.orig", txn.id());
+        match txn.commit().await {
+            Err(TransactionError::Partial { error, kept }) => {
+                assert!(matches!(*error, TransactionError::Storage(ref e) if e.to_string() == "weow"));
+                assert_eq!(kept, [backup.as_str()]);
+            }
+
+            result => panic!("expected a partial commit, got {result:?}"),
+        }
+
+        assert_eq!(storage.open(&backup).await.unwrap(), Some(Bytes::from_static(b"old")));
+        assert_eq!(
+            storage.open("./a.txt").await.unwrap(),
+            Some(Bytes::from_static(b"./a.txt"))
+        );
+
+        assert_eq!(storage.open("./b.txt").await.unwrap(), Some(Bytes::from_static(b"old")));
+    }
+
+    #[tokio::test]
+    async fn missing_staged_upload() {
+        let storage = TestStorage::new();
+        let mut txn = Transaction::new(&storage);
+        stage(&mut txn).await;
+
+        let staged = format!("{TRANSACTION_STAGING_PREFIX}/{}/1", txn.id());
+        assert!(storage.delete(&staged).await.unwrap());
+
+        match txn.commit().await {
+            Err(TransactionError::Missing { key }) => assert_eq!(key, staged),
+            result => panic!("expected the staged upload to be missing, got {result:?}"),
+        }
+
+        assert!(storage.is_empty());
+    }
+}
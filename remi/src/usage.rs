@@ -0,0 +1,431 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Usage of a storage service (how many files it has and how much space they take up),
+//! and a [`UsageWatcher`] that alerts when it goes over a threshold.
+
+use crate::{Blob, StorageService};
+use std::{fmt::Display, path::Path};
+
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+use crate::rt;
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// How often a [`UsageWatcher`] checks the usage of a storage service by default.
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(any(feature = "rt-tokio", feature = "rt-async-std"))))]
+pub const DEFAULT_USAGE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Amount of files in a storage service and how many bytes they take up, see [`usage`].
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Usage {
+    /// Amount of files.
+    pub objects: u64,
+
+    /// Total size of every file, in bytes.
+    pub bytes: u64,
+}
+
+/// Returns the [`Usage`] of the files in `path` (or the root of the storage service if
+/// `path` is `None`) by listing them, so this sends as many requests as listing the
+/// files with [`blob_stream`][StorageService::blob_stream] would.
+///
+/// * since: 0.11.0
+pub async fn usage<S, P>(service: &S, path: Option<P>) -> Result<Usage, S::Error>
+where
+    S: StorageService,
+    P: AsRef<Path> + Send,
+{
+    let mut stream = service.blob_stream(path, None);
+    let mut usage = Usage::default();
+    while let Some(blob) = stream.next().await? {
+        if let Blob::File(file) = blob {
+            usage.objects += 1;
+            usage.bytes += file.size as u64;
+        }
+    }
+
+    Ok(usage)
+}
+
+/// Threshold of a [`UsageWatcher`] that was exceeded.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageLimit {
+    /// Total size of every file, in bytes.
+    Bytes(u64),
+
+    /// Amount of files.
+    Objects(u64),
+}
+
+impl UsageLimit {
+    /// Returns `true` if `usage` is over this threshold.
+    pub fn is_exceeded_by(&self, usage: &Usage) -> bool {
+        match *self {
+            UsageLimit::Bytes(max) => usage.bytes > max,
+            UsageLimit::Objects(max) => usage.objects > max,
+        }
+    }
+}
+
+/// Alert from a [`UsageWatcher`] of a threshold that was exceeded.
+///
+/// * since: 0.11.0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageAlert {
+    /// Usage of the storage service when the threshold was exceeded.
+    pub usage: Usage,
+
+    /// Threshold that was exceeded.
+    pub limit: UsageLimit,
+}
+
+impl Display for UsageAlert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.limit {
+            UsageLimit::Bytes(max) => write!(f, "storage usage of {} bytes is over {max} bytes", self.usage.bytes),
+            UsageLimit::Objects(max) => write!(f, "storage usage of {} files is over {max} files", self.usage.objects),
+        }
+    }
+}
+
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+type AlertCallback = Arc<dyn Fn(&UsageAlert) + Send + Sync>;
+
+/// Checks the [`usage`] of a storage service on an interval in the background and alerts
+/// when it goes over one of its thresholds, by calling the callbacks that were registered
+/// with [`UsageWatcher::on_alert`] and emitting a warning with the `tracing` or `log`
+/// features.
+///
+/// An alert is only sent when a threshold is first exceeded, and again if the usage goes
+/// back under it and then exceeds it later, so a storage service that stays over a threshold
+/// doesn't alert on every check.
+///
+/// ## Example
+/// ```rust,ignore
+/// # use remi::UsageWatcher;
+/// #
+/// let handle = UsageWatcher::new(storage.clone())
+///     .with_max_bytes(10 * 1024 * 1024 * 1024)
+///     .on_alert(|alert| notify_admins(alert))
+///     .spawn();
+///
+/// // the watcher stops once the handle is dropped
+/// handle.stop();
+/// ```
+///
+/// * since: 0.11.0
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(any(feature = "rt-tokio", feature = "rt-async-std"))))]
+#[derive(Clone)]
+pub struct UsageWatcher<S> {
+    service: S,
+    path: Option<PathBuf>,
+    interval: Duration,
+    limits: Vec<UsageLimit>,
+    callbacks: Vec<AlertCallback>,
+}
+
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+impl<S: fmt::Debug> fmt::Debug for UsageWatcher<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UsageWatcher")
+            .field("service", &self.service)
+            .field("path", &self.path)
+            .field("interval", &self.interval)
+            .field("limits", &self.limits)
+            .field("callbacks", &self.callbacks.len())
+            .finish()
+    }
+}
+
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+impl<S> UsageWatcher<S>
+where
+    S: StorageService + 'static,
+    S::Error: Display + Send,
+{
+    /// Creates a new [`UsageWatcher`] for the root of `service` without any thresholds.
+    pub fn new(service: S) -> UsageWatcher<S> {
+        UsageWatcher {
+            service,
+            path: None,
+            interval: DEFAULT_USAGE_INTERVAL,
+            limits: Vec::new(),
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Only checks the usage of the files in `path`.
+    pub fn with_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets how often the usage is checked. This will default to [`DEFAULT_USAGE_INTERVAL`].
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Alerts when the files take up more than `max` bytes.
+    pub fn with_max_bytes(mut self, max: u64) -> Self {
+        self.limits.push(UsageLimit::Bytes(max));
+        self
+    }
+
+    /// Alerts when there are more than `max` files.
+    pub fn with_max_objects(mut self, max: u64) -> Self {
+        self.limits.push(UsageLimit::Objects(max));
+        self
+    }
+
+    /// Registers a callback that is called with every [`UsageAlert`].
+    pub fn on_alert<F: Fn(&UsageAlert) + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.callbacks.push(Arc::new(callback));
+        self
+    }
+
+    /// Checks the usage once and returns an alert for every threshold that is exceeded,
+    /// without calling any callbacks.
+    pub async fn check(&self) -> Result<Vec<UsageAlert>, S::Error> {
+        let usage = usage(&self.service, self.path.as_ref()).await?;
+        Ok(self
+            .limits
+            .iter()
+            .filter(|limit| limit.is_exceeded_by(&usage))
+            .map(|limit| UsageAlert { usage, limit: *limit })
+            .collect())
+    }
+
+    /// Starts checking the usage in the background, which runs until the returned
+    /// [`UsageWatcherHandle`] is stopped or dropped.
+    pub fn spawn(self) -> UsageWatcherHandle {
+        let stopped = Arc::new(AtomicBool::new(false));
+        rt::spawn("remi::usage::watch", watch(self, stopped.clone()));
+
+        UsageWatcherHandle { stopped }
+    }
+
+    fn alert(&self, alert: &UsageAlert) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            objects = alert.usage.objects,
+            bytes = alert.usage.bytes,
+            limit = ?alert.limit,
+            "storage usage is over threshold"
+        );
+
+        #[cfg(feature = "log")]
+        log::warn!("{alert}");
+
+        for callback in &self.callbacks {
+            callback(alert);
+        }
+    }
+}
+
+/// Handle to a [`UsageWatcher`] that is running in the background, which stops the watcher
+/// when it is dropped.
+///
+/// * since: 0.11.0
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+#[cfg_attr(any(noeldoc, docsrs), doc(cfg(any(feature = "rt-tokio", feature = "rt-async-std"))))]
+#[derive(Debug)]
+pub struct UsageWatcherHandle {
+    stopped: Arc<AtomicBool>,
+}
+
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+impl UsageWatcherHandle {
+    /// Stops the watcher. A check that is running is finished, but no new checks are started.
+    pub fn stop(self) {}
+}
+
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+impl Drop for UsageWatcherHandle {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Checks the usage of `watcher` on its interval until `stopped` is set.
+#[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+async fn watch<S>(watcher: UsageWatcher<S>, stopped: Arc<AtomicBool>)
+where
+    S: StorageService + 'static,
+    S::Error: Display + Send,
+{
+    // thresholds that were exceeded on the last check, which aren't alerted on again
+    let mut exceeded = Vec::new();
+    while !stopped.load(Ordering::SeqCst) {
+        match watcher.check().await {
+            Ok(alerts) => {
+                for alert in &alerts {
+                    if !exceeded.contains(&alert.limit) {
+                        watcher.alert(alert);
+                    }
+                }
+
+                exceeded = alerts.into_iter().map(|alert| alert.limit).collect();
+            }
+
+            #[allow(unused)]
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %e, "unable to check storage usage");
+
+                #[cfg(feature = "log")]
+                log::warn!("unable to check storage usage: {e}");
+            }
+        }
+
+        rt::sleep(watcher.interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{usage, Usage, UsageLimit};
+    use crate::{testing::TestStorage, StorageService, UploadRequest};
+
+    #[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+    use super::UsageWatcher;
+    #[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    async fn storage() -> TestStorage {
+        let storage = TestStorage::new();
+        for (path, data) in [
+            ("./a.txt", "weow"),
+            ("./logs/b.log", "fluff"),
+            ("./logs/c.log", "weow weow"),
+        ] {
+            storage
+                .upload(path, UploadRequest::default().with_data(data))
+                .await
+                .unwrap();
+        }
+
+        storage
+    }
+
+    #[tokio::test]
+    async fn sums_files() {
+        let storage = storage().await;
+        assert_eq!(
+            usage(&storage, Some("./logs")).await.unwrap(),
+            Usage { objects: 2, bytes: 14 }
+        );
+
+        assert_eq!(
+            usage(&TestStorage::new(), None::<&str>).await.unwrap(),
+            Usage::default()
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let usage = Usage { objects: 2, bytes: 14 };
+        assert!(UsageLimit::Bytes(13).is_exceeded_by(&usage));
+        assert!(!UsageLimit::Bytes(14).is_exceeded_by(&usage));
+        assert!(UsageLimit::Objects(1).is_exceeded_by(&usage));
+        assert!(!UsageLimit::Objects(2).is_exceeded_by(&usage));
+    }
+
+    #[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+    #[tokio::test]
+    async fn checks_limits() {
+        let watcher = UsageWatcher::new(storage().await)
+            .with_path("./logs")
+            .with_max_bytes(10)
+            .with_max_objects(2);
+
+        let alerts = watcher.check().await.unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].limit, UsageLimit::Bytes(10));
+        assert_eq!(alerts[0].usage, Usage { objects: 2, bytes: 14 });
+    }
+
+    #[cfg(any(feature = "rt-tokio", feature = "rt-async-std"))]
+    #[tokio::test(start_paused = true)]
+    async fn alerts_once_per_exceeded_limit() {
+        let storage = storage().await;
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let handle = UsageWatcher::new(storage.clone())
+            .with_interval(Duration::from_secs(1))
+            .with_max_objects(1)
+            .on_alert({
+                let alerts = alerts.clone();
+                move |alert| alerts.lock().unwrap().push(alert.usage.objects)
+            })
+            .spawn();
+
+        // `./a.txt` is the only file in the root, which is at the limit
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert!(alerts.lock().unwrap().is_empty());
+
+        storage
+            .upload("./d.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert_eq!(*alerts.lock().unwrap(), [2]);
+
+        // the limit has to be under the threshold again before it alerts again
+        storage.delete("./d.txt").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        storage
+            .upload("./e.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(*alerts.lock().unwrap(), [2, 2]);
+
+        handle.stop();
+        storage
+            .upload("./f.txt", UploadRequest::default().with_data("weow"))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert_eq!(alerts.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,206 @@
+// 🐻‍❄️🧶 remi-rs: Asynchronous Rust crate to handle communication between applications and object storage providers
+// Copyright (c) 2022-2024 Noelware, LLC. <team@noelware.org>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::{StorageService, UploadRequest};
+use bytes::Bytes;
+use std::{borrow::Cow, fmt::Display, time::SystemTime};
+
+/// Represents a report from [`StorageService::debug_validate`], which lists every
+/// probe that was performed against a storage service and whether it passed or not.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    /// List of checks that were performed, in order.
+    pub checks: Vec<ValidationCheck>,
+}
+
+/// A single probe that was performed in a [`ValidationReport`].
+#[derive(Debug, Clone)]
+pub struct ValidationCheck {
+    /// Name of the check, i.e, `bucket exists`.
+    pub name: Cow<'static, str>,
+
+    /// Outcome of this check.
+    pub status: CheckStatus,
+
+    /// Remediation hint on how to fix the check if it failed.
+    pub hint: Option<Cow<'static, str>>,
+}
+
+/// Outcome of a [`ValidationCheck`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// The check passed.
+    Passed,
+
+    /// The check failed with the given error message.
+    Failed(String),
+
+    /// The check was skipped because a previous check failed.
+    Skipped,
+}
+
+impl ValidationReport {
+    /// Returns `true` if no checks in this report have failed.
+    pub fn is_ok(&self) -> bool {
+        !self.checks.iter().any(|c| matches!(c.status, CheckStatus::Failed(_)))
+    }
+
+    /// Returns an iterator of all the checks that have failed.
+    pub fn failures(&self) -> impl Iterator<Item = &ValidationCheck> {
+        self.checks
+            .iter()
+            .filter(|c| matches!(c.status, CheckStatus::Failed(_)))
+    }
+
+    /// Records a check from the result of a probe. Returns `true` if the check passed.
+    pub fn record<T, E: Display>(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        result: &Result<T, E>,
+        hint: impl Into<Cow<'static, str>>,
+    ) -> bool {
+        let (status, hint) = match result {
+            Ok(_) => (CheckStatus::Passed, None),
+            Err(e) => (CheckStatus::Failed(e.to_string()), Some(hint.into())),
+        };
+
+        let passed = status == CheckStatus::Passed;
+        self.checks.push(ValidationCheck {
+            name: name.into(),
+            status,
+            hint,
+        });
+
+        passed
+    }
+
+    /// Records a check that was skipped.
+    pub fn skip(&mut self, name: impl Into<Cow<'static, str>>) {
+        self.checks.push(ValidationCheck {
+            name: name.into(),
+            status: CheckStatus::Skipped,
+            hint: None,
+        });
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            match check.status {
+                CheckStatus::Passed => writeln!(f, "[passed]  {}", check.name)?,
+                CheckStatus::Skipped => writeln!(f, "[skipped] {}", check.name)?,
+                CheckStatus::Failed(ref error) => {
+                    writeln!(f, "[failed]  {}: {error}", check.name)?;
+                    if let Some(ref hint) = check.hint {
+                        writeln!(f, "          hint: {hint}")?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Performs a write, read, and delete of a probe key (in `./.remi/probes`) and records the
+/// results in the given [`ValidationReport`]. This is what [`StorageService::debug_validate`]
+/// does by default, storage services usually perform their own checks (i.e, authentication or
+/// bucket existence) before calling this.
+///
+/// All probes are skipped if the storage service [is read-only][StorageService::is_read_only].
+pub async fn probe_read_write<S>(service: &S, report: &mut ValidationReport)
+where
+    S: StorageService,
+    S::Error: Display + Send,
+{
+    if service.is_read_only() {
+        report.skip("write probe object");
+        report.skip("read probe object");
+        report.skip("delete probe object");
+
+        return;
+    }
+
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    let key = format!("./.remi/probes/{nanos:x}");
+    let data = Bytes::from_static(b"remi probe");
+
+    let uploaded = service
+        .upload(&key, UploadRequest::default().with_data(data.clone()))
+        .await;
+
+    if !report.record(
+        "write probe object",
+        &uploaded,
+        "check that the credentials have permission to write objects",
+    ) {
+        report.skip("read probe object");
+        report.skip("delete probe object");
+
+        return;
+    }
+
+    let read = match service.open(&key).await {
+        Ok(Some(bytes)) if bytes == data => Ok(()),
+        Ok(Some(_)) => Err(String::from("contents of the probe object didn't match")),
+        Ok(None) => Err(String::from("probe object doesn't exist after it was written")),
+        Err(e) => Err(e.to_string()),
+    };
+
+    report.record(
+        "read probe object",
+        &read,
+        "check that the credentials have permission to read objects and that reads are consistent",
+    );
+
+    report.record(
+        "delete probe object",
+        &service.delete(&key).await,
+        "check that the credentials have permission to delete objects",
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let mut report = ValidationReport::default();
+        assert!(report.record("passes", &Ok::<(), &str>(()), "unused"));
+        assert!(report.is_ok());
+
+        assert!(!report.record("fails", &Err::<(), _>("uh oh"), "do something"));
+        report.skip("skipped");
+
+        assert!(!report.is_ok());
+        assert_eq!(report.failures().count(), 1);
+        assert_eq!(
+            report.to_string(),
+            "[passed]  passes\n[failed]  fails: uh oh\n          hint: do something\n[skipped] skipped\n"
+        );
+    }
+}